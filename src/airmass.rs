@@ -0,0 +1,94 @@
+//! Airmass, for correlating frame quality with how low the target sat.
+//!
+//! Frames carry airmass in one of three ways: an `AIRMASS` header (N.I.N.A.
+//! writes one, and the scheduler metadata keeps it as `Airmass`), the
+//! target's altitude, or enough to compute that altitude (RA/Dec, capture
+//! time and observing site).
+
+/// Kasten & Young (1989) relative airmass at an apparent altitude in degrees.
+/// Stays finite down to the horizon (≈38 at 0°); `None` below it.
+pub fn kasten_young(altitude_deg: f64) -> Option<f64> {
+    if !(0.0..=90.0).contains(&altitude_deg) {
+        return None;
+    }
+    Some(1.0 / (altitude_deg.to_radians().sin() + 0.50572 * (altitude_deg + 6.07995).powf(-1.6364)))
+}
+
+/// Geometric altitude in degrees of `ra_deg`/`dec_deg` seen from a site
+/// (longitude east-positive) at `unix_time`. Ignores refraction, which
+/// Kasten-Young already models.
+pub fn altitude_deg(
+    ra_deg: f64,
+    dec_deg: f64,
+    latitude_deg: f64,
+    longitude_deg: f64,
+    unix_time: i64,
+) -> f64 {
+    // Days since J2000.0 and the mean sidereal time at Greenwich.
+    let days = unix_time as f64 / 86_400.0 - 10_957.5;
+    let gmst_deg = (280.460_618_37 + 360.985_647_366_29 * days).rem_euclid(360.0);
+    let hour_angle = (gmst_deg + longitude_deg - ra_deg).to_radians();
+    let (lat, dec) = (latitude_deg.to_radians(), dec_deg.to_radians());
+    let sin_alt = lat.sin() * dec.sin() + lat.cos() * dec.cos() * hour_angle.cos();
+    sin_alt.clamp(-1.0, 1.0).asin().to_degrees()
+}
+
+/// A frame's airmass from its metadata JSON: the recorded `Airmass`, or one
+/// computed from a recorded `Altitude`. `None` when neither is present.
+pub fn airmass_from_metadata(metadata: &serde_json::Value) -> Option<f64> {
+    metadata["Airmass"]
+        .as_f64()
+        .filter(|airmass| *airmass >= 1.0)
+        .or_else(|| metadata["Altitude"].as_f64().and_then(kasten_young))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kasten_young_at_known_altitudes() {
+        let cases = [
+            (90.0, 0.99971),
+            (60.0, 1.15399),
+            (30.0, 1.99429),
+            (10.0, 5.58604),
+            (0.0, 37.9196),
+        ];
+        for (altitude, expected) in cases {
+            let airmass = kasten_young(altitude).unwrap();
+            assert!(
+                (airmass - expected).abs() < 1e-4,
+                "altitude {altitude}: {airmass} != {expected}"
+            );
+        }
+        assert_eq!(kasten_young(-1.0), None);
+        assert_eq!(kasten_young(f64::NAN), None);
+    }
+
+    #[test]
+    fn object_on_the_meridian_culminates() {
+        // 2024-01-15T06:00:00Z at longitude -118°: put the object on the
+        // local meridian; its altitude is then 90 - |lat - dec|.
+        let time = 1_705_298_400;
+        let days = time as f64 / 86_400.0 - 10_957.5;
+        let lst = (280.460_618_37 + 360.985_647_366_29 * days - 118.0).rem_euclid(360.0);
+        let altitude = altitude_deg(lst, 20.0, 34.0, -118.0, time);
+        assert!((altitude - 76.0).abs() < 1e-6, "{altitude}");
+        let below = altitude_deg(lst + 180.0, 20.0, 34.0, -118.0, time);
+        assert!((below - -36.0).abs() < 1e-6, "{below}");
+    }
+
+    #[test]
+    fn metadata_airmass_or_altitude() {
+        let recorded = serde_json::json!({"Airmass": 1.42, "Altitude": 30.0});
+        assert_eq!(airmass_from_metadata(&recorded), Some(1.42));
+        let altitude_only = serde_json::json!({"Altitude": 30.0});
+        let airmass = airmass_from_metadata(&altitude_only).unwrap();
+        assert!((airmass - 1.99429).abs() < 1e-4);
+        assert_eq!(
+            airmass_from_metadata(&serde_json::json!({"HFR": 2.1})),
+            None
+        );
+    }
+}
@@ -9,6 +9,7 @@ use std::sync::{Arc, RwLock};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub const SEIZA_VERSION: &str = "0.12.0";
 pub const SEIZA_FITS_VERSION: &str = "0.2.0";
@@ -120,7 +121,7 @@ impl AstrometryConfig {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AstrometryResourceStatus {
     NotConfigured,
@@ -129,7 +130,7 @@ pub enum AstrometryResourceStatus {
     Invalid,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryResourceCapability {
     pub name: String,
     pub status: AstrometryResourceStatus,
@@ -153,7 +154,7 @@ impl AstrometryResourceCapability {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryResources {
     pub objects: AstrometryResourceCapability,
     pub stars: AstrometryResourceCapability,
@@ -163,7 +164,7 @@ pub struct AstrometryResources {
     pub minor_bodies: AstrometryResourceCapability,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryFeatures {
     pub object_association: bool,
     pub object_name_search: bool,
@@ -174,7 +175,7 @@ pub struct AstrometryFeatures {
     pub minor_body_annotations: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryCapabilities {
     pub seiza_version: String,
     pub seiza_fits_version: String,
@@ -184,7 +185,7 @@ pub struct AstrometryCapabilities {
     pub features: AstrometryFeatures,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryResourceValidation {
     pub name: String,
     pub status: AstrometryResourceStatus,
@@ -195,7 +196,7 @@ pub struct AstrometryResourceValidation {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryValidationReport {
     pub all_configured_valid: bool,
     pub resources: Vec<AstrometryResourceValidation>,
@@ -203,7 +204,7 @@ pub struct AstrometryValidationReport {
 
 /// File identity used to invalidate derived image analysis when a path is
 /// replaced in place.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometrySourceFingerprint {
     pub canonical_path: String,
     pub size_bytes: u64,
@@ -216,14 +217,14 @@ pub struct AstrometrySourceFingerprint {
 /// annotation validity. Managed installs populate bundle/hash fields; custom
 /// directories can rely on the individual file metadata until explicitly
 /// hashed.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryCatalogSignature {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub bundle_version: Option<String>,
     pub files: Vec<AstrometryCatalogFileSignature>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryCatalogFileSignature {
     pub name: String,
     pub path: String,
@@ -236,7 +237,7 @@ pub struct AstrometryCatalogFileSignature {
     pub sha256: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AstrometryAnalysisStatus {
     Unavailable,
@@ -247,7 +248,7 @@ pub enum AstrometryAnalysisStatus {
 
 /// How the object-catalog search region was established. This keeps a
 /// conservative coordinate-only lookup distinct from a known image field.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AstrometryCatalogScope {
     EmbeddedFootprint,
@@ -256,7 +257,7 @@ pub enum AstrometryCatalogScope {
     NearbyTarget,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AstrometrySolveMode {
     EmbeddedWcs,
@@ -264,7 +265,7 @@ pub enum AstrometrySolveMode {
     Blind,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AstrometryAttemptOutcome {
     Solved,
@@ -277,7 +278,7 @@ pub enum AstrometryAttemptOutcome {
     InternalError,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometrySolveAttempt {
     pub outcome: AstrometryAttemptOutcome,
     #[serde(default)]
@@ -296,7 +297,7 @@ pub struct AstrometrySolveAttempt {
 /// A celestial coordinate plus the source that gave it its semantic role.
 /// Keeping hint and expected coordinates separate prevents a derived center
 /// from silently replacing the Target Scheduler target.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryCoordinateSource {
     pub ra_deg: f64,
     pub dec_deg: f64,
@@ -306,7 +307,7 @@ pub struct AstrometryCoordinateSource {
 }
 
 /// TAN WCS response compatible with the seiza-server/Tenrankai overlay model.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct WcsResponse {
     pub crval: [f64; 2],
     pub crpix: [f64; 2],
@@ -318,7 +319,7 @@ pub struct WcsResponse {
 }
 
 /// Seiza object identity, hierarchy, and provenance carried through PSF Guard APIs.
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct CatalogObjectIdentity {
     pub stable_id: String,
     pub source: String,
@@ -333,7 +334,7 @@ pub struct CatalogObjectIdentity {
 }
 
 /// Object association from known coordinates without a plate solve.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct CatalogHitResponse {
     #[serde(flatten)]
     pub identity: CatalogObjectIdentity,
@@ -356,13 +357,13 @@ pub struct CatalogHitResponse {
 /// Object projected into a solved image. Core names match
 /// `@seiza/astro-overlay` so the frontend can render the shared component
 /// without translating the geometry contract.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct OverlayContourResponse {
     pub closed: bool,
     pub points: Vec<[f64; 2]>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct OverlayOutlineResponse {
     pub geometry_id: String,
     pub source_record_id: String,
@@ -372,7 +373,7 @@ pub struct OverlayOutlineResponse {
     pub contours: Vec<OverlayContourResponse>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct OverlayObjectResponse {
     #[serde(flatten)]
     pub identity: CatalogObjectIdentity,
@@ -407,7 +408,7 @@ pub struct OverlayObjectResponse {
     pub outlines: Vec<OverlayOutlineResponse>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometrySolutionResponse {
     pub center_ra_deg: f64,
     pub center_dec_deg: f64,
@@ -429,7 +430,7 @@ pub struct AstrometrySolutionResponse {
 /// Reproducibility details for a pixel-derived plate solution. Object-catalog
 /// provenance remains in `catalog_signature`; this records the solver inputs
 /// that established the WCS itself.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometrySolverProvenance {
     pub seiza_version: String,
     pub detection_backend: String,
@@ -438,7 +439,7 @@ pub struct AstrometrySolverProvenance {
     pub blind_index: Option<AstrometryCatalogFileSignature>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct PointingResult {
     pub expected_ra_deg: f64,
     pub expected_dec_deg: f64,
@@ -457,7 +458,7 @@ pub struct PointingResult {
 
 /// Stable top-level per-image response. Header-only analysis fills catalog,
 /// embedded-WCS, and pointing fields; later solving phases keep the envelope.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct AstrometryAnalysis {
     pub image_id: i32,
     pub status: AstrometryAnalysisStatus,
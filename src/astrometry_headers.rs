@@ -88,6 +88,56 @@ pub struct FitsWcsHeaders {
     pub equinox: f64,
 }
 
+impl FitsWcsHeaders {
+    /// Sky position (degrees) of a zero-based pixel, by the gnomonic (TAN)
+    /// projection.
+    pub fn pixel_to_world(&self, x: f64, y: f64) -> (f64, f64) {
+        let dx = x - self.crpix[0];
+        let dy = y - self.crpix[1];
+        let xi = (self.cd[0][0] * dx + self.cd[0][1] * dy).to_radians();
+        let eta = (self.cd[1][0] * dx + self.cd[1][1] * dy).to_radians();
+        let ra0 = self.crval[0].to_radians();
+        let dec0 = self.crval[1].to_radians();
+
+        let denominator = dec0.cos() - eta * dec0.sin();
+        let ra = ra0 + xi.atan2(denominator);
+        let dec = (dec0.sin() + eta * dec0.cos()).atan2(xi.hypot(denominator));
+        (ra.to_degrees().rem_euclid(360.0), dec.to_degrees())
+    }
+
+    /// Zero-based pixel of a sky position (degrees); `None` on the far
+    /// hemisphere, where the projection is undefined.
+    pub fn world_to_pixel(&self, ra_deg: f64, dec_deg: f64) -> Option<(f64, f64)> {
+        let ra0 = self.crval[0].to_radians();
+        let dec0 = self.crval[1].to_radians();
+        let (ra, dec) = (ra_deg.to_radians(), dec_deg.to_radians());
+        let delta_ra = ra - ra0;
+
+        let cos_c = dec0.sin() * dec.sin() + dec0.cos() * dec.cos() * delta_ra.cos();
+        if cos_c <= 0.0 {
+            return None;
+        }
+        let xi = (dec.cos() * delta_ra.sin() / cos_c).to_degrees();
+        let eta = ((dec0.cos() * dec.sin() - dec0.sin() * dec.cos() * delta_ra.cos()) / cos_c)
+            .to_degrees();
+
+        let [[a, b], [c, d]] = self.cd;
+        let determinant = a * d - b * c;
+        if determinant == 0.0 {
+            return None;
+        }
+        let dx = (d * xi - b * eta) / determinant;
+        let dy = (a * eta - c * xi) / determinant;
+        Some((self.crpix[0] + dx, self.crpix[1] + dy))
+    }
+
+    /// Mean pixel scale in arcseconds.
+    pub fn pixel_scale_arcsec(&self) -> f64 {
+        let [[a, b], [c, d]] = self.cd;
+        (a * d - b * c).abs().sqrt() * 3600.0
+    }
+}
+
 impl FitsAstrometryHeaders {
     /// Read only the FITS header blocks, without touching the pixel payload.
     pub fn from_path(path: &Path) -> Result<Self, seiza_fits::FitsError> {
@@ -175,7 +225,7 @@ fn fits_observer(headers: &[(String, HeaderValue)]) -> Option<Provenanced<FitsOb
     ))
 }
 
-fn parse_longitude_deg(input: &str) -> Option<f64> {
+pub(crate) fn parse_longitude_deg(input: &str) -> Option<f64> {
     let value = input.trim();
     if let Ok(degrees) = value.parse::<f64>() {
         return (degrees.is_finite() && (-360.0..=360.0).contains(&degrees))
@@ -734,6 +784,28 @@ mod tests {
         assert_eq!(scale.sources, ["FOCALLEN", "XPIXSZ", "XBINNING"]);
     }
 
+    #[test]
+    fn wcs_projects_between_pixels_and_sky() {
+        let wcs = FitsWcsHeaders {
+            crval: [10.68, 41.27],
+            crpix: [1000.0, 700.0],
+            cd: [[-2.5e-4, 1.0e-5], [1.2e-5, 2.5e-4]],
+            ctype: ["RA---TAN".into(), "DEC--TAN".into()],
+            cunit: ["deg".into(), "deg".into()],
+            radesys: "ICRS".into(),
+            equinox: 2000.0,
+        };
+        let (ra, dec) = wcs.pixel_to_world(1000.0, 700.0);
+        assert!((ra - 10.68).abs() < 1e-12 && (dec - 41.27).abs() < 1e-12);
+        for (x, y) in [(0.0, 0.0), (2047.0, 1399.0), (1500.5, 12.25)] {
+            let (ra, dec) = wcs.pixel_to_world(x, y);
+            let (px, py) = wcs.world_to_pixel(ra, dec).unwrap();
+            assert!((px - x).abs() < 1e-6 && (py - y).abs() < 1e-6, "{x},{y}");
+        }
+        assert!(wcs.world_to_pixel(190.68, -41.27).is_none());
+        assert!((wcs.pixel_scale_arcsec() - 0.9).abs() < 2e-3);
+    }
+
     #[test]
     fn builds_legacy_cdelt_crota_wcs_and_converts_crpix_to_zero_based() {
         let parsed = FitsAstrometryHeaders::from_headers(&headers(&[
@@ -9,6 +9,12 @@ pub struct Cli {
     #[arg(short, long, default_value = "schedulerdb.sqlite")]
     pub database: String,
 
+    /// Open the scheduler database read-only (`SQLITE_OPEN_READ_ONLY`). Safe
+    /// to use against a live N.I.N.A. database; the server refuses grade
+    /// updates and edits with 403, CLI writes fail.
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -32,6 +38,11 @@ pub enum Commands {
         /// Output format (json, csv, table)
         #[arg(short, long, default_value = "table")]
         format: String,
+
+        /// TOML configuration whose `[reject_reasons]` mappings categorize
+        /// the reject reasons
+        #[arg(long)]
+        config: Option<String>,
     },
 
     /// Create a new Target Scheduler database and import FITS folders into it.
@@ -64,6 +75,11 @@ pub enum Commands {
         #[arg(long)]
         profile_id: Option<String>,
 
+        /// Group frames into targets by RA/Dec within this many degrees
+        /// instead of by OBJECT name (bare flag: 0.5)
+        #[arg(long, num_args = 0..=1, default_missing_value = "0.5")]
+        group_by_position: Option<f64>,
+
         /// Preview the plan; the database file is still created but the
         /// import transaction is rolled back.
         #[arg(long)]
@@ -118,6 +134,58 @@ pub enum Commands {
         #[arg(long, default_value_t = crate::commands::import::DEFAULT_MATCH_RADIUS_DEG)]
         match_radius_deg: f64,
 
+        /// Group new frames into targets by RA/Dec within this many degrees
+        /// instead of by OBJECT name (bare flag: 0.5)
+        #[arg(long, num_args = 0..=1, default_missing_value = "0.5")]
+        group_by_position: Option<f64>,
+
+        /// Where the frames come from: scan the directories (fits), or read a
+        /// capture log (--log) whose files are looked up in the directories.
+        #[arg(long, value_enum, default_value_t)]
+        format: crate::commands::import::logs::ImportFormat,
+
+        /// Capture log to import with a non-fits --format. Its HFR and star
+        /// counts are stored in each image's metadata.
+        #[arg(long)]
+        log: Option<String>,
+
+        /// Path to the database registry JSON file (defaults to the platform
+        /// config directory).
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Scan directories for FITS files the database doesn't reference yet
+    /// and add them to its existing targets.
+    ///
+    /// Each new light frame joins the target its OBJECT header (or
+    /// coordinates) matches, or the --target given; frames no target matches
+    /// are listed, never imported. Nothing is written without --apply.
+    Scan {
+        /// Registry slug or path of the database.
+        db: String,
+
+        /// Directory to walk; repeatable.
+        #[arg(long = "base-dir", required = true)]
+        base_dir: Vec<String>,
+
+        /// Only add frames to targets of this project (name).
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Add every new frame to this target (name) instead of matching
+        /// OBJECT headers.
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Write the new rows (the default is a dry run).
+        #[arg(long)]
+        apply: bool,
+
+        /// Coordinate-match radius (degrees) when OBJECT names differ.
+        #[arg(long, default_value_t = crate::commands::import::DEFAULT_MATCH_RADIUS_DEG)]
+        match_radius_deg: f64,
+
         /// Path to the database registry JSON file (defaults to the platform
         /// config directory).
         #[arg(long)]
@@ -349,6 +417,21 @@ pub enum Commands {
         #[arg(long, default_value = "none")]
         reset: String,
 
+        /// Dry-run output format: table, or json (a list of per-image
+        /// changes with the threshold that decided each)
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Never change an image whose reject reason marks a manual
+        /// decision, in any reset mode or statistical pass
+        #[arg(long)]
+        preserve_manual: bool,
+
+        /// TOML configuration whose `[reject_reasons] manual_markers` say
+        /// which reject reasons are manual (default: "Manual")
+        #[arg(long)]
+        config: Option<String>,
+
         #[command(flatten)]
         stat_options: StatisticalOptions,
     },
@@ -372,6 +455,69 @@ pub enum Commands {
         reason: Option<String>,
     },
 
+    /// Merge duplicate targets within a project.
+    ///
+    /// Reassigns every acquired image from the `--from` target to the
+    /// `--into` target in one transaction. Both targets must belong to the
+    /// same project.
+    MergeTargets {
+        /// Target ID whose images are moved
+        #[arg(long)]
+        from: i32,
+
+        /// Target ID that receives the images
+        #[arg(long)]
+        into: i32,
+
+        /// Delete the emptied source target and its exposure plans
+        #[arg(long)]
+        delete_source: bool,
+    },
+
+    /// Find image rows that are the same frame imported more than once (same
+    /// target, filter and filename, acquired within a second). Reports only,
+    /// unless --delete
+    FindDuplicates {
+        /// Filter by project name
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Filter by target name
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Delete every copy but one per cluster (the accepted, else rejected,
+        /// else oldest row is kept)
+        #[arg(long)]
+        delete: bool,
+    },
+
+    /// Back up a project's curation (grades, reject reasons, favorites, tags
+    /// and key metrics) as a versioned JSON document
+    ExportCuration {
+        /// Project name
+        project: String,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Re-apply a curation document from export-curation, matching frames by
+    /// filename and timestamp (robust to database rebuilds and moved files)
+    ImportCuration {
+        /// Curation JSON document
+        file: String,
+
+        /// Only match frames of this project (default: the whole database)
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Report what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Read and display metadata from FITS files
     ReadFits {
         /// Path to FITS file or directory containing FITS files
@@ -428,6 +574,370 @@ pub enum Commands {
         verbose: bool,
     },
 
+    /// Compute HFR, star count and eccentricity for every image in the
+    /// database and optionally store them in its metadata JSON.
+    ///
+    /// Files are located by the basename in each image's `FileName`, searched
+    /// across the base directories. Useful for frames captured by software
+    /// that doesn't record these metrics, so grading and sequence analysis
+    /// can use them.
+    AnalyzeBatch {
+        /// Directory containing the image files (repeatable; earlier wins when
+        /// a filename exists in several)
+        #[arg(long = "base-dir", required = true)]
+        base_dirs: Vec<String>,
+
+        /// Filter by project name
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Filter by target name
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Merge the computed metrics into each image's metadata (other keys
+        /// are preserved)
+        #[arg(long)]
+        write_metadata: bool,
+
+        /// With --write-metadata: print the merged metadata without writing
+        #[arg(long, requires = "write_metadata")]
+        dry_run: bool,
+
+        /// Replace metrics already present in the metadata instead of only
+        /// filling in missing ones
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Worker threads for frame analysis (default: all cores, bounded by
+        /// available memory)
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+
+    /// Fill the computed-metrics cache for a project so interactive analysis
+    /// doesn't have to detect stars: frames with metrics in their metadata
+    /// are cached from it, the rest detected with --detect
+    BackfillMetrics {
+        /// Directory containing the image files (repeatable; earlier wins when
+        /// a filename exists in several)
+        #[arg(long = "base-dir", required = true)]
+        base_dirs: Vec<String>,
+
+        /// Project name
+        #[arg(short, long)]
+        project: String,
+
+        /// Filter by target name
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Run star detection on frames whose metadata has no metrics
+        #[arg(long)]
+        detect: bool,
+
+        /// Recompute entries whose file hasn't changed
+        #[arg(long)]
+        force: bool,
+
+        /// Detect at most this many frames per second (default: no limit),
+        /// to keep shared storage responsive
+        #[arg(long)]
+        max_rate: Option<f64>,
+
+        /// Worker threads for detection (default: all cores, bounded by
+        /// available memory)
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+
+    /// Check the library for silent corruption: hash every located FITS file,
+    /// record the hash the first time, and report files whose content changed,
+    /// that no longer load, or that went missing
+    VerifyFiles {
+        /// Directory containing the image files (repeatable; earlier wins when
+        /// a filename exists in several)
+        #[arg(long = "base-dir", required = true)]
+        base_dirs: Vec<String>,
+
+        /// Filter by project name
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Filter by target name
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+
+        /// Worker threads (default: all cores, bounded by available memory)
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+
+    /// Score a project's image sequences exactly as the server's sequence
+    /// analysis does and write the result to a file
+    AnalyzeSequences {
+        /// Project name
+        #[arg(short, long)]
+        project: String,
+
+        /// Only this target
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Only this filter
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Report file to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Report format: json (one document) or ndjson (header line, then
+        /// one sequence per line)
+        #[arg(short, long, default_value = "json")]
+        format: String,
+
+        /// Analyzer configuration JSON (e.g. the `config` from an earlier
+        /// report's header); defaults to the server's settings
+        #[arg(long)]
+        config: Option<String>,
+
+        /// Override the session gap (minutes) that splits sequences
+        #[arg(long)]
+        session_gap_minutes: Option<u64>,
+
+        /// Analyze targets within this many degrees of each other as one
+        /// field, whatever they are named (bare flag: 0.5)
+        #[arg(long, num_args = 0..=1, default_missing_value = "0.5")]
+        group_by_position: Option<f64>,
+
+        /// Server cache root, for quality-scan and plate-solve evidence
+        #[arg(long, default_value = "./cache")]
+        cache_dir: String,
+
+        /// Registry file used to find the database's cache id (defaults to
+        /// the platform config location)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Write a self-contained HTML report of one night's acquisition:
+    /// per-target summaries, quality distributions, detected issues and
+    /// thumbnails of the flagged frames
+    Report {
+        /// Project name
+        #[arg(short, long)]
+        project: String,
+
+        /// Night to report, by the local date it started (YYYY-MM-DD); runs
+        /// from local noon to noon the next day
+        #[arg(long)]
+        date: chrono::NaiveDate,
+
+        /// HTML file to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Longest edge of the embedded thumbnails in pixels (controls the
+        /// report's size)
+        #[arg(long, default_value_t = crate::commands::night_report::DEFAULT_THUMBNAIL_SIZE)]
+        thumbnail_size: u32,
+
+        /// Directories holding the FITS files (defaults to the registry
+        /// entry's); without any the report has no thumbnails
+        #[arg(long, value_delimiter = ',')]
+        image_dirs: Option<Vec<String>>,
+
+        /// Override the session gap (minutes) that splits sequences
+        #[arg(long)]
+        session_gap_minutes: Option<u64>,
+
+        /// Server cache root, for quality-scan and plate-solve evidence
+        #[arg(long, default_value = "./cache")]
+        cache_dir: String,
+
+        /// Registry file used to find the database's cache id and image
+        /// directories (defaults to the platform config location)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Animated GIF of a target's frames in acquisition order, for spotting
+    /// drift and clouds
+    Animate {
+        /// Target name
+        #[arg(short, long)]
+        target: String,
+
+        /// Project name, when the target name is used in several projects
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Only frames taken through this filter
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Frames per second
+        #[arg(long, default_value_t = crate::commands::animate::DEFAULT_FPS)]
+        fps: f64,
+
+        /// Longest edge of the animation in pixels (16 to 4096)
+        #[arg(
+            long,
+            default_value_t = crate::commands::animate::DEFAULT_SIZE,
+            value_parser = clap::value_parser!(u32).range(
+                i64::from(crate::commands::animate::MIN_SIZE)
+                    ..=i64::from(crate::commands::animate::MAX_SIZE)
+            )
+        )]
+        size: u32,
+
+        /// Shift each frame onto the first frame's bright-pixel centroid so
+        /// only background and quality changes remain
+        #[arg(long)]
+        align: bool,
+
+        /// GIF file to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Directories holding the FITS files (defaults to the registry
+        /// entry's)
+        #[arg(long, value_delimiter = ',')]
+        image_dirs: Option<Vec<String>>,
+
+        /// Registry file used to find the database's image directories
+        /// (defaults to the platform config location)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Tile a target's frames into one PNG of stretched thumbnails, optionally
+    /// bordered by grade (green accepted, red rejected)
+    ContactSheet {
+        /// Target name
+        #[arg(short, long)]
+        target: String,
+
+        /// Project name, when the target name is used in several projects
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Only frames taken through this filter
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Thumbnails per row
+        #[arg(long, default_value_t = crate::commands::contact_sheet::DEFAULT_COLUMNS)]
+        cols: u32,
+
+        /// Edge of each square cell in pixels
+        #[arg(long, default_value_t = crate::commands::contact_sheet::DEFAULT_CELL_SIZE)]
+        cell_size: u32,
+
+        /// Leave out the grade borders
+        #[arg(long)]
+        no_grades: bool,
+
+        /// PNG file to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Directories holding the FITS files (defaults to the registry
+        /// entry's)
+        #[arg(long, value_delimiter = ',')]
+        image_dirs: Option<Vec<String>>,
+
+        /// Registry file used to find the database's image directories
+        /// (defaults to the platform config location)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// HFR against focuser position for a target's latest autofocus run,
+    /// with the fitted V-curve and best-focus position
+    FocusVcurve {
+        /// Target name
+        #[arg(short, long)]
+        target: String,
+
+        /// Project name, when the target name is used in several projects
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Only frames taken through this filter
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Longest pause between frames of one run, in seconds
+        #[arg(long, default_value_t = crate::commands::focus_vcurve::DEFAULT_MAX_GAP_SECS)]
+        max_gap: i64,
+
+        /// PNG plot to write
+        #[arg(short, long)]
+        output: String,
+
+        /// Also write the points and fitted values as CSV
+        #[arg(long)]
+        csv: Option<String>,
+
+        /// Directories holding the FITS files, for frames without an HFR in
+        /// their metadata (defaults to the registry entry's)
+        #[arg(long, value_delimiter = ',')]
+        image_dirs: Option<Vec<String>>,
+
+        /// Registry file used to find the database's image directories
+        /// (defaults to the platform config location)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Star-matched drift of each of a target's frames against a reference
+    /// frame, as CSV, flagging frames that moved too far
+    Register {
+        /// Target name
+        #[arg(short, long)]
+        target: String,
+
+        /// Project name, when the target name is used in several projects
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Only frames taken through this filter
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Image id of the reference frame
+        #[arg(long)]
+        reference: i32,
+
+        /// Drift in pixels beyond which a frame is flagged
+        #[arg(long, default_value_t = crate::commands::register::DEFAULT_MAX_DRIFT)]
+        max_drift: f64,
+
+        /// CSV file to write (standard output if omitted)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Add this tag to frames that drifted beyond --max-drift
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Directories holding the FITS files (defaults to the registry
+        /// entry's)
+        #[arg(long, value_delimiter = ',')]
+        image_dirs: Option<Vec<String>>,
+
+        /// Registry file used to find the database's image directories
+        /// (defaults to the platform config location)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
     /// Screen FITS frames for occlusion, clouds, pointing and cached satellite risk
     ScreenFits {
         /// Path to a FITS file or directory (searched recursively)
@@ -450,6 +960,27 @@ pub enum Commands {
         #[arg(long, default_value = "0.08")]
         dead_cell_rise: f64,
 
+        /// Reject frames whose temporal anomaly score (0.0-1.0, deviation
+        /// from the sequence's running baseline) exceeds this, even when
+        /// their quality score passes. Off by default.
+        #[arg(long)]
+        temporal_anomaly_reject: Option<f64>,
+
+        /// Reject frames with fewer detected stars than this as likely
+        /// clouded out, whatever their other metrics. Off by default.
+        #[arg(long)]
+        min_stars: Option<usize>,
+
+        /// Reject frames with more than this fraction of pixels clipped at
+        /// 0 ADU as under-exposed
+        #[arg(long, default_value_t = crate::image_analysis::DEFAULT_LOW_CLIP_FRACTION)]
+        low_clip_fraction: f64,
+
+        /// Reject frames with more than this fraction of pixels at full
+        /// scale as over-exposed
+        #[arg(long, default_value_t = crate::image_analysis::DEFAULT_HIGH_CLIP_FRACTION)]
+        high_clip_fraction: f64,
+
         /// Worker threads for frame analysis (default: all cores, bounded by
         /// available memory)
         #[arg(long)]
@@ -487,6 +1018,11 @@ pub enum Commands {
         #[arg(long)]
         annotate: Option<String>,
 
+        /// Also search every frame for long satellite/airplane trails and
+        /// reject frames that have one
+        #[arg(long)]
+        detect_trails: bool,
+
         /// Enable verbose debug output
         #[arg(long, short)]
         verbose: bool,
@@ -518,15 +1054,113 @@ pub enum Commands {
         invert: bool,
     },
 
+    /// Write a FITS frame as a 16-bit integer or 32-bit float TIFF, linear
+    /// unless --stretch is given
+    FitsToTiff {
+        /// Path to FITS file
+        #[arg(short, long)]
+        input: String,
+
+        /// Output TIFF path (defaults to the FITS filename with .tiff)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// 16 (integer) or 32 (float, 0..1)
+        #[arg(long, default_value_t = 16)]
+        bit_depth: u32,
+
+        /// Apply the MTF stretch instead of writing linear data
+        #[arg(long)]
+        stretch: bool,
+
+        /// MTF midtone balance factor, with --stretch
+        #[arg(long, default_value = "0.2")]
+        midtone_factor: f64,
+
+        /// Shadow clipping in standard deviations, with --stretch
+        #[arg(long, default_value = "-2.8")]
+        shadow_clipping: f64,
+    },
+
+    /// Estimate and subtract the sky background of a FITS frame.
+    ///
+    /// Writes `<name>_background.png` (the smooth background model) and
+    /// `<name>_flattened.png` (the frame with it removed), both auto-stretched,
+    /// for judging light-pollution gradients.
+    BackgroundExtract {
+        /// Path to FITS file
+        fits_path: String,
+
+        /// Output directory (defaults to the FITS file's directory)
+        #[arg(short, long)]
+        output_dir: Option<String>,
+
+        /// Wavelet levels treated as detail; higher gives a smoother background
+        #[arg(long, default_value_t = crate::commands::background_extract::DEFAULT_LEVELS)]
+        levels: usize,
+    },
+
+    /// Detect satellite and airplane trails in a FITS frame.
+    ///
+    /// Runs a Hough line transform over the high-pass filtered frame and
+    /// reports each long linear feature with its endpoints, length and angle.
+    DetectTrails {
+        /// Path to FITS file
+        fits_path: String,
+
+        /// Write a stretched PNG with the detected trails drawn in red
+        #[arg(long)]
+        overlay: Option<String>,
+
+        /// Detection threshold in units of background noise
+        #[arg(long, default_value_t = 4.0)]
+        sigma: f64,
+
+        /// Output format: table or json
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Estimate a frame's photometric zero-point against a reference catalog.
+    ///
+    /// Needs a TAN WCS in the FITS header. Detected stars are matched to a
+    /// catalog CSV (RA/Dec in degrees plus a magnitude column, e.g. a Gaia
+    /// or Tycho extract of the field) and the zero-point is the
+    /// sigma-clipped mean of catalog minus instrumental magnitude.
+    ZeroPoint {
+        /// Path to FITS file
+        fits_path: String,
+
+        /// Reference catalog CSV with a header row
+        #[arg(long)]
+        catalog: String,
+
+        /// Magnitude column to use (default: mag, phot_g_mean_mag, gmag, vtmag or vmag)
+        #[arg(long)]
+        mag_column: Option<String>,
+
+        /// Largest detection-to-catalog separation for a match, in arcseconds
+        #[arg(long, default_value_t = 3.0)]
+        match_radius: f64,
+
+        /// Output format: table or json
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
     /// Create annotated PNG with detected stars marked
     AnnotateStars {
         /// Path to FITS file
         fits_path: String,
 
-        /// Output PNG path (if not provided, uses FITS filename with _annotated.png suffix)
+        /// Output path (if not provided, uses FITS filename with _annotated.png or _annotated.svg suffix)
         #[arg(short, long)]
         output: Option<String>,
 
+        /// Output format: png (circles drawn into the image) or svg (vector circles and HFR labels over the embedded image)
+        #[arg(long, default_value = "png")]
+        format: String,
+
         /// Maximum number of stars to annotate (default: 500)
         #[arg(long, default_value = "500")]
         max_stars: usize,
@@ -555,11 +1189,49 @@ pub enum Commands {
         #[arg(long, default_value = "none")]
         psf_type: String,
 
+        /// Marker drawn at each star: circle, cross or box
+        #[arg(long, default_value = "circle")]
+        marker: String,
+
+        /// Marker size: fixed (the minimum size) or hfr-scaled (2.5 x HFR)
+        #[arg(long, default_value = "hfr-scaled")]
+        marker_size: String,
+
+        /// Smallest marker radius in pixels
+        #[arg(long, default_value = "5")]
+        min_marker_size: f64,
+
+        /// Largest marker radius in pixels
+        #[arg(long, default_value = "50")]
+        max_marker_size: f64,
+
         /// Enable verbose debug output
         #[arg(long, short)]
         verbose: bool,
     },
 
+    /// Export a frame's detected stars (position, HFR, FWHM, flux,
+    /// eccentricity) as a catalog for other astronomy software
+    ExportStars {
+        /// Path to FITS file
+        fits_path: String,
+
+        /// Output path (CSV and JSON default to stdout; FITS to the FITS
+        /// filename with a _stars.fits suffix)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Output format: csv, json, or fits (a BINTABLE extension with
+        /// columns X, Y, HFR, FWHM, FLUX, ECCENTRICITY)
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+
+        /// PSF fitting type (none, gaussian, moffat4); eccentricity is
+        /// left empty without a fit
+        #[arg(long, default_value = "moffat4")]
+        psf_type: String,
+    },
+
     /// Visualize PSF fit residuals for detected stars
     VisualizePsf {
         /// Path to FITS file
@@ -581,7 +1253,7 @@ pub enum Commands {
         #[arg(long, default_value = "9")]
         max_stars: usize,
 
-        /// Star selection mode (top, regions, quality, corners)
+        /// Star selection mode (top, regions, quality, corners, spatial-grid)
         #[arg(long, default_value = "top")]
         selection_mode: String,
 
@@ -619,10 +1291,14 @@ pub enum Commands {
         #[arg(long, default_value = "5")]
         grid_cols: usize,
 
-        /// Star selection mode (top, regions, quality, corners)
+        /// Star selection mode (top, regions, quality, corners, spatial-grid)
         #[arg(long, default_value = "corners")]
         selection_mode: String,
 
+        /// Omit the per-star metric labels under each cell
+        #[arg(long)]
+        no_labels: bool,
+
         /// Enable verbose debug output
         #[arg(long, short)]
         verbose: bool,
@@ -654,6 +1330,12 @@ pub enum Commands {
         kind: SyncKind,
     },
 
+    /// Inspect a server TOML configuration file without starting the server.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
     /// Start the web server for API access and static file serving
     Server {
         /// Path to TOML configuration file
@@ -667,6 +1349,7 @@ pub enum Commands {
 
         /// Database file to use. Registered into the registry on first run
         /// so subsequent starts pick it up automatically.
+        #[arg(conflicts_with = "no_database")]
         database: Option<String>,
 
         /// Base directories containing the image files. Used as the new
@@ -720,6 +1403,36 @@ pub enum Commands {
         /// trusted interface (e.g. localhost). Tauri mode always enables it.
         #[arg(long)]
         allow_database_management: bool,
+
+        /// Log output format. Defaults to `RUST_LOG_FORMAT`, then text
+        #[arg(long, value_enum)]
+        log_format: Option<LogFormat>,
+
+        /// Filesystem mode: serve the --scan-dirs folders without a N.I.N.A.
+        /// database. Projects, targets and images are synthesized from FITS
+        /// headers into a database kept in the cache directory, so grades
+        /// persist across restarts. The registry is not read or written.
+        #[arg(long, requires = "scan_dirs")]
+        no_database: bool,
+
+        /// Directories of FITS files to scan in filesystem mode (repeatable)
+        #[arg(long = "scan-dirs", num_args = 1.., requires = "no_database")]
+        scan_dirs: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Load and validate a config file, then print the effective
+    /// configuration (defaults applied, paths resolved). Exits non-zero
+    /// with the problem when the file is invalid.
+    Check {
+        /// Path to the TOML configuration file
+        path: String,
+
+        /// Output format (toml, json)
+        #[arg(short, long, default_value = "toml")]
+        format: String,
     },
 }
 
@@ -897,6 +1610,34 @@ impl StatisticalOptions {
     }
 }
 
+/// Server log output format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per event, for log shippers such as Loki or ELK
+    Json,
+}
+
+impl LogFormat {
+    /// Format named by `RUST_LOG_FORMAT` (`json` or `text`), defaulting to text.
+    pub fn from_env() -> Self {
+        match std::env::var("RUST_LOG_FORMAT") {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Self::Text,
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        if value.trim().eq_ignore_ascii_case("json") {
+            Self::Json
+        } else {
+            Self::Text
+        }
+    }
+}
+
 /// Configuration for background image pre-generation
 #[derive(Debug, Clone)]
 pub struct PregenerationConfig {
@@ -904,7 +1645,13 @@ pub struct PregenerationConfig {
     pub large_enabled: bool,
     pub original_enabled: bool,
     pub annotated_enabled: bool,
+    /// Further `[preview.sizes]` presets to pregenerate.
+    pub extra_sizes: Vec<String>,
+    /// Preview size presets, shared with the on-demand preview handlers.
+    pub preview_sizes: crate::config::PreviewSizes,
     pub cache_expiry: Duration,
+    /// `Cache-Control: max-age` for served images.
+    pub http_max_age: Duration,
 }
 
 impl Default for PregenerationConfig {
@@ -914,7 +1661,10 @@ impl Default for PregenerationConfig {
             large_enabled: false,
             original_enabled: false,
             annotated_enabled: false,
+            extra_sizes: Vec::new(),
+            preview_sizes: crate::config::PreviewSizes::default(),
             cache_expiry: Duration::from_secs(86400 * 365), // 1 year default
+            http_max_age: Duration::from_secs(86400),
         }
     }
 }
@@ -951,6 +1701,7 @@ impl PregenerationConfig {
             original_enabled: original,
             annotated_enabled: annotated,
             cache_expiry,
+            ..Self::default()
         })
     }
 
@@ -962,20 +1713,61 @@ impl PregenerationConfig {
                 large_enabled: cfg.enabled.unwrap_or(false) && cfg.large.unwrap_or(false),
                 original_enabled: false,  // Not supported in config yet
                 annotated_enabled: false, // Not supported in config yet
-                cache_expiry: Duration::from_secs(86400 * 365), // 1 year default
+                ..Self::default()
             }
         } else {
             Self::default()
         }
     }
 
+    /// Apply the configured preview presets and the extra sizes to
+    /// pregenerate (see [`crate::config::Config::get_pregeneration_sizes`]).
+    pub fn with_sizes(
+        mut self,
+        preview_sizes: crate::config::PreviewSizes,
+        extra_sizes: Vec<String>,
+    ) -> Self {
+        self.preview_sizes = preview_sizes;
+        self.extra_sizes = extra_sizes;
+        self
+    }
+
+    /// Apply the configured browser cache lifetime for served images.
+    pub fn with_http_max_age(mut self, http_max_age: Duration) -> Self {
+        self.http_max_age = http_max_age;
+        self
+    }
+
+    /// Preview sizes to pregenerate, in order and without repeats.
+    pub fn preview_sizes_to_generate(&self) -> Vec<&str> {
+        let builtin = [
+            (self.screen_enabled, "screen"),
+            (self.large_enabled, "large"),
+            (self.original_enabled, "original"),
+        ];
+        let mut sizes: Vec<&str> = builtin
+            .into_iter()
+            .filter_map(|(enabled, size)| enabled.then_some(size))
+            .collect();
+        for size in &self.extra_sizes {
+            if !sizes.contains(&size.as_str()) {
+                sizes.push(size);
+            }
+        }
+        sizes
+    }
+
     /// Check if any pre-generation is enabled
     pub fn is_enabled(&self) -> bool {
-        self.screen_enabled || self.large_enabled || self.original_enabled || self.annotated_enabled
+        self.screen_enabled
+            || self.large_enabled
+            || self.original_enabled
+            || self.annotated_enabled
+            || !self.extra_sizes.is_empty()
     }
 
     /// Get list of enabled formats for logging
-    pub fn enabled_formats(&self) -> Vec<&'static str> {
+    pub fn enabled_formats(&self) -> Vec<&str> {
         let mut formats = Vec::new();
         if self.screen_enabled {
             formats.push("screen");
@@ -986,6 +1778,9 @@ impl PregenerationConfig {
         if self.original_enabled {
             formats.push("original");
         }
+        for size in &self.extra_sizes {
+            formats.push(size.as_str());
+        }
         if self.annotated_enabled {
             formats.push("annotated");
         }
@@ -997,6 +1792,57 @@ impl PregenerationConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn log_format_parses_flag_and_env_values() {
+        let cli = Cli::try_parse_from(["psf-guard", "server", "--log-format", "json"]).unwrap();
+        match cli.command {
+            Commands::Server { log_format, .. } => assert_eq!(log_format, Some(LogFormat::Json)),
+            _ => panic!("expected server command"),
+        }
+        assert_eq!(LogFormat::parse("JSON"), LogFormat::Json);
+        assert_eq!(LogFormat::parse("text"), LogFormat::Text);
+        assert_eq!(LogFormat::parse("bogus"), LogFormat::Text);
+    }
+
+    #[test]
+    fn config_check_parses_path_and_format() {
+        let cli =
+            Cli::try_parse_from(["psf-guard", "config", "check", "server.toml", "-f", "json"])
+                .unwrap();
+        match cli.command {
+            Commands::Config {
+                action: ConfigAction::Check { path, format },
+            } => {
+                assert_eq!(path, "server.toml");
+                assert_eq!(format, "json");
+            }
+            _ => panic!("expected config check command"),
+        }
+    }
+
+    #[test]
+    fn animate_size_is_range_checked() {
+        let parse = |size: &str| {
+            Cli::try_parse_from([
+                "psf-guard",
+                "animate",
+                "-t",
+                "M31",
+                "-o",
+                "m31.gif",
+                "--size",
+                size,
+            ])
+        };
+        match parse("256").unwrap().command {
+            Commands::Animate { size, .. } => assert_eq!(size, 256),
+            _ => panic!("expected animate command"),
+        }
+        assert!(parse("0").is_err());
+        assert!(parse("15").is_err());
+        assert!(parse("4097").is_err());
+    }
+
     #[test]
     fn test_statistical_options_to_grading_config_disabled() {
         let options = StatisticalOptions {
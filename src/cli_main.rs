@@ -5,9 +5,10 @@ use std::path::{Path, PathBuf};
 
 use crate::cli::{Cli, Commands};
 use crate::commands::{
-    analyze_fits_and_compare, annotate_stars, benchmark_psf, dump_grading_results,
-    filter_rejected_files, list_projects, list_targets, read_fits, regrade_images, screen_fits,
-    show_images, stretch_to_png, update_grade,
+    analyze_batch, analyze_fits_and_compare, annotate_stars, background_extract, benchmark_psf,
+    detect_trails, dump_grading_results, export_stars, filter_rejected_files, list_projects,
+    list_targets, merge_targets, read_fits, regrade_images, screen_fits, show_images,
+    stretch_to_png, update_grade,
 };
 
 struct SyncPair {
@@ -61,6 +62,32 @@ fn open_sync_pair(from: &str, to: &str, registry: Option<&str>) -> Result<SyncPa
     })
 }
 
+/// Load the registry at `path` (or the platform default); a missing or
+/// unreadable registry is `None`, for commands that only use it for lookups.
+fn load_registry(path: Option<&str>) -> Option<crate::db_registry::DbRegistry> {
+    use crate::db_registry::DbRegistry;
+    match path {
+        Some(path) => DbRegistry::load_or_init(Path::new(path)).ok(),
+        None => DbRegistry::default_path()
+            .ok()
+            .and_then(|path| DbRegistry::load_or_init(&path).ok()),
+    }
+}
+
+/// The server's per-database cache directory under `cache_root` for the
+/// database at `db_path`, plus its registry entry when it has one.
+fn registered_database<'a>(
+    registry: Option<&'a crate::db_registry::DbRegistry>,
+    db_path: &str,
+    cache_root: &str,
+) -> (PathBuf, Option<&'a crate::db_registry::DbEntry>) {
+    let entry = registry.and_then(|registry| registry.find_by_path(db_path));
+    let db_id = entry
+        .map(|entry| entry.id.clone())
+        .unwrap_or_else(|| crate::server::slug::compute_default_slug(db_path));
+    (Path::new(cache_root).join(db_id), entry)
+}
+
 pub fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -70,14 +97,19 @@ pub fn main() -> Result<()> {
             project,
             target,
             format,
+            config,
         } => {
-            let conn = Connection::open(&cli.database)
-                .with_context(|| format!("Failed to open database: {}", cli.database))?;
-            dump_grading_results(&conn, status, project, target, &format)?;
+            let reason_mapper = match config {
+                Some(path) => crate::config::Config::from_file(&path)
+                    .with_context(|| format!("Failed to load config file: {}", path))?
+                    .get_reason_mapper()?,
+                None => Default::default(),
+            };
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
+            dump_grading_results(&conn, status, project, target, &format, &reason_mapper)?;
         }
         Commands::ListProjects => {
-            let conn = Connection::open(&cli.database)
-                .with_context(|| format!("Failed to open database: {}", cli.database))?;
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
             list_projects(&conn)?;
         }
         Commands::RemoveImported {
@@ -198,6 +230,7 @@ pub fn main() -> Result<()> {
             name,
             time_gap_days,
             profile_id,
+            group_by_position,
             dry_run,
             no_register,
             registry,
@@ -225,6 +258,7 @@ pub fn main() -> Result<()> {
                 time_gap_days,
                 profile_id,
                 dry_run,
+                group_by_position,
                 ..Default::default()
             };
             let outcome = import_frames(&mut conn, frames, &options)?;
@@ -277,8 +311,12 @@ pub fn main() -> Result<()> {
             dry_run,
             no_attach,
             match_radius_deg,
+            group_by_position,
+            format,
+            log,
             registry,
         } => {
+            use crate::commands::import::logs::{frames_from_log, parse_log, ImportFormat};
             use crate::commands::import::{
                 collect_fits_files, import_frames, print_outcome, scan_frames, ImportOptions,
             };
@@ -296,9 +334,37 @@ pub fn main() -> Result<()> {
             let db_path = resolve_db_path(reg.as_ref(), &db)?;
 
             let dirs: Vec<PathBuf> = directories.iter().map(PathBuf::from).collect();
-            let files = collect_fits_files(&dirs)?;
-            println!("Found {} FITS file(s); reading headers...", files.len());
-            let frames = scan_frames(&files);
+            let frames = match (format, log) {
+                (ImportFormat::Fits, None) => {
+                    let files = collect_fits_files(&dirs)?;
+                    println!("Found {} FITS file(s); reading headers...", files.len());
+                    scan_frames(&files)
+                }
+                (ImportFormat::Fits, Some(_)) => {
+                    anyhow::bail!("--log needs a log --format (e.g. --format asiair)")
+                }
+                (_, None) => anyhow::bail!("A log --format needs --log <path>"),
+                (format, Some(log_path)) => {
+                    let text = std::fs::read_to_string(&log_path)
+                        .with_context(|| format!("reading log {}", log_path))?;
+                    let entries = parse_log(format, &text)
+                        .with_context(|| format!("parsing log {}", log_path))?;
+                    println!("Log lists {} frame(s); locating files...", entries.len());
+                    let roots: Vec<&Path> = dirs.iter().map(PathBuf::as_path).collect();
+                    let tree = crate::directory_tree::DirectoryTree::build_multiple(&roots)?;
+                    let (frames, missing) = frames_from_log(entries, &tree);
+                    if !missing.is_empty() {
+                        println!(
+                            "⚠️  {} logged file(s) not found under the directories; skipped:",
+                            missing.len()
+                        );
+                        for name in &missing {
+                            println!("   {}", name);
+                        }
+                    }
+                    frames
+                }
+            };
 
             // READ_WRITE without CREATE: a wrong path must error, not leave a
             // junk sqlite file behind (same rule as screen-fits --regrade-db).
@@ -316,13 +382,70 @@ pub fn main() -> Result<()> {
                 dry_run,
                 attach_existing: !no_attach,
                 match_radius_deg,
+                group_by_position,
             };
             let outcome = import_frames(&mut conn, frames, &options)?;
             print_outcome(&outcome);
         }
+        Commands::Scan {
+            db,
+            base_dir,
+            project,
+            target,
+            apply,
+            match_radius_deg,
+            registry,
+        } => {
+            use crate::commands::import::{
+                collect_fits_files, print_outcome, scan_frames, scan_frames_into, ScanOptions,
+            };
+            use crate::commands::sync::{require_pull_capable, resolve_db_path};
+            use crate::db_registry::DbRegistry;
+            use rusqlite::OpenFlags;
+            use std::path::PathBuf;
+
+            let registry_path = match &registry {
+                Some(p) => PathBuf::from(p),
+                None => DbRegistry::default_path().context("resolving default registry path")?,
+            };
+            let reg = DbRegistry::load_or_init(&registry_path).ok();
+            let db_path = resolve_db_path(reg.as_ref(), &db)?;
+
+            let dirs: Vec<PathBuf> = base_dir.iter().map(PathBuf::from).collect();
+            let files = collect_fits_files(&dirs)?;
+            println!("Found {} FITS file(s); reading headers...", files.len());
+            let frames = scan_frames(&files);
+
+            let mut conn = Connection::open_with_flags(
+                &db_path,
+                OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI,
+            )
+            .with_context(|| format!("opening database at {}", db_path.display()))?;
+            require_pull_capable(&conn)?;
+
+            let options = ScanOptions {
+                project,
+                target,
+                dry_run: !apply,
+                match_radius_deg,
+            };
+            let outcome = scan_frames_into(&mut conn, frames, &options)?;
+            print_outcome(&outcome.import);
+            if !outcome.unmatched.is_empty() {
+                println!(
+                    "⚠️  {} new frame(s) matched no target; not added:",
+                    outcome.unmatched.len()
+                );
+                for path in &outcome.unmatched {
+                    println!("   {}", path.display());
+                }
+            }
+            if !apply {
+                println!("Re-run with --apply to write these rows.");
+            }
+        }
         Commands::ListTargets { project } => {
-            let conn = Connection::open(&cli.database)
-                .with_context(|| format!("Failed to open database: {}", cli.database))?;
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
             list_targets(&conn, &project)?;
         }
         Commands::MoveRejects {
@@ -364,8 +487,7 @@ pub fn main() -> Result<()> {
                 ));
             }
 
-            let conn = Connection::open(&entry.db_path)
-                .with_context(|| format!("opening database at {}", entry.db_path))?;
+            let conn = crate::db::open_connection(&entry.db_path, cli.read_only)?;
             require_target_scheduler_guid(&conn)?;
             // Dry-run must not write to the DB — defer table creation to live
             // runs. move_rejects tolerates a missing table in dry-run mode.
@@ -431,8 +553,7 @@ pub fn main() -> Result<()> {
                     registry_path.display(),
                 ))?;
 
-            let conn = Connection::open(&entry.db_path)
-                .with_context(|| format!("opening database at {}", entry.db_path))?;
+            let conn = crate::db::open_connection(&entry.db_path, cli.read_only)?;
             require_target_scheduler_guid(&conn)?;
 
             let options = RestoreRejectsOptions {
@@ -477,8 +598,7 @@ pub fn main() -> Result<()> {
                  still works for now and retains its statistical-analysis \
                  flags that the new command does not duplicate.\n"
             );
-            let conn = Connection::open(&database)
-                .with_context(|| format!("Failed to open database: {}", database))?;
+            let conn = crate::db::open_connection(&database, cli.read_only)?;
 
             let stat_config = stat_options.to_grading_config();
             filter_rejected_files(
@@ -498,24 +618,118 @@ pub fn main() -> Result<()> {
             project,
             days,
             reset,
+            format,
+            preserve_manual,
+            config,
             stat_options,
         } => {
-            let conn = Connection::open(&database)
-                .with_context(|| format!("Failed to open database: {}", database))?;
+            let manual_markers = match config {
+                Some(path) => crate::config::Config::from_file(&path)
+                    .with_context(|| format!("Failed to load config file: {}", path))?
+                    .get_manual_markers()?,
+                None => Default::default(),
+            };
+            let conn = crate::db::open_connection(&database, cli.read_only)?;
 
-            let stat_config = stat_options.to_grading_config();
-            regrade_images(&conn, dry_run, target, project, days, &reset, stat_config)?;
+            let options = crate::commands::regrade::RegradeOptions {
+                dry_run,
+                target,
+                project,
+                days,
+                reset_mode: reset,
+                stat_config: stat_options.to_grading_config(),
+                format,
+                preserve_manual,
+                manual_markers,
+            };
+            regrade_images(&conn, &options)?;
         }
         Commands::ShowImages { ids } => {
-            let conn = Connection::open(&cli.database)
-                .with_context(|| format!("Failed to open database: {}", cli.database))?;
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
             show_images(&conn, &ids)?;
         }
         Commands::UpdateGrade { id, status, reason } => {
-            let conn = Connection::open(&cli.database)
-                .with_context(|| format!("Failed to open database: {}", cli.database))?;
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
             update_grade(&conn, id, &status, reason)?;
         }
+        Commands::MergeTargets {
+            from,
+            into,
+            delete_source,
+        } => {
+            if cli.read_only {
+                anyhow::bail!("merge-targets writes to the database; drop --read-only");
+            }
+            let conn = crate::db::open_connection(&cli.database, false)?;
+            merge_targets(&conn, from, into, delete_source)?;
+        }
+        Commands::FindDuplicates {
+            project,
+            target,
+            delete,
+        } => {
+            if cli.read_only && delete {
+                anyhow::bail!("--delete needs a writable database; drop --read-only");
+            }
+            let conn = crate::db::open_connection(&cli.database, !delete)?;
+            crate::commands::find_duplicates::find_duplicates(
+                &conn,
+                project.as_deref(),
+                target.as_deref(),
+                delete,
+            )?;
+        }
+        Commands::ExportCuration { project, output } => {
+            use crate::commands::curation::{export_project, project_id_by_name};
+
+            let conn = crate::db::open_connection(&cli.database, true)?;
+            let document = export_project(&conn, project_id_by_name(&conn, &project)?)?
+                .with_context(|| format!("Project '{}' not found", project))?;
+            let json = serde_json::to_string_pretty(&document)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json).with_context(|| format!("writing {}", path))?;
+                    eprintln!("Exported {} images to {}", document.images.len(), path);
+                }
+                None => println!("{}", json),
+            }
+            if document.skipped_without_filename > 0 {
+                eprintln!(
+                    "Warning: skipped {} image(s) without a FileName in their metadata; \
+                     their curation can't be exported",
+                    document.skipped_without_filename
+                );
+            }
+        }
+        Commands::ImportCuration {
+            file,
+            project,
+            dry_run,
+        } => {
+            use crate::commands::curation::{import_curation, project_id_by_name, CurationExport};
+
+            let content =
+                std::fs::read_to_string(&file).with_context(|| format!("reading {}", file))?;
+            let document: CurationExport = serde_json::from_str(&content)
+                .with_context(|| format!("parsing curation document {}", file))?;
+            // Writable even for --dry-run: the staged writes are rolled back.
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
+            let project_id = project
+                .as_deref()
+                .map(|name| project_id_by_name(&conn, name))
+                .transpose()?;
+            let summary = import_curation(&conn, &document, project_id, dry_run)?;
+            println!(
+                "{}{} records: {} matched ({} updated, {} unchanged), {} unmatched, {} ambiguous",
+                if dry_run { "[dry run] " } else { "" },
+                summary.records,
+                summary.matched,
+                summary.updated,
+                summary.unchanged,
+                summary.unmatched,
+                summary.ambiguous
+            );
+        }
         Commands::ReadFits {
             path,
             verbose,
@@ -523,6 +737,295 @@ pub fn main() -> Result<()> {
         } => {
             read_fits(&path, verbose, &format)?;
         }
+        Commands::AnalyzeBatch {
+            base_dirs,
+            project,
+            target,
+            write_metadata,
+            dry_run,
+            overwrite,
+            threads,
+        } => {
+            if cli.read_only && write_metadata && !dry_run {
+                anyhow::bail!(
+                    "--write-metadata needs a writable database; drop --read-only or add --dry-run"
+                );
+            }
+            let conn = crate::db::open_connection(
+                &cli.database,
+                cli.read_only || !write_metadata || dry_run,
+            )?;
+            analyze_batch(
+                &conn,
+                &crate::commands::analyze_batch::AnalyzeBatchOptions {
+                    base_dirs,
+                    project,
+                    target,
+                    write_metadata,
+                    dry_run,
+                    overwrite,
+                    threads,
+                },
+            )?;
+        }
+        Commands::BackfillMetrics {
+            base_dirs,
+            project,
+            target,
+            detect,
+            force,
+            max_rate,
+            threads,
+        } => {
+            if cli.read_only {
+                anyhow::bail!(
+                    "backfill-metrics writes its cache to the database; drop --read-only"
+                );
+            }
+            let conn = crate::db::open_connection(&cli.database, false)?;
+            crate::commands::backfill_metrics::backfill_metrics(
+                &conn,
+                &crate::commands::backfill_metrics::BackfillMetricsOptions {
+                    base_dirs,
+                    project,
+                    target,
+                    detect,
+                    force,
+                    max_rate,
+                    threads,
+                },
+            )?;
+        }
+        Commands::VerifyFiles {
+            base_dirs,
+            project,
+            target,
+            format,
+            threads,
+        } => {
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
+            crate::commands::verify_files::verify_files(
+                &conn,
+                &crate::commands::verify_files::VerifyFilesOptions {
+                    base_dirs,
+                    project,
+                    target,
+                    format,
+                    threads,
+                    record: !cli.read_only,
+                },
+            )?;
+        }
+        Commands::AnalyzeSequences {
+            project,
+            target,
+            filter,
+            output,
+            format,
+            config,
+            session_gap_minutes,
+            group_by_position,
+            cache_dir,
+            registry,
+        } => {
+            let registry = load_registry(registry.as_deref());
+            let (cache_dir, _) = registered_database(registry.as_ref(), &cli.database, &cache_dir);
+            let conn = crate::db::open_connection(&cli.database, true)?;
+            crate::commands::analyze_sequences::analyze_sequences(
+                &conn,
+                &cli.database,
+                &crate::commands::analyze_sequences::AnalyzeSequencesOptions {
+                    project,
+                    target,
+                    filter,
+                    output,
+                    format,
+                    config,
+                    session_gap_minutes,
+                    position_radius_deg: group_by_position,
+                    cache_dir,
+                },
+            )?;
+        }
+        Commands::Report {
+            project,
+            date,
+            output,
+            thumbnail_size,
+            image_dirs,
+            session_gap_minutes,
+            cache_dir,
+            registry,
+        } => {
+            let registry = load_registry(registry.as_deref());
+            let (cache_dir, entry) =
+                registered_database(registry.as_ref(), &cli.database, &cache_dir);
+            // Image dirs: explicit flag > registry entry.
+            let image_dirs = match image_dirs {
+                Some(dirs) if !dirs.is_empty() => dirs,
+                _ => entry.map(|e| e.image_dirs.clone()).unwrap_or_default(),
+            };
+            let conn = crate::db::open_connection(&cli.database, true)?;
+            crate::commands::night_report::night_report(
+                &conn,
+                &crate::commands::night_report::NightReportOptions {
+                    project,
+                    date,
+                    output,
+                    thumbnail_size,
+                    image_dirs,
+                    cache_dir,
+                    session_gap_minutes,
+                },
+            )?;
+        }
+        Commands::Animate {
+            target,
+            project,
+            filter,
+            fps,
+            size,
+            align,
+            output,
+            image_dirs,
+            registry,
+        } => {
+            let registry = load_registry(registry.as_deref());
+            let entry = registry
+                .as_ref()
+                .and_then(|registry| registry.find_by_path(&cli.database));
+            // Image dirs: explicit flag > registry entry.
+            let image_dirs = match image_dirs {
+                Some(dirs) if !dirs.is_empty() => dirs,
+                _ => entry.map(|e| e.image_dirs.clone()).unwrap_or_default(),
+            };
+            let conn = crate::db::open_connection(&cli.database, true)?;
+            crate::commands::animate::animate(
+                &conn,
+                &crate::commands::animate::AnimateOptions {
+                    target,
+                    project,
+                    filter,
+                    output,
+                    fps,
+                    size,
+                    align,
+                    image_dirs,
+                },
+            )?;
+        }
+        Commands::FocusVcurve {
+            target,
+            project,
+            filter,
+            max_gap,
+            output,
+            csv,
+            image_dirs,
+            registry,
+        } => {
+            let registry = load_registry(registry.as_deref());
+            let entry = registry
+                .as_ref()
+                .and_then(|registry| registry.find_by_path(&cli.database));
+            // Image dirs: explicit flag > registry entry.
+            let image_dirs = match image_dirs {
+                Some(dirs) if !dirs.is_empty() => dirs,
+                _ => entry.map(|e| e.image_dirs.clone()).unwrap_or_default(),
+            };
+            let conn = crate::db::open_connection(&cli.database, true)?;
+            crate::commands::focus_vcurve::focus_vcurve(
+                &conn,
+                &crate::commands::focus_vcurve::FocusVcurveOptions {
+                    target,
+                    project,
+                    filter,
+                    output,
+                    csv,
+                    max_gap,
+                    image_dirs,
+                },
+            )?;
+        }
+        Commands::Register {
+            target,
+            project,
+            filter,
+            reference,
+            max_drift,
+            output,
+            tag,
+            image_dirs,
+            registry,
+        } => {
+            let registry = load_registry(registry.as_deref());
+            let entry = registry
+                .as_ref()
+                .and_then(|registry| registry.find_by_path(&cli.database));
+            // Image dirs: explicit flag > registry entry.
+            let image_dirs = match image_dirs {
+                Some(dirs) if !dirs.is_empty() => dirs,
+                _ => entry.map(|e| e.image_dirs.clone()).unwrap_or_default(),
+            };
+            if cli.read_only && tag.is_some() {
+                anyhow::bail!("--tag needs a writable database; drop --read-only");
+            }
+            // Writable only when flagged frames get tagged.
+            let conn = crate::db::open_connection(&cli.database, tag.is_none())?;
+            crate::commands::register::register(
+                &conn,
+                &crate::commands::register::RegisterOptions {
+                    target,
+                    project,
+                    filter,
+                    reference,
+                    max_drift,
+                    output,
+                    tag,
+                    image_dirs,
+                },
+            )?;
+        }
+        Commands::ContactSheet {
+            target,
+            project,
+            filter,
+            cols,
+            cell_size,
+            no_grades,
+            output,
+            image_dirs,
+            registry,
+        } => {
+            if cols == 0 || cell_size < 16 {
+                anyhow::bail!("--cols must be at least 1 and --cell-size at least 16");
+            }
+            let registry = load_registry(registry.as_deref());
+            let entry = registry
+                .as_ref()
+                .and_then(|registry| registry.find_by_path(&cli.database));
+            // Image dirs: explicit flag > registry entry.
+            let image_dirs = match image_dirs {
+                Some(dirs) if !dirs.is_empty() => dirs,
+                _ => entry.map(|e| e.image_dirs.clone()).unwrap_or_default(),
+            };
+            let conn = crate::db::open_connection(&cli.database, true)?;
+            crate::commands::contact_sheet::contact_sheet(
+                &conn,
+                &crate::commands::contact_sheet::ContactSheetOptions {
+                    target,
+                    project,
+                    filter,
+                    output,
+                    layout: crate::commands::contact_sheet::SheetLayout {
+                        columns: cols,
+                        cell_size,
+                        grade_borders: !no_grades,
+                    },
+                    image_dirs,
+                },
+            )?;
+        }
         Commands::AnalyzeFits {
             path,
             project,
@@ -535,8 +1038,7 @@ pub fn main() -> Result<()> {
             psf_type,
             verbose,
         } => {
-            let conn = Connection::open(&cli.database)
-                .with_context(|| format!("Failed to open database: {}", cli.database))?;
+            let conn = crate::db::open_connection(&cli.database, cli.read_only)?;
             analyze_fits_and_compare(
                 &conn,
                 &path,
@@ -557,6 +1059,10 @@ pub fn main() -> Result<()> {
             format,
             min_score,
             dead_cell_rise,
+            temporal_anomaly_reject,
+            min_stars,
+            low_clip_fraction,
+            high_clip_fraction,
             threads,
             session_gap,
             regrade_db,
@@ -564,6 +1070,7 @@ pub fn main() -> Result<()> {
             registry,
             cache_dir,
             annotate,
+            detect_trails,
             verbose,
         } => {
             crate::debug::init_debug(verbose);
@@ -572,6 +1079,12 @@ pub fn main() -> Result<()> {
                 format,
                 min_score,
                 dead_cell_rise,
+                temporal_anomaly_reject,
+                min_stars,
+                exposure: crate::image_analysis::ExposureThresholds {
+                    low_clip_fraction,
+                    high_clip_fraction,
+                },
                 threads,
                 session_gap_minutes: session_gap,
                 regrade_db,
@@ -579,6 +1092,7 @@ pub fn main() -> Result<()> {
                 registry,
                 cache_dir,
                 annotate_dir: annotate,
+                detect_trails,
             };
             screen_fits(&path, &options)?;
         }
@@ -599,9 +1113,57 @@ pub fn main() -> Result<()> {
                 invert,
             )?;
         }
+        Commands::FitsToTiff {
+            input,
+            output,
+            bit_depth,
+            stretch,
+            midtone_factor,
+            shadow_clipping,
+        } => {
+            use crate::commands::fits_to_tiff::{fits_to_tiff, TiffDepth};
+
+            fits_to_tiff(
+                &input,
+                output,
+                TiffDepth::from_bits(bit_depth)?,
+                stretch.then_some((midtone_factor, shadow_clipping)),
+            )?;
+        }
+        Commands::BackgroundExtract {
+            fits_path,
+            output_dir,
+            levels,
+        } => {
+            background_extract(&fits_path, output_dir, levels)?;
+        }
+        Commands::DetectTrails {
+            fits_path,
+            overlay,
+            sigma,
+            format,
+        } => {
+            detect_trails(&fits_path, overlay, sigma, &format)?;
+        }
+        Commands::ZeroPoint {
+            fits_path,
+            catalog,
+            mag_column,
+            match_radius,
+            format,
+        } => {
+            crate::commands::zero_point(
+                &fits_path,
+                &catalog,
+                mag_column.as_deref(),
+                match_radius,
+                &format,
+            )?;
+        }
         Commands::AnnotateStars {
             fits_path,
             output,
+            format,
             max_stars,
             detector,
             sensitivity,
@@ -609,8 +1171,18 @@ pub fn main() -> Result<()> {
             shadow_clipping,
             annotation_color,
             psf_type,
+            marker,
+            marker_size,
+            min_marker_size,
+            max_marker_size,
             verbose,
         } => {
+            let markers = crate::commands::annotate_stars_common::MarkerOptions {
+                style: marker.parse().map_err(anyhow::Error::msg)?,
+                scaling: marker_size.parse().map_err(anyhow::Error::msg)?,
+                min_radius: min_marker_size,
+                max_radius: max_marker_size,
+            };
             annotate_stars(
                 &fits_path,
                 output,
@@ -621,9 +1193,19 @@ pub fn main() -> Result<()> {
                 shadow_clipping,
                 &annotation_color,
                 &psf_type,
+                markers,
+                &format,
                 verbose,
             )?;
         }
+        Commands::ExportStars {
+            fits_path,
+            output,
+            format,
+            psf_type,
+        } => {
+            export_stars(&fits_path, output, &format, &psf_type)?;
+        }
         Commands::VisualizePsf {
             fits_path,
             output,
@@ -647,6 +1229,7 @@ pub fn main() -> Result<()> {
                 &sort_by,
                 3, // Default to 3 columns
                 &selection_mode,
+                true,
                 verbose,
             )?;
         }
@@ -658,6 +1241,7 @@ pub fn main() -> Result<()> {
             sort_by,
             grid_cols,
             selection_mode,
+            no_labels,
             verbose,
         } => {
             use crate::commands::visualize_psf::visualize_psf_multi;
@@ -670,6 +1254,7 @@ pub fn main() -> Result<()> {
                 &sort_by,
                 grid_cols,
                 &selection_mode,
+                !no_labels,
                 verbose,
             )?;
         }
@@ -680,6 +1265,11 @@ pub fn main() -> Result<()> {
         } => {
             benchmark_psf(&fits_path, runs, verbose)?;
         }
+        Commands::Config { action } => match action {
+            crate::cli::ConfigAction::Check { path, format } => {
+                crate::commands::config_check(&path, &format)?;
+            }
+        },
         Commands::Sync { kind } => match kind {
             crate::cli::SyncKind::Grades {
                 from,
@@ -918,21 +1508,50 @@ pub fn main() -> Result<()> {
             pregenerate_all,
             cache_expiry,
             allow_database_management,
+            log_format,
+            no_database,
+            scan_dirs,
         } => {
             use crate::config::Config;
             use crate::db_registry::DbRegistry;
             use std::path::PathBuf;
 
-            // 1) Resolve and load the database registry.
+            crate::server::init_tracing(log_format.unwrap_or_else(crate::cli::LogFormat::from_env));
+
+            // 1) Resolve and load the database registry. Filesystem mode
+            //    leaves it alone entirely.
             let registry_path = match registry {
                 Some(p) => PathBuf::from(p),
                 None => DbRegistry::default_path().context("resolving default registry path")?,
             };
-            let mut db_registry = DbRegistry::load_or_init(&registry_path)
-                .with_context(|| format!("loading registry at {}", registry_path.display()))?;
+            let mut db_registry = if no_database {
+                DbRegistry::default()
+            } else {
+                DbRegistry::load_or_init(&registry_path)
+                    .with_context(|| format!("loading registry at {}", registry_path.display()))?
+            };
+
+            // 2) Load shared TOML config for port/cache_dir/pregeneration
+            //    knobs, then PSF_GUARD_* env overrides; CLI flags win below.
+            let mut app_config = if let Some(config_path) = config {
+                Config::from_file(&config_path)
+                    .with_context(|| format!("Failed to load config file: {}", config_path))?
+            } else {
+                Config::default()
+            };
+            app_config.apply_env()?;
+            app_config.merge_with_cli(database, Some(image_dirs), port, host, cache_dir);
+            // The positional DB/dirs win; otherwise PSF_GUARD_DATABASE_PATH /
+            // PSF_GUARD_IMAGE_DIRS (or the legacy [database]/[images]) stand in.
+            let database = app_config.database.as_ref().map(|db| db.path.clone());
+            let image_dirs = app_config
+                .images
+                .as_ref()
+                .map(|images| images.directories.clone())
+                .unwrap_or_default();
 
-            // 2) If the user passed a positional DB, register it (idempotent).
-            if let Some(db_path) = database {
+            // 3) If a DB was given, register it (idempotent).
+            if let Some(db_path) = database.filter(|_| !no_database) {
                 if db_registry.find_by_path(&db_path).is_none() {
                     let name = PathBuf::from(&db_path)
                         .file_stem()
@@ -954,17 +1573,9 @@ pub fn main() -> Result<()> {
                 }
             }
 
-            // 3) Load shared TOML config for port/cache_dir/pregeneration knobs.
-            let mut app_config = if let Some(config_path) = config {
-                Config::from_file(&config_path)
-                    .with_context(|| format!("Failed to load config file: {}", config_path))?
-            } else {
-                Config::default()
-            };
-            app_config.merge_with_cli(None, None, port, host, cache_dir);
-
             // We deliberately do NOT call app_config.validate() — the DB path
             // requirement no longer applies (DBs come from the registry).
+            app_config.validate_network()?;
 
             use crate::cli::PregenerationConfig;
             let pregeneration_config = if pregenerate_all
@@ -983,33 +1594,50 @@ pub fn main() -> Result<()> {
                 )?
             } else {
                 PregenerationConfig::from_config(app_config.get_pregeneration())
-            };
+            }
+            .with_sizes(
+                app_config.get_preview_sizes()?,
+                app_config.get_pregeneration_sizes()?,
+            )
+            .with_http_max_age(app_config.get_http_max_age());
 
-            let cache_directory = app_config.get_cache_directory();
-            let server_host = app_config.get_host();
-            let server_port = app_config.get_port();
-            let worker_policy = app_config.get_worker_policy();
-            let site_banner = app_config.get_site_banner()?;
-            let databases = db_registry.databases.clone();
-            let astrometry_config = db_registry.astrometry.clone();
+            let (databases, registry_path) = if no_database {
+                let entry = crate::server::standalone::prepare_filesystem_database(
+                    &scan_dirs,
+                    &app_config.get_cache_directory(),
+                )?;
+                (vec![entry], None)
+            } else {
+                (db_registry.databases.clone(), Some(registry_path))
+            };
+            let config = crate::server::ServerConfig {
+                databases,
+                static_dir,
+                cache_dir: app_config.get_cache_directory(),
+                host: app_config.get_host(),
+                port: app_config.get_port(),
+                pregeneration_config,
+                registry_path,
+                allow_database_management,
+                site_banner: app_config.get_site_banner()?,
+                reason_mapper: app_config.get_reason_mapper()?,
+                worker_policy: app_config.get_worker_policy(),
+                astrometry_config: db_registry.astrometry.clone(),
+                connection_options: crate::db::ConnectionOptions {
+                    read_only: cli.read_only,
+                    ..app_config.get_connection_options()
+                },
+                fits_read_retry: app_config.get_fits_read_retry(),
+                case_insensitive_filenames: app_config.get_case_insensitive_filenames(),
+                cors: app_config.get_cors_policy()?,
+                auth_token: app_config.get_auth_token(),
+                request_timeouts: app_config.get_request_timeouts(),
+                min_stars: app_config.get_min_stars(),
+                exposure_thresholds: app_config.get_exposure_thresholds(),
+            };
 
             let runtime = tokio::runtime::Runtime::new()?;
-            runtime.block_on(async {
-                crate::server::run_server(
-                    databases,
-                    static_dir,
-                    cache_directory,
-                    server_host,
-                    server_port,
-                    pregeneration_config,
-                    Some(registry_path),
-                    allow_database_management,
-                    site_banner,
-                    worker_policy,
-                    astrometry_config,
-                )
-                .await
-            })?;
+            runtime.block_on(crate::server::run_server_with_config(config))?;
         }
     }
 
@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::computed_metrics::FrameMetrics;
+use crate::db::Database;
+use crate::directory_tree::DirectoryTree;
+use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
+use crate::image_analysis::FitsImage;
+use crate::psf_fitting::PSFType;
+
+pub struct AnalyzeBatchOptions {
+    pub base_dirs: Vec<String>,
+    pub project: Option<String>,
+    pub target: Option<String>,
+    pub write_metadata: bool,
+    pub dry_run: bool,
+    /// Replace values already present in the metadata instead of only
+    /// filling in missing ones.
+    pub overwrite: bool,
+    pub threads: Option<usize>,
+}
+
+/// Run star detection on every image the database knows about (optionally
+/// narrowed by project/target), locating the files through a directory tree
+/// of `base_dirs`. With `write_metadata`, the computed HFR, star count and
+/// eccentricity are merged into each image's metadata JSON.
+pub fn analyze_batch(conn: &Connection, options: &AnalyzeBatchOptions) -> Result<()> {
+    let db = Database::new(conn);
+    let images = db.query_light_images(
+        None,
+        options.project.as_deref(),
+        options.target.as_deref(),
+        None,
+    )?;
+    println!("Found {} images in the database", images.len());
+
+    let roots: Vec<&Path> = options.base_dirs.iter().map(Path::new).collect();
+    let tree = DirectoryTree::build_multiple(&roots)
+        .context("Failed to scan the base directories for FITS files")?;
+
+    let mut missing = 0;
+    let mut jobs: Vec<(i32, String, PathBuf)> = Vec::new();
+    for (image, _, _) in images {
+        let path = metadata_filename(&image.metadata)
+            .and_then(|filename| tree.find_file_first(&filename).cloned());
+        match path {
+            Some(path) => jobs.push((image.id, image.metadata, path)),
+            None => missing += 1,
+        }
+    }
+    println!(
+        "Located {} FITS files ({} not found under the base directories)",
+        jobs.len(),
+        missing
+    );
+
+    let frame_pixels = jobs
+        .first()
+        .and_then(|(_, _, path)| crate::concurrency::probe_frame_pixels(path));
+    let budget = crate::concurrency::plan_workers(
+        options.threads,
+        &crate::concurrency::WorkerPolicy::all_cores(),
+        crate::concurrency::Priority::Interactive,
+        frame_pixels,
+    );
+    eprintln!(
+        "Analyzing with {} worker thread(s) — {}",
+        budget.workers, budget.rationale
+    );
+
+    let done = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let updates: Mutex<Vec<(i32, String)>> = Mutex::new(Vec::new());
+    let total = jobs.len();
+    crate::concurrency::parallel_index(total, budget.workers, |i| {
+        let (image_id, metadata, path) = &jobs[i];
+        let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+        let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("?");
+        let metrics = match analyze_frame(path) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                failed.fetch_add(1, Ordering::Relaxed);
+                eprintln!("[{}/{}] {}: {}", n, total, name, e);
+                return;
+            }
+        };
+        eprintln!(
+            "[{}/{}] {}: {} stars, hfr {:.2}, ecc {}",
+            n,
+            total,
+            name,
+            metrics.detected_stars,
+            metrics.hfr,
+            metrics
+                .eccentricity
+                .map(|e| format!("{:.2}", e))
+                .unwrap_or_else(|| "n/a".to_string()),
+        );
+
+        match merge_metrics(metadata, &metrics, options.overwrite) {
+            Ok(Some(merged)) => updates.lock().unwrap().push((*image_id, merged)),
+            Ok(None) => {}
+            Err(e) => {
+                failed.fetch_add(1, Ordering::Relaxed);
+                eprintln!("Image {}: {}", image_id, e);
+            }
+        }
+    });
+
+    let mut updates = updates.into_inner().unwrap();
+    updates.sort_by_key(|(id, _)| *id);
+    let failed = failed.into_inner();
+
+    if !options.write_metadata {
+        println!(
+            "\nAnalyzed {} images ({} failed); {} would gain metrics. Pass --write-metadata to store them.",
+            total - failed,
+            failed,
+            updates.len()
+        );
+        return Ok(());
+    }
+
+    if options.dry_run {
+        for (id, merged) in &updates {
+            println!("Image {}: {}", id, merged);
+        }
+        println!(
+            "\n[DRY RUN] Would update metadata for {} images ({} failed)",
+            updates.len(),
+            failed
+        );
+        return Ok(());
+    }
+
+    db.batch_update_image_metadata(&updates)?;
+    println!(
+        "\nUpdated metadata for {} images ({} failed)",
+        updates.len(),
+        failed
+    );
+    Ok(())
+}
+
+/// Detect stars and fit PSFs for one frame.
+pub fn analyze_frame(path: &Path) -> Result<FrameMetrics> {
+    let fits = FitsImage::from_file(path)
+        .with_context(|| format!("Failed to load FITS file: {}", path.display()))?;
+    let params = HocusFocusParams {
+        psf_type: PSFType::Moffat4,
+        ..Default::default()
+    };
+    let result = detect_stars_hocus_focus(&fits.data, fits.width, fits.height, &params);
+    Ok(FrameMetrics::from_detection(&result))
+}
+
+/// Merge computed metrics into an image's metadata JSON, keeping every other
+/// key. They go under the keys N.I.N.A. uses, so grading and sequence
+/// analysis pick them up unchanged. Existing values win unless `overwrite`
+/// is set. Returns `None` when nothing would change.
+pub fn merge_metrics(
+    metadata: &str,
+    metrics: &FrameMetrics,
+    overwrite: bool,
+) -> Result<Option<String>> {
+    let mut object: Map<String, Value> = if metadata.trim().is_empty() {
+        Map::new()
+    } else {
+        serde_json::from_str(metadata).context("Image metadata is not a JSON object")?
+    };
+
+    let mut computed = vec![
+        ("HFR", Value::from(metrics.hfr)),
+        ("DetectedStars", Value::from(metrics.detected_stars)),
+    ];
+    if let Some(eccentricity) = metrics.eccentricity {
+        computed.push(("Eccentricity", Value::from(eccentricity)));
+    }
+
+    let mut changed = false;
+    for (key, value) in computed {
+        let present = object.get(key).is_some_and(|v| !v.is_null());
+        if (overwrite || !present) && object.get(key) != Some(&value) {
+            object.insert(key.to_string(), value);
+            changed = true;
+        }
+    }
+
+    Ok(changed.then(|| Value::Object(object).to_string()))
+}
+
+/// Basename of the `FileName` recorded in the metadata (N.I.N.A. stores the
+/// capture machine's full path, often with Windows separators).
+fn metadata_filename(metadata: &str) -> Option<String> {
+    let metadata: Value = serde_json::from_str(metadata).ok()?;
+    metadata["FileName"]
+        .as_str()?
+        .split(&['\\', '/'][..])
+        .next_back()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> FrameMetrics {
+        FrameMetrics {
+            hfr: 2.25,
+            detected_stars: 812,
+            eccentricity: Some(0.42),
+            background: 1200.0,
+        }
+    }
+
+    #[test]
+    fn merge_fills_missing_metrics_and_keeps_other_keys() {
+        let merged = merge_metrics(
+            r#"{"FileName":"C:\\data\\a.fits","ExposureTime":120,"HFR":null}"#,
+            &metrics(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        let value: Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(value["FileName"], "C:\\data\\a.fits");
+        assert_eq!(value["ExposureTime"], 120);
+        assert_eq!(value["HFR"], 2.25);
+        assert_eq!(value["DetectedStars"], 812);
+        assert_eq!(value["Eccentricity"], 0.42);
+    }
+
+    #[test]
+    fn merge_keeps_existing_values_unless_overwriting() {
+        let metadata = r#"{"HFR":1.9,"DetectedStars":700,"Eccentricity":0.3}"#;
+        assert_eq!(merge_metrics(metadata, &metrics(), false).unwrap(), None);
+
+        let merged = merge_metrics(metadata, &metrics(), true).unwrap().unwrap();
+        let value: Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(value["HFR"], 2.25);
+        assert_eq!(value["DetectedStars"], 812);
+    }
+
+    #[test]
+    fn merge_rejects_non_object_metadata() {
+        assert!(merge_metrics("[1, 2]", &metrics(), false).is_err());
+    }
+
+    #[test]
+    fn metadata_filename_takes_the_basename() {
+        assert_eq!(
+            metadata_filename(r#"{"FileName":"C:\\N.I.N.A\\M31\\LIGHT\\frame_001.fits"}"#),
+            Some("frame_001.fits".to_string())
+        );
+        assert_eq!(
+            metadata_filename(r#"{"FileName":"/data/M31/frame_002.fits"}"#),
+            Some("frame_002.fits".to_string())
+        );
+        assert_eq!(metadata_filename("{}"), None);
+    }
+}
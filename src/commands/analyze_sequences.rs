@@ -0,0 +1,253 @@
+//! Offline sequence-analysis report: score every target of a project with the
+//! same code path as the server's `/analysis/sequence` endpoint and write the
+//! scored sequences to disk.
+//!
+//! The report opens with a header recording when and how it was produced,
+//! including the full analyzer configuration, so an archived report can be
+//! reproduced later (`--config` accepts that configuration back).
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::db::Database;
+use crate::scoring::{load_field_images, score_target_sequences, ScoringCaches};
+use crate::sequence_analysis::{ScoredSequence, SequenceAnalyzerConfig};
+use crate::sky_grouping::group_targets_by_position;
+
+pub struct AnalyzeSequencesOptions {
+    pub project: String,
+    pub target: Option<String>,
+    pub filter: Option<String>,
+    pub output: String,
+    /// `json` (one document) or `ndjson` (header line, then one sequence per
+    /// line).
+    pub format: String,
+    /// Analyzer configuration as JSON; defaults to the server's defaults.
+    pub config: Option<String>,
+    pub session_gap_minutes: Option<u64>,
+    /// Analyze targets within this many degrees of each other as one field.
+    pub position_radius_deg: Option<f64>,
+    /// Per-database cache directory holding quality-scan and plate-solve
+    /// evidence (`<server cache root>/<db id>`).
+    pub cache_dir: PathBuf,
+}
+
+/// How, when and from what a report was produced.
+#[derive(Debug, Serialize)]
+pub struct ReportHeader {
+    pub generated_at: String,
+    pub psf_guard_version: String,
+    pub database: String,
+    pub project: String,
+    pub target: Option<String>,
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_radius_deg: Option<f64>,
+    pub config: SequenceAnalyzerConfig,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    header: &'a ReportHeader,
+    sequences: &'a [ScoredSequence],
+}
+
+pub fn analyze_sequences(
+    conn: &Connection,
+    database: &str,
+    options: &AnalyzeSequencesOptions,
+) -> Result<()> {
+    if !matches!(options.format.as_str(), "json" | "ndjson") {
+        bail!(
+            "Unknown format '{}': expected json or ndjson",
+            options.format
+        );
+    }
+    if let Some(radius_deg) = options.position_radius_deg
+        && !(radius_deg.is_finite() && radius_deg > 0.0)
+    {
+        bail!("position radius must be a positive number of degrees");
+    }
+
+    let mut config = match &options.config {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("reading analyzer config {}", path))?;
+            serde_json::from_str::<SequenceAnalyzerConfig>(&text)
+                .with_context(|| format!("parsing analyzer config {}", path))?
+        }
+        None => SequenceAnalyzerConfig::default(),
+    };
+    if let Some(gap) = options.session_gap_minutes {
+        config.session_gap_minutes = gap;
+    }
+
+    let db = Database::new(conn);
+    let project_id = db.find_project_id_by_name(&options.project)?;
+    let targets: Vec<_> = db
+        .get_targets_with_images(project_id)?
+        .into_iter()
+        .map(|(target, _, _, _)| target)
+        .collect();
+    // A named target brings along the other targets of its field.
+    let fields: Vec<_> = group_targets_by_position(&targets, options.position_radius_deg)
+        .into_iter()
+        .filter(|field| {
+            options.target.as_ref().is_none_or(|name| {
+                targets
+                    .iter()
+                    .any(|t| t.name == *name && field.target_ids.contains(&t.id))
+            })
+        })
+        .collect();
+    if fields.is_empty() {
+        bail!(
+            "No targets with images in project '{}'{}",
+            options.project,
+            options
+                .target
+                .as_ref()
+                .map(|t| format!(" named '{}'", t))
+                .unwrap_or_default()
+        );
+    }
+
+    let spatial = crate::server::spatial_scan::SharedSpatialStore::default();
+    crate::server::spatial_scan::ensure_loaded(&spatial, &options.cache_dir);
+    let astrometry = crate::astrometry::AstrometryEvidenceCache::new();
+    let computed = crate::computed_metrics::load_all(conn)?;
+    let caches = ScoringCaches {
+        spatial: &spatial,
+        cache_dir: &options.cache_dir,
+        astrometry: &astrometry,
+        computed: &computed,
+    };
+
+    let mut sequences = Vec::new();
+    for field in &fields {
+        let (images, expected_by_image) =
+            load_field_images(conn, &field.target_ids, options.filter.as_deref())?;
+        if images.is_empty() {
+            continue;
+        }
+        let scored = score_target_sequences(
+            &images,
+            &expected_by_image,
+            field.target_id,
+            &field.name,
+            config.clone(),
+            &caches,
+        );
+        println!(
+            "{}: {} image(s) in {} sequence(s)",
+            field.name,
+            images.len(),
+            scored.len()
+        );
+        sequences.extend(scored);
+    }
+
+    let header = ReportHeader {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        psf_guard_version: env!("CARGO_PKG_VERSION").to_string(),
+        database: database.to_string(),
+        project: options.project.clone(),
+        target: options.target.clone(),
+        filter: options.filter.clone(),
+        position_radius_deg: options.position_radius_deg,
+        config,
+    };
+    write_report(
+        Path::new(&options.output),
+        &options.format,
+        &header,
+        &sequences,
+    )?;
+    println!(
+        "Wrote {} sequence(s) to {}",
+        sequences.len(),
+        options.output
+    );
+    Ok(())
+}
+
+/// Write the report as one JSON document or as NDJSON: a `{"header": ...}`
+/// line followed by one scored sequence per line.
+pub fn write_report(
+    path: &Path,
+    format: &str,
+    header: &ReportHeader,
+    sequences: &[ScoredSequence],
+) -> Result<()> {
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("creating report {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    if format == "ndjson" {
+        serde_json::to_writer(&mut out, &serde_json::json!({ "header": header }))?;
+        writeln!(out)?;
+        for sequence in sequences {
+            serde_json::to_writer(&mut out, sequence)?;
+            writeln!(out)?;
+        }
+    } else {
+        serde_json::to_writer_pretty(&mut out, &JsonReport { header, sequences })?;
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> ReportHeader {
+        ReportHeader {
+            generated_at: "2024-01-16T06:00:00+00:00".to_string(),
+            psf_guard_version: "0.0.0".to_string(),
+            database: "schedulerdb.sqlite".to_string(),
+            project: "M31".to_string(),
+            target: None,
+            filter: Some("Ha".to_string()),
+            position_radius_deg: None,
+            config: SequenceAnalyzerConfig::default(),
+        }
+    }
+
+    #[test]
+    fn ndjson_report_starts_with_a_header_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.ndjson");
+        write_report(&path, "ndjson", &header(), &[]).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["header"]["project"], "M31");
+        assert_eq!(
+            first["header"]["config"]["session_gap_minutes"],
+            SequenceAnalyzerConfig::default().session_gap_minutes
+        );
+    }
+
+    #[test]
+    fn json_report_config_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        write_report(&path, "json", &header(), &[]).unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["sequences"], serde_json::json!([]));
+        let config: SequenceAnalyzerConfig =
+            serde_json::from_value(report["header"]["config"].clone()).unwrap();
+        assert_eq!(
+            config.min_sequence_length,
+            SequenceAnalyzerConfig::default().min_sequence_length
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Animated GIF of a target's sequence in acquisition order, for spotting
+//! tracking drift, clouds or focus changes by eye.
+//!
+//! Each sub goes through the preview stretch at a common size and is encoded
+//! as soon as it's rendered, so memory stays at one frame however long the
+//! sequence. With `--align`, every frame is shifted so the centroid of its
+//! brightest pixels sits where the first frame's did; what's left moving is
+//! the background and star quality rather than the pointing.
+
+use anyhow::{bail, Context, Result};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, GrayImage};
+use rusqlite::Connection;
+use std::path::Path;
+
+use crate::commands::contact_sheet::target_frames;
+use crate::commands::stretch_to_png::render_stretched;
+use crate::directory_tree::DirectoryTree;
+use crate::scoring::filename_from_metadata;
+
+pub const DEFAULT_FPS: f64 = 4.0;
+/// Default longest edge of the animation, in pixels.
+pub const DEFAULT_SIZE: u32 = 512;
+/// Accepted range of `--size`: below this the frames show nothing, above
+/// it a long sequence makes a GIF too large to open.
+pub const MIN_SIZE: u32 = 16;
+pub const MAX_SIZE: u32 = 4096;
+/// Fraction of pixels (the brightest) that feed the alignment centroid.
+const CENTROID_FRACTION: f64 = 0.005;
+
+pub struct AnimateOptions {
+    pub target: String,
+    /// Disambiguates a target name used in several projects.
+    pub project: Option<String>,
+    /// Only this filter's frames; all of them otherwise.
+    pub filter: Option<String>,
+    pub output: String,
+    pub fps: f64,
+    /// Longest edge of the frames.
+    pub size: u32,
+    /// Shift frames onto the first frame's bright-pixel centroid.
+    pub align: bool,
+    /// Directories to find the FITS files in.
+    pub image_dirs: Vec<String>,
+}
+
+pub fn animate(conn: &Connection, options: &AnimateOptions) -> Result<()> {
+    if !(options.fps > 0.0 && options.fps <= 50.0) {
+        bail!("--fps must be greater than 0 and at most 50");
+    }
+    if !(MIN_SIZE..=MAX_SIZE).contains(&options.size) {
+        bail!("--size must be between {} and {}", MIN_SIZE, MAX_SIZE);
+    }
+    if options.image_dirs.is_empty() {
+        bail!("No image directories configured; pass --image-dirs");
+    }
+    let images = target_frames(
+        conn,
+        &options.target,
+        options.project.as_deref(),
+        options.filter.as_deref(),
+    )?;
+
+    let roots: Vec<&Path> = options.image_dirs.iter().map(Path::new).collect();
+    let tree = DirectoryTree::build_multiple(&roots)
+        .context("Failed to scan the image directories for FITS files")?;
+
+    let file = std::fs::File::create(&options.output)
+        .with_context(|| format!("Failed to create output file: {}", options.output))?;
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms((1000.0 / options.fps).round() as u32, 1);
+
+    // The first rendered frame fixes the canvas size and alignment reference.
+    let mut canvas: Option<(u32, u32)> = None;
+    let mut reference: Option<(f64, f64)> = None;
+    let mut written = 0;
+    for image in &images {
+        let Some(path) = filename_from_metadata(&image.metadata)
+            .and_then(|name| tree.find_file_first(&name).map(Path::to_path_buf))
+        else {
+            eprintln!("Image {}: file not found, skipped", image.id);
+            continue;
+        };
+        let frame = match crate::fits_read::load_with_retry(&path)
+            .and_then(|fits| render_stretched(&fits, 0.2, -2.8, Some((options.size, options.size))))
+        {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("{}: {:#}, skipped", path.display(), e);
+                continue;
+            }
+        };
+        let (width, height) = *canvas.get_or_insert(frame.dimensions());
+        let mut frame = if frame.dimensions() == (width, height) {
+            frame
+        } else {
+            image::imageops::resize(&frame, width, height, image::imageops::FilterType::Triangle)
+        };
+
+        if options.align
+            && let Some((cx, cy)) = bright_centroid(&frame)
+        {
+            let (rx, ry) = *reference.get_or_insert((cx, cy));
+            frame = shift_image(&frame, (rx - cx).round() as i32, (ry - cy).round() as i32);
+        }
+
+        let rgba = DynamicImage::ImageLuma8(frame).into_rgba8();
+        encoder
+            .encode_frame(Frame::from_parts(rgba, 0, 0, delay))
+            .with_context(|| format!("Failed to encode frame {}", path.display()))?;
+        written += 1;
+    }
+    if written == 0 {
+        bail!("None of the {} frame(s) could be rendered", images.len());
+    }
+    println!(
+        "Wrote {} ({} of {} frame(s))",
+        options.output,
+        written,
+        images.len()
+    );
+    Ok(())
+}
+
+/// Intensity-weighted centroid of the brightest [`CENTROID_FRACTION`] of
+/// pixels, or `None` for a frame with nothing standing out.
+pub fn bright_centroid(image: &GrayImage) -> Option<(f64, f64)> {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    let wanted = ((image.len() as f64 * CENTROID_FRACTION).ceil() as u64).max(1);
+    let mut seen = 0;
+    let mut threshold = 255;
+    for value in (0..256).rev() {
+        seen += histogram[value];
+        if seen >= wanted {
+            threshold = value as u8;
+            break;
+        }
+    }
+
+    let (mut sum_x, mut sum_y, mut sum_w) = (0.0, 0.0, 0.0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[0] > threshold || (threshold == 255 && pixel[0] == 255) {
+            let weight = f64::from(pixel[0]) - f64::from(threshold) + 1.0;
+            sum_x += x as f64 * weight;
+            sum_y += y as f64 * weight;
+            sum_w += weight;
+        }
+    }
+    (sum_w > 0.0).then(|| (sum_x / sum_w, sum_y / sum_w))
+}
+
+/// Translate by `(dx, dy)` pixels; uncovered areas are black.
+pub fn shift_image(image: &GrayImage, dx: i32, dy: i32) -> GrayImage {
+    let (width, height) = image.dimensions();
+    let mut out = GrayImage::new(width, height);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let (nx, ny) = (x as i64 + dx as i64, y as i64 + dy as i64);
+        if nx >= 0 && ny >= 0 && nx < width as i64 && ny < height as i64 {
+            out.put_pixel(nx as u32, ny as u32, *pixel);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn star_at(x: u32, y: u32) -> GrayImage {
+        let mut image = GrayImage::from_pixel(40, 30, Luma([20]));
+        for (sx, sy) in [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+            image.put_pixel(sx, sy, Luma([250]));
+        }
+        image
+    }
+
+    #[test]
+    fn centroid_follows_the_bright_pixels() {
+        let (cx, cy) = bright_centroid(&star_at(10, 5)).unwrap();
+        assert!((cx - 10.5).abs() < 0.01, "{cx}");
+        assert!((cy - 5.5).abs() < 0.01, "{cy}");
+        // A featureless frame has nothing to align on.
+        assert!(bright_centroid(&GrayImage::new(40, 30)).is_none());
+    }
+
+    #[test]
+    fn shifting_onto_the_reference_aligns_frames() {
+        let reference = bright_centroid(&star_at(10, 5)).unwrap();
+        let drifted = star_at(14, 8);
+        let (cx, cy) = bright_centroid(&drifted).unwrap();
+        let aligned = shift_image(
+            &drifted,
+            (reference.0 - cx).round() as i32,
+            (reference.1 - cy).round() as i32,
+        );
+        assert_eq!(aligned.get_pixel(10, 5)[0], 250);
+        assert_eq!(aligned.get_pixel(14, 8)[0], 20);
+        // The strip uncovered by the shift is black.
+        assert_eq!(aligned.get_pixel(39, 29)[0], 0);
+    }
+}
@@ -2,11 +2,11 @@ use anyhow::{Context, Result};
 use image::codecs::png::{CompressionType, FilterType, PngEncoder};
 use image::{ColorType, ImageEncoder};
 use image::{ImageBuffer, Rgb};
-use imageproc::drawing::{draw_filled_circle_mut, draw_hollow_circle_mut};
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::Path;
 
+use crate::commands::annotate_stars_common::{annotated_svg, draw_marker, MarkerOptions};
 use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
 use crate::image_analysis::FitsImage;
 use crate::nina_star_detection::{
@@ -41,8 +41,17 @@ pub fn annotate_stars(
     shadow_clipping: f64,
     annotation_color: &str,
     psf_type: &str,
+    markers: MarkerOptions,
+    format: &str,
     verbose: bool,
 ) -> Result<()> {
+    markers.validate().map_err(anyhow::Error::msg)?;
+    let svg = match format.to_lowercase().as_str() {
+        "png" => false,
+        "svg" => true,
+        _ => anyhow::bail!("Unknown format: {}. Use 'png' or 'svg'", format),
+    };
+
     if verbose {
         eprintln!("Loading FITS file: {}", fits_path);
     }
@@ -167,56 +176,43 @@ pub fn annotate_stars(
         );
     }
 
-    // Convert stretched 16-bit data to 8-bit RGB
-    let mut rgb_image = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(width as u32, height as u32);
-
-    for (x, y, pixel) in rgb_image.enumerate_pixels_mut() {
-        let idx = y as usize * width + x as usize;
-        let value = (stretched[idx] >> 8) as u8; // Convert 16-bit to 8-bit
-        *pixel = Rgb([value, value, value]); // Grayscale to RGB
-    }
-
     // Parse annotation color
     let color = parse_color(annotation_color);
 
-    // Draw circles around detected stars
-    for (x, y, hfr) in &stars_to_annotate {
-        // Calculate circle radius based on HFR
-        // Use 2.5 * HFR for circle radius, with minimum of 5 pixels
-        let radius = (hfr * 2.5).max(5.0) as i32;
-
-        // Draw hollow circle
-        draw_hollow_circle_mut(&mut rgb_image, (*x as i32, *y as i32), radius, color);
-
-        // For very small stars, also draw a filled center point
-        if radius < 8 {
-            draw_filled_circle_mut(&mut rgb_image, (*x as i32, *y as i32), 1, color);
-        }
-    }
-
     // Generate output filename
     let output_path = output.unwrap_or_else(|| {
         let base = fits_path.trim_end_matches(".fits").trim_end_matches(".fit");
-        format!("{}_annotated.png", base)
+        format!("{}_annotated.{}", base, if svg { "svg" } else { "png" })
     });
 
-    // Save the annotated image with compression
-    let file = File::create(&output_path)
-        .with_context(|| format!("Failed to create output file: {}", output_path))?;
-    let writer = BufWriter::new(file);
-
-    // Create PNG encoder with best compression
-    let encoder = PngEncoder::new_with_quality(writer, CompressionType::Best, FilterType::Adaptive);
-
-    // Write the image data
-    encoder
-        .write_image(
-            &rgb_image,
+    if svg {
+        // Vector markers over the embedded stretched frame
+        let base = image::GrayImage::from_raw(
             width as u32,
             height as u32,
-            ColorType::Rgb8.into(),
+            stretched.iter().map(|&v| (v >> 8) as u8).collect(),
         )
-        .with_context(|| format!("Failed to write PNG image to {}", output_path))?;
+        .context("Failed to create image buffer")?;
+        let document = annotated_svg(
+            &base,
+            (width as u32, height as u32),
+            &stars_to_annotate,
+            color,
+            &markers,
+        )?;
+        std::fs::write(&output_path, document)
+            .with_context(|| format!("Failed to write SVG to {}", output_path))?;
+    } else {
+        write_annotated_png(
+            &stretched,
+            width,
+            height,
+            &stars_to_annotate,
+            color,
+            &markers,
+            &output_path,
+        )?;
+    }
 
     println!("Created annotated image: {}", output_path);
     println!(
@@ -240,3 +236,55 @@ pub fn annotate_stars(
 
     Ok(())
 }
+
+/// Draw the star markers over the stretched frame and save it as a PNG.
+#[allow(clippy::too_many_arguments)]
+fn write_annotated_png(
+    stretched: &[u16],
+    width: usize,
+    height: usize,
+    stars_to_annotate: &[(f64, f64, f64)],
+    color: Rgb<u8>,
+    markers: &MarkerOptions,
+    output_path: &str,
+) -> Result<()> {
+    // Convert stretched 16-bit data to 8-bit RGB
+    let mut rgb_image = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(width as u32, height as u32);
+
+    for (x, y, pixel) in rgb_image.enumerate_pixels_mut() {
+        let idx = y as usize * width + x as usize;
+        let value = (stretched[idx] >> 8) as u8; // Convert 16-bit to 8-bit
+        *pixel = Rgb([value, value, value]); // Grayscale to RGB
+    }
+
+    // Mark detected stars
+    for (x, y, hfr) in stars_to_annotate {
+        draw_marker(
+            &mut rgb_image,
+            (*x, *y),
+            markers.radius(*hfr),
+            markers.style,
+            color,
+        );
+    }
+
+    // Save the annotated image with compression
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create output file: {}", output_path))?;
+    let writer = BufWriter::new(file);
+
+    // Create PNG encoder with best compression
+    let encoder = PngEncoder::new_with_quality(writer, CompressionType::Best, FilterType::Adaptive);
+
+    // Write the image data
+    encoder
+        .write_image(
+            &rgb_image,
+            width as u32,
+            height as u32,
+            ColorType::Rgb8.into(),
+        )
+        .with_context(|| format!("Failed to write PNG image to {}", output_path))?;
+
+    Ok(())
+}
@@ -1,85 +1,597 @@
-use anyhow::Result;
-use image::{ImageBuffer, Rgb};
-use imageproc::drawing::{draw_filled_circle_mut, draw_hollow_circle_mut};
+use anyhow::{Context, Result};
+use image::codecs::png::PngEncoder;
+use image::{ColorType, GrayImage, ImageBuffer, ImageEncoder, Rgb};
+use imageproc::drawing::{
+    draw_filled_circle_mut, draw_hollow_circle_mut, draw_hollow_rect_mut, draw_line_segment_mut,
+};
+use imageproc::rect::Rect;
 
-use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
+use crate::commands::visualize_psf::star_selection::{select_stars, SelectionStrategy, SortMetric};
+use crate::hocus_focus_star_detection::{
+    detect_stars_hocus_focus, HocusFocusParams, HocusFocusStar,
+};
 use crate::image_analysis::FitsImage;
 use crate::psf_fitting::PSFType;
 use seiza_stretch::{stretch_u16_to_u16, StretchParams};
 
+/// Shape drawn at each star.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MarkerStyle {
+    #[default]
+    Circle,
+    /// Crosshair; stays readable where circles would overlap.
+    Cross,
+    Box,
+}
+
+impl MarkerStyle {
+    /// Short tag for cache keys.
+    pub fn tag(self) -> &'static str {
+        match self {
+            MarkerStyle::Circle => "circle",
+            MarkerStyle::Cross => "cross",
+            MarkerStyle::Box => "box",
+        }
+    }
+}
+
+impl std::str::FromStr for MarkerStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "circle" => Ok(MarkerStyle::Circle),
+            "cross" => Ok(MarkerStyle::Cross),
+            "box" => Ok(MarkerStyle::Box),
+            _ => Err(format!("marker must be circle, cross or box, got {}", s)),
+        }
+    }
+}
+
+/// How a marker's radius is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MarkerScaling {
+    /// Every marker is the minimum size.
+    Fixed,
+    /// 2.5 * HFR, so bloated stars get bigger markers.
+    #[default]
+    HfrScaled,
+}
+
+impl MarkerScaling {
+    /// Short tag for cache keys.
+    pub fn tag(self) -> &'static str {
+        match self {
+            MarkerScaling::Fixed => "fixed",
+            MarkerScaling::HfrScaled => "hfr",
+        }
+    }
+}
+
+impl std::str::FromStr for MarkerScaling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fixed" => Ok(MarkerScaling::Fixed),
+            "hfr-scaled" | "hfr" => Ok(MarkerScaling::HfrScaled),
+            _ => Err(format!(
+                "marker size must be fixed or hfr-scaled, got {}",
+                s
+            )),
+        }
+    }
+}
+
+/// Marker shape and size. Radii are in full-resolution pixels; a box or
+/// cross of radius r spans 2r.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkerOptions {
+    pub style: MarkerStyle,
+    pub scaling: MarkerScaling,
+    pub min_radius: f64,
+    pub max_radius: f64,
+}
+
+impl Default for MarkerOptions {
+    fn default() -> Self {
+        Self {
+            style: MarkerStyle::default(),
+            scaling: MarkerScaling::default(),
+            min_radius: 5.0,
+            max_radius: 50.0,
+        }
+    }
+}
+
+/// Largest marker radius accepted, in pixels. Larger values only produce
+/// markers bigger than any frame, and drawing them costs time per pixel of
+/// circumference.
+pub const MAX_MARKER_RADIUS: f64 = 1000.0;
+
+impl MarkerOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.min_radius > 0.0
+            && self.min_radius <= self.max_radius
+            && self.max_radius <= MAX_MARKER_RADIUS)
+        {
+            return Err(format!(
+                "marker sizes must satisfy 0 < min <= max <= {}, got {}..{}",
+                MAX_MARKER_RADIUS, self.min_radius, self.max_radius
+            ));
+        }
+        Ok(())
+    }
+
+    /// Marker radius for a star of this HFR, clamped to the size limits.
+    pub fn radius(&self, hfr: f64) -> f64 {
+        let radius = match self.scaling {
+            MarkerScaling::Fixed => self.min_radius,
+            MarkerScaling::HfrScaled => hfr * 2.5,
+        };
+        radius.clamp(self.min_radius, self.max_radius)
+    }
+
+    /// Cache-key suffix. Always present: annotations cached before marker
+    /// sizes were clamped used unbounded HFR-scaled radii and must not be
+    /// served for the defaults.
+    pub fn cache_suffix(&self) -> String {
+        format!(
+            "_{}_{}_{}_{}",
+            self.style.tag(),
+            self.scaling.tag(),
+            self.min_radius,
+            self.max_radius
+        )
+    }
+}
+
+/// Which detected stars get annotated: those passing every threshold, ranked
+/// by `rank_by` (best first, as in the PSF visualization) and cut to
+/// `max_stars`. A minimum eccentricity turns the overlay into a map of
+/// elongated stars rather than of every star.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnnotationSelection {
+    pub max_stars: usize,
+    pub rank_by: SortMetric,
+    pub min_snr: Option<f64>,
+    /// Peak above background, ADU.
+    pub min_brightness: Option<f64>,
+    /// Only stars more elongated than this; needs a PSF fit per star.
+    pub min_eccentricity: Option<f64>,
+}
+
+impl AnnotationSelection {
+    /// The `max_stars` best-focused stars, unfiltered.
+    pub fn top(max_stars: usize) -> Self {
+        Self {
+            max_stars,
+            rank_by: SortMetric::Hfr,
+            min_snr: None,
+            min_brightness: None,
+            min_eccentricity: None,
+        }
+    }
+
+    fn needs_psf(&self) -> bool {
+        self.min_eccentricity.is_some() || self.rank_by == SortMetric::R2
+    }
+
+    fn accepts(&self, star: &HocusFocusStar) -> bool {
+        self.min_snr.is_none_or(|min| star.snr >= min)
+            && self.min_brightness.is_none_or(|min| star.brightness >= min)
+            && self.min_eccentricity.is_none_or(|min| {
+                star.psf_model
+                    .as_ref()
+                    .is_some_and(|psf| psf.eccentricity > min)
+            })
+    }
+
+    /// Cache-key suffix beyond `max_stars`; empty for [`Self::top`] so
+    /// existing cached annotations stay valid.
+    pub fn cache_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if self.rank_by != SortMetric::Hfr {
+            suffix.push_str(&format!("_by{}", self.rank_by.tag()));
+        }
+        for (tag, threshold) in [
+            ("snr", self.min_snr),
+            ("bright", self.min_brightness),
+            ("ecc", self.min_eccentricity),
+        ] {
+            if let Some(threshold) = threshold {
+                suffix.push_str(&format!("_{}{}", tag, threshold));
+            }
+        }
+        suffix
+    }
+}
+
+/// Draw one marker centred on `(x, y)`.
+pub fn draw_marker(
+    image: &mut ImageBuffer<Rgb<u8>, Vec<u8>>,
+    (x, y): (f64, f64),
+    radius: f64,
+    style: MarkerStyle,
+    color: Rgb<u8>,
+) {
+    let (cx, cy, r) = (x as i32, y as i32, radius as i32);
+    match style {
+        MarkerStyle::Circle => {
+            draw_hollow_circle_mut(image, (cx, cy), r, color);
+            // For very small stars, also draw a filled center point
+            if r < 8 {
+                draw_filled_circle_mut(image, (cx, cy), 1, color);
+            }
+        }
+        MarkerStyle::Cross => {
+            let (x, y, radius) = (x as f32, y as f32, radius as f32);
+            draw_line_segment_mut(image, (x - radius, y), (x + radius, y), color);
+            draw_line_segment_mut(image, (x, y - radius), (x, y + radius), color);
+        }
+        MarkerStyle::Box => {
+            let side = (2 * r).max(1) as u32;
+            draw_hollow_rect_mut(image, Rect::at(cx - r, cy - r).of_size(side, side), color);
+        }
+    }
+}
+
 /// Create an annotated RGB image from FITS data
 pub fn create_annotated_image(
     fits: &FitsImage,
-    max_stars: usize,
+    selection: &AnnotationSelection,
     midtone_factor: f64,
     shadow_clipping: f64,
     annotation_color: Rgb<u8>,
+    markers: &MarkerOptions,
 ) -> Result<ImageBuffer<Rgb<u8>, Vec<u8>>> {
-    let width = fits.width;
-    let height = fits.height;
+    let stretched = stretched_gray(fits, midtone_factor, shadow_clipping)?;
+    let stars = stars_to_annotate(fits, selection);
 
-    // Calculate image statistics
-    let stats = fits.calculate_basic_statistics();
+    // Grayscale to RGB so the annotations can be colored
+    let mut rgb_image = image::DynamicImage::ImageLuma8(stretched).into_rgb8();
 
-    // Apply MTF stretch
+    // Mark detected stars
+    for (x, y, hfr) in &stars {
+        draw_marker(
+            &mut rgb_image,
+            (*x, *y),
+            markers.radius(*hfr),
+            markers.style,
+            annotation_color,
+        );
+    }
+
+    Ok(rgb_image)
+}
+
+/// Create the annotation as an SVG document: the stretched frame embedded as
+/// a PNG, with a vector marker and HFR label per star. Only the embedded
+/// raster is downscaled to `max_dimensions`; the markup stays in
+/// full-resolution pixel coordinates so markers remain sharp when zoomed.
+pub fn create_annotated_svg(
+    fits: &FitsImage,
+    selection: &AnnotationSelection,
+    midtone_factor: f64,
+    shadow_clipping: f64,
+    annotation_color: Rgb<u8>,
+    markers: &MarkerOptions,
+    max_dimensions: Option<(u32, u32)>,
+) -> Result<String> {
+    let full_size = (fits.width as u32, fits.height as u32);
+    let mut base = stretched_gray(fits, midtone_factor, shadow_clipping)?;
+    if let Some((max_w, max_h)) = max_dimensions {
+        let (new_w, new_h) =
+            crate::image_utils::fit_dimensions(full_size.0, full_size.1, max_w, max_h);
+        if (new_w, new_h) != full_size {
+            base =
+                image::imageops::resize(&base, new_w, new_h, image::imageops::FilterType::Lanczos3);
+        }
+    }
+    let stars = stars_to_annotate(fits, selection);
+    annotated_svg(&base, full_size, &stars, annotation_color, markers)
+}
+
+/// The MTF-stretched frame as 8-bit grayscale.
+pub fn stretched_gray(
+    fits: &FitsImage,
+    midtone_factor: f64,
+    shadow_clipping: f64,
+) -> Result<GrayImage> {
+    let stats = fits.calculate_basic_statistics();
     let stretch_params = StretchParams {
         target_median: midtone_factor,
         shadows_clip: shadow_clipping,
     };
-
     let stretched = stretch_u16_to_u16(&fits.data, &stats.to_stretch_statistics(), &stretch_params);
+    GrayImage::from_raw(
+        fits.width as u32,
+        fits.height as u32,
+        stretched.iter().map(|&v| (v >> 8) as u8).collect(),
+    )
+    .context("Failed to create image buffer")
+}
 
-    // Detect stars using HocusFocus (default for server)
+/// Detect stars with HocusFocus (the server default) and keep those
+/// `selection` picks as `(x, y, hfr)`, best first.
+fn stars_to_annotate(fits: &FitsImage, selection: &AnnotationSelection) -> Vec<(f64, f64, f64)> {
     let params = HocusFocusParams {
-        psf_type: PSFType::None,
+        psf_type: if selection.needs_psf() {
+            PSFType::Gaussian
+        } else {
+            PSFType::None
+        },
         ..Default::default()
     };
+    let detection_result = detect_stars_hocus_focus(&fits.data, fits.width, fits.height, &params);
+    let detected = detection_result.stars.len();
 
-    let detection_result = detect_stars_hocus_focus(&fits.data, width, height, &params);
-
-    // Sort stars by HFR (smallest first - best focus) and take top N
-    let mut stars: Vec<_> = detection_result
-        .stars
-        .iter()
-        .map(|s| (s.position.0, s.position.1, s.hfr))
-        .collect();
-    stars.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
-    let stars_to_annotate: Vec<_> = stars.into_iter().take(max_stars).collect();
+    let stars = select_annotated(detection_result.stars, selection, fits.width, fits.height);
 
     eprintln!(
         "Annotating {} stars out of {} detected",
-        stars_to_annotate.len(),
-        detection_result.stars.len()
+        stars.len(),
+        detected
     );
+    stars
+}
 
-    // Convert stretched 16-bit data to 8-bit RGB
-    let mut rgb_image = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(width as u32, height as u32);
+/// Filter and rank detected stars with the PSF visualization's top-N
+/// selection.
+fn select_annotated(
+    stars: Vec<HocusFocusStar>,
+    selection: &AnnotationSelection,
+    width: usize,
+    height: usize,
+) -> Vec<(f64, f64, f64)> {
+    let candidates: Vec<_> = stars
+        .into_iter()
+        .filter(|star| selection.accepts(star))
+        .collect();
+    let strategy = SelectionStrategy::TopN {
+        n: selection.max_stars,
+        metric: selection.rank_by,
+    };
+    select_stars(candidates, &strategy, width, height)
+        .into_iter()
+        .map(|s| (s.position.0, s.position.1, s.hfr))
+        .collect()
+}
+
+/// SVG document with `base` embedded as a PNG stretched over `full_size`,
+/// and a marker plus HFR label for each `(x, y, hfr)` star in full-resolution
+/// pixel coordinates. The document's display size is that of `base`.
+pub fn annotated_svg(
+    base: &GrayImage,
+    full_size: (u32, u32),
+    stars: &[(f64, f64, f64)],
+    annotation_color: Rgb<u8>,
+    markers: &MarkerOptions,
+) -> Result<String> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(base, base.width(), base.height(), ColorType::L8.into())
+        .context("Failed to encode annotation base image")?;
 
-    for (x, y, pixel) in rgb_image.enumerate_pixels_mut() {
-        let idx = y as usize * width + x as usize;
-        let value = (stretched[idx] >> 8) as u8; // Convert 16-bit to 8-bit
-        *pixel = Rgb([value, value, value]); // Grayscale to RGB
+    let (width, height) = full_size;
+    let [r, g, b] = annotation_color.0;
+    let color = format!("#{:02x}{:02x}{:02x}", r, g, b);
+    // Keep strokes and labels legible when the document is shown downscaled.
+    let scale = width as f64 / base.width().max(1) as f64;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        base.width(),
+        base.height(),
+        width,
+        height
+    );
+    svg.push_str(&format!(
+        "<image width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" href=\"{}\"/>\n",
+        width,
+        height,
+        crate::utils::png_data_uri(&png)
+    ));
+    svg.push_str(&format!(
+        "<g fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\">\n",
+        color, scale
+    ));
+    for (x, y, hfr) in stars {
+        let r = markers.radius(*hfr);
+        svg.push_str(&match markers.style {
+            MarkerStyle::Circle => {
+                format!("<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\"/>\n", x, y, r)
+            }
+            MarkerStyle::Cross => format!(
+                "<path d=\"M{:.2} {:.2}H{:.2}M{:.2} {:.2}V{:.2}\"/>\n",
+                x - r,
+                y,
+                x + r,
+                x,
+                y - r,
+                y + r
+            ),
+            MarkerStyle::Box => format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>\n",
+                x - r,
+                y - r,
+                2.0 * r,
+                2.0 * r
+            ),
+        });
     }
+    svg.push_str("</g>\n");
+    svg.push_str(&format!(
+        "<g fill=\"{}\" font-family=\"sans-serif\" font-size=\"{:.1}\">\n",
+        color,
+        10.0 * scale
+    ));
+    for (x, y, hfr) in stars {
+        let radius = markers.radius(*hfr);
+        svg.push_str(&format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\">{:.2}</text>\n",
+            x + radius + 2.0 * scale,
+            y - radius,
+            hfr
+        ));
+    }
+    svg.push_str("</g>\n</svg>\n");
+    Ok(svg)
+}
 
-    // Draw circles around detected stars
-    for (x, y, hfr) in &stars_to_annotate {
-        // Calculate circle radius based on HFR
-        // Use 2.5 * HFR for circle radius, with minimum of 5 pixels
-        let radius = (hfr * 2.5).max(5.0) as i32;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Draw hollow circle
-        draw_hollow_circle_mut(
-            &mut rgb_image,
-            (*x as i32, *y as i32),
-            radius,
-            annotation_color,
-        );
+    #[test]
+    fn svg_places_markers_in_full_resolution_coordinates() {
+        let base = GrayImage::from_pixel(50, 25, image::Luma([128]));
+        let stars = [(10.0, 20.0, 1.0), (60.5, 30.25, 3.0)];
+        let svg = annotated_svg(
+            &base,
+            (100, 50),
+            &stars,
+            Rgb([255, 255, 0]),
+            &MarkerOptions::default(),
+        )
+        .unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("width=\"50\" height=\"25\" viewBox=\"0 0 100 50\""));
+        assert!(svg.contains("href=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(svg.contains("stroke=\"#ffff00\""));
+        // The minimum radius applies to the small star.
+        assert!(svg.contains("<circle cx=\"10.00\" cy=\"20.00\" r=\"5.00\"/>"));
+        assert!(svg.contains("<circle cx=\"60.50\" cy=\"30.25\" r=\"7.50\"/>"));
+        assert_eq!(svg.matches("<text ").count(), 2);
+        assert!(svg.contains(">3.00</text>"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn marker_sizes_scale_with_hfr_within_limits() {
+        let markers = MarkerOptions {
+            min_radius: 4.0,
+            max_radius: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(markers.radius(1.0), 4.0);
+        assert_eq!(markers.radius(3.0), 7.5);
+        assert_eq!(markers.radius(40.0), 10.0);
+
+        let fixed = MarkerOptions {
+            scaling: MarkerScaling::Fixed,
+            ..markers
+        };
+        assert_eq!(fixed.radius(3.0), 4.0);
+
+        assert_eq!(MarkerOptions::default().cache_suffix(), "_circle_hfr_5_50");
+        assert_eq!(fixed.cache_suffix(), "_circle_fixed_4_10");
+        assert!(MarkerOptions {
+            min_radius: 8.0,
+            max_radius: 2.0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        for max_radius in [1e12, f64::INFINITY, f64::NAN] {
+            assert!(MarkerOptions {
+                max_radius,
+                ..Default::default()
+            }
+            .validate()
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn markers_draw_their_shape() {
+        let yellow = Rgb([255, 255, 0]);
+        let blank = || ImageBuffer::from_pixel(41, 41, Rgb([0, 0, 0]));
+
+        let mut cross = blank();
+        draw_marker(&mut cross, (20.0, 20.0), 10.0, MarkerStyle::Cross, yellow);
+        assert_eq!(cross.get_pixel(20, 20), &yellow);
+        assert_eq!(cross.get_pixel(30, 20), &yellow);
+        assert_eq!(cross.get_pixel(30, 30), &Rgb([0, 0, 0]));
+
+        let mut square = blank();
+        draw_marker(&mut square, (20.0, 20.0), 10.0, MarkerStyle::Box, yellow);
+        assert_eq!(square.get_pixel(10, 10), &yellow);
+        assert_eq!(square.get_pixel(20, 20), &Rgb([0, 0, 0]));
+
+        let svg = annotated_svg(
+            &GrayImage::new(41, 41),
+            (41, 41),
+            &[(20.0, 20.0, 4.0)],
+            yellow,
+            &MarkerOptions {
+                style: MarkerStyle::Box,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("<rect x=\"10.00\" y=\"10.00\" width=\"20.00\" height=\"20.00\"/>"));
+    }
 
-        // For very small stars, also draw a filled center point
-        if radius < 8 {
-            draw_filled_circle_mut(&mut rgb_image, (*x as i32, *y as i32), 1, annotation_color);
+    fn star(position: (f64, f64), hfr: f64, snr: f64, eccentricity: Option<f64>) -> HocusFocusStar {
+        HocusFocusStar {
+            position,
+            hfr,
+            fwhm: hfr * 2.0,
+            brightness: snr * 10.0,
+            background: 100.0,
+            snr,
+            flux: snr * 100.0,
+            pixel_count: 9,
+            psf_model: eccentricity.map(|eccentricity| crate::psf_fitting::PSFModel {
+                psf_type: PSFType::Gaussian,
+                amplitude: 1000.0,
+                background: 100.0,
+                x0: 0.0,
+                y0: 0.0,
+                sigma_x: 1.0,
+                sigma_y: 1.0,
+                theta: 0.0,
+                r_squared: 0.9,
+                rmse: 1.0,
+                fwhm: 2.4,
+                eccentricity,
+            }),
         }
     }
 
-    Ok(rgb_image)
+    #[test]
+    fn selection_thresholds_then_ranks() {
+        let stars = vec![
+            star((1.0, 1.0), 2.0, 50.0, Some(0.2)),
+            star((2.0, 2.0), 1.5, 5.0, Some(0.7)),
+            star((3.0, 3.0), 3.0, 80.0, Some(0.6)),
+            star((4.0, 4.0), 1.0, 30.0, None),
+        ];
+
+        // Default: best focus first, no thresholds.
+        let top = select_annotated(stars.clone(), &AnnotationSelection::top(2), 10, 10);
+        assert_eq!(top, vec![(4.0, 4.0, 1.0), (2.0, 2.0, 1.5)]);
+
+        let bright = AnnotationSelection {
+            rank_by: SortMetric::Snr,
+            min_snr: Some(20.0),
+            ..AnnotationSelection::top(10)
+        };
+        let picked = select_annotated(stars.clone(), &bright, 10, 10);
+        assert_eq!(
+            picked.iter().map(|s| s.0).collect::<Vec<_>>(),
+            vec![3.0, 1.0, 4.0]
+        );
+        assert_eq!(bright.cache_suffix(), "_bysnr_snr20");
+
+        // Only elongated stars; unfitted ones can't qualify.
+        let elongated = AnnotationSelection {
+            min_eccentricity: Some(0.5),
+            ..AnnotationSelection::top(10)
+        };
+        let picked = select_annotated(stars, &elongated, 10, 10);
+        assert_eq!(picked, vec![(2.0, 2.0, 1.5), (3.0, 3.0, 3.0)]);
+        assert_eq!(AnnotationSelection::top(10).cache_suffix(), "");
+    }
 }
@@ -0,0 +1,311 @@
+//! Fill the `psf_guard_computed_metrics` cache for a whole project offline,
+//! so interactive analysis never has to wait on detection.
+//!
+//! Frames whose metadata already carries a star count and HFR are cached
+//! from it; the rest are detected with `--detect` (the same detection as
+//! `analyze-batch`). Entries for unchanged files are kept unless `--force`.
+//! Detected entries are written in batches as they arrive, so an interrupted
+//! run keeps what it measured.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::commands::analyze_batch::analyze_frame;
+use crate::computed_metrics::{self, file_mtime, ComputedMetrics};
+use crate::db::Database;
+use crate::directory_tree::DirectoryTree;
+use crate::scoring::filename_from_metadata;
+use crate::sequence_analysis::extract_metrics_from_metadata;
+
+/// Detected entries written per transaction.
+const RECORD_BATCH: usize = 32;
+
+pub struct BackfillMetricsOptions {
+    pub base_dirs: Vec<String>,
+    pub project: String,
+    pub target: Option<String>,
+    /// Run star detection on frames whose metadata lacks metrics.
+    pub detect: bool,
+    /// Recompute entries that are still current.
+    pub force: bool,
+    /// Most frames detected per second; `None` for no limit.
+    pub max_rate: Option<f64>,
+    pub threads: Option<usize>,
+}
+
+/// Where each image's entry came from, for the closing summary.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BackfillCounts {
+    pub from_metadata: usize,
+    pub detected: usize,
+    pub already_cached: usize,
+    /// Metadata lacks metrics and `--detect` wasn't given.
+    pub needs_detection: usize,
+    pub missing_files: usize,
+    pub failed: usize,
+}
+
+/// Spaces detection starts at least `interval` apart across all workers.
+struct Throttle {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl Throttle {
+    fn new(max_rate: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / max_rate),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        std::thread::sleep(slot.saturating_duration_since(Instant::now()));
+    }
+}
+
+/// Cache entry from N.I.N.A.'s own metrics, when it recorded both a star
+/// count and an HFR.
+fn from_metadata(
+    image_id: i32,
+    metadata: &str,
+    path: &Path,
+    mtime: i64,
+) -> Option<ComputedMetrics> {
+    let metrics = extract_metrics_from_metadata(image_id, metadata, None);
+    metrics.star_count?;
+    metrics.hfr?;
+    Some(ComputedMetrics {
+        image_id,
+        file_path: path.to_string_lossy().into_owned(),
+        file_mtime: mtime,
+        star_count: metrics.star_count,
+        hfr: metrics.hfr,
+        eccentricity: metrics.eccentricity,
+        snr: metrics.snr,
+        background: metrics.background,
+    })
+}
+
+/// Record entries from `rx` in transactions of up to [`RECORD_BATCH`] until
+/// every sender is gone. Returns how many were recorded.
+fn record_in_batches(conn: &Connection, rx: mpsc::Receiver<ComputedMetrics>) -> Result<usize> {
+    let mut recorded = 0;
+    let mut batch = Vec::with_capacity(RECORD_BATCH);
+    for entry in rx {
+        batch.push(entry);
+        if batch.len() == RECORD_BATCH {
+            computed_metrics::record_all(conn, &batch)?;
+            recorded += batch.len();
+            batch.clear();
+        }
+    }
+    computed_metrics::record_all(conn, &batch)?;
+    Ok(recorded + batch.len())
+}
+
+pub fn backfill_metrics(
+    conn: &Connection,
+    options: &BackfillMetricsOptions,
+) -> Result<BackfillCounts> {
+    if options
+        .max_rate
+        .is_some_and(|rate| !(rate.is_finite() && rate > 0.0))
+    {
+        anyhow::bail!("--max-rate must be a positive number of frames per second");
+    }
+    let db = Database::new(conn);
+    let images = db.query_light_images(
+        None,
+        Some(&options.project),
+        options.target.as_deref(),
+        None,
+    )?;
+    let existing = computed_metrics::load_all(conn)?;
+    println!("Found {} images in the database", images.len());
+
+    let roots: Vec<&Path> = options.base_dirs.iter().map(Path::new).collect();
+    let tree = DirectoryTree::build_multiple(&roots)
+        .context("Failed to scan the base directories for FITS files")?;
+
+    let mut counts = BackfillCounts::default();
+    let mut entries = Vec::new();
+    let mut jobs: Vec<(i32, PathBuf, i64)> = Vec::new();
+    for (image, _, _) in images {
+        let Some((path, mtime)) = filename_from_metadata(&image.metadata)
+            .and_then(|name| tree.find_file_first(&name).cloned())
+            .and_then(|path| file_mtime(&path).map(|mtime| (path, mtime)))
+        else {
+            counts.missing_files += 1;
+            continue;
+        };
+        if !options.force
+            && existing.get(&image.id).is_some_and(|entry| {
+                entry.file_mtime == mtime && Path::new(&entry.file_path) == path.as_path()
+            })
+        {
+            counts.already_cached += 1;
+        } else if let Some(entry) = from_metadata(image.id, &image.metadata, &path, mtime) {
+            counts.from_metadata += 1;
+            entries.push(entry);
+        } else if options.detect {
+            jobs.push((image.id, path, mtime));
+        } else {
+            counts.needs_detection += 1;
+        }
+    }
+
+    computed_metrics::record_all(conn, &entries)?;
+
+    if !jobs.is_empty() {
+        let frame_pixels = crate::concurrency::probe_frame_pixels(&jobs[0].1);
+        let budget = crate::concurrency::plan_workers(
+            options.threads,
+            &crate::concurrency::WorkerPolicy::all_cores(),
+            crate::concurrency::Priority::Interactive,
+            frame_pixels,
+        );
+        eprintln!(
+            "Detecting stars in {} frame(s) with {} worker thread(s) — {}",
+            jobs.len(),
+            budget.workers,
+            budget.rationale
+        );
+
+        let throttle = options.max_rate.map(Throttle::new);
+        let done = AtomicUsize::new(0);
+        let total = jobs.len();
+        let (tx, rx) = mpsc::channel::<ComputedMetrics>();
+        counts.detected = std::thread::scope(|scope| -> Result<usize> {
+            scope.spawn(|| {
+                let tx = tx;
+                crate::concurrency::parallel_index(total, budget.workers, |i| {
+                    let (image_id, path, mtime) = &jobs[i];
+                    if let Some(throttle) = &throttle {
+                        throttle.wait();
+                    }
+                    let result = analyze_frame(path);
+                    let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("?");
+                    match result {
+                        Ok(frame) => {
+                            eprintln!(
+                                "[{}/{}] {}: {} stars, hfr {:.2}",
+                                n, total, name, frame.detected_stars, frame.hfr
+                            );
+                            let entry =
+                                ComputedMetrics::from_frame(*image_id, path, *mtime, &frame);
+                            // The receiver only goes away on a write error,
+                            // which is reported below.
+                            let _ = tx.send(entry);
+                        }
+                        Err(e) => eprintln!("[{}/{}] {}: {:#}", n, total, name, e),
+                    }
+                });
+            });
+            record_in_batches(conn, rx)
+        })?;
+        counts.failed = total - counts.detected;
+    }
+
+    println!(
+        "\nCached {} from metadata, {} detected; {} already cached, {} failed, {} file(s) not found",
+        counts.from_metadata,
+        counts.detected,
+        counts.already_cached,
+        counts.failed,
+        counts.missing_files
+    );
+    if counts.needs_detection > 0 {
+        println!(
+            "{} image(s) have no metrics in their metadata; pass --detect to measure them",
+            counts.needs_detection
+        );
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_metrics_need_both_stars_and_hfr() {
+        let path = Path::new("/data/a.fits");
+        let entry = from_metadata(
+            3,
+            r#"{"DetectedStars": 240, "HFR": 2.1, "Eccentricity": 0.4}"#,
+            path,
+            1_700_000_000,
+        )
+        .unwrap();
+        assert_eq!(entry.star_count, Some(240.0));
+        assert_eq!(entry.hfr, Some(2.1));
+        assert_eq!(entry.eccentricity, Some(0.4));
+        assert_eq!(entry.file_mtime, 1_700_000_000);
+
+        assert!(from_metadata(3, r#"{"DetectedStars": 240}"#, path, 0).is_none());
+        assert!(from_metadata(3, "{}", path, 0).is_none());
+    }
+
+    #[test]
+    fn batches_are_recorded_as_entries_arrive() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("scheduler.sqlite");
+        let entry = |image_id| ComputedMetrics {
+            image_id,
+            file_path: format!("/data/{image_id}.fits"),
+            file_mtime: 1,
+            star_count: Some(100.0),
+            hfr: Some(2.0),
+            eccentricity: None,
+            snr: None,
+            background: None,
+        };
+        let (tx, rx) = mpsc::channel();
+        let writer = {
+            let conn = Connection::open(&db_path).unwrap();
+            std::thread::spawn(move || record_in_batches(&conn, rx).unwrap())
+        };
+        for id in 0..RECORD_BATCH as i32 {
+            tx.send(entry(id)).unwrap();
+        }
+
+        // A full batch lands while detection is still running.
+        let reader = Connection::open(&db_path).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while computed_metrics::load_all(&reader).unwrap().len() < RECORD_BATCH {
+            assert!(Instant::now() < deadline, "first batch never written");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        tx.send(entry(RECORD_BATCH as i32)).unwrap();
+        drop(tx);
+        assert_eq!(writer.join().unwrap(), RECORD_BATCH + 1);
+        assert_eq!(
+            computed_metrics::load_all(&reader).unwrap().len(),
+            RECORD_BATCH + 1
+        );
+    }
+
+    #[test]
+    fn throttle_spaces_out_starts() {
+        let throttle = Throttle::new(50.0);
+        let start = Instant::now();
+        for _ in 0..4 {
+            throttle.wait();
+        }
+        // The first start is immediate, the next three 20ms apart.
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+}
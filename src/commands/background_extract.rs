@@ -0,0 +1,280 @@
+use anyhow::{Context, Result};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ColorType, ImageEncoder};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::image_analysis::FitsImage;
+
+/// Default number of wavelet layers treated as small-scale detail. Matches
+/// the HocusFocus structure-removal default.
+pub const DEFAULT_LEVELS: usize = 4;
+
+/// A smooth background model and the frame with it subtracted.
+pub struct BackgroundExtraction {
+    pub width: usize,
+    pub height: usize,
+    pub background: Vec<f32>,
+    /// `data - background`, re-based on the background median so the sky
+    /// level stays positive and the result stretches like a normal frame.
+    pub flattened: Vec<f32>,
+}
+
+/// Estimate the large-scale background with the multi-scale wavelet
+/// structure remover and subtract it. More `levels` push more (larger)
+/// structure into the detail layers, leaving a smoother background.
+pub fn extract_background(image: &FitsImage, levels: usize) -> BackgroundExtraction {
+    use seiza_imgproc::wavelets::StructureRemover;
+
+    let data: Vec<f32> = image.data.iter().map(|&v| v as f32).collect();
+    let background = StructureRemover::new(levels.max(1)).remove_structures_filtered_f32(
+        &data,
+        image.width,
+        image.height,
+    );
+
+    let pedestal = median(&background);
+    let flattened = data
+        .iter()
+        .zip(&background)
+        .map(|(&d, &b)| (d - b + pedestal).max(0.0))
+        .collect();
+
+    BackgroundExtraction {
+        width: image.width,
+        height: image.height,
+        background,
+        flattened,
+    }
+}
+
+/// Which plane of a background extraction a preview shows.
+///
+/// The model is the wavelet structure remover's large-scale residual at
+/// [`DEFAULT_LEVELS`]: it follows light-pollution gradients and vignetting
+/// well, but anything larger than the top wavelet scale (a galaxy or nebula
+/// filling much of the frame) is partly taken as background and dimmed by
+/// `Subtract`. It is estimated over the whole frame, which for large sensors
+/// costs a few full-size float buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackgroundView {
+    /// The frame with the background removed.
+    Subtract,
+    /// Just the estimated background, for checking the model.
+    Show,
+}
+
+impl BackgroundView {
+    /// Short tag for cache keys.
+    pub fn tag(self) -> &'static str {
+        match self {
+            BackgroundView::Subtract => "bgsub",
+            BackgroundView::Show => "bgshow",
+        }
+    }
+
+    /// The chosen plane as a frame, with the source's ADU scaling, ready for
+    /// the usual stretch.
+    pub fn apply(self, image: &FitsImage) -> FitsImage {
+        let extraction = extract_background(image, DEFAULT_LEVELS);
+        let plane = match self {
+            BackgroundView::Subtract => extraction.flattened,
+            BackgroundView::Show => extraction.background,
+        };
+        FitsImage {
+            width: image.width,
+            height: image.height,
+            data: plane
+                .iter()
+                .map(|&v| v.round().clamp(0.0, u16::MAX as f32) as u16)
+                .collect(),
+            raw_min: image.raw_min,
+            raw_scale: image.raw_scale,
+            bzero: image.bzero,
+            header: image.header.clone(),
+        }
+    }
+}
+
+impl std::str::FromStr for BackgroundView {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "subtract" => Ok(BackgroundView::Subtract),
+            "show" => Ok(BackgroundView::Show),
+            _ => Err(format!(
+                "background must be subtract, show or none, got {}",
+                s
+            )),
+        }
+    }
+}
+
+fn median(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    let mid = sorted.len() / 2;
+    let (_, m, _) = sorted.select_nth_unstable_by(mid, f32::total_cmp);
+    *m
+}
+
+/// Auto-stretch (MTF) a float plane into 8-bit grayscale pixels.
+pub fn stretch_to_l8(values: &[f32], width: usize, height: usize) -> Vec<u8> {
+    use seiza_stretch::{stretch_u16_to_u16, StretchParams};
+
+    let plane = FitsImage {
+        width,
+        height,
+        data: values
+            .iter()
+            .map(|&v| v.round().clamp(0.0, u16::MAX as f32) as u16)
+            .collect(),
+        raw_min: 0.0,
+        raw_scale: 1.0,
+        bzero: 0.0,
+        header: Default::default(),
+    };
+    let stats = plane.calculate_basic_statistics();
+    let params = StretchParams {
+        target_median: 0.2,
+        shadows_clip: -2.8,
+    };
+    stretch_u16_to_u16(&plane.data, &stats.to_stretch_statistics(), &params)
+        .into_iter()
+        .map(|v| (v >> 8) as u8)
+        .collect()
+}
+
+/// Auto-stretch (MTF) a float plane into an 8-bit grayscale PNG.
+pub fn encode_stretched_png(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    writer: impl Write,
+) -> Result<()> {
+    let pixels = stretch_to_l8(values, width, height);
+    PngEncoder::new_with_quality(writer, CompressionType::Best, FilterType::Adaptive)
+        .write_image(&pixels, width as u32, height as u32, ColorType::L8.into())
+        .context("Failed to encode PNG")
+}
+
+/// CLI entry point: write `<stem>_background.png` and `<stem>_flattened.png`.
+pub fn background_extract(
+    fits_path: &str,
+    output_dir: Option<String>,
+    levels: usize,
+) -> Result<()> {
+    let fits_path = Path::new(fits_path);
+    println!("Loading FITS file: {}", fits_path.display());
+    let image = FitsImage::from_file(fits_path)
+        .with_context(|| format!("Failed to load FITS file: {}", fits_path.display()))?;
+    println!("Image dimensions: {}x{}", image.width, image.height);
+
+    println!("Extracting background ({} wavelet levels)...", levels);
+    let extraction = extract_background(&image, levels);
+
+    let out_dir = match output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => fits_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let stem = fits_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "image".to_string());
+
+    for (suffix, plane) in [
+        ("background", &extraction.background),
+        ("flattened", &extraction.flattened),
+    ] {
+        let path = out_dir.join(format!("{}_{}.png", stem, suffix));
+        let file = std::fs::File::create(&path)
+            .with_context(|| format!("Failed to create output file: {}", path.display()))?;
+        encode_stretched_png(
+            plane,
+            extraction.width,
+            extraction.height,
+            std::io::BufWriter::new(file),
+        )?;
+        println!("Saved {} to: {}", suffix, path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Left-to-right brightness difference between the outer column strips.
+    fn gradient(values: &[f32], width: usize, height: usize) -> f32 {
+        let strip = |x0: usize| {
+            let mut sum = 0.0;
+            for y in 0..height {
+                for x in x0..x0 + 8 {
+                    sum += values[y * width + x];
+                }
+            }
+            sum / (height * 8) as f32
+        };
+        strip(width - 8) - strip(0)
+    }
+
+    #[test]
+    fn flattening_removes_a_linear_gradient() {
+        let (width, height) = (128, 128);
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut v = 1000.0 + 20.0 * x as f32;
+                // A sparse grid of point sources on top of the gradient.
+                if (x % 32 == 16) && (y % 32 == 16) {
+                    v += 3000.0;
+                }
+                data.push(v as u16);
+            }
+        }
+        let image = FitsImage {
+            width,
+            height,
+            data,
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: Default::default(),
+        };
+        let original: Vec<f32> = image.data.iter().map(|&v| v as f32).collect();
+
+        let extraction = extract_background(&image, DEFAULT_LEVELS);
+        assert_eq!(extraction.background.len(), width * height);
+
+        // The preview views carry the same planes.
+        let shown = BackgroundView::Show.apply(&image);
+        assert_eq!((shown.width, shown.height), (width, height));
+        assert!(
+            gradient(
+                &shown.data.iter().map(|&v| v as f32).collect::<Vec<_>>(),
+                width,
+                height
+            ) > 0.0
+        );
+
+        let before = gradient(&original, width, height);
+        let after = gradient(&extraction.flattened, width, height);
+        assert!(
+            after.abs() < before.abs() * 0.5,
+            "gradient {before} -> {after}"
+        );
+
+        let mut png = Vec::new();
+        encode_stretched_png(&extraction.flattened, width, height, &mut png).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -0,0 +1,29 @@
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+
+/// `config check`: load `path`, apply `PSF_GUARD_*` env overrides, validate,
+/// then print the effective configuration. Any problem is returned as an error naming the file, so
+/// the process exits non-zero.
+pub fn config_check(path: &str, format: &str) -> Result<()> {
+    if !matches!(format, "toml" | "json") {
+        bail!("Invalid format: {}. Use toml or json", format);
+    }
+
+    let effective = Config::from_file(path)
+        .and_then(|mut config| {
+            config.apply_env()?;
+            config.validate()?;
+            config.effective()
+        })
+        .map_err(|e| anyhow::anyhow!("{} is not a valid configuration: {:#}", path, e))?;
+
+    let rendered = match format {
+        "json" => serde_json::to_string_pretty(&effective)
+            .context("Failed to serialize configuration to JSON")?,
+        _ => toml_edit::ser::to_string_pretty(&effective)
+            .context("Failed to serialize configuration to TOML")?,
+    };
+    eprintln!("{} is valid; effective configuration:", path);
+    println!("{}", rendered.trim_end());
+    Ok(())
+}
@@ -0,0 +1,370 @@
+//! Contact sheets: every frame of a target (optionally one filter) as a grid
+//! of small stretched thumbnails in a single PNG, so a whole run can be
+//! eyeballed at once. Cells can be bordered by grade (green accepted, red
+//! rejected).
+//!
+//! Frames are loaded, stretched and downsized one at a time, and only the
+//! thumbnail is kept, so memory is the output canvas plus a single frame no
+//! matter how many subs the target has.
+
+use anyhow::{bail, Context, Result};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ColorType, GrayImage, ImageEncoder, Rgb, RgbImage};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+use crate::commands::stretch_to_png::render_stretched;
+use crate::db::Database;
+use crate::directory_tree::DirectoryTree;
+use crate::models::AcquiredImage;
+use crate::scoring::filename_from_metadata;
+
+pub const DEFAULT_COLUMNS: u32 = 10;
+/// Default edge of a (square) cell, in pixels.
+pub const DEFAULT_CELL_SIZE: u32 = 160;
+/// Largest sheet rendered, in pixels; the RGB canvas is held in memory.
+pub const MAX_SHEET_PIXELS: u64 = 40_000_000;
+
+/// Grade border width, inside the cell.
+const BORDER_WIDTH: u32 = 3;
+/// Background strip between cells.
+const GAP: u32 = 2;
+const BACKGROUND: Rgb<u8> = Rgb([17, 17, 17]);
+/// Fill for a frame whose file is missing or unreadable.
+const MISSING: Rgb<u8> = Rgb([48, 48, 48]);
+const ACCEPTED: Rgb<u8> = Rgb([64, 192, 96]);
+const REJECTED: Rgb<u8> = Rgb([220, 64, 64]);
+
+/// Grid shape of a sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetLayout {
+    pub columns: u32,
+    /// Edge of a square cell, border included.
+    pub cell_size: u32,
+    /// Border accepted cells green and rejected cells red.
+    pub grade_borders: bool,
+}
+
+impl SheetLayout {
+    /// Sheet size in pixels for `frames` cells.
+    pub fn dimensions(&self, frames: usize) -> (u32, u32) {
+        let columns = self.columns.min(frames as u32).max(1);
+        let rows = (frames as u32).div_ceil(self.columns).max(1);
+        let span = |cells: u32| cells * self.cell_size + (cells - 1) * GAP;
+        (span(columns), span(rows))
+    }
+
+    /// Refuse sheets whose canvas would exceed [`MAX_SHEET_PIXELS`].
+    pub fn check_size(&self, frames: usize) -> Result<()> {
+        let (width, height) = self.dimensions(frames);
+        if u64::from(width) * u64::from(height) > MAX_SHEET_PIXELS {
+            bail!(
+                "A {}x{} contact sheet of {} frames is too large; use smaller cells or pick a filter",
+                width,
+                height,
+                frames
+            );
+        }
+        Ok(())
+    }
+
+    /// Top-left corner of cell `index`.
+    fn cell_origin(&self, index: usize) -> (u32, u32) {
+        let index = index as u32;
+        let pitch = self.cell_size + GAP;
+        (
+            (index % self.columns) * pitch,
+            (index / self.columns) * pitch,
+        )
+    }
+
+    /// Longest edge a thumbnail may have inside the border.
+    fn thumbnail_size(&self) -> u32 {
+        self.cell_size.saturating_sub(2 * BORDER_WIDTH).max(1)
+    }
+}
+
+/// One cell: the frame's file (if found) and its grade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetFrame {
+    pub path: Option<PathBuf>,
+    pub grading_status: i32,
+}
+
+/// Tile `frames` into a sheet, in order, rendering each through `thumbnail`
+/// (given the longest edge allowed). A frame without a path, or whose
+/// thumbnail fails, is left as a grey cell. Returns the sheet and the number
+/// of thumbnails drawn.
+pub fn render_contact_sheet(
+    frames: &[SheetFrame],
+    layout: &SheetLayout,
+    mut thumbnail: impl FnMut(&Path, u32) -> Result<GrayImage>,
+) -> (RgbImage, usize) {
+    let (width, height) = layout.dimensions(frames.len());
+    let mut sheet = RgbImage::from_pixel(width, height, BACKGROUND);
+    let inner = layout.thumbnail_size();
+    let mut drawn = 0;
+    for (index, frame) in frames.iter().enumerate() {
+        let (x0, y0) = layout.cell_origin(index);
+        fill(
+            &mut sheet,
+            x0,
+            y0,
+            layout.cell_size,
+            layout.cell_size,
+            MISSING,
+        );
+
+        let border = match frame.grading_status {
+            1 if layout.grade_borders => Some(ACCEPTED),
+            2 if layout.grade_borders => Some(REJECTED),
+            _ => None,
+        };
+        if let Some(color) = border {
+            let size = layout.cell_size;
+            fill(&mut sheet, x0, y0, size, BORDER_WIDTH, color);
+            fill(
+                &mut sheet,
+                x0,
+                y0 + size - BORDER_WIDTH,
+                size,
+                BORDER_WIDTH,
+                color,
+            );
+            fill(&mut sheet, x0, y0, BORDER_WIDTH, size, color);
+            fill(
+                &mut sheet,
+                x0 + size - BORDER_WIDTH,
+                y0,
+                BORDER_WIDTH,
+                size,
+                color,
+            );
+        }
+
+        let Some(path) = &frame.path else { continue };
+        let thumb = match thumbnail(path, inner) {
+            Ok(thumb) => thumb,
+            Err(e) => {
+                tracing::warn!("Contact sheet: skipping {}: {:#}", path.display(), e);
+                continue;
+            }
+        };
+        // Centre the thumbnail inside the border; the rest stays grey.
+        let (w, h) = (thumb.width().min(inner), thumb.height().min(inner));
+        let x = x0 + BORDER_WIDTH + (inner - w) / 2;
+        let y = y0 + BORDER_WIDTH + (inner - h) / 2;
+        for (px, py, value) in thumb.enumerate_pixels() {
+            if px < w && py < h {
+                let v = value[0];
+                sheet.put_pixel(x + px, y + py, Rgb([v, v, v]));
+            }
+        }
+        drawn += 1;
+    }
+    (sheet, drawn)
+}
+
+fn fill(image: &mut RgbImage, x0: u32, y0: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for y in y0..(y0 + height).min(image.height()) {
+        for x in x0..(x0 + width).min(image.width()) {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// Render the sheet from FITS files with the preview stretch and write it
+/// to `output` as a PNG. Fails if no frame could be drawn at all.
+pub fn write_contact_sheet(
+    frames: &[SheetFrame],
+    layout: &SheetLayout,
+    output: &Path,
+) -> Result<usize> {
+    layout.check_size(frames.len())?;
+    let (sheet, drawn) = render_contact_sheet(frames, layout, |path, size| {
+        let fits = crate::fits_read::load_with_retry(path)?;
+        render_stretched(&fits, 0.2, -2.8, Some((size, size)))
+    });
+    if drawn == 0 {
+        bail!("None of the {} frame(s) could be rendered", frames.len());
+    }
+    let file = std::fs::File::create(output)
+        .with_context(|| format!("Failed to create output file: {}", output.display()))?;
+    let encoder = PngEncoder::new_with_quality(
+        std::io::BufWriter::new(file),
+        CompressionType::Best,
+        FilterType::Adaptive,
+    );
+    encoder
+        .write_image(
+            &sheet,
+            sheet.width(),
+            sheet.height(),
+            ColorType::Rgb8.into(),
+        )
+        .with_context(|| format!("Failed to write PNG image to {}", output.display()))?;
+    Ok(drawn)
+}
+
+pub struct ContactSheetOptions {
+    pub target: String,
+    /// Disambiguates a target name used in several projects.
+    pub project: Option<String>,
+    /// Only this filter's frames; all of them otherwise.
+    pub filter: Option<String>,
+    pub output: String,
+    pub layout: SheetLayout,
+    /// Directories to find the FITS files in.
+    pub image_dirs: Vec<String>,
+}
+
+/// A target's frames, optionally one filter, oldest first. Errors when the
+/// target is unknown or ambiguous, or has no matching frames.
+pub fn target_frames(
+    conn: &Connection,
+    target: &str,
+    project: Option<&str>,
+    filter: Option<&str>,
+) -> Result<Vec<AcquiredImage>> {
+    let db = Database::new(conn);
+    let project_id = project
+        .map(|name| db.find_project_id_by_name(name))
+        .transpose()?;
+    let target_id = db.find_target_id_by_name(target, project_id)?;
+
+    let mut images: Vec<_> = db
+        .query_light_images_scoped(None, Some(target_id))?
+        .into_iter()
+        .map(|(image, _, _)| image)
+        .filter(|image| filter.is_none_or(|f| image.filter_name == f))
+        .collect();
+    if images.is_empty() {
+        bail!(
+            "No frames for target '{}'{}",
+            target,
+            filter
+                .map(|f| format!(" in filter {}", f))
+                .unwrap_or_default()
+        );
+    }
+    images.sort_by_key(|image| (image.acquired_date, image.id));
+    Ok(images)
+}
+
+pub fn contact_sheet(conn: &Connection, options: &ContactSheetOptions) -> Result<()> {
+    if options.image_dirs.is_empty() {
+        bail!("No image directories configured; pass --image-dirs");
+    }
+    let images = target_frames(
+        conn,
+        &options.target,
+        options.project.as_deref(),
+        options.filter.as_deref(),
+    )?;
+    options.layout.check_size(images.len())?;
+
+    let roots: Vec<&Path> = options.image_dirs.iter().map(Path::new).collect();
+    let tree = DirectoryTree::build_multiple(&roots)
+        .context("Failed to scan the image directories for FITS files")?;
+    let frames: Vec<SheetFrame> = images
+        .iter()
+        .map(|image| SheetFrame {
+            path: filename_from_metadata(&image.metadata)
+                .and_then(|name| tree.find_file_first(&name).map(Path::to_path_buf)),
+            grading_status: image.grading_status,
+        })
+        .collect();
+    let missing = frames.iter().filter(|frame| frame.path.is_none()).count();
+    if missing > 0 {
+        eprintln!("{} of {} frame(s) not found on disk", missing, frames.len());
+    }
+
+    let drawn = write_contact_sheet(&frames, &options.layout, Path::new(&options.output))?;
+    println!(
+        "Wrote {} ({} of {} frame(s))",
+        options.output,
+        drawn,
+        frames.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: SheetLayout = SheetLayout {
+        columns: 3,
+        cell_size: 10,
+        grade_borders: true,
+    };
+
+    fn frame(path: Option<&str>, grading_status: i32) -> SheetFrame {
+        SheetFrame {
+            path: path.map(PathBuf::from),
+            grading_status,
+        }
+    }
+
+    #[test]
+    fn layout_wraps_rows_and_shrinks_to_short_runs() {
+        assert_eq!(LAYOUT.dimensions(7), (3 * 10 + 2 * GAP, 3 * 10 + 2 * GAP));
+        assert_eq!(LAYOUT.dimensions(2), (2 * 10 + GAP, 10));
+        assert_eq!(LAYOUT.cell_origin(4), (10 + GAP, 10 + GAP));
+
+        let huge = SheetLayout {
+            columns: 10,
+            cell_size: 512,
+            grade_borders: false,
+        };
+        assert!(huge.check_size(100).is_ok());
+        assert!(huge.check_size(10_000).is_err());
+    }
+
+    #[test]
+    fn cells_carry_thumbnails_and_grade_borders() {
+        let frames = [
+            frame(Some("a.fits"), 1),
+            frame(Some("b.fits"), 2),
+            frame(None, 0),
+            frame(Some("broken.fits"), 0),
+        ];
+        let mut loaded = Vec::new();
+        let (sheet, drawn) = render_contact_sheet(&frames, &LAYOUT, |path, size| {
+            loaded.push(path.to_path_buf());
+            if path.ends_with("broken.fits") {
+                bail!("unreadable");
+            }
+            Ok(GrayImage::from_pixel(size, size, image::Luma([200])))
+        });
+        assert_eq!(drawn, 2);
+        assert_eq!(loaded.len(), 3);
+
+        // Accepted: green border, thumbnail inside.
+        assert_eq!(*sheet.get_pixel(0, 0), ACCEPTED);
+        assert_eq!(*sheet.get_pixel(5, 5), Rgb([200, 200, 200]));
+        // Rejected: red border.
+        let (x, y) = LAYOUT.cell_origin(1);
+        assert_eq!(*sheet.get_pixel(x, y), REJECTED);
+        // Missing and unreadable frames stay grey, without a pending border.
+        let (x, y) = LAYOUT.cell_origin(2);
+        assert_eq!(*sheet.get_pixel(x + 5, y + 5), MISSING);
+        let (x, y) = LAYOUT.cell_origin(3);
+        assert_eq!(*sheet.get_pixel(x, y), MISSING);
+        // Unused trailing cells are background.
+        let (x, y) = LAYOUT.cell_origin(5);
+        assert_eq!(*sheet.get_pixel(x, y), BACKGROUND);
+    }
+
+    #[test]
+    fn grade_borders_are_optional() {
+        let layout = SheetLayout {
+            grade_borders: false,
+            ..LAYOUT
+        };
+        let (sheet, _) = render_contact_sheet(&[frame(None, 2)], &layout, |_, size| {
+            Ok(GrayImage::new(size, size))
+        });
+        assert_eq!(*sheet.get_pixel(0, 0), MISSING);
+    }
+}
@@ -0,0 +1,405 @@
+//! Portable curation backups: a project's grades, reject reasons, favorites,
+//! tags and key metrics as one versioned JSON document, and re-applying one
+//! to a (possibly rebuilt) database.
+//!
+//! N.I.N.A. occasionally regenerates its database, so ids and guids can't be
+//! trusted across an import. Frames are matched on FITS basename (any
+//! directory, either separator, case-insensitive) plus `acquired_date`,
+//! falling back to the basename alone when exactly one frame carries it.
+//!
+//! Pure DB logic: the CLI glue lives in `cli_main.rs`, the HTTP glue in
+//! `server::handlers`.
+
+use crate::db::Database;
+use crate::models::GradingStatus;
+use crate::utils::extract_filename;
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+/// `format` field of every curation document.
+pub const CURATION_FORMAT: &str = "psf-guard-curation";
+/// Current document version; older versions are read, newer ones rejected.
+pub const CURATION_VERSION: u32 = 1;
+
+/// Metadata keys copied into each record's `metrics`.
+const METRIC_KEYS: &[&str] = &[
+    "HFR",
+    "FWHM",
+    "DetectedStars",
+    "Eccentricity",
+    "GuidingRMS",
+    "GuidingRMSRA",
+    "GuidingRMSDec",
+    "ExposureDuration",
+    "Gain",
+    "Offset",
+    "FocuserTemp",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CurationExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: i64,
+    pub project: String,
+    pub images: Vec<CuratedImage>,
+    /// Frames of the project left out because their metadata carries no
+    /// `FileName`, so an import could never match them.
+    #[serde(default)]
+    pub skipped_without_filename: usize,
+}
+
+impl CurationExport {
+    /// Check the format marker, version and every record's grade.
+    pub fn validate(&self) -> Result<()> {
+        if self.format != CURATION_FORMAT {
+            bail!(
+                "Not a curation document (format '{}', expected '{}')",
+                self.format,
+                CURATION_FORMAT
+            );
+        }
+        if self.version == 0 || self.version > CURATION_VERSION {
+            bail!(
+                "Unsupported curation document version {} (this build reads up to {})",
+                self.version,
+                CURATION_VERSION
+            );
+        }
+        for record in &self.images {
+            parse_status_name(&record.grading_status)?;
+        }
+        Ok(())
+    }
+}
+
+/// One frame's curation state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CuratedImage {
+    /// FITS basename (no directory).
+    pub filename: String,
+    pub acquired_date: Option<i64>,
+    pub target: String,
+    pub filter: String,
+    /// `pending`, `accepted` or `rejected`.
+    pub grading_status: String,
+    #[serde(default)]
+    pub reject_reason: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Informational only; never written back.
+    #[serde(default)]
+    pub metrics: BTreeMap<String, serde_json::Value>,
+}
+
+/// Outcome counters for an import.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct CurationImportSummary {
+    /// Records in the document.
+    pub records: usize,
+    /// Records matched to exactly one frame.
+    pub matched: usize,
+    /// Matched frames whose grade, reason, favorite and tags already agreed.
+    pub unchanged: usize,
+    /// Matched frames that were (or, on a dry run, would be) changed.
+    pub updated: usize,
+    /// Records with no frame of that filename.
+    pub unmatched: usize,
+    /// Records whose filename (and timestamp) fit several frames.
+    pub ambiguous: usize,
+    pub dry_run: bool,
+}
+
+fn parse_status_name(name: &str) -> Result<GradingStatus> {
+    match name.to_ascii_lowercase().as_str() {
+        "pending" => Ok(GradingStatus::Pending),
+        "accepted" => Ok(GradingStatus::Accepted),
+        "rejected" => Ok(GradingStatus::Rejected),
+        other => bail!("Invalid grading_status '{}' in curation document", other),
+    }
+}
+
+/// Basename as matched on import: last path component of either separator,
+/// lowercased.
+fn match_key(filename: &str) -> String {
+    filename
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(filename)
+        .to_lowercase()
+}
+
+/// Id of the project named `name` (case-insensitive).
+pub fn project_id_by_name(conn: &Connection, name: &str) -> Result<i32> {
+    Database::new(conn)
+        .get_all_projects()?
+        .into_iter()
+        .find(|project| project.name.eq_ignore_ascii_case(name))
+        .map(|project| project.id)
+        .with_context(|| format!("Project '{}' not found", name))
+}
+
+/// Every frame of `project_id` with its curation state; `None` when there is
+/// no such project.
+pub fn export_project(conn: &Connection, project_id: i32) -> Result<Option<CurationExport>> {
+    let db = Database::new(conn);
+    let Some(project) = db
+        .get_all_projects()?
+        .into_iter()
+        .find(|project| project.id == project_id)
+    else {
+        return Ok(None);
+    };
+    let rows = db.query_images_scoped(None, Some(project_id), None, None, 0)?;
+    let ids: Vec<i32> = rows.iter().map(|(image, _, _)| image.id).collect();
+    let favorites = db.favorite_ids(&ids)?;
+    let mut tags = db.tags_for_images(&ids)?;
+
+    let total = rows.len();
+    let images: Vec<CuratedImage> = rows
+        .into_iter()
+        .filter_map(|(image, _, target)| {
+            let filename = extract_filename(&image.metadata)?;
+            let metadata: serde_json::Value =
+                serde_json::from_str(&image.metadata).unwrap_or_default();
+            let metrics = METRIC_KEYS
+                .iter()
+                .filter_map(|key| {
+                    let value = metadata.get(*key)?;
+                    (!value.is_null()).then(|| (key.to_string(), value.clone()))
+                })
+                .collect();
+            Some(CuratedImage {
+                filename,
+                acquired_date: image.acquired_date,
+                target,
+                filter: image.filter_name,
+                grading_status: GradingStatus::lowercase_name(image.grading_status).to_string(),
+                reject_reason: image.reject_reason,
+                favorite: favorites.contains(&image.id),
+                tags: tags.remove(&image.id).unwrap_or_default(),
+                metrics,
+            })
+        })
+        .collect();
+
+    Ok(Some(CurationExport {
+        format: CURATION_FORMAT.to_string(),
+        version: CURATION_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        project: project.name,
+        skipped_without_filename: total - images.len(),
+        images,
+    }))
+}
+
+/// Re-apply `document` to the frames of `project_id` (or the whole database).
+/// Grades and reject reasons are overwritten, favorites set to the document's
+/// value, and tags added (existing tags are kept). All writes share one
+/// transaction, rolled back on `dry_run`.
+pub fn import_curation(
+    conn: &Connection,
+    document: &CurationExport,
+    project_id: Option<i32>,
+    dry_run: bool,
+) -> Result<CurationImportSummary> {
+    document.validate()?;
+
+    let tx = conn.unchecked_transaction()?;
+    let db = Database::new(&tx);
+    let rows = db.query_images_scoped(None, project_id, None, None, 0)?;
+
+    let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, (image, _, _)) in rows.iter().enumerate() {
+        if let Some(filename) = extract_filename(&image.metadata) {
+            by_name.entry(match_key(&filename)).or_default().push(index);
+        }
+    }
+    let ids: Vec<i32> = rows.iter().map(|(image, _, _)| image.id).collect();
+    let favorites = db.favorite_ids(&ids)?;
+    let tags = db.tags_for_images(&ids)?;
+
+    let mut summary = CurationImportSummary {
+        records: document.images.len(),
+        dry_run,
+        ..Default::default()
+    };
+    for record in &document.images {
+        let status = parse_status_name(&record.grading_status)?;
+        let Some(candidates) = by_name.get(&match_key(&record.filename)) else {
+            summary.unmatched += 1;
+            continue;
+        };
+        let same_time: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&index| rows[index].0.acquired_date == record.acquired_date)
+            .collect();
+        let index = match (same_time.as_slice(), candidates.as_slice()) {
+            ([index], _) => *index,
+            ([], [index]) => *index,
+            _ => {
+                summary.ambiguous += 1;
+                continue;
+            }
+        };
+        summary.matched += 1;
+
+        let image = &rows[index].0;
+        let current_tags = tags.get(&image.id);
+        let new_tags: Vec<String> = record
+            .tags
+            .iter()
+            .filter(|tag| {
+                !current_tags.is_some_and(|current| {
+                    current.iter().any(|have| have.eq_ignore_ascii_case(tag))
+                })
+            })
+            .cloned()
+            .collect();
+        let grade_changed =
+            image.grading_status != status as i32 || image.reject_reason != record.reject_reason;
+        let favorite_changed = favorites.contains(&image.id) != record.favorite;
+        if !grade_changed && !favorite_changed && new_tags.is_empty() {
+            summary.unchanged += 1;
+            continue;
+        }
+
+        summary.updated += 1;
+        if grade_changed {
+            db.update_grading_status(image.id, status, record.reject_reason.as_deref())?;
+        }
+        if favorite_changed {
+            db.set_favorite(image.id, record.favorite)?;
+        }
+        if !new_tags.is_empty() {
+            db.add_tags(image.id, &new_tags)?;
+        }
+    }
+
+    if dry_run {
+        tx.rollback()?;
+    } else {
+        tx.commit()?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    /// `images`: (Id, FileName, acquiredDate, gradingStatus, rejectreason).
+    fn setup_db(images: &[(i32, &str, i64, i32, Option<&str>)]) -> Connection {
+        let conn = crate::db::test_schema::in_memory();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'p', 'M31', NULL);
+             INSERT INTO target VALUES (1, 'M31', 1, 0.7, 41.2, 1);",
+        )
+        .unwrap();
+        for (id, filename, date, status, reason) in images {
+            let metadata = serde_json::json!({ "FileName": filename, "HFR": 2.1 }).to_string();
+            conn.execute(
+                "INSERT INTO acquiredimage VALUES (?, 1, 1, ?, 'Ha', ?, ?, ?, 'p')",
+                params![id, date, status, metadata, reason],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn export_then_import_into_a_rebuilt_database() {
+        let source = setup_db(&[
+            (1, r"C:\Lights\M31\a.fits", 100, 2, Some("Clouds")),
+            (2, r"C:\Lights\M31\b.fits", 200, 1, None),
+            (3, r"C:\Lights\M31\c.fits", 300, 0, None),
+        ]);
+        Database::new(&source).set_favorite(2, true).unwrap();
+        Database::new(&source)
+            .add_tags(1, &["satellite".to_string()])
+            .unwrap();
+
+        let document = export_project(&source, 1).unwrap().unwrap();
+        assert!(export_project(&source, 2).unwrap().is_none());
+        assert_eq!(document.version, CURATION_VERSION);
+        assert_eq!(document.project, "M31");
+        assert_eq!(document.images.len(), 3);
+        assert_eq!(document.skipped_without_filename, 0);
+        let a = document
+            .images
+            .iter()
+            .find(|image| image.filename == "a.fits")
+            .unwrap();
+        assert_eq!(a.grading_status, "rejected");
+        assert_eq!(a.tags, vec!["satellite"]);
+        assert_eq!(a.metrics["HFR"], serde_json::json!(2.1));
+        let json = serde_json::to_string(&document).unwrap();
+        let document: CurationExport = serde_json::from_str(&json).unwrap();
+
+        // Rebuilt DB: new ids, other directory and separator, a.fits
+        // re-stamped, and a duplicate c.fits from another night.
+        let dest = setup_db(&[
+            (10, "/mnt/lights/M31/A.fits", 101, 0, None),
+            (11, "/mnt/lights/M31/b.fits", 200, 0, None),
+            (12, "/mnt/lights/M31/c.fits", 299, 0, None),
+            (13, "/mnt/lights/M31/c.fits", 301, 0, None),
+        ]);
+
+        let dry = import_curation(&dest, &document, Some(1), true).unwrap();
+        assert_eq!((dry.matched, dry.updated, dry.ambiguous), (2, 2, 1));
+        let db = Database::new(&dest);
+        assert_eq!(db.get_images_by_ids(&[10]).unwrap()[0].grading_status, 0);
+
+        let summary = import_curation(&dest, &document, Some(1), false).unwrap();
+        assert_eq!(summary.records, 3);
+        assert_eq!((summary.matched, summary.updated), (2, 2));
+        assert_eq!((summary.unmatched, summary.ambiguous), (0, 1));
+        let images = db.get_images_by_ids(&[10, 11]).unwrap();
+        let a = images.iter().find(|image| image.id == 10).unwrap();
+        assert_eq!(a.grading_status, 2);
+        assert_eq!(a.reject_reason.as_deref(), Some("Clouds"));
+        assert_eq!(db.tags_for_images(&[10]).unwrap()[&10], vec!["satellite"]);
+        assert!(db.favorite_ids(&[11]).unwrap().contains(&11));
+
+        let again = import_curation(&dest, &document, Some(1), false).unwrap();
+        assert_eq!((again.unchanged, again.updated), (2, 0));
+    }
+
+    #[test]
+    fn import_rejects_foreign_or_newer_documents() {
+        let conn = setup_db(&[]);
+        let mut document = CurationExport {
+            format: CURATION_FORMAT.to_string(),
+            version: CURATION_VERSION + 1,
+            exported_at: 0,
+            project: "M31".to_string(),
+            images: Vec::new(),
+            skipped_without_filename: 0,
+        };
+        assert!(import_curation(&conn, &document, None, false).is_err());
+        document.version = CURATION_VERSION;
+        document.format = "something-else".to_string();
+        assert!(import_curation(&conn, &document, None, false).is_err());
+    }
+
+    #[test]
+    fn export_counts_frames_without_a_filename() {
+        let conn = setup_db(&[(1, "a.fits", 100, 1, None)]);
+        conn.execute(
+            "INSERT INTO acquiredimage VALUES (2, 1, 1, 200, 'Ha', 0, '{\"HFR\": 2.0}', NULL, 'p')",
+            [],
+        )
+        .unwrap();
+
+        let document = export_project(&conn, 1).unwrap().unwrap();
+        assert_eq!(document.images.len(), 1);
+        assert_eq!(document.skipped_without_filename, 1);
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::image_analysis::FitsImage;
+use crate::trail_detection::{self, TrailConfig};
+
+/// CLI entry point: report linear trails in one frame and optionally write
+/// an overlay PNG with them drawn in red.
+pub fn detect_trails(
+    fits_path: &str,
+    overlay: Option<String>,
+    sigma: f64,
+    format: &str,
+) -> Result<()> {
+    let fits_path = Path::new(fits_path);
+    let image = FitsImage::from_file(fits_path)
+        .with_context(|| format!("Failed to load FITS file: {}", fits_path.display()))?;
+
+    let config = TrailConfig {
+        sigma,
+        ..TrailConfig::default()
+    };
+    let detection = trail_detection::detect_trails(&image, &config);
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&detection)?),
+        _ => {
+            println!(
+                "{} ({}x{}): {} trail(s)",
+                fits_path.display(),
+                detection.width,
+                detection.height,
+                detection.trails.len()
+            );
+            if !detection.trails.is_empty() {
+                println!(
+                    "{:>3} {:>22} {:>22} {:>9} {:>7} {:>8}",
+                    "#", "Start", "End", "Length", "Angle", "Contrast"
+                );
+            }
+            for (i, trail) in detection.trails.iter().enumerate() {
+                println!(
+                    "{:>3} {:>22} {:>22} {:>9.0} {:>7.1} {:>8.1}",
+                    i + 1,
+                    format!("({:.0}, {:.0})", trail.x1, trail.y1),
+                    format!("({:.0}, {:.0})", trail.x2, trail.y2),
+                    trail.length_px,
+                    trail.angle_deg,
+                    trail.contrast
+                );
+            }
+        }
+    }
+
+    if let Some(path) = overlay {
+        trail_detection::render_overlay(&image, &detection)
+            .save(&path)
+            .with_context(|| format!("Failed to write overlay: {}", path))?;
+        eprintln!("Saved overlay to: {}", path);
+    }
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::db::Database;
 use crate::models::{AcquiredImage, GradingStatus};
+use crate::reject_reasons::ReasonMapper;
 use crate::utils::{extract_filename, truncate_string};
 use anyhow::Result;
 use rusqlite::Connection;
@@ -10,6 +11,7 @@ pub fn dump_grading_results(
     project_filter: Option<String>,
     target_filter: Option<String>,
     format: &str,
+    mapper: &ReasonMapper,
 ) -> Result<()> {
     let db = Database::new(conn);
 
@@ -37,20 +39,34 @@ pub fn dump_grading_results(
     )?;
 
     match format {
-        "json" => output_json(&results)?,
-        "csv" => output_csv(&results)?,
-        _ => output_table(&results)?,
+        "json" => output_json(&results, mapper)?,
+        "csv" => output_csv(&results, mapper)?,
+        _ => output_table(&results, mapper)?,
     }
 
     Ok(())
 }
 
-fn output_table(results: &[(AcquiredImage, String, String)]) -> Result<()> {
+/// Reject category of a rejected frame; `None` for pending/accepted ones.
+fn reject_category(image: &AcquiredImage, mapper: &ReasonMapper) -> Option<String> {
+    (image.grading_status == GradingStatus::Rejected as i32)
+        .then(|| mapper.categorize(image.reject_reason.as_deref()))
+}
+
+fn output_table(results: &[(AcquiredImage, String, String)], mapper: &ReasonMapper) -> Result<()> {
     println!(
-        "{:<10} {:<50} {:<20} {:<20} {:<15} {:<10} {:<16} {:<20}",
-        "ID", "Filename", "Project", "Target", "Filter", "Status", "Date", "Reject Reason"
+        "{:<10} {:<50} {:<20} {:<20} {:<15} {:<10} {:<16} {:<20} {:<20}",
+        "ID",
+        "Filename",
+        "Project",
+        "Target",
+        "Filter",
+        "Status",
+        "Date",
+        "Category",
+        "Reject Reason"
     );
-    println!("{:-<180}", "");
+    println!("{:-<200}", "");
 
     for (image, project_name, target_name) in results {
         let date_str = image
@@ -62,7 +78,7 @@ fn output_table(results: &[(AcquiredImage, String, String)]) -> Result<()> {
         let filename = extract_filename(&image.metadata).unwrap_or_else(|| "Unknown".to_string());
 
         println!(
-            "{:<10} {:<50} {:<20} {:<20} {:<15} {:<10} {:<16} {:<20}",
+            "{:<10} {:<50} {:<20} {:<20} {:<15} {:<10} {:<16} {:<20} {:<20}",
             image.id,
             truncate_string(&filename, 50),
             truncate_string(project_name, 20),
@@ -70,6 +86,7 @@ fn output_table(results: &[(AcquiredImage, String, String)]) -> Result<()> {
             truncate_string(&image.filter_name, 15),
             GradingStatus::from_i32(image.grading_status),
             date_str,
+            truncate_string(&reject_category(image, mapper).unwrap_or_default(), 20),
             image.reject_reason.as_deref().unwrap_or("")
         );
     }
@@ -78,7 +95,7 @@ fn output_table(results: &[(AcquiredImage, String, String)]) -> Result<()> {
     Ok(())
 }
 
-fn output_json(results: &[(AcquiredImage, String, String)]) -> Result<()> {
+fn output_json(results: &[(AcquiredImage, String, String)], mapper: &ReasonMapper) -> Result<()> {
     let json_results: Vec<serde_json::Value> = results
         .iter()
         .map(|(image, project, target)| {
@@ -97,6 +114,7 @@ fn output_json(results: &[(AcquiredImage, String, String)]) -> Result<()> {
                 "grading_status_code": image.grading_status,
                 "acquired_date": image.acquired_date,
                 "reject_reason": image.reject_reason,
+                "reject_category": reject_category(image, mapper),
                 "metadata": image.metadata,
             })
         })
@@ -106,8 +124,8 @@ fn output_json(results: &[(AcquiredImage, String, String)]) -> Result<()> {
     Ok(())
 }
 
-fn output_csv(results: &[(AcquiredImage, String, String)]) -> Result<()> {
-    println!("id,filename,project_name,target_name,filter_name,grading_status,acquired_date,reject_reason");
+fn output_csv(results: &[(AcquiredImage, String, String)], mapper: &ReasonMapper) -> Result<()> {
+    println!("id,filename,project_name,target_name,filter_name,grading_status,acquired_date,reject_reason,reject_category");
 
     for (image, project_name, target_name) in results {
         let date_str = image
@@ -118,7 +136,7 @@ fn output_csv(results: &[(AcquiredImage, String, String)]) -> Result<()> {
         let filename = extract_filename(&image.metadata).unwrap_or_else(|| "Unknown".to_string());
 
         println!(
-            "{},{},{},{},{},{},{},{}",
+            "{},{},{},{},{},{},{},{},{}",
             image.id,
             filename,
             project_name,
@@ -126,7 +144,8 @@ fn output_csv(results: &[(AcquiredImage, String, String)]) -> Result<()> {
             image.filter_name,
             GradingStatus::from_i32(image.grading_status),
             date_str,
-            image.reject_reason.as_deref().unwrap_or("")
+            image.reject_reason.as_deref().unwrap_or(""),
+            reject_category(image, mapper).unwrap_or_default()
         );
     }
     Ok(())
@@ -69,7 +69,7 @@ pub struct ExportOptions {
     pub filter_name: Option<String>,
 }
 
-#[derive(Debug, Default, serde::Serialize)]
+#[derive(Debug, Default, serde::Serialize, utoipa::ToSchema)]
 pub struct ExportSummary {
     pub planned: usize,
     pub copied: usize,
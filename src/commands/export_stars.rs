@@ -0,0 +1,287 @@
+//! Detected-star catalogs for other astronomy software: one row per star
+//! with position, HFR, FWHM, flux and (when a PSF was fitted) eccentricity,
+//! as CSV, JSON or a FITS BINTABLE.
+//!
+//! CSV and JSON keep PSF Guard's 0-based pixel positions. The FITS table
+//! follows the FITS convention instead (first pixel centre at 1.0), as
+//! source-extraction tools do, so it lines up with the image in any viewer.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::fits_write::{write_binary_table, TableColumn};
+use crate::hocus_focus_star_detection::{
+    detect_stars_hocus_focus, HocusFocusParams, HocusFocusStar,
+};
+use crate::image_analysis::FitsImage;
+use crate::psf_fitting::PSFType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogFormat {
+    Csv,
+    Json,
+    Fits,
+}
+
+impl std::str::FromStr for CatalogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(CatalogFormat::Csv),
+            "json" => Ok(CatalogFormat::Json),
+            "fits" | "fit" => Ok(CatalogFormat::Fits),
+            _ => Err(format!("format must be csv, json or fits, got {}", s)),
+        }
+    }
+}
+
+/// One detected star. Eccentricity needs a PSF fit and is `None` without.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CatalogStar {
+    pub x: f64,
+    pub y: f64,
+    pub hfr: f64,
+    pub fwhm: f64,
+    pub flux: f64,
+    pub eccentricity: Option<f64>,
+}
+
+impl From<&HocusFocusStar> for CatalogStar {
+    fn from(star: &HocusFocusStar) -> Self {
+        Self {
+            x: star.position.0,
+            y: star.position.1,
+            hfr: star.hfr,
+            fwhm: star.fwhm,
+            flux: star.flux,
+            eccentricity: star
+                .psf_model
+                .as_ref()
+                .map(|psf| psf.eccentricity)
+                .filter(|e| e.is_finite()),
+        }
+    }
+}
+
+/// Column layout of the FITS table.
+pub const FITS_COLUMNS: [TableColumn<'static>; 6] = [
+    TableColumn {
+        name: "X",
+        unit: "pix",
+    },
+    TableColumn {
+        name: "Y",
+        unit: "pix",
+    },
+    TableColumn {
+        name: "HFR",
+        unit: "pix",
+    },
+    TableColumn {
+        name: "FWHM",
+        unit: "pix",
+    },
+    TableColumn {
+        name: "FLUX",
+        unit: "adu",
+    },
+    TableColumn {
+        name: "ECCENTRICITY",
+        unit: "",
+    },
+];
+
+/// CLI entry point: detect stars in one frame and write their catalog to
+/// `output`, or stdout for CSV/JSON without one. FITS output defaults to
+/// `<name>_stars.fits` beside the frame.
+pub fn export_stars(
+    fits_path: &str,
+    output: Option<String>,
+    format: &str,
+    psf_type: &str,
+) -> Result<()> {
+    let format: CatalogFormat = format.parse().map_err(anyhow::Error::msg)?;
+    let psf_type: PSFType = psf_type.parse().map_err(anyhow::Error::msg)?;
+    let fits_path = Path::new(fits_path);
+    let fits = FitsImage::from_file(fits_path)
+        .with_context(|| format!("Failed to load FITS file: {}", fits_path.display()))?;
+
+    let params = HocusFocusParams {
+        psf_type,
+        ..Default::default()
+    };
+    let result = detect_stars_hocus_focus(&fits.data, fits.width, fits.height, &params);
+    let stars: Vec<CatalogStar> = result.stars.iter().map(CatalogStar::from).collect();
+
+    let output = match (output, format) {
+        (Some(path), _) => Some(PathBuf::from(path)),
+        (None, CatalogFormat::Fits) => Some(default_fits_output(fits_path)),
+        (None, _) => None,
+    };
+    match output {
+        Some(path) => {
+            write_catalog(&path, format, &stars)?;
+            eprintln!("Wrote {} stars to: {}", stars.len(), path.display());
+        }
+        None => print!("{}", catalog_text(format, &stars)?),
+    }
+    Ok(())
+}
+
+fn default_fits_output(fits_path: &Path) -> PathBuf {
+    let stem = fits_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "stars".to_string());
+    fits_path.with_file_name(format!("{}_stars.fits", stem))
+}
+
+/// Write `stars` to `path` in `format`.
+pub fn write_catalog(path: &Path, format: CatalogFormat, stars: &[CatalogStar]) -> Result<()> {
+    if format == CatalogFormat::Fits {
+        let rows: Vec<Vec<f64>> = stars
+            .iter()
+            .map(|s| {
+                vec![
+                    s.x + 1.0,
+                    s.y + 1.0,
+                    s.hfr,
+                    s.fwhm,
+                    s.flux,
+                    s.eccentricity.unwrap_or(f64::NAN),
+                ]
+            })
+            .collect();
+        return write_binary_table(path, "STARS", &FITS_COLUMNS, &rows);
+    }
+    std::fs::write(path, catalog_text(format, stars)?)
+        .with_context(|| format!("Failed to write catalog: {}", path.display()))
+}
+
+fn catalog_text(format: CatalogFormat, stars: &[CatalogStar]) -> Result<String> {
+    match format {
+        CatalogFormat::Json => Ok(serde_json::to_string_pretty(stars)? + "\n"),
+        CatalogFormat::Csv => {
+            let mut text = String::from("x,y,hfr,fwhm,flux,eccentricity\n");
+            for s in stars {
+                text.push_str(&format!(
+                    "{:.3},{:.3},{:.4},{:.4},{:.1},{}\n",
+                    s.x,
+                    s.y,
+                    s.hfr,
+                    s.fwhm,
+                    s.flux,
+                    s.eccentricity
+                        .map(|e| format!("{:.4}", e))
+                        .unwrap_or_default()
+                ));
+            }
+            Ok(text)
+        }
+        CatalogFormat::Fits => bail!("FITS catalogs are binary; give an output path"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fits_write::BLOCK_SIZE;
+
+    /// Cards of the header starting at `offset` as (keyword, unquoted
+    /// value), and the offset where its data begins.
+    fn read_header(bytes: &[u8], offset: usize) -> (Vec<(String, String)>, usize) {
+        let mut cards = Vec::new();
+        let mut pos = offset;
+        loop {
+            let card = std::str::from_utf8(&bytes[pos..pos + 80]).unwrap();
+            pos += 80;
+            let key = card[..8].trim().to_string();
+            if key == "END" {
+                break;
+            }
+            let value = card[10..].trim().trim_matches('\'').trim().to_string();
+            cards.push((key, value));
+        }
+        (cards, pos.div_ceil(BLOCK_SIZE) * BLOCK_SIZE)
+    }
+
+    fn card<'a>(cards: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        cards
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn fits_catalog_reads_back_as_a_bintable() {
+        let stars = [
+            CatalogStar {
+                x: 10.0,
+                y: 20.0,
+                hfr: 2.1,
+                fwhm: 3.4,
+                flux: 5000.0,
+                eccentricity: Some(0.3),
+            },
+            CatalogStar {
+                x: 100.5,
+                y: 200.25,
+                hfr: 2.6,
+                fwhm: 4.0,
+                flux: 800.0,
+                eccentricity: None,
+            },
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stars.fits");
+        write_catalog(&path, CatalogFormat::Fits, &stars).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len() % BLOCK_SIZE, 0);
+        let (primary, table_start) = read_header(&bytes, 0);
+        assert_eq!(card(&primary, "SIMPLE"), Some("T"));
+        assert_eq!(card(&primary, "NAXIS"), Some("0"));
+
+        let (table, data_start) = read_header(&bytes, table_start);
+        assert_eq!(card(&table, "XTENSION"), Some("BINTABLE"));
+        assert_eq!(card(&table, "EXTNAME"), Some("STARS"));
+        assert_eq!(card(&table, "TFIELDS"), Some("6"));
+        assert_eq!(card(&table, "NAXIS1"), Some("48"));
+        assert_eq!(card(&table, "NAXIS2"), Some("2"));
+        let names: Vec<&str> = (1..=6)
+            .map(|n| card(&table, &format!("TTYPE{}", n)).unwrap())
+            .collect();
+        assert_eq!(names, ["X", "Y", "HFR", "FWHM", "FLUX", "ECCENTRICITY"]);
+        assert_eq!(card(&table, "TFORM3"), Some("1D"));
+
+        let value = |row: usize, col: usize| {
+            let at = data_start + row * 48 + col * 8;
+            f64::from_be_bytes(bytes[at..at + 8].try_into().unwrap())
+        };
+        // Positions move to the 1-based FITS pixel convention.
+        assert_eq!((value(0, 0), value(0, 1)), (11.0, 21.0));
+        assert_eq!((value(1, 0), value(1, 1)), (101.5, 201.25));
+        assert_eq!(value(0, 4), 5000.0);
+        assert_eq!(value(0, 5), 0.3);
+        assert!(value(1, 5).is_nan(), "no PSF fit is a null eccentricity");
+    }
+
+    #[test]
+    fn csv_leaves_missing_eccentricity_empty() {
+        let star = CatalogStar {
+            x: 1.0,
+            y: 2.0,
+            hfr: 2.0,
+            fwhm: 3.0,
+            flux: 10.0,
+            eccentricity: None,
+        };
+        let text = catalog_text(CatalogFormat::Csv, &[star]).unwrap();
+        assert_eq!(
+            text,
+            "x,y,hfr,fwhm,flux,eccentricity\n1.000,2.000,2.0000,3.0000,10.0,\n"
+        );
+    }
+}
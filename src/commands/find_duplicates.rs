@@ -0,0 +1,249 @@
+use anyhow::Result;
+use rusqlite::Connection;
+
+use crate::db::Database;
+use crate::models::GradingStatus;
+use crate::scoring::filename_from_metadata;
+
+/// Frames further apart than this are never the same capture.
+const SAME_CAPTURE_SECONDS: i64 = 1;
+
+/// What makes two image rows the same frame: target, filter, resolved
+/// filename and (to within a second) acquisition time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSignature {
+    pub image_id: i32,
+    pub target_id: i32,
+    pub target_name: String,
+    pub filter_name: String,
+    pub acquired_date: i64,
+    pub filename: String,
+    pub grading_status: i32,
+}
+
+/// Rows that are one frame imported more than once. `keep` is the row to
+/// preserve; `remove` are the extra copies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateCluster {
+    pub keep: ImageSignature,
+    pub remove: Vec<ImageSignature>,
+}
+
+/// Report (and with `delete`, remove) rows that duplicate another row's
+/// frame. Dry run unless `delete` is set.
+pub fn find_duplicates(
+    conn: &Connection,
+    project: Option<&str>,
+    target: Option<&str>,
+    delete: bool,
+) -> Result<()> {
+    let db = Database::new(conn);
+    let images = db.query_images(None, project, target, None)?;
+    let total = images.len();
+
+    // Rows without a capture time or a filename can't be matched reliably.
+    let signatures: Vec<ImageSignature> = images
+        .into_iter()
+        .filter_map(|(image, _, target_name)| {
+            Some(ImageSignature {
+                image_id: image.id,
+                target_id: image.target_id,
+                target_name,
+                filter_name: image.filter_name,
+                acquired_date: image.acquired_date?,
+                filename: filename_from_metadata(&image.metadata).filter(|f| !f.is_empty())?,
+                grading_status: image.grading_status,
+            })
+        })
+        .collect();
+    let unsigned = total - signatures.len();
+    let clusters = duplicate_clusters(signatures);
+
+    for cluster in &clusters {
+        let keep = &cluster.keep;
+        println!(
+            "{} [{}] {} @ {}: keep #{} ({}), remove {}",
+            keep.target_name,
+            keep.filter_name,
+            keep.filename,
+            chrono::DateTime::from_timestamp(keep.acquired_date, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            keep.image_id,
+            GradingStatus::lowercase_name(keep.grading_status),
+            cluster
+                .remove
+                .iter()
+                .map(|s| format!(
+                    "#{} ({})",
+                    s.image_id,
+                    GradingStatus::lowercase_name(s.grading_status)
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let remove: Vec<i32> = clusters
+        .iter()
+        .flat_map(|cluster| cluster.remove.iter().map(|s| s.image_id))
+        .collect();
+    println!(
+        "\n{} duplicate cluster(s) among {} images; {} extra row(s){}",
+        clusters.len(),
+        total,
+        remove.len(),
+        if unsigned > 0 {
+            format!(
+                " ({} images without a capture time or filename were not checked)",
+                unsigned
+            )
+        } else {
+            String::new()
+        }
+    );
+
+    if remove.is_empty() {
+        return Ok(());
+    }
+    if !delete {
+        println!("[DRY RUN] Pass --delete to remove the extra rows");
+        return Ok(());
+    }
+    let deleted = db.delete_images(&remove)?;
+    println!("Deleted {} image row(s)", deleted);
+    Ok(())
+}
+
+/// Group signatures into clusters of the same frame. Within a cluster the
+/// row kept is the accepted one, else the rejected one, else any; ties go to
+/// the oldest (lowest id) row. Clusters come back ordered by their kept id.
+pub fn duplicate_clusters(mut signatures: Vec<ImageSignature>) -> Vec<DuplicateCluster> {
+    signatures.sort_by(|a, b| {
+        (
+            a.target_id,
+            &a.filter_name,
+            &a.filename,
+            a.acquired_date,
+            a.image_id,
+        )
+            .cmp(&(
+                b.target_id,
+                &b.filter_name,
+                &b.filename,
+                b.acquired_date,
+                b.image_id,
+            ))
+    });
+
+    // Each row is compared with the first (earliest) row of its cluster,
+    // not the previous one, so a chain of near-matches a second apart can't
+    // pull frames several seconds apart into one cluster.
+    let mut clusters = Vec::new();
+    let mut current: Vec<ImageSignature> = Vec::new();
+    for signature in signatures {
+        let same = current.first().is_some_and(|first| {
+            first.target_id == signature.target_id
+                && first.filter_name == signature.filter_name
+                && first.filename == signature.filename
+                && signature.acquired_date - first.acquired_date <= SAME_CAPTURE_SECONDS
+        });
+        if !same {
+            clusters.extend(into_cluster(std::mem::take(&mut current)));
+        }
+        current.push(signature);
+    }
+    clusters.extend(into_cluster(current));
+    clusters.sort_by_key(|cluster| cluster.keep.image_id);
+    clusters
+}
+
+fn into_cluster(mut rows: Vec<ImageSignature>) -> Option<DuplicateCluster> {
+    if rows.len() < 2 {
+        return None;
+    }
+    // Accepted (1) first, then rejected (2), then pending (0).
+    let rank = |status: i32| match status {
+        1 => 0,
+        2 => 1,
+        _ => 2,
+    };
+    rows.sort_by_key(|s| (rank(s.grading_status), s.image_id));
+    let keep = rows.remove(0);
+    rows.sort_by_key(|s| s.image_id);
+    Some(DuplicateCluster { keep, remove: rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(id: i32, filename: &str, date: i64, status: i32) -> ImageSignature {
+        ImageSignature {
+            image_id: id,
+            target_id: 1,
+            target_name: "M31".to_string(),
+            filter_name: "L".to_string(),
+            acquired_date: date,
+            filename: filename.to_string(),
+            grading_status: status,
+        }
+    }
+
+    #[test]
+    fn copies_within_a_second_cluster_and_keep_the_graded_row() {
+        let clusters = duplicate_clusters(vec![
+            sig(1, "a.fits", 1000, 0),
+            sig(7, "a.fits", 1001, 1),
+            sig(2, "b.fits", 1300, 0),
+            sig(9, "a.fits", 1000, 0),
+        ]);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].keep.image_id, 7);
+        let removed: Vec<i32> = clusters[0].remove.iter().map(|s| s.image_id).collect();
+        assert_eq!(removed, vec![1, 9]);
+    }
+
+    #[test]
+    fn different_time_filter_or_target_is_not_a_duplicate() {
+        let mut other_filter = sig(2, "a.fits", 1000, 0);
+        other_filter.filter_name = "Ha".to_string();
+        let mut other_target = sig(3, "a.fits", 1000, 0);
+        other_target.target_id = 2;
+        let clusters = duplicate_clusters(vec![
+            sig(1, "a.fits", 1000, 0),
+            other_filter,
+            other_target,
+            sig(4, "a.fits", 1002, 0),
+        ]);
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn near_matches_do_not_chain() {
+        let clusters = duplicate_clusters(vec![
+            sig(1, "a.fits", 1000, 0),
+            sig(2, "a.fits", 1001, 0),
+            sig(3, "a.fits", 1002, 0),
+            sig(4, "a.fits", 1003, 0),
+        ]);
+        let ids: Vec<(i32, Vec<i32>)> = clusters
+            .iter()
+            .map(|c| {
+                (
+                    c.keep.image_id,
+                    c.remove.iter().map(|s| s.image_id).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(ids, vec![(1, vec![2]), (3, vec![4])]);
+    }
+
+    #[test]
+    fn ties_keep_the_oldest_row() {
+        let clusters =
+            duplicate_clusters(vec![sig(5, "a.fits", 1000, 2), sig(3, "a.fits", 1000, 2)]);
+        assert_eq!(clusters[0].keep.image_id, 3);
+        assert_eq!(clusters[0].remove[0].image_id, 5);
+    }
+}
@@ -0,0 +1,224 @@
+//! FITS to TIFF, for processing tools that want TIFF rather than FITS or an
+//! 8-bit PNG.
+//!
+//! 16-bit output keeps the stored u16 values, which are physical ADU for
+//! integer camera data. 32-bit output is IEEE float normalized to 0..1, the
+//! range processing tools expect. Either is linear unless the preview's MTF
+//! stretch is asked for.
+
+use anyhow::{bail, Context, Result};
+use image::{ImageBuffer, Luma};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tiff::encoder::{colortype, TiffEncoder};
+
+use crate::image_analysis::{FitsImage, Roi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TiffDepth {
+    /// Unsigned 16-bit integer samples.
+    U16,
+    /// 32-bit float samples in 0..1.
+    F32,
+}
+
+impl TiffDepth {
+    pub fn from_bits(bits: u32) -> Result<Self> {
+        match bits {
+            16 => Ok(TiffDepth::U16),
+            32 => Ok(TiffDepth::F32),
+            other => bail!("bit depth must be 16 or 32, got {}", other),
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            TiffDepth::U16 => 16,
+            TiffDepth::F32 => 32,
+        }
+    }
+}
+
+/// The frame's pixels, MTF-stretched with `(midtone, shadow)` when given,
+/// downscaled to fit `max_dimensions`.
+pub fn tiff_pixels(
+    image: &FitsImage,
+    stretch: Option<(f64, f64)>,
+    max_dimensions: Option<(u32, u32)>,
+) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>> {
+    let data = match stretch {
+        Some((midtone, shadow)) => {
+            let stats = image.calculate_basic_statistics();
+            seiza_stretch::stretch_u16_to_u16(
+                &image.data,
+                &stats.to_stretch_statistics(),
+                &seiza_stretch::StretchParams {
+                    target_median: midtone,
+                    shadows_clip: shadow,
+                },
+            )
+        }
+        None => image.data.clone(),
+    };
+    let (width, height) = (image.width as u32, image.height as u32);
+    let buffer = ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(width, height, data)
+        .context("Failed to create image buffer")?;
+    let Some((max_width, max_height)) = max_dimensions else {
+        return Ok(buffer);
+    };
+    let (new_width, new_height) =
+        crate::image_utils::fit_dimensions(width, height, max_width, max_height);
+    if (new_width, new_height) == (width, height) {
+        return Ok(buffer);
+    }
+    Ok(image::imageops::resize(
+        &buffer,
+        new_width,
+        new_height,
+        image::imageops::FilterType::Lanczos3,
+    ))
+}
+
+/// Encode 16-bit pixels as a grayscale TIFF of the given depth.
+pub fn write_tiff(
+    pixels: &ImageBuffer<Luma<u16>, Vec<u16>>,
+    depth: TiffDepth,
+    output_path: &Path,
+) -> Result<()> {
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create output file: {}", output_path.display()))?;
+    let mut encoder = TiffEncoder::new(BufWriter::new(file))?;
+    let (width, height) = pixels.dimensions();
+    match depth {
+        TiffDepth::U16 => encoder.write_image::<colortype::Gray16>(width, height, pixels.as_raw()),
+        TiffDepth::F32 => {
+            let samples: Vec<f32> = pixels
+                .as_raw()
+                .iter()
+                .map(|&v| f32::from(v) / 65535.0)
+                .collect();
+            encoder.write_image::<colortype::Gray32Float>(width, height, &samples)
+        }
+    }
+    .with_context(|| format!("Failed to write TIFF image to {}", output_path.display()))
+}
+
+/// Load a frame, optionally crop and stretch it, and write it as a TIFF.
+/// Shared by the `fits-to-tiff` command and the preview queue.
+pub fn render_tiff(
+    fits_path: &Path,
+    output_path: &Path,
+    depth: TiffDepth,
+    stretch: Option<(f64, f64)>,
+    roi: Option<&Roi>,
+    max_dimensions: Option<(u32, u32)>,
+) -> Result<()> {
+    let image = crate::fits_read::load_with_retry(fits_path)
+        .with_context(|| format!("Failed to load FITS file: {}", fits_path.display()))?;
+    let image = match roi {
+        Some(roi) => image.cropped(roi)?,
+        None => image,
+    };
+    write_tiff(
+        &tiff_pixels(&image, stretch, max_dimensions)?,
+        depth,
+        output_path,
+    )
+}
+
+pub fn fits_to_tiff(
+    fits_path: &str,
+    output: Option<String>,
+    depth: TiffDepth,
+    stretch: Option<(f64, f64)>,
+) -> Result<()> {
+    let fits_path = Path::new(fits_path);
+    let output_path = match output {
+        Some(path) => PathBuf::from(path),
+        None => fits_path.with_extension("tiff"),
+    };
+    render_tiff(fits_path, &output_path, depth, stretch, None, None)?;
+    println!(
+        "Saved {}-bit {} TIFF to: {}",
+        depth.bits(),
+        if stretch.is_some() {
+            "stretched"
+        } else {
+            "linear"
+        },
+        output_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiff::decoder::{Decoder, DecodingResult};
+    use tiff::ColorType;
+
+    fn frame() -> FitsImage {
+        FitsImage {
+            width: 4,
+            height: 3,
+            data: vec![0, 100, 1000, 65535, 7, 8, 9, 10, 500, 600, 700, 800],
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: Default::default(),
+        }
+    }
+
+    fn read_back(path: &Path) -> (ColorType, (u32, u32), DecodingResult) {
+        let mut decoder = Decoder::new(std::io::BufReader::new(File::open(path).unwrap())).unwrap();
+        (
+            decoder.colortype().unwrap(),
+            decoder.dimensions().unwrap(),
+            decoder.read_image().unwrap(),
+        )
+    }
+
+    #[test]
+    fn linear_tiffs_round_trip_depth_and_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = frame();
+        let pixels = tiff_pixels(&image, None, None).unwrap();
+
+        let path = dir.path().join("frame16.tiff");
+        write_tiff(&pixels, TiffDepth::U16, &path).unwrap();
+        let (color, dimensions, data) = read_back(&path);
+        assert_eq!(color, ColorType::Gray(16));
+        assert_eq!(dimensions, (4, 3));
+        let DecodingResult::U16(values) = data else {
+            panic!("expected 16-bit samples");
+        };
+        assert_eq!(values, image.data);
+
+        let path = dir.path().join("frame32.tiff");
+        write_tiff(&pixels, TiffDepth::F32, &path).unwrap();
+        let (color, _, data) = read_back(&path);
+        assert_eq!(color, ColorType::Gray(32));
+        let DecodingResult::F32(values) = data else {
+            panic!("expected float samples");
+        };
+        assert_eq!(values[0], 0.0);
+        assert_eq!(values[3], 1.0);
+        assert!((values[2] - 1000.0 / 65535.0).abs() < 1e-7);
+    }
+
+    #[test]
+    fn stretch_and_resize_change_the_pixels_not_the_format() {
+        let image = frame();
+        let stretched = tiff_pixels(&image, Some((0.2, -2.8)), None).unwrap();
+        assert_eq!(stretched.dimensions(), (4, 3));
+        assert_ne!(stretched.as_raw(), &image.data);
+
+        let smaller = tiff_pixels(&image, None, Some((2, 2))).unwrap();
+        assert_eq!(smaller.width(), 2);
+        assert!(smaller.height() <= 2);
+
+        assert_eq!(TiffDepth::from_bits(32).unwrap(), TiffDepth::F32);
+        assert!(TiffDepth::from_bits(8).is_err());
+    }
+}
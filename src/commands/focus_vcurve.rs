@@ -0,0 +1,513 @@
+//! V-curve from an autofocus run: HFR against focuser position for subs
+//! taken at stepped focuser positions, with a parabola fitted through them
+//! to find best focus.
+//!
+//! A run is a stretch of consecutive frames (in acquisition order, no gap
+//! longer than `max_gap` seconds) that covers at least [`MIN_POSITIONS`]
+//! distinct focuser positions; the latest one is analyzed. HFR comes from
+//! the metadata when N.I.N.A. recorded it and from star detection otherwise.
+
+use anyhow::{bail, Context, Result};
+use image::{Rgb, RgbImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_line_segment_mut};
+use rusqlite::Connection;
+use serde_json::Value;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::commands::analyze_batch::analyze_frame;
+use crate::commands::contact_sheet::target_frames;
+use crate::commands::screen_annotate::draw_text;
+use crate::directory_tree::DirectoryTree;
+use crate::scoring::filename_from_metadata;
+
+/// Longest pause between two frames of the same run, in seconds.
+pub const DEFAULT_MAX_GAP_SECS: i64 = 300;
+/// Fewest distinct focuser positions that make a run worth fitting.
+pub const MIN_POSITIONS: usize = 5;
+
+const PLOT_WIDTH: u32 = 800;
+const PLOT_HEIGHT: u32 = 500;
+const MARGIN: u32 = 40;
+const BACKGROUND: Rgb<u8> = Rgb([17, 17, 17]);
+const AXIS: Rgb<u8> = Rgb([128, 128, 128]);
+const POINT: Rgb<u8> = Rgb([240, 240, 240]);
+const CURVE: Rgb<u8> = Rgb([64, 160, 255]);
+const BEST: Rgb<u8> = Rgb([64, 192, 96]);
+
+pub struct FocusVcurveOptions {
+    pub target: String,
+    /// Disambiguates a target name used in several projects.
+    pub project: Option<String>,
+    /// Only this filter's frames; all of them otherwise.
+    pub filter: Option<String>,
+    /// PNG plot of the points and the fitted curve.
+    pub output: String,
+    /// Optional CSV of the points.
+    pub csv: Option<String>,
+    pub max_gap: i64,
+    /// Directories to find the FITS files in, for frames without an HFR.
+    pub image_dirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusPoint {
+    pub image_id: i32,
+    pub position: f64,
+    pub hfr: f64,
+}
+
+/// `hfr = a·position² + b·position + c`, least-squares over a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VCurveFit {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    /// Focuser position at the bottom of the curve.
+    pub best_position: f64,
+    pub best_hfr: f64,
+    /// Coefficient of determination; 1.0 is a perfect fit.
+    pub r_squared: f64,
+}
+
+impl VCurveFit {
+    pub fn hfr_at(&self, position: f64) -> f64 {
+        (self.a * position + self.b) * position + self.c
+    }
+}
+
+/// Focuser position recorded in an image's metadata.
+pub fn focuser_position(metadata: &str) -> Option<f64> {
+    metadata_number(metadata, "FocuserPosition")
+}
+
+fn metadata_number(metadata: &str, key: &str) -> Option<f64> {
+    let value: Value = serde_json::from_str(metadata).ok()?;
+    match &value[key] {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|v| v.is_finite())
+}
+
+/// Split `(acquired_date, position)` frames, in acquisition order, into
+/// runs of at least [`MIN_POSITIONS`] distinct positions with no pause over
+/// `max_gap` seconds. Frames without a date end a run.
+pub fn focus_runs(frames: &[(Option<i64>, f64)], max_gap: i64) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for end in 1..=frames.len() {
+        let split = end == frames.len()
+            || match (frames[end - 1].0, frames[end].0) {
+                (Some(previous), Some(current)) => current - previous > max_gap,
+                _ => true,
+            };
+        if split {
+            let mut positions: Vec<f64> = frames[start..end].iter().map(|(_, p)| *p).collect();
+            positions.sort_by(f64::total_cmp);
+            positions.dedup();
+            if positions.len() >= MIN_POSITIONS {
+                runs.push(start..end);
+            }
+            start = end;
+        }
+    }
+    runs
+}
+
+/// Fit a parabola through the points. Fails unless the curve opens upward,
+/// i.e. the run brackets a focus minimum.
+pub fn fit_vcurve(points: &[FocusPoint]) -> Result<VCurveFit> {
+    if points.len() < 3 {
+        bail!("A V-curve needs at least 3 points, got {}", points.len());
+    }
+    // Fit in centered, scaled coordinates so large focuser steps don't make
+    // the normal equations ill-conditioned.
+    let n = points.len() as f64;
+    let mean = points.iter().map(|p| p.position).sum::<f64>() / n;
+    let scale = points
+        .iter()
+        .map(|p| (p.position - mean).abs())
+        .fold(0.0, f64::max);
+    if scale == 0.0 {
+        bail!("All points share one focuser position");
+    }
+    let mut sums = [0.0f64; 5];
+    let mut rhs = [0.0f64; 3];
+    for p in points {
+        let u = (p.position - mean) / scale;
+        for (k, sum) in sums.iter_mut().enumerate() {
+            *sum += u.powi(k as i32);
+        }
+        for (k, r) in rhs.iter_mut().enumerate() {
+            *r += p.hfr * u.powi(k as i32);
+        }
+    }
+    // Normal equations for hfr = A·u² + B·u + C, by Cramer's rule.
+    let m = [
+        [sums[4], sums[3], sums[2]],
+        [sums[3], sums[2], sums[1]],
+        [sums[2], sums[1], sums[0]],
+    ];
+    let det = determinant(m);
+    if det.abs() < 1e-12 {
+        bail!("Focuser positions are too few or too clustered to fit a curve");
+    }
+    // Rows run from the highest power down, so the right-hand side goes in
+    // reversed.
+    let solve = |column: usize| {
+        let mut replaced = m;
+        for (row, value) in replaced.iter_mut().zip(rhs.iter().rev()) {
+            row[column] = *value;
+        }
+        determinant(replaced) / det
+    };
+    let (big_a, big_b, big_c) = (solve(0), solve(1), solve(2));
+    if big_a <= 0.0 {
+        bail!("The HFR curve has no minimum; the run doesn't bracket focus");
+    }
+
+    let a = big_a / (scale * scale);
+    let b = big_b / scale - 2.0 * big_a * mean / (scale * scale);
+    let c = big_a * mean * mean / (scale * scale) - big_b * mean / scale + big_c;
+    let best_position = mean - big_b * scale / (2.0 * big_a);
+
+    let mut fit = VCurveFit {
+        a,
+        b,
+        c,
+        best_position,
+        best_hfr: 0.0,
+        r_squared: 0.0,
+    };
+    fit.best_hfr = fit.hfr_at(best_position);
+    let mean_hfr = points.iter().map(|p| p.hfr).sum::<f64>() / n;
+    let total: f64 = points.iter().map(|p| (p.hfr - mean_hfr).powi(2)).sum();
+    let residual: f64 = points
+        .iter()
+        .map(|p| (p.hfr - fit.hfr_at(p.position)).powi(2))
+        .sum();
+    fit.r_squared = if total > 0.0 {
+        1.0 - residual / total
+    } else {
+        1.0
+    };
+    Ok(fit)
+}
+
+fn determinant(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+pub fn focus_vcurve(conn: &Connection, options: &FocusVcurveOptions) -> Result<()> {
+    let images = target_frames(
+        conn,
+        &options.target,
+        options.project.as_deref(),
+        options.filter.as_deref(),
+    )?;
+    let positioned: Vec<_> = images
+        .iter()
+        .filter_map(|image| focuser_position(&image.metadata).map(|p| (image, p)))
+        .collect();
+    if positioned.is_empty() {
+        bail!(
+            "None of the {} frame(s) of '{}' record a focuser position (FocuserPosition in the metadata)",
+            images.len(),
+            options.target
+        );
+    }
+    let frames: Vec<(Option<i64>, f64)> = positioned
+        .iter()
+        .map(|(image, position)| (image.acquired_date, *position))
+        .collect();
+    let runs = focus_runs(&frames, options.max_gap);
+    let Some(run) = runs.last() else {
+        bail!(
+            "No focus run found: no frames within {}s of each other cover {} focuser positions",
+            options.max_gap,
+            MIN_POSITIONS
+        );
+    };
+    println!(
+        "Found {} focus run(s); using the latest ({} frames)",
+        runs.len(),
+        run.len()
+    );
+
+    // Only scan the image directories if some frame needs detection.
+    let mut tree: Option<DirectoryTree> = None;
+    let mut points = Vec::new();
+    for (image, position) in &positioned[run.clone()] {
+        let hfr = match metadata_number(&image.metadata, "HFR").filter(|hfr| *hfr > 0.0) {
+            Some(hfr) => hfr,
+            None => {
+                if tree.is_none() {
+                    if options.image_dirs.is_empty() {
+                        bail!(
+                            "Image {} has no HFR in its metadata and no image directories are configured; pass --image-dirs",
+                            image.id
+                        );
+                    }
+                    let roots: Vec<&Path> = options.image_dirs.iter().map(Path::new).collect();
+                    tree = Some(
+                        DirectoryTree::build_multiple(&roots)
+                            .context("Failed to scan the image directories for FITS files")?,
+                    );
+                }
+                let Some(path) = filename_from_metadata(&image.metadata).and_then(|name| {
+                    tree.as_ref()
+                        .and_then(|tree| tree.find_file_first(&name).cloned())
+                }) else {
+                    eprintln!("Image {}: no HFR and file not found, skipped", image.id);
+                    continue;
+                };
+                match analyze_frame(&path) {
+                    Ok(metrics) if metrics.detected_stars > 0 => metrics.hfr,
+                    Ok(_) => {
+                        eprintln!("{}: no stars detected, skipped", path.display());
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("{}: {:#}, skipped", path.display(), e);
+                        continue;
+                    }
+                }
+            }
+        };
+        points.push(FocusPoint {
+            image_id: image.id,
+            position: *position,
+            hfr,
+        });
+    }
+
+    let fit = fit_vcurve(&points)?;
+    println!("{:>10} {:>8} {:>8}", "Position", "HFR", "Fitted");
+    for point in &points {
+        println!(
+            "{:>10.0} {:>8.3} {:>8.3}",
+            point.position,
+            point.hfr,
+            fit.hfr_at(point.position)
+        );
+    }
+    let (low, high) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+            (lo.min(p.position), hi.max(p.position))
+        });
+    println!(
+        "Best focus: {:.0} (HFR {:.3}), fit R² {:.3} over {} points",
+        fit.best_position,
+        fit.best_hfr,
+        fit.r_squared,
+        points.len()
+    );
+    if fit.best_position < low || fit.best_position > high {
+        println!(
+            "Warning: best focus lies outside the sampled positions {:.0}..{:.0}",
+            low, high
+        );
+    }
+
+    write_plot(&points, &fit, &options.output)?;
+    println!("Wrote {}", options.output);
+    if let Some(csv) = &options.csv {
+        write_csv(&points, &fit, csv)?;
+        println!("Wrote {}", csv);
+    }
+    Ok(())
+}
+
+/// Points, fitted curve and best-focus line, captioned with the result.
+pub fn render_plot(points: &[FocusPoint], fit: &VCurveFit) -> RgbImage {
+    let mut plot = RgbImage::from_pixel(PLOT_WIDTH, PLOT_HEIGHT, BACKGROUND);
+    let (x_low, x_high) = points
+        .iter()
+        .fold((fit.best_position, fit.best_position), |(lo, hi), p| {
+            (lo.min(p.position), hi.max(p.position))
+        });
+    let y_high = points.iter().map(|p| p.hfr).fold(fit.best_hfr, f64::max) * 1.1;
+    let y_low = points
+        .iter()
+        .map(|p| p.hfr)
+        .fold(fit.best_hfr, f64::min)
+        .min(0.0);
+    let (left, top) = (MARGIN as f32, MARGIN as f32);
+    let (right, bottom) = ((PLOT_WIDTH - MARGIN) as f32, (PLOT_HEIGHT - MARGIN) as f32);
+    let to_x = |position: f64| {
+        left + ((position - x_low) / (x_high - x_low).max(1.0)) as f32 * (right - left)
+    };
+    let to_y =
+        |hfr: f64| bottom - ((hfr - y_low) / (y_high - y_low).max(1e-9)) as f32 * (bottom - top);
+
+    draw_line_segment_mut(&mut plot, (left, bottom), (right, bottom), AXIS);
+    draw_line_segment_mut(&mut plot, (left, top), (left, bottom), AXIS);
+
+    let steps = 200;
+    let mut previous: Option<(f32, f32)> = None;
+    for step in 0..=steps {
+        let position = x_low + (x_high - x_low) * step as f64 / steps as f64;
+        let hfr = fit.hfr_at(position);
+        let current = (to_x(position), to_y(hfr).clamp(top, bottom));
+        if let Some(previous) = previous {
+            draw_line_segment_mut(&mut plot, previous, current, CURVE);
+        }
+        previous = Some(current);
+    }
+    let best_x = to_x(fit.best_position);
+    draw_line_segment_mut(&mut plot, (best_x, top), (best_x, bottom), BEST);
+    for point in points {
+        draw_filled_circle_mut(
+            &mut plot,
+            (to_x(point.position) as i32, to_y(point.hfr) as i32),
+            4,
+            POINT,
+        );
+    }
+
+    let caption = format!(
+        "BEST {:.0}  HFR {:.2}  R2 {:.3}",
+        fit.best_position, fit.best_hfr, fit.r_squared
+    );
+    draw_text(&mut plot, MARGIN, 12, &caption, POINT, 2);
+    let label_y = PLOT_HEIGHT - MARGIN + 10;
+    draw_text(
+        &mut plot,
+        MARGIN,
+        label_y,
+        &format!("{:.0}", x_low),
+        AXIS,
+        1,
+    );
+    let high_label = format!("{:.0}", x_high);
+    draw_text(
+        &mut plot,
+        PLOT_WIDTH - MARGIN - 6 * high_label.len() as u32,
+        label_y,
+        &high_label,
+        AXIS,
+        1,
+    );
+    plot
+}
+
+fn write_plot(points: &[FocusPoint], fit: &VCurveFit, output: &str) -> Result<()> {
+    render_plot(points, fit)
+        .save(output)
+        .with_context(|| format!("Failed to write plot: {}", output))
+}
+
+fn write_csv(points: &[FocusPoint], fit: &VCurveFit, output: &str) -> Result<()> {
+    let mut file = std::io::BufWriter::new(
+        std::fs::File::create(output)
+            .with_context(|| format!("Failed to create CSV file: {}", output))?,
+    );
+    writeln!(file, "image_id,position,hfr,fitted_hfr")?;
+    for point in points {
+        writeln!(
+            file,
+            "{},{},{:.4},{:.4}",
+            point.image_id,
+            point.position,
+            point.hfr,
+            fit.hfr_at(point.position)
+        )?;
+    }
+    writeln!(
+        file,
+        "# best_position={:.1},best_hfr={:.4},r_squared={:.4}",
+        fit.best_position, fit.best_hfr, fit.r_squared
+    )?;
+    file.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(hfr: impl Fn(f64) -> f64) -> Vec<FocusPoint> {
+        (0..9)
+            .map(|i| {
+                let position = 10_000.0 + 100.0 * i as f64;
+                FocusPoint {
+                    image_id: i,
+                    position,
+                    hfr: hfr(position),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fit_recovers_the_bottom_of_a_parabola() {
+        let fit = fit_vcurve(&points(|x| 2.0 + ((x - 10_350.0) / 200.0).powi(2))).unwrap();
+        assert!(
+            (fit.best_position - 10_350.0).abs() < 1e-6,
+            "{}",
+            fit.best_position
+        );
+        assert!((fit.best_hfr - 2.0).abs() < 1e-9, "{}", fit.best_hfr);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+
+        // Noise lowers the fit quality but not the minimum by much.
+        let noisy = points(|x| {
+            2.0 + ((x - 10_350.0) / 200.0).powi(2)
+                + if (x / 100.0) as i64 % 2 == 0 {
+                    0.1
+                } else {
+                    -0.1
+                }
+        });
+        let fit = fit_vcurve(&noisy).unwrap();
+        assert!(
+            (fit.best_position - 10_350.0).abs() < 30.0,
+            "{}",
+            fit.best_position
+        );
+        assert!(
+            fit.r_squared < 1.0 && fit.r_squared > 0.9,
+            "{}",
+            fit.r_squared
+        );
+
+        let plot = render_plot(&noisy, &fit);
+        assert_eq!(plot.dimensions(), (PLOT_WIDTH, PLOT_HEIGHT));
+    }
+
+    #[test]
+    fn a_curve_without_a_minimum_is_rejected() {
+        assert!(fit_vcurve(&points(|x| 10.0 - ((x - 10_400.0) / 200.0).powi(2))).is_err());
+        assert!(fit_vcurve(&points(|_| 2.0)[..2]).is_err());
+    }
+
+    #[test]
+    fn runs_split_on_gaps_and_need_enough_positions() {
+        let mut frames: Vec<(Option<i64>, f64)> = (0..6)
+            .map(|i| (Some(1_000 + 10 * i), 100.0 * i as f64))
+            .collect();
+        // Light frames at one position, after a long pause.
+        frames.extend((0..4).map(|i| (Some(5_000 + 300 * i), 500.0)));
+        // A second, later run.
+        frames.extend((0..5).map(|i| (Some(20_000 + 10 * i), 50.0 * i as f64)));
+        assert_eq!(focus_runs(&frames, 120), vec![0..6, 10..15]);
+        // With a wide gap everything merges into one run.
+        assert_eq!(focus_runs(&frames, 100_000), vec![0..15]);
+
+        assert_eq!(
+            focuser_position(r#"{"FocuserPosition":12345}"#),
+            Some(12345.0)
+        );
+        assert_eq!(
+            focuser_position(r#"{"FocuserPosition":"8000"}"#),
+            Some(8000.0)
+        );
+        assert_eq!(focuser_position(r#"{"HFR":2.1}"#), None);
+    }
+}
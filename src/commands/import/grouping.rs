@@ -4,6 +4,8 @@
 //! - Frames never share a project across different equipment signatures
 //!   (telescope, camera, focal length, binning).
 //! - Each distinct OBJECT becomes its own project and target by default.
+//!   With `--group-by-position`, frames are first renamed by sky position
+//!   (see [`group_objects_by_position`]) so one field is one target.
 //! - Targets with a shared panel-style name, nearby coordinates, and nearby
 //!   capture dates share one project as a likely mosaic.
 //! - Within a target, each distinct (filter, gain, offset, binning, readout,
@@ -13,6 +15,7 @@
 //! exactly what a real run inserts.
 
 use super::headers::FrameMeta;
+use crate::sky_grouping::{angular_distance_deg, cluster_by_position};
 use std::collections::BTreeMap;
 
 /// Equipment that must not be mixed within one project.
@@ -352,16 +355,57 @@ fn capture_ranges_near(a: &TargetGroup, b: &TargetGroup, max_gap: i64) -> bool {
     gap <= max_gap
 }
 
-fn angular_distance_deg(ra1: f64, dec1: f64, ra2: f64, dec2: f64) -> f64 {
-    let (ra1, dec1, ra2, dec2) = (
-        ra1.to_radians(),
-        dec1.to_radians(),
-        ra2.to_radians(),
-        dec2.to_radians(),
-    );
-    let cosine =
-        (dec1.sin() * dec2.sin() + dec1.cos() * dec2.cos() * (ra1 - ra2).cos()).clamp(-1.0, 1.0);
-    cosine.acos().to_degrees()
+/// Give frames of one field the same OBJECT, whatever they were called.
+/// Frames are clustered by RA/Dec within `radius_deg`; each cluster takes
+/// the name most of its frames carry (alphabetically first on a tie), or a
+/// coordinate label when none has one. Frames without coordinates keep
+/// their OBJECT.
+pub fn group_objects_by_position(frames: &mut [FrameMeta], radius_deg: f64) {
+    let positions: Vec<_> = frames
+        .iter()
+        .map(|frame| frame.ra_deg.zip(frame.dec_deg))
+        .collect();
+    let clusters = cluster_by_position(&positions, radius_deg);
+
+    let mut name_counts: BTreeMap<usize, BTreeMap<String, usize>> = BTreeMap::new();
+    let mut anchors: BTreeMap<usize, (f64, f64)> = BTreeMap::new();
+    for ((frame, cluster), position) in frames.iter().zip(&clusters).zip(&positions) {
+        let (Some(cluster), Some(position)) = (cluster, position) else {
+            continue;
+        };
+        anchors.entry(*cluster).or_insert(*position);
+        if let Some(object) = frame
+            .object
+            .as_deref()
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+        {
+            *name_counts
+                .entry(*cluster)
+                .or_default()
+                .entry(object.to_string())
+                .or_default() += 1;
+        }
+    }
+
+    for (frame, cluster) in frames.iter_mut().zip(&clusters) {
+        let Some(cluster) = cluster else {
+            continue;
+        };
+        let name = name_counts
+            .get(cluster)
+            .and_then(|counts| {
+                counts
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(name, _)| name.clone())
+            })
+            .unwrap_or_else(|| {
+                let (ra, dec) = anchors[cluster];
+                format!("Field RA {:.2}h Dec {:+.2}°", ra / 15.0, dec)
+            });
+        frame.object = Some(name);
+    }
 }
 
 fn build_target(frames: &[FrameMeta], name: String, indices: Vec<usize>) -> PlannedTarget {
@@ -460,6 +504,63 @@ mod tests {
         assert_eq!(plan.template_keys().len(), 2);
     }
 
+    #[test]
+    fn position_grouping_merges_names_of_one_field() {
+        let at = |object: Option<&str>, ts: i64, ra: f64, dec: f64| FrameMeta {
+            object: object.map(str::to_string),
+            ra_deg: Some(ra),
+            dec_deg: Some(dec),
+            ..frame("", "Ha", ts, "EdgeHD", 1960.0, 300.0)
+        };
+        let mut frames = vec![
+            at(Some("M31"), 1_000, 10.684, 41.269),
+            at(Some("M 31"), 2_000, 10.80, 41.10),
+            at(Some("M31"), 3_000, 10.70, 41.30),
+            at(Some("M42"), 4_000, 83.8, -5.4),
+            at(None, 5_000, 83.9, -5.3),
+            at(None, 6_000, 56.75, 24.12),
+        ];
+        let mut unpositioned = frame("Sequence 1", "Ha", 7_000, "EdgeHD", 1960.0, 300.0);
+        unpositioned.ra_deg = None;
+        frames.push(unpositioned);
+
+        group_objects_by_position(&mut frames, 0.5);
+        let objects: Vec<_> = frames
+            .iter()
+            .map(|frame| frame.object.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                "M31",
+                "M31",
+                "M31",
+                "M42",
+                "M42",
+                "Field RA 3.78h Dec +24.12°",
+                "Sequence 1"
+            ]
+        );
+
+        let plan = build_plan(&frames, 14.0);
+        let mut targets: Vec<_> = plan
+            .projects
+            .iter()
+            .flat_map(|project| &project.targets)
+            .map(|target| (target.name.as_str(), target.frame_count()))
+            .collect();
+        targets.sort();
+        assert_eq!(
+            targets,
+            [
+                ("Field RA 3.78h Dec +24.12°", 1),
+                ("M31", 3),
+                ("M42", 2),
+                ("Sequence 1", 1)
+            ]
+        );
+    }
+
     #[test]
     fn different_equipment_splits_projects() {
         let frames = vec![
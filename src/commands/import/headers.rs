@@ -3,7 +3,7 @@
 //! Only headers are read — never pixel data — so scanning thousands of frames
 //! stays I/O bound. Field names follow N.I.N.A.'s FITS writer.
 
-use crate::astrometry_headers::{parse_dec_deg, parse_ra_deg};
+use crate::astrometry_headers::{parse_dec_deg, parse_longitude_deg, parse_ra_deg};
 use seiza_fits::HeaderValue;
 use std::path::{Path, PathBuf};
 
@@ -42,6 +42,10 @@ pub struct FrameMeta {
     pub rotator_position: Option<f64>,
     pub pier_side: Option<String>,
     pub airmass: Option<f64>,
+    /// Star metrics recorded by the capture software's own log (see
+    /// `logs`); FITS headers never carry them.
+    pub hfr: Option<f64>,
+    pub detected_stars: Option<i64>,
 }
 
 impl FrameMeta {
@@ -50,10 +54,10 @@ impl FrameMeta {
     /// A missing IMAGETYP is treated as a light: plenty of processed archives
     /// strip it, and lights are what people point the importer at.
     pub fn is_light(&self) -> bool {
-        match &self.image_type {
-            None => true,
-            Some(t) => t.contains("LIGHT"),
-        }
+        self.image_type.as_deref().is_none_or(|t| {
+            crate::image_analysis::FrameType::from_imagetyp(t)
+                == crate::image_analysis::FrameType::Light
+        })
     }
 
     pub fn basename(&self) -> String {
@@ -134,7 +138,25 @@ pub fn read_frame_meta(path: &Path) -> FrameMeta {
     meta.focuser_temp = f64_of(&["FOCTEMP", "FOCUSTEM"]);
     meta.rotator_position = f64_of(&["ROTATANG", "ROTATOR"]);
     meta.pier_side = text(&["PIERSIDE"]);
-    meta.airmass = f64_of(&["AIRMASS"]).filter(|v| *v >= 1.0);
+    // Without an AIRMASS header, derive it from the recorded altitude or,
+    // failing that, from the pointing, capture time and observing site.
+    meta.airmass = f64_of(&["AIRMASS"]).filter(|v| *v >= 1.0).or_else(|| {
+        let altitude = f64_of(&["OBJCTALT", "CENTALT"]).or_else(|| {
+            let latitude = coordinate(&["SITELAT", "LAT-OBS", "OBSGEO-B"], parse_dec_deg)?;
+            let longitude = coordinate(
+                &["SITELONG", "SITELON", "LONG-OBS", "OBSGEO-L"],
+                parse_longitude_deg,
+            )?;
+            Some(crate::airmass::altitude_deg(
+                meta.ra_deg?,
+                meta.dec_deg?,
+                latitude,
+                longitude,
+                meta.timestamp?,
+            ))
+        });
+        altitude.and_then(crate::airmass::kasten_young)
+    });
     meta
 }
 
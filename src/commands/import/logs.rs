@@ -0,0 +1,236 @@
+//! Capture-log import: frames described by another program's per-sub log
+//! rather than found by scanning folders.
+//!
+//! A log names each sub and carries the metrics that program measured (HFR,
+//! star count), which FITS headers don't. Every logged file must still be
+//! found on disk through the directory tree: its headers supply the object,
+//! coordinates and rig that import groups by, and the log fills in what the
+//! headers lack. Files the tree can't find are reported, not imported.
+
+use super::headers::{parse_fits_datetime, read_frame_meta, FrameMeta};
+use crate::directory_tree::DirectoryTree;
+use anyhow::{bail, Result};
+
+/// Where `import` gets its frames from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// Scan the directories for FITS files (the default)
+    #[default]
+    Fits,
+    /// ASIAIR per-sub CSV (as exported by ASIAIR or rebuilt from its logs):
+    /// a header row naming at least the file column, plus any of time,
+    /// filter, HFR (or HFD) and star count
+    Asiair,
+}
+
+/// One sub as described by a capture log.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogFrame {
+    /// Basename of the FITS file.
+    pub file: String,
+    pub timestamp: Option<i64>,
+    pub filter: Option<String>,
+    pub hfr: Option<f64>,
+    pub detected_stars: Option<i64>,
+}
+
+/// Parse a capture log in `format`.
+pub fn parse_log(format: ImportFormat, text: &str) -> Result<Vec<LogFrame>> {
+    match format {
+        ImportFormat::Fits => bail!("The fits format scans directories and has no log"),
+        ImportFormat::Asiair => parse_asiair_csv(text),
+    }
+}
+
+/// Column a CSV header names, matched case-insensitively and ignoring spaces,
+/// underscores and dashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    File,
+    Time,
+    Filter,
+    Hfr,
+    Hfd,
+    Stars,
+}
+
+impl Column {
+    fn from_header(name: &str) -> Option<Self> {
+        let key: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .collect::<String>()
+            .to_lowercase();
+        match key.as_str() {
+            "file" | "filename" | "image" | "name" => Some(Self::File),
+            "time" | "date" | "datetime" | "timestamp" | "dateobs" => Some(Self::Time),
+            "filter" | "filtername" => Some(Self::Filter),
+            "hfr" => Some(Self::Hfr),
+            "hfd" => Some(Self::Hfd),
+            "stars" | "starcount" | "numstars" | "detectedstars" => Some(Self::Stars),
+            _ => None,
+        }
+    }
+}
+
+fn parse_asiair_csv(text: &str) -> Result<Vec<LogFrame>> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let Some(header) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<Option<Column>> = split_csv_line(header)
+        .iter()
+        .map(|name| Column::from_header(name))
+        .collect();
+    if !columns.contains(&Some(Column::File)) {
+        bail!("Log header has no file column: {}", header);
+    }
+
+    let mut frames = Vec::new();
+    for line in lines {
+        let mut frame = LogFrame::default();
+        for (column, value) in columns.iter().zip(split_csv_line(line)) {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match column {
+                Some(Column::File) => {
+                    frame.file = value
+                        .rsplit(['\\', '/'])
+                        .next()
+                        .unwrap_or(value)
+                        .to_string();
+                }
+                Some(Column::Time) => frame.timestamp = parse_log_time(value),
+                Some(Column::Filter) => frame.filter = Some(value.to_string()),
+                Some(Column::Hfr) => frame.hfr = value.parse().ok(),
+                // Half-flux diameter; HFR is half of it.
+                Some(Column::Hfd) if frame.hfr.is_none() => {
+                    frame.hfr = value.parse::<f64>().ok().map(|hfd| hfd / 2.0);
+                }
+                Some(Column::Stars) => frame.detected_stars = value.parse().ok(),
+                _ => {}
+            }
+        }
+        if !frame.file.is_empty() {
+            frames.push(frame);
+        }
+    }
+    Ok(frames)
+}
+
+/// Log timestamps, read as UTC: FITS/RFC 3339 forms, or the
+/// `2024/01/15 21:03:12` style ASIAIR writes.
+fn parse_log_time(value: &str) -> Option<i64> {
+    parse_fits_datetime(value).or_else(|| {
+        ["%Y-%m-%d %H:%M:%S", "%Y/%m/%d %H:%M:%S", "%Y%m%d-%H%M%S"]
+            .iter()
+            .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+            .map(|dt| dt.and_utc().timestamp())
+    })
+}
+
+/// Split one CSV line, honouring double-quoted fields (`""` escapes a quote).
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Locate each logged sub through `tree` and build its import record from
+/// the FITS headers, filling gaps (filter, time) from the log and adding the
+/// log's star metrics. Returns the frames plus the basenames not found.
+pub fn frames_from_log(log: Vec<LogFrame>, tree: &DirectoryTree) -> (Vec<FrameMeta>, Vec<String>) {
+    let mut frames = Vec::with_capacity(log.len());
+    let mut missing = Vec::new();
+    for entry in log {
+        let Some(path) = tree.find_file_first(&entry.file) else {
+            missing.push(entry.file);
+            continue;
+        };
+        let mut meta = read_frame_meta(path);
+        if meta.filter.is_none() {
+            meta.filter = entry.filter;
+        }
+        if meta.timestamp.is_none() {
+            meta.timestamp = entry.timestamp;
+        }
+        meta.hfr = entry.hfr;
+        meta.detected_stars = entry.detected_stars;
+        frames.push(meta);
+    }
+    (frames, missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asiair_csv_maps_known_columns_in_any_order() {
+        let log = "\
+# exported 2024-01-16
+Stars,HFD,Date Time,File Name,Filter,Exposure
+812,4.5,2024/01/15 21:03:12,Light_M31_300.0s_Bin1_L_0001.fit,L,300
+,,2024-01-15T21:08:20,\"C:\\Images\\Light_M31_300.0s_Bin1_L_0002.fit\",L,300
+";
+        let frames = parse_log(ImportFormat::Asiair, log).unwrap();
+        assert_eq!(
+            frames,
+            vec![
+                LogFrame {
+                    file: "Light_M31_300.0s_Bin1_L_0001.fit".to_string(),
+                    timestamp: Some(1_705_352_592),
+                    filter: Some("L".to_string()),
+                    hfr: Some(2.25),
+                    detected_stars: Some(812),
+                },
+                LogFrame {
+                    file: "Light_M31_300.0s_Bin1_L_0002.fit".to_string(),
+                    timestamp: Some(1_705_352_900),
+                    filter: Some("L".to_string()),
+                    hfr: None,
+                    detected_stars: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn hfr_column_wins_over_hfd() {
+        let frames = parse_log(ImportFormat::Asiair, "File,HFR,HFD\na.fit,1.8,5.0\n").unwrap();
+        assert_eq!(frames[0].hfr, Some(1.8));
+    }
+
+    #[test]
+    fn log_without_file_column_is_an_error() {
+        assert!(parse_log(ImportFormat::Asiair, "Time,HFR\n2024-01-15T21:03:12,2.0\n").is_err());
+        assert!(parse_log(ImportFormat::Fits, "File\na.fit\n").is_err());
+    }
+
+    #[test]
+    fn csv_quotes_protect_commas() {
+        assert_eq!(
+            split_csv_line(r#"a,"b, c","say ""hi""",d"#),
+            vec!["a", "b, c", r#"say "hi""#, "d"]
+        );
+    }
+}
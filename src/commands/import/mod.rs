@@ -16,6 +16,7 @@
 
 pub mod grouping;
 pub mod headers;
+pub mod logs;
 
 use crate::ts_schema::new_guid;
 use anyhow::{bail, Context, Result};
@@ -45,6 +46,9 @@ pub struct ImportOptions {
     /// Coordinate-match radius for attaching to an existing target, in
     /// degrees of angular separation.
     pub match_radius_deg: f64,
+    /// Group new frames into targets by sky position within this radius
+    /// (degrees) rather than by their OBJECT string. `None` groups by name.
+    pub group_by_position: Option<f64>,
 }
 
 pub const DEFAULT_MATCH_RADIUS_DEG: f64 = 0.5;
@@ -57,12 +61,13 @@ impl Default for ImportOptions {
             dry_run: false,
             attach_existing: true,
             match_radius_deg: DEFAULT_MATCH_RADIUS_DEG,
+            group_by_position: None,
         }
     }
 }
 
 /// Per-project report line.
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ProjectSummary {
     pub name: String,
     pub targets: usize,
@@ -70,7 +75,7 @@ pub struct ProjectSummary {
 }
 
 /// One existing target that received attached frames.
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct AttachSummary {
     pub project: String,
     pub target: String,
@@ -79,7 +84,7 @@ pub struct AttachSummary {
     pub matched_by: String,
 }
 
-#[derive(Debug, Default, Clone, serde::Serialize)]
+#[derive(Debug, Default, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct ImportOutcome {
     pub scanned: usize,
     pub unreadable: usize,
@@ -169,6 +174,11 @@ pub fn import_frames(
     frames: Vec<FrameMeta>,
     options: &ImportOptions,
 ) -> Result<ImportOutcome> {
+    if let Some(radius_deg) = options.group_by_position
+        && !(radius_deg.is_finite() && radius_deg > 0.0)
+    {
+        bail!("group-by-position radius must be a positive number of degrees");
+    }
     let mut outcome = ImportOutcome {
         scanned: frames.len(),
         dry_run: options.dry_run,
@@ -177,19 +187,7 @@ pub fn import_frames(
 
     let tx = conn.transaction().context("starting import transaction")?;
 
-    let existing = existing_basenames(&tx)?;
-    let mut lights: Vec<FrameMeta> = Vec::new();
-    for frame in frames {
-        if !frame.readable {
-            outcome.unreadable += 1;
-        } else if !frame.is_light() {
-            outcome.non_light += 1;
-        } else if existing.contains(&frame.basename().to_lowercase()) {
-            outcome.skipped_existing += 1;
-        } else {
-            lights.push(frame);
-        }
-    }
+    let lights = new_lights(&tx, frames, &mut outcome)?;
 
     // Merge phase: attach frames to targets that already exist (matched by
     // name, then coordinates). Only what's left falls through to the
@@ -210,25 +208,14 @@ pub fn import_frames(
             }
         }
 
-        // Deterministic order for summaries and tests.
-        let mut attach: Vec<(usize, Vec<FrameMeta>)> = attach.into_iter().collect();
-        attach.sort_by_key(|(idx, _)| *idx);
-        for (idx, frames) in attach {
-            let target = &existing_targets[idx];
-            attach_frames_to_target(&tx, target, &frames, &mut outcome)?;
-            outcome.attached += frames.len();
-            outcome.attached_target_ids.push(target.id as i32);
-            outcome.attach_summaries.push(AttachSummary {
-                project: target.project_name.clone(),
-                target: target.name.clone(),
-                frames: frames.len(),
-                matched_by: matched_by.get(&idx).copied().unwrap_or("name").to_string(),
-            });
-        }
+        attach_matched(&tx, &existing_targets, attach, &matched_by, &mut outcome)?;
     } else {
         fresh = lights;
     }
-    let lights = fresh;
+    let mut lights = fresh;
+    if let Some(radius_deg) = options.group_by_position {
+        grouping::group_objects_by_position(&mut lights, radius_deg);
+    }
 
     let plan = build_plan(&lights, options.time_gap_days);
     // Only resolve (or create) an import profile when new structure is
@@ -298,6 +285,151 @@ pub fn import_frames(
     Ok(outcome)
 }
 
+/// How the `scan` command places new frames.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Only attach to targets of this project (name, case-insensitive).
+    pub project: Option<String>,
+    /// Attach every new frame to this target (name, case-insensitive)
+    /// instead of matching each frame's OBJECT header.
+    pub target: Option<String>,
+    /// Run every insert, then roll the transaction back.
+    pub dry_run: bool,
+    /// Coordinate-match radius, as for import.
+    pub match_radius_deg: f64,
+}
+
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct ScanOutcome {
+    #[serde(flatten)]
+    pub import: ImportOutcome,
+    /// New light frames no existing target matched. Not inserted: a scan
+    /// never creates projects or targets.
+    pub unmatched: Vec<PathBuf>,
+}
+
+/// Add frames the database doesn't reference yet to its EXISTING targets —
+/// for captures that land on disk without going through the scheduler.
+/// Frames match a target by OBJECT name, then coordinates (as import's
+/// attach phase does), within `options.project` when given; with
+/// `options.target` they all go to that target. Frames nothing matches are
+/// reported, not imported. One transaction, rolled back on `dry_run`.
+pub fn scan_frames_into(
+    conn: &mut Connection,
+    frames: Vec<FrameMeta>,
+    options: &ScanOptions,
+) -> Result<ScanOutcome> {
+    let mut outcome = ScanOutcome {
+        import: ImportOutcome {
+            scanned: frames.len(),
+            dry_run: options.dry_run,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let tx = conn.transaction().context("starting scan transaction")?;
+    let lights = new_lights(&tx, frames, &mut outcome.import)?;
+
+    let named = |name: &str, wanted: &Option<String>| {
+        wanted
+            .as_deref()
+            .is_none_or(|wanted| name.trim().eq_ignore_ascii_case(wanted.trim()))
+    };
+    let mut targets = load_existing_targets(&tx)?;
+    targets.retain(|t| named(&t.project_name, &options.project));
+    if targets.is_empty()
+        && let Some(project) = &options.project
+    {
+        bail!(
+            "No project named '{}' with targets in the database",
+            project
+        );
+    }
+    targets.retain(|t| named(&t.name, &options.target));
+    if targets.is_empty()
+        && let Some(target) = &options.target
+    {
+        bail!("No target named '{}' in the selected project(s)", target);
+    }
+
+    let mut attach: HashMap<usize, Vec<FrameMeta>> = HashMap::new();
+    let mut matched_by: HashMap<usize, &'static str> = HashMap::new();
+    for frame in lights {
+        let found = match_existing_target(&frame, &targets, options.match_radius_deg);
+        let (idx, how) = match (found, &options.target) {
+            // Same-named targets (mosaic panels): keep the nearest.
+            (found, Some(_)) => (found.map_or(0, |(idx, _)| idx), "target flag"),
+            (Some(found), None) => found,
+            (None, None) => {
+                outcome.unmatched.push(frame.path);
+                continue;
+            }
+        };
+        attach.entry(idx).or_default().push(frame);
+        matched_by.entry(idx).or_insert(how);
+    }
+    attach_matched(&tx, &targets, attach, &matched_by, &mut outcome.import)?;
+
+    if options.dry_run {
+        tx.rollback().context("rolling back dry-run transaction")?;
+        outcome.import.attached_target_ids.clear();
+    } else {
+        tx.commit().context("committing scan transaction")?;
+    }
+    Ok(outcome)
+}
+
+/// Readable light frames whose basename the database doesn't have yet,
+/// counting the rest into `outcome`.
+fn new_lights(
+    conn: &Connection,
+    frames: Vec<FrameMeta>,
+    outcome: &mut ImportOutcome,
+) -> Result<Vec<FrameMeta>> {
+    let existing = existing_basenames(conn)?;
+    let mut lights: Vec<FrameMeta> = Vec::new();
+    for frame in frames {
+        if !frame.readable {
+            outcome.unreadable += 1;
+        } else if !frame.is_light() {
+            outcome.non_light += 1;
+        } else if existing.contains(&frame.basename().to_lowercase()) {
+            outcome.skipped_existing += 1;
+        } else {
+            lights.push(frame);
+        }
+    }
+    Ok(lights)
+}
+
+/// Insert matched frames into their existing targets (`attach` maps an index
+/// into `targets` to its frames) and record the attach summaries.
+fn attach_matched(
+    conn: &Connection,
+    targets: &[ExistingTarget],
+    attach: HashMap<usize, Vec<FrameMeta>>,
+    matched_by: &HashMap<usize, &'static str>,
+    outcome: &mut ImportOutcome,
+) -> Result<()> {
+    // Deterministic order for summaries and tests.
+    let mut attach: Vec<(usize, Vec<FrameMeta>)> = attach.into_iter().collect();
+    attach.sort_by_key(|(idx, _)| *idx);
+    for (idx, frames) in attach {
+        let target = &targets[idx];
+        attach_frames_to_target(conn, target, &frames, outcome)?;
+        outcome.attached += frames.len();
+        outcome.attached_target_ids.push(target.id as i32);
+        outcome.attach_summaries.push(AttachSummary {
+            project: target.project_name.clone(),
+            target: target.name.clone(),
+            frames: frames.len(),
+            matched_by: matched_by.get(&idx).copied().unwrap_or("name").to_string(),
+        });
+    }
+    Ok(())
+}
+
 /// Remove every project this importer created (recognized by the
 /// `Imported by PSF Guard` description marker) together with its rule
 /// weights, targets, exposure plans, acquired images, and their thumbnail
@@ -886,6 +1018,7 @@ fn insert_acquired_image(
 /// `ImageMetadata` DTO; keys whose values we cannot know from headers
 /// (star metrics, ADU stats, guiding RMS) are omitted entirely — readers
 /// treat missing keys as None, while zeros would read as measurements.
+/// Star metrics are written only when a capture log supplied them.
 fn frame_metadata_json(frame: &FrameMeta) -> String {
     let mut map = serde_json::Map::new();
     let mut put = |key: &str, value: serde_json::Value| {
@@ -900,6 +1033,9 @@ fn frame_metadata_json(frame: &FrameMeta) -> String {
     if let Some(filter) = &frame.filter {
         put("FilterName", filter.clone().into());
     }
+    if let Some(image_type) = &frame.image_type {
+        put("ImageType", image_type.clone().into());
+    }
     if let Some(ts) = frame.timestamp
         && let Some(dt) = chrono::Utc.timestamp_opt(ts, 0).single()
     {
@@ -948,6 +1084,12 @@ fn frame_metadata_json(frame: &FrameMeta) -> String {
     if let Some(airmass) = frame.airmass {
         put("Airmass", airmass.into());
     }
+    if let Some(hfr) = frame.hfr {
+        put("HFR", hfr.into());
+    }
+    if let Some(stars) = frame.detected_stars {
+        put("DetectedStars", stars.into());
+    }
 
     serde_json::Value::Object(map).to_string()
 }
@@ -1332,6 +1474,58 @@ mod tests {
         assert_eq!((plan_target, plan_profile.as_str()), (20, "p1"));
     }
 
+    #[test]
+    fn scan_adds_new_frames_to_existing_targets_only() {
+        let mut conn = fresh_conn();
+        seed_existing_target(&conn, "M31", 10.68, 41.27);
+        import_frames(
+            &mut conn,
+            vec![light("M31", "Ha", 1_000)],
+            &ImportOptions::default(),
+        )
+        .unwrap();
+
+        let mut stranger = light("NGC 7000", "Ha", 3_000);
+        stranger.ra_deg = Some(314.7);
+        stranger.dec_deg = Some(44.5);
+        let frames = || {
+            vec![
+                light("M31", "Ha", 1_000),
+                light("M31", "OIII", 2_000),
+                stranger.clone(),
+            ]
+        };
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM acquiredimage", [], |r| r.get(0))
+                .unwrap()
+        };
+        let mut options = ScanOptions {
+            project: None,
+            target: None,
+            dry_run: true,
+            match_radius_deg: DEFAULT_MATCH_RADIUS_DEG,
+        };
+
+        let outcome = scan_frames_into(&mut conn, frames(), &options).unwrap();
+        assert_eq!(outcome.import.skipped_existing, 1);
+        assert_eq!(outcome.import.attached, 1);
+        assert_eq!(outcome.unmatched, vec![stranger.path.clone()]);
+        assert_eq!(count(&conn), 1, "dry run rolls back");
+
+        // Naming the target takes the stranger too.
+        options.dry_run = false;
+        options.target = Some("m31".into());
+        let outcome = scan_frames_into(&mut conn, frames(), &options).unwrap();
+        assert_eq!(outcome.import.attached, 2);
+        assert!(outcome.unmatched.is_empty());
+        assert_eq!(outcome.import.attach_summaries[0].matched_by, "target flag");
+        assert_eq!(outcome.import.projects_created, 0);
+        assert_eq!(count(&conn), 3);
+
+        options.project = Some("Nope".into());
+        assert!(scan_frames_into(&mut conn, frames(), &options).is_err());
+    }
+
     #[test]
     fn attaches_by_coordinates_when_name_differs() {
         let mut conn = fresh_conn();
@@ -0,0 +1,18 @@
+use crate::db::Database;
+use anyhow::Result;
+use rusqlite::Connection;
+
+pub fn merge_targets(conn: &Connection, from: i32, into: i32, delete_source: bool) -> Result<()> {
+    let db = Database::new(conn);
+    let moved = db.merge_targets(from, into, delete_source)?;
+
+    println!(
+        "Merged target {} into target {}: {} image(s) moved",
+        from, into, moved
+    );
+    if delete_source {
+        println!("Deleted source target {}", from);
+    }
+
+    Ok(())
+}
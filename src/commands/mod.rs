@@ -1,14 +1,30 @@
+pub mod analyze_batch;
 pub mod analyze_fits;
+pub mod analyze_sequences;
+pub mod animate;
 pub mod annotate_stars;
 pub mod annotate_stars_common;
+pub mod backfill_metrics;
+pub mod background_extract;
 pub mod benchmark_psf;
+pub mod config_check;
+pub mod contact_sheet;
+pub mod curation;
+pub mod detect_trails;
 pub mod dump_grading;
 pub mod export;
+pub mod export_stars;
 pub mod filter_rejected;
+pub mod find_duplicates;
+pub mod fits_to_tiff;
+pub mod focus_vcurve;
 pub mod import;
 pub mod list_projects;
 pub mod list_targets;
+pub mod merge_targets;
+pub mod night_report;
 pub mod read_fits;
+pub mod register;
 pub mod regrade;
 pub mod reject_archive;
 pub mod screen_annotate;
@@ -17,16 +33,24 @@ pub mod show_images;
 pub mod stretch_to_png;
 pub mod sync;
 pub mod update_grade;
+pub mod verify_files;
 pub mod visualize_psf;
 pub mod visualize_psf_multi_common;
+pub mod zero_point;
 
+pub use analyze_batch::analyze_batch;
 pub use analyze_fits::analyze_fits_and_compare;
 pub use annotate_stars::annotate_stars;
+pub use background_extract::background_extract;
 pub use benchmark_psf::benchmark_psf;
+pub use config_check::config_check;
+pub use detect_trails::detect_trails;
 pub use dump_grading::dump_grading_results;
+pub use export_stars::export_stars;
 pub use filter_rejected::filter_rejected_files;
 pub use list_projects::list_projects;
 pub use list_targets::list_targets;
+pub use merge_targets::merge_targets;
 pub use read_fits::read_fits;
 pub use regrade::regrade_images;
 pub use screen_fits::screen_fits;
@@ -34,3 +58,4 @@ pub use show_images::show_images;
 pub use stretch_to_png::stretch_to_png;
 pub use update_grade::update_grade;
 pub use visualize_psf::visualize_psf_residuals;
+pub use zero_point::zero_point;
@@ -0,0 +1,431 @@
+//! End-of-night HTML report: one self-contained page per project and night
+//! with per-target summaries, quality distributions, detected issues and the
+//! flagged frames as embedded thumbnails.
+//!
+//! Scores come from the same sequence analysis as the web UI
+//! (`score_target_sequences`), run over the night's frames only; sessions
+//! never span the noon-to-noon window, so the numbers match. Thumbnails are
+//! rendered with the preview stretch and embedded as base64 PNGs, so the
+//! page has no external references and can be shared as a single file.
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, TimeZone};
+use image::codecs::png::PngEncoder;
+use image::{ColorType, GrayImage, ImageEncoder};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::db::Database;
+use crate::directory_tree::DirectoryTree;
+use crate::image_analysis::FitsImage;
+use crate::scoring::{
+    filename_from_metadata, load_target_images, score_target_sequences, ScoringCaches,
+};
+use crate::sequence_analysis::{IssueCategory, ScoredSequence, SequenceAnalyzerConfig};
+
+/// Default longest edge of the embedded thumbnails, in pixels.
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+/// The page skeleton; `{title}`, `{generated}` and `{body}` are filled in.
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body { font-family: system-ui, sans-serif; background: #111; color: #ddd; margin: 2em; }
+h1, h2, h3 { color: #fff; font-weight: 500; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { padding: 0.25em 0.75em; text-align: left; border-bottom: 1px solid #333; }
+.bar { display: inline-block; height: 0.8em; background: #4a8; vertical-align: middle; }
+.issues li { color: #e96; }
+.frames { display: flex; flex-wrap: wrap; gap: 1em; }
+figure { margin: 0; background: #1b1b1b; padding: 0.5em; }
+figcaption { font-size: 0.8em; max-width: 20em; overflow-wrap: anywhere; }
+.muted { color: #888; }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p class="muted">Generated {generated}</p>
+{body}
+</body>
+</html>
+"#;
+
+pub struct NightReportOptions {
+    pub project: String,
+    pub date: NaiveDate,
+    pub output: String,
+    /// Longest edge of the embedded thumbnails.
+    pub thumbnail_size: u32,
+    /// Directories to find the FITS files in; without them the report has no
+    /// thumbnails.
+    pub image_dirs: Vec<String>,
+    /// Per-database cache directory holding quality-scan and plate-solve
+    /// evidence (`<server cache root>/<db id>`).
+    pub cache_dir: PathBuf,
+    pub session_gap_minutes: Option<u64>,
+}
+
+/// One target's share of the night.
+struct TargetSection {
+    name: String,
+    accepted: usize,
+    rejected: usize,
+    pending: usize,
+    sequences: Vec<ScoredSequence>,
+    /// Image id → FITS basename, for captions and thumbnails.
+    filenames: HashMap<i32, String>,
+}
+
+/// The night of `date`: from local noon that day to local noon the next, so
+/// a night's frames share one date whichever side of midnight they fall on.
+pub fn night_window<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> (i64, i64) {
+    let noon = |day: NaiveDate| {
+        let naive = day.and_hms_opt(12, 0, 0).expect("noon is a valid time");
+        tz.from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.timestamp())
+            .unwrap_or_else(|| naive.and_utc().timestamp())
+    };
+    (noon(date), noon(date + chrono::Days::new(1)))
+}
+
+pub fn night_report(conn: &Connection, options: &NightReportOptions) -> Result<()> {
+    let (start, end) = night_window(options.date, &chrono::Local);
+    let mut config = SequenceAnalyzerConfig::default();
+    if let Some(gap) = options.session_gap_minutes {
+        config.session_gap_minutes = gap;
+    }
+
+    let db = Database::new(conn);
+    let project_id = db.find_project_id_by_name(&options.project)?;
+    let targets = db.get_targets_with_images(project_id)?;
+
+    let spatial = crate::server::spatial_scan::SharedSpatialStore::default();
+    crate::server::spatial_scan::ensure_loaded(&spatial, &options.cache_dir);
+    let astrometry = crate::astrometry::AstrometryEvidenceCache::new();
+    let computed = crate::computed_metrics::load_all(conn)?;
+    let caches = ScoringCaches {
+        spatial: &spatial,
+        cache_dir: &options.cache_dir,
+        astrometry: &astrometry,
+        computed: &computed,
+    };
+
+    let mut sections = Vec::new();
+    for (target, _, _, _) in &targets {
+        let (mut images, expected_by_image) = load_target_images(conn, target.id, None)?;
+        images.retain(|(image, _, _)| {
+            image
+                .acquired_date
+                .is_some_and(|time| time >= start && time < end)
+        });
+        if images.is_empty() {
+            continue;
+        }
+        let count = |status: i32| {
+            images
+                .iter()
+                .filter(|(image, _, _)| image.grading_status == status)
+                .count()
+        };
+        let (accepted, rejected) = (count(1), count(2));
+        let filenames = images
+            .iter()
+            .filter_map(|(image, _, _)| {
+                filename_from_metadata(&image.metadata).map(|name| (image.id, name))
+            })
+            .collect();
+        let sequences = score_target_sequences(
+            &images,
+            &expected_by_image,
+            target.id,
+            &target.name,
+            config.clone(),
+            &caches,
+        );
+        println!("{}: {} frame(s)", target.name, images.len());
+        sections.push(TargetSection {
+            name: target.name.clone(),
+            accepted,
+            rejected,
+            pending: images.len() - accepted - rejected,
+            sequences,
+            filenames,
+        });
+    }
+    if sections.is_empty() {
+        bail!(
+            "No frames from project '{}' on the night of {}",
+            options.project,
+            options.date
+        );
+    }
+
+    let tree = if options.image_dirs.is_empty() {
+        eprintln!("No image directories configured; the report will have no thumbnails");
+        None
+    } else {
+        let roots: Vec<&Path> = options.image_dirs.iter().map(Path::new).collect();
+        Some(
+            DirectoryTree::build_multiple(&roots)
+                .context("Failed to scan the image directories for FITS files")?,
+        )
+    };
+    let thumbnail = |filename: &str| -> Option<String> {
+        let path = tree.as_ref()?.find_file_first(filename)?;
+        match thumbnail_data_uri(path, options.thumbnail_size) {
+            Ok(uri) => Some(uri),
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                None
+            }
+        }
+    };
+
+    let title = format!("{} — night of {}", options.project, options.date);
+    let generated = chrono::Local::now().format("%Y-%m-%d %H:%M %Z").to_string();
+    let body = render_body(&sections, &thumbnail);
+    let html = TEMPLATE
+        .replace("{title}", &escape_html(&title))
+        .replace("{generated}", &escape_html(&generated))
+        .replace("{body}", &body);
+    std::fs::write(&options.output, html)
+        .with_context(|| format!("writing report {}", options.output))?;
+    println!("Wrote {}", options.output);
+    Ok(())
+}
+
+fn render_body(sections: &[TargetSection], thumbnail: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut html = String::new();
+    for section in sections {
+        let _ = write!(
+            html,
+            "<h2>{}</h2>\n<p>{} frame(s): {} accepted, {} rejected, {} pending</p>\n",
+            escape_html(&section.name),
+            section.accepted + section.rejected + section.pending,
+            section.accepted,
+            section.rejected,
+            section.pending
+        );
+
+        html.push_str(
+            "<table>\n<tr><th>Filter</th><th>Session</th><th>Frames</th>\
+             <th>Excellent</th><th>Good</th><th>Fair</th><th>Poor</th><th>Bad</th></tr>\n",
+        );
+        for sequence in &section.sequences {
+            let s = &sequence.summary;
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&sequence.filter_name),
+                session_span(sequence),
+                sequence.image_count,
+                s.excellent_count,
+                s.good_count,
+                s.fair_count,
+                s.poor_count,
+                s.bad_count
+            );
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h3>Quality scores</h3>\n<table>\n");
+        let scores: Vec<f64> = section
+            .sequences
+            .iter()
+            .flat_map(|sequence| sequence.images.iter().map(|image| image.quality_score))
+            .collect();
+        let histogram = score_histogram(&scores);
+        let tallest = histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (bin, count) in histogram.iter().enumerate().rev() {
+            let _ = writeln!(
+                html,
+                "<tr><td>{:.1}–{:.1}</td><td><span class=\"bar\" style=\"width:{}px\"></span> {}</td></tr>",
+                bin as f64 / 10.0,
+                (bin + 1) as f64 / 10.0,
+                count * 300 / tallest,
+                count
+            );
+        }
+        html.push_str("</table>\n");
+
+        let issues = sequence_issues(&section.sequences);
+        if !issues.is_empty() {
+            html.push_str("<h3>Detected issues</h3>\n<ul class=\"issues\">\n");
+            for issue in issues {
+                let _ = writeln!(html, "<li>{}</li>", escape_html(&issue));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        let flagged: Vec<_> = section
+            .sequences
+            .iter()
+            .flat_map(|sequence| sequence.images.iter())
+            .filter(|image| image.category.is_some() || !image.flags.is_empty())
+            .collect();
+        if flagged.is_empty() {
+            continue;
+        }
+        let _ = writeln!(
+            html,
+            "<h3>Flagged frames ({})</h3>\n<div class=\"frames\">",
+            flagged.len()
+        );
+        for image in flagged {
+            let filename = section.filenames.get(&image.image_id);
+            let labels: Vec<String> = image
+                .category
+                .iter()
+                .chain(image.flags.iter())
+                .map(issue_label)
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            html.push_str("<figure>");
+            if let Some(uri) = filename.and_then(|name| thumbnail(name)) {
+                let _ = write!(html, "<img src=\"{}\" alt=\"\">", uri);
+            }
+            let _ = writeln!(
+                html,
+                "<figcaption>{}<br>score {:.2} · {}</figcaption></figure>",
+                escape_html(filename.map_or("(unknown file)", String::as_str)),
+                image.quality_score,
+                escape_html(&labels.join(", "))
+            );
+        }
+        html.push_str("</div>\n");
+    }
+    html
+}
+
+/// Counts of quality scores in ten 0.1-wide bins (1.0 lands in the top bin).
+fn score_histogram(scores: &[f64]) -> [usize; 10] {
+    let mut bins = [0; 10];
+    for score in scores {
+        bins[((score.clamp(0.0, 1.0) * 10.0) as usize).min(9)] += 1;
+    }
+    bins
+}
+
+/// Sequence-level problems worth calling out, one line each.
+fn sequence_issues(sequences: &[ScoredSequence]) -> Vec<String> {
+    let mut issues = Vec::new();
+    for sequence in sequences {
+        let s = &sequence.summary;
+        let label = format!("{} {}", sequence.filter_name, session_span(sequence));
+        if s.cloud_events_detected > 0 {
+            issues.push(format!(
+                "{}: {} cloud event(s)",
+                label, s.cloud_events_detected
+            ));
+        }
+        if s.focus_drift_detected {
+            issues.push(format!("{}: focus drift", label));
+        }
+        if s.tracking_issues_detected {
+            issues.push(format!("{}: tracking problems", label));
+        }
+        if s.out_of_target_count > 0 {
+            issues.push(format!(
+                "{}: {} frame(s) off target",
+                label, s.out_of_target_count
+            ));
+        }
+        if s.plate_solve_failed_count > 0 {
+            issues.push(format!(
+                "{}: {} failed plate solve(s)",
+                label, s.plate_solve_failed_count
+            ));
+        }
+        if s.trail_count > 0 {
+            issues.push(format!("{}: {} trail(s)", label, s.trail_count));
+        }
+    }
+    issues
+}
+
+fn session_span(sequence: &ScoredSequence) -> String {
+    let time = |ts: Option<i64>| {
+        ts.and_then(|ts| chrono::Local.timestamp_opt(ts, 0).single())
+            .map(|dt| dt.format("%H:%M").to_string())
+            .unwrap_or_else(|| "?".to_string())
+    };
+    format!(
+        "{}–{}",
+        time(sequence.session_start),
+        time(sequence.session_end)
+    )
+}
+
+/// `likely_clouds` → `likely clouds`.
+fn issue_label(issue: &IssueCategory) -> String {
+    serde_json::to_value(issue)
+        .ok()
+        .and_then(|value| value.as_str().map(|s| s.replace('_', " ")))
+        .unwrap_or_else(|| format!("{:?}", issue))
+}
+
+/// Render a frame with the preview stretch and return it as a PNG data URI.
+fn thumbnail_data_uri(path: &Path, size: u32) -> Result<String> {
+    let fits = FitsImage::from_file(path)
+        .with_context(|| format!("Failed to load FITS file: {}", path.display()))?;
+    let image =
+        crate::commands::stretch_to_png::render_stretched(&fits, 0.2, -2.8, Some((size, size)))?;
+    Ok(crate::utils::png_data_uri(&encode_png(&image)?))
+}
+
+fn encode_png(image: &GrayImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(image, image.width(), image.height(), ColorType::L8.into())
+        .context("Failed to encode thumbnail")?;
+    Ok(png)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn night_runs_noon_to_noon() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let (start, end) = night_window(date, &chrono::Utc);
+        // 2024-01-15 12:00 UTC .. 2024-01-16 12:00 UTC
+        assert_eq!(start, 1_705_320_000);
+        assert_eq!(end - start, 86_400);
+
+        let west = chrono::FixedOffset::west_opt(7 * 3600).unwrap();
+        assert_eq!(night_window(date, &west).0, start + 7 * 3600);
+    }
+
+    #[test]
+    fn histogram_puts_perfect_scores_in_the_top_bin() {
+        let bins = score_histogram(&[0.0, 0.05, 0.55, 0.999, 1.0]);
+        assert_eq!(bins[0], 2);
+        assert_eq!(bins[5], 1);
+        assert_eq!(bins[9], 2);
+    }
+
+    #[test]
+    fn issue_labels_use_the_api_names() {
+        assert_eq!(issue_label(&IssueCategory::LikelyClouds), "likely clouds");
+        assert_eq!(
+            escape_html("<M 31 & \"x\">"),
+            "&lt;M 31 &amp; &quot;x&quot;&gt;"
+        );
+    }
+}
@@ -0,0 +1,484 @@
+//! Registration offsets: how far each sub of a target has drifted from a
+//! reference frame, measured by matching star patterns, as pre-stacking QA.
+//!
+//! Stars are detected in every frame and the brightest are matched to the
+//! reference's by triangle similarity: triangles whose side ratios agree
+//! vote for their vertex pairings, pairs of the best-supported pairings
+//! seed candidate similarity transforms (translation, rotation, scale), and
+//! the one most stars agree with is refined on every star it brings within
+//! [`MATCH_RADIUS`] of a reference star.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::commands::contact_sheet::target_frames;
+use crate::db::Database;
+use crate::directory_tree::DirectoryTree;
+use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
+use crate::scoring::filename_from_metadata;
+
+/// Drift, in pixels, beyond which a frame is flagged.
+pub const DEFAULT_MAX_DRIFT: f64 = 20.0;
+/// Brightest stars per frame that take part in matching.
+const MATCH_STARS: usize = 40;
+/// Of those, the brightest that form triangles (C(n, 3) per frame).
+const TRIANGLE_STARS: usize = 15;
+/// Side-ratio difference under which two triangles count as similar.
+const RATIO_TOLERANCE: f64 = 0.005;
+/// Triangles with two sides closer than this (relative to the longest) are
+/// skipped, since their vertex order is ambiguous.
+const MIN_SIDE_SEPARATION: f64 = 0.02;
+/// Largest residual, in pixels, for a star pair to count as matched.
+pub const MATCH_RADIUS: f64 = 3.0;
+/// Fewest matched stars for a registration to be trusted.
+pub const MIN_MATCHES: usize = 4;
+/// Best-voted pairings tried pairwise as transform seeds.
+const SEED_PAIRS: usize = 12;
+
+pub type Point = (f64, f64);
+
+/// `scale · R(rotation) · p + (tx, ty)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Similarity {
+    pub scale: f64,
+    /// Radians, counter-clockwise in pixel coordinates.
+    pub rotation: f64,
+    pub tx: f64,
+    pub ty: f64,
+}
+
+impl Similarity {
+    pub fn apply(&self, (x, y): Point) -> Point {
+        let (sin, cos) = self.rotation.sin_cos();
+        (
+            self.scale * (cos * x - sin * y) + self.tx,
+            self.scale * (sin * x + cos * y) + self.ty,
+        )
+    }
+}
+
+/// A frame's registration against the reference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Registration {
+    /// Maps reference pixel coordinates onto the frame's.
+    pub transform: Similarity,
+    pub matched_stars: usize,
+}
+
+impl Registration {
+    /// How far the frame's center content moved, in pixels.
+    pub fn offset(&self, width: usize, height: usize) -> Point {
+        let center = (width as f64 / 2.0, height as f64 / 2.0);
+        let moved = self.transform.apply(center);
+        (moved.0 - center.0, moved.1 - center.1)
+    }
+}
+
+/// Least-squares similarity taking each pair's first point onto its second.
+pub fn fit_similarity(pairs: &[(Point, Point)]) -> Option<Similarity> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let (mut fx, mut fy, mut tx, mut ty) = (0.0, 0.0, 0.0, 0.0);
+    for ((x, y), (u, v)) in pairs {
+        fx += x;
+        fy += y;
+        tx += u;
+        ty += v;
+    }
+    let (fx, fy, tx, ty) = (fx / n, fy / n, tx / n, ty / n);
+    let (mut dot, mut cross, mut spread) = (0.0, 0.0, 0.0);
+    for ((x, y), (u, v)) in pairs {
+        let (px, py, qx, qy) = (x - fx, y - fy, u - tx, v - ty);
+        dot += px * qx + py * qy;
+        cross += px * qy - py * qx;
+        spread += px * px + py * py;
+    }
+    if spread <= f64::EPSILON {
+        return None;
+    }
+    let rotation = cross.atan2(dot);
+    let scale = dot.hypot(cross) / spread;
+    let mut transform = Similarity {
+        scale,
+        rotation,
+        tx: 0.0,
+        ty: 0.0,
+    };
+    let (mx, my) = transform.apply((fx, fy));
+    transform.tx = tx - mx;
+    transform.ty = ty - my;
+    Some(transform)
+}
+
+/// A triangle of three star indices, vertex `k` opposite the `k`-th
+/// shortest side, with its scale- and rotation-free shape.
+struct Triangle {
+    vertices: [usize; 3],
+    /// Shortest and middle side over the longest.
+    ratios: (f64, f64),
+}
+
+fn triangles(stars: &[Point]) -> Vec<Triangle> {
+    let stars = &stars[..stars.len().min(TRIANGLE_STARS)];
+    let distance = |a: usize, b: usize| (stars[a].0 - stars[b].0).hypot(stars[a].1 - stars[b].1);
+    let mut triangles = Vec::new();
+    for i in 0..stars.len() {
+        for j in i + 1..stars.len() {
+            for k in j + 1..stars.len() {
+                let mut sides = [
+                    (distance(j, k), i),
+                    (distance(i, k), j),
+                    (distance(i, j), k),
+                ];
+                sides.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let longest = sides[2].0;
+                if longest <= f64::EPSILON
+                    || (sides[1].0 - sides[0].0) / longest < MIN_SIDE_SEPARATION
+                    || (sides[2].0 - sides[1].0) / longest < MIN_SIDE_SEPARATION
+                {
+                    continue;
+                }
+                triangles.push(Triangle {
+                    vertices: [sides[0].1, sides[1].1, sides[2].1],
+                    ratios: (sides[0].0 / longest, sides[1].0 / longest),
+                });
+            }
+        }
+    }
+    triangles.sort_by(|a, b| a.ratios.0.total_cmp(&b.ratios.0));
+    triangles
+}
+
+/// Register `frame` against `reference`; both are star positions, brightest
+/// first. `None` when fewer than [`MIN_MATCHES`] stars line up.
+pub fn register_stars(reference: &[Point], frame: &[Point]) -> Option<Registration> {
+    let reference = &reference[..reference.len().min(MATCH_STARS)];
+    let frame = &frame[..frame.len().min(MATCH_STARS)];
+
+    // Similar triangles vote for their vertex pairings.
+    let frame_triangles = triangles(frame);
+    let mut votes: HashMap<(usize, usize), u32> = HashMap::new();
+    for triangle in triangles(reference) {
+        let start =
+            frame_triangles.partition_point(|t| t.ratios.0 < triangle.ratios.0 - RATIO_TOLERANCE);
+        for candidate in frame_triangles[start..]
+            .iter()
+            .take_while(|t| t.ratios.0 <= triangle.ratios.0 + RATIO_TOLERANCE)
+            .filter(|t| (t.ratios.1 - triangle.ratios.1).abs() <= RATIO_TOLERANCE)
+        {
+            for (r, f) in triangle.vertices.iter().zip(candidate.vertices) {
+                *votes.entry((*r, f)).or_default() += 1;
+            }
+        }
+    }
+
+    // Best-supported pairings, each star used once.
+    let mut ranked: Vec<((usize, usize), u32)> = votes.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let (mut used_reference, mut used_frame) =
+        (vec![false; reference.len()], vec![false; frame.len()]);
+    let mut seeds = Vec::new();
+    for ((r, f), _) in ranked {
+        if !used_reference[r] && !used_frame[f] {
+            used_reference[r] = true;
+            used_frame[f] = true;
+            seeds.push((reference[r], frame[f]));
+            if seeds.len() == SEED_PAIRS {
+                break;
+            }
+        }
+    }
+
+    // Every two seeds fix a candidate transform; keep the one that brings
+    // the most stars together, then refine it on those stars.
+    let mut best: Option<Vec<(Point, Point)>> = None;
+    for i in 0..seeds.len() {
+        for j in i + 1..seeds.len() {
+            let Some(transform) = fit_similarity(&[seeds[i], seeds[j]]) else {
+                continue;
+            };
+            let matched = match_under(&transform, reference, frame);
+            if best.as_ref().is_none_or(|best| matched.len() > best.len()) {
+                best = Some(matched);
+            }
+        }
+    }
+    let matched = best.filter(|matched| matched.len() >= MIN_MATCHES)?;
+    let transform = fit_similarity(&matched)?;
+    let matched = match_under(&transform, reference, frame);
+    if matched.len() < MIN_MATCHES {
+        return None;
+    }
+    Some(Registration {
+        transform: fit_similarity(&matched)?,
+        matched_stars: matched.len(),
+    })
+}
+
+/// Pairs of reference and frame stars within [`MATCH_RADIUS`] of each other
+/// once the reference is mapped by `transform`; nearest first, one-to-one.
+fn match_under(
+    transform: &Similarity,
+    reference: &[Point],
+    frame: &[Point],
+) -> Vec<(Point, Point)> {
+    let mut used = vec![false; frame.len()];
+    let mut pairs = Vec::new();
+    for &star in reference {
+        let (x, y) = transform.apply(star);
+        let nearest = frame
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !used[*index])
+            .map(|(index, other)| (index, (other.0 - x).hypot(other.1 - y)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, distance)) = nearest
+            && distance <= MATCH_RADIUS
+        {
+            used[index] = true;
+            pairs.push((star, frame[index]));
+        }
+    }
+    pairs
+}
+
+pub struct RegisterOptions {
+    pub target: String,
+    /// Disambiguates a target name used in several projects.
+    pub project: Option<String>,
+    /// Only this filter's frames; all of them otherwise.
+    pub filter: Option<String>,
+    /// Image id of the frame the others are measured against.
+    pub reference: i32,
+    /// Drift in pixels beyond which a frame is flagged.
+    pub max_drift: f64,
+    /// CSV file to write; standard output otherwise.
+    pub output: Option<String>,
+    /// Tag to add to flagged frames.
+    pub tag: Option<String>,
+    /// Directories to find the FITS files in.
+    pub image_dirs: Vec<String>,
+}
+
+/// Frame size and star positions, brightest first.
+fn frame_stars(path: &Path) -> Result<((usize, usize), Vec<Point>)> {
+    let fits = crate::fits_read::load_with_retry(path)?;
+    let mut stars = detect_stars_hocus_focus(
+        &fits.data,
+        fits.width,
+        fits.height,
+        &HocusFocusParams::default(),
+    )
+    .stars;
+    stars.sort_by(|a, b| b.flux.total_cmp(&a.flux));
+    Ok((
+        (fits.width, fits.height),
+        stars
+            .iter()
+            .take(MATCH_STARS)
+            .map(|star| star.position)
+            .collect(),
+    ))
+}
+
+pub fn register(conn: &Connection, options: &RegisterOptions) -> Result<()> {
+    if options.image_dirs.is_empty() {
+        bail!("No image directories configured; pass --image-dirs");
+    }
+    let images = target_frames(
+        conn,
+        &options.target,
+        options.project.as_deref(),
+        options.filter.as_deref(),
+    )?;
+    let Some(reference) = images.iter().find(|image| image.id == options.reference) else {
+        bail!(
+            "Reference image {} is not one of the selected frames of '{}'",
+            options.reference,
+            options.target
+        );
+    };
+
+    let roots: Vec<&Path> = options.image_dirs.iter().map(Path::new).collect();
+    let tree = DirectoryTree::build_multiple(&roots)
+        .context("Failed to scan the image directories for FITS files")?;
+    let locate = |metadata: &str| {
+        filename_from_metadata(metadata).and_then(|name| tree.find_file_first(&name).cloned())
+    };
+
+    let reference_path = locate(&reference.metadata)
+        .with_context(|| format!("Reference image {}: file not found", reference.id))?;
+    let (_, reference_stars) = frame_stars(&reference_path)?;
+    if reference_stars.len() < MIN_MATCHES {
+        bail!(
+            "Only {} star(s) detected in the reference frame; pick a frame with more",
+            reference_stars.len()
+        );
+    }
+
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create CSV file: {}", path))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    writeln!(
+        out,
+        "image_id,dx,dy,rotation_deg,scale,matched_stars,drifted"
+    )?;
+
+    let mut drifted = Vec::new();
+    let (mut registered, mut failed) = (0, 0);
+    for image in &images {
+        let registration = match locate(&image.metadata) {
+            Some(path) => frame_stars(&path)
+                .map(|(size, stars)| (size, register_stars(&reference_stars, &stars))),
+            None => Err(anyhow::anyhow!("file not found")),
+        };
+        match registration {
+            Ok(((width, height), Some(registration))) => {
+                let (dx, dy) = registration.offset(width, height);
+                let over = dx.hypot(dy) > options.max_drift;
+                if over {
+                    drifted.push(image.id);
+                }
+                registered += 1;
+                writeln!(
+                    out,
+                    "{},{:.2},{:.2},{:.4},{:.5},{},{}",
+                    image.id,
+                    dx,
+                    dy,
+                    registration.transform.rotation.to_degrees(),
+                    registration.transform.scale,
+                    registration.matched_stars,
+                    over
+                )?;
+            }
+            Ok((_, None)) => {
+                failed += 1;
+                eprintln!(
+                    "Image {}: no star pattern match with the reference",
+                    image.id
+                );
+                writeln!(out, "{},,,,,0,", image.id)?;
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("Image {}: {:#}", image.id, e);
+                writeln!(out, "{},,,,,0,", image.id)?;
+            }
+        }
+    }
+    out.flush()?;
+
+    eprintln!(
+        "Registered {} of {} frame(s) against image {} ({} failed); {} drifted more than {} px",
+        registered,
+        images.len(),
+        options.reference,
+        failed,
+        drifted.len(),
+        options.max_drift
+    );
+    if let Some(tag) = &options.tag
+        && !drifted.is_empty()
+    {
+        let db = Database::new(conn);
+        let tags = [tag.clone()];
+        for id in &drifted {
+            db.add_tags(*id, &tags)?;
+        }
+        eprintln!("Tagged {} frame(s) '{}'", drifted.len(), tag);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic scatter of `n` stars over a 3000x2000 frame.
+    fn star_field(n: usize) -> Vec<Point> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 1_000_000) as f64 / 1_000_000.0
+        };
+        (0..n).map(|_| (next() * 3000.0, next() * 2000.0)).collect()
+    }
+
+    #[test]
+    fn fit_similarity_recovers_an_exact_transform() {
+        let truth = Similarity {
+            scale: 1.01,
+            rotation: 0.02,
+            tx: 14.0,
+            ty: -6.5,
+        };
+        let pairs: Vec<(Point, Point)> = star_field(5)
+            .into_iter()
+            .map(|p| (p, truth.apply(p)))
+            .collect();
+        let fit = fit_similarity(&pairs).unwrap();
+        assert!((fit.scale - truth.scale).abs() < 1e-9);
+        assert!((fit.rotation - truth.rotation).abs() < 1e-9);
+        assert!((fit.tx - truth.tx).abs() < 1e-6 && (fit.ty - truth.ty).abs() < 1e-6);
+        assert!(fit_similarity(&pairs[..1]).is_none());
+    }
+
+    #[test]
+    fn drifted_frame_registers_despite_missing_and_extra_stars() {
+        let reference = star_field(40);
+        let truth = Similarity {
+            scale: 1.0,
+            rotation: 0.3f64.to_radians(),
+            tx: 22.0,
+            ty: -9.0,
+        };
+        let mut frame: Vec<Point> = reference.iter().map(|&p| truth.apply(p)).collect();
+        // Brightness order shuffles a little, a few stars vanish under a
+        // cloud edge and a hot pixel or two show up.
+        frame.swap(0, 3);
+        frame.swap(5, 9);
+        frame.remove(7);
+        frame.remove(1);
+        frame.insert(2, (1500.0, 1000.0));
+        frame.insert(6, (10.0, 1990.0));
+
+        let registration = register_stars(&reference, &frame).unwrap();
+        assert!(
+            registration.matched_stars >= 30,
+            "{}",
+            registration.matched_stars
+        );
+        let fit = registration.transform;
+        assert!((fit.rotation - truth.rotation).abs() < 1e-6);
+        assert!((fit.tx - truth.tx).abs() < 1e-3 && (fit.ty - truth.ty).abs() < 1e-3);
+
+        let (dx, dy) = registration.offset(3000, 2000);
+        let expected = truth.apply((1500.0, 1000.0));
+        assert!((dx - (expected.0 - 1500.0)).abs() < 1e-3);
+        assert!((dy - (expected.1 - 1000.0)).abs() < 1e-3);
+
+        // The reference registers onto itself with no offset.
+        let itself = register_stars(&reference, &reference).unwrap();
+        let (dx, dy) = itself.offset(3000, 2000);
+        assert!(dx.abs() < 1e-9 && dy.abs() < 1e-9);
+    }
+
+    #[test]
+    fn unrelated_fields_do_not_register() {
+        let reference = star_field(40);
+        let unrelated: Vec<Point> = star_field(80)[40..].to_vec();
+        assert!(register_stars(&reference, &unrelated).is_none());
+    }
+}
@@ -1,18 +1,42 @@
 use crate::db::Database;
 use crate::grading;
 use crate::models::GradingStatus;
+use crate::reject_reasons::ManualMarkers;
 use anyhow::Result;
 use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-pub fn regrade_images(
-    conn: &Connection,
-    dry_run: bool,
-    target_filter: Option<String>,
-    project_filter: Option<String>,
-    days: u32,
-    reset_mode: &str,
-    stat_config: Option<grading::StatisticalGradingConfig>,
-) -> Result<()> {
+pub struct RegradeOptions {
+    pub dry_run: bool,
+    pub target: Option<String>,
+    pub project: Option<String>,
+    /// Only images acquired within this many days are regraded.
+    pub days: u32,
+    /// `none`, `automatic` (keeps manual rejections) or `all`.
+    pub reset_mode: String,
+    pub stat_config: Option<grading::StatisticalGradingConfig>,
+    /// Dry-run output: `table` or `json`.
+    pub format: String,
+    /// Leave every manually graded image untouched.
+    pub preserve_manual: bool,
+    /// Reject-reason markers that identify a manual grade.
+    pub manual_markers: ManualMarkers,
+}
+
+/// One image whose grading status a regrade would change.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegradeChange {
+    pub image_id: i32,
+    pub old_status: &'static str,
+    pub new_status: &'static str,
+    pub reason: String,
+    /// Statistical threshold that triggered a rejection; `None` for resets.
+    pub deciding_metric: Option<&'static str>,
+}
+
+pub fn regrade_images(conn: &Connection, options: &RegradeOptions) -> Result<()> {
+    let reset_mode = options.reset_mode.as_str();
     // Validate reset mode
     match reset_mode {
         "none" | "automatic" | "all" => {}
@@ -23,73 +47,57 @@ pub fn regrade_images(
             ))
         }
     }
+    if !matches!(options.format.as_str(), "table" | "json") {
+        return Err(anyhow::anyhow!(
+            "Invalid format: {}. Use 'table' or 'json'",
+            options.format
+        ));
+    }
 
     let db = Database::new(conn);
+    let dry_run = options.dry_run;
+
+    // Calculate date cutoff
+    let now = chrono::Utc::now();
+    let cutoff_date = now - chrono::Duration::days(options.days as i64);
+    let cutoff_timestamp = cutoff_date.timestamp();
+
+    if dry_run && options.format == "json" {
+        let changes = plan_regrade(&db, options, cutoff_timestamp)?;
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
 
     println!(
         "{}Regrading images...",
         if dry_run { "[DRY RUN] " } else { "" }
     );
 
-    // Calculate date cutoff
-    let now = chrono::Utc::now();
-    let cutoff_date = now - chrono::Duration::days(days as i64);
-    let cutoff_timestamp = cutoff_date.timestamp();
-
     println!("  Date range: {} to now", cutoff_date.format("%Y-%m-%d"));
+    if options.preserve_manual {
+        println!("  Preserving manually graded images");
+    }
 
     // Wrap all operations in a transaction for consistency
-    if !dry_run && (reset_mode != "none" || stat_config.is_some()) {
+    if !dry_run && (reset_mode != "none" || options.stat_config.is_some()) {
         db.with_transaction(|_tx| {
             // First, handle reset if requested
             if reset_mode != "none" {
-                handle_reset(
-                    &db,
-                    false, // Not a dry run inside transaction
-                    reset_mode,
-                    cutoff_timestamp,
-                    &project_filter,
-                    &target_filter,
-                )?;
+                handle_reset(&db, options, cutoff_timestamp)?;
             }
 
             // Now perform statistical grading if enabled
-            if let Some(config) = stat_config {
-                perform_statistical_grading(
-                    &db,
-                    false, // Not a dry run inside transaction
-                    cutoff_timestamp,
-                    &project_filter,
-                    &target_filter,
-                    config,
-                )?;
+            if let Some(config) = &options.stat_config {
+                perform_statistical_grading(&db, options, cutoff_timestamp, config.clone())?;
             }
 
             Ok(())
         })?;
     } else if dry_run {
-        // For dry runs, execute without transaction
-        if reset_mode != "none" {
-            handle_reset(
-                &db,
-                dry_run,
-                reset_mode,
-                cutoff_timestamp,
-                &project_filter,
-                &target_filter,
-            )?;
-        }
-
-        if let Some(config) = stat_config {
-            perform_statistical_grading(
-                &db,
-                dry_run,
-                cutoff_timestamp,
-                &project_filter,
-                &target_filter,
-                config,
-            )?;
-        }
+        // Plan the same reset and grading without touching the database
+        println!("  Reset mode: {}", reset_mode);
+        let changes = plan_regrade(&db, options, cutoff_timestamp)?;
+        print_changes(&changes);
     }
 
     println!("\nRegrading complete.");
@@ -101,58 +109,94 @@ pub fn regrade_images(
     Ok(())
 }
 
-fn handle_reset(
-    db: &Database,
-    dry_run: bool,
-    reset_mode: &str,
-    cutoff_timestamp: i64,
-    project_filter: &Option<String>,
-    target_filter: &Option<String>,
-) -> Result<()> {
-    println!("  Reset mode: {}", reset_mode);
+fn handle_reset(db: &Database, options: &RegradeOptions, cutoff_timestamp: i64) -> Result<()> {
+    println!("  Reset mode: {}", options.reset_mode);
 
-    if dry_run {
-        let count = db.count_images_to_reset(
-            reset_mode,
-            cutoff_timestamp,
-            project_filter.as_deref(),
-            target_filter.as_deref(),
-        )?;
-        println!("  Would reset {} images to pending status", count);
-    } else {
-        let affected = db.reset_grading_status(
-            reset_mode,
-            cutoff_timestamp,
-            project_filter.as_deref(),
-            target_filter.as_deref(),
-        )?;
-        println!("  Reset {} images to pending status", affected);
-    }
+    let affected = db.reset_grading_status(
+        &options.reset_mode,
+        cutoff_timestamp,
+        options.project.as_deref(),
+        options.target.as_deref(),
+        &options.manual_markers,
+        options.preserve_manual,
+    )?;
+    println!("  Reset {} images to pending status", affected);
 
     Ok(())
 }
 
 fn perform_statistical_grading(
     db: &Database,
-    dry_run: bool,
+    options: &RegradeOptions,
     cutoff_timestamp: i64,
-    project_filter: &Option<String>,
-    target_filter: &Option<String>,
     config: grading::StatisticalGradingConfig,
 ) -> Result<()> {
     println!("\nPerforming statistical analysis...");
 
-    // Get all images in date range
-    let all_images = db.query_images(
+    let (image_stats, manual) = load_image_statistics(db, options, cutoff_timestamp)?;
+    println!("  Analyzing {} images", image_stats.len());
+
+    // Run statistical analysis
+    let grader = grading::StatisticalGrader::new(config);
+    match grader.analyze_images(image_stats) {
+        Ok(mut rejections) => {
+            println!("  Found {} statistical rejections", rejections.len());
+            if options.preserve_manual {
+                let before = rejections.len();
+                rejections.retain(|r| !manual.contains(&r.image_id));
+                println!(
+                    "  Skipped {} manually graded images",
+                    before - rejections.len()
+                );
+            }
+
+            // Build updates list
+            let updates: Vec<(i32, GradingStatus, Option<String>)> = rejections
+                .iter()
+                .map(|r| {
+                    (
+                        r.image_id,
+                        GradingStatus::Rejected,
+                        Some(auto_reject_reason(r)),
+                    )
+                })
+                .collect();
+
+            // Apply updates
+            db.batch_update_grading_status(&updates)?;
+            println!("  Applied {} rejections", updates.len());
+        }
+        Err(e) => println!("  Warning: Statistical analysis failed: {}", e),
+    }
+
+    Ok(())
+}
+
+/// Grading statistics for every image in the regrade window, plus the ids of
+/// those carrying a manual reject reason. Images whose metadata cannot be
+/// parsed are skipped with a warning.
+fn load_image_statistics(
+    db: &Database,
+    options: &RegradeOptions,
+    cutoff_timestamp: i64,
+) -> Result<(Vec<grading::ImageStatistics>, HashSet<i32>)> {
+    let all_images = db.query_light_images(
         None,
-        project_filter.as_deref(),
-        target_filter.as_deref(),
+        options.project.as_deref(),
+        options.target.as_deref(),
         Some(cutoff_timestamp),
     )?;
 
-    println!("  Analyzing {} images", all_images.len());
+    let manual = all_images
+        .iter()
+        .filter(|(image, _, _)| {
+            options
+                .manual_markers
+                .matches(image.reject_reason.as_deref())
+        })
+        .map(|(image, _, _)| image.id)
+        .collect();
 
-    // Convert to format expected by grader
     let mut image_stats = Vec::new();
     for (image, _project_name, target_name) in &all_images {
         match grading::parse_image_metadata(
@@ -164,47 +208,293 @@ fn perform_statistical_grading(
             image.grading_status,
         ) {
             Ok(stats) => image_stats.push(stats),
-            Err(e) => println!(
+            Err(e) => eprintln!(
                 "  Warning: Failed to parse metadata for image {}: {}",
                 image.id, e
             ),
         }
     }
+    Ok((image_stats, manual))
+}
 
-    // Run statistical analysis
-    let grader = grading::StatisticalGrader::new(config);
-    match grader.analyze_images(image_stats) {
-        Ok(rejections) => {
-            println!("  Found {} statistical rejections", rejections.len());
+fn auto_reject_reason(rejection: &grading::StatisticalRejection) -> String {
+    format!("[Auto] {} - {}", rejection.reason, rejection.details)
+}
+
+/// Work out what a regrade would do, without writing anything: the reset
+/// runs first, then statistical rejections override it, exactly as in a real
+/// run. Only images whose status would actually change are returned, ordered
+/// by image id. With `preserve_manual`, manually graded images never change.
+pub fn plan_regrade(
+    db: &Database,
+    options: &RegradeOptions,
+    cutoff_timestamp: i64,
+) -> Result<Vec<RegradeChange>> {
+    let reset_mode = options.reset_mode.as_str();
+    let mut changes: BTreeMap<i32, RegradeChange> = BTreeMap::new();
+
+    if reset_mode != "none" {
+        for (image_id, status) in db.list_images_to_reset(
+            reset_mode,
+            cutoff_timestamp,
+            options.project.as_deref(),
+            options.target.as_deref(),
+            &options.manual_markers,
+            options.preserve_manual,
+        )? {
+            changes.insert(
+                image_id,
+                RegradeChange {
+                    image_id,
+                    old_status: GradingStatus::lowercase_name(status),
+                    new_status: "pending",
+                    reason: format!("Reset ({} mode)", reset_mode),
+                    deciding_metric: None,
+                },
+            );
+        }
+    }
 
-            if dry_run {
-                // Just show what would be rejected
-                for rejection in &rejections {
-                    println!(
-                        "    Would reject image {}: {} - {}",
-                        rejection.image_id, rejection.reason, rejection.details
-                    );
-                }
-            } else {
-                // Build updates list
-                let updates: Vec<(i32, GradingStatus, Option<String>)> = rejections
-                    .iter()
-                    .map(|r| {
-                        (
-                            r.image_id,
-                            GradingStatus::Rejected,
-                            Some(format!("[Auto] {} - {}", r.reason, r.details)),
-                        )
-                    })
-                    .collect();
-
-                // Apply updates
-                db.batch_update_grading_status(&updates)?;
-                println!("  Applied {} rejections", updates.len());
+    if let Some(config) = &options.stat_config {
+        let (image_stats, manual) = load_image_statistics(db, options, cutoff_timestamp)?;
+        let old_status: HashMap<i32, i32> = image_stats
+            .iter()
+            .map(|stats| (stats.id, stats.original_status))
+            .collect();
+        let rejections =
+            grading::StatisticalGrader::new(config.clone()).analyze_images(image_stats)?;
+        for rejection in rejections {
+            if options.preserve_manual && manual.contains(&rejection.image_id) {
+                continue;
             }
+            changes.insert(
+                rejection.image_id,
+                RegradeChange {
+                    image_id: rejection.image_id,
+                    old_status: GradingStatus::lowercase_name(old_status[&rejection.image_id]),
+                    new_status: "rejected",
+                    reason: auto_reject_reason(&rejection),
+                    deciding_metric: Some(rejection.threshold),
+                },
+            );
         }
-        Err(e) => println!("  Warning: Statistical analysis failed: {}", e),
     }
 
-    Ok(())
+    Ok(changes
+        .into_values()
+        .filter(|change| change.old_status != change.new_status)
+        .collect())
+}
+
+fn print_changes(changes: &[RegradeChange]) {
+    println!("\n  Would change {} images:", changes.len());
+    if changes.is_empty() {
+        return;
+    }
+    println!(
+        "  {:>8}  {:<9} {:<9} {:<28} Reason",
+        "Image", "Old", "New", "Deciding metric"
+    );
+    for change in changes {
+        println!(
+            "  {:>8}  {:<9} {:<9} {:<28} {}",
+            change.image_id,
+            change.old_status,
+            change.new_status,
+            change.deciding_metric.unwrap_or("-"),
+            change.reason
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    /// `images`: (Id, filtername, HFR, gradingStatus, rejectreason).
+    fn setup_db(images: &[(i32, &str, f64, i32, Option<&str>)]) -> Connection {
+        let conn = crate::db::test_schema::in_memory();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'p', 'M31', NULL);
+             INSERT INTO target VALUES (1, 'M31', 1, 0.7, 41.2, 1);",
+        )
+        .unwrap();
+        // Inside regrade's look-back window.
+        let recent = chrono::Utc::now().timestamp() - 3600;
+        for (id, filter, hfr, status, reason) in images {
+            let metadata = serde_json::json!({
+                "FileName": format!("frame{}.fits", id),
+                "FilterName": filter,
+                "HFR": hfr,
+                "ExposureStartTime": format!("2024-01-15T22:{:02}:00", id),
+            })
+            .to_string();
+            conn.execute(
+                "INSERT INTO acquiredimage VALUES (?, 1, 1, ?, ?, ?, ?, ?, 'p')",
+                params![id, recent + id as i64, filter, status, metadata, reason],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn hfr_only() -> grading::StatisticalGradingConfig {
+        grading::StatisticalGradingConfig {
+            enable_star_count_analysis: false,
+            enable_distribution_analysis: false,
+            enable_cloud_detection: false,
+            ..grading::StatisticalGradingConfig::default()
+        }
+    }
+
+    fn options(
+        reset_mode: &str,
+        stat_config: Option<grading::StatisticalGradingConfig>,
+        preserve_manual: bool,
+    ) -> RegradeOptions {
+        RegradeOptions {
+            dry_run: false,
+            target: None,
+            project: None,
+            days: 90,
+            reset_mode: reset_mode.to_string(),
+            stat_config,
+            format: "table".to_string(),
+            preserve_manual,
+            manual_markers: ManualMarkers::default(),
+        }
+    }
+
+    #[test]
+    fn plan_lists_resets_and_rejections_without_writing() {
+        // Nine steady Ha frames and one soft one; three OIII frames that
+        // include an automatic and a manual rejection.
+        let mut images: Vec<(i32, &str, f64, i32, Option<&str>)> =
+            (1..=9).map(|id| (id, "Ha", 2.0, 1, None)).collect();
+        images.push((10, "Ha", 6.0, 1, None));
+        images.push((11, "OIII", 2.5, 2, Some("[Auto] Statistical HFR - old")));
+        images.push((12, "OIII", 2.5, 2, Some("Manual: trailing")));
+        images.push((13, "OIII", 2.5, 0, None));
+        let conn = setup_db(&images);
+        let db = Database::new(&conn);
+
+        let changes = plan_regrade(&db, &options("automatic", Some(hfr_only()), false), 0).unwrap();
+        let by_id: HashMap<i32, &RegradeChange> = changes
+            .iter()
+            .map(|change| (change.image_id, change))
+            .collect();
+
+        let soft = by_id[&10];
+        assert_eq!((soft.old_status, soft.new_status), ("accepted", "rejected"));
+        assert_eq!(soft.deciding_metric, Some("hfr_stddev_threshold"));
+        assert!(soft.reason.starts_with("[Auto] Statistical HFR"));
+
+        let auto = by_id[&11];
+        assert_eq!((auto.old_status, auto.new_status), ("rejected", "pending"));
+        assert_eq!(auto.deciding_metric, None);
+        assert_eq!(by_id[&1].new_status, "pending");
+        // Manual rejections survive an automatic reset; pending stays pending.
+        assert!(!by_id.contains_key(&12));
+        assert!(!by_id.contains_key(&13));
+
+        let status: i32 = conn
+            .query_row(
+                "SELECT gradingStatus FROM acquiredimage WHERE Id = 11",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, 2);
+    }
+
+    fn status_of(conn: &Connection, id: i32) -> (i32, Option<String>) {
+        conn.query_row(
+            "SELECT gradingStatus, rejectreason FROM acquiredimage WHERE Id = ?",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn preserve_manual_keeps_manual_grades_through_a_full_regrade() {
+        // Ten Ha frames: two soft ones, one of them manually accepted. OIII
+        // holds an automatic and a manual rejection.
+        let mut images: Vec<(i32, &str, f64, i32, Option<&str>)> =
+            (1..=8).map(|id| (id, "Ha", 2.0, 1, None)).collect();
+        images.push((9, "Ha", 6.0, 1, Some("Manual accept: fine for luminance")));
+        images.push((10, "Ha", 6.0, 1, None));
+        images.push((11, "OIII", 2.5, 2, Some("[Auto] Statistical HFR - old")));
+        images.push((12, "OIII", 2.5, 2, Some("Manual: trailing")));
+        images.push((13, "OIII", 2.5, 0, None));
+        let conn = setup_db(&images);
+        let config = grading::StatisticalGradingConfig {
+            hfr_stddev_threshold: 1.0,
+            ..hfr_only()
+        };
+        let options = options("all", Some(config), true);
+
+        let db = Database::new(&conn);
+        let planned: Vec<i32> = plan_regrade(&db, &options, 0)
+            .unwrap()
+            .iter()
+            .map(|change| change.image_id)
+            .collect();
+        assert!(!planned.contains(&9));
+        assert!(!planned.contains(&12));
+        assert!(planned.contains(&10));
+        assert!(planned.contains(&11));
+
+        regrade_images(&conn, &options).unwrap();
+
+        // Manual grades are untouched, even by an "all" reset.
+        assert_eq!(
+            status_of(&conn, 9),
+            (1, Some("Manual accept: fine for luminance".to_string()))
+        );
+        assert_eq!(
+            status_of(&conn, 12),
+            (2, Some("Manual: trailing".to_string()))
+        );
+        // Automatic grades are redone.
+        assert_eq!(status_of(&conn, 11), (0, None));
+        let (status, reason) = status_of(&conn, 10);
+        assert_eq!(status, 2);
+        assert!(reason.unwrap().starts_with("[Auto] Statistical HFR"));
+        assert_eq!(status_of(&conn, 1), (0, None));
+    }
+
+    #[test]
+    fn custom_manual_markers_replace_the_default() {
+        let conn = setup_db(&[
+            (1, "Ha", 2.0, 2, Some("Rejected BY HAND: satellite")),
+            (2, "Ha", 2.0, 2, Some("Manual: trailing")),
+            (3, "Ha", 2.0, 2, Some("[Auto] Clouds")),
+            (4, "Ha", 2.0, 1, Some("kept by hand")),
+        ]);
+        let markers = ManualMarkers::new(&["by hand".to_string()]).unwrap();
+        let db = Database::new(&conn);
+
+        // Automatic mode keeps only rejections carrying a configured marker.
+        let to_reset = db
+            .list_images_to_reset("automatic", 0, None, None, &markers, false)
+            .unwrap();
+        assert_eq!(to_reset, vec![(2, 2), (3, 2), (4, 1)]);
+        assert_eq!(
+            db.count_images_to_reset("automatic", 0, None, None, &markers, false)
+                .unwrap(),
+            3
+        );
+
+        let options = RegradeOptions {
+            manual_markers: markers,
+            ..options("all", None, true)
+        };
+        regrade_images(&conn, &options).unwrap();
+        assert_eq!(status_of(&conn, 1).0, 2);
+        assert_eq!(status_of(&conn, 2), (0, None));
+        assert_eq!(status_of(&conn, 3), (0, None));
+        assert_eq!(status_of(&conn, 4).0, 1);
+    }
 }
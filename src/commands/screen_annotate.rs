@@ -297,7 +297,7 @@ fn glyph(ch: char) -> [u8; 7] {
 }
 
 /// Draw ASCII text with the built-in font at integer `scale`.
-fn draw_text(
+pub(crate) fn draw_text(
     img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>,
     x: u32,
     y: u32,
@@ -345,6 +345,7 @@ mod tests {
             raw_min: 0.0,
             raw_scale: 1.0,
             bzero: 0.0,
+            header: Default::default(),
         };
         let mut star_cells = vec![100.0; 48];
         star_cells[0] = 0.0; // dead cell -> red tint
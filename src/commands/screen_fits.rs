@@ -9,10 +9,14 @@
 //! ADU. Frames are grouped by (filter, exposure) from FITS headers, ordered
 //! by DATE-OBS, and run through the `SequenceAnalyzer` so both absolute
 //! (spatial) and sequence-relative (temporal) signals contribute. Prints a
-//! per-frame verdict: OK / WARN / REJECT.
+//! per-frame verdict: OK / WARN / REJECT. With `--detect-trails`, frames are
+//! also searched for long satellite/airplane trails, which are rejected.
+//! Frames whose histogram is clipped at 0 ADU or at full scale past the
+//! `--low-clip-fraction` / `--high-clip-fraction` limits are rejected as
+//! under- or over-exposed.
 
 use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
-use crate::image_analysis::FitsImage;
+use crate::image_analysis::{ExposureClipping, ExposureThresholds, FitsImage, FrameType};
 use crate::nina_star_detection::{
     detect_stars_with_original, NoiseReduction, StarDetectionParams, StarSensitivity,
 };
@@ -22,8 +26,10 @@ use crate::photometry::{
 };
 use crate::sequence_analysis::{
     AstrometryFrameMetrics, ImageMetrics, IssueCategory, SequenceAnalyzer, SequenceAnalyzerConfig,
+    TrailFrameMetrics,
 };
 use crate::spatial_analysis::{compute_spatial_metrics, PixelCalibration, SpatialAnalysisConfig};
+use crate::trail_detection::{detect_trails, TrailConfig};
 use anyhow::Result;
 use seiza_stretch::{stretch_u16_to_u16, StretchParams};
 use std::collections::{BTreeMap, HashMap};
@@ -41,6 +47,14 @@ pub struct ScreenOptions {
     pub format: String,
     pub min_score: f64,
     pub dead_cell_rise: f64,
+    /// Temporal-anomaly score above which a frame is rejected regardless of
+    /// its quality score.
+    pub temporal_anomaly_reject: Option<f64>,
+    /// Star count below which a frame fails as likely clouded out.
+    pub min_stars: Option<usize>,
+    /// Clip fractions past which a frame is rejected as under- or
+    /// over-exposed.
+    pub exposure: ExposureThresholds,
     pub threads: Option<usize>,
     pub session_gap_minutes: u64,
     /// Registry slug or path of a scheduler DB to write `[Auto]` rejections
@@ -55,6 +69,8 @@ pub struct ScreenOptions {
     pub cache_dir: String,
     /// Directory to write annotated diagnostic PNGs for WARN/REJECT frames.
     pub annotate_dir: Option<String>,
+    /// Run pixel-level satellite/airplane trail detection on every frame.
+    pub detect_trails: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -81,6 +97,8 @@ struct FrameRecord {
     bg_glow_cells: Vec<bool>,
     astrometry: Option<AstrometryFrameMetrics>,
     satellite: Option<crate::sequence_analysis::SatelliteFrameMetrics>,
+    trails: Option<crate::sequence_analysis::TrailFrameMetrics>,
+    exposure_clipping: Option<ExposureClipping>,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize)]
@@ -128,12 +146,17 @@ struct ScreenResult {
     flags: Vec<IssueCategory>,
     pointing: Option<crate::sequence_analysis::PointingQuality>,
     satellite: Option<crate::sequence_analysis::SatelliteFrameMetrics>,
+    trails: Option<crate::sequence_analysis::TrailFrameMetrics>,
     regrade_reason: Option<String>,
     details: Option<String>,
     verdict: Verdict,
 }
 
 pub fn screen_fits(path: &str, options: &ScreenOptions) -> Result<()> {
+    options.exposure.validate().map_err(anyhow::Error::msg)?;
+    if options.min_stars == Some(0) {
+        anyhow::bail!("--min-stars must be at least 1");
+    }
     let dir = Path::new(path);
     let files = collect_fits_files(dir)?;
     if files.is_empty() {
@@ -142,6 +165,21 @@ pub fn screen_fits(path: &str, options: &ScreenOptions) -> Result<()> {
             dir.display()
         ));
     }
+    // Darks, flats and bias frames would grade as starless disasters and
+    // skew every sequence baseline they share a filter with.
+    let (files, calibration) = partition_lights(files);
+    if calibration > 0 {
+        eprintln!(
+            "Skipping {} calibration frame(s) (IMAGETYP dark/flat/bias)",
+            calibration
+        );
+    }
+    if files.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No light frames found under: {}",
+            dir.display()
+        ));
+    }
     eprintln!("Screening {} FITS frames...", files.len());
 
     let mut records = analyze_frames(&files, options)?;
@@ -606,6 +644,15 @@ fn collect_fits_files(dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
+/// Split out calibration frames by their IMAGETYP header, returning the
+/// frames to screen and how many were dropped.
+fn partition_lights(files: Vec<PathBuf>) -> (Vec<PathBuf>, usize) {
+    let (lights, calibration): (Vec<PathBuf>, Vec<PathBuf>) = files
+        .into_iter()
+        .partition(|path| !extract_headers(path).frame_type.is_calibration());
+    (lights, calibration.len())
+}
+
 /// Analyze all frames, in parallel worker threads.
 fn analyze_frames(files: &[PathBuf], options: &ScreenOptions) -> Result<Vec<FrameRecord>> {
     // Default to all cores (bounded by available memory); `--threads`
@@ -679,6 +726,11 @@ fn analyze_one_frame(path: &Path, options: &ScreenOptions) -> Result<FrameRecord
         &SpatialAnalysisConfig::default(),
     );
 
+    let trails = options.detect_trails.then(|| {
+        let detection = detect_trails(&fits, &TrailConfig::default());
+        TrailFrameMetrics::from(&detection)
+    });
+
     Ok(FrameRecord {
         path: path.to_path_buf(),
         filter: headers.filter.unwrap_or_else(|| "unknown".to_string()),
@@ -700,6 +752,8 @@ fn analyze_one_frame(path: &Path, options: &ScreenOptions) -> Result<FrameRecord
         bg_glow_cells: spatial.bg_glow_cells,
         astrometry: None,
         satellite: None,
+        trails,
+        exposure_clipping: fits.exposure_clipping(),
     })
 }
 
@@ -772,11 +826,23 @@ fn detect_stars(
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct FrameHeaders {
     pub(crate) filter: Option<String>,
     pub(crate) exposure_s: Option<f64>,
     pub(crate) timestamp: Option<i64>,
+    pub(crate) frame_type: FrameType,
+}
+
+impl Default for FrameHeaders {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            exposure_s: None,
+            timestamp: None,
+            frame_type: FrameType::Light,
+        }
+    }
 }
 
 /// Extract filter, exposure and observation time from the FITS header.
@@ -797,6 +863,9 @@ pub(crate) fn extract_headers(path: &Path) -> FrameHeaders {
     out.timestamp = find(&["DATE-OBS", "DATE-LOC"])
         .and_then(|v| v.as_str())
         .and_then(parse_fits_datetime);
+    if let Some(image_type) = find(&["IMAGETYP", "FRAME"]).and_then(|v| v.as_str()) {
+        out.frame_type = FrameType::from_imagetyp(image_type);
+    }
     out
 }
 
@@ -826,6 +895,9 @@ fn score_records(
     let config = SequenceAnalyzerConfig {
         session_gap_minutes: options.session_gap_minutes,
         dead_cell_rise_threshold: options.dead_cell_rise,
+        temporal_anomaly_reject_threshold: options.temporal_anomaly_reject,
+        min_stars: options.min_stars,
+        exposure_thresholds: Some(options.exposure),
         ..Default::default()
     };
     let analyzer = SequenceAnalyzer::new(config.clone());
@@ -874,6 +946,7 @@ fn score_records(
                 flags: Vec::new(),
                 pointing: None,
                 satellite: None,
+                trails: r.trails.clone(),
                 regrade_reason: None,
                 details: None,
                 verdict: Verdict::Ok,
@@ -936,6 +1009,8 @@ fn score_records(
                     bg_glow_max: (r.bg_glow_max > 0.0).then_some(r.bg_glow_max),
                     astrometry: r.astrometry.clone(),
                     satellite: r.satellite.clone(),
+                    trails: r.trails.clone(),
+                    exposure_clipping: r.exposure_clipping,
                 }
             })
             .collect();
@@ -1008,6 +1083,8 @@ fn category_label(category: &Option<IssueCategory>) -> &'static str {
         Some(IssueCategory::PointingDrift) => "pointing-drift",
         Some(IssueCategory::PlateSolveFailed) => "unsolved",
         Some(IssueCategory::SatelliteTrailRisk) => "satellite-risk",
+        Some(IssueCategory::SatelliteTrail) => "trail",
+        Some(IssueCategory::BadExposure) => "exposure",
         Some(IssueCategory::UnknownDegradation) => "unknown",
         None => "-",
     }
@@ -1178,6 +1255,51 @@ mod tests {
         assert!(parse_fits_datetime("garbage").is_none());
     }
 
+    /// Header-only FITS file with the given IMAGETYP (none when `None`).
+    fn write_frame(path: &Path, image_type: Option<&str>) {
+        let mut cards = vec![
+            "SIMPLE  =                    T".to_string(),
+            "BITPIX  =                   16".to_string(),
+            "NAXIS   =                    0".to_string(),
+        ];
+        if let Some(image_type) = image_type {
+            cards.push(format!("IMAGETYP= '{}'", image_type));
+        }
+        cards.push("END".to_string());
+        let mut bytes = Vec::new();
+        for card in cards {
+            let mut card = card.into_bytes();
+            card.resize(80, b' ');
+            bytes.extend_from_slice(&card);
+        }
+        bytes.resize(2880, b' ');
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn only_light_frames_are_screened() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, image_type) in [
+            ("light.fits", Some("LIGHT")),
+            ("untyped.fits", None),
+            ("dark.fits", Some("DARK")),
+            ("flat.fits", Some("FLAT")),
+            ("bias.fits", Some("BIAS")),
+            ("darkflat.fits", Some("DARKFLAT")),
+        ] {
+            write_frame(&dir.path().join(name), image_type);
+        }
+
+        let files = collect_fits_files(dir.path()).unwrap();
+        let (lights, calibration) = partition_lights(files);
+        let names: Vec<_> = lights
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["light.fits", "untyped.fits"]);
+        assert_eq!(calibration, 4);
+    }
+
     #[test]
     fn truncate_name_is_utf8_safe() {
         // Regression (code review): multi-byte characters at the cut point
@@ -1199,6 +1321,9 @@ mod tests {
             format: "table".into(),
             min_score: 0.35,
             dead_cell_rise: 0.08,
+            temporal_anomaly_reject: None,
+            min_stars: None,
+            exposure: ExposureThresholds::default(),
             threads: None,
             session_gap_minutes: 60,
             regrade_db: None,
@@ -1206,6 +1331,7 @@ mod tests {
             registry: None,
             cache_dir: "./cache".into(),
             annotate_dir: None,
+            detect_trails: false,
         };
         assert_eq!(verdict_for(&0.9, &None, None, &options), Verdict::Ok);
         assert_eq!(verdict_for(&0.2, &None, None, &options), Verdict::Reject);
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use image::codecs::png::{CompressionType, FilterType, PngEncoder};
 use image::{ColorType, ImageEncoder};
-use image::{ImageBuffer, Luma};
+use image::{GrayImage, ImageBuffer, Luma};
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
@@ -40,7 +40,7 @@ pub fn stretch_to_png_with_resize(
     let fits_path = Path::new(fits_path);
     println!("Loading FITS file: {}", fits_path.display());
 
-    let image = FitsImage::from_file(fits_path)
+    let image = crate::fits_read::load_with_retry(fits_path)
         .with_context(|| format!("Failed to load FITS file: {}", fits_path.display()))?;
 
     println!("Image dimensions: {}x{}", image.width, image.height);
@@ -81,39 +81,80 @@ pub fn stretch_to_png_with_resize(
     )
     .context("Failed to create image buffer")?;
 
-    // Resize if requested
-    let final_buffer = if let Some((max_width, max_height)) = max_dimensions {
-        let (orig_width, orig_height) = (img_buffer.width(), img_buffer.height());
-
-        // Calculate scaling to fit within max dimensions while preserving aspect ratio
-        let scale_x = max_width as f32 / orig_width as f32;
-        let scale_y = max_height as f32 / orig_height as f32;
-        let scale = scale_x.min(scale_y).min(1.0); // Don't upscale
-
-        if scale < 1.0 {
-            let new_width = (orig_width as f32 * scale) as u32;
-            let new_height = (orig_height as f32 * scale) as u32;
-
-            println!(
-                "Resizing from {}x{} to {}x{}",
-                orig_width, orig_height, new_width, new_height
-            );
-
-            image::imageops::resize(
-                &img_buffer,
-                new_width,
-                new_height,
-                image::imageops::FilterType::Lanczos3,
-            )
-        } else {
-            img_buffer
-        }
+    let final_buffer = fit_within(img_buffer, max_dimensions);
+    write_gray_png(&final_buffer, &output_path)?;
+
+    println!("Saved stretched image to: {}", output_path.display());
+    Ok(())
+}
+
+/// MTF-stretch a frame into an 8-bit grayscale image, downscaled to fit
+/// `max_dimensions`. The in-memory half of [`stretch_to_png_with_resize`],
+/// for callers that composite the result before writing it.
+pub fn render_stretched(
+    image: &FitsImage,
+    midtone_factor: f64,
+    shadow_clipping: f64,
+    max_dimensions: Option<(u32, u32)>,
+) -> Result<GrayImage> {
+    render_preview(
+        image,
+        midtone_factor,
+        shadow_clipping,
+        false,
+        false,
+        max_dimensions,
+    )
+}
+
+/// [`render_stretched`] with the preview endpoint's logarithmic and invert
+/// options.
+pub fn render_preview(
+    image: &FitsImage,
+    midtone_factor: f64,
+    shadow_clipping: f64,
+    logarithmic: bool,
+    invert: bool,
+    max_dimensions: Option<(u32, u32)>,
+) -> Result<GrayImage> {
+    let processed_data = if logarithmic {
+        apply_logarithmic_stretch(image, invert)
     } else {
-        img_buffer
+        let stats = image.calculate_basic_statistics();
+        apply_mtf_stretch(image, &stats, midtone_factor, shadow_clipping, invert)?
     };
+    let img_buffer = GrayImage::from_raw(image.width as u32, image.height as u32, processed_data)
+        .context("Failed to create image buffer")?;
+    Ok(fit_within(img_buffer, max_dimensions))
+}
 
-    // Save PNG with compression
-    let file = File::create(&output_path)
+/// Width of the divider drawn between the halves of a comparison image.
+pub const COMPARE_DIVIDER_WIDTH: u32 = 2;
+
+/// Place two renderings side by side with a white divider between them.
+/// Halves of different heights are top-aligned on a black background.
+pub fn compose_side_by_side(left: &GrayImage, right: &GrayImage) -> GrayImage {
+    let width = left.width() + COMPARE_DIVIDER_WIDTH + right.width();
+    let height = left.height().max(right.height());
+    let mut out = GrayImage::new(width, height);
+    image::imageops::replace(&mut out, left, 0, 0);
+    for x in left.width()..left.width() + COMPARE_DIVIDER_WIDTH {
+        for y in 0..height {
+            out.put_pixel(x, y, Luma([255]));
+        }
+    }
+    image::imageops::replace(
+        &mut out,
+        right,
+        (left.width() + COMPARE_DIVIDER_WIDTH) as i64,
+        0,
+    );
+    out
+}
+
+/// Write an 8-bit grayscale image as a best-compression PNG.
+pub fn write_gray_png(image: &GrayImage, output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)
         .with_context(|| format!("Failed to create output file: {}", output_path.display()))?;
     let writer = BufWriter::new(file);
 
@@ -122,16 +163,34 @@ pub fn stretch_to_png_with_resize(
 
     // Write the image data
     encoder
-        .write_image(
-            &final_buffer,
-            final_buffer.width(),
-            final_buffer.height(),
-            ColorType::L8.into(),
-        )
-        .with_context(|| format!("Failed to write PNG image to {}", output_path.display()))?;
+        .write_image(image, image.width(), image.height(), ColorType::L8.into())
+        .with_context(|| format!("Failed to write PNG image to {}", output_path.display()))
+}
 
-    println!("Saved stretched image to: {}", output_path.display());
-    Ok(())
+/// Downscale to fit within `max_dimensions`, preserving aspect ratio. Never
+/// upscales.
+fn fit_within(img_buffer: GrayImage, max_dimensions: Option<(u32, u32)>) -> GrayImage {
+    let Some((max_width, max_height)) = max_dimensions else {
+        return img_buffer;
+    };
+    let (orig_width, orig_height) = img_buffer.dimensions();
+    let (new_width, new_height) =
+        crate::image_utils::fit_dimensions(orig_width, orig_height, max_width, max_height);
+    if (new_width, new_height) == (orig_width, orig_height) {
+        return img_buffer;
+    }
+
+    println!(
+        "Resizing from {}x{} to {}x{}",
+        orig_width, orig_height, new_width, new_height
+    );
+
+    image::imageops::resize(
+        &img_buffer,
+        new_width,
+        new_height,
+        image::imageops::FilterType::Lanczos3,
+    )
 }
 
 fn apply_mtf_stretch(
@@ -193,3 +252,28 @@ fn apply_logarithmic_stretch(image: &FitsImage, invert: bool) -> Vec<u8> {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_by_side_places_halves_around_a_divider() {
+        let left = GrayImage::from_pixel(3, 2, Luma([10]));
+        let right = GrayImage::from_pixel(4, 3, Luma([20]));
+        let out = compose_side_by_side(&left, &right);
+
+        assert_eq!(out.dimensions(), (3 + COMPARE_DIVIDER_WIDTH + 4, 3));
+        assert_eq!(out.get_pixel(0, 0)[0], 10);
+        assert_eq!(out.get_pixel(2, 1)[0], 10);
+        // Below the shorter left half is background.
+        assert_eq!(out.get_pixel(0, 2)[0], 0);
+        for x in 3..3 + COMPARE_DIVIDER_WIDTH {
+            for y in 0..3 {
+                assert_eq!(out.get_pixel(x, y)[0], 255);
+            }
+        }
+        assert_eq!(out.get_pixel(3 + COMPARE_DIVIDER_WIDTH, 0)[0], 20);
+        assert_eq!(out.get_pixel(out.width() - 1, 2)[0], 20);
+    }
+}
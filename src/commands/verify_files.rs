@@ -0,0 +1,321 @@
+//! File integrity checks for the image library.
+//!
+//! N.I.N.A. records no checksum, so the first run hashes every located FITS
+//! file and stores the SHA-256 in the `psf_guard_checksum` sibling table
+//! inside the scheduler database (owned by psf-guard, like
+//! `psf_guard_archive`). Later runs re-hash and report files whose content
+//! changed, that can no longer be loaded, or that disappeared. Recorded
+//! hashes are never overwritten, so a corrupted file keeps being reported.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::db::Database;
+use crate::directory_tree::DirectoryTree;
+use crate::image_analysis::FitsImage;
+use crate::scoring::filename_from_metadata;
+
+pub struct VerifyFilesOptions {
+    pub base_dirs: Vec<String>,
+    pub project: Option<String>,
+    pub target: Option<String>,
+    /// `table` (problems plus a summary) or `json` (every file).
+    pub format: String,
+    pub threads: Option<usize>,
+    /// Store hashes for files seen for the first time. Off for read-only
+    /// databases: new files are then reported but not recorded.
+    pub record: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    /// First time seen; hash recorded (or would be, read-only).
+    New,
+    Unchanged,
+    /// Content differs from the recorded hash.
+    Changed,
+    /// Hashes fine but no longer loads as a FITS image.
+    Unreadable,
+    /// Not found under the base directories.
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    pub image_id: i32,
+    pub file_name: String,
+    pub path: Option<String>,
+    pub status: FileStatus,
+    pub sha256: Option<String>,
+    pub recorded_sha256: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Create the checksum table if it doesn't exist yet.
+pub fn ensure_checksum_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS psf_guard_checksum (
+            acquired_image_id INTEGER PRIMARY KEY,
+            file_name         TEXT NOT NULL,
+            sha256            TEXT NOT NULL,
+            size_bytes        INTEGER NOT NULL,
+            recorded_at       INTEGER NOT NULL
+        );
+        "#,
+    )
+    .context("creating psf_guard_checksum table")?;
+    Ok(())
+}
+
+/// Recorded hashes by image id, as (file name, sha256). Empty when the table
+/// doesn't exist yet.
+pub fn recorded_checksums(conn: &Connection) -> Result<HashMap<i32, (String, String)>> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'psf_guard_checksum'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(HashMap::new());
+    }
+    let mut stmt =
+        conn.prepare("SELECT acquired_image_id, file_name, sha256 FROM psf_guard_checksum")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok(rows)
+}
+
+/// Record first-seen hashes as (image id, file name, sha256, size).
+pub fn record_checksums(conn: &Connection, entries: &[(i32, String, String, u64)]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let now = chrono::Utc::now().timestamp();
+    for (image_id, file_name, sha256, size) in entries {
+        tx.execute(
+            "INSERT OR IGNORE INTO psf_guard_checksum
+                (acquired_image_id, file_name, sha256, size_bytes, recorded_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![image_id, file_name, sha256, *size as i64, now],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// SHA-256 of a file as lowercase hex, plus its size.
+pub fn sha256_file(path: &Path) -> Result<(String, u64)> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    let mut size = 0u64;
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    let hex = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Ok((hex, size))
+}
+
+pub fn verify_files(conn: &Connection, options: &VerifyFilesOptions) -> Result<()> {
+    if !matches!(options.format.as_str(), "table" | "json") {
+        bail!(
+            "Unknown format '{}': expected table or json",
+            options.format
+        );
+    }
+    let db = Database::new(conn);
+    let images = db.query_images(
+        None,
+        options.project.as_deref(),
+        options.target.as_deref(),
+        None,
+    )?;
+    let recorded = recorded_checksums(conn)?;
+
+    let roots: Vec<&Path> = options.base_dirs.iter().map(Path::new).collect();
+    let tree = DirectoryTree::build_multiple(&roots)
+        .context("Failed to scan the base directories for FITS files")?;
+
+    let mut reports = Vec::new();
+    let mut jobs: Vec<(i32, String, PathBuf)> = Vec::new();
+    for (image, _, _) in images {
+        let Some(file_name) = filename_from_metadata(&image.metadata).filter(|f| !f.is_empty())
+        else {
+            continue;
+        };
+        match tree.find_file_first(&file_name) {
+            Some(path) => jobs.push((image.id, file_name, path.clone())),
+            None => reports.push(FileReport {
+                image_id: image.id,
+                recorded_sha256: recorded.get(&image.id).map(|(_, sha)| sha.clone()),
+                file_name,
+                path: None,
+                status: FileStatus::Missing,
+                sha256: None,
+                error: None,
+            }),
+        }
+    }
+
+    let frame_pixels = jobs
+        .first()
+        .and_then(|(_, _, path)| crate::concurrency::probe_frame_pixels(path));
+    let budget = crate::concurrency::plan_workers(
+        options.threads,
+        &crate::concurrency::WorkerPolicy::all_cores(),
+        crate::concurrency::Priority::Interactive,
+        frame_pixels,
+    );
+    eprintln!(
+        "Verifying {} files with {} worker thread(s) — {}",
+        jobs.len(),
+        budget.workers,
+        budget.rationale
+    );
+
+    let checked: Mutex<Vec<(FileReport, u64)>> = Mutex::new(Vec::new());
+    crate::concurrency::parallel_index(jobs.len(), budget.workers, |i| {
+        let (image_id, file_name, path) = &jobs[i];
+        let recorded_sha256 = recorded.get(image_id).map(|(_, sha)| sha.clone());
+        let mut report = FileReport {
+            image_id: *image_id,
+            file_name: file_name.clone(),
+            path: Some(path.display().to_string()),
+            status: FileStatus::New,
+            sha256: None,
+            recorded_sha256,
+            error: None,
+        };
+        let mut size = 0;
+        match sha256_file(path) {
+            Ok((sha256, bytes)) => {
+                size = bytes;
+                report.status = match &report.recorded_sha256 {
+                    None => FileStatus::New,
+                    Some(recorded) if *recorded == sha256 => FileStatus::Unchanged,
+                    Some(_) => FileStatus::Changed,
+                };
+                report.sha256 = Some(sha256);
+                if report.status != FileStatus::Changed
+                    && let Err(e) = FitsImage::from_file(path)
+                {
+                    report.status = FileStatus::Unreadable;
+                    report.error = Some(format!("{:#}", e));
+                }
+            }
+            Err(e) => {
+                report.status = FileStatus::Unreadable;
+                report.error = Some(format!("{:#}", e));
+            }
+        }
+        checked.lock().unwrap().push((report, size));
+    });
+
+    let mut to_record = Vec::new();
+    for (report, size) in checked.into_inner().unwrap() {
+        if report.status == FileStatus::New
+            && let Some(sha256) = &report.sha256
+        {
+            to_record.push((
+                report.image_id,
+                report.file_name.clone(),
+                sha256.clone(),
+                size,
+            ));
+        }
+        reports.push(report);
+    }
+    reports.sort_by_key(|report| report.image_id);
+
+    if options.record && !to_record.is_empty() {
+        ensure_checksum_schema(conn)?;
+        record_checksums(conn, &to_record)?;
+    }
+
+    if options.format == "json" {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in reports
+            .iter()
+            .filter(|r| !matches!(r.status, FileStatus::New | FileStatus::Unchanged))
+        {
+            println!(
+                "{:<10} #{} {}{}",
+                format!("{:?}", report.status).to_uppercase(),
+                report.image_id,
+                report.path.as_deref().unwrap_or(&report.file_name),
+                report
+                    .error
+                    .as_ref()
+                    .map(|e| format!(": {}", e))
+                    .unwrap_or_default()
+            );
+        }
+        let count = |status| reports.iter().filter(|r| r.status == status).count();
+        println!(
+            "\n{} unchanged, {} new, {} changed, {} unreadable, {} missing",
+            count(FileStatus::Unchanged),
+            count(FileStatus::New),
+            count(FileStatus::Changed),
+            count(FileStatus::Unreadable),
+            count(FileStatus::Missing)
+        );
+    }
+    if !options.record && !to_record.is_empty() {
+        eprintln!(
+            "{} new file hash(es) not recorded (database opened read-only)",
+            to_record.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_of_a_known_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.fits");
+        std::fs::write(&path, b"abc").unwrap();
+        let (hex, size) = sha256_file(&path).unwrap();
+        assert_eq!(
+            hex,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn recorded_hashes_are_never_overwritten() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(recorded_checksums(&conn).unwrap().is_empty());
+
+        ensure_checksum_schema(&conn).unwrap();
+        record_checksums(&conn, &[(7, "a.fits".into(), "aaaa".into(), 10)]).unwrap();
+        record_checksums(&conn, &[(7, "a.fits".into(), "bbbb".into(), 10)]).unwrap();
+
+        let recorded = recorded_checksums(&conn).unwrap();
+        assert_eq!(recorded[&7], ("a.fits".to_string(), "aaaa".to_string()));
+    }
+}
@@ -27,6 +27,7 @@ pub fn visualize_psf_residuals(
         fits_path, output, num_stars, psf_type, "r2",  // Sort by R² by default
         3,     // 3 columns grid
         "top", // Default to top selection mode
+        true,  // Metric labels under each star
         verbose,
     )
 }
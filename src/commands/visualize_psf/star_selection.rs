@@ -1,4 +1,6 @@
 /// Star selection strategies for PSF visualization
+use std::cmp::Ordering;
+
 use crate::hocus_focus_star_detection::HocusFocusStar;
 
 #[allow(dead_code)]
@@ -11,6 +13,13 @@ pub enum SelectionStrategy {
     QualityRange { per_tier: usize },
     /// Stars from corners and edges (9 positions: 4 corners + 4 edges + center)
     Corners,
+    /// Best star (by metric) in each cell of a `cols` x `rows` grid laid
+    /// over the frame, for judging tilt and field curvature corner to corner
+    SpatialGrid {
+        cols: usize,
+        rows: usize,
+        metric: SortMetric,
+    },
     /// Custom selection based on criteria
     Custom {
         min_hfr: Option<f64>,
@@ -19,10 +28,41 @@ pub enum SelectionStrategy {
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SortMetric {
     Hfr,
     R2,
     Brightness,
+    Snr,
+}
+
+impl SortMetric {
+    /// Short tag for cache keys.
+    pub fn tag(self) -> &'static str {
+        match self {
+            SortMetric::Hfr => "hfr",
+            SortMetric::R2 => "r2",
+            SortMetric::Brightness => "brightness",
+            SortMetric::Snr => "snr",
+        }
+    }
+}
+
+impl std::str::FromStr for SortMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hfr" => Ok(SortMetric::Hfr),
+            "r2" => Ok(SortMetric::R2),
+            "brightness" => Ok(SortMetric::Brightness),
+            "snr" => Ok(SortMetric::Snr),
+            _ => Err(format!(
+                "sort metric must be hfr, r2, brightness or snr, got {}",
+                s
+            )),
+        }
+    }
 }
 
 /// Select stars based on the given strategy
@@ -39,6 +79,12 @@ pub fn select_stars(
         }
         SelectionStrategy::QualityRange { per_tier } => select_quality_range(stars, *per_tier),
         SelectionStrategy::Corners => select_corners(stars, image_width, image_height),
+        SelectionStrategy::SpatialGrid { cols, rows, metric } => {
+            select_spatial_grid(stars, *cols, *rows, metric, image_width, image_height)
+                .into_iter()
+                .flatten()
+                .collect()
+        }
         SelectionStrategy::Custom {
             min_hfr,
             max_hfr,
@@ -47,27 +93,75 @@ pub fn select_stars(
     }
 }
 
+/// Order two stars best-first by `metric`: lowest HFR, highest R²,
+/// brightest, or highest SNR.
+fn compare_best_first(metric: &SortMetric, a: &HocusFocusStar, b: &HocusFocusStar) -> Ordering {
+    match metric {
+        SortMetric::Hfr => a.hfr.partial_cmp(&b.hfr),
+        SortMetric::R2 => {
+            let r2_a = a.psf_model.as_ref().map(|m| m.r_squared).unwrap_or(0.0);
+            let r2_b = b.psf_model.as_ref().map(|m| m.r_squared).unwrap_or(0.0);
+            r2_b.partial_cmp(&r2_a) // Higher R² first
+        }
+        SortMetric::Brightness => b.brightness.partial_cmp(&a.brightness),
+        SortMetric::Snr => b.snr.partial_cmp(&a.snr),
+    }
+    .unwrap_or(Ordering::Equal)
+}
+
 fn select_top_n(
     mut stars: Vec<HocusFocusStar>,
     n: usize,
     metric: &SortMetric,
 ) -> Vec<HocusFocusStar> {
     // Sort by the specified metric
-    match metric {
-        SortMetric::Hfr => stars.sort_by(|a, b| a.hfr.partial_cmp(&b.hfr).unwrap()),
-        SortMetric::R2 => stars.sort_by(|a, b| {
-            let r2_a = a.psf_model.as_ref().map(|m| m.r_squared).unwrap_or(0.0);
-            let r2_b = b.psf_model.as_ref().map(|m| m.r_squared).unwrap_or(0.0);
-            r2_b.partial_cmp(&r2_a).unwrap() // Higher R² first
-        }),
-        SortMetric::Brightness => {
-            stars.sort_by(|a, b| b.brightness.partial_cmp(&a.brightness).unwrap())
-        }
-    }
+    stars.sort_by(|a, b| compare_best_first(metric, a, b));
 
     stars.into_iter().take(n).collect()
 }
 
+/// Grid for `SpatialGrid` with at least `num_stars` cells, shaped like the
+/// frame so cells stay roughly square.
+pub fn spatial_grid_shape(
+    num_stars: usize,
+    image_width: usize,
+    image_height: usize,
+) -> (usize, usize) {
+    let num_stars = num_stars.max(1);
+    let aspect = image_width.max(1) as f64 / image_height.max(1) as f64;
+    let cols = ((num_stars as f64 * aspect).sqrt().round() as usize).clamp(1, num_stars);
+    (cols, num_stars.div_ceil(cols))
+}
+
+/// Best star in each grid cell, row-major from the top-left. Cells without
+/// a star are `None` so callers can keep the grid's geometry.
+pub fn select_spatial_grid(
+    stars: Vec<HocusFocusStar>,
+    cols: usize,
+    rows: usize,
+    metric: &SortMetric,
+    image_width: usize,
+    image_height: usize,
+) -> Vec<Option<HocusFocusStar>> {
+    let (cols, rows) = (cols.max(1), rows.max(1));
+    let cell_w = image_width.max(1) as f64 / cols as f64;
+    let cell_h = image_height.max(1) as f64 / rows as f64;
+
+    let mut cells: Vec<Option<HocusFocusStar>> = vec![None; cols * rows];
+    for star in stars {
+        let col = ((star.position.0 / cell_w).max(0.0) as usize).min(cols - 1);
+        let row = ((star.position.1 / cell_h).max(0.0) as usize).min(rows - 1);
+        let cell = &mut cells[row * cols + col];
+        if cell
+            .as_ref()
+            .is_none_or(|best| compare_best_first(metric, &star, best) == Ordering::Less)
+        {
+            *cell = Some(star);
+        }
+    }
+    cells
+}
+
 fn select_five_regions(
     stars: Vec<HocusFocusStar>,
     per_region: usize,
@@ -303,3 +397,47 @@ fn select_custom(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn star(x: f64, y: f64, hfr: f64) -> HocusFocusStar {
+        HocusFocusStar {
+            position: (x, y),
+            hfr,
+            fwhm: hfr * 2.0,
+            brightness: 1000.0,
+            background: 100.0,
+            snr: 50.0,
+            flux: 10000.0,
+            pixel_count: 25,
+            psf_model: None,
+        }
+    }
+
+    #[test]
+    fn spatial_grid_picks_the_best_star_per_cell_and_keeps_empty_cells() {
+        let stars = vec![
+            star(10.0, 10.0, 3.0),
+            star(40.0, 20.0, 2.0), // better, same top-left cell
+            star(150.0, 30.0, 2.5),
+            star(199.9, 99.9, 4.0), // on the far edge: bottom-right cell
+        ];
+        let cells = select_spatial_grid(stars, 2, 2, &SortMetric::Hfr, 200, 100);
+
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[0].as_ref().unwrap().position, (40.0, 20.0));
+        assert_eq!(cells[1].as_ref().unwrap().position, (150.0, 30.0));
+        assert!(cells[2].is_none());
+        assert_eq!(cells[3].as_ref().unwrap().hfr, 4.0);
+    }
+
+    #[test]
+    fn spatial_grid_shape_follows_the_frame_aspect() {
+        assert_eq!(spatial_grid_shape(9, 1000, 1000), (3, 3));
+        assert_eq!(spatial_grid_shape(9, 6000, 4000), (4, 3));
+        assert_eq!(spatial_grid_shape(1, 6000, 4000), (1, 1));
+        assert_eq!(spatial_grid_shape(0, 10, 10), (1, 1));
+    }
+}
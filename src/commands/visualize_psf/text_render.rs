@@ -2,9 +2,16 @@
 /// Uses a basic bitmap font approach
 use image::Rgba;
 
-/// Simple 5x7 bitmap font patterns for digits and basic characters
+/// Drawn for characters the font lacks, so a label never silently loses a
+/// character: a hollow box keeps the spacing and shows something is there.
+const MISSING_GLYPH: [u8; 7] = [
+    0b11111, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11111,
+];
+
+/// Simple 5x7 bitmap font patterns for digits and basic characters.
+/// Lowercase letters render as their uppercase glyphs.
 fn get_char_pattern(c: char) -> Option<[u8; 7]> {
-    match c {
+    match c.to_ascii_uppercase() {
         '0' => Some([
             0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
         ]),
@@ -56,6 +63,21 @@ fn get_char_pattern(c: char) -> Option<[u8; 7]> {
         ' ' => Some([
             0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000,
         ]),
+        ':' => Some([
+            0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+        ]),
+        '-' => Some([
+            0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+        ]),
+        '/' => Some([
+            0b00001, 0b00010, 0b00010, 0b00100, 0b01000, 0b01000, 0b10000,
+        ]),
+        '%' => Some([
+            0b11001, 0b11010, 0b00010, 0b00100, 0b01000, 0b01011, 0b10011,
+        ]),
+        '²' => Some([
+            0b01100, 0b10010, 0b00100, 0b01000, 0b11110, 0b00000, 0b00000,
+        ]),
         'A' => Some([
             0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
         ]),
@@ -140,18 +162,17 @@ fn get_char_pattern(c: char) -> Option<[u8; 7]> {
 
 /// Draw a single character at the given position
 pub fn draw_char(img: &mut image::RgbaImage, x: u32, y: u32, c: char, color: Rgba<u8>, scale: u32) {
-    if let Some(pattern) = get_char_pattern(c) {
-        for (row_idx, &row) in pattern.iter().enumerate() {
-            for col in 0..5 {
-                if row & (1 << (4 - col)) != 0 {
-                    // Draw scaled pixel
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            let px = x + col * scale + dx;
-                            let py = y + row_idx as u32 * scale + dy;
-                            if px < img.width() && py < img.height() {
-                                img.put_pixel(px, py, color);
-                            }
+    let pattern = get_char_pattern(c).unwrap_or(MISSING_GLYPH);
+    for (row_idx, &row) in pattern.iter().enumerate() {
+        for col in 0..5 {
+            if row & (1 << (4 - col)) != 0 {
+                // Draw scaled pixel
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = x + col * scale + dx;
+                        let py = y + row_idx as u32 * scale + dy;
+                        if px < img.width() && py < img.height() {
+                            img.put_pixel(px, py, color);
                         }
                     }
                 }
@@ -178,6 +199,11 @@ pub fn draw_text(
     }
 }
 
+/// Width in pixels of `text` drawn at `scale`.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    text.chars().count() as u32 * 6 * scale
+}
+
 /// Draw text with background for better visibility
 pub fn draw_text_with_bg(
     img: &mut image::RgbaImage,
@@ -190,7 +216,7 @@ pub fn draw_text_with_bg(
 ) {
     let char_width = 6 * scale;
     let char_height = 7 * scale;
-    let text_width = text.len() as u32 * char_width;
+    let text_width = text_width(text, scale);
     let padding = scale;
 
     // Draw background
@@ -207,3 +233,25 @@ pub fn draw_text_with_bg(
     // Draw text
     draw_text(img, x, y, text, fg_color, scale);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercase_uses_uppercase_glyphs_and_unknowns_get_a_box() {
+        assert_eq!(get_char_pattern('h'), get_char_pattern('H'));
+        assert!(get_char_pattern('²').is_some());
+        assert_eq!(get_char_pattern('@'), None);
+
+        let mut img = image::RgbaImage::new(20, 10);
+        draw_char(&mut img, 0, 0, '@', Rgba([255, 0, 0, 255]), 1);
+        assert_eq!(img.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(img.get_pixel(2, 3), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn text_width_counts_characters_not_bytes() {
+        assert_eq!(text_width("R²", 2), 24);
+    }
+}
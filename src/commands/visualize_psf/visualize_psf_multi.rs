@@ -19,6 +19,7 @@ pub fn visualize_psf_multi(
     sort_by: &str,
     grid_cols: usize,
     selection_mode: &str,
+    show_labels: bool,
     verbose: bool,
 ) -> Result<()> {
     if verbose {
@@ -50,6 +51,7 @@ pub fn visualize_psf_multi(
         sort_by,
         Some(grid_cols),
         selection_mode,
+        show_labels,
     )?;
 
     // Generate output filename
@@ -3,11 +3,15 @@ use image::{ImageBuffer, Rgba};
 use imageproc::drawing::draw_hollow_rect_mut;
 use imageproc::rect::Rect;
 
-use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
+use crate::hocus_focus_star_detection::{
+    detect_stars_hocus_focus, HocusFocusParams, HocusFocusStar,
+};
 use crate::image_analysis::FitsImage;
 use crate::psf_fitting::{PSFFitter, PSFType};
 
-use super::visualize_psf::star_selection::{select_stars, SelectionStrategy, SortMetric};
+use super::visualize_psf::star_selection::{
+    select_spatial_grid, select_stars, spatial_grid_shape, SelectionStrategy, SortMetric,
+};
 use super::visualize_psf::text_render::{draw_text, draw_text_with_bg};
 
 /// Create a heatmap color from value (0.0 to 1.0)
@@ -41,15 +45,20 @@ fn heatmap_color(value: f64, mode: &str) -> (u8, u8, u8) {
     }
 }
 
-/// Generate PSF multi visualization image
-pub fn create_psf_multi_image(
+/// Detect stars, fit PSFs and pick the ones to show, exactly as the multi-star
+/// visualization does. Shared by the PNG renderer and the JSON data endpoint
+/// so both describe the same stars.
+///
+/// Returns one slot per grid cell. Only `spatial-grid` selection leaves
+/// slots empty (cells of the frame with no fitted star); every other mode
+/// fills each slot.
+pub fn select_psf_stars(
     fits: &FitsImage,
     num_stars: usize,
     psf_type: PSFType,
     sort_by: &str,
-    grid_cols: Option<usize>,
     selection_mode: &str,
-) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+) -> Result<Vec<Option<HocusFocusStar>>> {
     let width = fits.width;
     let height = fits.height;
 
@@ -84,6 +93,15 @@ pub fn create_psf_multi_image(
         _ => SortMetric::R2,
     };
 
+    if selection_mode == "spatial-grid" {
+        let (cols, rows) = spatial_grid_shape(num_stars, width, height);
+        let cells = select_spatial_grid(stars_with_psf, cols, rows, &sort_metric, width, height);
+        if cells.iter().all(Option::is_none) {
+            anyhow::bail!("No stars selected with the given criteria");
+        }
+        return Ok(cells);
+    }
+
     // Select stars based on strategy
     let strategy = match selection_mode {
         "regions" => SelectionStrategy::FiveRegions {
@@ -105,16 +123,47 @@ pub fn create_psf_multi_image(
         anyhow::bail!("No stars selected with the given criteria");
     }
 
+    Ok(stars_to_show.into_iter().map(Some).collect())
+}
+
+/// Generate PSF multi visualization image.
+///
+/// Rows follow from the selected star count and `grid_cols` (default: a
+/// square-ish grid, 3 wide for `corners`). `spatial-grid` always lays cells
+/// out like the frame, ignoring `grid_cols`, and leaves starless cells
+/// blank. With `show_labels`, each cell
+/// gets its star number and HFR, FWHM, eccentricity and R² underneath,
+/// matching the numbers on the location map.
+pub fn create_psf_multi_image(
+    fits: &FitsImage,
+    num_stars: usize,
+    psf_type: PSFType,
+    sort_by: &str,
+    grid_cols: Option<usize>,
+    selection_mode: &str,
+    show_labels: bool,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    let width = fits.width;
+    let height = fits.height;
+
+    let stars_to_show = select_psf_stars(fits, num_stars, psf_type, sort_by, selection_mode)?;
+
     // Calculate square grid layout
     let num_stars_actual = stars_to_show.len();
     let grid_size = (num_stars_actual as f64).sqrt().ceil() as usize;
-    let grid_cols = grid_cols.unwrap_or_else(|| {
-        if selection_mode == "corners" {
+    let grid_cols = if selection_mode == "spatial-grid" {
+        Some(spatial_grid_shape(num_stars, width, height).0)
+    } else {
+        grid_cols
+    };
+    let grid_cols = grid_cols
+        .filter(|&cols| cols > 0)
+        .unwrap_or(if selection_mode == "corners" {
             3
         } else {
             grid_size
-        }
-    });
+        })
+        .min(num_stars_actual);
     let num_rows = num_stars_actual.div_ceil(grid_cols);
 
     // Panel dimensions
@@ -123,7 +172,11 @@ pub fn create_psf_multi_image(
 
     // Each star gets 3 panels (observed, fitted, residual)
     let star_panel_width = panel_size * 3 + panel_spacing * 2;
-    let star_panel_height = panel_size + 120; // Extra space for two lines of larger text
+    let star_panel_height = if show_labels {
+        panel_size + 120 // Extra space for two lines of larger text
+    } else {
+        panel_size + 50 // Panel titles only
+    };
 
     // Total image size
     let total_width = grid_cols * star_panel_width + (grid_cols - 1) * panel_spacing + 40;
@@ -159,13 +212,32 @@ pub fn create_psf_multi_image(
     // Generate residual maps for each star
     let fitter = PSFFitter::new(psf_type);
 
-    for (star_idx, star) in stars_to_show.iter().enumerate() {
+    for (star_idx, slot) in stars_to_show.iter().enumerate() {
         let row = star_idx / grid_cols;
         let col = star_idx % grid_cols;
 
         let x_offset = 20 + col * (star_panel_width + panel_spacing);
         let y_offset = 20 + row * (star_panel_height + panel_spacing);
 
+        let Some(star) = slot else {
+            // No star in this part of the frame: outline the cell only
+            draw_hollow_rect_mut(
+                &mut img,
+                Rect::at(x_offset as i32, y_offset as i32 + 39)
+                    .of_size(star_panel_width as u32, (panel_size + 2) as u32),
+                Rgba([70, 70, 70, 255]),
+            );
+            draw_text(
+                &mut img,
+                (x_offset + 10) as u32,
+                (y_offset + 50) as u32,
+                "NO STAR",
+                Rgba([120, 120, 120, 255]),
+                2,
+            );
+            continue;
+        };
+
         let psf_model = star.psf_model.as_ref().unwrap();
 
         // Generate residual maps
@@ -280,6 +352,10 @@ pub fn create_psf_multi_image(
                 );
             }
 
+            if !show_labels {
+                continue;
+            }
+
             // Star information with better formatting
             let info_y = y_offset + panel_size + 50;
 
@@ -298,8 +374,8 @@ pub fn create_psf_multi_image(
             // Draw metrics on the next line with more spacing for larger text
             let metrics_y = info_y + 35;
             let metrics_text = format!(
-                "HFR: {:.2}  FWHM: {:.2}  R²: {:.3}",
-                star.hfr, psf_model.fwhm, psf_model.r_squared
+                "HFR {:.2}  FWHM {:.2}  ECC {:.2}  R² {:.3}",
+                star.hfr, psf_model.fwhm, psf_model.eccentricity, psf_model.r_squared
             );
 
             // Color code based on R² value
@@ -385,7 +461,11 @@ pub fn create_psf_multi_image(
     let y_scale = map_height as f64 / height as f64;
 
     // Draw selected stars with numbers
-    for (idx, star) in stars_to_show.iter().enumerate() {
+    for (idx, star) in stars_to_show
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, slot)| Some((idx, slot.as_ref()?)))
+    {
         let map_x = (star.position.0 * x_scale) as i32 + map_x_offset as i32;
         let map_y = (star.position.1 * y_scale) as i32 + map_y_offset as i32;
 
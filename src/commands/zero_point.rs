@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::astrometry_headers::FitsAstrometryHeaders;
+use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
+use crate::image_analysis::FitsImage;
+use crate::photometry::CatalogStar;
+use crate::zero_point::{self, MatchedStar, ZeroPointFit};
+
+#[derive(Debug, Serialize)]
+struct ZeroPointReport {
+    file: String,
+    exposure_s: f64,
+    detected_stars: usize,
+    catalog_stars: usize,
+    fit: Option<ZeroPointFit>,
+    stars: Vec<MatchedStar>,
+}
+
+/// CLI entry point: match one frame's stars against a reference catalog CSV
+/// through the frame's WCS and report the photometric zero-point.
+pub fn zero_point(
+    fits_path: &str,
+    catalog: &str,
+    mag_column: Option<&str>,
+    match_radius_arcsec: f64,
+    format: &str,
+) -> Result<()> {
+    let fits_path = Path::new(fits_path);
+    let wcs = FitsAstrometryHeaders::from_path(fits_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read FITS header {}: {e:?}", fits_path.display()))?
+        .embedded_wcs
+        .with_context(|| {
+            format!(
+                "{} has no usable TAN WCS; plate-solve it and write the solution into the header first",
+                fits_path.display()
+            )
+        })?
+        .value;
+    let reference = zero_point::load_reference_csv(Path::new(catalog), mag_column)?;
+    let image = FitsImage::from_file(fits_path)
+        .with_context(|| format!("Failed to load FITS file: {}", fits_path.display()))?;
+    let exposure_s = image
+        .header
+        .exposure()
+        .filter(|e| *e > 0.0)
+        .unwrap_or_else(|| {
+            eprintln!(
+                "No EXPTIME in the header; instrumental magnitudes are per frame, not per second"
+            );
+            1.0
+        });
+
+    let detection = detect_stars_hocus_focus(
+        &image.data,
+        image.width,
+        image.height,
+        &HocusFocusParams::default(),
+    );
+    // Physical ADU: stored values are rescaled per frame.
+    let detected: Vec<CatalogStar> = detection
+        .stars
+        .iter()
+        .map(|s| CatalogStar {
+            x: s.position.0,
+            y: s.position.1,
+            flux: s.flux / image.raw_scale,
+        })
+        .collect();
+
+    let mut stars = zero_point::match_stars(
+        &detected,
+        &reference,
+        &wcs,
+        (image.width, image.height),
+        exposure_s,
+        match_radius_arcsec,
+    );
+    let fit = zero_point::fit_zero_point(&mut stars);
+    let report = ZeroPointReport {
+        file: fits_path.display().to_string(),
+        exposure_s,
+        detected_stars: detected.len(),
+        catalog_stars: reference.len(),
+        fit,
+        stars,
+    };
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        _ => print_table(&report),
+    }
+    Ok(())
+}
+
+fn print_table(report: &ZeroPointReport) {
+    println!(
+        "{}: {} detected star(s), {} catalog star(s), {} matched",
+        report.file,
+        report.detected_stars,
+        report.catalog_stars,
+        report.stars.len()
+    );
+    if !report.stars.is_empty() {
+        println!(
+            "{:>8} {:>8} {:>11} {:>10} {:>8} {:>8} {:>6} {:>8}",
+            "X", "Y", "RA", "Dec", "Catalog", "Instr", "Sep\"", "Resid"
+        );
+    }
+    for star in &report.stars {
+        println!(
+            "{:>8.1} {:>8.1} {:>11.5} {:>10.5} {:>8.3} {:>8.3} {:>6.2} {:>+8.3}{}",
+            star.x,
+            star.y,
+            star.ra_deg,
+            star.dec_deg,
+            star.catalog_mag,
+            star.instrumental_mag,
+            star.separation_arcsec,
+            star.residual,
+            if star.clipped { "  clipped" } else { "" }
+        );
+    }
+    match &report.fit {
+        Some(fit) => {
+            println!(
+                "\nZero-point: {:.3} ± {:.3} mag (1 ADU/s), scatter {:.3} mag, {} of {} stars used",
+                fit.zero_point, fit.zero_point_error, fit.scatter, fit.used, fit.matched
+            );
+            if let Some(slope) = fit.slope {
+                println!(
+                    "Catalog vs instrumental slope: {:.3} (1.0 is linear)",
+                    slope
+                );
+            }
+        }
+        None => println!("\nToo few matched stars (need 3) to fit a zero-point"),
+    }
+}
@@ -0,0 +1,272 @@
+//! Per-image quality metrics psf-guard measured itself, cached in the
+//! scheduler database so analysis doesn't have to detect them again.
+//!
+//! N.I.N.A. records star count and HFR in each frame's metadata, but frames
+//! imported from folders or captured without the Hocus Focus plugin often
+//! lack them. When psf-guard detects stars on such a frame the result is kept
+//! in `psf_guard_computed_metrics`, keyed on the image id and the file's
+//! modification time. Sequence analysis, trends and focus stability fill
+//! their missing metrics from it. A row whose file has since changed (or
+//! vanished) is ignored, and replaced the next time the frame is measured.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::hocus_focus_star_detection::HocusFocusDetectionResult;
+use crate::sequence_analysis::ImageMetrics;
+
+/// One cached measurement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedMetrics {
+    pub image_id: i32,
+    /// Where the measured file was found.
+    pub file_path: String,
+    /// The file's modification time (Unix seconds) when it was measured.
+    pub file_mtime: i64,
+    pub star_count: Option<f64>,
+    pub hfr: Option<f64>,
+    pub eccentricity: Option<f64>,
+    pub snr: Option<f64>,
+    pub background: Option<f64>,
+}
+
+/// Frame-level metrics of one star detection: what the cache, the detect
+/// endpoint and `analyze-batch` all record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameMetrics {
+    pub hfr: f64,
+    pub detected_stars: usize,
+    /// Median PSF eccentricity; `None` when no star could be fitted.
+    pub eccentricity: Option<f64>,
+    pub background: f64,
+}
+
+impl FrameMetrics {
+    pub fn from_detection(detection: &HocusFocusDetectionResult) -> Self {
+        let mut eccentricities: Vec<f64> = detection
+            .stars
+            .iter()
+            .filter_map(|star| star.psf_model.as_ref().map(|psf| psf.eccentricity))
+            .filter(|e| e.is_finite())
+            .collect();
+        eccentricities.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            hfr: detection.average_hfr,
+            detected_stars: detection.stars.len(),
+            eccentricity: (!eccentricities.is_empty())
+                .then(|| eccentricities[eccentricities.len() / 2]),
+            background: detection.background_mean,
+        }
+    }
+}
+
+impl ComputedMetrics {
+    /// Cache entry for `frame`, measured on `path` as of `mtime`. A frame
+    /// without stars has no HFR.
+    pub fn from_frame(image_id: i32, path: &Path, mtime: i64, frame: &FrameMetrics) -> Self {
+        Self {
+            image_id,
+            file_path: path.to_string_lossy().into_owned(),
+            file_mtime: mtime,
+            star_count: Some(frame.detected_stars as f64),
+            hfr: (frame.detected_stars > 0).then_some(frame.hfr),
+            eccentricity: frame.eccentricity,
+            snr: None,
+            background: Some(frame.background),
+        }
+    }
+
+    /// Whether the measured file is still there, unchanged.
+    pub fn is_current(&self) -> bool {
+        file_mtime(Path::new(&self.file_path)) == Some(self.file_mtime)
+    }
+
+    /// Fill the metrics `metrics` lacks from this measurement. Values already
+    /// present (from N.I.N.A.'s metadata) win. The row is trusted as is:
+    /// take it from [`current_for`], which drops stale ones.
+    pub fn fill_missing(&self, metrics: &mut ImageMetrics) {
+        metrics.star_count = metrics.star_count.or(self.star_count);
+        metrics.hfr = metrics.hfr.or(self.hfr);
+        metrics.eccentricity = metrics.eccentricity.or(self.eccentricity);
+        metrics.snr = metrics.snr.or(self.snr);
+        metrics.background = metrics.background.or(self.background);
+    }
+}
+
+/// The rows of `rows` for `image_ids` whose file is still there, unchanged.
+/// Each file is checked once here rather than per use; it's a stat per row,
+/// so call it after releasing the database lock.
+pub fn current_for(
+    rows: &HashMap<i32, ComputedMetrics>,
+    image_ids: impl IntoIterator<Item = i32>,
+) -> HashMap<i32, ComputedMetrics> {
+    image_ids
+        .into_iter()
+        .filter_map(|id| rows.get(&id))
+        .filter(|row| row.is_current())
+        .map(|row| (row.image_id, row.clone()))
+        .collect()
+}
+
+/// Modification time of `path` in Unix seconds, if it can be read.
+pub fn file_mtime(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_secs() as i64)
+}
+
+/// Create the cache table if it doesn't exist yet. Owned by psf-guard and
+/// never touched by Target Scheduler migrations.
+pub fn ensure_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS psf_guard_computed_metrics (
+            acquired_image_id INTEGER PRIMARY KEY,
+            file_path         TEXT NOT NULL,
+            file_mtime        INTEGER NOT NULL,
+            star_count        REAL,
+            hfr               REAL,
+            eccentricity      REAL,
+            snr               REAL,
+            background        REAL,
+            computed_at       INTEGER NOT NULL
+        );
+        "#,
+    )
+    .context("creating psf_guard_computed_metrics table")?;
+    Ok(())
+}
+
+/// Store a measurement, replacing any earlier one for the image.
+pub fn record(conn: &Connection, metrics: &ComputedMetrics) -> Result<()> {
+    ensure_schema(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO psf_guard_computed_metrics
+            (acquired_image_id, file_path, file_mtime, star_count, hfr,
+             eccentricity, snr, background, computed_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            metrics.image_id,
+            metrics.file_path,
+            metrics.file_mtime,
+            metrics.star_count,
+            metrics.hfr,
+            metrics.eccentricity,
+            metrics.snr,
+            metrics.background,
+            chrono::Utc::now().timestamp(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Store `entries` in one transaction.
+pub fn record_all(conn: &Connection, entries: &[ComputedMetrics]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for entry in entries {
+        record(&tx, entry)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Every cached measurement by image id. Empty when the table doesn't exist
+/// yet. Rows aren't checked against their files here; see [`current_for`].
+pub fn load_all(conn: &Connection) -> Result<HashMap<i32, ComputedMetrics>> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master
+         WHERE type = 'table' AND name = 'psf_guard_computed_metrics'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(HashMap::new());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT acquired_image_id, file_path, file_mtime, star_count, hfr,
+                eccentricity, snr, background
+         FROM psf_guard_computed_metrics",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            let metrics = ComputedMetrics {
+                image_id: row.get(0)?,
+                file_path: row.get(1)?,
+                file_mtime: row.get(2)?,
+                star_count: row.get(3)?,
+                hfr: row.get(4)?,
+                eccentricity: row.get(5)?,
+                snr: row.get(6)?,
+                background: row.get(7)?,
+            };
+            Ok((metrics.image_id, metrics))
+        })?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence_analysis::extract_metrics_from_metadata;
+
+    #[test]
+    fn cached_metrics_fill_gaps_until_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frame.fits");
+        std::fs::write(&path, b"SIMPLE").unwrap();
+
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(load_all(&conn).unwrap().is_empty());
+        record(
+            &conn,
+            &ComputedMetrics {
+                image_id: 7,
+                file_path: path.to_string_lossy().into_owned(),
+                file_mtime: file_mtime(&path).unwrap(),
+                star_count: Some(412.0),
+                hfr: Some(2.3),
+                eccentricity: Some(0.4),
+                snr: None,
+                background: Some(810.0),
+            },
+        )
+        .unwrap();
+        let rows = load_all(&conn).unwrap();
+        let cached = current_for(&rows, [7, 8]).remove(&7).unwrap();
+
+        // N.I.N.A.'s own HFR wins; the missing star count comes from cache.
+        let mut metrics = extract_metrics_from_metadata(7, r#"{"HFR": 2.9}"#, Some(0));
+        cached.fill_missing(&mut metrics);
+        assert_eq!(metrics.star_count, Some(412.0));
+        assert_eq!(metrics.hfr, Some(2.9));
+        assert_eq!(metrics.eccentricity, Some(0.4));
+        assert_eq!(metrics.snr, None);
+
+        // Rewriting the file makes the row stale.
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000))
+            .unwrap();
+        assert!(current_for(&rows, [7]).is_empty());
+    }
+
+    #[test]
+    fn starless_frame_is_cached_without_hfr() {
+        let frame = FrameMetrics {
+            hfr: 0.0,
+            detected_stars: 0,
+            eccentricity: None,
+            background: 640.0,
+        };
+        let entry = ComputedMetrics::from_frame(3, Path::new("/data/a.fits"), 10, &frame);
+        assert_eq!(entry.star_count, Some(0.0));
+        assert_eq!(entry.hfr, None);
+        assert_eq!(entry.background, Some(640.0));
+
+        let conn = Connection::open_in_memory().unwrap();
+        record_all(&conn, &[entry.clone()]).unwrap();
+        assert_eq!(load_all(&conn).unwrap()[&3], entry);
+    }
+}
@@ -93,6 +93,10 @@ pub struct WorkerPolicy {
     pub hard_max_workers: usize,
     /// Estimated peak resident bytes per image pixel during processing.
     pub peak_bytes_per_pixel: usize,
+    /// Explicit number of on-demand image generations (previews, annotated
+    /// and PSF images) that may run at once. `None` plans it like other
+    /// interactive work, from `interactive_ratio` and memory.
+    pub generation_workers: Option<usize>,
 }
 
 impl Default for WorkerPolicy {
@@ -103,6 +107,7 @@ impl Default for WorkerPolicy {
             memory_budget_fraction: DEFAULT_MEMORY_BUDGET_FRACTION,
             hard_max_workers: DEFAULT_HARD_MAX_WORKERS,
             peak_bytes_per_pixel: DEFAULT_PEAK_BYTES_PER_PIXEL,
+            generation_workers: None,
         }
     }
 }
@@ -129,6 +134,12 @@ impl WorkerPolicy {
         self
     }
 
+    /// This policy with `generation_workers` replaced.
+    pub fn with_generation_workers(mut self, workers: Option<usize>) -> Self {
+        self.generation_workers = workers;
+        self
+    }
+
     /// The core ratio for the given priority.
     pub fn ratio_for(&self, priority: Priority) -> f64 {
         match priority {
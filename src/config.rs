@@ -1,7 +1,9 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::time::Duration;
+use utoipa::ToSchema;
 
 /// Main configuration structure for PSF Guard server
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -21,6 +23,23 @@ pub struct Config {
     pub cache: CacheConfig,
     /// Optional pregeneration configuration
     pub pregeneration: Option<PregenerationConfig>,
+    /// Optional preview size presets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<PreviewConfig>,
+    /// Optional reject-reason categories for statistics and dumps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_reasons: Option<RejectReasonsConfig>,
+    /// Optional API authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Bearer token required on every `/api` request. Unset leaves the API
+    /// open. `PSF_GUARD_TOKEN` overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,8 +48,14 @@ pub struct ServerConfig {
     pub port: Option<u16>,
     /// Host to bind to (default: "0.0.0.0")
     pub host: Option<String>,
-    /// Enable CORS (default: true)
+    /// Enable CORS (default: true). `false` sends no CORS headers, so only
+    /// same-origin pages can call the API.
     pub cors: Option<bool>,
+    /// Origins allowed to call the API cross-origin, e.g.
+    /// `["https://psf.example.lan"]`. Unset keeps the permissive default
+    /// (any origin) for backward compatibility.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors_origins: Option<Vec<String>>,
     /// Fraction of logical CPU cores interactive, user-triggered work (the
     /// occlusion / spatial scan) may use (0.0–1.0, default 0.5). It runs on
     /// the blocking pool while the server keeps serving the UI, so this leaves
@@ -44,16 +69,75 @@ pub struct ServerConfig {
     /// an interactive scan is running. See `concurrency::WorkerPolicy`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub background_worker_ratio: Option<f64>,
+    /// How many on-demand image generations (preview, annotated and PSF
+    /// images) may run at once; further cache misses queue. Unset plans it
+    /// from `scan_worker_ratio` and available memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation_workers: Option<usize>,
     /// Optional notice shown below the application header on every page.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub banner: Option<SiteBannerConfig>,
+    /// How long a scheduler DB connection waits on a lock before failing, as
+    /// a human readable time (default: "60s").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlite_busy_timeout: Option<String>,
+    /// Switch scheduler databases to SQLite WAL journaling (default: true).
+    /// Turn off when N.I.N.A. on another machine opens the same file over a
+    /// network share — WAL's shared memory index only works on one host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlite_wal: Option<bool>,
+    /// Attempts per FITS read when generating previews, annotated images and
+    /// star data (default: 3). Absorbs transient errors on networked storage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fits_read_attempts: Option<u32>,
+    /// Delay before the first FITS read retry, doubling for each one after,
+    /// as a human readable time (default: "200ms").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fits_read_retry_delay: Option<String>,
+    /// When a frame's filename isn't found exactly, also match it ignoring
+    /// case (default: false). For files copied through a case-insensitive
+    /// filesystem; leave off on case-sensitive storage holding names that
+    /// differ only by case, where it could pick the wrong file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_insensitive_filenames: Option<bool>,
+    /// How long a browsing request may run before the client gets a 504, as
+    /// a human readable time (default: "30s").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<String>,
+    /// The same for whole-dataset requests: exports, database sync, sequence
+    /// analysis, plate solving and catalog validation (default: "10m").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bulk_request_timeout: Option<String>,
+    /// Frames with fewer detected stars than this get `low_star_warning` and
+    /// fail sequence analysis outright (default: off).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_stars: Option<usize>,
+    /// Fraction of pixels at 0 ADU past which a frame is judged
+    /// under-exposed (default: 0.01).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_clip_fraction: Option<f64>,
+    /// Fraction of pixels at full scale past which a frame is judged
+    /// over-exposed (default: 0.01).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_clip_fraction: Option<f64>,
+}
+
+/// Effective cross-origin policy for the HTTP API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsPolicy {
+    /// No CORS headers: browsers only allow same-origin requests.
+    Disabled,
+    /// Any origin (the historical default).
+    Permissive,
+    /// Only these exact origins (scheme://host[:port]).
+    AllowList(Vec<String>),
 }
 
 /// Plain-text site notice configured by the server administrator.
 ///
 /// The frontend never renders these values as HTML. An optional link must use
 /// HTTP(S), which prevents a config typo from exposing a script URL.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SiteBannerConfig {
     pub title: String,
@@ -147,9 +231,13 @@ pub struct CacheConfig {
     pub file_ttl: Option<String>,
     /// Directory tree cache TTL as human readable time (default: "5m")  
     pub directory_ttl: Option<String>,
+    /// How long browsers may cache served images (`Cache-Control: max-age`),
+    /// as human readable time (default: "1d")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_max_age: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PregenerationConfig {
     /// Enable pregeneration of images (default: false)
     pub enabled: Option<bool>,
@@ -159,6 +247,100 @@ pub struct PregenerationConfig {
     pub large: Option<bool>,
     /// Number of worker threads (default: num_cpus)
     pub workers: Option<usize>,
+    /// Extra `[preview.sizes]` presets to pregenerate, e.g. `["4k"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreviewConfig {
+    /// Preview size presets, name → max dimension in pixels. Merged over the
+    /// built-in `screen = 1200` and `large = 2000`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<BTreeMap<String, u32>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RejectReasonsConfig {
+    /// Rules mapping raw reject reasons onto canonical categories, tried in
+    /// order. Once any rule is configured, unmatched reasons count as `other`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mappings: Vec<ReasonMapping>,
+    /// Substrings (case-insensitive) marking a reject reason as a manual
+    /// decision that regrade must not undo. Defaults to `["Manual"]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manual_markers: Option<Vec<String>>,
+}
+
+/// One reject-reason rule: set exactly one of `exact` (case-insensitive,
+/// against the reason without its score annotation) or `regex` (searched in
+/// the full stored reason).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReasonMapping {
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+}
+
+/// Named preview sizes (the `size` query parameter) and the longest edge
+/// each is scaled down to. `original` is reserved for full resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewSizes(BTreeMap<String, u32>);
+
+impl Default for PreviewSizes {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            ("screen".to_string(), 1200),
+            ("large".to_string(), 2000),
+        ]))
+    }
+}
+
+impl PreviewSizes {
+    /// Size used when a request names none.
+    pub const DEFAULT: &'static str = "screen";
+    /// Full resolution; never resized.
+    pub const ORIGINAL: &'static str = "original";
+
+    /// Pixel bounds for a preview `size`: `original` → none, a preset →
+    /// its dimension squared, anything else → the `screen` preset.
+    pub fn max_dimensions(&self, size: &str) -> Option<(u32, u32)> {
+        if size == Self::ORIGINAL {
+            return None;
+        }
+        let max = self
+            .0
+            .get(size)
+            .or_else(|| self.0.get(Self::DEFAULT))
+            .copied()
+            .unwrap_or(1200);
+        Some((max, max))
+    }
+
+    /// Pixel bounds for a requested `size`, or an error naming the valid
+    /// sizes when it is neither a preset nor `original`.
+    pub fn resolve(&self, size: &str) -> std::result::Result<Option<(u32, u32)>, String> {
+        if !self.contains(size) {
+            return Err(format!(
+                "unknown size '{}': expected one of {}, {}",
+                size,
+                self.names().collect::<Vec<_>>().join(", "),
+                Self::ORIGINAL
+            ));
+        }
+        Ok(self.max_dimensions(size))
+    }
+
+    /// Whether `size` names a preset (or `original`).
+    pub fn contains(&self, size: &str) -> bool {
+        size == Self::ORIGINAL || self.0.contains_key(size)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
 }
 
 impl Default for ServerConfig {
@@ -167,9 +349,21 @@ impl Default for ServerConfig {
             port: Some(3000),
             host: Some("0.0.0.0".to_string()),
             cors: Some(true),
+            cors_origins: None,
             scan_worker_ratio: None,
             background_worker_ratio: None,
+            generation_workers: None,
             banner: None,
+            sqlite_busy_timeout: None,
+            sqlite_wal: None,
+            fits_read_attempts: None,
+            fits_read_retry_delay: None,
+            case_insensitive_filenames: None,
+            request_timeout: None,
+            bulk_request_timeout: None,
+            min_stars: None,
+            low_clip_fraction: None,
+            high_clip_fraction: None,
         }
     }
 }
@@ -180,6 +374,7 @@ impl Default for CacheConfig {
             directory: Some("./cache".to_string()),
             file_ttl: Some("5m".to_string()),
             directory_ttl: Some("5m".to_string()),
+            http_max_age: None,
         }
     }
 }
@@ -207,6 +402,187 @@ impl Config {
         Ok(())
     }
 
+    /// Apply `PSF_GUARD_*` environment overrides (see [`Config::apply_env_from`]).
+    pub fn apply_env(&mut self) -> Result<()> {
+        self.apply_env_from(|name| std::env::var(name).ok())
+    }
+
+    /// Override config values from environment variables, read through
+    /// `lookup`. Precedence is CLI > env > file > default, so this runs
+    /// after loading the file and before [`Config::merge_with_cli`]. Empty
+    /// values count as unset; list values are comma-separated.
+    ///
+    /// `PSF_GUARD_DATABASE_PATH`, `PSF_GUARD_IMAGE_DIRS`, `PSF_GUARD_PORT`,
+    /// `PSF_GUARD_HOST`, `PSF_GUARD_CORS`, `PSF_GUARD_CORS_ORIGINS`,
+    /// `PSF_GUARD_SCAN_WORKER_RATIO`, `PSF_GUARD_BACKGROUND_WORKER_RATIO`,
+    /// `PSF_GUARD_SQLITE_BUSY_TIMEOUT`, `PSF_GUARD_SQLITE_WAL`,
+    /// `PSF_GUARD_FITS_READ_ATTEMPTS`, `PSF_GUARD_FITS_READ_RETRY_DELAY`,
+    /// `PSF_GUARD_CASE_INSENSITIVE_FILENAMES`, `PSF_GUARD_MIN_STARS`,
+    /// `PSF_GUARD_LOW_CLIP_FRACTION`, `PSF_GUARD_HIGH_CLIP_FRACTION`,
+    /// `PSF_GUARD_CACHE_DIR`, `PSF_GUARD_FILE_TTL`, `PSF_GUARD_DIRECTORY_TTL`,
+    /// `PSF_GUARD_HTTP_MAX_AGE`, `PSF_GUARD_PREGENERATION_ENABLED`,
+    /// `PSF_GUARD_PREGENERATION_SCREEN`, `PSF_GUARD_PREGENERATION_LARGE`,
+    /// `PSF_GUARD_PREGENERATION_WORKERS`, `PSF_GUARD_PREGENERATION_SIZES`.
+    /// `PSF_GUARD_TOKEN` is read by [`Config::get_auth_token`].
+    pub fn apply_env_from(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let var = |name: &str| {
+            lookup(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let list = |name: &str| {
+            var(name).map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+        };
+        fn parse<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<Option<T>>
+        where
+            T::Err: std::fmt::Display,
+        {
+            value
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|e| anyhow::anyhow!("Invalid {}={}: {}", name, value, e))
+                })
+                .transpose()
+        }
+        let flag = |name: &str| -> Result<Option<bool>> {
+            var(name)
+                .map(|value| match value.to_ascii_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => Ok(true),
+                    "0" | "false" | "no" | "off" => Ok(false),
+                    _ => Err(anyhow::anyhow!(
+                        "Invalid {}={}: expected true or false",
+                        name,
+                        value
+                    )),
+                })
+                .transpose()
+        };
+
+        if let Some(path) = var("PSF_GUARD_DATABASE_PATH") {
+            self.database = Some(DatabaseConfig { path });
+        }
+        if let Some(directories) = list("PSF_GUARD_IMAGE_DIRS") {
+            self.images = Some(ImagesConfig { directories });
+        }
+
+        let server = &mut self.server;
+        if let Some(port) = parse("PSF_GUARD_PORT", var("PSF_GUARD_PORT"))? {
+            server.port = Some(port);
+        }
+        if let Some(host) = var("PSF_GUARD_HOST") {
+            server.host = Some(host);
+        }
+        if let Some(cors) = flag("PSF_GUARD_CORS")? {
+            server.cors = Some(cors);
+        }
+        if let Some(origins) = list("PSF_GUARD_CORS_ORIGINS") {
+            server.cors_origins = Some(origins);
+        }
+        if let Some(ratio) = parse(
+            "PSF_GUARD_SCAN_WORKER_RATIO",
+            var("PSF_GUARD_SCAN_WORKER_RATIO"),
+        )? {
+            server.scan_worker_ratio = Some(ratio);
+        }
+        if let Some(ratio) = parse(
+            "PSF_GUARD_BACKGROUND_WORKER_RATIO",
+            var("PSF_GUARD_BACKGROUND_WORKER_RATIO"),
+        )? {
+            server.background_worker_ratio = Some(ratio);
+        }
+        if let Some(workers) = parse(
+            "PSF_GUARD_GENERATION_WORKERS",
+            var("PSF_GUARD_GENERATION_WORKERS"),
+        )? {
+            server.generation_workers = Some(workers);
+        }
+        if let Some(timeout) = var("PSF_GUARD_SQLITE_BUSY_TIMEOUT") {
+            server.sqlite_busy_timeout = Some(timeout);
+        }
+        if let Some(wal) = flag("PSF_GUARD_SQLITE_WAL")? {
+            server.sqlite_wal = Some(wal);
+        }
+        if let Some(attempts) = parse(
+            "PSF_GUARD_FITS_READ_ATTEMPTS",
+            var("PSF_GUARD_FITS_READ_ATTEMPTS"),
+        )? {
+            server.fits_read_attempts = Some(attempts);
+        }
+        if let Some(delay) = var("PSF_GUARD_FITS_READ_RETRY_DELAY") {
+            server.fits_read_retry_delay = Some(delay);
+        }
+        if let Some(timeout) = var("PSF_GUARD_REQUEST_TIMEOUT") {
+            server.request_timeout = Some(timeout);
+        }
+        if let Some(timeout) = var("PSF_GUARD_BULK_REQUEST_TIMEOUT") {
+            server.bulk_request_timeout = Some(timeout);
+        }
+        if let Some(ignore_case) = flag("PSF_GUARD_CASE_INSENSITIVE_FILENAMES")? {
+            server.case_insensitive_filenames = Some(ignore_case);
+        }
+        if let Some(min_stars) = parse("PSF_GUARD_MIN_STARS", var("PSF_GUARD_MIN_STARS"))? {
+            server.min_stars = Some(min_stars);
+        }
+        if let Some(fraction) = parse(
+            "PSF_GUARD_LOW_CLIP_FRACTION",
+            var("PSF_GUARD_LOW_CLIP_FRACTION"),
+        )? {
+            server.low_clip_fraction = Some(fraction);
+        }
+        if let Some(fraction) = parse(
+            "PSF_GUARD_HIGH_CLIP_FRACTION",
+            var("PSF_GUARD_HIGH_CLIP_FRACTION"),
+        )? {
+            server.high_clip_fraction = Some(fraction);
+        }
+
+        let cache = &mut self.cache;
+        if let Some(directory) = var("PSF_GUARD_CACHE_DIR") {
+            cache.directory = Some(directory);
+        }
+        if let Some(ttl) = var("PSF_GUARD_FILE_TTL") {
+            cache.file_ttl = Some(ttl);
+        }
+        if let Some(ttl) = var("PSF_GUARD_DIRECTORY_TTL") {
+            cache.directory_ttl = Some(ttl);
+        }
+        if let Some(max_age) = var("PSF_GUARD_HTTP_MAX_AGE") {
+            cache.http_max_age = Some(max_age);
+        }
+
+        let enabled = flag("PSF_GUARD_PREGENERATION_ENABLED")?;
+        let screen = flag("PSF_GUARD_PREGENERATION_SCREEN")?;
+        let large = flag("PSF_GUARD_PREGENERATION_LARGE")?;
+        let workers = parse(
+            "PSF_GUARD_PREGENERATION_WORKERS",
+            var("PSF_GUARD_PREGENERATION_WORKERS"),
+        )?;
+        let sizes = list("PSF_GUARD_PREGENERATION_SIZES");
+        if enabled.is_some()
+            || screen.is_some()
+            || large.is_some()
+            || workers.is_some()
+            || sizes.is_some()
+        {
+            let pregeneration = self.pregeneration.get_or_insert_with(Default::default);
+            pregeneration.enabled = enabled.or(pregeneration.enabled);
+            pregeneration.screen = screen.or(pregeneration.screen);
+            pregeneration.large = large.or(pregeneration.large);
+            pregeneration.workers = workers.or(pregeneration.workers);
+            pregeneration.sizes = sizes.or(pregeneration.sizes.take());
+        }
+
+        Ok(())
+    }
+
     /// Merge configuration with command line arguments, prioritizing CLI values
     pub fn merge_with_cli(
         &mut self,
@@ -216,12 +592,13 @@ impl Config {
         host: Option<String>,
         cache_dir: Option<String>,
     ) {
-        // CLI database path overrides config (legacy; server mode ignores this)
+        // CLI database path overrides config; server mode registers the result
         if let Some(db_path) = database_path {
             self.database = Some(DatabaseConfig { path: db_path });
         }
 
-        // CLI image directories override config (legacy; server mode ignores this)
+        // CLI image directories override config; server mode registers them with
+        // the database
         if let Some(dirs) = image_dirs
             && !dirs.is_empty()
         {
@@ -260,6 +637,65 @@ impl Config {
         self.server.cors.unwrap_or(true)
     }
 
+    /// Cross-origin policy from `cors` and `cors_origins`. Origins are
+    /// trimmed and stripped of a trailing slash; invalid ones are an error.
+    pub fn get_cors_policy(&self) -> Result<CorsPolicy> {
+        if !self.get_cors_enabled() {
+            return Ok(CorsPolicy::Disabled);
+        }
+        let Some(origins) = &self.server.cors_origins else {
+            return Ok(CorsPolicy::Permissive);
+        };
+        let origins = origins
+            .iter()
+            .map(|origin| {
+                let origin = origin.trim().trim_end_matches('/');
+                let rest = origin
+                    .strip_prefix("https://")
+                    .or_else(|| origin.strip_prefix("http://"))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "server.cors_origins entry must start with http:// or https://: {}",
+                            origin
+                        )
+                    })?;
+                if rest.is_empty() || rest.contains('/') || !origin.is_ascii() {
+                    return Err(anyhow::anyhow!(
+                        "server.cors_origins entry must be a bare origin (scheme://host[:port]): {}",
+                        origin
+                    ));
+                }
+                Ok(origin.to_string())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(CorsPolicy::AllowList(origins))
+    }
+
+    /// Check the bind host and CORS settings. Unlike [`Config::validate`]
+    /// this skips the legacy database/image checks, so server mode can call
+    /// it directly.
+    pub fn validate_network(&self) -> Result<()> {
+        let host = self.get_host();
+        if host != "localhost" && host.parse::<std::net::IpAddr>().is_err() {
+            return Err(anyhow::anyhow!(
+                "Host must be an IP address (e.g. 0.0.0.0, 127.0.0.1, ::) or localhost, got: {}",
+                host
+            ));
+        }
+        self.get_cors_policy()?;
+        Ok(())
+    }
+
+    /// API bearer token: `PSF_GUARD_TOKEN` if set, else `[auth] token`.
+    /// Blank values count as unset.
+    pub fn get_auth_token(&self) -> Option<String> {
+        std::env::var("PSF_GUARD_TOKEN")
+            .ok()
+            .or_else(|| self.auth.as_ref().and_then(|auth| auth.token.clone()))
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+    }
+
     /// Validated, whitespace-normalized site banner for the server API.
     pub fn get_site_banner(&self) -> Result<Option<SiteBannerConfig>> {
         self.server
@@ -287,6 +723,80 @@ impl Config {
         crate::concurrency::WorkerPolicy::default()
             .with_interactive_ratio(interactive)
             .with_background_ratio(background)
+            .with_generation_workers(self.server.generation_workers.map(|n| n.max(1)))
+    }
+
+    /// Effective SQLite connection tuning for the scheduler databases.
+    pub fn get_connection_options(&self) -> crate::db::ConnectionOptions {
+        let busy_timeout = self
+            .server
+            .sqlite_busy_timeout
+            .as_deref()
+            .and_then(|s| humantime::parse_duration(s).ok())
+            .unwrap_or(crate::db::DEFAULT_BUSY_TIMEOUT);
+        crate::db::ConnectionOptions {
+            busy_timeout,
+            wal: self.server.sqlite_wal.unwrap_or(true),
+            read_only: false,
+        }
+    }
+
+    /// Effective retry policy for FITS reads in the server.
+    pub fn get_fits_read_retry(&self) -> crate::fits_read::ReadRetryPolicy {
+        let default = crate::fits_read::ReadRetryPolicy::DEFAULT;
+        crate::fits_read::ReadRetryPolicy {
+            attempts: self
+                .server
+                .fits_read_attempts
+                .unwrap_or(default.attempts)
+                .max(1),
+            delay: self
+                .server
+                .fits_read_retry_delay
+                .as_deref()
+                .and_then(|s| humantime::parse_duration(s).ok())
+                .unwrap_or(default.delay),
+        }
+    }
+
+    /// Effective per-class request timeouts for the server.
+    pub fn get_request_timeouts(&self) -> crate::server::RequestTimeouts {
+        let default = crate::server::RequestTimeouts::DEFAULT;
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|s| humantime::parse_duration(s).ok())
+        };
+        crate::server::RequestTimeouts {
+            interactive: parse(&self.server.request_timeout).unwrap_or(default.interactive),
+            bulk: parse(&self.server.bulk_request_timeout).unwrap_or(default.bulk),
+        }
+    }
+
+    /// Whether filename lookups fall back to ignoring case.
+    pub fn get_case_insensitive_filenames(&self) -> bool {
+        self.server.case_insensitive_filenames.unwrap_or(false)
+    }
+
+    /// Star count below which a frame is treated as a detection failure;
+    /// `None` leaves star counts to the regular scoring.
+    pub fn get_min_stars(&self) -> Option<usize> {
+        self.server.min_stars
+    }
+
+    /// Clip fractions past which a frame is judged under- or over-exposed.
+    pub fn get_exposure_thresholds(&self) -> crate::image_analysis::ExposureThresholds {
+        use crate::image_analysis::{DEFAULT_HIGH_CLIP_FRACTION, DEFAULT_LOW_CLIP_FRACTION};
+        crate::image_analysis::ExposureThresholds {
+            low_clip_fraction: self
+                .server
+                .low_clip_fraction
+                .unwrap_or(DEFAULT_LOW_CLIP_FRACTION),
+            high_clip_fraction: self
+                .server
+                .high_clip_fraction
+                .unwrap_or(DEFAULT_HIGH_CLIP_FRACTION),
+        }
     }
 
     pub fn get_cache_directory(&self) -> String {
@@ -306,6 +816,163 @@ impl Config {
         humantime::parse_duration(ttl_str).unwrap_or(Duration::from_secs(300))
     }
 
+    /// Effective preview size presets: the built-in ones with `[preview.sizes]`
+    /// merged over them.
+    pub fn get_preview_sizes(&self) -> Result<PreviewSizes> {
+        let mut sizes = PreviewSizes::default();
+        let configured = self.preview.as_ref().and_then(|p| p.sizes.as_ref());
+        for (name, &max) in configured.into_iter().flatten() {
+            if name == PreviewSizes::ORIGINAL {
+                return Err(anyhow::anyhow!(
+                    "preview.sizes.original is reserved for full-resolution previews"
+                ));
+            }
+            if max == 0 {
+                return Err(anyhow::anyhow!(
+                    "preview.sizes.{} must be greater than 0",
+                    name
+                ));
+            }
+            sizes.0.insert(name.clone(), max);
+        }
+        Ok(sizes)
+    }
+
+    /// Extra preview sizes to pregenerate (`[pregeneration] sizes`); empty
+    /// unless pregeneration is enabled. Every name must be a
+    /// `[preview.sizes]` preset or `original`.
+    pub fn get_pregeneration_sizes(&self) -> Result<Vec<String>> {
+        let presets = self.get_preview_sizes()?;
+        let Some(pregeneration) = &self.pregeneration else {
+            return Ok(Vec::new());
+        };
+        let sizes = pregeneration.sizes.clone().unwrap_or_default();
+        if let Some(unknown) = sizes.iter().find(|size| !presets.contains(size)) {
+            return Err(anyhow::anyhow!(
+                "pregeneration.sizes names unknown preview size '{}' (known: {}, original)",
+                unknown,
+                presets.names().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if !pregeneration.enabled.unwrap_or(false) {
+            return Ok(Vec::new());
+        }
+        Ok(sizes)
+    }
+
+    pub fn get_http_max_age(&self) -> Duration {
+        let max_age = self.cache.http_max_age.as_deref().unwrap_or("1d");
+        humantime::parse_duration(max_age).unwrap_or(Duration::from_secs(86400))
+    }
+
+    /// Compiled reject-reason mapping; empty (reasons pass through
+    /// normalized) when `[reject_reasons]` has no mappings.
+    pub fn get_reason_mapper(&self) -> Result<crate::reject_reasons::ReasonMapper> {
+        let mappings = self
+            .reject_reasons
+            .as_ref()
+            .map(|r| r.mappings.as_slice())
+            .unwrap_or_default();
+        crate::reject_reasons::ReasonMapper::new(mappings)
+    }
+
+    /// Reject-reason markers for manual grades; `"Manual"` unless
+    /// `[reject_reasons] manual_markers` is set.
+    pub fn get_manual_markers(&self) -> Result<crate::reject_reasons::ManualMarkers> {
+        match self
+            .reject_reasons
+            .as_ref()
+            .and_then(|r| r.manual_markers.as_deref())
+        {
+            Some(markers) => crate::reject_reasons::ManualMarkers::new(markers),
+            None => Ok(Default::default()),
+        }
+    }
+
+    /// The configuration as the server would run it: every default filled
+    /// in, durations in canonical form, paths made absolute and the auth
+    /// token (including `PSF_GUARD_TOKEN`) redacted. Meant to be called
+    /// after [`Config::validate`]; it only re-checks what it has to resolve.
+    pub fn effective(&self) -> Result<Config> {
+        let absolute = |path: &str| {
+            std::path::absolute(path)
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| path.to_string())
+        };
+        let format_duration = |d: Duration| humantime::format_duration(d).to_string();
+        let worker_policy = self.get_worker_policy();
+        let connection = self.get_connection_options();
+        let fits_read_retry = self.get_fits_read_retry();
+        let request_timeouts = self.get_request_timeouts();
+        let preview_sizes = self.get_preview_sizes()?;
+        let pregeneration = self.pregeneration.clone().unwrap_or_default();
+        let pregeneration_enabled = pregeneration.enabled.unwrap_or(false);
+
+        Ok(Config {
+            server: ServerConfig {
+                port: Some(self.get_port()),
+                host: Some(self.get_host()),
+                cors: Some(self.get_cors_enabled()),
+                cors_origins: match self.get_cors_policy()? {
+                    CorsPolicy::AllowList(origins) => Some(origins),
+                    _ => None,
+                },
+                scan_worker_ratio: Some(worker_policy.interactive_ratio),
+                background_worker_ratio: Some(worker_policy.background_ratio),
+                generation_workers: worker_policy.generation_workers,
+                banner: self.get_site_banner()?,
+                sqlite_busy_timeout: Some(format_duration(connection.busy_timeout)),
+                sqlite_wal: Some(connection.wal),
+                fits_read_attempts: Some(fits_read_retry.attempts),
+                fits_read_retry_delay: Some(format_duration(fits_read_retry.delay)),
+                case_insensitive_filenames: Some(self.get_case_insensitive_filenames()),
+                request_timeout: Some(format_duration(request_timeouts.interactive)),
+                bulk_request_timeout: Some(format_duration(request_timeouts.bulk)),
+                min_stars: self.get_min_stars(),
+                low_clip_fraction: Some(self.get_exposure_thresholds().low_clip_fraction),
+                high_clip_fraction: Some(self.get_exposure_thresholds().high_clip_fraction),
+            },
+            database: self.database.as_ref().map(|database| DatabaseConfig {
+                path: absolute(&database.path),
+            }),
+            images: self.images.as_ref().map(|images| ImagesConfig {
+                directories: images
+                    .directories
+                    .iter()
+                    .map(|d| absolute(d.as_str()))
+                    .collect(),
+            }),
+            cache: CacheConfig {
+                directory: Some(absolute(&self.get_cache_directory())),
+                file_ttl: Some(format_duration(self.get_file_ttl())),
+                directory_ttl: Some(format_duration(self.get_directory_ttl())),
+                http_max_age: Some(format_duration(self.get_http_max_age())),
+            },
+            pregeneration: Some(PregenerationConfig {
+                enabled: Some(pregeneration_enabled),
+                screen: Some(pregeneration.screen.unwrap_or(true)),
+                large: Some(pregeneration.large.unwrap_or(false)),
+                workers: pregeneration.workers,
+                sizes: Some(pregeneration.sizes.unwrap_or_default()),
+            }),
+            preview: Some(PreviewConfig {
+                sizes: Some(
+                    preview_sizes
+                        .names()
+                        .filter_map(|name| {
+                            let (max, _) = preview_sizes.max_dimensions(name)?;
+                            Some((name.to_string(), max))
+                        })
+                        .collect(),
+                ),
+            }),
+            reject_reasons: self.reject_reasons.clone(),
+            auth: self.get_auth_token().map(|_| AuthConfig {
+                token: Some("<redacted>".to_string()),
+            }),
+        })
+    }
+
     /// Get pregeneration configuration for use with CLI converter
     pub fn get_pregeneration(&self) -> Option<&PregenerationConfig> {
         self.pregeneration.as_ref()
@@ -370,7 +1037,51 @@ impl Config {
                 .with_context(|| format!("Invalid directory_ttl format: {}", dir_ttl_str))?;
         }
 
+        if let Some(ref max_age) = self.cache.http_max_age {
+            humantime::parse_duration(max_age)
+                .with_context(|| format!("Invalid http_max_age format: {}", max_age))?;
+        }
+
+        if let Some(ref busy_timeout) = self.server.sqlite_busy_timeout {
+            humantime::parse_duration(busy_timeout)
+                .with_context(|| format!("Invalid sqlite_busy_timeout format: {}", busy_timeout))?;
+        }
+
+        if let Some(ref delay) = self.server.fits_read_retry_delay {
+            humantime::parse_duration(delay)
+                .with_context(|| format!("Invalid fits_read_retry_delay format: {}", delay))?;
+        }
+        if self.server.fits_read_attempts == Some(0) {
+            return Err(anyhow::anyhow!("fits_read_attempts must be at least 1"));
+        }
+        if self.server.generation_workers == Some(0) {
+            return Err(anyhow::anyhow!("generation_workers must be at least 1"));
+        }
+        if self.server.min_stars == Some(0) {
+            return Err(anyhow::anyhow!("min_stars must be at least 1"));
+        }
+        self.get_exposure_thresholds()
+            .validate()
+            .map_err(anyhow::Error::msg)?;
+
+        for (name, value) in [
+            ("request_timeout", &self.server.request_timeout),
+            ("bulk_request_timeout", &self.server.bulk_request_timeout),
+        ] {
+            if let Some(value) = value {
+                let timeout = humantime::parse_duration(value)
+                    .with_context(|| format!("Invalid {} format: {}", name, value))?;
+                if timeout.is_zero() {
+                    return Err(anyhow::anyhow!("{} must be greater than zero", name));
+                }
+            }
+        }
+
         self.get_site_banner()?;
+        self.get_pregeneration_sizes()?;
+        self.get_reason_mapper()?;
+        self.get_manual_markers()?;
+        self.validate_network()?;
 
         Ok(())
     }
@@ -379,6 +1090,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -485,6 +1197,23 @@ directory = "./cache"
         assert_eq!(config.get_host(), "0.0.0.0");
     }
 
+    #[test]
+    fn cli_host_overrides_config_file_host() {
+        let toml = r#"
+[server]
+host = "192.168.1.20"
+
+[cache]
+directory = "./cache"
+"#;
+        let mut config: Config = toml_edit::de::from_str(toml).unwrap();
+        config.merge_with_cli(None, None, None, None, None);
+        assert_eq!(config.get_host(), "192.168.1.20");
+
+        config.merge_with_cli(None, None, None, Some("0.0.0.0".to_string()), None);
+        assert_eq!(config.get_host(), "0.0.0.0");
+    }
+
     #[test]
     fn test_config_file_operations() {
         let config = Config::default();
@@ -508,6 +1237,7 @@ directory = "./cache"
                 screen: Some(false),
                 large: Some(true),
                 workers: Some(4),
+                sizes: None,
             }),
             ..Default::default()
         };
@@ -550,6 +1280,61 @@ directory = "./cache"
         assert_eq!(policy.background_ratio, 1.0);
     }
 
+    #[test]
+    fn test_generation_workers_default_and_override() {
+        assert_eq!(
+            Config::default().get_worker_policy().generation_workers,
+            None
+        );
+
+        let config: Config = toml_edit::de::from_str("[server]\ngeneration_workers = 4\n").unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.get_worker_policy().generation_workers, Some(4));
+
+        let zero: Config = toml_edit::de::from_str("[server]\ngeneration_workers = 0\n").unwrap();
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_min_stars_default_env_and_validation() {
+        assert_eq!(Config::default().get_min_stars(), None);
+
+        let mut config: Config = toml_edit::de::from_str("[server]\nmin_stars = 25\n").unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.get_min_stars(), Some(25));
+
+        config
+            .apply_env_from(|name| (name == "PSF_GUARD_MIN_STARS").then(|| "5".to_string()))
+            .unwrap();
+        assert_eq!(config.get_min_stars(), Some(5));
+
+        let zero: Config = toml_edit::de::from_str("[server]\nmin_stars = 0\n").unwrap();
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_clip_fractions_default_env_and_validation() {
+        let defaults = Config::default().get_exposure_thresholds();
+        assert_eq!(defaults.low_clip_fraction, 0.01);
+        assert_eq!(defaults.high_clip_fraction, 0.01);
+
+        let mut config: Config =
+            toml_edit::de::from_str("[server]\nlow_clip_fraction = 0.05\n").unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.get_exposure_thresholds().low_clip_fraction, 0.05);
+
+        config
+            .apply_env_from(|name| {
+                (name == "PSF_GUARD_HIGH_CLIP_FRACTION").then(|| "0.2".to_string())
+            })
+            .unwrap();
+        assert_eq!(config.get_exposure_thresholds().high_clip_fraction, 0.2);
+
+        let out_of_range: Config =
+            toml_edit::de::from_str("[server]\nhigh_clip_fraction = 1.5\n").unwrap();
+        assert!(out_of_range.validate().is_err());
+    }
+
     #[test]
     fn test_worker_ratios_toml_roundtrip() {
         // The knobs live in [server] alongside port/host and round-trip.
@@ -641,6 +1426,116 @@ directory = "./cache"
             .contains("must start with http:// or https://"));
     }
 
+    #[test]
+    fn test_connection_options_default_and_override() {
+        let config = Config::default();
+        let options = config.get_connection_options();
+        assert_eq!(options, crate::db::ConnectionOptions::default());
+        assert!(options.wal);
+
+        let toml = r#"
+[server]
+port = 3000
+sqlite_busy_timeout = "5s"
+sqlite_wal = false
+
+[cache]
+directory = "./cache"
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        let options = config.get_connection_options();
+        assert_eq!(options.busy_timeout, Duration::from_secs(5));
+        assert!(!options.wal);
+    }
+
+    #[test]
+    fn test_fits_read_retry_default_and_override() {
+        assert_eq!(
+            Config::default().get_fits_read_retry(),
+            crate::fits_read::ReadRetryPolicy::DEFAULT
+        );
+
+        let toml = r#"
+[server]
+fits_read_attempts = 5
+fits_read_retry_delay = "1s"
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        config.validate().unwrap();
+        let policy = config.get_fits_read_retry();
+        assert_eq!(policy.attempts, 5);
+        assert_eq!(policy.delay, Duration::from_secs(1));
+
+        let zero: Config = toml_edit::de::from_str("[server]\nfits_read_attempts = 0\n").unwrap();
+        assert!(zero.validate().is_err());
+        let bad: Config =
+            toml_edit::de::from_str("[server]\nfits_read_retry_delay = \"soon\"\n").unwrap();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_request_timeouts_default_and_override() {
+        assert_eq!(
+            Config::default().get_request_timeouts(),
+            crate::server::RequestTimeouts::DEFAULT
+        );
+
+        let toml = r#"
+[server]
+request_timeout = "5s"
+bulk_request_timeout = "1h"
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        config.validate().unwrap();
+        let timeouts = config.get_request_timeouts();
+        assert_eq!(timeouts.interactive, Duration::from_secs(5));
+        assert_eq!(timeouts.bulk, Duration::from_secs(3600));
+
+        let zero: Config = toml_edit::de::from_str("[server]\nrequest_timeout = \"0s\"\n").unwrap();
+        assert!(zero.validate().is_err());
+        let bad: Config =
+            toml_edit::de::from_str("[server]\nbulk_request_timeout = \"later\"\n").unwrap();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_cors_policy_and_host_validation() {
+        let config = Config::default();
+        assert_eq!(config.get_cors_policy().unwrap(), CorsPolicy::Permissive);
+        config.validate_network().unwrap();
+
+        let toml = r#"
+[server]
+host = "192.168.1.20"
+cors_origins = ["https://psf.example.lan/", " http://10.0.0.5:8080"]
+
+[cache]
+directory = "./cache"
+"#;
+        let mut config: Config = toml_edit::de::from_str(toml).unwrap();
+        config.validate_network().unwrap();
+        assert_eq!(
+            config.get_cors_policy().unwrap(),
+            CorsPolicy::AllowList(vec![
+                "https://psf.example.lan".to_string(),
+                "http://10.0.0.5:8080".to_string(),
+            ])
+        );
+
+        config.server.cors_origins = Some(vec!["psf.example.lan".to_string()]);
+        assert!(config.get_cors_policy().is_err());
+        config.server.cors_origins = Some(vec!["https://psf.example.lan/app".to_string()]);
+        assert!(config.get_cors_policy().is_err());
+
+        config.server.cors = Some(false);
+        assert_eq!(config.get_cors_policy().unwrap(), CorsPolicy::Disabled);
+
+        config.server.host = Some("not a host".to_string());
+        assert!(config.validate_network().is_err());
+        config.server.host = Some("::".to_string());
+        config.validate_network().unwrap();
+    }
+
     #[test]
     fn test_humantime_ttl_parsing() {
         let mut config = Config::default();
@@ -679,4 +1574,197 @@ directory = "./cache"
             .to_string()
             .contains("Invalid file_ttl format"));
     }
+
+    #[test]
+    fn test_preview_size_presets() {
+        let toml = r#"
+[preview.sizes]
+screen = 1600
+4k = 3840
+
+[pregeneration]
+enabled = true
+sizes = ["4k"]
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        let sizes = config.get_preview_sizes().unwrap();
+        assert_eq!(sizes.max_dimensions("screen"), Some((1600, 1600)));
+        assert_eq!(sizes.max_dimensions("large"), Some((2000, 2000)));
+        assert_eq!(sizes.max_dimensions("4k"), Some((3840, 3840)));
+        assert_eq!(sizes.max_dimensions("original"), None);
+        // Unknown names fall back to the screen preset.
+        assert_eq!(sizes.max_dimensions("weird"), Some((1600, 1600)));
+        assert_eq!(config.get_pregeneration_sizes().unwrap(), vec!["4k"]);
+        assert_eq!(sizes.resolve("4k"), Ok(Some((3840, 3840))));
+        assert_eq!(sizes.resolve("original"), Ok(None));
+        assert_eq!(
+            sizes.resolve("4K").unwrap_err(),
+            "unknown size '4K': expected one of 4k, large, screen, original"
+        );
+
+        let defaults = Config::default().get_preview_sizes().unwrap();
+        assert_eq!(defaults, PreviewSizes::default());
+        assert_eq!(defaults.max_dimensions("screen"), Some((1200, 1200)));
+    }
+
+    #[test]
+    fn test_pregeneration_sizes_must_be_presets() {
+        let toml = r#"
+[pregeneration]
+enabled = true
+sizes = ["8k"]
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        let err = config.get_pregeneration_sizes().unwrap_err().to_string();
+        assert!(err.contains("'8k'"), "{err}");
+        assert!(config.validate().is_err());
+
+        let reserved: Config =
+            toml_edit::de::from_str("[preview.sizes]\noriginal = 100\n").unwrap();
+        assert!(reserved.get_preview_sizes().is_err());
+    }
+
+    #[test]
+    fn test_reject_reason_mappings() {
+        let toml = r#"
+[[reject_reasons.mappings]]
+category = "Focus"
+regex = '(?i)\bHFR\b'
+
+[[reject_reasons.mappings]]
+category = "Manual"
+exact = "manual"
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        let mapper = config.get_reason_mapper().unwrap();
+        assert_eq!(mapper.categorize(Some("HFR too high")), "Focus");
+        assert_eq!(mapper.categorize(Some("Manual")), "Manual");
+        assert_eq!(mapper.categorize(Some("Clouds")), "other");
+        assert!(Config::default().get_reason_mapper().unwrap().is_empty());
+
+        let bad: Config = toml_edit::de::from_str(
+            "[[reject_reasons.mappings]]\ncategory = \"Focus\"\nregex = \"(\"\n",
+        )
+        .unwrap();
+        assert!(bad.get_reason_mapper().is_err());
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_manual_markers() {
+        let defaults = Config::default().get_manual_markers().unwrap();
+        assert!(defaults.matches(Some("Manual reject")));
+
+        let config: Config =
+            toml_edit::de::from_str("[reject_reasons]\nmanual_markers = [\"by hand\", \"[Me]\"]\n")
+                .unwrap();
+        let markers = config.get_manual_markers().unwrap();
+        assert!(markers.matches(Some("Rejected by hand: satellite")));
+        assert!(markers.matches(Some("[me] soft")));
+        assert!(!markers.matches(Some("Manual reject")));
+
+        let empty: Config =
+            toml_edit::de::from_str("[reject_reasons]\nmanual_markers = []\n").unwrap();
+        assert!(empty.get_manual_markers().is_err());
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_effective_config_fills_defaults() {
+        let toml = r#"
+[server]
+port = 8080
+
+[cache]
+directory = "relative-cache"
+file_ttl = "90s"
+
+[auth]
+token = "secret"
+"#;
+        let config: Config = toml_edit::de::from_str(toml).unwrap();
+        let effective = config.effective().unwrap();
+        assert_eq!(effective.server.port, Some(8080));
+        assert_eq!(effective.server.host.as_deref(), Some("0.0.0.0"));
+        assert_eq!(effective.server.sqlite_wal, Some(true));
+        let cache_dir = effective.cache.directory.unwrap();
+        assert!(Path::new(&cache_dir).is_absolute(), "{cache_dir}");
+        assert!(cache_dir.ends_with("relative-cache"));
+        assert_eq!(effective.cache.file_ttl.as_deref(), Some("1m 30s"));
+        assert_eq!(effective.cache.http_max_age.as_deref(), Some("1day"));
+        let pregeneration = effective.pregeneration.unwrap();
+        assert_eq!(pregeneration.enabled, Some(false));
+        assert_eq!(pregeneration.screen, Some(true));
+        let sizes = effective.preview.unwrap().sizes.unwrap();
+        assert_eq!(sizes.get("screen"), Some(&1200));
+        assert_eq!(effective.auth.unwrap().token.as_deref(), Some("<redacted>"));
+    }
+
+    #[test]
+    fn test_env_overrides_file_but_not_cli() {
+        let toml = r#"
+[server]
+port = 4000
+host = "192.168.1.20"
+
+[cache]
+directory = "/file/cache"
+file_ttl = "10m"
+"#;
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("PSF_GUARD_DATABASE_PATH", "/data/database.sqlite"),
+            ("PSF_GUARD_IMAGE_DIRS", "/images/a, /images/b,"),
+            ("PSF_GUARD_PORT", "5000"),
+            ("PSF_GUARD_CACHE_DIR", "/env/cache"),
+            ("PSF_GUARD_SQLITE_WAL", "false"),
+            ("PSF_GUARD_PREGENERATION_ENABLED", "yes"),
+            ("PSF_GUARD_HOST", ""),
+        ]);
+        let lookup = |name: &str| env.get(name).map(|value| value.to_string());
+
+        let mut config: Config = toml_edit::de::from_str(toml).unwrap();
+        config.apply_env_from(lookup).unwrap();
+        assert_eq!(config.get_port(), 5000);
+        // Blank env values leave the file value alone.
+        assert_eq!(config.get_host(), "192.168.1.20");
+        assert_eq!(config.get_cache_directory(), "/env/cache");
+        assert_eq!(config.get_file_ttl(), Duration::from_secs(600));
+        assert!(!config.get_connection_options().wal);
+        assert_eq!(config.get_pregeneration().unwrap().enabled, Some(true));
+        assert_eq!(
+            config.database.as_ref().unwrap().path,
+            "/data/database.sqlite"
+        );
+        assert_eq!(
+            config.images.as_ref().unwrap().directories,
+            vec!["/images/a", "/images/b"]
+        );
+
+        config.merge_with_cli(
+            Some("/cli/database.sqlite".to_string()),
+            None,
+            Some(6000),
+            None,
+            Some("/cli/cache".to_string()),
+        );
+        assert_eq!(config.get_port(), 6000);
+        assert_eq!(config.get_cache_directory(), "/cli/cache");
+        assert_eq!(config.database.unwrap().path, "/cli/database.sqlite");
+        // No CLI image dirs: the env ones stand.
+        assert_eq!(config.images.unwrap().directories.len(), 2);
+    }
+
+    #[test]
+    fn test_env_rejects_unparseable_values() {
+        let mut config = Config::default();
+        let err = config
+            .apply_env_from(|name| (name == "PSF_GUARD_PORT").then(|| "http".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("PSF_GUARD_PORT=http"), "{err}");
+
+        let err = config
+            .apply_env_from(|name| (name == "PSF_GUARD_CORS").then(|| "maybe".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("PSF_GUARD_CORS"), "{err}");
+    }
 }
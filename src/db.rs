@@ -1,10 +1,118 @@
 use crate::models::{
-    AcquiredImage, GradingStatus, OverallDesiredStats, OverallStats, Profile, Project,
-    ProjectDesiredStats, ProjectOverviewStats, ProjectWithProfile, RecentImageSummary, Target,
-    TargetWithDesiredStats, TargetWithStats,
+    AcquiredImage, AcquisitionSession, FilterIntegration, GradingStatus, IntegrationTime,
+    OverallDesiredStats, OverallStats, Profile, Project, ProjectDesiredStats, ProjectOverviewStats,
+    ProjectWithProfile, RecentImageSummary, Target, TargetWithDesiredStats, TargetWithStats,
 };
-use anyhow::{Context, Result};
+use crate::reject_reasons::ManualMarkers;
+use anyhow::{bail, Context, Result};
+use rusqlite::types::Value;
 use rusqlite::{params, Connection};
+use std::path::Path;
+use std::time::Duration;
+
+/// How long a connection waits on SQLITE_BUSY before giving up. The server
+/// creates read/write contention itself: the background file-check refresh
+/// runs on its own dedicated connection (so its slow queries never hold the
+/// request mutex), and each of its streaming reads holds a shared lock for the
+/// statement's duration — 30-80s per project on an SMB-mounted DB. Without a
+/// busy handler, a grade written during a refresh surfaces instantly as
+/// "database is locked" → HTTP 500; with one, the writer waits out the current
+/// refresh statement and wins the gap between statements. Sized to cover the
+/// worst observed single-query duration. (External writers like N.I.N.A. on a
+/// network share get the same courtesy.)
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Tuning applied to every scheduler DB connection right after it is opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    /// SQLite busy handler timeout (see [`DEFAULT_BUSY_TIMEOUT`]).
+    pub busy_timeout: Duration,
+    /// Switch the database to `journal_mode=WAL`, so readers no longer block
+    /// the writer at commit. WAL is persistent in the file and needs write
+    /// access to the DB's directory for the `-wal`/`-shm` side files; it also
+    /// must not be used when another HOST opens the same file over a network
+    /// share, so it can be turned off.
+    pub wal: bool,
+    /// Open with `SQLITE_OPEN_READ_ONLY` (`--read-only`), for browsing a live
+    /// database N.I.N.A. is writing to without any risk to it. Implies no WAL
+    /// switch, which would itself be a write.
+    pub read_only: bool,
+}
+
+impl ConnectionOptions {
+    pub const DEFAULT: Self = Self {
+        busy_timeout: DEFAULT_BUSY_TIMEOUT,
+        wal: true,
+        read_only: false,
+    };
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Apply `options` to a freshly opened connection. The busy timeout is
+/// mandatory; WAL is best-effort. A read-only connection, a read-only
+/// directory, or a filesystem without shared-memory support leaves the
+/// database in its existing journal mode — that is reported back as
+/// `Ok(Some(warning))` for the caller to log rather than failing the open.
+pub fn configure_connection(
+    conn: &Connection,
+    options: &ConnectionOptions,
+) -> rusqlite::Result<Option<String>> {
+    conn.busy_timeout(options.busy_timeout)?;
+    if !options.wal || options.read_only {
+        return Ok(None);
+    }
+    let mode =
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0));
+    Ok(match mode {
+        Ok(mode) if mode.eq_ignore_ascii_case("wal") => None,
+        Ok(mode) => Some(format!(
+            "could not enable WAL; database stays in journal_mode={}",
+            mode
+        )),
+        Err(e) => Some(format!(
+            "could not enable WAL ({}); using the default journal mode",
+            e
+        )),
+    })
+}
+
+/// Open an existing scheduler database for a CLI command with the default
+/// [`ConnectionOptions`]. A missing file is an error, never a new database;
+/// `read_only` opens `SQLITE_OPEN_READ_ONLY` so any write the command
+/// attempts fails instead of touching the file. The schema is checked before
+/// anything (such as switching to WAL) writes to the file, so a non-scheduler
+/// database passed by mistake is rejected untouched.
+pub fn open_connection(path: impl AsRef<Path>, read_only: bool) -> Result<Connection> {
+    use rusqlite::OpenFlags;
+
+    let path = path.as_ref();
+    let access = if read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+    };
+    let conn = Connection::open_with_flags(
+        path,
+        access | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open database: {}", path.display()))?;
+    Database::new(&conn)
+        .detect_schema()
+        .with_context(|| format!("Checking database schema: {}", path.display()))?;
+    let options = ConnectionOptions {
+        read_only,
+        ..ConnectionOptions::default()
+    };
+    if let Some(warning) = configure_connection(&conn, &options)? {
+        eprintln!("⚠️  {}: {}", path.display(), warning);
+    }
+    Ok(conn)
+}
 
 /// Schema version detection - checks if guid columns exist
 #[derive(Debug, Clone, Copy, Default)]
@@ -39,16 +147,453 @@ impl SchemaCapabilities {
     }
 }
 
+/// Tables and columns every `Database` query relies on. Anything missing here
+/// means the queries would fail or silently return nothing. Columns whose name
+/// changed between plugin versions are checked through [`ColumnMap`] instead.
+const REQUIRED_COLUMNS: &[(&str, &[&str])] = &[
+    ("project", &["Id", "profileId", "name"]),
+    (
+        "target",
+        &["Id", "projectId", "name", "active", "ra", "dec"],
+    ),
+    (
+        "acquiredimage",
+        &["Id", "projectId", "targetId", "filtername", "metadata"],
+    ),
+];
+
+/// Target Scheduler schema layouts PSF Guard knows how to query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaVariant {
+    /// Plugin schema v10–v16: grading state lives in `acquiredimage.accepted`.
+    Legacy,
+    /// Plugin schema v17–v21: `accepted` renamed to `gradingStatus`, no
+    /// `guid` columns.
+    Graded,
+    /// Plugin schema v22+: adds stable `guid` columns on the core tables.
+    Guid,
+}
+
+impl SchemaVariant {
+    /// Classify a database from its columns. `user_version` is not trusted
+    /// here: hand-built and test databases often leave it at 0.
+    pub fn detect(conn: &Connection, capabilities: &SchemaCapabilities) -> Self {
+        if capabilities.has_acquiredimage_guid {
+            SchemaVariant::Guid
+        } else if !SchemaCapabilities::table_has_column(conn, "acquiredimage", "gradingStatus")
+            && SchemaCapabilities::table_has_column(conn, "acquiredimage", "accepted")
+        {
+            SchemaVariant::Legacy
+        } else {
+            SchemaVariant::Graded
+        }
+    }
+}
+
+/// `acquiredimage` column names whose spelling depends on the plugin schema
+/// version. `Database` queries are written against `{acquired_date}`,
+/// `{grading_status}` and `{reject_reason}` placeholders and expanded through
+/// [`ColumnMap::sql`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub acquired_date: &'static str,
+    pub grading_status: &'static str,
+    pub reject_reason: &'static str,
+}
+
+impl ColumnMap {
+    /// Plugin schema v17 and later.
+    pub const CURRENT: Self = Self {
+        acquired_date: "acquireddate",
+        grading_status: "gradingStatus",
+        reject_reason: "rejectreason",
+    };
+
+    /// Plugin schema v10–v16, before migration 17 renamed `accepted`. Values
+    /// are read as stored; the plugin's own v17 upgrade also repairs some
+    /// grades, so counts can shift once the database is opened in N.I.N.A.
+    pub const LEGACY: Self = Self {
+        grading_status: "accepted",
+        ..Self::CURRENT
+    };
+
+    pub fn for_variant(variant: SchemaVariant) -> Self {
+        match variant {
+            SchemaVariant::Legacy => Self::LEGACY,
+            SchemaVariant::Graded | SchemaVariant::Guid => Self::CURRENT,
+        }
+    }
+
+    /// Expand the column placeholders in a SQL template.
+    pub fn sql(&self, template: &str) -> String {
+        template
+            .replace("{acquired_date}", self.acquired_date)
+            .replace("{grading_status}", self.grading_status)
+            .replace("{reject_reason}", self.reject_reason)
+    }
+}
+
+/// Result of [`Database::detect_schema`].
+#[derive(Debug, Clone, Copy)]
+pub struct DetectedSchema {
+    /// `PRAGMA user_version` as set by the plugin's migrations; 0 for a
+    /// database that never ran them (hand-built or test fixtures).
+    pub user_version: i64,
+    pub variant: SchemaVariant,
+    pub capabilities: SchemaCapabilities,
+}
+
+impl std::fmt::Display for DetectedSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variant = match self.variant {
+            SchemaVariant::Legacy => "legacy `accepted` column",
+            SchemaVariant::Graded => "no guid columns",
+            SchemaVariant::Guid => "guid columns",
+        };
+        if self.user_version > 0 {
+            write!(
+                f,
+                "Target Scheduler schema v{} ({})",
+                self.user_version, variant
+            )
+        } else {
+            write!(f, "unversioned Target Scheduler schema ({})", variant)
+        }
+    }
+}
+
+/// Order of the image listing. Every order ends in an id tiebreak so that
+/// paging and neighbor lookups see one stable sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageSort {
+    /// Most recently acquired first (the listing's historical order).
+    #[default]
+    Newest,
+    Oldest,
+    /// Grouped by filter name, newest first within each filter.
+    Filter,
+}
+
+impl ImageSort {
+    /// Parse the `sort_by` query value.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "newest" => Some(Self::Newest),
+            "oldest" => Some(Self::Oldest),
+            "filter" => Some(Self::Filter),
+            _ => None,
+        }
+    }
+
+    /// `ORDER BY` template (column placeholders unexpanded).
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::Newest => "ai.{acquired_date} DESC, ai.Id DESC",
+            Self::Oldest => "ai.{acquired_date} ASC, ai.Id ASC",
+            Self::Filter => "ai.filtername ASC, ai.{acquired_date} DESC, ai.Id DESC",
+        }
+    }
+}
+
+/// A condition on one key of an image's metadata JSON, evaluated in SQLite
+/// with `json_extract`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataFilter {
+    /// Text equality, e.g. `Camera` or `FilterName`.
+    Equals { key: String, value: String },
+    /// Inclusive numeric bounds, e.g. `HFR` at most 2.5. Images without the
+    /// key never match.
+    Range {
+        key: String,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+}
+
+impl MetadataFilter {
+    fn key(&self) -> &str {
+        match self {
+            Self::Equals { key, .. } | Self::Range { key, .. } => key,
+        }
+    }
+
+    /// JSON path for the key, quoted so keys with dots or spaces address a
+    /// single member. Bound as a parameter, never spliced into the SQL.
+    fn json_path(&self) -> String {
+        format!("$.\"{}\"", self.key().replace('"', ""))
+    }
+}
+
+/// Light or calibration frames, by the metadata's `ImageType` (IMAGETYP).
+/// Images the scheduler recorded carry none and are lights. The importer
+/// skips calibration frames, so those only appear in catalogs written by
+/// other tools; grading, scoring and previews leave them out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageTypeFilter {
+    Light,
+    Calibration,
+}
+
+impl ImageTypeFilter {
+    /// Mirrors [`crate::image_analysis::FrameType::is_calibration`].
+    const CALIBRATION_SQL: &'static str =
+        "(UPPER(COALESCE(json_extract(ai.metadata, '$.ImageType'), '')) LIKE '%DARK%'
+          OR UPPER(COALESCE(json_extract(ai.metadata, '$.ImageType'), '')) LIKE '%FLAT%'
+          OR UPPER(COALESCE(json_extract(ai.metadata, '$.ImageType'), '')) LIKE '%BIAS%'
+          OR UPPER(COALESCE(json_extract(ai.metadata, '$.ImageType'), '')) LIKE '%OFFSET%'
+          OR UPPER(COALESCE(json_extract(ai.metadata, '$.ImageType'), '')) = 'ZERO')";
+
+    /// `light` or `calibration`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "light" => Some(Self::Light),
+            "calibration" => Some(Self::Calibration),
+            _ => None,
+        }
+    }
+}
+
+/// Which images a listing covers. Every set field narrows the result.
+#[derive(Debug, Clone, Default)]
+pub struct ImageFilter {
+    pub status: Option<GradingStatus>,
+    pub project_id: Option<i32>,
+    pub target_id: Option<i32>,
+    /// Substring match on the project name.
+    pub project_name: Option<String>,
+    /// Substring match on the target name.
+    pub target_name: Option<String>,
+    /// Only images acquired at or after this Unix time.
+    pub acquired_since: Option<i64>,
+    pub metadata: Vec<MetadataFilter>,
+    /// Only images flagged as favorites (`psf_guard_favorite`).
+    pub favorites_only: bool,
+    /// Only images carrying this tag (`psf_guard_image_tag`, case-insensitive).
+    pub tag: Option<String>,
+    pub image_type: Option<ImageTypeFilter>,
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImagePage {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Composes the `WHERE`/`ORDER BY`/`LIMIT` of image queries from an
+/// [`ImageFilter`], [`ImageSort`] and optional [`ImagePage`], so the listing,
+/// the CLI's name-based queries and neighbor lookups agree on what "the
+/// filtered list" is. Queries select from `acquiredimage ai` joined to
+/// `project p` and `target t`.
+#[derive(Debug, Clone)]
+pub struct ImageQueryBuilder<'a> {
+    columns: ColumnMap,
+    filter: &'a ImageFilter,
+    sort: ImageSort,
+    page: Option<ImagePage>,
+}
+
+impl<'a> ImageQueryBuilder<'a> {
+    pub fn new(columns: ColumnMap, filter: &'a ImageFilter) -> Self {
+        Self {
+            columns,
+            filter,
+            sort: ImageSort::default(),
+            page: None,
+        }
+    }
+
+    pub fn sort(mut self, sort: ImageSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn page(mut self, page: Option<ImagePage>) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// `FROM ... WHERE ...` for the filter, with its parameters in order.
+    pub fn filter_clause(&self) -> (String, Vec<Value>) {
+        let filter = self.filter;
+        let mut sql = String::from(
+            "FROM acquiredimage ai
+             JOIN project p ON ai.projectId = p.Id
+             JOIN target t ON ai.targetId = t.Id
+             WHERE 1=1",
+        );
+        let mut params = Vec::new();
+
+        if let Some(status) = filter.status {
+            sql.push_str(" AND ai.{grading_status} = ?");
+            params.push(Value::Integer(status as i64));
+        }
+        if let Some(project_id) = filter.project_id {
+            sql.push_str(" AND ai.projectId = ?");
+            params.push(Value::Integer(project_id.into()));
+        }
+        if let Some(target_id) = filter.target_id {
+            sql.push_str(" AND ai.targetId = ?");
+            params.push(Value::Integer(target_id.into()));
+        }
+        if let Some(project) = &filter.project_name {
+            sql.push_str(" AND p.name LIKE ?");
+            params.push(Value::Text(format!("%{}%", project)));
+        }
+        if let Some(target) = &filter.target_name {
+            sql.push_str(" AND t.name LIKE ?");
+            params.push(Value::Text(format!("%{}%", target)));
+        }
+        if let Some(cutoff) = filter.acquired_since {
+            sql.push_str(" AND ai.{acquired_date} >= ?");
+            params.push(Value::Integer(cutoff));
+        }
+        if filter.favorites_only {
+            sql.push_str(" AND ai.Id IN (SELECT acquired_image_id FROM psf_guard_favorite)");
+        }
+        if let Some(tag) = &filter.tag {
+            sql.push_str(
+                " AND ai.Id IN (SELECT acquired_image_id FROM psf_guard_image_tag WHERE tag = ?)",
+            );
+            params.push(Value::Text(tag.clone()));
+        }
+        match filter.image_type {
+            Some(ImageTypeFilter::Light) => {
+                sql.push_str(" AND NOT ");
+                sql.push_str(ImageTypeFilter::CALIBRATION_SQL);
+            }
+            Some(ImageTypeFilter::Calibration) => {
+                sql.push_str(" AND ");
+                sql.push_str(ImageTypeFilter::CALIBRATION_SQL);
+            }
+            None => {}
+        }
+        for condition in &filter.metadata {
+            let path = Value::Text(condition.json_path());
+            match condition {
+                MetadataFilter::Equals { value, .. } => {
+                    sql.push_str(" AND json_extract(ai.metadata, ?) = ?");
+                    params.push(path);
+                    params.push(Value::Text(value.clone()));
+                }
+                MetadataFilter::Range { min, max, .. } => {
+                    if let Some(min) = min {
+                        sql.push_str(" AND CAST(json_extract(ai.metadata, ?) AS REAL) >= ?");
+                        params.push(path.clone());
+                        params.push(Value::Real(*min));
+                    }
+                    if let Some(max) = max {
+                        sql.push_str(" AND CAST(json_extract(ai.metadata, ?) AS REAL) <= ?");
+                        params.push(path.clone());
+                        params.push(Value::Real(*max));
+                    }
+                    if min.is_none() && max.is_none() {
+                        sql.push_str(" AND json_extract(ai.metadata, ?) IS NOT NULL");
+                        params.push(path);
+                    }
+                }
+            }
+        }
+
+        (self.columns.sql(&sql), params)
+    }
+
+    /// The `ORDER BY` expression, without the keywords.
+    pub fn order_by(&self) -> String {
+        self.columns.sql(self.sort.order_by())
+    }
+
+    /// Full listing query selecting `columns` (an expanded select list).
+    pub fn select(&self, columns: &str) -> (String, Vec<Value>) {
+        let (from_where, mut params) = self.filter_clause();
+        let mut sql = format!(
+            "SELECT {} {} ORDER BY {}",
+            columns,
+            from_where,
+            self.order_by()
+        );
+        if let Some(page) = self.page {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            params.push(Value::Integer(page.limit as i64));
+            params.push(Value::Integer(page.offset as i64));
+        }
+        (sql, params)
+    }
+
+    /// `(prev_id, next_id)` of one image within the sorted, filtered list;
+    /// no row when the image isn't in it. Paging doesn't apply.
+    pub fn neighbors(&self, image_id: i32) -> (String, Vec<Value>) {
+        let (from_where, mut params) = self.filter_clause();
+        let order = self.order_by();
+        let sql = format!(
+            "SELECT prev_id, next_id FROM (
+                 SELECT ai.Id AS id,
+                        LAG(ai.Id) OVER (ORDER BY {order}) AS prev_id,
+                        LEAD(ai.Id) OVER (ORDER BY {order}) AS next_id
+                 {from_where}
+             ) WHERE id = ?"
+        );
+        params.push(Value::Integer(image_id.into()));
+        (sql, params)
+    }
+}
+
+/// Why [`Database::merge_projects`] or [`Database::merge_targets`] refused
+/// to run. Any other error from them is a database failure.
+#[derive(Debug)]
+pub enum MergeRejected {
+    /// The source or destination row doesn't exist.
+    NotFound(String),
+    /// The rows exist but can't be merged (same row, or different profile
+    /// or project).
+    Invalid(String),
+}
+
+impl MergeRejected {
+    fn project_not_found(project_id: i32) -> Self {
+        MergeRejected::NotFound(format!("project {} not found", project_id))
+    }
+
+    fn target_not_found(target_id: i32) -> Self {
+        MergeRejected::NotFound(format!("target {} not found", target_id))
+    }
+}
+
+impl std::fmt::Display for MergeRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeRejected::NotFound(message) | MergeRejected::Invalid(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeRejected {}
+
 /// Database access layer for PSF Guard
 pub struct Database<'a> {
     conn: &'a Connection,
     schema: SchemaCapabilities,
+    variant: SchemaVariant,
+    columns: ColumnMap,
 }
 
 impl<'a> Database<'a> {
     pub fn new(conn: &'a Connection) -> Self {
         let schema = SchemaCapabilities::detect(conn);
-        Database { conn, schema }
+        let variant = SchemaVariant::detect(conn, &schema);
+        Database {
+            conn,
+            schema,
+            variant,
+            columns: ColumnMap::for_variant(variant),
+        }
+    }
+
+    /// Column names used for this database's schema variant
+    pub fn columns(&self) -> &ColumnMap {
+        &self.columns
     }
 
     /// Get schema capabilities for this database
@@ -56,6 +601,60 @@ impl<'a> Database<'a> {
         &self.schema
     }
 
+    /// Check that the database carries every table and column the queries
+    /// need and classify its layout. The error lists everything missing at
+    /// once, so an unsupported or non-scheduler file is reported up front
+    /// instead of surfacing later as empty results.
+    pub fn detect_schema(&self) -> Result<DetectedSchema> {
+        let mut missing = Vec::new();
+        for (table, columns) in REQUIRED_COLUMNS {
+            let present = self.table_columns(table)?;
+            if present.is_empty() {
+                missing.push(format!("table `{}`", table));
+                continue;
+            }
+            let mapped: &[&str] = if *table == "acquiredimage" {
+                &[self.columns.acquired_date, self.columns.grading_status]
+            } else {
+                &[]
+            };
+            for column in columns.iter().chain(mapped) {
+                if !present.iter().any(|c| c.eq_ignore_ascii_case(column)) {
+                    missing.push(format!("`{}.{}`", table, column));
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Unsupported N.I.N.A. Target Scheduler database schema: missing {}. \
+                 Check that this is a Target Scheduler database and open it once in \
+                 a recent N.I.N.A. + Target Scheduler version to migrate it.",
+                missing.join(", ")
+            ));
+        }
+
+        let user_version: i64 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .context("reading PRAGMA user_version")?;
+        Ok(DetectedSchema {
+            user_version,
+            variant: self.variant,
+            capabilities: self.schema,
+        })
+    }
+
+    /// Column names of `table`; empty when the table does not exist.
+    fn table_columns(&self, table: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let columns = stmt
+            .query_map([table], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns)
+    }
+
     // Profile queries
     pub fn get_all_profiles(&self) -> Result<Vec<Profile>> {
         let mut stmt = self.conn.prepare(
@@ -206,30 +805,54 @@ impl<'a> Database<'a> {
             .with_context(|| format!("Project '{}' not found", name))
     }
 
+    /// Id of the target named `name`, within `project_id` when given. A name
+    /// shared by several targets is an error unless the project narrows it.
+    pub fn find_target_id_by_name(&self, name: &str, project_id: Option<i32>) -> Result<i32> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT Id FROM target WHERE name = ?1 AND (?2 IS NULL OR projectid = ?2)")?;
+        let ids = stmt
+            .query_map(params![name, project_id], |row| row.get::<_, i32>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        match ids.as_slice() {
+            [id] => Ok(*id),
+            [] => bail!("Target '{}' not found", name),
+            _ => bail!(
+                "{} targets are named '{}'; pass a project to pick one",
+                ids.len(),
+                name
+            ),
+        }
+    }
+
     // Target queries
     pub fn get_targets_with_stats(&self, project_id: i32) -> Result<Vec<(Target, i32, i32, i32)>> {
         let query = if self.schema.has_target_guid {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.guid,
+            self.columns.sql(
+                "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.guid,
                     COUNT(ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count
              FROM target t
              LEFT JOIN acquiredimage ai ON t.Id = ai.targetId
              WHERE t.projectid = ?
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec, t.guid
-             ORDER BY t.name"
+             ORDER BY t.name",
+            )
         } else {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec,
+            self.columns.sql(
+                "SELECT t.Id, t.name, t.active, t.ra, t.dec,
                     COUNT(ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count
              FROM target t
              LEFT JOIN acquiredimage ai ON t.Id = ai.targetId
              WHERE t.projectid = ?
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec
-             ORDER BY t.name"
+             ORDER BY t.name",
+            )
         };
-        let mut stmt = self.conn.prepare(query)?;
+        let mut stmt = self.conn.prepare(&query)?;
         let has_guid = self.schema.has_target_guid;
 
         let targets = stmt
@@ -261,29 +884,33 @@ impl<'a> Database<'a> {
 
     pub fn get_targets_with_images(&self, project_id: i32) -> Result<Vec<(Target, i32, i32, i32)>> {
         let query = if self.schema.has_target_guid {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.guid,
+            self.columns.sql(
+                "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.guid,
                     COUNT(ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count
              FROM target t
              INNER JOIN acquiredimage ai ON t.Id = ai.targetId
              WHERE t.projectid = ?
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec, t.guid
              HAVING COUNT(ai.Id) > 0
-             ORDER BY t.name"
+             ORDER BY t.name",
+            )
         } else {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec,
+            self.columns.sql(
+                "SELECT t.Id, t.name, t.active, t.ra, t.dec,
                     COUNT(ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count
              FROM target t
              INNER JOIN acquiredimage ai ON t.Id = ai.targetId
              WHERE t.projectid = ?
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec
              HAVING COUNT(ai.Id) > 0
-             ORDER BY t.name"
+             ORDER BY t.name",
+            )
         };
-        let mut stmt = self.conn.prepare(query)?;
+        let mut stmt = self.conn.prepare(&query)?;
         let has_guid = self.schema.has_target_guid;
 
         let targets = stmt
@@ -319,25 +946,29 @@ impl<'a> Database<'a> {
         project_id: i32,
     ) -> Result<Vec<(AcquiredImage, String, String)>> {
         let query = if self.schema.has_acquiredimage_guid {
-            "SELECT ai.Id, ai.projectId, ai.targetId, ai.acquireddate, ai.filtername,
-                    ai.gradingStatus, ai.metadata, ai.rejectreason, ai.profileId, ai.guid,
+            self.columns.sql(
+                "SELECT ai.Id, ai.projectId, ai.targetId, ai.{acquired_date}, ai.filtername,
+                    ai.{grading_status}, ai.metadata, ai.{reject_reason}, ai.profileId, ai.guid,
                     p.name as project_name, t.name as target_name
              FROM acquiredimage ai
              JOIN project p ON ai.projectId = p.Id
              JOIN target t ON ai.targetId = t.Id
              WHERE ai.projectId = ?
-             ORDER BY ai.acquireddate DESC"
+             ORDER BY ai.{acquired_date} DESC",
+            )
         } else {
-            "SELECT ai.Id, ai.projectId, ai.targetId, ai.acquireddate, ai.filtername,
-                    ai.gradingStatus, ai.metadata, ai.rejectreason, ai.profileId,
+            self.columns.sql(
+                "SELECT ai.Id, ai.projectId, ai.targetId, ai.{acquired_date}, ai.filtername,
+                    ai.{grading_status}, ai.metadata, ai.{reject_reason}, ai.profileId,
                     p.name as project_name, t.name as target_name
              FROM acquiredimage ai
              JOIN project p ON ai.projectId = p.Id
              JOIN target t ON ai.targetId = t.Id
              WHERE ai.projectId = ?
-             ORDER BY ai.acquireddate DESC"
+             ORDER BY ai.{acquired_date} DESC",
+            )
         };
-        let mut stmt = self.conn.prepare(query)?;
+        let mut stmt = self.conn.prepare(&query)?;
         let has_guid = self.schema.has_acquiredimage_guid;
 
         let rows = stmt.query_map([project_id], |row| {
@@ -377,51 +1008,81 @@ impl<'a> Database<'a> {
         limit: Option<usize>,
         offset: usize,
     ) -> Result<Vec<(AcquiredImage, String, String)>> {
-        let has_guid = self.schema.has_acquiredimage_guid;
-        let base_select = if has_guid {
-            "SELECT ai.Id, ai.projectId, ai.targetId, ai.acquireddate, ai.filtername,
-                    ai.gradingStatus, ai.metadata, ai.rejectreason, ai.profileId, ai.guid,
-                    p.name as project_name, t.name as target_name
-             FROM acquiredimage ai
-             JOIN project p ON ai.projectId = p.Id
-             JOIN target t ON ai.targetId = t.Id
-             WHERE 1=1"
-        } else {
-            "SELECT ai.Id, ai.projectId, ai.targetId, ai.acquireddate, ai.filtername,
-                    ai.gradingStatus, ai.metadata, ai.rejectreason, ai.profileId,
-                    p.name as project_name, t.name as target_name
-             FROM acquiredimage ai
-             JOIN project p ON ai.projectId = p.Id
-             JOIN target t ON ai.targetId = t.Id
-             WHERE 1=1"
+        let filter = ImageFilter {
+            status: status_filter,
+            project_id,
+            target_id,
+            ..ImageFilter::default()
         };
-        let mut query = String::from(base_select);
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let page = limit.map(|limit| ImagePage { limit, offset });
+        self.find_images(&filter, ImageSort::default(), page)
+    }
 
-        if let Some(status) = status_filter {
-            query.push_str(" AND ai.gradingStatus = ?");
-            params.push(Box::new(status as i32));
-        }
-        if let Some(project_id) = project_id {
-            query.push_str(" AND ai.projectId = ?");
-            params.push(Box::new(project_id));
-        }
-        if let Some(target_id) = target_id {
-            query.push_str(" AND ai.targetId = ?");
-            params.push(Box::new(target_id));
-        }
+    /// [`Self::query_images`] without calibration frames: what grading and
+    /// batch analysis measure.
+    pub fn query_light_images(
+        &self,
+        status_filter: Option<GradingStatus>,
+        project_filter: Option<&str>,
+        target_filter: Option<&str>,
+        date_cutoff: Option<i64>,
+    ) -> Result<Vec<(AcquiredImage, String, String)>> {
+        let filter = ImageFilter {
+            status: status_filter,
+            project_name: project_filter.map(str::to_string),
+            target_name: target_filter.map(str::to_string),
+            acquired_since: date_cutoff,
+            image_type: Some(ImageTypeFilter::Light),
+            ..ImageFilter::default()
+        };
+        self.find_images(&filter, ImageSort::default(), None)
+    }
+
+    /// Every light frame of a project and/or target: what sequence scoring,
+    /// previews and per-target charts cover.
+    pub fn query_light_images_scoped(
+        &self,
+        project_id: Option<i32>,
+        target_id: Option<i32>,
+    ) -> Result<Vec<(AcquiredImage, String, String)>> {
+        let filter = ImageFilter {
+            project_id,
+            target_id,
+            image_type: Some(ImageTypeFilter::Light),
+            ..ImageFilter::default()
+        };
+        self.find_images(&filter, ImageSort::default(), None)
+    }
 
-        query.push_str(" ORDER BY ai.acquireddate DESC");
-        if let Some(limit) = limit {
-            query.push_str(" LIMIT ? OFFSET ?");
-            params.push(Box::new(limit as i64));
-            params.push(Box::new(offset as i64));
+    /// Images matching `filter` in `sort` order, with project and target
+    /// names. The query behind the image listing.
+    pub fn find_images(
+        &self,
+        filter: &ImageFilter,
+        sort: ImageSort,
+        page: Option<ImagePage>,
+    ) -> Result<Vec<(AcquiredImage, String, String)>> {
+        if !self.has_filter_tables(filter) {
+            return Ok(Vec::new());
         }
+        let has_guid = self.schema.has_acquiredimage_guid;
+        let select_list = self.columns.sql(if has_guid {
+            "ai.Id, ai.projectId, ai.targetId, ai.{acquired_date}, ai.filtername,
+                    ai.{grading_status}, ai.metadata, ai.{reject_reason}, ai.profileId, ai.guid,
+                    p.name as project_name, t.name as target_name"
+        } else {
+            "ai.Id, ai.projectId, ai.targetId, ai.{acquired_date}, ai.filtername,
+                    ai.{grading_status}, ai.metadata, ai.{reject_reason}, ai.profileId,
+                    p.name as project_name, t.name as target_name"
+        });
+        let (query, params) = ImageQueryBuilder::new(self.columns, filter)
+            .sort(sort)
+            .page(page)
+            .select(&select_list);
 
         let mut stmt = self.conn.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
         let images = stmt
-            .query_map(param_refs.as_slice(), |row| {
+            .query_map(rusqlite::params_from_iter(&params), |row| {
                 let (guid, name_offset) = if has_guid {
                     (row.get(9)?, 10)
                 } else {
@@ -449,6 +1110,31 @@ impl<'a> Database<'a> {
         Ok(images)
     }
 
+    /// The images either side of `image_id` in the listing with the same
+    /// filter and order, as `(previous, next)`. `None` when the image isn't in
+    /// that listing at all; an end of the list is a `None` inside the pair.
+    pub fn get_image_neighbors(
+        &self,
+        image_id: i32,
+        filter: &ImageFilter,
+        sort: ImageSort,
+    ) -> Result<Option<(Option<i32>, Option<i32>)>> {
+        use rusqlite::OptionalExtension;
+
+        if !self.has_filter_tables(filter) {
+            return Ok(None);
+        }
+        let (query, params) = ImageQueryBuilder::new(self.columns, filter)
+            .sort(sort)
+            .neighbors(image_id);
+        self.conn
+            .query_row(&query, rusqlite::params_from_iter(&params), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()
+            .map_err(Into::into)
+    }
+
     /// Fetch the newest few images for every project in one query.
     ///
     /// The overview uses these small records for its thumbnail strip. Keeping
@@ -462,22 +1148,22 @@ impl<'a> Database<'a> {
             return Ok(Vec::new());
         }
 
-        let mut stmt = self.conn.prepare(
-            "SELECT Id, projectId, targetId, target_name, acquireddate,
-                    filtername, gradingStatus
+        let mut stmt = self.conn.prepare(&self.columns.sql(
+            "SELECT Id, projectId, targetId, target_name, {acquired_date},
+                    filtername, {grading_status}
              FROM (
                  SELECT ai.Id, ai.projectId, ai.targetId, t.name AS target_name,
-                        ai.acquireddate, ai.filtername, ai.gradingStatus,
+                        ai.{acquired_date}, ai.filtername, ai.{grading_status},
                         ROW_NUMBER() OVER (
                             PARTITION BY ai.projectId
-                            ORDER BY ai.acquireddate DESC, ai.Id DESC
+                            ORDER BY ai.{acquired_date} DESC, ai.Id DESC
                         ) AS recent_rank
                  FROM acquiredimage ai
                  JOIN target t ON ai.targetId = t.Id
              )
              WHERE recent_rank <= ?
-             ORDER BY projectId, acquireddate DESC, Id DESC",
-        )?;
+             ORDER BY projectId, {acquired_date} DESC, Id DESC",
+        ))?;
 
         let rows = stmt.query_map([limit_per_project as i64], |row| {
             Ok(RecentImageSummary {
@@ -494,87 +1180,106 @@ impl<'a> Database<'a> {
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
-    pub fn query_images(
+    /// Group images into acquisition sessions: runs on the same target and
+    /// filter whose consecutive frames are at most `gap_minutes` apart (the
+    /// same rule `SequenceAnalyzer` uses). Images without an acquired date
+    /// can't be placed and are skipped. Newest sessions come first.
+    pub fn get_sessions(
         &self,
-        status_filter: Option<GradingStatus>,
-        project_filter: Option<&str>,
-        target_filter: Option<&str>,
-        date_cutoff: Option<i64>,
-    ) -> Result<Vec<(AcquiredImage, String, String)>> {
-        let has_guid = self.schema.has_acquiredimage_guid;
-        let base_select = if has_guid {
-            "SELECT ai.Id, ai.projectId, ai.targetId, ai.acquireddate, ai.filtername,
-                    ai.gradingStatus, ai.metadata, ai.rejectreason, ai.profileId, ai.guid,
-                    p.name as project_name, t.name as target_name
-             FROM acquiredimage ai
-             JOIN project p ON ai.projectId = p.Id
-             JOIN target t ON ai.targetId = t.Id
-             WHERE 1=1"
-        } else {
-            "SELECT ai.Id, ai.projectId, ai.targetId, ai.acquireddate, ai.filtername,
-                    ai.gradingStatus, ai.metadata, ai.rejectreason, ai.profileId,
-                    p.name as project_name, t.name as target_name
+        project_id: Option<i32>,
+        target_id: Option<i32>,
+        gap_minutes: u64,
+    ) -> Result<Vec<AcquisitionSession>> {
+        let Some(gap_seconds) = crate::sequence_analysis::session_gap_seconds(gap_minutes) else {
+            bail!("Session gap of {} minutes is out of range", gap_minutes);
+        };
+        let mut query = self.columns.sql(
+            "SELECT ai.projectId, ai.targetId, t.name, ai.filtername,
+                    ai.{acquired_date}, ai.{grading_status}
              FROM acquiredimage ai
-             JOIN project p ON ai.projectId = p.Id
              JOIN target t ON ai.targetId = t.Id
-             WHERE 1=1"
-        };
-        let mut query = String::from(base_select);
-
+             WHERE ai.{acquired_date} IS NOT NULL",
+        );
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
-        if let Some(status) = status_filter {
-            query.push_str(" AND ai.gradingStatus = ?");
-            params.push(Box::new(status as i32));
-        }
-
-        if let Some(project) = project_filter {
-            query.push_str(" AND p.name LIKE ?");
-            params.push(Box::new(format!("%{}%", project)));
-        }
-
-        if let Some(target) = target_filter {
-            query.push_str(" AND t.name LIKE ?");
-            params.push(Box::new(format!("%{}%", target)));
+        if let Some(project_id) = project_id {
+            query.push_str(" AND ai.projectId = ?");
+            params.push(Box::new(project_id));
         }
 
-        if let Some(cutoff) = date_cutoff {
-            query.push_str(" AND ai.acquireddate >= ?");
-            params.push(Box::new(cutoff));
+        if let Some(target_id) = target_id {
+            query.push_str(" AND ai.targetId = ?");
+            params.push(Box::new(target_id));
         }
 
-        query.push_str(" ORDER BY ai.acquireddate DESC");
+        query.push_str(
+            &self
+                .columns
+                .sql(" ORDER BY ai.targetId, ai.filtername, ai.{acquired_date}, ai.Id"),
+        );
 
         let mut stmt = self.conn.prepare(&query)?;
         let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let images = stmt
+        let rows = stmt
             .query_map(param_refs.as_slice(), |row| {
-                let (guid, offset) = if has_guid {
-                    (row.get(9)?, 10)
-                } else {
-                    (None, 9)
-                };
                 Ok((
-                    AcquiredImage {
-                        id: row.get(0)?,
-                        project_id: row.get(1)?,
-                        target_id: row.get(2)?,
-                        acquired_date: row.get(3)?,
-                        filter_name: row.get(4)?,
-                        grading_status: row.get(5)?,
-                        metadata: row.get(6)?,
-                        reject_reason: row.get(7)?,
-                        profile_id: row.get(8)?,
-                        guid,
-                    },
-                    row.get::<_, String>(offset)?,     // project_name
-                    row.get::<_, String>(offset + 1)?, // target_name
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, i32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, i32>(5)?,
                 ))
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(images)
+        let mut sessions = Vec::new();
+        for group in rows.chunk_by(|a, b| a.1 == b.1 && a.3 == b.3) {
+            let timestamps: Vec<Option<i64>> = group.iter().map(|row| Some(row.4)).collect();
+            for indices in crate::photometry::split_sessions(&timestamps, gap_seconds) {
+                let first = &group[indices[0]];
+                let mut session = AcquisitionSession {
+                    project_id: first.0,
+                    target_id: first.1,
+                    target_name: first.2.clone(),
+                    filter_name: first.3.clone(),
+                    start: first.4,
+                    end: group[indices[indices.len() - 1]].4,
+                    image_count: indices.len() as i32,
+                    accepted_count: 0,
+                    rejected_count: 0,
+                    pending_count: 0,
+                };
+                for &i in &indices {
+                    match group[i].5 {
+                        1 => session.accepted_count += 1,
+                        2 => session.rejected_count += 1,
+                        _ => session.pending_count += 1,
+                    }
+                }
+                sessions.push(session);
+            }
+        }
+
+        sessions.sort_by(|a, b| b.start.cmp(&a.start).then(a.target_id.cmp(&b.target_id)));
+        Ok(sessions)
+    }
+
+    pub fn query_images(
+        &self,
+        status_filter: Option<GradingStatus>,
+        project_filter: Option<&str>,
+        target_filter: Option<&str>,
+        date_cutoff: Option<i64>,
+    ) -> Result<Vec<(AcquiredImage, String, String)>> {
+        let filter = ImageFilter {
+            status: status_filter,
+            project_name: project_filter.map(str::to_string),
+            target_name: target_filter.map(str::to_string),
+            acquired_since: date_cutoff,
+            ..ImageFilter::default()
+        };
+        self.find_images(&filter, ImageSort::default(), None)
     }
 
     pub fn get_images_by_ids(&self, ids: &[i32]) -> Result<Vec<AcquiredImage>> {
@@ -584,23 +1289,23 @@ impl<'a> Database<'a> {
 
         let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
         let has_guid = self.schema.has_acquiredimage_guid;
-        let query = if has_guid {
+        let query = self.columns.sql(&if has_guid {
             format!(
-                "SELECT Id, projectId, targetId, acquireddate, filtername,
-                        gradingStatus, metadata, rejectreason, profileId, guid
+                "SELECT Id, projectId, targetId, {{acquired_date}}, filtername,
+                        {{grading_status}}, metadata, {{reject_reason}}, profileId, guid
                  FROM acquiredimage
                  WHERE Id IN ({})",
                 placeholders
             )
         } else {
             format!(
-                "SELECT Id, projectId, targetId, acquireddate, filtername,
-                        gradingStatus, metadata, rejectreason, profileId
+                "SELECT Id, projectId, targetId, {{acquired_date}}, filtername,
+                        {{grading_status}}, metadata, {{reject_reason}}, profileId
                  FROM acquiredimage
                  WHERE Id IN ({})",
                 placeholders
             )
-        };
+        });
 
         let mut stmt = self.conn.prepare(&query)?;
         let params: Vec<&dyn rusqlite::ToSql> =
@@ -678,9 +1383,11 @@ impl<'a> Database<'a> {
         reject_reason: Option<&str>,
     ) -> Result<()> {
         self.conn.execute(
-            "UPDATE acquiredimage 
-             SET gradingStatus = ?, rejectreason = ? 
+            &self.columns.sql(
+                "UPDATE acquiredimage 
+             SET {grading_status} = ?, {reject_reason} = ? 
              WHERE Id = ?",
+            ),
             params![status as i32, reject_reason, image_id],
         )?;
         Ok(())
@@ -694,9 +1401,11 @@ impl<'a> Database<'a> {
 
         for (id, status, reason) in updates {
             tx.execute(
-                "UPDATE acquiredimage 
-                 SET gradingStatus = ?, rejectreason = ? 
+                &self.columns.sql(
+                    "UPDATE acquiredimage 
+                 SET {grading_status} = ?, {reject_reason} = ? 
                  WHERE Id = ?",
+                ),
                 params![*status as i32, reason.as_deref(), id],
             )?;
         }
@@ -705,6 +1414,221 @@ impl<'a> Database<'a> {
         Ok(())
     }
 
+    /// Replace the metadata JSON of several images in one transaction.
+    pub fn batch_update_image_metadata(&self, updates: &[(i32, String)]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        for (id, metadata) in updates {
+            tx.execute(
+                "UPDATE acquiredimage SET metadata = ? WHERE Id = ?",
+                params![metadata, id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete images and their thumbnail blobs in one transaction. Returns
+    /// the number of image rows removed.
+    pub fn delete_images(&self, ids: &[i32]) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let has_imagedata =
+            SchemaCapabilities::table_has_column(&tx, "imagedata", "acquiredimageid");
+
+        let has_favorites = self.has_favorite_table();
+        let has_tags = self.has_tag_table();
+
+        let mut deleted = 0;
+        for id in ids {
+            if has_imagedata {
+                tx.execute("DELETE FROM imagedata WHERE acquiredimageid = ?", [id])?;
+            }
+            if has_favorites {
+                tx.execute(
+                    "DELETE FROM psf_guard_favorite WHERE acquired_image_id = ?",
+                    [id],
+                )?;
+            }
+            if has_tags {
+                tx.execute(
+                    "DELETE FROM psf_guard_image_tag WHERE acquired_image_id = ?",
+                    [id],
+                )?;
+            }
+            deleted += tx.execute("DELETE FROM acquiredimage WHERE Id = ?", [id])?;
+        }
+
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    // ── Favorites: reviewer bookmarks, independent of grading ──────────────
+
+    /// Create the `psf_guard_favorite` sibling table if it doesn't exist.
+    /// Like `psf_guard_archive`, the table is owned by psf-guard and never
+    /// touched by Target Scheduler migrations.
+    pub fn ensure_favorite_schema(&self) -> Result<()> {
+        self.conn
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS psf_guard_favorite (
+                     acquired_image_id INTEGER PRIMARY KEY,
+                     flagged_at        INTEGER NOT NULL
+                 );",
+            )
+            .context("creating psf_guard_favorite table")?;
+        Ok(())
+    }
+
+    fn has_favorite_table(&self) -> bool {
+        SchemaCapabilities::table_has_column(self.conn, "psf_guard_favorite", "acquired_image_id")
+    }
+
+    /// False when `filter` narrows by a psf-guard table that doesn't exist
+    /// yet, so nothing can match (and querying it would fail).
+    fn has_filter_tables(&self, filter: &ImageFilter) -> bool {
+        (!filter.favorites_only || self.has_favorite_table())
+            && (filter.tag.is_none() || self.has_tag_table())
+    }
+
+    /// Flag or unflag an image. Flagging an already flagged image keeps its
+    /// original `flagged_at`.
+    pub fn set_favorite(&self, image_id: i32, favorite: bool) -> Result<()> {
+        self.ensure_favorite_schema()?;
+        if favorite {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO psf_guard_favorite (acquired_image_id, flagged_at)
+                 VALUES (?, ?)",
+                params![image_id, chrono::Utc::now().timestamp()],
+            )?;
+        } else {
+            self.conn.execute(
+                "DELETE FROM psf_guard_favorite WHERE acquired_image_id = ?",
+                [image_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Which of `ids` are flagged as favorites. Empty until the first flag
+    /// creates the table.
+    pub fn favorite_ids(&self, ids: &[i32]) -> Result<std::collections::HashSet<i32>> {
+        if ids.is_empty() || !self.has_favorite_table() {
+            return Ok(std::collections::HashSet::new());
+        }
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT acquired_image_id FROM psf_guard_favorite WHERE acquired_image_id IN ({})",
+            placeholders
+        ))?;
+        let favorites = stmt
+            .query_map(rusqlite::params_from_iter(ids), |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(favorites)
+    }
+
+    // ── Tags: free-form labels, many per image ─────────────────────────────
+
+    /// Create the `psf_guard_image_tag` sibling table if it doesn't exist.
+    /// Tags compare case-insensitively, so "Satellite" and "satellite" are one
+    /// tag (the first spelling used is kept).
+    pub fn ensure_tag_schema(&self) -> Result<()> {
+        self.conn
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS psf_guard_image_tag (
+                     acquired_image_id INTEGER NOT NULL,
+                     tag               TEXT NOT NULL COLLATE NOCASE,
+                     tagged_at         INTEGER NOT NULL,
+                     PRIMARY KEY (acquired_image_id, tag)
+                 );
+                 CREATE INDEX IF NOT EXISTS idx_psf_guard_image_tag_tag
+                     ON psf_guard_image_tag(tag);",
+            )
+            .context("creating psf_guard_image_tag table")?;
+        Ok(())
+    }
+
+    fn has_tag_table(&self) -> bool {
+        SchemaCapabilities::table_has_column(self.conn, "psf_guard_image_tag", "tag")
+    }
+
+    /// Add tags to an image in one transaction (the caller's, when one is
+    /// already open). Tags it already has are skipped.
+    pub fn add_tags(&self, image_id: i32, tags: &[String]) -> Result<()> {
+        self.ensure_tag_schema()?;
+        let tx = self
+            .conn
+            .is_autocommit()
+            .then(|| self.conn.unchecked_transaction())
+            .transpose()?;
+        let now = chrono::Utc::now().timestamp();
+        for tag in tags {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO psf_guard_image_tag (acquired_image_id, tag, tagged_at)
+                 VALUES (?, ?, ?)",
+                params![image_id, tag, now],
+            )?;
+        }
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Remove one tag from an image. Returns false when it didn't have it.
+    pub fn remove_tag(&self, image_id: i32, tag: &str) -> Result<bool> {
+        if !self.has_tag_table() {
+            return Ok(false);
+        }
+        let removed = self.conn.execute(
+            "DELETE FROM psf_guard_image_tag WHERE acquired_image_id = ? AND tag = ?",
+            params![image_id, tag],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Tags of each of `ids`, alphabetically. Images without tags are absent.
+    pub fn tags_for_images(
+        &self,
+        ids: &[i32],
+    ) -> Result<std::collections::HashMap<i32, Vec<String>>> {
+        let mut tags: std::collections::HashMap<i32, Vec<String>> =
+            std::collections::HashMap::new();
+        if ids.is_empty() || !self.has_tag_table() {
+            return Ok(tags);
+        }
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT acquired_image_id, tag FROM psf_guard_image_tag
+             WHERE acquired_image_id IN ({})
+             ORDER BY tag",
+            placeholders
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(ids), |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (image_id, tag) = row?;
+            tags.entry(image_id).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+
+    /// Every tag in use with the number of images carrying it, alphabetically.
+    pub fn tag_counts(&self) -> Result<Vec<(String, i64)>> {
+        if !self.has_tag_table() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT MIN(tag), COUNT(*) FROM psf_guard_image_tag
+             GROUP BY tag ORDER BY tag",
+        )?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(counts)
+    }
+
     // ── Organize: correct imported project/target groupings ────────────────
 
     /// Rename a project. Returns false when no such project exists.
@@ -830,16 +1754,18 @@ impl<'a> Database<'a> {
         into_project_id: i32,
     ) -> Result<(usize, usize)> {
         if source_project_id == into_project_id {
-            anyhow::bail!("cannot merge a project into itself");
+            return Err(MergeRejected::Invalid("cannot merge a project into itself".into()).into());
         }
         let source_profile = self
             .project_profile(source_project_id)?
-            .ok_or_else(|| anyhow::anyhow!("project {} not found", source_project_id))?;
+            .ok_or_else(|| MergeRejected::project_not_found(source_project_id))?;
         let dest_profile = self
             .project_profile(into_project_id)?
-            .ok_or_else(|| anyhow::anyhow!("project {} not found", into_project_id))?;
+            .ok_or_else(|| MergeRejected::project_not_found(into_project_id))?;
         if source_profile != dest_profile {
-            anyhow::bail!("cannot merge projects across profiles");
+            return Err(
+                MergeRejected::Invalid("cannot merge projects across profiles".into()).into(),
+            );
         }
 
         let tx = self.conn.unchecked_transaction()?;
@@ -863,36 +1789,162 @@ impl<'a> Database<'a> {
         Ok((targets, images))
     }
 
-    pub fn reset_grading_status(
+    /// Merge one target into another within the same project: every
+    /// acquired image is reassigned to `into_target_id`. With
+    /// `delete_source`, the emptied source target is removed together with
+    /// its exposure plans and per-target ordering rows; moved images that
+    /// pointed at a deleted plan fall back to exposureId 0 ("no plan").
+    ///
+    /// Returns the number of images moved.
+    pub fn merge_targets(
+        &self,
+        source_target_id: i32,
+        into_target_id: i32,
+        delete_source: bool,
+    ) -> Result<usize> {
+        if source_target_id == into_target_id {
+            return Err(MergeRejected::Invalid("cannot merge a target into itself".into()).into());
+        }
+        let source_project = self
+            .target_project(source_target_id)?
+            .ok_or_else(|| MergeRejected::target_not_found(source_target_id))?;
+        let dest_project = self
+            .target_project(into_target_id)?
+            .ok_or_else(|| MergeRejected::target_not_found(into_target_id))?;
+        if source_project != dest_project {
+            return Err(MergeRejected::Invalid(format!(
+                "cannot merge targets across projects ({} → {}): move the target first",
+                source_project, dest_project
+            ))
+            .into());
+        }
+
+        let has_plans = SchemaCapabilities::table_has_column(self.conn, "exposureplan", "targetid");
+        let tx = self.conn.unchecked_transaction()?;
+        let images = tx.execute(
+            "UPDATE acquiredimage SET targetId = ? WHERE targetId = ?",
+            params![into_target_id, source_target_id],
+        )?;
+        if delete_source {
+            if has_plans
+                && SchemaCapabilities::table_has_column(self.conn, "acquiredimage", "exposureId")
+            {
+                tx.execute(
+                    "UPDATE acquiredimage SET exposureId = 0
+                     WHERE targetId = ? AND exposureId IN
+                        (SELECT Id FROM exposureplan WHERE targetid = ?)",
+                    params![into_target_id, source_target_id],
+                )?;
+            }
+            for table in [
+                "exposureplan",
+                "overrideexposureorderitem",
+                "filtercadenceitem",
+            ] {
+                if SchemaCapabilities::table_has_column(self.conn, table, "targetid") {
+                    tx.execute(
+                        &format!("DELETE FROM {} WHERE targetid = ?", table),
+                        params![source_target_id],
+                    )?;
+                }
+            }
+            tx.execute("DELETE FROM target WHERE Id = ?", params![source_target_id])?;
+        }
+        tx.commit()?;
+        Ok(images)
+    }
+
+    fn target_project(&self, target_id: i32) -> Result<Option<i32>> {
+        use rusqlite::OptionalExtension;
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT projectid FROM target WHERE Id = ?",
+                params![target_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// `WHERE` clause and parameters selecting the images a regrade reset
+    /// applies to, shared by the reset itself and its dry-run previews.
+    /// A reject reason matching `manual` marks a manual grade: automatic mode
+    /// keeps manual rejections, and `preserve_manual` spares every manually
+    /// graded image whatever its status.
+    fn reset_scope(
         &self,
         mode: &str,
         date_cutoff: i64,
         project_filter: Option<&str>,
         target_filter: Option<&str>,
-    ) -> Result<usize> {
-        let mut query = String::from(
-            "UPDATE acquiredimage 
-             SET gradingStatus = 0, rejectreason = NULL 
-             WHERE acquireddate >= ?",
-        );
-
+        manual: &ManualMarkers,
+        preserve_manual: bool,
+    ) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+        let mut clause = self.columns.sql(" WHERE {acquired_date} >= ?");
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(date_cutoff)];
 
         if let Some(project) = project_filter {
-            query.push_str(" AND projectId IN (SELECT Id FROM project WHERE name LIKE ?)");
+            clause.push_str(" AND projectId IN (SELECT Id FROM project WHERE name LIKE ?)");
             params.push(Box::new(format!("%{}%", project)));
         }
 
         if let Some(target) = target_filter {
-            query.push_str(" AND targetId IN (SELECT Id FROM target WHERE name LIKE ?)");
+            clause.push_str(" AND targetId IN (SELECT Id FROM target WHERE name LIKE ?)");
             params.push(Box::new(format!("%{}%", target)));
         }
 
+        let (is_manual, markers) = manual.sql_condition(self.columns.reject_reason);
+
         // For automatic mode, only reset non-manual rejections
         if mode == "automatic" {
-            query.push_str(" AND (gradingStatus != 2 OR rejectreason NOT LIKE '%Manual%')");
+            clause.push_str(&self.columns.sql(&format!(
+                " AND ({{grading_status}} != 2 OR NOT {})",
+                is_manual
+            )));
+            params.extend(
+                markers
+                    .iter()
+                    .map(|m| Box::new(m.clone()) as Box<dyn rusqlite::ToSql>),
+            );
         }
 
+        if preserve_manual {
+            clause.push_str(&self.columns.sql(&format!(
+                " AND ({{reject_reason}} IS NULL OR NOT {})",
+                is_manual
+            )));
+            params.extend(
+                markers
+                    .into_iter()
+                    .map(|m| Box::new(m) as Box<dyn rusqlite::ToSql>),
+            );
+        }
+
+        (clause, params)
+    }
+
+    pub fn reset_grading_status(
+        &self,
+        mode: &str,
+        date_cutoff: i64,
+        project_filter: Option<&str>,
+        target_filter: Option<&str>,
+        manual: &ManualMarkers,
+        preserve_manual: bool,
+    ) -> Result<usize> {
+        let (clause, params) = self.reset_scope(
+            mode,
+            date_cutoff,
+            project_filter,
+            target_filter,
+            manual,
+            preserve_manual,
+        );
+        let query = self.columns.sql(
+            "UPDATE acquiredimage 
+             SET {grading_status} = 0, {reject_reason} = NULL",
+        ) + &clause;
+
         let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
         let count = self.conn.execute(&query, param_refs.as_slice())?;
 
@@ -905,30 +1957,19 @@ impl<'a> Database<'a> {
         date_cutoff: i64,
         project_filter: Option<&str>,
         target_filter: Option<&str>,
+        manual: &ManualMarkers,
+        preserve_manual: bool,
     ) -> Result<usize> {
-        let mut query = String::from(
-            "SELECT COUNT(*) 
-             FROM acquiredimage 
-             WHERE acquireddate >= ?",
+        let (clause, params) = self.reset_scope(
+            mode,
+            date_cutoff,
+            project_filter,
+            target_filter,
+            manual,
+            preserve_manual,
         );
-
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(date_cutoff)];
-
-        if let Some(project) = project_filter {
-            query.push_str(" AND projectId IN (SELECT Id FROM project WHERE name LIKE ?)");
-            params.push(Box::new(format!("%{}%", project)));
-        }
-
-        if let Some(target) = target_filter {
-            query.push_str(" AND targetId IN (SELECT Id FROM target WHERE name LIKE ?)");
-            params.push(Box::new(format!("%{}%", target)));
-        }
-
-        if mode == "automatic" {
-            query.push_str(" AND (gradingStatus != 2 OR rejectreason NOT LIKE '%Manual%')");
-        }
-
-        query.push_str(" AND gradingStatus != 0");
+        let mut query = "SELECT COUNT(*) FROM acquiredimage".to_string() + &clause;
+        query.push_str(&self.columns.sql(" AND {grading_status} != 0"));
 
         let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
         // rusqlite 0.40 dropped the `usize: FromSql` impl; read as i64 and cast.
@@ -939,6 +1980,40 @@ impl<'a> Database<'a> {
         Ok(count as usize)
     }
 
+    /// `(image id, current grading status)` of every non-pending image a reset
+    /// would return to pending, ordered by id.
+    pub fn list_images_to_reset(
+        &self,
+        mode: &str,
+        date_cutoff: i64,
+        project_filter: Option<&str>,
+        target_filter: Option<&str>,
+        manual: &ManualMarkers,
+        preserve_manual: bool,
+    ) -> Result<Vec<(i32, i32)>> {
+        let (clause, params) = self.reset_scope(
+            mode,
+            date_cutoff,
+            project_filter,
+            target_filter,
+            manual,
+            preserve_manual,
+        );
+        let mut query = self
+            .columns
+            .sql("SELECT Id, {grading_status} FROM acquiredimage")
+            + &clause;
+        query.push_str(&self.columns.sql(" AND {grading_status} != 0 ORDER BY Id"));
+
+        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt
+            .query_map(param_refs.as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    }
+
     // Transaction helpers
     pub fn with_transaction<T, F>(&self, f: F) -> Result<T>
     where
@@ -952,17 +2027,17 @@ impl<'a> Database<'a> {
 
     // Overview and statistics methods
     pub fn get_project_overview_stats(&self, project_id: i32) -> Result<ProjectOverviewStats> {
-        let mut stmt = self.conn.prepare(
+        let mut stmt = self.conn.prepare(&self.columns.sql(
             "SELECT 
                 COUNT(*) as total_images,
-                SUM(CASE WHEN gradingStatus = 1 THEN 1 ELSE 0 END) as accepted,
-                SUM(CASE WHEN gradingStatus = 2 THEN 1 ELSE 0 END) as rejected,
-                SUM(CASE WHEN gradingStatus = 0 THEN 1 ELSE 0 END) as pending,
-                MIN(acquireddate) as earliest_date,
-                MAX(acquireddate) as latest_date
+                SUM(CASE WHEN {grading_status} = 1 THEN 1 ELSE 0 END) as accepted,
+                SUM(CASE WHEN {grading_status} = 2 THEN 1 ELSE 0 END) as rejected,
+                SUM(CASE WHEN {grading_status} = 0 THEN 1 ELSE 0 END) as pending,
+                MIN({acquired_date}) as earliest_date,
+                MAX({acquired_date}) as latest_date
              FROM acquiredimage 
              WHERE projectId = ?",
-        )?;
+        ))?;
 
         let (total, accepted, rejected, pending, earliest, latest) =
             stmt.query_row([project_id], |row| {
@@ -1005,16 +2080,16 @@ impl<'a> Database<'a> {
 
     pub fn get_overall_statistics(&self) -> Result<OverallStats> {
         // Get overall image statistics
-        let mut stmt = self.conn.prepare(
+        let mut stmt = self.conn.prepare(&self.columns.sql(
             "SELECT 
                 COUNT(*) as total_images,
-                SUM(CASE WHEN gradingStatus = 1 THEN 1 ELSE 0 END) as accepted,
-                SUM(CASE WHEN gradingStatus = 2 THEN 1 ELSE 0 END) as rejected,
-                SUM(CASE WHEN gradingStatus = 0 THEN 1 ELSE 0 END) as pending,
-                MIN(acquireddate) as earliest_date,
-                MAX(acquireddate) as latest_date
+                SUM(CASE WHEN {grading_status} = 1 THEN 1 ELSE 0 END) as accepted,
+                SUM(CASE WHEN {grading_status} = 2 THEN 1 ELSE 0 END) as rejected,
+                SUM(CASE WHEN {grading_status} = 0 THEN 1 ELSE 0 END) as pending,
+                MIN({acquired_date}) as earliest_date,
+                MAX({acquired_date}) as latest_date
              FROM acquiredimage",
-        )?;
+        ))?;
 
         let (total_images, accepted, rejected, pending, earliest, latest) =
             stmt.query_row([], |row| {
@@ -1073,31 +2148,35 @@ impl<'a> Database<'a> {
 
     pub fn get_all_targets_with_project_info(&self) -> Result<Vec<TargetWithStats>> {
         let query = if self.schema.has_target_guid {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectId, t.guid, p.name,
+            self.columns.sql(
+                "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectId, t.guid, p.name,
                     COUNT(ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count,
-                    SUM(CASE WHEN ai.gradingStatus = 0 THEN 1 ELSE 0 END) as pending_count
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count,
+                    SUM(CASE WHEN ai.{grading_status} = 0 THEN 1 ELSE 0 END) as pending_count
              FROM target t
              INNER JOIN project p ON t.projectId = p.Id
              LEFT JOIN acquiredimage ai ON t.Id = ai.targetId
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec, t.projectId, t.guid, p.name
              HAVING COUNT(ai.Id) > 0
-             ORDER BY p.name, t.name"
+             ORDER BY p.name, t.name",
+            )
         } else {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectId, p.name,
+            self.columns.sql(
+                "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectId, p.name,
                     COUNT(ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count,
-                    SUM(CASE WHEN ai.gradingStatus = 0 THEN 1 ELSE 0 END) as pending_count
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count,
+                    SUM(CASE WHEN ai.{grading_status} = 0 THEN 1 ELSE 0 END) as pending_count
              FROM target t
              INNER JOIN project p ON t.projectId = p.Id
              LEFT JOIN acquiredimage ai ON t.Id = ai.targetId
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec, t.projectId, p.name
              HAVING COUNT(ai.Id) > 0
-             ORDER BY p.name, t.name"
+             ORDER BY p.name, t.name",
+            )
         };
-        let mut stmt = self.conn.prepare(query)?;
+        let mut stmt = self.conn.prepare(&query)?;
         let has_guid = self.schema.has_target_guid;
 
         let targets = stmt
@@ -1198,35 +2277,92 @@ impl<'a> Database<'a> {
         Ok(rows)
     }
 
+    /// Sum exposure seconds per filter and grading status for a target.
+    /// N.I.N.A. writes `ExposureTime` as a number; older exports sometimes
+    /// stored it as a string, so both are accepted.
+    pub fn get_integration_time(&self, target_id: i32) -> Result<IntegrationTime> {
+        let mut stmt = self.conn.prepare(&self.columns.sql(
+            "SELECT filtername, {grading_status}, metadata
+             FROM acquiredimage
+             WHERE targetId = ?
+             ORDER BY filtername",
+        ))?;
+        let rows = stmt
+            .query_map([target_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i32>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut filters: Vec<FilterIntegration> = Vec::new();
+        let mut skipped_images = 0;
+        for (filter_name, grading_status, metadata) in rows {
+            let Some(seconds) = exposure_seconds(&metadata) else {
+                skipped_images += 1;
+                continue;
+            };
+            if filters.last().is_none_or(|f| f.filter_name != filter_name) {
+                filters.push(FilterIntegration {
+                    filter_name,
+                    ..FilterIntegration::default()
+                });
+            }
+            let entry = filters.last_mut().expect("pushed above");
+            match grading_status {
+                1 => {
+                    entry.accepted_seconds += seconds;
+                    entry.accepted_images += 1;
+                }
+                2 => {
+                    entry.rejected_seconds += seconds;
+                    entry.rejected_images += 1;
+                }
+                _ => {
+                    entry.pending_seconds += seconds;
+                    entry.pending_images += 1;
+                }
+            }
+        }
+
+        Ok(IntegrationTime {
+            target_id,
+            filters,
+            skipped_images,
+        })
+    }
+
     pub fn get_all_targets_with_desired_stats(&self) -> Result<Vec<TargetWithDesiredStats>> {
         let query = if self.schema.has_target_guid {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectid, t.guid, p.name,
+            self.columns.sql("SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectid, t.guid, p.name,
                     COUNT(DISTINCT ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count,
-                    SUM(CASE WHEN ai.gradingStatus = 0 THEN 1 ELSE 0 END) as pending_count,
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count,
+                    SUM(CASE WHEN ai.{grading_status} = 0 THEN 1 ELSE 0 END) as pending_count,
                     COALESCE((SELECT SUM(ep2.desired) FROM exposureplan ep2 WHERE ep2.targetid = t.Id), 0) as total_desired
              FROM target t
              INNER JOIN project p ON t.projectId = p.Id
              LEFT JOIN acquiredimage ai ON t.Id = ai.targetId
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec, t.projectId, t.guid, p.name
              HAVING COUNT(DISTINCT ai.Id) > 0 OR (SELECT SUM(ep2.desired) FROM exposureplan ep2 WHERE ep2.targetid = t.Id) > 0
-             ORDER BY p.name, t.name"
+             ORDER BY p.name, t.name")
         } else {
-            "SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectid, p.name,
+            self.columns.sql("SELECT t.Id, t.name, t.active, t.ra, t.dec, t.projectid, p.name,
                     COUNT(DISTINCT ai.Id) as image_count,
-                    SUM(CASE WHEN ai.gradingStatus = 1 THEN 1 ELSE 0 END) as accepted_count,
-                    SUM(CASE WHEN ai.gradingStatus = 2 THEN 1 ELSE 0 END) as rejected_count,
-                    SUM(CASE WHEN ai.gradingStatus = 0 THEN 1 ELSE 0 END) as pending_count,
+                    SUM(CASE WHEN ai.{grading_status} = 1 THEN 1 ELSE 0 END) as accepted_count,
+                    SUM(CASE WHEN ai.{grading_status} = 2 THEN 1 ELSE 0 END) as rejected_count,
+                    SUM(CASE WHEN ai.{grading_status} = 0 THEN 1 ELSE 0 END) as pending_count,
                     COALESCE((SELECT SUM(ep2.desired) FROM exposureplan ep2 WHERE ep2.targetid = t.Id), 0) as total_desired
              FROM target t
              INNER JOIN project p ON t.projectId = p.Id
              LEFT JOIN acquiredimage ai ON t.Id = ai.targetId
              GROUP BY t.Id, t.name, t.active, t.ra, t.dec, t.projectId, p.name
              HAVING COUNT(DISTINCT ai.Id) > 0 OR (SELECT SUM(ep2.desired) FROM exposureplan ep2 WHERE ep2.targetid = t.Id) > 0
-             ORDER BY p.name, t.name"
+             ORDER BY p.name, t.name")
         };
-        let mut stmt = self.conn.prepare(query)?;
+        let mut stmt = self.conn.prepare(&query)?;
         let has_guid = self.schema.has_target_guid;
 
         let targets = stmt
@@ -1281,6 +2417,46 @@ impl<'a> Database<'a> {
     }
 }
 
+/// Exposure length in seconds from an image's metadata JSON, if present and
+/// positive.
+pub(crate) fn exposure_seconds(metadata: &str) -> Option<f64> {
+    let metadata: serde_json::Value = serde_json::from_str(metadata).ok()?;
+    let value = &metadata["ExposureTime"];
+    let seconds = value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))?;
+    (seconds.is_finite() && seconds > 0.0).then_some(seconds)
+}
+
+/// The scheduler tables as the fixture tests need them, shared so every
+/// module's tests build the same shape.
+#[cfg(test)]
+pub(crate) mod test_schema {
+    use rusqlite::Connection;
+
+    pub(crate) const SCHEDULER_TABLES: &str = "CREATE TABLE project (
+            Id INTEGER PRIMARY KEY, profileId TEXT NOT NULL,
+            name TEXT NOT NULL, description TEXT
+        );
+        CREATE TABLE target (
+            Id INTEGER PRIMARY KEY, name TEXT NOT NULL, active INTEGER NOT NULL,
+            ra REAL, dec REAL, projectId INTEGER NOT NULL
+        );
+        CREATE TABLE acquiredimage (
+            Id INTEGER PRIMARY KEY, projectId INTEGER NOT NULL,
+            targetId INTEGER NOT NULL, acquireddate INTEGER,
+            filtername TEXT NOT NULL, gradingStatus INTEGER NOT NULL,
+            metadata TEXT NOT NULL, rejectreason TEXT, profileId TEXT
+        );";
+
+    /// An in-memory database holding the empty scheduler tables.
+    pub(crate) fn in_memory() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEDULER_TABLES).unwrap();
+        conn
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1315,23 +2491,9 @@ mod tests {
 
     #[test]
     fn scoped_image_query_filters_and_paginates_in_sql() {
-        let conn = Connection::open_in_memory().unwrap();
+        let conn = test_schema::in_memory();
         conn.execute_batch(
-            "CREATE TABLE project (
-                Id INTEGER PRIMARY KEY, profileId TEXT NOT NULL,
-                name TEXT NOT NULL, description TEXT
-             );
-             CREATE TABLE target (
-                Id INTEGER PRIMARY KEY, name TEXT NOT NULL, active INTEGER NOT NULL,
-                ra REAL, dec REAL, projectId INTEGER NOT NULL
-             );
-             CREATE TABLE acquiredimage (
-                Id INTEGER PRIMARY KEY, projectId INTEGER NOT NULL,
-                targetId INTEGER NOT NULL, acquireddate INTEGER,
-                filtername TEXT NOT NULL, gradingStatus INTEGER NOT NULL,
-                metadata TEXT NOT NULL, rejectreason TEXT, profileId TEXT
-             );
-             INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
+            "INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
              INSERT INTO target VALUES (10, 'First', 1, NULL, NULL, 1);
              INSERT INTO target VALUES (11, 'Second', 1, NULL, NULL, 1);
              INSERT INTO acquiredimage VALUES
@@ -1365,6 +2527,286 @@ mod tests {
         assert_eq!(second_project_row[0].0.id, 2);
     }
 
+    /// Collapse the builder's indented SQL to single spaces for comparison.
+    fn flat(sql: &str) -> String {
+        sql.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn image_query_builder_without_filters_selects_everything() {
+        let filter = ImageFilter::default();
+        let (sql, params) = ImageQueryBuilder::new(ColumnMap::CURRENT, &filter).select("ai.Id");
+        assert_eq!(
+            flat(&sql),
+            "SELECT ai.Id FROM acquiredimage ai \
+             JOIN project p ON ai.projectId = p.Id \
+             JOIN target t ON ai.targetId = t.Id \
+             WHERE 1=1 ORDER BY ai.acquireddate DESC, ai.Id DESC"
+        );
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn image_query_builder_composes_scope_sort_and_page() {
+        let filter = ImageFilter {
+            status: Some(GradingStatus::Rejected),
+            project_id: Some(2),
+            target_id: Some(7),
+            ..ImageFilter::default()
+        };
+        let (sql, params) = ImageQueryBuilder::new(ColumnMap::LEGACY, &filter)
+            .sort(ImageSort::Oldest)
+            .page(Some(ImagePage {
+                limit: 50,
+                offset: 100,
+            }))
+            .select("ai.Id");
+        let sql = flat(&sql);
+        assert!(
+            sql.ends_with(
+                "WHERE 1=1 AND ai.accepted = ? AND ai.projectId = ? AND ai.targetId = ? \
+                 ORDER BY ai.acquireddate ASC, ai.Id ASC LIMIT ? OFFSET ?"
+            ),
+            "{sql}"
+        );
+        assert_eq!(
+            params,
+            vec![
+                Value::Integer(2),
+                Value::Integer(2),
+                Value::Integer(7),
+                Value::Integer(50),
+                Value::Integer(100),
+            ]
+        );
+    }
+
+    #[test]
+    fn image_query_builder_matches_names_dates_and_metadata() {
+        let filter = ImageFilter {
+            project_name: Some("M31".to_string()),
+            target_name: Some("Core".to_string()),
+            acquired_since: Some(1_700_000_000),
+            metadata: vec![
+                MetadataFilter::Equals {
+                    key: "Camera".to_string(),
+                    value: "ZWO ASI2600MM".to_string(),
+                },
+                MetadataFilter::Range {
+                    key: "HFR".to_string(),
+                    min: None,
+                    max: Some(2.5),
+                },
+            ],
+            ..ImageFilter::default()
+        };
+        let (sql, params) = ImageQueryBuilder::new(ColumnMap::CURRENT, &filter)
+            .sort(ImageSort::Filter)
+            .select("ai.Id");
+        assert!(flat(&sql).ends_with(
+            "WHERE 1=1 AND p.name LIKE ? AND t.name LIKE ? AND ai.acquireddate >= ? \
+             AND json_extract(ai.metadata, ?) = ? \
+             AND CAST(json_extract(ai.metadata, ?) AS REAL) <= ? \
+             ORDER BY ai.filtername ASC, ai.acquireddate DESC, ai.Id DESC"
+        ));
+        assert_eq!(
+            params,
+            vec![
+                Value::Text("%M31%".to_string()),
+                Value::Text("%Core%".to_string()),
+                Value::Integer(1_700_000_000),
+                Value::Text("$.\"Camera\"".to_string()),
+                Value::Text("ZWO ASI2600MM".to_string()),
+                Value::Text("$.\"HFR\"".to_string()),
+                Value::Real(2.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn image_query_builder_neighbors_ignore_paging() {
+        let filter = ImageFilter {
+            target_id: Some(3),
+            ..ImageFilter::default()
+        };
+        let (sql, params) = ImageQueryBuilder::new(ColumnMap::CURRENT, &filter)
+            .page(Some(ImagePage {
+                limit: 10,
+                offset: 0,
+            }))
+            .neighbors(42);
+        let sql = flat(&sql);
+        assert!(sql.contains("LAG(ai.Id) OVER (ORDER BY ai.acquireddate DESC, ai.Id DESC)"));
+        assert!(sql.ends_with("AND ai.targetId = ? ) WHERE id = ?"), "{sql}");
+        assert!(!sql.contains("LIMIT"));
+        assert_eq!(params, vec![Value::Integer(3), Value::Integer(42)]);
+    }
+
+    #[test]
+    fn metadata_filters_run_against_sqlite_json() {
+        let conn = test_schema::in_memory();
+        conn.execute_batch(
+            r#"INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
+             INSERT INTO target VALUES (10, 'First', 1, NULL, NULL, 1);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'L', 0, '{"HFR": 1.8, "Camera": "A"}', NULL, 'profile'),
+                (2, 1, 10, 200, 'L', 0, '{"HFR": "3.1", "Camera": "A"}', NULL, 'profile'),
+                (3, 1, 10, 300, 'L', 0, '{"Camera": "B"}', NULL, 'profile');"#,
+        )
+        .unwrap();
+        let db = Database::new(&conn);
+        let ids = |metadata| {
+            let filter = ImageFilter {
+                metadata,
+                ..ImageFilter::default()
+            };
+            db.find_images(&filter, ImageSort::Oldest, None)
+                .unwrap()
+                .iter()
+                .map(|(image, _, _)| image.id)
+                .collect::<Vec<_>>()
+        };
+
+        let hfr = |min, max| MetadataFilter::Range {
+            key: "HFR".to_string(),
+            min,
+            max,
+        };
+        assert_eq!(ids(vec![hfr(None, Some(2.5))]), vec![1]);
+        // String-typed numbers still compare numerically.
+        assert_eq!(ids(vec![hfr(Some(2.5), None)]), vec![2]);
+        assert_eq!(ids(vec![hfr(None, None)]), vec![1, 2]);
+        assert_eq!(
+            ids(vec![MetadataFilter::Equals {
+                key: "Camera".to_string(),
+                value: "B".to_string(),
+            }]),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn image_neighbors_follow_the_listing_order() {
+        let conn = test_schema::in_memory();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
+             INSERT INTO target VALUES (10, 'First', 1, NULL, NULL, 1);
+             INSERT INTO target VALUES (11, 'Second', 1, NULL, NULL, 1);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'R', 0, '{}', NULL, 'profile'),
+                (2, 1, 10, 200, 'G', 1, '{}', NULL, 'profile'),
+                (3, 1, 11, 300, 'B', 2, '{}', NULL, 'profile'),
+                (4, 1, 10, 200, 'R', 0, '{}', NULL, 'profile');",
+        )
+        .unwrap();
+        let db = Database::new(&conn);
+
+        // Newest first: 3, 4, 2, 1 (4 and 2 tie on date; id breaks it).
+        let listing: Vec<i32> = db
+            .find_images(&ImageFilter::default(), ImageSort::Newest, None)
+            .unwrap()
+            .iter()
+            .map(|(image, _, _)| image.id)
+            .collect();
+        assert_eq!(listing, vec![3, 4, 2, 1]);
+        let neighbors = |id, status, target_id, sort| {
+            let filter = ImageFilter {
+                status,
+                target_id,
+                ..ImageFilter::default()
+            };
+            db.get_image_neighbors(id, &filter, sort).unwrap()
+        };
+        assert_eq!(
+            neighbors(4, None, None, ImageSort::Newest),
+            Some((Some(3), Some(2)))
+        );
+        assert_eq!(
+            neighbors(3, None, None, ImageSort::Newest),
+            Some((None, Some(4)))
+        );
+        assert_eq!(
+            neighbors(1, None, None, ImageSort::Newest),
+            Some((Some(2), None))
+        );
+        assert_eq!(
+            neighbors(2, None, None, ImageSort::Oldest),
+            Some((Some(1), Some(4)))
+        );
+
+        // Scope narrows the sequence; an image outside it has no neighbors.
+        assert_eq!(
+            neighbors(4, Some(GradingStatus::Pending), Some(10), ImageSort::Newest),
+            Some((None, Some(1)))
+        );
+        assert_eq!(neighbors(3, None, Some(10), ImageSort::Newest), None);
+
+        // Filter order groups by name: B(3), G(2), R(4), R(1).
+        assert_eq!(
+            neighbors(2, None, None, ImageSort::Filter),
+            Some((Some(3), Some(4)))
+        );
+    }
+
+    #[test]
+    fn calibration_frames_are_kept_out_of_analysis_queries() {
+        let conn = test_schema::in_memory();
+        conn.execute_batch(
+            r#"INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
+             INSERT INTO target VALUES (10, 'M31', 1, NULL, NULL, 1);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'L', 0, '{"HFR": 2.0}', NULL, 'profile'),
+                (2, 1, 10, 200, 'L', 0, '{"ImageType": "DARK"}', NULL, 'profile'),
+                (3, 1, 10, 300, 'L', 0, '{"ImageType": "LIGHT", "HFR": 2.1}', NULL, 'profile'),
+                (4, 1, 10, 400, 'L', 0, '{"ImageType": "Flat Field"}', NULL, 'profile');"#,
+        )
+        .unwrap();
+        let db = Database::new(&conn);
+        let ids = |images: Vec<(AcquiredImage, String, String)>| {
+            let mut ids: Vec<i32> = images.iter().map(|(image, _, _)| image.id).collect();
+            ids.sort();
+            ids
+        };
+        let listing = |image_type| {
+            let filter = ImageFilter {
+                image_type,
+                ..ImageFilter::default()
+            };
+            ids(db.find_images(&filter, ImageSort::Oldest, None).unwrap())
+        };
+
+        assert_eq!(listing(None), vec![1, 2, 3, 4]);
+        assert_eq!(listing(Some(ImageTypeFilter::Light)), vec![1, 3]);
+        assert_eq!(listing(Some(ImageTypeFilter::Calibration)), vec![2, 4]);
+
+        assert_eq!(
+            ids(db.query_light_images_scoped(None, Some(10)).unwrap()),
+            vec![1, 3]
+        );
+        assert_eq!(
+            ids(db
+                .query_light_images(None, Some("Project"), None, None)
+                .unwrap()),
+            vec![1, 3]
+        );
+
+        // Stepping through lights skips the dark between them.
+        let lights = ImageFilter {
+            image_type: Some(ImageTypeFilter::Light),
+            ..ImageFilter::default()
+        };
+        assert_eq!(
+            db.get_image_neighbors(1, &lights, ImageSort::Oldest)
+                .unwrap(),
+            Some((None, Some(3)))
+        );
+        assert_eq!(
+            db.get_image_neighbors(2, &lights, ImageSort::Oldest)
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn recent_images_are_limited_and_sorted_per_project() {
         let conn = Connection::open_in_memory().unwrap();
@@ -1400,6 +2842,322 @@ mod tests {
         assert!(db.get_recent_images_by_project(0).unwrap().is_empty());
     }
 
+    #[test]
+    fn sessions_split_on_gaps_per_target_and_filter() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE target (
+                Id INTEGER PRIMARY KEY, name TEXT NOT NULL, projectId INTEGER NOT NULL
+             );
+             CREATE TABLE acquiredimage (
+                Id INTEGER PRIMARY KEY, projectId INTEGER NOT NULL,
+                targetId INTEGER NOT NULL, acquireddate INTEGER,
+                filtername TEXT NOT NULL, gradingStatus INTEGER NOT NULL
+             );
+             INSERT INTO target VALUES (10, 'M42', 1), (20, 'M45', 2);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 1000, 'Ha', 1),
+                (2, 1, 10, 1300, 'Ha', 2),
+                (3, 1, 10, 1600, 'Ha', 0),
+                (4, 1, 10, 90000, 'Ha', 1),
+                (5, 1, 10, 1100, 'OIII', 1),
+                (6, 1, 10, NULL, 'Ha', 1),
+                (7, 2, 20, 5000, 'L', 1);",
+        )
+        .unwrap();
+
+        let db = Database::new(&conn);
+        let sessions = db.get_sessions(Some(1), None, 60).unwrap();
+        assert_eq!(sessions.len(), 3);
+
+        // Newest first: the second Ha night, then the two first-night runs.
+        assert_eq!(sessions[0].start, 90000);
+        assert_eq!(sessions[0].image_count, 1);
+        let first_ha = sessions
+            .iter()
+            .find(|s| s.filter_name == "Ha" && s.start == 1000)
+            .unwrap();
+        assert_eq!(first_ha.end, 1600);
+        assert_eq!(first_ha.image_count, 3);
+        assert_eq!(
+            (
+                first_ha.accepted_count,
+                first_ha.rejected_count,
+                first_ha.pending_count
+            ),
+            (1, 1, 1)
+        );
+        assert_eq!(first_ha.target_name, "M42");
+
+        // A gap wider than the whole night keeps everything in one session.
+        let merged = db.get_sessions(None, Some(10), 24 * 60 * 2).unwrap();
+        assert_eq!(merged.len(), 2);
+        assert!(merged.iter().all(|s| s.target_id == 10));
+
+        // A gap that overflows in seconds is an error, not a wrapped value.
+        assert!(db.get_sessions(None, None, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn integration_time_sums_exposure_per_filter_and_status() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE acquiredimage (
+                Id INTEGER PRIMARY KEY, projectId INTEGER NOT NULL,
+                targetId INTEGER NOT NULL, acquireddate INTEGER,
+                filtername TEXT NOT NULL, gradingStatus INTEGER NOT NULL,
+                metadata TEXT NOT NULL
+             );
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'Ha', 1, '{"ExposureTime": 300.0}'),
+                (2, 1, 10, 200, 'Ha', 1, '{"ExposureTime": 300}'),
+                (3, 1, 10, 300, 'Ha', 2, '{"ExposureTime": "300"}'),
+                (4, 1, 10, 400, 'L', 0, '{"ExposureTime": 60.0}'),
+                (5, 1, 10, 500, 'L', 1, '{"HFR": 2.1}'),
+                (6, 1, 10, 600, 'L', 1, 'not json'),
+                (7, 1, 20, 700, 'Ha', 1, '{"ExposureTime": 600.0}');"#,
+        )
+        .unwrap();
+
+        let db = Database::new(&conn);
+        let report = db.get_integration_time(10).unwrap();
+        assert_eq!(report.skipped_images, 2);
+        assert_eq!(report.filters.len(), 2);
+
+        let ha = &report.filters[0];
+        assert_eq!(ha.filter_name, "Ha");
+        assert_eq!(ha.accepted_seconds, 600.0);
+        assert_eq!(ha.accepted_images, 2);
+        assert_eq!(ha.rejected_seconds, 300.0);
+        assert_eq!(ha.total_seconds(), 900.0);
+
+        let l = &report.filters[1];
+        assert_eq!(l.filter_name, "L");
+        assert_eq!(l.pending_seconds, 60.0);
+        assert_eq!(l.total_images(), 1);
+    }
+
+    /// Minimal scheduler DB whose grading column is `grading_column`.
+    fn variant_db(grading_column: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "CREATE TABLE project (
+                Id INTEGER PRIMARY KEY, profileId TEXT NOT NULL,
+                name TEXT NOT NULL, description TEXT
+             );
+             CREATE TABLE target (
+                Id INTEGER PRIMARY KEY, name TEXT NOT NULL, active INTEGER NOT NULL,
+                ra REAL, dec REAL, projectId INTEGER NOT NULL
+             );
+             CREATE TABLE acquiredimage (
+                Id INTEGER PRIMARY KEY, projectId INTEGER NOT NULL,
+                targetId INTEGER NOT NULL, acquireddate INTEGER,
+                filtername TEXT NOT NULL, {grading_column} INTEGER NOT NULL,
+                metadata TEXT NOT NULL, rejectreason TEXT, profileId TEXT
+             );
+             INSERT INTO project VALUES (1, 'profile', 'M31', NULL);
+             INSERT INTO target VALUES (10, 'M31 Core', 1, NULL, NULL, 1);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'L', 0, '{{}}', NULL, 'profile'),
+                (2, 1, 10, 200, 'R', 1, '{{}}', NULL, 'profile'),
+                (3, 1, 10, 300, 'G', 2, '{{}}', 'Manual', 'profile');"
+        ))
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn query_images_runs_against_both_grading_column_layouts() {
+        for (column, variant) in [
+            ("accepted", SchemaVariant::Legacy),
+            ("gradingStatus", SchemaVariant::Graded),
+        ] {
+            let conn = variant_db(column);
+            let db = Database::new(&conn);
+            assert_eq!(db.detect_schema().unwrap().variant, variant, "{column}");
+            assert_eq!(db.columns().grading_status, column);
+
+            let all = db.query_images(None, Some("M31"), None, None).unwrap();
+            assert_eq!(
+                all.iter().map(|(image, _, _)| image.id).collect::<Vec<_>>(),
+                vec![3, 2, 1],
+                "{column}"
+            );
+
+            let rejected = db
+                .query_images(Some(GradingStatus::Rejected), None, None, Some(150))
+                .unwrap();
+            assert_eq!(rejected.len(), 1, "{column}");
+            assert_eq!(rejected[0].0.reject_reason.as_deref(), Some("Manual"));
+
+            db.update_grading_status(1, GradingStatus::Accepted, None)
+                .unwrap();
+            let accepted = db
+                .query_images(Some(GradingStatus::Accepted), None, None, None)
+                .unwrap();
+            assert_eq!(accepted.len(), 2, "{column}");
+        }
+    }
+
+    #[test]
+    fn merge_targets_moves_images_and_optionally_deletes_source() {
+        let conn = variant_db("gradingStatus");
+        conn.execute_batch(
+            "INSERT INTO target VALUES (11, 'M 31', 1, NULL, NULL, 1);
+             INSERT INTO project VALUES (2, 'profile', 'Other', NULL);
+             INSERT INTO target VALUES (20, 'Elsewhere', 1, NULL, NULL, 2);
+             CREATE TABLE exposureplan (Id INTEGER PRIMARY KEY, targetid INTEGER);
+             INSERT INTO exposureplan VALUES (5, 11);
+             UPDATE acquiredimage SET targetId = 11 WHERE Id IN (2, 3);",
+        )
+        .unwrap();
+        let db = Database::new(&conn);
+
+        assert!(db.merge_targets(11, 11, false).is_err());
+        assert!(db.merge_targets(11, 20, false).is_err());
+        assert!(db.merge_targets(11, 99, false).is_err());
+
+        assert_eq!(db.merge_targets(11, 10, true).unwrap(), 2);
+        let on_dest: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM acquiredimage WHERE targetId = 10",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(on_dest, 3);
+        let leftovers: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM target WHERE Id = 11)
+                      + (SELECT COUNT(*) FROM exposureplan WHERE targetid = 11)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn detect_schema_accepts_vendored_target_scheduler_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::ts_schema::apply_schema(&conn).unwrap();
+
+        let schema = Database::new(&conn).detect_schema().unwrap();
+        assert_eq!(schema.variant, SchemaVariant::Guid);
+        assert_eq!(schema.user_version, crate::ts_schema::TS_SCHEMA_VERSION);
+        assert!(schema.capabilities.has_acquiredimage_guid);
+        assert_eq!(
+            schema.to_string(),
+            format!(
+                "Target Scheduler schema v{} (guid columns)",
+                crate::ts_schema::TS_SCHEMA_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn detect_schema_lists_everything_missing() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE project (Id INTEGER PRIMARY KEY, profileId TEXT, name TEXT);
+             CREATE TABLE acquiredimage (
+                Id INTEGER PRIMARY KEY, projectId INTEGER, targetId INTEGER,
+                acquireddate INTEGER, filtername TEXT, metadata TEXT
+             );",
+        )
+        .unwrap();
+
+        let err = Database::new(&conn)
+            .detect_schema()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("table `target`"), "{err}");
+        assert!(err.contains("`acquiredimage.gradingStatus`"), "{err}");
+        assert!(!err.contains("project"), "{err}");
+    }
+
+    #[test]
+    fn configure_connection_enables_wal_and_busy_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        let options = ConnectionOptions {
+            busy_timeout: Duration::from_millis(5000),
+            wal: true,
+            read_only: false,
+        };
+        assert_eq!(configure_connection(&conn, &options).unwrap(), None);
+
+        let mode: String = conn
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode.to_ascii_lowercase(), "wal");
+        let timeout: i64 = conn
+            .pragma_query_value(None, "busy_timeout", |row| row.get(0))
+            .unwrap();
+        assert_eq!(timeout, 5000);
+    }
+
+    #[test]
+    fn configure_connection_falls_back_on_read_only_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x INTEGER);")
+            .unwrap();
+
+        let conn =
+            Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+        let warning = configure_connection(&conn, &ConnectionOptions::default()).unwrap();
+        assert!(warning.is_some(), "read-only open must not switch to WAL");
+        let mode: String = conn
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        assert_ne!(mode.to_ascii_lowercase(), "wal");
+    }
+
+    #[test]
+    fn open_connection_read_only_refuses_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x INTEGER);")
+            .unwrap();
+        crate::ts_schema::apply_schema(&Connection::open(&path).unwrap()).unwrap();
+
+        let conn = open_connection(&path, true).unwrap();
+        assert!(conn.execute("INSERT INTO t VALUES (1)", []).is_err());
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+
+        // A missing file is an error, never a new database.
+        for read_only in [true, false] {
+            assert!(open_connection(dir.path().join("missing.sqlite"), read_only).is_err());
+        }
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn open_connection_rejects_other_databases_before_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("other.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE notes (body TEXT);")
+            .unwrap();
+
+        assert!(open_connection(&path, false).is_err());
+        let mode: String = Connection::open(&path)
+            .unwrap()
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        assert_ne!(mode.to_ascii_lowercase(), "wal");
+    }
+
     #[test]
     fn test_database_queries_new_schema() {
         let db_path = std::path::Path::new("schedulerdb-2.sqlite");
@@ -1427,4 +3185,108 @@ mod tests {
             .expect("Failed to get targets");
         assert!(!targets.is_empty(), "Should have targets with images");
     }
+
+    #[test]
+    fn favorites_flag_filter_and_follow_deletes() {
+        let conn = test_schema::in_memory();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
+             INSERT INTO target VALUES (10, 'First', 1, NULL, NULL, 1);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'R', 0, '{}', NULL, 'profile'),
+                (2, 1, 10, 200, 'G', 1, '{}', NULL, 'profile'),
+                (3, 1, 10, 300, 'B', 2, '{}', NULL, 'profile');",
+        )
+        .unwrap();
+        let db = Database::new(&conn);
+        let favorites_only = ImageFilter {
+            favorites_only: true,
+            ..ImageFilter::default()
+        };
+
+        // No table yet: nothing is a favorite, and reads don't create it.
+        assert!(db.favorite_ids(&[1, 2, 3]).unwrap().is_empty());
+        assert!(db
+            .find_images(&favorites_only, ImageSort::default(), None)
+            .unwrap()
+            .is_empty());
+
+        db.set_favorite(1, true).unwrap();
+        db.set_favorite(3, true).unwrap();
+        db.set_favorite(3, true).unwrap();
+        let ids: Vec<i32> = db
+            .find_images(&favorites_only, ImageSort::Oldest, None)
+            .unwrap()
+            .into_iter()
+            .map(|(image, _, _)| image.id)
+            .collect();
+        assert_eq!(ids, vec![1, 3]);
+
+        db.set_favorite(1, false).unwrap();
+        db.delete_images(&[3]).unwrap();
+        assert!(db.favorite_ids(&[1, 2, 3]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tags_are_many_to_many_and_case_insensitive() {
+        let conn = test_schema::in_memory();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'profile', 'Project', NULL);
+             INSERT INTO target VALUES (10, 'First', 1, NULL, NULL, 1);
+             INSERT INTO acquiredimage VALUES
+                (1, 1, 10, 100, 'R', 0, '{}', NULL, 'profile'),
+                (2, 1, 10, 200, 'G', 1, '{}', NULL, 'profile'),
+                (3, 1, 10, 300, 'B', 2, '{}', NULL, 'profile');",
+        )
+        .unwrap();
+        let db = Database::new(&conn);
+        let tagged = |tag: &str| -> Vec<i32> {
+            let filter = ImageFilter {
+                tag: Some(tag.to_string()),
+                ..ImageFilter::default()
+            };
+            db.find_images(&filter, ImageSort::Oldest, None)
+                .unwrap()
+                .into_iter()
+                .map(|(image, _, _)| image.id)
+                .collect()
+        };
+
+        assert!(tagged("satellite").is_empty());
+        assert!(db.tag_counts().unwrap().is_empty());
+
+        db.add_tags(1, &["satellite".into(), "great seeing".into()])
+            .unwrap();
+        db.add_tags(2, &["Satellite".into()]).unwrap();
+        db.add_tags(3, &["test".into()]).unwrap();
+
+        assert_eq!(tagged("SATELLITE"), vec![1, 2]);
+        let counts: Vec<(String, i64)> = db
+            .tag_counts()
+            .unwrap()
+            .into_iter()
+            .map(|(tag, count)| (tag.to_lowercase(), count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("great seeing".to_string(), 1),
+                ("satellite".to_string(), 2),
+                ("test".to_string(), 1),
+            ]
+        );
+        let tags = db.tags_for_images(&[1, 2, 3]).unwrap();
+        assert_eq!(tags[&1], vec!["great seeing", "satellite"]);
+
+        assert!(db.remove_tag(1, "Satellite").unwrap());
+        assert!(!db.remove_tag(1, "satellite").unwrap());
+        db.delete_images(&[3]).unwrap();
+        assert_eq!(
+            db.tag_counts().unwrap(),
+            vec![
+                ("great seeing".to_string(), 1),
+                ("Satellite".to_string(), 1)
+            ]
+        );
+    }
 }
@@ -2,6 +2,7 @@ use anyhow::Result;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
 use std::time::{Duration, SystemTime};
 
 /// Represents a cached directory tree with file lookups
@@ -9,12 +10,28 @@ use std::time::{Duration, SystemTime};
 pub struct DirectoryTree {
     /// Map from filename to all possible full paths (ordered by directory priority)
     file_map: HashMap<String, Vec<PathBuf>>,
+    /// Same as `file_map`, keyed by lowercased filename
+    folded_map: HashMap<String, Vec<PathBuf>>,
     /// Map from directory path to its contents (for faster directory-specific lookups)
     dir_map: HashMap<PathBuf, Vec<PathBuf>>,
     /// When this tree was built
     created_at: SystemTime,
     /// Root directories that were scanned (in priority order)
     roots: Vec<PathBuf>,
+    /// Per-root scan results, in the same order as `roots`
+    root_scans: Vec<RootScanStats>,
+    /// Wall time of the whole (concurrent) build
+    build_time: Duration,
+}
+
+/// One root's share of a tree build, before merging
+#[derive(Default)]
+struct RootScan {
+    file_map: HashMap<String, Vec<PathBuf>>,
+    dir_map: HashMap<PathBuf, Vec<PathBuf>>,
+    files: usize,
+    directories: usize,
+    elapsed: Duration,
 }
 
 impl DirectoryTree {
@@ -29,6 +46,12 @@ impl DirectoryTree {
     }
 
     /// Build a complete directory tree in memory from multiple root directories with progress callback
+    ///
+    /// Roots are scanned concurrently (they often live on different disks or
+    /// mounts) and merged in configured order, so on a filename collision the
+    /// first-configured root still wins in [`Self::find_file_first`]. Progress
+    /// from every root is funneled back to the calling thread and reported as
+    /// running totals.
     pub fn build_multiple_with_progress<F>(
         roots: &[&Path],
         progress_callback: &mut F,
@@ -49,23 +72,84 @@ impl DirectoryTree {
         );
         let start_time = std::time::Instant::now();
 
+        let scans: Vec<Result<RootScan>> = if roots.len() == 1 {
+            vec![Self::scan_root(roots[0], progress_callback)]
+        } else {
+            let (tx, rx) = mpsc::channel::<(usize, usize, usize, String)>();
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = roots
+                    .iter()
+                    .enumerate()
+                    .map(|(index, root)| {
+                        let tx = tx.clone();
+                        scope.spawn(move || {
+                            Self::scan_root(root, &mut |dirs, files, current: &str| {
+                                let _ = tx.send((index, dirs, files, current.to_string()));
+                            })
+                        })
+                    })
+                    .collect();
+                drop(tx);
+
+                let mut counts = vec![(0, 0); roots.len()];
+                for (index, dirs, files, current) in rx {
+                    counts[index] = (dirs, files);
+                    let (total_dirs, total_files) = counts
+                        .iter()
+                        .fold((0, 0), |(d, f), (dirs, files)| (d + dirs, f + files));
+                    progress_callback(total_dirs, total_files, &current);
+                }
+
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|_| Err(anyhow::anyhow!("Directory scan panicked")))
+                    })
+                    .collect()
+            })
+        };
+
+        // Merge in configured order to keep first-hit priority deterministic.
         let mut file_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut folded_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
         let mut dir_map: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
-        let mut total_files = 0;
-        let mut total_dirs = 0;
-
-        // Process directories in order to maintain priority for first-hit preference
-        for root in roots {
-            tracing::debug!("📁 Scanning directory tree: {:?}", root);
-            Self::scan_directory_with_progress(
+        let mut root_scans = Vec::with_capacity(roots.len());
+        for (root, scan) in roots.iter().zip(scans) {
+            let scan = scan?;
+            // Within a root, case variants of one name are ordered by path.
+            let mut root_folded: HashMap<String, Vec<PathBuf>> = HashMap::new();
+            for (filename, paths) in scan.file_map {
+                root_folded
+                    .entry(filename.to_lowercase())
+                    .or_default()
+                    .extend(paths.iter().cloned());
+                file_map.entry(filename).or_default().extend(paths);
+            }
+            for (folded, mut paths) in root_folded {
+                paths.sort();
+                folded_map.entry(folded).or_default().extend(paths);
+            }
+            for (dir, contents) in scan.dir_map {
+                dir_map.entry(dir).or_insert(contents);
+            }
+            tracing::debug!(
+                "📁 Scanned {:?} in {:.2}s: {} files, {} directories",
                 root,
-                &mut file_map,
-                &mut dir_map,
-                &mut total_files,
-                &mut total_dirs,
-                progress_callback,
-            )?;
+                scan.elapsed.as_secs_f64(),
+                scan.files,
+                scan.directories
+            );
+            root_scans.push(RootScanStats {
+                root: root.to_path_buf(),
+                files: scan.files,
+                directories: scan.directories,
+                elapsed: scan.elapsed,
+            });
         }
+        let total_files: usize = root_scans.iter().map(|r| r.files).sum();
+        let total_dirs: usize = root_scans.iter().map(|r| r.directories).sum();
 
         let elapsed = start_time.elapsed();
         tracing::info!(
@@ -78,33 +162,33 @@ impl DirectoryTree {
 
         Ok(DirectoryTree {
             file_map,
+            folded_map,
             dir_map,
             created_at: SystemTime::now(),
             roots: roots.iter().map(|p| p.to_path_buf()).collect(),
+            root_scans,
+            build_time: elapsed,
         })
     }
 
-    /// Recursively scan a directory and populate the maps with progress tracking
-    fn scan_directory_with_progress<F>(
-        dir: &Path,
-        file_map: &mut HashMap<String, Vec<PathBuf>>,
-        dir_map: &mut HashMap<PathBuf, Vec<PathBuf>>,
-        total_files: &mut usize,
-        total_dirs: &mut usize,
-        progress_callback: &mut F,
-    ) -> Result<()>
+    /// Scan one root into its own maps, timing it
+    fn scan_root<F>(root: &Path, progress_callback: &mut F) -> Result<RootScan>
     where
-        F: FnMut(usize, usize, &str), // (directories_processed, files_processed, current_directory)
+        F: FnMut(usize, usize, &str),
     {
-        // Use the existing scan logic with progress tracking
+        tracing::debug!("📁 Scanning directory tree: {:?}", root);
+        let start_time = std::time::Instant::now();
+        let mut scan = RootScan::default();
         Self::scan_directory_internal(
-            dir,
-            file_map,
-            dir_map,
-            total_files,
-            total_dirs,
+            root,
+            &mut scan.file_map,
+            &mut scan.dir_map,
+            &mut scan.files,
+            &mut scan.directories,
             progress_callback,
-        )
+        )?;
+        scan.elapsed = start_time.elapsed();
+        Ok(scan)
     }
 
     /// Recursively scan a directory and populate the maps (internal implementation)
@@ -194,6 +278,20 @@ impl DirectoryTree {
         self.file_map.get(filename).and_then(|paths| paths.first())
     }
 
+    /// Find all paths whose filename matches ignoring case, in priority order
+    pub fn find_file_ignore_case(&self, filename: &str) -> Option<&Vec<PathBuf>> {
+        self.folded_map.get(&filename.to_lowercase())
+    }
+
+    /// Find the first (highest priority) path whose filename matches ignoring
+    /// case. A fallback for names that changed case in transit; prefer
+    /// [`Self::find_file_first`].
+    pub fn find_file_first_ignore_case(&self, filename: &str) -> Option<&PathBuf> {
+        self.folded_map
+            .get(&filename.to_lowercase())
+            .and_then(|paths| paths.first())
+    }
+
     /// Find files matching a pattern in the filename
     pub fn find_files_matching<F>(&self, predicate: F) -> Vec<&PathBuf>
     where
@@ -228,6 +326,11 @@ impl DirectoryTree {
         self.dir_map.get(dir)
     }
 
+    /// Every cached file path, in no particular order
+    pub fn all_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.file_map.values().flatten()
+    }
+
     /// Get all filenames in the tree (for debugging/stats)
     pub fn get_all_filenames(&self) -> Vec<&String> {
         self.file_map.keys().collect()
@@ -241,6 +344,8 @@ impl DirectoryTree {
             total_directories: self.dir_map.len(),
             age: self.created_at.elapsed().unwrap_or(Duration::from_secs(0)),
             roots: self.roots.clone(),
+            root_scans: self.root_scans.clone(),
+            build_time: self.build_time,
         }
     }
 
@@ -258,6 +363,19 @@ pub struct DirectoryTreeStats {
     pub total_directories: usize,
     pub age: Duration,
     pub roots: Vec<PathBuf>,
+    /// How long each root took to scan, in priority order
+    pub root_scans: Vec<RootScanStats>,
+    /// Wall time of the whole build (roots scan concurrently)
+    pub build_time: Duration,
+}
+
+/// Scan size and timing for one root directory
+#[derive(Debug, Clone)]
+pub struct RootScanStats {
+    pub root: PathBuf,
+    pub files: usize,
+    pub directories: usize,
+    pub elapsed: Duration,
 }
 
 impl DirectoryTreeStats {
@@ -337,4 +455,76 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_case_insensitive_fallback() -> Result<()> {
+        let first = TempDir::new()?;
+        let second = TempDir::new()?;
+        fs::write(first.path().join("light_001.FITS"), "test")?;
+        fs::write(second.path().join("LIGHT_001.fits"), "test")?;
+
+        let tree = DirectoryTree::build_multiple(&[first.path(), second.path()])?;
+        assert!(tree.find_file_first("Light_001.fits").is_none());
+        assert_eq!(
+            tree.find_file_first_ignore_case("Light_001.fits"),
+            Some(&first.path().join("light_001.FITS"))
+        );
+        assert!(tree.find_file_first_ignore_case("Light_002.fits").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_build_keeps_root_priority() -> Result<()> {
+        let first = TempDir::new()?;
+        let second = TempDir::new()?;
+        for root in [first.path(), second.path()] {
+            fs::create_dir_all(root.join("2024-03-01/M31"))?;
+            fs::write(root.join("2024-03-01/M31/shared.fits"), "test")?;
+        }
+        fs::write(first.path().join("only_first.fits"), "test")?;
+        fs::write(second.path().join("only_second.fits"), "test")?;
+
+        let shared = |root: &Path| root.join("2024-03-01/M31/shared.fits");
+        for _ in 0..5 {
+            let mut last_progress = (0, 0);
+            let tree = DirectoryTree::build_multiple_with_progress(
+                &[first.path(), second.path()],
+                &mut |dirs, files, _| last_progress = (dirs, files),
+            )?;
+            assert_eq!(
+                tree.find_file_first("shared.fits"),
+                Some(&shared(first.path()))
+            );
+            assert_eq!(
+                tree.find_file("shared.fits").unwrap(),
+                &vec![shared(first.path()), shared(second.path())]
+            );
+            assert!(tree.find_file("only_second.fits").is_some());
+
+            let stats = tree.stats();
+            assert_eq!(stats.total_files, 4);
+            let scanned: Vec<_> = stats
+                .root_scans
+                .iter()
+                .map(|r| (&r.root, r.files))
+                .collect();
+            assert_eq!(
+                scanned,
+                vec![
+                    (&first.path().to_path_buf(), 2),
+                    (&second.path().to_path_buf(), 2)
+                ]
+            );
+            assert_eq!(last_progress.0, stats.total_directories);
+        }
+
+        let reversed = DirectoryTree::build_multiple(&[second.path(), first.path()])?;
+        assert_eq!(
+            reversed.find_file_first("shared.fits"),
+            Some(&shared(second.path()))
+        );
+
+        Ok(())
+    }
 }
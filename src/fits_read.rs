@@ -0,0 +1,216 @@
+//! Retrying FITS loads for the server's preview / annotated / star paths.
+//!
+//! Frames on networked storage (SMB/NFS shares next to the imaging rig) see
+//! intermittent I/O errors that succeed on the next read. A short retry with
+//! exponential backoff absorbs those; a file that keeps failing across
+//! requests is logged distinctly so a storage problem stands out from a
+//! single corrupt frame.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use crate::image_analysis::FitsImage;
+
+/// How often, and how patiently, a FITS read is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadRetryPolicy {
+    /// Total attempts, including the first (at least 1).
+    pub attempts: u32,
+    /// Delay before the first retry; doubles for each one after.
+    pub delay: Duration,
+}
+
+impl ReadRetryPolicy {
+    pub const DEFAULT: Self = Self {
+        attempts: 3,
+        delay: Duration::from_millis(200),
+    };
+}
+
+impl Default for ReadRetryPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Set once at startup from the `[server]` TOML (`fits_read_attempts`,
+/// `fits_read_retry_delay`), like the SQLite connection options.
+static POLICY: RwLock<ReadRetryPolicy> = RwLock::new(ReadRetryPolicy::DEFAULT);
+
+/// Install the process-wide retry policy.
+pub fn set_read_retry_policy(policy: ReadRetryPolicy) {
+    *POLICY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = policy;
+}
+
+pub fn read_retry_policy() -> ReadRetryPolicy {
+    *POLICY
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Consecutive failed loads (after retries) at which a file is reported as a
+/// likely storage problem rather than a one-off.
+pub const REPEATED_FAILURE_THRESHOLD: u32 = 3;
+
+/// Cap on tracked failing paths, so a dead share can't grow the map forever.
+const MAX_TRACKED_FAILURES: usize = 1024;
+
+/// Path -> consecutive failed loads. Cleared for a path on its next success.
+static FAILURES: Mutex<Option<HashMap<PathBuf, u32>>> = Mutex::new(None);
+
+/// Load a frame, retrying transient failures under the process-wide policy.
+pub fn load_with_retry(path: &Path) -> Result<FitsImage> {
+    retry_with_backoff(path, read_retry_policy(), || FitsImage::from_file(path))
+}
+
+/// Run `read` up to `policy.attempts` times, sleeping `delay`, `2 * delay`,
+/// ... between attempts. Returns the first success or the last error.
+/// Blocking; call from `spawn_blocking`.
+pub fn retry_with_backoff<T>(
+    path: &Path,
+    policy: ReadRetryPolicy,
+    mut read: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let attempts = policy.attempts.max(1);
+    let mut delay = policy.delay;
+    let mut attempt = 1;
+    loop {
+        match read() {
+            Ok(value) => {
+                if attempt > 1 {
+                    tracing::info!(
+                        "💾 Read {} on attempt {}/{}",
+                        path.display(),
+                        attempt,
+                        attempts
+                    );
+                }
+                record_success(path);
+                return Ok(value);
+            }
+            Err(e) if attempt < attempts => {
+                tracing::debug!(
+                    "💾 Read of {} failed (attempt {}/{}), retrying in {:?}: {:#}",
+                    path.display(),
+                    attempt,
+                    attempts,
+                    delay,
+                    e
+                );
+                std::thread::sleep(delay);
+                delay = delay.saturating_mul(2);
+                attempt += 1;
+            }
+            Err(e) => {
+                record_failure(path, attempts, &e);
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Consecutive failed loads recorded for `path`.
+pub fn consecutive_failures(path: &Path) -> u32 {
+    let failures = FAILURES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    failures
+        .as_ref()
+        .and_then(|map| map.get(path).copied())
+        .unwrap_or(0)
+}
+
+fn record_success(path: &Path) {
+    let mut failures = FAILURES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(map) = failures.as_mut() {
+        map.remove(path);
+    }
+}
+
+fn record_failure(path: &Path, attempts: u32, error: &anyhow::Error) {
+    let count = {
+        let mut failures = FAILURES
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let map = failures.get_or_insert_with(HashMap::new);
+        if map.len() >= MAX_TRACKED_FAILURES && !map.contains_key(path) {
+            map.clear();
+        }
+        let count = map.entry(path.to_path_buf()).or_default();
+        *count += 1;
+        *count
+    };
+    if count >= REPEATED_FAILURE_THRESHOLD {
+        tracing::error!(
+            "💾 Storage problem? {} has failed {} consecutive loads ({} attempts each): {:#}",
+            path.display(),
+            count,
+            attempts,
+            error
+        );
+    } else {
+        tracing::warn!(
+            "💾 Failed to read {} after {} attempt(s): {:#}",
+            path.display(),
+            attempts,
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+
+    const NO_DELAY: ReadRetryPolicy = ReadRetryPolicy {
+        attempts: 3,
+        delay: Duration::ZERO,
+    };
+
+    /// A reader that fails its first `failures` calls, then succeeds.
+    fn flaky(failures: u32) -> impl FnMut() -> Result<u32> {
+        let mut calls = 0;
+        move || {
+            calls += 1;
+            if calls <= failures {
+                bail!("transient I/O error on call {calls}");
+            }
+            Ok(calls)
+        }
+    }
+
+    #[test]
+    fn retries_until_the_reader_succeeds() {
+        let path = Path::new("/share/flaky-succeeds.fits");
+        assert_eq!(retry_with_backoff(path, NO_DELAY, flaky(2)).unwrap(), 3);
+        assert_eq!(consecutive_failures(path), 0);
+    }
+
+    #[test]
+    fn gives_up_after_the_configured_attempts() {
+        let path = Path::new("/share/flaky-fails.fits");
+        let err = retry_with_backoff(path, NO_DELAY, flaky(3)).unwrap_err();
+        assert!(err.to_string().contains("call 3"), "{err}");
+        assert_eq!(consecutive_failures(path), 1);
+
+        // A single attempt never retries.
+        let once = ReadRetryPolicy {
+            attempts: 1,
+            ..NO_DELAY
+        };
+        assert!(retry_with_backoff(path, once, flaky(1)).is_err());
+        assert_eq!(consecutive_failures(path), 2);
+
+        // Success clears the streak.
+        retry_with_backoff(path, NO_DELAY, flaky(0)).unwrap();
+        assert_eq!(consecutive_failures(path), 0);
+    }
+}
@@ -0,0 +1,204 @@
+//! Minimal FITS writing: header cards, 2880-byte block padding and a
+//! BINTABLE extension (images are written by
+//! [`FitsImage::write_to_file`](crate::image_analysis::FitsImage::write_to_file)).
+//! Reading goes through `seiza_fits`; this only covers the few files PSF
+//! Guard produces itself.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// FITS files are written in blocks of this many bytes.
+pub const BLOCK_SIZE: usize = 2880;
+const CARD_SIZE: usize = 80;
+/// Characters of a string value between its quotes on one card.
+const MAX_STRING: usize = 68;
+
+/// A header under construction: fixed-format 80-column cards.
+#[derive(Debug, Default)]
+pub struct HeaderBuilder {
+    bytes: Vec<u8>,
+}
+
+impl HeaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn logical(&mut self, key: &str, value: bool) -> &mut Self {
+        self.value_card(key, &format!("{:>20}", if value { "T" } else { "F" }))
+    }
+
+    pub fn int(&mut self, key: &str, value: i64) -> &mut Self {
+        self.value_card(key, &format!("{:>20}", value))
+    }
+
+    pub fn float(&mut self, key: &str, value: f64) -> &mut Self {
+        // `{:?}` keeps a decimal point on whole numbers (1.0, not 1) and
+        // round-trips every f64.
+        let text = format!("{:?}", value).replace("e", "E");
+        self.value_card(key, &format!("{:>20}", text))
+    }
+
+    /// Quoted string, padded to the 8 characters readers expect at least and
+    /// cut to the 68 that fit on one card. Header cards are printable ASCII,
+    /// so any other character (from a copied source header, say) becomes `?`.
+    pub fn string(&mut self, key: &str, value: &str) -> &mut Self {
+        let mut escaped = String::new();
+        for c in value.chars() {
+            let c = if (' '..='~').contains(&c) { c } else { '?' };
+            let width = if c == '\'' { 2 } else { 1 };
+            if escaped.len() + width > MAX_STRING {
+                break;
+            }
+            escaped.push(c);
+            if c == '\'' {
+                escaped.push(c);
+            }
+        }
+        self.value_card(key, &format!("'{:<8}'", escaped))
+    }
+
+    fn value_card(&mut self, key: &str, value: &str) -> &mut Self {
+        self.push_card(&format!("{:<8}= {}", key, value))
+    }
+
+    fn push_card(&mut self, card: &str) -> &mut Self {
+        let mut bytes: Vec<u8> = card
+            .bytes()
+            .map(|b| if (0x20..0x7f).contains(&b) { b } else { b' ' })
+            .take(CARD_SIZE)
+            .collect();
+        bytes.resize(CARD_SIZE, b' ');
+        self.bytes.extend_from_slice(&bytes);
+        self
+    }
+
+    /// Append END and pad with spaces to a whole block.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.push_card("END");
+        pad_to_block(&mut self.bytes, b' ');
+        self.bytes
+    }
+}
+
+/// Pad `bytes` to a multiple of [`BLOCK_SIZE`] with `fill` (spaces for
+/// headers, zeros for data).
+pub fn pad_to_block(bytes: &mut Vec<u8>, fill: u8) {
+    let len = bytes.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+    bytes.resize(len, fill);
+}
+
+/// One column of a binary table, stored as 64-bit floats (`TFORM = '1D'`).
+#[derive(Debug, Clone, Copy)]
+pub struct TableColumn<'a> {
+    pub name: &'a str,
+    /// `TUNITn`, omitted when empty.
+    pub unit: &'a str,
+}
+
+/// Write a FITS file holding an empty primary HDU followed by one BINTABLE
+/// extension named `extname`, one row per entry of `rows` (each as long as
+/// `columns`). NaN is the FITS null for floating-point columns.
+pub fn write_binary_table(
+    path: &Path,
+    extname: &str,
+    columns: &[TableColumn],
+    rows: &[Vec<f64>],
+) -> Result<()> {
+    let mut bytes = primary_header();
+    bytes.extend(binary_table(extname, columns, rows));
+    std::fs::write(path, bytes)
+        .with_context(|| format!("Failed to write FITS file: {}", path.display()))
+}
+
+/// Dataless primary HDU announcing extensions.
+fn primary_header() -> Vec<u8> {
+    let mut header = HeaderBuilder::new();
+    header
+        .logical("SIMPLE", true)
+        .int("BITPIX", 8)
+        .int("NAXIS", 0)
+        .logical("EXTEND", true);
+    header.finish()
+}
+
+fn binary_table(extname: &str, columns: &[TableColumn], rows: &[Vec<f64>]) -> Vec<u8> {
+    let row_bytes = columns.len() * 8;
+    let mut header = HeaderBuilder::new();
+    header
+        .string("XTENSION", "BINTABLE")
+        .int("BITPIX", 8)
+        .int("NAXIS", 2)
+        .int("NAXIS1", row_bytes as i64)
+        .int("NAXIS2", rows.len() as i64)
+        .int("PCOUNT", 0)
+        .int("GCOUNT", 1)
+        .int("TFIELDS", columns.len() as i64);
+    for (i, column) in columns.iter().enumerate() {
+        let n = i + 1;
+        header
+            .string(&format!("TTYPE{}", n), column.name)
+            .string(&format!("TFORM{}", n), "1D");
+        if !column.unit.is_empty() {
+            header.string(&format!("TUNIT{}", n), column.unit);
+        }
+    }
+    header.string("EXTNAME", extname);
+
+    let mut data = Vec::with_capacity(rows.len() * row_bytes);
+    for row in rows {
+        debug_assert_eq!(row.len(), columns.len());
+        for value in row {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    pad_to_block(&mut data, 0);
+
+    let mut bytes = header.finish();
+    bytes.extend(data);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_are_fixed_format_and_blocks_padded() {
+        let mut header = HeaderBuilder::new();
+        header
+            .logical("SIMPLE", true)
+            .int("NAXIS", 2)
+            .float("BSCALE", 1.0)
+            .string("OBJECT", "Barnard's Loop")
+            .string("FILTER", "Ha");
+        let bytes = header.finish();
+
+        assert_eq!(bytes.len(), BLOCK_SIZE);
+        let text = std::str::from_utf8(&bytes).unwrap();
+        let card = |i: usize| text[i * 80..(i + 1) * 80].trim_end();
+        assert_eq!(card(0), "SIMPLE  =                    T");
+        assert_eq!(card(1), "NAXIS   =                    2");
+        assert_eq!(card(2), "BSCALE  =                  1.0");
+        assert_eq!(card(3), "OBJECT  = 'Barnard''s Loop'");
+        assert_eq!(card(4), "FILTER  = 'Ha      '");
+        assert_eq!(card(5), "END");
+        assert!(text[6 * 80..].bytes().all(|b| b == b' '));
+
+        let mut header = HeaderBuilder::new();
+        header.string("COMMENT2", &"x".repeat(100));
+        let text = String::from_utf8(header.finish()).unwrap();
+        assert_eq!(text[..80], format!("COMMENT2= '{}'", "x".repeat(68)));
+
+        // Non-ASCII and control characters take one column each, as `?`.
+        let mut header = HeaderBuilder::new();
+        header
+            .string("OBJECT", "Cône\tNébula")
+            .string("LONG", &"é".repeat(100));
+        let bytes = header.finish();
+        assert!(bytes.iter().all(|b| (0x20..0x7f).contains(b)));
+        let text = String::from_utf8(bytes).unwrap();
+        assert_eq!(text[..80].trim_end(), "OBJECT  = 'C?ne?N?bula'");
+        assert_eq!(text[80..160], format!("LONG    = '{}'", "?".repeat(68)));
+    }
+}
@@ -0,0 +1,208 @@
+//! "Was focus stable tonight": the spread of HFR over one session's accepted
+//! frames, as a coefficient of variation, with a classification.
+//!
+//! A session whose HFR varies little is stable. Otherwise a straight-line
+//! fit against time tells the two failure modes apart: a slow drift (focuser
+//! creep as the temperature falls) is explained by the trend, while erratic
+//! focus (seeing, wind, a slipping focuser) is not.
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Coefficient of variation up to which a session counts as stable.
+pub const STABLE_MAX_CV: f64 = 0.05;
+/// Share of HFR variance the linear trend must explain to call an unstable
+/// session drifting rather than erratic.
+pub const DRIFT_MIN_R_SQUARED: f64 = 0.5;
+/// Frames needed before the spread means anything.
+pub const MIN_FRAMES: usize = 3;
+
+/// A frame of the target, as read from its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusFrame {
+    pub image_id: i32,
+    /// Unix seconds.
+    pub timestamp: i64,
+    pub hfr: Option<f64>,
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusClass {
+    Stable,
+    Drifting,
+    Erratic,
+}
+
+/// One point of the HFR series.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FocusSample {
+    pub image_id: i32,
+    pub timestamp: i64,
+    pub hfr: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FocusSummary {
+    pub mean_hfr: f64,
+    /// Sample standard deviation of HFR.
+    pub std_dev: f64,
+    /// `std_dev / mean_hfr`.
+    pub coefficient_of_variation: f64,
+    /// Least-squares HFR change per hour.
+    pub trend_per_hour: f64,
+    /// Share of the HFR variance the trend explains.
+    pub trend_r_squared: f64,
+    pub classification: FocusClass,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FocusStability {
+    /// Accepted frames with an HFR, in time order.
+    pub series: Vec<FocusSample>,
+    /// `None` with fewer than [`MIN_FRAMES`] samples.
+    pub summary: Option<FocusSummary>,
+}
+
+/// Sort frames by time and split them into sessions on idle gaps longer
+/// than `gap_seconds`, as the sessions endpoint does. Every frame counts
+/// towards the boundaries, whatever its grade.
+pub fn split_into_sessions(mut frames: Vec<FocusFrame>, gap_seconds: i64) -> Vec<Vec<FocusFrame>> {
+    frames.sort_by_key(|frame| (frame.timestamp, frame.image_id));
+    let timestamps: Vec<Option<i64>> = frames.iter().map(|frame| Some(frame.timestamp)).collect();
+    let sessions = crate::photometry::split_sessions(&timestamps, gap_seconds);
+    let mut frames = frames.into_iter();
+    sessions
+        .iter()
+        .map(|indices| frames.by_ref().take(indices.len()).collect())
+        .collect()
+}
+
+pub fn focus_stability(session: &[FocusFrame]) -> FocusStability {
+    let series: Vec<FocusSample> = session
+        .iter()
+        .filter(|frame| frame.accepted)
+        .filter_map(|frame| {
+            Some(FocusSample {
+                image_id: frame.image_id,
+                timestamp: frame.timestamp,
+                hfr: frame.hfr.filter(|hfr| hfr.is_finite() && *hfr > 0.0)?,
+            })
+        })
+        .collect();
+    let summary = summarize(&series);
+    FocusStability { series, summary }
+}
+
+fn summarize(series: &[FocusSample]) -> Option<FocusSummary> {
+    if series.len() < MIN_FRAMES {
+        return None;
+    }
+    let n = series.len() as f64;
+    let mean = series.iter().map(|s| s.hfr).sum::<f64>() / n;
+    let variance = series.iter().map(|s| (s.hfr - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let std_dev = variance.sqrt();
+    let cv = std_dev / mean;
+
+    // HFR against hours since the first frame.
+    let start = series[0].timestamp;
+    let hours: Vec<f64> = series
+        .iter()
+        .map(|s| (s.timestamp - start) as f64 / 3600.0)
+        .collect();
+    let mean_hours = hours.iter().sum::<f64>() / n;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (t, s) in hours.iter().zip(series) {
+        sxy += (t - mean_hours) * (s.hfr - mean);
+        sxx += (t - mean_hours).powi(2);
+    }
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    let total = variance * (n - 1.0);
+    let r_squared = if total > 0.0 {
+        (slope * sxy / total).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let classification = if cv <= STABLE_MAX_CV {
+        FocusClass::Stable
+    } else if r_squared >= DRIFT_MIN_R_SQUARED {
+        FocusClass::Drifting
+    } else {
+        FocusClass::Erratic
+    };
+    Some(FocusSummary {
+        mean_hfr: mean,
+        std_dev,
+        coefficient_of_variation: cv,
+        trend_per_hour: slope,
+        trend_r_squared: r_squared,
+        classification,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(start: i64, hfrs: &[f64]) -> Vec<FocusFrame> {
+        hfrs.iter()
+            .enumerate()
+            .map(|(i, &hfr)| FocusFrame {
+                image_id: start as i32 + i as i32,
+                timestamp: start + 600 * i as i64,
+                hfr: Some(hfr),
+                accepted: true,
+            })
+            .collect()
+    }
+
+    fn class(hfrs: &[f64]) -> FocusClass {
+        focus_stability(&frames(0, hfrs))
+            .summary
+            .unwrap()
+            .classification
+    }
+
+    #[test]
+    fn classifies_stable_drifting_and_erratic_sessions() {
+        assert_eq!(class(&[2.0, 2.05, 1.98, 2.02, 2.0]), FocusClass::Stable);
+        assert_eq!(class(&[2.0, 2.2, 2.4, 2.6, 2.8]), FocusClass::Drifting);
+        assert_eq!(class(&[2.0, 3.0, 2.1, 2.9, 2.0]), FocusClass::Erratic);
+
+        let drifting = focus_stability(&frames(0, &[2.0, 2.2, 2.4, 2.6, 2.8]));
+        let summary = drifting.summary.unwrap();
+        // 0.2 per 10 minutes.
+        assert!((summary.trend_per_hour - 1.2).abs() < 1e-9);
+        assert!((summary.trend_r_squared - 1.0).abs() < 1e-9);
+        assert_eq!(drifting.series.len(), 5);
+    }
+
+    #[test]
+    fn only_accepted_frames_with_hfr_count() {
+        let mut session = frames(0, &[2.0, 2.0, 2.0]);
+        session[1].accepted = false;
+        session.push(FocusFrame {
+            image_id: 99,
+            timestamp: 5_000,
+            hfr: None,
+            accepted: true,
+        });
+        let stability = focus_stability(&session);
+        assert_eq!(stability.series.len(), 2);
+        assert_eq!(stability.summary, None);
+    }
+
+    #[test]
+    fn sessions_split_on_long_gaps_across_grades() {
+        let mut all = frames(100_000, &[2.0, 2.1]);
+        all.extend(frames(0, &[3.0, 3.1, 3.2]));
+        all[0].accepted = false;
+        let sessions = split_into_sessions(all, 3600);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].len(), 3);
+        assert_eq!(sessions[0][0].timestamp, 0);
+        assert_eq!(sessions[1][0].timestamp, 100_000);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone)]
 pub struct StatisticalGradingConfig {
@@ -59,7 +60,7 @@ struct ImageMetadata {
     exposure_start_time: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ImageStatistics {
     pub id: i32,
     pub target_id: i32,
@@ -90,6 +91,61 @@ pub struct StatisticalRejection {
     pub image_id: i32,
     pub reason: String,
     pub details: String,
+    /// Name of the `StatisticalGradingConfig` threshold that was exceeded.
+    pub threshold: &'static str,
+}
+
+/// One grading rule checked against one image: the rule's statistic (a
+/// z-score, MAD multiple or baseline ratio), the configured limit it is
+/// compared with, and whether the image stayed within it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RuleEvaluation {
+    pub rule_name: String,
+    pub metric_value: f64,
+    pub threshold: f64,
+    pub passed: bool,
+    /// The same explanation a rejection by this rule records.
+    pub details: String,
+}
+
+/// A [`RuleEvaluation`] with what is needed to turn it into a rejection.
+struct Evaluation {
+    image_id: i32,
+    rule: RuleEvaluation,
+    threshold_name: &'static str,
+}
+
+impl Evaluation {
+    fn new(
+        image_id: i32,
+        rule_name: &str,
+        metric_value: f64,
+        threshold: f64,
+        threshold_name: &'static str,
+        details: String,
+    ) -> Self {
+        let failed = metric_value > threshold;
+        Self {
+            image_id,
+            rule: RuleEvaluation {
+                rule_name: rule_name.to_string(),
+                metric_value,
+                threshold,
+                passed: !failed,
+                details,
+            },
+            threshold_name,
+        }
+    }
+
+    fn into_rejection(self) -> StatisticalRejection {
+        StatisticalRejection {
+            image_id: self.image_id,
+            reason: self.rule.rule_name,
+            details: self.rule.details,
+            threshold: self.threshold_name,
+        }
+    }
 }
 
 pub struct StatisticalGrader {
@@ -104,58 +160,66 @@ impl StatisticalGrader {
     /// Analyze images and return additional rejections based on statistical analysis
     pub fn analyze_images(
         &self,
-        mut images: Vec<ImageStatistics>,
+        images: Vec<ImageStatistics>,
     ) -> Result<Vec<StatisticalRejection>> {
         let mut rejections = Vec::new();
 
-        // Sort images by target, filter, and time to ensure proper sequence
-        images.sort_by(|a, b| {
-            a.target_id
-                .cmp(&b.target_id)
-                .then_with(|| a.filter_name.cmp(&b.filter_name))
-                .then_with(|| a.exposure_time.cmp(&b.exposure_time))
-        });
-
-        // Group images by target and filter
-        let mut target_filter_groups: HashMap<(i32, String), Vec<&ImageStatistics>> =
-            HashMap::new();
-        for image in &images {
-            target_filter_groups
-                .entry((image.target_id, image.filter_name.clone()))
-                .or_default()
-                .push(image);
+        for group in group_by_target_and_filter(&images).into_values() {
+            rejections.extend(rejections_from(self.evaluate_group(&group)));
         }
 
-        // Analyze each target/filter group
-        for ((_target_id, _filter_name), target_filter_images) in target_filter_groups {
-            if target_filter_images.len() < 3 {
-                // Not enough images for statistical analysis
-                continue;
-            }
+        Ok(rejections)
+    }
 
-            // Calculate statistics for this target/filter combination
-            let stats = self.calculate_filter_statistics(&target_filter_images);
+    /// Every rule evaluated against one image, in the context of the other
+    /// images of its target and filter, in the order `analyze_images` runs
+    /// them. The image is rejected exactly when one of these did not pass.
+    /// Rules that do not apply (too few frames, no spread, frames seeding a
+    /// cloud baseline) are absent.
+    pub fn evaluate_image(&self, images: &[ImageStatistics], image_id: i32) -> Vec<RuleEvaluation> {
+        let Some(group) = group_by_target_and_filter(images)
+            .into_values()
+            .find(|group| group.iter().any(|image| image.id == image_id))
+        else {
+            return Vec::new();
+        };
+        self.evaluate_group(&group)
+            .into_iter()
+            .filter(|evaluation| evaluation.image_id == image_id)
+            .map(|evaluation| evaluation.rule)
+            .collect()
+    }
 
-            // Check for outliers
-            if self.config.enable_hfr_analysis {
-                rejections.extend(self.check_hfr_outliers(&target_filter_images, &stats));
-            }
+    /// All enabled rules over one target/filter group, in time order.
+    fn evaluate_group(&self, images: &[&ImageStatistics]) -> Vec<Evaluation> {
+        let mut evaluations = Vec::new();
+        if images.len() < 3 {
+            // Not enough images for statistical analysis
+            return evaluations;
+        }
 
-            if self.config.enable_star_count_analysis {
-                rejections.extend(self.check_star_count_outliers(&target_filter_images, &stats));
-            }
+        // Calculate statistics for this target/filter combination
+        let stats = self.calculate_filter_statistics(images);
 
-            if self.config.enable_distribution_analysis {
-                rejections.extend(self.check_distribution_quality(&target_filter_images, &stats));
-            }
+        // Check for outliers
+        if self.config.enable_hfr_analysis {
+            evaluations.extend(self.check_hfr_outliers(images, &stats));
+        }
 
-            // Check for cloud detection (sequence analysis)
-            if self.config.enable_cloud_detection {
-                rejections.extend(self.check_cloud_sequence(&target_filter_images));
-            }
+        if self.config.enable_star_count_analysis {
+            evaluations.extend(self.check_star_count_outliers(images, &stats));
         }
 
-        Ok(rejections)
+        if self.config.enable_distribution_analysis {
+            evaluations.extend(self.check_distribution_quality(images, &stats));
+        }
+
+        // Check for cloud detection (sequence analysis)
+        if self.config.enable_cloud_detection {
+            evaluations.extend(self.check_cloud_sequence(images));
+        }
+
+        evaluations
     }
 
     fn calculate_filter_statistics(&self, images: &[&ImageStatistics]) -> FilterStatistics {
@@ -250,107 +314,107 @@ impl StatisticalGrader {
         &self,
         images: &[&ImageStatistics],
         stats: &FilterStatistics,
-    ) -> Vec<StatisticalRejection> {
-        let mut rejections = Vec::new();
+    ) -> Vec<Evaluation> {
+        let mut evaluations = Vec::new();
 
         if stats.hfr_stddev == 0.0 {
-            return rejections;
+            return evaluations;
         }
 
+        let threshold = self.config.hfr_stddev_threshold;
         for image in images {
             if let Some(hfr) = image.hfr {
                 let z_score = (hfr - stats.hfr_mean).abs() / stats.hfr_stddev;
 
-                if z_score > self.config.hfr_stddev_threshold {
-                    rejections.push(StatisticalRejection {
-                        image_id: image.id,
-                        reason: "Statistical HFR".to_string(),
-                        details: format!(
-                            "HFR {:.3} is {:.1}σ from mean {:.3} (threshold: {:.1}σ)",
-                            hfr, z_score, stats.hfr_mean, self.config.hfr_stddev_threshold
-                        ),
-                    });
-                }
+                evaluations.push(Evaluation::new(
+                    image.id,
+                    "Statistical HFR",
+                    z_score,
+                    threshold,
+                    "hfr_stddev_threshold",
+                    format!(
+                        "HFR {:.3} is {:.1}σ from mean {:.3} (threshold: {:.1}σ)",
+                        hfr, z_score, stats.hfr_mean, threshold
+                    ),
+                ));
             }
         }
 
-        rejections
+        evaluations
     }
 
     fn check_star_count_outliers(
         &self,
         images: &[&ImageStatistics],
         stats: &FilterStatistics,
-    ) -> Vec<StatisticalRejection> {
-        let mut rejections = Vec::new();
+    ) -> Vec<Evaluation> {
+        let mut evaluations = Vec::new();
 
         if stats.star_count_stddev == 0.0 {
-            return rejections;
+            return evaluations;
         }
 
+        let threshold = self.config.star_count_stddev_threshold;
         for image in images {
             if let Some(star_count) = image.star_count {
                 let z_score =
                     (star_count as f64 - stats.star_count_mean).abs() / stats.star_count_stddev;
 
-                if z_score > self.config.star_count_stddev_threshold {
-                    rejections.push(StatisticalRejection {
-                        image_id: image.id,
-                        reason: "Statistical Stars".to_string(),
-                        details: format!(
-                            "Star count {} is {:.1}σ from mean {:.0} (threshold: {:.1}σ)",
-                            star_count,
-                            z_score,
-                            stats.star_count_mean,
-                            self.config.star_count_stddev_threshold
-                        ),
-                    });
-                }
+                evaluations.push(Evaluation::new(
+                    image.id,
+                    "Statistical Stars",
+                    z_score,
+                    threshold,
+                    "star_count_stddev_threshold",
+                    format!(
+                        "Star count {} is {:.1}σ from mean {:.0} (threshold: {:.1}σ)",
+                        star_count, z_score, stats.star_count_mean, threshold
+                    ),
+                ));
             }
         }
 
-        rejections
+        evaluations
     }
 
     fn check_distribution_quality(
         &self,
         images: &[&ImageStatistics],
         stats: &FilterStatistics,
-    ) -> Vec<StatisticalRejection> {
-        let mut rejections = Vec::new();
+    ) -> Vec<Evaluation> {
+        let mut evaluations = Vec::new();
+        let mad_multiplier = 1.4826; // Constant to make MAD comparable to stddev
 
         // Check if median significantly differs from mean (indicating skewed distribution)
         if stats.hfr_stddev > 0.0 {
             let hfr_median_shift = (stats.hfr_median - stats.hfr_mean).abs() / stats.hfr_mean;
 
             if hfr_median_shift > self.config.median_shift_threshold {
-                // The distribution is skewed, use median for outlier detection
-                for image in images {
-                    if let Some(hfr) = image.hfr {
-                        // Use median-based rejection for skewed distributions
-                        let deviation_from_median = (hfr - stats.hfr_median).abs();
-                        let mad_multiplier = 1.4826; // Constant to make MAD comparable to stddev
-
-                        // Calculate Median Absolute Deviation (MAD)
-                        let mut deviations: Vec<f64> = stats
-                            .hfr_values
-                            .iter()
-                            .map(|&v| (v - stats.hfr_median).abs())
-                            .collect();
-                        let mad = self.calculate_median(&mut deviations) * mad_multiplier;
-
-                        if mad > 0.0 {
-                            let z_score = deviation_from_median / mad;
-                            if z_score > self.config.hfr_stddev_threshold {
-                                rejections.push(StatisticalRejection {
-                                    image_id: image.id,
-                                    reason: "Distribution HFR".to_string(),
-                                    details: format!(
-                                        "HFR {:.3} deviates {:.1} MAD from median {:.3} (threshold: {:.1})",
-                                        hfr, z_score, stats.hfr_median, self.config.hfr_stddev_threshold
-                                    ),
-                                });
-                            }
+                // The distribution is skewed, use median-based rejection:
+                // Median Absolute Deviation (MAD) instead of stddev
+                let mut deviations: Vec<f64> = stats
+                    .hfr_values
+                    .iter()
+                    .map(|&v| (v - stats.hfr_median).abs())
+                    .collect();
+                let mad = self.calculate_median(&mut deviations) * mad_multiplier;
+                let threshold = self.config.hfr_stddev_threshold;
+
+                if mad > 0.0 {
+                    for image in images {
+                        if let Some(hfr) = image.hfr {
+                            let z_score = (hfr - stats.hfr_median).abs() / mad;
+                            evaluations.push(Evaluation::new(
+                                image.id,
+                                "Distribution HFR",
+                                z_score,
+                                threshold,
+                                "hfr_stddev_threshold",
+                                format!(
+                                    "HFR {:.3} deviates {:.1} MAD from median {:.3} (threshold: {:.1})",
+                                    hfr, z_score, stats.hfr_median, threshold
+                                ),
+                            ));
                         }
                     }
                 }
@@ -363,56 +427,50 @@ impl StatisticalGrader {
                 (stats.star_count_median - stats.star_count_mean).abs() / stats.star_count_mean;
 
             if star_median_shift > self.config.median_shift_threshold {
-                // The distribution is skewed, use median for outlier detection
-                for image in images {
-                    if let Some(star_count) = image.star_count {
-                        // Use median-based rejection for skewed distributions
-                        let deviation_from_median =
-                            (star_count as f64 - stats.star_count_median).abs();
-                        let mad_multiplier = 1.4826; // Constant to make MAD comparable to stddev
-
-                        // Calculate Median Absolute Deviation (MAD)
-                        let mut deviations: Vec<f64> = stats
-                            .star_counts
-                            .iter()
-                            .map(|&v| (v as f64 - stats.star_count_median).abs())
-                            .collect();
-                        let mad = self.calculate_median(&mut deviations) * mad_multiplier;
-
-                        if mad > 0.0 {
-                            let z_score = deviation_from_median / mad;
-                            if z_score > self.config.star_count_stddev_threshold {
-                                rejections.push(StatisticalRejection {
-                                    image_id: image.id,
-                                    reason: "Distribution Stars".to_string(),
-                                    details: format!(
-                                        "Star count {} deviates {:.1} MAD from median {:.0} (threshold: {:.1})",
-                                        star_count, z_score, stats.star_count_median, self.config.star_count_stddev_threshold
-                                    ),
-                                });
-                            }
+                let mut deviations: Vec<f64> = stats
+                    .star_counts
+                    .iter()
+                    .map(|&v| (v as f64 - stats.star_count_median).abs())
+                    .collect();
+                let mad = self.calculate_median(&mut deviations) * mad_multiplier;
+                let threshold = self.config.star_count_stddev_threshold;
+
+                if mad > 0.0 {
+                    for image in images {
+                        if let Some(star_count) = image.star_count {
+                            let z_score = (star_count as f64 - stats.star_count_median).abs() / mad;
+                            evaluations.push(Evaluation::new(
+                                image.id,
+                                "Distribution Stars",
+                                z_score,
+                                threshold,
+                                "star_count_stddev_threshold",
+                                format!(
+                                    "Star count {} deviates {:.1} MAD from median {:.0} (threshold: {:.1})",
+                                    star_count, z_score, stats.star_count_median, threshold
+                                ),
+                            ));
                         }
                     }
                 }
             }
         }
 
-        rejections
+        evaluations
     }
 
-    fn check_cloud_sequence(&self, images: &[&ImageStatistics]) -> Vec<StatisticalRejection> {
-        let mut rejections = Vec::new();
-
+    fn check_cloud_sequence(&self, images: &[&ImageStatistics]) -> Vec<Evaluation> {
         if images.len() < 3 {
-            return rejections;
+            return Vec::new();
         }
 
         // Star count drop is the primary cloud/occlusion indicator: validated
         // occlusion sequences (NGC 6820 2026-06) show star counts collapsing
         // while HFR of the surviving stars stays flat until the frame is
         // mostly gone. HFR rise runs as a second, independent check; an image
-        // flagged by both is reported once (first reason wins).
-        let star_rejections = self.detect_baseline_anomalies(
+        // flagged by both is reported once (first reason wins, see
+        // `rejections_from`).
+        let mut evaluations = self.detect_baseline_anomalies(
             images,
             |img| img.star_count.map(|s| s as f64),
             /* drop_is_bad = */ true,
@@ -427,7 +485,7 @@ impl StatisticalGrader {
                 )
             },
         );
-        let hfr_rejections = self.detect_baseline_anomalies(
+        evaluations.extend(self.detect_baseline_anomalies(
             images,
             |img| img.hfr,
             /* drop_is_bad = */ false,
@@ -441,16 +499,9 @@ impl StatisticalGrader {
                     self.config.cloud_threshold * 100.0
                 )
             },
-        );
-
-        let mut rejected_ids = std::collections::HashSet::new();
-        for rejection in star_rejections.into_iter().chain(hfr_rejections) {
-            if rejected_ids.insert(rejection.image_id) {
-                rejections.push(rejection);
-            }
-        }
+        ));
 
-        rejections
+        evaluations
     }
 
     /// Rolling-median anomaly detection over one metric. The baseline is
@@ -464,10 +515,10 @@ impl StatisticalGrader {
         images: &[&ImageStatistics],
         metric: impl Fn(&ImageStatistics) -> Option<f64>,
         drop_is_bad: bool,
-        reason: &str,
+        rule_name: &str,
         details: impl Fn(f64, f64, f64) -> String,
-    ) -> Vec<StatisticalRejection> {
-        let mut rejections = Vec::new();
+    ) -> Vec<Evaluation> {
+        let mut evaluations = Vec::new();
         let mut baseline_values: Vec<f64> = Vec::new();
         let mut baseline_established = false;
         // Values of the current anomalous run, kept so a persistent condition
@@ -507,12 +558,15 @@ impl StatisticalGrader {
                 (value - baseline_median) / baseline_median
             };
 
-            if bad_ratio > self.config.cloud_threshold {
-                rejections.push(StatisticalRejection {
-                    image_id: image.id,
-                    reason: reason.to_string(),
-                    details: details(value, bad_ratio, baseline_median),
-                });
+            let evaluation = Evaluation::new(
+                image.id,
+                rule_name,
+                bad_ratio,
+                self.config.cloud_threshold,
+                "cloud_threshold",
+                details(value, bad_ratio, baseline_median),
+            );
+            if !evaluation.rule.passed {
                 // Anomalous frame: not folded into the baseline, so a
                 // multi-frame event keeps being rejected instead of becoming
                 // its own baseline after the first hit...
@@ -533,10 +587,49 @@ impl StatisticalGrader {
                 }
                 baseline_values.push(value);
             }
+            evaluations.push(evaluation);
         }
 
-        rejections
+        evaluations
+    }
+}
+
+/// The failed evaluations as rejections. An image flagged by both cloud
+/// checks is reported once: the star-count check runs first and wins.
+fn rejections_from(evaluations: Vec<Evaluation>) -> Vec<StatisticalRejection> {
+    let mut cloud_rejected = HashSet::new();
+    evaluations
+        .into_iter()
+        .filter(|evaluation| {
+            !evaluation.rule.passed
+                && (evaluation.threshold_name != "cloud_threshold"
+                    || cloud_rejected.insert(evaluation.image_id))
+        })
+        .map(Evaluation::into_rejection)
+        .collect()
+}
+
+/// Images grouped by target and filter, each group in capture order.
+fn group_by_target_and_filter(
+    images: &[ImageStatistics],
+) -> HashMap<(i32, String), Vec<&ImageStatistics>> {
+    let mut sorted: Vec<&ImageStatistics> = images.iter().collect();
+    // Sort images by target, filter, and time to ensure proper sequence
+    sorted.sort_by(|a, b| {
+        a.target_id
+            .cmp(&b.target_id)
+            .then_with(|| a.filter_name.cmp(&b.filter_name))
+            .then_with(|| a.exposure_time.cmp(&b.exposure_time))
+    });
+
+    let mut groups: HashMap<(i32, String), Vec<&ImageStatistics>> = HashMap::new();
+    for image in sorted {
+        groups
+            .entry((image.target_id, image.filter_name.clone()))
+            .or_default()
+            .push(image);
     }
+    groups
 }
 
 /// Parse image metadata from JSON to extract HFR and star count
@@ -606,7 +699,7 @@ mod tests {
             images.push(make_stats(i, Some(2.6), Some(300)));
         }
         let refs: Vec<&ImageStatistics> = images.iter().collect();
-        let rejections = grader.check_cloud_sequence(&refs);
+        let rejections = rejections_from(grader.check_cloud_sequence(&refs));
 
         let rejected: std::collections::HashSet<i32> =
             rejections.iter().map(|r| r.image_id).collect();
@@ -636,7 +729,7 @@ mod tests {
             images.push(make_stats(i, Some(2.5), Some(700)));
         }
         let refs: Vec<&ImageStatistics> = images.iter().collect();
-        let rejections = grader.check_cloud_sequence(&refs);
+        let rejections = rejections_from(grader.check_cloud_sequence(&refs));
         let rejected: std::collections::HashSet<i32> =
             rejections.iter().map(|r| r.image_id).collect();
 
@@ -667,7 +760,7 @@ mod tests {
         images.push(make_stats(5, Some(2.5), Some(400)));
 
         let refs: Vec<&ImageStatistics> = images.iter().collect();
-        let rejections = grader.check_cloud_sequence(&refs);
+        let rejections = rejections_from(grader.check_cloud_sequence(&refs));
         let rejected: std::collections::HashSet<i32> =
             rejections.iter().map(|r| r.image_id).collect();
 
@@ -769,6 +862,7 @@ mod tests {
             image_id: 123,
             reason: "Test Reason".to_string(),
             details: "Test Details".to_string(),
+            threshold: "hfr_stddev_threshold",
         };
 
         assert_eq!(rejection.image_id, 123);
@@ -1020,4 +1114,64 @@ mod tests {
         assert_eq!(result[0].reason, "Cloud Detection");
         assert!(result[0].details.contains("30%"));
     }
+
+    #[test]
+    fn test_rule_breakdown_matches_rejections() {
+        let grader = StatisticalGrader::new(StatisticalGradingConfig {
+            cloud_baseline_count: 3,
+            ..Default::default()
+        });
+        // One steady L sequence with a soft frame, a star-count dip and a
+        // cloud run, plus a too-short R group that no rule applies to.
+        let mut images: Vec<ImageStatistics> = (0..20)
+            .map(|i| {
+                let (hfr, stars) = match i {
+                    6 => (4.8, 1010),
+                    11..=13 => (2.6, 450),
+                    17 => (2.5, 700),
+                    _ => (2.4 + (i % 3) as f64 * 0.05, 1000 + (i % 4) * 10),
+                };
+                let mut stats = make_stats(i, Some(hfr), Some(stars));
+                stats.exposure_time = format!("2024-01-15T22:{:02}:00", i);
+                stats
+            })
+            .collect();
+        for i in 20..22 {
+            let mut stats = make_stats(i, Some(9.0), Some(10));
+            stats.filter_name = "R".to_string();
+            images.push(stats);
+        }
+
+        let rejected: HashSet<i32> = grader
+            .analyze_images(images.clone())
+            .unwrap()
+            .iter()
+            .map(|r| r.image_id)
+            .collect();
+        assert!(rejected.contains(&6));
+        assert!(rejected.contains(&12));
+
+        for image in &images {
+            let rules = grader.evaluate_image(&images, image.id);
+            let failed = rules.iter().any(|rule| !rule.passed);
+            assert_eq!(
+                failed,
+                rejected.contains(&image.id),
+                "image {}: {:?}",
+                image.id,
+                rules
+            );
+        }
+
+        let soft = grader.evaluate_image(&images, 6);
+        let hfr = soft
+            .iter()
+            .find(|rule| rule.rule_name == "Statistical HFR")
+            .unwrap();
+        assert!(!hfr.passed);
+        assert!(hfr.metric_value > hfr.threshold);
+        assert_eq!(hfr.threshold, 2.0);
+        assert!(grader.evaluate_image(&images, 20).is_empty());
+        assert!(grader.evaluate_image(&images, 99).is_empty());
+    }
 }
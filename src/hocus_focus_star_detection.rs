@@ -39,6 +39,40 @@ pub struct HocusFocusParams {
 
     // PSF fitting
     pub psf_type: PSFType, // PSF model type to fit (None, Gaussian, Moffat4)
+
+    // Reported HFR definition; detection is the same either way
+    pub hfr_method: HfrMethod,
+}
+
+/// Which HFR definition stars report.
+///
+/// Both are a flux-weighted mean distance from the centroid; they differ in
+/// which pixels count and what is subtracted first, so the same star reads
+/// differently (N.I.N.A.'s value is usually the larger, since its aperture
+/// takes in the faint wings the detector leaves out).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HfrMethod {
+    /// HocusFocus: only the star's detected pixels, less the median of a
+    /// ring around its bounding box.
+    #[default]
+    Psf,
+    /// N.I.N.A.'s built-in detector: every pixel of the bounding box within
+    /// 1.2x its half-size, less the mean of the surrounding 3x box, each
+    /// value rounded half to even and clamped at zero. The same measurement
+    /// `nina_star_detection` (the port of N.I.N.A.'s detector) makes.
+    Nina,
+}
+
+impl std::str::FromStr for HfrMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "psf" | "hocusfocus" => Ok(HfrMethod::Psf),
+            "nina" => Ok(HfrMethod::Nina),
+            _ => Err(format!("Unknown HFR method: {}", s)),
+        }
+    }
 }
 
 impl Default for HocusFocusParams {
@@ -62,6 +96,7 @@ impl Default for HocusFocusParams {
             saturation_threshold: 65535.0 * 0.99, // 99% of max
             min_hfr: 1.5,                         // Actual default
             psf_type: PSFType::None,              // No PSF fitting by default
+            hfr_method: HfrMethod::Psf,
         }
     }
 }
@@ -90,6 +125,14 @@ pub struct HocusFocusDetectionResult {
     pub background_mean: f64,
 }
 
+impl HocusFocusDetectionResult {
+    /// Fewer than `min_stars` detections: a failed frame, whatever its
+    /// other metrics say.
+    pub fn too_few_stars(&self, min_stars: usize) -> bool {
+        self.stars.len() < min_stars
+    }
+}
+
 /// Kappa-Sigma noise estimation result
 #[derive(Debug, Clone)]
 struct KappaSigmaResult {
@@ -233,6 +276,63 @@ pub fn detect_stars_hocus_focus(
     }
 }
 
+/// Fast approximate detection for triage on very large sensors: detect on a
+/// `factor`x`factor` software-binned copy, then report positions, HFR, FWHM
+/// and PSF widths in full-resolution pixels.
+///
+/// Accuracy tradeoff: stars whose HFR is near one binned pixel are
+/// undersampled, so their HFR reads high (roughly +10-20% at bin 2 for
+/// tight stars); close pairs merge; and the faintest stars drop out, so
+/// counts run somewhat lower than at full resolution. Positions are good to
+/// about half a binned pixel. Size thresholds in `params` are given in
+/// full-resolution pixels and scaled here. `factor <= 1` is exact detection.
+pub fn detect_stars_hocus_focus_binned(
+    image: &crate::image_analysis::FitsImage,
+    factor: usize,
+    params: &HocusFocusParams,
+) -> HocusFocusDetectionResult {
+    if factor <= 1 {
+        return detect_stars_hocus_focus(&image.data, image.width, image.height, params);
+    }
+
+    let binned = image.binned(factor);
+    let min_star_size = (params.min_star_size / factor).max(3);
+    let binned_params = HocusFocusParams {
+        noise_reduction_radius: if params.noise_reduction_radius > 0 {
+            (params.noise_reduction_radius / factor).max(1)
+        } else {
+            0
+        },
+        min_star_size,
+        max_star_size: (params.max_star_size / factor).max(min_star_size + 1),
+        min_hfr: params.min_hfr / factor as f64,
+        ..params.clone()
+    };
+    let mut result =
+        detect_stars_hocus_focus(&binned.data, binned.width, binned.height, &binned_params);
+
+    let scale = factor as f64;
+    for star in &mut result.stars {
+        star.position = (
+            crate::image_analysis::unbin_coordinate(star.position.0, factor),
+            crate::image_analysis::unbin_coordinate(star.position.1, factor),
+        );
+        star.hfr *= scale;
+        star.fwhm *= scale;
+        star.pixel_count *= factor * factor;
+        if let Some(psf) = &mut star.psf_model {
+            psf.x0 *= scale;
+            psf.y0 *= scale;
+            psf.sigma_x *= scale;
+            psf.sigma_y *= scale;
+            psf.fwhm *= scale;
+        }
+    }
+    result.average_hfr *= scale;
+    result.average_fwhm *= scale;
+    result
+}
+
 /// Apply hot pixel filtering using 3x3 median filter
 fn apply_hotpixel_filter(
     data: &[u16],
@@ -842,6 +942,19 @@ fn measure_stars(
             None
         };
 
+        // Validation above uses the detector's own HFR, so both methods
+        // report the same stars.
+        let hfr = match params.hfr_method {
+            HfrMethod::Psf => hfr,
+            HfrMethod::Nina => nina_hfr(
+                data,
+                width,
+                height,
+                candidate.center,
+                candidate.bounding_box,
+            ),
+        };
+
         // Use PSF-derived FWHM if available
         let final_fwhm = if let Some(ref psf) = psf_model {
             psf.fwhm
@@ -865,6 +978,53 @@ fn measure_stars(
     stars
 }
 
+/// HFR of the star at `center` with bounding box `(x, y, width, height)`,
+/// measured by N.I.N.A.'s star detection (see [`HfrMethod::Nina`]). Only the
+/// background comes from here: the mean of the box three times the star's
+/// size, star excluded, as that detector takes it.
+pub fn nina_hfr(
+    data: &[u16],
+    width: usize,
+    height: usize,
+    center: (f64, f64),
+    bounding_box: (usize, usize, usize, usize),
+) -> f64 {
+    let (bx, by, bw, bh) = bounding_box;
+
+    let large_x = bx.saturating_sub(bw);
+    let large_y = by.saturating_sub(bh);
+    let mut background_sum = 0.0;
+    let mut background_count = 0usize;
+    for y in large_y..(large_y + bh * 3).min(height) {
+        for x in large_x..(large_x + bw * 3).min(width) {
+            if x < bx || x >= bx + bw || y < by || y >= by + bh {
+                background_sum += data[y * width + x] as f64;
+                background_count += 1;
+            }
+        }
+    }
+    let background = if background_count > 0 {
+        background_sum / background_count as f64
+    } else {
+        0.0
+    };
+
+    crate::nina_star_detection::star_hfr(
+        data,
+        width,
+        height,
+        center,
+        bw.max(bh) as f64 / 2.0,
+        crate::nina_star_detection::Rectangle {
+            x: bx as i32,
+            y: by as i32,
+            width: bw as i32,
+            height: bh as i32,
+        },
+        background,
+    )
+}
+
 /// Measure star properties including median for flatness check
 fn measure_star_properties(
     data: &[u16],
@@ -1027,6 +1187,40 @@ fn validate_star(
 mod tests {
     use super::*;
 
+    #[test]
+    fn hfr_methods_differ_by_aperture() {
+        // Flat 100 ADU sky; a star at (10, 10) of 1000 ADU peak with four
+        // 500 ADU neighbours, plus a 200 ADU wing pixel three pixels out
+        // that the detector didn't include in the star.
+        let (width, height) = (21, 21);
+        let mut data = vec![100u16; width * height];
+        let mut set = |x: usize, y: usize, v: u16| data[y * width + x] = v;
+        set(10, 10, 1100);
+        for (x, y) in [(9, 10), (11, 10), (10, 9), (10, 11)] {
+            set(x, y, 600);
+        }
+        set(10, 13, 300);
+        let candidate = StarCandidate {
+            pixels: vec![(10, 10), (9, 10), (11, 10), (10, 9), (10, 11)],
+            center: (10.0, 10.0),
+            bounding_box: (7, 7, 7, 7),
+        };
+
+        // Detected pixels only: 4 * 500 * 1 / (1000 + 4 * 500).
+        let (psf_hfr, ..) = measure_star_properties(&data, width, height, &candidate, 3);
+        assert!((psf_hfr - 2000.0 / 3000.0).abs() < 1e-9, "{psf_hfr}");
+
+        // Whole 4.2 px aperture, wing included: (2000 + 200 * 3) / 3200.
+        let nina = nina_hfr(
+            &data,
+            width,
+            height,
+            candidate.center,
+            candidate.bounding_box,
+        );
+        assert!((nina - 2600.0 / 3200.0).abs() < 1e-9, "{nina}");
+    }
+
     fn lcg_u16(len: usize, mut state: u64) -> Vec<u16> {
         (0..len)
             .map(|_| {
@@ -1135,6 +1329,86 @@ mod tests {
         }
     }
 
+    /// Bright, well-separated Gaussian stars on a noisy pedestal: the first
+    /// `stars` of a 5x5 grid.
+    fn star_field(width: usize, height: usize, stars: usize) -> crate::image_analysis::FitsImage {
+        let noise = lcg_u16(width * height, 17);
+        let mut data: Vec<f64> = noise
+            .iter()
+            .map(|&n| 1000.0 + (n % 64) as f64 - 32.0)
+            .collect();
+        let sigma: f64 = 3.0;
+        for (row, col) in (0..25).map(|i| (i / 5, i % 5)).take(stars) {
+            let cx = 40.0 + col as f64 * 80.0 + row as f64 * 3.3;
+            let cy = 40.0 + row as f64 * 80.0 + col as f64 * 2.1;
+            for y in (cy as usize - 15)..(cy as usize + 15) {
+                for x in (cx as usize - 15)..(cx as usize + 15) {
+                    let r2 = (x as f64 - cx).powi(2) + (y as f64 - cy).powi(2);
+                    data[y * width + x] += 20000.0 * (-r2 / (2.0 * sigma * sigma)).exp();
+                }
+            }
+        }
+        crate::image_analysis::FitsImage {
+            width,
+            height,
+            data: data.iter().map(|&v| v.clamp(0.0, 65535.0) as u16).collect(),
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: Default::default(),
+        }
+    }
+
+    #[test]
+    fn near_starless_frame_is_flagged() {
+        let params = HocusFocusParams::default();
+        let clouded = star_field(400, 400, 2);
+        let result =
+            detect_stars_hocus_focus(&clouded.data, clouded.width, clouded.height, &params);
+        assert!(result.stars.len() <= 2);
+        assert!(result.too_few_stars(10));
+
+        let clear = star_field(400, 400, 25);
+        let result = detect_stars_hocus_focus(&clear.data, clear.width, clear.height, &params);
+        assert!(!result.too_few_stars(10), "{}", result.stars.len());
+    }
+
+    #[test]
+    fn binned_detection_reports_full_resolution_units() {
+        let image = star_field(400, 400, 25);
+        let params = HocusFocusParams::default();
+        let full = detect_stars_hocus_focus_binned(&image, 1, &params);
+        let binned = detect_stars_hocus_focus_binned(&image, 2, &params);
+
+        let (n_full, n_binned) = (full.stars.len(), binned.stars.len());
+        assert!(
+            n_binned.abs_diff(n_full) <= n_full / 4 + 2,
+            "full={n_full} binned={n_binned}"
+        );
+
+        // Every binned star lands on a full-resolution one, in full-res pixels.
+        for star in &binned.stars {
+            let nearest = full
+                .stars
+                .iter()
+                .map(|f| {
+                    ((f.position.0 - star.position.0).powi(2)
+                        + (f.position.1 - star.position.1).powi(2))
+                    .sqrt()
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert!(
+                nearest < 2.0,
+                "star at {:?} is {nearest:.2}px off",
+                star.position
+            );
+        }
+        if n_full > 0 && n_binned > 0 {
+            let ratio = binned.average_hfr / full.average_hfr;
+            assert!((0.7..1.5).contains(&ratio), "HFR ratio {ratio:.2}");
+        }
+    }
+
     #[test]
     fn median_selection_matches_full_sort() {
         let mut state = 12345u64;
@@ -1,6 +1,8 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 
+use crate::fits_write::{pad_to_block, HeaderBuilder};
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ImageStatistics {
     pub width: usize,
@@ -35,6 +37,472 @@ impl ImageStatistics {
     }
 }
 
+/// A typed FITS header value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FitsValue {
+    Float(f64),
+    Int(i64),
+    String(String),
+    Bool(bool),
+}
+
+impl FitsValue {
+    /// Numeric value; integers widen to float.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FitsValue::Float(v) => Some(*v),
+            FitsValue::Int(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    /// Integer value; floats only when they are whole numbers.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            FitsValue::Int(v) => Some(*v),
+            FitsValue::Float(v) if v.is_finite() && v.fract() == 0.0 => Some(*v as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FitsValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FitsValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl From<&seiza_fits::HeaderValue> for FitsValue {
+    fn from(value: &seiza_fits::HeaderValue) -> Self {
+        use seiza_fits::HeaderValue;
+        match value {
+            HeaderValue::Float(v) => FitsValue::Float(*v),
+            HeaderValue::Integer(v) => FitsValue::Int(*v),
+            HeaderValue::Logical(v) => FitsValue::Bool(*v),
+            HeaderValue::String(v) => FitsValue::String(v.trim().to_string()),
+            // Cards the reader couldn't classify: retry as number or logical
+            // (Fortran `D` exponents included) before giving up to text.
+            HeaderValue::Raw(v) => {
+                let v = v.trim();
+                match v {
+                    "T" => FitsValue::Bool(true),
+                    "F" => FitsValue::Bool(false),
+                    _ => v
+                        .parse()
+                        .map(FitsValue::Int)
+                        .or_else(|_| v.replace(['D', 'd'], "E").parse().map(FitsValue::Float))
+                        .unwrap_or_else(|_| FitsValue::String(v.to_string())),
+                }
+            }
+        }
+    }
+}
+
+/// What a frame is, from its `IMAGETYP` (or `FRAME`) card. Calibration
+/// frames share folders with lights often enough that anything grading or
+/// analyzing a directory has to tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameType {
+    Light,
+    Dark,
+    Flat,
+    Bias,
+    /// Anything else a capture program writes (e.g. `SNAPSHOT`).
+    Other,
+}
+
+impl FrameType {
+    /// Classify an `IMAGETYP` value: N.I.N.A. writes `LIGHT`, `DARK`, `FLAT`,
+    /// `BIAS`, `DARKFLAT`; other programs `Light Frame`, `Bias Frame`,
+    /// `Offset`. Dark flats count as darks. An empty value is a light, as a
+    /// missing card is.
+    pub fn from_imagetyp(value: &str) -> Self {
+        let value = value.trim().to_ascii_uppercase();
+        if value.contains("DARK") {
+            Self::Dark
+        } else if value.contains("FLAT") {
+            Self::Flat
+        } else if value.contains("BIAS") || value.contains("OFFSET") || value == "ZERO" {
+            Self::Bias
+        } else if value.is_empty() || value.contains("LIGHT") {
+            Self::Light
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn is_calibration(self) -> bool {
+        matches!(self, Self::Dark | Self::Flat | Self::Bias)
+    }
+}
+
+/// Parsed primary-header cards, kept so repeated keyword lookups don't
+/// re-read the file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FitsHeader {
+    cards: Vec<(String, FitsValue)>,
+}
+
+impl FitsHeader {
+    const TEMPERATURE_KEYWORDS: [&str; 6] = [
+        "CCD-TEMP", "TEMP", "SET-TEMP", "CCD_TEMP", "TEMPERAT", "CCDTEMP",
+    ];
+    const CAMERA_KEYWORDS: [&str; 5] = ["INSTRUME", "CAMERA", "DETECTOR", "CCD_NAME", "CCDNAME"];
+
+    pub fn from_cards(cards: &[(String, seiza_fits::HeaderValue)]) -> Self {
+        FitsHeader {
+            cards: cards
+                .iter()
+                .map(|(keyword, value)| (keyword.clone(), FitsValue::from(value)))
+                .collect(),
+        }
+    }
+
+    /// Read only the header of a FITS file, without decoding pixels.
+    pub fn read(path: &Path) -> Result<Self> {
+        let cards = seiza_fits::read_header(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read FITS header {}: {e:?}", path.display()))?;
+        Ok(Self::from_cards(&cards))
+    }
+
+    /// First card with this keyword (case-insensitive).
+    pub fn value(&self, keyword: &str) -> Option<&FitsValue> {
+        self.cards
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(keyword))
+            .map(|(_, v)| v)
+    }
+
+    /// First numeric value among `keywords`, in order of preference.
+    fn first_f64(&self, keywords: &[&str]) -> Option<f64> {
+        keywords
+            .iter()
+            .find_map(|keyword| self.value(keyword)?.as_f64())
+    }
+
+    /// First non-empty string value among `keywords`, in order of preference.
+    fn first_str(&self, keywords: &[&str]) -> Option<&str> {
+        keywords
+            .iter()
+            .find_map(|keyword| self.value(keyword)?.as_str().filter(|v| !v.is_empty()))
+    }
+
+    /// Sensor temperature in °C.
+    pub fn temperature(&self) -> Option<f64> {
+        self.first_f64(&Self::TEMPERATURE_KEYWORDS)
+    }
+
+    pub fn camera_model(&self) -> Option<&str> {
+        self.first_str(&Self::CAMERA_KEYWORDS)
+    }
+
+    pub fn gain(&self) -> Option<f64> {
+        self.first_f64(&["GAIN"])
+    }
+
+    pub fn offset(&self) -> Option<f64> {
+        self.first_f64(&["OFFSET"])
+    }
+
+    /// Exposure length in seconds.
+    pub fn exposure(&self) -> Option<f64> {
+        self.first_f64(&["EXPTIME", "EXPOSURE"])
+    }
+
+    /// Focal length in millimetres.
+    pub fn focal_length(&self) -> Option<f64> {
+        self.first_f64(&["FOCALLEN"])
+    }
+
+    /// Pixel size in micrometres, including binning as N.I.N.A. writes it.
+    pub fn pixel_size(&self) -> Option<f64> {
+        self.first_f64(&["XPIXSZ", "PIXSIZE1", "PIXSIZE"])
+    }
+
+    pub fn object(&self) -> Option<&str> {
+        self.first_str(&["OBJECT"])
+    }
+
+    /// Frame type from `IMAGETYP`/`FRAME`. A missing card means a light:
+    /// processed archives often strip it.
+    pub fn frame_type(&self) -> FrameType {
+        self.first_str(&["IMAGETYP", "FRAME"])
+            .map_or(FrameType::Light, FrameType::from_imagetyp)
+    }
+
+    /// Frame size from `NAXIS1`/`NAXIS2`.
+    pub fn dimensions(&self) -> Option<(usize, usize)> {
+        let axis = |keyword| {
+            self.value(keyword)?
+                .as_i64()
+                .and_then(|v| usize::try_from(v).ok())
+        };
+        Some((axis("NAXIS1")?, axis("NAXIS2")?))
+    }
+}
+
+/// A region of interest in full-resolution pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Roi {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Roi {
+    /// Parse `x,y,w,h`.
+    pub fn parse(value: &str) -> Result<Self> {
+        let parts: Vec<usize> = value
+            .split(',')
+            .map(|part| part.trim().parse::<usize>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| anyhow::anyhow!("roi must be x,y,w,h in pixels, got '{}'", value))?;
+        let [x, y, width, height] = parts[..] else {
+            anyhow::bail!("roi must be x,y,w,h in pixels, got '{}'", value);
+        };
+        if width == 0 || height == 0 {
+            anyhow::bail!("roi width and height must be positive");
+        }
+        if x.checked_add(width).is_none() || y.checked_add(height).is_none() {
+            anyhow::bail!("roi {} extends past any frame", value);
+        }
+        Ok(Roi {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// Fail unless the region lies inside a `width`x`height` frame.
+    pub fn check_within(&self, width: usize, height: usize) -> Result<()> {
+        let fits = |start: usize, len: usize, limit: usize| {
+            start.checked_add(len).is_some_and(|end| end <= limit)
+        };
+        if !fits(self.x, self.width, width) || !fits(self.y, self.height, height) {
+            anyhow::bail!(
+                "roi {},{},{},{} is outside the {}x{} frame",
+                self.x,
+                self.y,
+                self.width,
+                self.height,
+                width,
+                height
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Summary of one color channel; `L` for a mono frame.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ChannelStatistics {
+    pub channel: String,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+}
+
+/// Where each color channel's samples sit in a frame's pixel data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayout {
+    Mono,
+    /// A color filter array; the channel of each site of the 2x2 tile in
+    /// row-major order (`RGGB` is `['R', 'G', 'G', 'B']`).
+    Bayer([char; 4]),
+    /// Three full-size planes one after another (`NAXIS3 = 3`).
+    Planar,
+}
+
+impl ChannelLayout {
+    /// Layout described by a header: a `BAYERPAT` card (shifted by
+    /// `XBAYROFF`/`YBAYROFF`) or a three-plane cube; mono otherwise.
+    pub fn from_header(header: &FitsHeader) -> Self {
+        if let Some(pattern) = header.value("BAYERPAT").and_then(FitsValue::as_str) {
+            let sites: Vec<char> = pattern.trim().to_ascii_uppercase().chars().collect();
+            if let [a, b, c, d] = sites[..]
+                && sites.iter().all(|site| matches!(site, 'R' | 'G' | 'B'))
+            {
+                let offset = |keyword| {
+                    header
+                        .value(keyword)
+                        .and_then(FitsValue::as_i64)
+                        .unwrap_or(0)
+                };
+                let (dx, dy) = (
+                    offset("XBAYROFF").rem_euclid(2),
+                    offset("YBAYROFF").rem_euclid(2),
+                );
+                let tile = [a, b, c, d];
+                return ChannelLayout::Bayer(std::array::from_fn(|site| {
+                    let (x, y) = (site as i64 % 2 + dx, site as i64 / 2 + dy);
+                    tile[((y % 2) * 2 + x % 2) as usize]
+                }));
+            }
+        }
+        if header.value("NAXIS3").and_then(FitsValue::as_i64) == Some(3) {
+            return ChannelLayout::Planar;
+        }
+        ChannelLayout::Mono
+    }
+
+    pub fn is_color(&self) -> bool {
+        *self != ChannelLayout::Mono
+    }
+}
+
+/// Per-channel min/max/mean/median of a `width`x`height` frame.
+///
+/// Bayer mosaics are measured on the raw sites of each color rather than a
+/// debayered image, so interpolation doesn't blur the channel balance. A
+/// mono layout yields a single `L` channel; channels come back in R, G, B
+/// order otherwise.
+pub fn calculate_channel_statistics<T: Copy + Into<f64>>(
+    data: &[T],
+    width: usize,
+    height: usize,
+    layout: ChannelLayout,
+) -> Vec<ChannelStatistics> {
+    let plane = width * height;
+    let samples = |channel: char| -> Vec<f64> {
+        match layout {
+            ChannelLayout::Mono => data[..plane.min(data.len())]
+                .iter()
+                .map(|&v| v.into())
+                .collect(),
+            ChannelLayout::Planar => {
+                let index = "RGB".find(channel).unwrap_or(0);
+                data.iter()
+                    .skip(index * plane)
+                    .take(plane)
+                    .map(|&v| v.into())
+                    .collect()
+            }
+            ChannelLayout::Bayer(tile) => data[..plane.min(data.len())]
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| tile[(i / width % 2) * 2 + i % width % 2] == channel)
+                .map(|(_, &v)| v.into())
+                .collect(),
+        }
+    };
+    let channels: &[char] = if layout.is_color() {
+        &['R', 'G', 'B']
+    } else {
+        &['L']
+    };
+    channels
+        .iter()
+        .filter_map(|&channel| {
+            let mut values = samples(channel);
+            if values.is_empty() {
+                return None;
+            }
+            let mid = values.len() / 2;
+            let (_, median, _) = values.select_nth_unstable_by(mid, f64::total_cmp);
+            let median = *median;
+            Some(ChannelStatistics {
+                channel: channel.to_string(),
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                mean: values.iter().sum::<f64>() / values.len() as f64,
+                median,
+            })
+        })
+        .collect()
+}
+
+/// Whether a frame's histogram is clipped at the sensor floor or ceiling.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ExposureVerdict {
+    Under,
+    Ok,
+    Over,
+}
+
+/// Fraction of a frame's pixels sitting at each end of the 16-bit range:
+/// at 0 ADU (background clipped by too little offset or exposure) and at or
+/// above [`HIGH_CLIP_LEVEL`] (saturated sky or flats, not just star cores).
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExposureClipping {
+    pub low_fraction: f64,
+    pub high_fraction: f64,
+}
+
+/// Stored value from which a pixel counts as clipped high: 99% of full
+/// scale, as the star detector's saturation threshold.
+pub const HIGH_CLIP_LEVEL: u16 = 64880;
+
+/// Clip fractions above which a frame is judged under- or over-exposed.
+/// A well exposed light has practically no pixels at 0 ADU and only star
+/// cores at full scale, so 1% at either end is a mistake, not a bright star.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExposureThresholds {
+    pub low_clip_fraction: f64,
+    pub high_clip_fraction: f64,
+}
+
+pub const DEFAULT_LOW_CLIP_FRACTION: f64 = 0.01;
+pub const DEFAULT_HIGH_CLIP_FRACTION: f64 = 0.01;
+
+impl Default for ExposureThresholds {
+    fn default() -> Self {
+        Self {
+            low_clip_fraction: DEFAULT_LOW_CLIP_FRACTION,
+            high_clip_fraction: DEFAULT_HIGH_CLIP_FRACTION,
+        }
+    }
+}
+
+impl ExposureThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("low_clip_fraction", self.low_clip_fraction),
+            ("high_clip_fraction", self.high_clip_fraction),
+        ] {
+            if !(value > 0.0 && value <= 1.0) {
+                return Err(format!("{name} must be in (0, 1], got {value}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Judge a frame's clipping. A frame clipped at both ends takes the
+    /// verdict of the end that exceeds its threshold by more.
+    pub fn verdict(&self, clipping: &ExposureClipping) -> ExposureVerdict {
+        let under = clipping.low_fraction / self.low_clip_fraction;
+        let over = clipping.high_fraction / self.high_clip_fraction;
+        if under > 1.0 && under >= over {
+            ExposureVerdict::Under
+        } else if over > 1.0 {
+            ExposureVerdict::Over
+        } else {
+            ExposureVerdict::Ok
+        }
+    }
+}
+
+/// Map a pixel coordinate in a `factor`-binned image back to full
+/// resolution. Coordinates are pixel-center based, so binned pixel 0 covers
+/// full-res pixels `0..factor` and is centered at `(factor - 1) / 2`.
+pub fn unbin_coordinate(binned: f64, factor: usize) -> f64 {
+    binned * factor as f64 + (factor as f64 - 1.0) / 2.0
+}
+
 /// FITS image data structure
 pub struct FitsImage {
     pub width: usize,
@@ -47,34 +515,54 @@ pub struct FitsImage {
     pub raw_scale: f64,
     /// BZERO offset from the FITS header (0.0 when absent).
     pub bzero: f64,
+    /// Primary header parsed at load time (empty for synthetic images).
+    pub header: FitsHeader,
+}
+
+/// Sample format for [`FitsImage::write_to_file`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FitsDepth {
+    /// 16-bit integers (BITPIX 16), the camera's native format.
+    U16,
+    /// 32-bit IEEE floats (BITPIX -32) in physical units.
+    F32,
+}
+
+impl FitsDepth {
+    pub fn bitpix(self) -> i64 {
+        match self {
+            FitsDepth::U16 => 16,
+            FitsDepth::F32 => -32,
+        }
+    }
+}
+
+/// Keywords describing the data layout, which a written file must take from
+/// the written pixels rather than from a source header.
+fn is_structural_keyword(keyword: &str) -> bool {
+    matches!(
+        keyword,
+        "SIMPLE" | "BITPIX" | "EXTEND" | "BZERO" | "BSCALE" | "END" | "XTENSION"
+    ) || keyword.starts_with("NAXIS")
 }
 
 impl FitsImage {
     /// Extract temperature from FITS headers
     pub fn extract_temperature(path: &Path) -> Option<f64> {
-        let headers = seiza_fits::read_header(path).ok()?;
-        let temp_keywords = [
-            "CCD-TEMP", "TEMP", "SET-TEMP", "CCD_TEMP", "TEMPERAT", "CCDTEMP",
-        ];
-        temp_keywords.iter().find_map(|keyword| {
-            headers
-                .iter()
-                .find(|(k, _)| k == keyword)
-                .and_then(|(_, v)| v.as_f64())
-        })
+        FitsHeader::read(path).ok()?.temperature()
     }
 
     /// Extract camera model from FITS headers
     pub fn extract_camera_model(path: &Path) -> Option<String> {
-        let headers = seiza_fits::read_header(path).ok()?;
-        let camera_keywords = ["INSTRUME", "CAMERA", "DETECTOR", "CCD_NAME", "CCDNAME"];
-        camera_keywords.iter().find_map(|keyword| {
-            headers
-                .iter()
-                .find(|(k, _)| k == keyword)
-                .and_then(|(_, v)| v.as_str())
-                .map(str::to_string)
-        })
+        FitsHeader::read(path)
+            .ok()?
+            .camera_model()
+            .map(str::to_string)
+    }
+
+    /// Value of a header card from the cached header (case-insensitive).
+    pub fn header_value(&self, keyword: &str) -> Option<&FitsValue> {
+        self.header.value(keyword)
     }
 
     /// Load FITS image data from file.
@@ -87,6 +575,7 @@ impl FitsImage {
     pub fn from_file(path: &Path) -> Result<Self> {
         let fits = seiza_fits::FitsImage::open(path)
             .map_err(|e| anyhow::anyhow!("Failed to open FITS file {}: {e:?}", path.display()))?;
+        let header = FitsHeader::from_cards(&fits.headers);
 
         if let Some(rgb) = fits.debayer() {
             // Luminance of the debayered mosaic, already in physical ADU
@@ -97,6 +586,7 @@ impl FitsImage {
                 raw_min: 0.0,
                 raw_scale: 1.0,
                 bzero: 0.0,
+                header,
             });
         }
 
@@ -110,6 +600,7 @@ impl FitsImage {
                 raw_min: 0.0,
                 raw_scale: 1.0,
                 bzero: 0.0,
+                header,
             }),
             // Float and wide-integer data: min-max rescale into u16 and
             // keep the mapping so values can go back to physical units
@@ -142,11 +633,86 @@ impl FitsImage {
                     raw_min: min,
                     raw_scale: scale,
                     bzero: 0.0,
+                    header,
                 })
             }
         }
     }
 
+    /// Per-channel statistics of a FITS file, read from the undebayered
+    /// pixels (see [`calculate_channel_statistics`]). Integer data is in
+    /// physical ADU; float data in its stored units.
+    pub fn channel_statistics_from_file(path: &Path) -> Result<Vec<ChannelStatistics>> {
+        let fits = seiza_fits::FitsImage::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open FITS file {}: {e:?}", path.display()))?;
+        let layout = ChannelLayout::from_header(&FitsHeader::from_cards(&fits.headers));
+        let (width, height) = (fits.width, fits.height);
+        Ok(match &fits.pixels {
+            seiza_fits::Pixels::I32(data) => {
+                calculate_channel_statistics(&data[..], width, height, layout)
+            }
+            seiza_fits::Pixels::F32(data) => {
+                calculate_channel_statistics(&data[..], width, height, layout)
+            }
+            seiza_fits::Pixels::F64(data) => {
+                calculate_channel_statistics(&data[..], width, height, layout)
+            }
+            // Integer camera data arrives BZERO-folded as physical ADU
+            _ => calculate_channel_statistics(&*fits.to_u16(), width, height, layout),
+        })
+    }
+
+    /// Software-bin by averaging `factor`x`factor` blocks. Trailing rows and
+    /// columns that don't fill a whole block are dropped. Averaging (not
+    /// summing) keeps values in u16 range and leaves the stored-to-ADU
+    /// mapping unchanged.
+    pub fn binned(&self, factor: usize) -> FitsImage {
+        let factor = factor.max(1);
+        let (width, height) = (self.width / factor, self.height / factor);
+        let block = (factor * factor) as u32;
+        let mut data = vec![0u16; width * height];
+        for by in 0..height {
+            for bx in 0..width {
+                let mut sum = 0u32;
+                for y in by * factor..(by + 1) * factor {
+                    let row = &self.data[y * self.width + bx * factor..][..factor];
+                    sum += row.iter().map(|&v| v as u32).sum::<u32>();
+                }
+                data[by * width + bx] = ((sum + block / 2) / block) as u16;
+            }
+        }
+        FitsImage {
+            width,
+            height,
+            data,
+            raw_min: self.raw_min,
+            raw_scale: self.raw_scale,
+            bzero: self.bzero,
+            header: self.header.clone(),
+        }
+    }
+
+    /// Copy out a region, so stretching and detection only touch its pixels.
+    /// Stored-to-ADU scaling is kept, so values stay comparable with the
+    /// full frame.
+    pub fn cropped(&self, roi: &Roi) -> Result<FitsImage> {
+        roi.check_within(self.width, self.height)?;
+        let mut data = Vec::with_capacity(roi.width * roi.height);
+        for y in roi.y..roi.y + roi.height {
+            let start = y * self.width + roi.x;
+            data.extend_from_slice(&self.data[start..start + roi.width]);
+        }
+        Ok(FitsImage {
+            width: roi.width,
+            height: roi.height,
+            data,
+            raw_min: self.raw_min,
+            raw_scale: self.raw_scale,
+            bzero: self.bzero,
+            header: self.header.clone(),
+        })
+    }
+
     /// Map a value in stored (rescaled u16) units back to physical ADU.
     ///
     /// The stored data is per-frame min/max rescaled, so stored values are
@@ -156,6 +722,89 @@ impl FitsImage {
         stored / self.raw_scale + self.raw_min + self.bzero
     }
 
+    /// Write the frame as a single-HDU FITS file.
+    ///
+    /// 16-bit output stores `data` as signed integers offset by BZERO 32768;
+    /// for frames rescaled from float data, BZERO/BSCALE instead carry the
+    /// mapping back to physical units. 32-bit output writes physical values
+    /// as IEEE floats. `copy_from` names keywords to carry over from a
+    /// source header (OBJECT, EXPTIME, DATE-OBS...); structural keywords
+    /// the writer sets itself are never copied.
+    pub fn write_to_file(
+        &self,
+        path: &Path,
+        depth: FitsDepth,
+        copy_from: Option<(&FitsHeader, &[&str])>,
+    ) -> Result<()> {
+        let mut header = HeaderBuilder::new();
+        header
+            .logical("SIMPLE", true)
+            .int("BITPIX", depth.bitpix())
+            .int("NAXIS", 2)
+            .int("NAXIS1", self.width as i64)
+            .int("NAXIS2", self.height as i64);
+
+        let mut data = Vec::with_capacity(self.data.len() * 4);
+        match depth {
+            FitsDepth::U16 => {
+                if self.raw_scale != 1.0 || self.raw_min != 0.0 || self.bzero != 0.0 {
+                    // physical = BZERO + BSCALE * (stored - 32768)
+                    header
+                        .float("BZERO", self.stored_to_adu(32768.0))
+                        .float("BSCALE", 1.0 / self.raw_scale);
+                } else {
+                    header.int("BZERO", 32768).int("BSCALE", 1);
+                }
+                for &v in &self.data {
+                    data.extend_from_slice(&((v as i32 - 32768) as i16).to_be_bytes());
+                }
+            }
+            FitsDepth::F32 => {
+                for &v in &self.data {
+                    data.extend_from_slice(&(self.stored_to_adu(v as f64) as f32).to_be_bytes());
+                }
+            }
+        }
+
+        if let Some((source, keywords)) = copy_from {
+            for keyword in keywords {
+                let keyword = keyword.to_ascii_uppercase();
+                if keyword.len() > 8 || is_structural_keyword(&keyword) {
+                    continue;
+                }
+                match source.value(&keyword) {
+                    Some(FitsValue::Bool(v)) => header.logical(&keyword, *v),
+                    Some(FitsValue::Int(v)) => header.int(&keyword, *v),
+                    Some(FitsValue::Float(v)) => header.float(&keyword, *v),
+                    Some(FitsValue::String(v)) => header.string(&keyword, v),
+                    None => continue,
+                };
+            }
+        }
+
+        let mut bytes = header.finish();
+        pad_to_block(&mut data, 0);
+        bytes.extend(data);
+        std::fs::write(path, bytes)
+            .with_context(|| format!("Failed to write FITS file: {}", path.display()))
+    }
+
+    /// Histogram tails at the ends of the sensor's range. `None` for float
+    /// and 32-bit data, which is min/max rescaled and has no fixed floor or
+    /// ceiling to be clipped at.
+    pub fn exposure_clipping(&self) -> Option<ExposureClipping> {
+        if self.raw_scale != 1.0 || self.raw_min != 0.0 || self.data.is_empty() {
+            return None;
+        }
+        let low = self.data.iter().filter(|&&v| v == 0).count();
+        let high = self.data.iter().filter(|&&v| v >= HIGH_CLIP_LEVEL).count();
+        let total = self.data.len() as f64;
+        Some(ExposureClipping {
+            low_fraction: low as f64 / total,
+            high_fraction: high as f64 / total,
+        })
+    }
+
     /// Calculate basic statistics without star detection  
     pub fn calculate_basic_statistics(&self) -> ImageStatistics {
         self.calculate_statistics_with_mad()
@@ -200,3 +849,366 @@ impl FitsImage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seiza_fits::HeaderValue;
+
+    fn header(cards: Vec<(&str, HeaderValue)>) -> FitsHeader {
+        let cards: Vec<(String, HeaderValue)> = cards
+            .into_iter()
+            .map(|(keyword, value)| (keyword.to_string(), value))
+            .collect();
+        FitsHeader::from_cards(&cards)
+    }
+
+    #[test]
+    fn roi_parses_validates_and_crops() {
+        assert_eq!(
+            Roi::parse("2, 1,3,2").unwrap(),
+            Roi {
+                x: 2,
+                y: 1,
+                width: 3,
+                height: 2
+            }
+        );
+        assert!(Roi::parse("1,2,3").is_err());
+        assert!(Roi::parse("0,0,0,5").is_err());
+        assert!(Roi::parse("a,b,c,d").is_err());
+        // x + w past usize::MAX must not wrap into a valid-looking region.
+        assert!(Roi::parse("18446744073709551615,0,2,2").is_err());
+        let huge = Roi {
+            x: usize::MAX,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+        assert!(huge.check_within(6, 4).is_err());
+
+        let image = FitsImage {
+            width: 6,
+            height: 4,
+            data: (0..24).collect(),
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: FitsHeader::default(),
+        };
+        let crop = image.cropped(&Roi::parse("2,1,3,2").unwrap()).unwrap();
+        assert_eq!((crop.width, crop.height), (3, 2));
+        assert_eq!(crop.data, vec![8, 9, 10, 14, 15, 16]);
+        assert!(image.cropped(&Roi::parse("4,0,3,1").unwrap()).is_err());
+
+        let dims = header(vec![
+            ("NAXIS1", HeaderValue::Integer(6248)),
+            ("NAXIS2", HeaderValue::Integer(4176)),
+        ]);
+        assert_eq!(dims.dimensions(), Some((6248, 4176)));
+    }
+
+    #[test]
+    fn channel_statistics_split_a_mosaic_by_color() {
+        // 4x2 RGGB mosaic: R sites 100/300, G sites 10..40, B sites 7/9.
+        let mosaic: Vec<u16> = vec![100, 10, 300, 20, 30, 7, 40, 9];
+        let layout = ChannelLayout::from_header(&header(vec![(
+            "BAYERPAT",
+            HeaderValue::String("RGGB".into()),
+        )]));
+        assert_eq!(layout, ChannelLayout::Bayer(['R', 'G', 'G', 'B']));
+        let stats = calculate_channel_statistics(&mosaic, 4, 2, layout);
+        let summary: Vec<(&str, f64, f64, f64)> = stats
+            .iter()
+            .map(|c| (c.channel.as_str(), c.min, c.max, c.mean))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("R", 100.0, 300.0, 200.0),
+                ("G", 10.0, 40.0, 25.0),
+                ("B", 7.0, 9.0, 8.0)
+            ]
+        );
+        assert_eq!(stats[1].median, 30.0);
+
+        // A one-column offset turns RGGB into GRBG.
+        let shifted = ChannelLayout::from_header(&header(vec![
+            ("BAYERPAT", HeaderValue::String("RGGB".into())),
+            ("XBAYROFF", HeaderValue::Integer(1)),
+        ]));
+        assert_eq!(shifted, ChannelLayout::Bayer(['G', 'R', 'B', 'G']));
+
+        // Three planes of constant 1, 2 and 3.
+        let planes: Vec<f32> = [1.0, 2.0, 3.0].iter().flat_map(|&v| [v; 6]).collect();
+        let planar = ChannelLayout::from_header(&header(vec![("NAXIS3", HeaderValue::Integer(3))]));
+        let medians: Vec<f64> = calculate_channel_statistics(&planes, 3, 2, planar)
+            .iter()
+            .map(|c| c.median)
+            .collect();
+        assert_eq!(medians, vec![1.0, 2.0, 3.0]);
+
+        let mono = calculate_channel_statistics(&mosaic, 4, 2, ChannelLayout::Mono);
+        assert_eq!(mono.len(), 1);
+        assert_eq!(mono[0].channel, "L");
+        assert_eq!((mono[0].min, mono[0].max), (7.0, 300.0));
+    }
+
+    #[test]
+    fn typed_values_and_common_keywords_come_from_one_header() {
+        let header = header(vec![
+            ("SIMPLE", HeaderValue::Logical(true)),
+            ("EXPTIME", HeaderValue::Float(300.0)),
+            ("GAIN", HeaderValue::Integer(100)),
+            ("OFFSET", HeaderValue::Raw("50".into())),
+            ("XPIXSZ", HeaderValue::Raw("3.76D0".into())),
+            ("FOCALLEN", HeaderValue::Float(530.0)),
+            ("OBJECT", HeaderValue::String("M 31  ".into())),
+            ("INSTRUME", HeaderValue::String("ZWO ASI2600MM Pro".into())),
+            ("SET-TEMP", HeaderValue::Float(-10.0)),
+            ("CCD-TEMP", HeaderValue::Float(-9.8)),
+        ]);
+
+        assert_eq!(header.value("simple"), Some(&FitsValue::Bool(true)));
+        assert_eq!(header.value("GAIN"), Some(&FitsValue::Int(100)));
+        assert_eq!(header.value("OFFSET"), Some(&FitsValue::Int(50)));
+        assert_eq!(header.value("MISSING"), None);
+
+        assert_eq!(header.exposure(), Some(300.0));
+        assert_eq!(header.gain(), Some(100.0));
+        assert_eq!(header.offset(), Some(50.0));
+        assert_eq!(header.pixel_size(), Some(3.76));
+        assert_eq!(header.focal_length(), Some(530.0));
+        assert_eq!(header.object(), Some("M 31"));
+        assert_eq!(header.camera_model(), Some("ZWO ASI2600MM Pro"));
+        // CCD-TEMP (measured) wins over SET-TEMP (set point).
+        assert_eq!(header.temperature(), Some(-9.8));
+    }
+
+    #[test]
+    fn frame_type_classifies_imagetyp_spellings() {
+        for (value, expected) in [
+            ("LIGHT", FrameType::Light),
+            ("Light Frame", FrameType::Light),
+            ("DARK", FrameType::Dark),
+            ("DARKFLAT", FrameType::Dark),
+            ("Flat Field", FrameType::Flat),
+            ("Bias Frame", FrameType::Bias),
+            ("OFFSET", FrameType::Bias),
+            ("SNAPSHOT", FrameType::Other),
+        ] {
+            assert_eq!(FrameType::from_imagetyp(value), expected, "{value}");
+        }
+        assert!(FrameType::Flat.is_calibration());
+        assert!(!FrameType::Other.is_calibration());
+
+        assert_eq!(header(vec![]).frame_type(), FrameType::Light);
+        let flat = header(vec![("IMAGETYP", HeaderValue::String("FLAT".into()))]);
+        assert_eq!(flat.frame_type(), FrameType::Flat);
+    }
+
+    /// Minimal 4x4 16-bit frame with a few N.I.N.A.-style cards.
+    fn write_fits(path: &Path) {
+        let mut bytes = Vec::new();
+        for card in [
+            "SIMPLE  =                    T",
+            "BITPIX  =                   16",
+            "NAXIS   =                    2",
+            "NAXIS1  =                    4",
+            "NAXIS2  =                    4",
+            "EXPTIME =                120.0",
+            "GAIN    =                  139",
+            "CCD-TEMP=                 -5.2",
+            "INSTRUME= 'TestCam '",
+            "END",
+        ] {
+            let mut card = card.as_bytes().to_vec();
+            card.resize(80, b' ');
+            bytes.extend_from_slice(&card);
+        }
+        bytes.resize(2880, b' ');
+        bytes.resize(2 * 2880, 0);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn from_file_keeps_the_header_so_lookups_need_no_further_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frame.fits");
+        write_fits(&path);
+
+        let image = FitsImage::from_file(&path).unwrap();
+        // The file is gone: anything still answering came from the cache.
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.header_value("GAIN"), Some(&FitsValue::Int(139)));
+        assert_eq!(image.header.exposure(), Some(120.0));
+        assert_eq!(image.header.temperature(), Some(-5.2));
+        assert_eq!(image.header.camera_model(), Some("TestCam"));
+        assert_eq!(FitsImage::extract_temperature(&path), None);
+    }
+
+    #[test]
+    fn written_frames_read_back_identically() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = header(vec![
+            ("OBJECT", HeaderValue::String("M 31".into())),
+            ("EXPTIME", HeaderValue::Float(120.0)),
+            ("GAIN", HeaderValue::Integer(139)),
+            ("NAXIS1", HeaderValue::Integer(9999)),
+        ]);
+        let keywords: &[&str] = &["OBJECT", "exptime", "GAIN", "NAXIS1", "FILTER"];
+
+        let image = FitsImage {
+            width: 4,
+            height: 3,
+            data: vec![
+                0, 1, 2, 3, 1234, 32767, 32768, 32769, 40000, 65000, 65534, 65535,
+            ],
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: FitsHeader::default(),
+        };
+        let path = dir.path().join("u16.fits");
+        image
+            .write_to_file(&path, FitsDepth::U16, Some((&source, keywords)))
+            .unwrap();
+        let back = FitsImage::from_file(&path).unwrap();
+        assert_eq!((back.width, back.height), (4, 3));
+        assert_eq!(back.data, image.data);
+        assert_eq!((back.raw_min, back.raw_scale), (0.0, 1.0));
+        assert_eq!(
+            back.header.value("OBJECT").and_then(FitsValue::as_str),
+            Some("M 31")
+        );
+        assert_eq!(back.header.exposure(), Some(120.0));
+        assert_eq!(back.header_value("GAIN"), Some(&FitsValue::Int(139)));
+        // Layout keywords come from the pixels, never the source header.
+        assert_eq!(back.header.dimensions(), Some((4, 3)));
+
+        // Float data keeps its physical units through a 32-bit write.
+        let float = FitsImage {
+            raw_min: 100.0,
+            raw_scale: 0.5,
+            ..image
+        };
+        let path = dir.path().join("f32.fits");
+        float.write_to_file(&path, FitsDepth::F32, None).unwrap();
+        let back = FitsImage::from_file(&path).unwrap();
+        assert_eq!((back.width, back.height), (4, 3));
+        assert_eq!(back.data, float.data);
+        assert_eq!((back.raw_min, back.raw_scale), (100.0, 0.5));
+        assert_eq!(back.header.value("OBJECT"), None);
+    }
+
+    #[test]
+    fn binning_averages_blocks_and_drops_partial_edges() {
+        let image = FitsImage {
+            width: 5,
+            height: 4,
+            data: vec![
+                0, 2, 10, 10, 99, //
+                4, 6, 10, 10, 99, //
+                1, 1, 65535, 65535, 99, //
+                1, 1, 65535, 65535, 99,
+            ],
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: Default::default(),
+        };
+        let binned = image.binned(2);
+        assert_eq!((binned.width, binned.height), (2, 2));
+        assert_eq!(binned.data, vec![3, 10, 1, 65535]);
+
+        assert_eq!(unbin_coordinate(0.0, 2), 0.5);
+        assert_eq!(unbin_coordinate(10.0, 4), 41.5);
+        assert_eq!(unbin_coordinate(7.25, 1), 7.25);
+    }
+
+    #[test]
+    fn clipped_frames_are_judged_under_and_over_exposed() {
+        let frame = |data: Vec<u16>| FitsImage {
+            width: data.len(),
+            height: 1,
+            data,
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: FitsHeader::default(),
+        };
+        let thresholds = ExposureThresholds::default();
+        let judge = |data: Vec<u16>| thresholds.verdict(&frame(data).exposure_clipping().unwrap());
+
+        // Sky around 800 ADU with a few saturated star cores.
+        let mut good = vec![800u16; 1000];
+        good[..5].fill(65535);
+        assert_eq!(judge(good), ExposureVerdict::Ok);
+
+        // Offset too low: 20% of the background pinned at zero.
+        let mut under = vec![40u16; 1000];
+        under[..200].fill(0);
+        let clipping = frame(under.clone()).exposure_clipping().unwrap();
+        assert_eq!(clipping.low_fraction, 0.2);
+        assert_eq!(clipping.high_fraction, 0.0);
+        assert_eq!(judge(under), ExposureVerdict::Under);
+
+        // Twilight flat: the histogram piles up at full scale.
+        let mut over = vec![60000u16; 1000];
+        over[..300].fill(65535);
+        over[300..350].fill(HIGH_CLIP_LEVEL);
+        assert_eq!(
+            frame(over.clone())
+                .exposure_clipping()
+                .unwrap()
+                .high_fraction,
+            0.35
+        );
+        assert_eq!(judge(over), ExposureVerdict::Over);
+
+        // Clipped at both ends: the worse end decides.
+        let mut both = vec![1000u16; 1000];
+        both[..20].fill(0);
+        both[20..100].fill(65535);
+        assert_eq!(judge(both), ExposureVerdict::Over);
+
+        // Thresholds are configurable.
+        let lenient = ExposureThresholds {
+            low_clip_fraction: 0.5,
+            high_clip_fraction: 0.5,
+        };
+        let mut under = vec![40u16; 1000];
+        under[..200].fill(0);
+        let clipping = frame(under).exposure_clipping().unwrap();
+        assert_eq!(lenient.verdict(&clipping), ExposureVerdict::Ok);
+        assert!(lenient.validate().is_ok());
+        assert!(ExposureThresholds {
+            low_clip_fraction: 0.0,
+            ..lenient
+        }
+        .validate()
+        .is_err());
+
+        // Rescaled float data has no fixed range to clip against.
+        let mut float = frame(vec![0, 65535]);
+        float.raw_scale = 0.5;
+        assert_eq!(float.exposure_clipping(), None);
+    }
+
+    #[test]
+    fn value_conversions_are_strict_about_type() {
+        assert_eq!(FitsValue::Float(4.0).as_i64(), Some(4));
+        assert_eq!(FitsValue::Float(4.5).as_i64(), None);
+        assert_eq!(FitsValue::String("4".into()).as_f64(), None);
+        assert_eq!(FitsValue::Bool(true).as_str(), None);
+        assert_eq!(
+            FitsValue::from(&HeaderValue::Raw("F".into())),
+            FitsValue::Bool(false)
+        );
+        assert_eq!(
+            FitsValue::from(&HeaderValue::Raw("not a number".into())),
+            FitsValue::String("not a number".into())
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Per-pixel difference of two stretched frames, rendered as a heatmap, for
+//! spotting what changed between subs (a satellite, a cloud edge).
+//!
+//! Both inputs are 8-bit stretched renderings; frames of different sizes are
+//! both resized to the smaller so every pixel has a partner.
+
+use image::{imageops::FilterType, GrayImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+/// Normalized difference above which a pixel counts as changed.
+pub const CHANGED_THRESHOLD: f64 = 0.1;
+
+/// Summary of a difference image; differences are normalized to 0..1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub width: u32,
+    pub height: u32,
+    pub mean_difference: f64,
+    pub max_difference: f64,
+    /// Share of pixels differing by more than [`CHANGED_THRESHOLD`].
+    pub changed_fraction: f64,
+}
+
+/// Heatmap of `|a - b|` (black = identical, through red and yellow to white
+/// = opposite) plus its summary.
+pub fn difference(a: &GrayImage, b: &GrayImage) -> (RgbImage, DiffStats) {
+    let width = a.width().min(b.width());
+    let height = a.height().min(b.height());
+    let a = fit_to(a, width, height);
+    let b = fit_to(b, width, height);
+
+    let mut heatmap = RgbImage::new(width, height);
+    let (mut sum, mut max, mut changed) = (0.0, 0.0f64, 0usize);
+    for (x, y, pixel) in heatmap.enumerate_pixels_mut() {
+        let d = (f64::from(a.get_pixel(x, y)[0]) - f64::from(b.get_pixel(x, y)[0])).abs() / 255.0;
+        sum += d;
+        max = max.max(d);
+        if d > CHANGED_THRESHOLD {
+            changed += 1;
+        }
+        *pixel = heat(d);
+    }
+    let pixels = (width as usize * height as usize).max(1) as f64;
+    (
+        heatmap,
+        DiffStats {
+            width,
+            height,
+            mean_difference: sum / pixels,
+            max_difference: max,
+            changed_fraction: changed as f64 / pixels,
+        },
+    )
+}
+
+fn fit_to(image: &GrayImage, width: u32, height: u32) -> std::borrow::Cow<'_, GrayImage> {
+    if image.dimensions() == (width, height) {
+        std::borrow::Cow::Borrowed(image)
+    } else {
+        std::borrow::Cow::Owned(image::imageops::resize(
+            image,
+            width,
+            height,
+            FilterType::Triangle,
+        ))
+    }
+}
+
+/// Black → red → yellow → white ramp over `t` in 0..1.
+fn heat(t: f64) -> Rgb<u8> {
+    const STOPS: [(f64, [f64; 3]); 4] = [
+        (0.0, [0.0, 0.0, 0.0]),
+        (0.33, [255.0, 0.0, 0.0]),
+        (0.66, [255.0, 255.0, 0.0]),
+        (1.0, [255.0, 255.0, 255.0]),
+    ];
+    let t = t.clamp(0.0, 1.0);
+    let upper = STOPS
+        .iter()
+        .position(|(at, _)| *at >= t)
+        .unwrap_or(3)
+        .max(1);
+    let (t0, c0) = STOPS[upper - 1];
+    let (t1, c1) = STOPS[upper];
+    let f = (t - t0) / (t1 - t0);
+    Rgb(std::array::from_fn(|i| {
+        (c0[i] + (c1[i] - c0[i]) * f).round() as u8
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn identical_frames_have_no_difference() {
+        let frame = GrayImage::from_pixel(8, 6, Luma([120]));
+        let (heatmap, stats) = difference(&frame, &frame);
+        assert_eq!(stats.max_difference, 0.0);
+        assert_eq!(stats.changed_fraction, 0.0);
+        assert_eq!(*heatmap.get_pixel(3, 3), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn a_transient_shows_up_and_sizes_meet_at_the_smaller() {
+        let a = GrayImage::from_pixel(10, 10, Luma([40]));
+        let mut b = a.clone();
+        b.put_pixel(2, 2, Luma([255]));
+        let (heatmap, stats) = difference(&a, &b);
+        assert!((stats.max_difference - 215.0 / 255.0).abs() < 1e-9);
+        assert!((stats.changed_fraction - 0.01).abs() < 1e-9);
+        assert_ne!(*heatmap.get_pixel(2, 2), Rgb([0, 0, 0]));
+
+        let (heatmap, stats) = difference(&GrayImage::new(20, 10), &GrayImage::new(10, 8));
+        assert_eq!(heatmap.dimensions(), (10, 8));
+        assert_eq!((stats.width, stats.height), (10, 8));
+    }
+
+    #[test]
+    fn heat_ramp_ends() {
+        assert_eq!(heat(0.0), Rgb([0, 0, 0]));
+        assert_eq!(heat(1.0), Rgb([255, 255, 255]));
+        assert_eq!(heat(0.33), Rgb([255, 0, 0]));
+    }
+}
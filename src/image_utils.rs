@@ -0,0 +1,55 @@
+//! Small image helpers shared by the preview, annotated and CLI render paths.
+
+use image::RgbImage;
+
+/// Dimensions of a `width`×`height` image scaled down to fit within
+/// `max_w`×`max_h`, preserving aspect ratio. Never upscales.
+pub fn fit_dimensions(width: u32, height: u32, max_w: u32, max_h: u32) -> (u32, u32) {
+    let scale = (max_w as f32 / width as f32)
+        .min(max_h as f32 / height as f32)
+        .min(1.0);
+    if scale >= 1.0 {
+        return (width, height);
+    }
+    (
+        ((width as f32 * scale) as u32).max(1),
+        ((height as f32 * scale) as u32).max(1),
+    )
+}
+
+/// Downscale an RGB image to fit within `max_w`×`max_h` (Lanczos3),
+/// preserving aspect ratio. An image that already fits is returned as is.
+pub fn resize_to_max(image: RgbImage, max_w: u32, max_h: u32) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let (new_w, new_h) = fit_dimensions(width, height, max_w, max_h);
+    if (new_w, new_h) == (width, height) {
+        return image;
+    }
+    image::imageops::resize(&image, new_w, new_h, image::imageops::FilterType::Lanczos3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_to_max_keeps_aspect_ratio() {
+        let landscape = resize_to_max(RgbImage::new(4000, 2000), 1200, 1200);
+        assert_eq!(landscape.dimensions(), (1200, 600));
+
+        let portrait = resize_to_max(RgbImage::new(2000, 4000), 1200, 1200);
+        assert_eq!(portrait.dimensions(), (600, 1200));
+
+        // Non-square bounds: the tighter axis wins.
+        let wide = resize_to_max(RgbImage::new(3000, 2000), 2000, 1000);
+        assert_eq!(wide.dimensions(), (1500, 1000));
+    }
+
+    #[test]
+    fn resize_to_max_never_upscales() {
+        let small = resize_to_max(RgbImage::new(800, 600), 1200, 1200);
+        assert_eq!(small.dimensions(), (800, 600));
+        let exact = resize_to_max(RgbImage::new(1200, 900), 1200, 1200);
+        assert_eq!(exact.dimensions(), (1200, 900));
+    }
+}
@@ -1,29 +1,42 @@
 pub mod accord_imaging;
 pub mod acquisition_context;
+pub mod airmass;
 pub mod astrometry;
 pub mod astrometry_headers;
 pub mod cli;
 pub mod commands;
+pub mod computed_metrics;
 pub mod concurrency;
 pub mod config;
 pub mod db;
 pub mod db_registry;
 pub mod debug;
 pub mod directory_tree;
+pub mod fits_read;
+pub mod fits_write;
+pub mod focus_stability;
 pub mod grading;
 pub mod hocus_focus_star_detection;
 pub mod image_analysis;
+pub mod image_diff;
+pub mod image_utils;
 pub mod models;
 pub mod nina_star_detection;
 pub mod photometry;
 pub mod psf_fitting;
+pub mod reject_reasons;
 pub mod satellites;
+pub mod scoring;
 pub mod sequence_analysis;
 pub mod server;
+pub mod sky_grouping;
 pub mod spatial_analysis;
 pub mod star_contours;
+pub mod timeline;
+pub mod trail_detection;
 pub mod ts_schema;
 pub mod utils;
+pub mod zero_point;
 
 // Main entry points
 pub mod cli_main;
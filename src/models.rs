@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Profile {
@@ -69,7 +70,7 @@ pub struct ProjectOverviewStats {
 }
 
 /// Small image record for project overview thumbnails.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub struct RecentImageSummary {
     pub id: i32,
     pub project_id: i32,
@@ -80,6 +81,53 @@ pub struct RecentImageSummary {
     pub grading_status: i32,
 }
 
+/// A run of images on one target/filter with no inter-frame gap larger than
+/// the session threshold, i.e. one night's (or one meridian side's) data.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct AcquisitionSession {
+    pub project_id: i32,
+    pub target_id: i32,
+    pub target_name: String,
+    pub filter_name: String,
+    pub start: i64,
+    pub end: i64,
+    pub image_count: i32,
+    pub accepted_count: i32,
+    pub rejected_count: i32,
+    pub pending_count: i32,
+}
+
+/// Exposure seconds for one filter of a target, split by grading status.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FilterIntegration {
+    pub filter_name: String,
+    pub accepted_seconds: f64,
+    pub rejected_seconds: f64,
+    pub pending_seconds: f64,
+    pub accepted_images: i32,
+    pub rejected_images: i32,
+    pub pending_images: i32,
+}
+
+impl FilterIntegration {
+    pub fn total_seconds(&self) -> f64 {
+        self.accepted_seconds + self.rejected_seconds + self.pending_seconds
+    }
+
+    pub fn total_images(&self) -> i32 {
+        self.accepted_images + self.rejected_images + self.pending_images
+    }
+}
+
+/// Integration time summed from image metadata `ExposureTime`. Images
+/// without a usable exposure are counted in `skipped_images` only.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IntegrationTime {
+    pub target_id: i32,
+    pub filters: Vec<FilterIntegration>,
+    pub skipped_images: i32,
+}
+
 /// Overall system statistics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OverallStats {
@@ -148,6 +196,16 @@ impl GradingStatus {
             _ => "Unknown",
         }
     }
+
+    /// Lowercase name of a status code, as the CLI reports it and curation
+    /// documents store it. Unknown codes read as pending.
+    pub fn lowercase_name(value: i32) -> &'static str {
+        match value {
+            1 => "accepted",
+            2 => "rejected",
+            _ => "pending",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -164,6 +222,14 @@ mod tests {
         assert_eq!(GradingStatus::from_i32(999), "Unknown");
     }
 
+    #[test]
+    fn test_grading_status_lowercase_name() {
+        assert_eq!(GradingStatus::lowercase_name(0), "pending");
+        assert_eq!(GradingStatus::lowercase_name(1), "accepted");
+        assert_eq!(GradingStatus::lowercase_name(2), "rejected");
+        assert_eq!(GradingStatus::lowercase_name(7), "pending");
+    }
+
     #[test]
     fn test_grading_status_enum_values() {
         assert_eq!(GradingStatus::Pending as i32, 0);
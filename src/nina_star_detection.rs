@@ -13,7 +13,7 @@ fn convert_16bpp_to_8bpp_nina(data: &[u16]) -> Vec<u8> {
 }
 
 /// Banker's rounding (round half to even) to match .NET's default Math.Round
-fn round_half_to_even(x: f64) -> f64 {
+pub(crate) fn round_half_to_even(x: f64) -> f64 {
     let truncated = x.trunc();
     let fraction = x - truncated;
 
@@ -677,6 +677,43 @@ fn analyze_star_pixels(
     (is_star, star)
 }
 
+/// HFR of one star measured exactly as [`detect_stars_with_original`] does:
+/// the pixels of `rectangle` within 1.2x `radius` of `center`, less
+/// `surrounding_mean`. For detectors of their own that want N.I.N.A.'s HFR
+/// for the stars they found.
+pub(crate) fn star_hfr(
+    data: &[u16],
+    width: usize,
+    height: usize,
+    center: (f64, f64),
+    radius: f64,
+    rectangle: Rectangle,
+    surrounding_mean: f64,
+) -> f64 {
+    let state = DetectionState {
+        detection_data: data,
+        original_data: data,
+        width,
+        height,
+        resize_factor: 1.0,
+        inverse_resize_factor: 1.0,
+        min_star_size: 0,
+        max_star_size: usize::MAX,
+    };
+    let star = Star {
+        position: center,
+        radius,
+        rectangle,
+        mean_brightness: 0.0,
+        surrounding_mean,
+        max_pixel_value: 0.0,
+        hfr: 0.0,
+        average: 0.0,
+        flux: 0.0,
+    };
+    calculate_star_hfr(&state, star).hfr
+}
+
 fn calculate_star_hfr(state: &DetectionState, mut star: Star) -> Star {
     let outer_radius = star.radius * 1.2;
     let mut sum = 0.0;
@@ -61,11 +61,29 @@ impl PSFModel {
     /// Calculate FWHM from sigma values based on PSF type
     pub fn calculate_fwhm(&self) -> f64 {
         let avg_sigma = (self.sigma_x + self.sigma_y) / 2.0;
+        avg_sigma * self.sigma_to_fwhm_factor()
+    }
+
+    /// FWHM along each fitted axis (`sigma_x`/`sigma_y`, before `theta`).
+    pub fn axis_fwhm(&self) -> (f64, f64) {
+        let factor = self.sigma_to_fwhm_factor();
+        (self.sigma_x * factor, self.sigma_y * factor)
+    }
+
+    /// Moffat beta of the model, `None` for non-Moffat fits.
+    pub fn beta(&self) -> Option<f64> {
+        match self.psf_type {
+            PSFType::Moffat4 => Some(4.0),
+            PSFType::Gaussian | PSFType::None => None,
+        }
+    }
+
+    fn sigma_to_fwhm_factor(&self) -> f64 {
         match self.psf_type {
-            PSFType::Gaussian => avg_sigma * 2.0 * (2.0 * 2.0_f64.ln()).sqrt(), // 2.354
+            PSFType::Gaussian => 2.0 * (2.0 * 2.0_f64.ln()).sqrt(), // 2.354
             PSFType::Moffat4 => {
                 // For Moffat with beta=4: FWHM = sigma * 2 * sqrt(2^(1/4) - 1)
-                avg_sigma * 2.0 * (2.0_f64.powf(0.25) - 1.0).sqrt() // ≈ 1.1895
+                2.0 * (2.0_f64.powf(0.25) - 1.0).sqrt() // ≈ 1.1895
             }
             PSFType::None => 0.0,
         }
@@ -0,0 +1,431 @@
+//! Rejection-reason aggregation: groups rejected frames by why they were
+//! rejected so the loss budget (clouds vs focus vs manual) is visible.
+//! Raw reasons can be folded into configured categories (`[reject_reasons]`)
+//! without rewriting the database.
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, TimeZone};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+use crate::config::ReasonMapping;
+use crate::models::AcquiredImage;
+
+/// Bucket for rejected frames with no recorded reason.
+pub const NO_REASON: &str = "No reason";
+
+/// Category for reasons no configured mapping matches.
+pub const OTHER: &str = "other";
+
+/// A raw `reject_reason` without its trailing score annotation: the details
+/// after `" - "` (as written by `filter-rejected`) or a trailing
+/// parenthetical, so "Statistical HFR - HFR 3.1 is 2.4σ ..." and
+/// "Statistical HFR - HFR 2.9 is 2.1σ ..." count as one reason.
+pub fn normalize_reason(raw: Option<&str>) -> String {
+    let Some(raw) = raw else {
+        return NO_REASON.to_string();
+    };
+    let mut reason = raw.split(" - ").next().unwrap_or(raw).trim();
+    if reason.ends_with(')')
+        && let Some(open) = reason.rfind('(')
+    {
+        reason = reason[..open].trim_end();
+    }
+    if reason.is_empty() {
+        NO_REASON.to_string()
+    } else {
+        reason.to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ReasonMatcher {
+    Exact(String),
+    Regex(Regex),
+}
+
+/// Compiled `[reject_reasons]` mappings. Without rules every reason is its
+/// own (normalized) category; with rules, the first match wins and anything
+/// unmatched is [`OTHER`].
+#[derive(Debug, Clone, Default)]
+pub struct ReasonMapper {
+    rules: Vec<(ReasonMatcher, String)>,
+}
+
+impl ReasonMapper {
+    pub fn new(mappings: &[ReasonMapping]) -> Result<Self> {
+        let rules = mappings
+            .iter()
+            .enumerate()
+            .map(|(index, mapping)| {
+                let category = mapping.category.trim();
+                if category.is_empty() {
+                    bail!(
+                        "reject_reasons mapping #{} has an empty category",
+                        index + 1
+                    );
+                }
+                let matcher = match (&mapping.exact, &mapping.regex) {
+                    (Some(exact), None) => ReasonMatcher::Exact(exact.trim().to_string()),
+                    (None, Some(pattern)) => {
+                        ReasonMatcher::Regex(Regex::new(pattern).with_context(|| {
+                            format!(
+                                "Invalid regex in reject_reasons mapping '{}': {}",
+                                category, pattern
+                            )
+                        })?)
+                    }
+                    _ => bail!(
+                        "reject_reasons mapping '{}' needs exactly one of exact or regex",
+                        category
+                    ),
+                };
+                Ok((matcher, category.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The category a stored reject reason counts toward.
+    pub fn categorize(&self, raw: Option<&str>) -> String {
+        let normalized = normalize_reason(raw);
+        if self.rules.is_empty() {
+            return normalized;
+        }
+        let full = raw.unwrap_or("");
+        self.rules
+            .iter()
+            .find(|(matcher, _)| match matcher {
+                ReasonMatcher::Exact(exact) => {
+                    exact.eq_ignore_ascii_case(&normalized)
+                        || exact.eq_ignore_ascii_case(full.trim())
+                }
+                ReasonMatcher::Regex(regex) => regex.is_match(full),
+            })
+            .map(|(_, category)| category.clone())
+            .unwrap_or_else(|| OTHER.to_string())
+    }
+}
+
+/// Marker recognised as a manual grading decision when none is configured.
+pub const DEFAULT_MANUAL_MARKER: &str = "Manual";
+
+/// Substrings that mark a stored reject reason as a manual decision, which
+/// regrade resets and `--preserve-manual` leave alone. Matching is
+/// ASCII case-insensitive, like SQLite's `LIKE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManualMarkers(Vec<String>);
+
+impl Default for ManualMarkers {
+    fn default() -> Self {
+        Self(vec![DEFAULT_MANUAL_MARKER.to_string()])
+    }
+}
+
+impl ManualMarkers {
+    pub fn new(markers: &[String]) -> Result<Self> {
+        let markers: Vec<String> = markers.iter().map(|m| m.trim().to_string()).collect();
+        if markers.is_empty() {
+            bail!("reject_reasons.manual_markers needs at least one marker");
+        }
+        if markers.iter().any(String::is_empty) {
+            bail!("reject_reasons.manual_markers entries must not be blank");
+        }
+        Ok(Self(markers))
+    }
+
+    /// Whether a stored reject reason records a manual decision.
+    pub fn matches(&self, reason: Option<&str>) -> bool {
+        let Some(reason) = reason else {
+            return false;
+        };
+        let reason = reason.to_ascii_lowercase();
+        self.0
+            .iter()
+            .any(|marker| reason.contains(&marker.to_ascii_lowercase()))
+    }
+
+    /// SQL condition equivalent to [`ManualMarkers::matches`] on `column`,
+    /// with one `LIKE` parameter per marker. `%`, `_` and `\` in markers
+    /// match literally. NULL reasons yield NULL, as a bare `LIKE` would.
+    pub fn sql_condition(&self, column: &str) -> (String, Vec<String>) {
+        let condition = self
+            .0
+            .iter()
+            .map(|_| format!("{} LIKE ? ESCAPE '\\'", column))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let params = self
+            .0
+            .iter()
+            .map(|marker| {
+                let escaped = marker
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                format!("%{}%", escaped)
+            })
+            .collect();
+        (format!("({})", condition), params)
+    }
+}
+
+/// The night a timestamp belongs to, by the local date it started: frames
+/// before local noon count toward the previous evening, matching
+/// `night_report::night_window`.
+pub fn night_of<Tz: TimeZone>(timestamp: i64, tz: &Tz) -> Option<NaiveDate> {
+    let local = tz.timestamp_opt(timestamp, 0).earliest()?;
+    Some((local.naive_local() - chrono::Duration::hours(12)).date())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ReasonCount {
+    pub reason: String,
+    pub count: usize,
+    /// Share of the group's rejected frames, 0-100.
+    pub percent: f64,
+}
+
+/// Rejections from one night; `night` is null for frames without a date.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct NightRejections {
+    pub night: Option<NaiveDate>,
+    pub total_rejected: usize,
+    pub reasons: Vec<ReasonCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct RejectionSummary {
+    pub total_rejected: usize,
+    pub reasons: Vec<ReasonCount>,
+    /// Present only when bucketing by night; oldest night first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nights: Option<Vec<NightRejections>>,
+}
+
+/// Summarize already-rejected frames by `mapper` category, optionally also
+/// per night in `tz`.
+pub fn summarize_rejections<'a, Tz: TimeZone>(
+    images: impl IntoIterator<Item = &'a AcquiredImage>,
+    mapper: &ReasonMapper,
+    by_night: Option<&Tz>,
+) -> RejectionSummary {
+    let mut overall: HashMap<String, usize> = HashMap::new();
+    let mut nights: BTreeMap<Option<NaiveDate>, HashMap<String, usize>> = BTreeMap::new();
+    for image in images {
+        let reason = mapper.categorize(image.reject_reason.as_deref());
+        if let Some(tz) = by_night {
+            let night = image.acquired_date.and_then(|ts| night_of(ts, tz));
+            *nights
+                .entry(night)
+                .or_default()
+                .entry(reason.clone())
+                .or_default() += 1;
+        }
+        *overall.entry(reason).or_default() += 1;
+    }
+
+    let (total_rejected, reasons) = reason_counts(overall);
+    RejectionSummary {
+        total_rejected,
+        reasons,
+        nights: by_night.map(|_| {
+            nights
+                .into_iter()
+                .map(|(night, counts)| {
+                    let (total_rejected, reasons) = reason_counts(counts);
+                    NightRejections {
+                        night,
+                        total_rejected,
+                        reasons,
+                    }
+                })
+                .collect()
+        }),
+    }
+}
+
+/// Counts with percentages, most frequent first (ties alphabetically).
+fn reason_counts(counts: HashMap<String, usize>) -> (usize, Vec<ReasonCount>) {
+    let total: usize = counts.values().sum();
+    let mut reasons: Vec<ReasonCount> = counts
+        .into_iter()
+        .map(|(reason, count)| ReasonCount {
+            reason,
+            count,
+            percent: if total == 0 {
+                0.0
+            } else {
+                count as f64 * 100.0 / total as f64
+            },
+        })
+        .collect();
+    reasons.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.reason.cmp(&b.reason)));
+    (total, reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn rejected(id: i32, reason: Option<&str>, acquired_date: Option<i64>) -> AcquiredImage {
+        AcquiredImage {
+            id,
+            project_id: 1,
+            target_id: 1,
+            acquired_date,
+            filter_name: "Ha".to_string(),
+            grading_status: 2,
+            metadata: "{}".to_string(),
+            reject_reason: reason.map(str::to_string),
+            profile_id: None,
+            guid: None,
+        }
+    }
+
+    #[test]
+    fn manual_markers_match_case_insensitively() {
+        let markers =
+            ManualMarkers::new(&["hand-picked".to_string(), "100%_sure".to_string()]).unwrap();
+        assert!(markers.matches(Some("Hand-Picked reject")));
+        assert!(markers.matches(Some("100%_SURE")));
+        assert!(!markers.matches(Some("Manual")));
+        assert!(!markers.matches(None));
+        assert!(ManualMarkers::default().matches(Some("manual: trailing")));
+        assert!(ManualMarkers::new(&[]).is_err());
+        assert!(ManualMarkers::new(&[" ".to_string()]).is_err());
+
+        let (condition, params) = markers.sql_condition("rejectreason");
+        assert_eq!(
+            condition,
+            "(rejectreason LIKE ? ESCAPE '\\' OR rejectreason LIKE ? ESCAPE '\\')"
+        );
+        assert_eq!(params, vec!["%hand-picked%", "%100\\%\\_sure%"]);
+    }
+
+    #[test]
+    fn normalize_strips_score_annotations() {
+        assert_eq!(
+            normalize_reason(Some(
+                "Statistical HFR - HFR 3.100 is 2.4σ from mean 2.500 (threshold: 2.0σ)"
+            )),
+            "Statistical HFR"
+        );
+        assert_eq!(normalize_reason(Some("HFR too high (3.2)")), "HFR too high");
+        assert_eq!(normalize_reason(Some("  Manual ")), "Manual");
+        assert_eq!(normalize_reason(Some("")), NO_REASON);
+        assert_eq!(normalize_reason(None), NO_REASON);
+    }
+
+    #[test]
+    fn summary_groups_reasons_with_percentages_and_nights() {
+        // 2024-03-01 22:00 UTC and 2024-03-02 03:00 UTC share the night of
+        // 03-01; 2024-03-02 21:00 UTC starts the next one.
+        let first_evening = 1_709_330_400;
+        let first_morning = first_evening + 5 * 3600;
+        let second_evening = first_evening + 23 * 3600;
+        let images = vec![
+            rejected(1, Some("Manual"), Some(first_evening)),
+            rejected(
+                2,
+                Some("Statistical HFR - HFR 3.1 is 2.4σ from mean 2.5"),
+                Some(first_morning),
+            ),
+            rejected(
+                3,
+                Some("Statistical HFR - HFR 2.9 is 2.1σ from mean 2.5"),
+                Some(second_evening),
+            ),
+            rejected(4, Some("Clouds"), Some(second_evening)),
+            rejected(5, None, None),
+        ];
+
+        let summary = summarize_rejections::<Utc>(&images, &ReasonMapper::default(), None);
+        assert_eq!(summary.total_rejected, 5);
+        assert!(summary.nights.is_none());
+        let reasons: Vec<_> = summary
+            .reasons
+            .iter()
+            .map(|r| (r.reason.as_str(), r.count))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("Statistical HFR", 2),
+                ("Clouds", 1),
+                ("Manual", 1),
+                (NO_REASON, 1)
+            ]
+        );
+        assert!((summary.reasons[0].percent - 40.0).abs() < 1e-9);
+
+        let nights = summarize_rejections(&images, &ReasonMapper::default(), Some(&Utc))
+            .nights
+            .unwrap();
+        let totals: Vec<_> = nights
+            .iter()
+            .map(|n| (n.night.map(|d| d.to_string()), n.total_rejected))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                (None, 1),
+                (Some("2024-03-01".to_string()), 2),
+                (Some("2024-03-02".to_string()), 2),
+            ]
+        );
+        assert!((nights[1].reasons[0].percent - 50.0).abs() < 1e-9);
+    }
+
+    fn mapping(category: &str, exact: Option<&str>, regex: Option<&str>) -> ReasonMapping {
+        ReasonMapping {
+            category: category.to_string(),
+            exact: exact.map(str::to_string),
+            regex: regex.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn mapper_folds_reasons_into_categories() {
+        let mapper = ReasonMapper::new(&[
+            mapping("Focus", Some("hfr too high"), None),
+            mapping("Focus", None, Some(r"(?i)\bHFR\b")),
+            mapping("Clouds", None, Some(r"(?i)too (few|many) stars|cloud")),
+            mapping("Manual", Some("Manual"), None),
+        ])
+        .unwrap();
+
+        assert_eq!(mapper.categorize(Some("HFR too high")), "Focus");
+        assert_eq!(
+            mapper.categorize(Some("Statistical HFR - HFR 3.1 is 2.4σ from mean 2.5")),
+            "Focus"
+        );
+        assert_eq!(mapper.categorize(Some("Too many stars")), "Clouds");
+        assert_eq!(mapper.categorize(Some("Thin clouds (0.8)")), "Clouds");
+        assert_eq!(mapper.categorize(Some("manual")), "Manual");
+        assert_eq!(mapper.categorize(Some("Satellite trail")), OTHER);
+        assert_eq!(mapper.categorize(None), OTHER);
+
+        // No rules: reasons pass through normalized.
+        assert_eq!(
+            ReasonMapper::default().categorize(Some("Satellite trail - 2 trails")),
+            "Satellite trail"
+        );
+    }
+
+    #[test]
+    fn mapper_rejects_ambiguous_or_invalid_rules() {
+        assert!(ReasonMapper::new(&[mapping("Focus", None, None)]).is_err());
+        assert!(ReasonMapper::new(&[mapping("Focus", Some("HFR"), Some("HFR"))]).is_err());
+        assert!(ReasonMapper::new(&[mapping(" ", Some("HFR"), None)]).is_err());
+        let err = ReasonMapper::new(&[mapping("Focus", None, Some("(unclosed"))]).unwrap_err();
+        assert!(err.to_string().contains("Focus"), "{err}");
+    }
+}
@@ -19,6 +19,7 @@ use seiza_satellites::{
     SingleExposure, TrackOptions, UtcTimestamp,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::astrometry::{
     wcs_from_response, AstrometryAnalysis, AstrometrySourceFingerprint, WcsResponse,
@@ -28,7 +29,7 @@ use crate::FitsImage;
 
 pub const SEIZA_SATELLITES_VERSION: &str = "0.4.2";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SatelliteCatalogState {
     Configured,
@@ -38,10 +39,11 @@ pub enum SatelliteCatalogState {
     Cached,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteCatalogProvenance {
     pub source: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Value>)]
     pub provider: Option<OrbitalCatalogProvider>,
     pub state: SatelliteCatalogState,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -208,7 +210,7 @@ fn file_identity(path: &Path) -> (Option<u64>, Option<u64>) {
     (Some(metadata.len()), modified)
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteExposureContext {
     pub start_utc: String,
     pub end_utc: String,
@@ -220,7 +222,7 @@ pub struct SatelliteExposureContext {
     pub header_keywords: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteTrackPrediction {
     pub name: String,
     pub label: String,
@@ -244,14 +246,16 @@ pub struct SatelliteTrackPrediction {
     /// Heuristic 0..1 chance of a visible trail. This is deliberately not an
     /// apparent magnitude and does not claim a pixel detection.
     pub bright_trail_risk: f64,
+    #[schema(value_type = Value)]
     pub risk_level: BrightTrailRiskLevel,
     /// Pixel evidence fitted inside a bounded corridor around this orbital
     /// prediction. The predicted segments above remain unchanged.
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Value>)]
     pub pixel_alignment: Option<PixelTrailAlignment>,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteRiskSummary {
     pub track_count: usize,
     pub potentially_bright_count: usize,
@@ -266,7 +270,7 @@ pub struct SatelliteRiskSummary {
     pub reject_recommended: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteAnalysis {
     pub image_id: i32,
     pub association: String,
@@ -292,7 +296,7 @@ pub struct SatelliteAnalysis {
     pub computed_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteAnalysisStatus {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub analysis: Option<SatelliteAnalysis>,
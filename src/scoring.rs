@@ -0,0 +1,292 @@
+//! Sequence scoring shared by the server and the CLI: loading a target's
+//! images with their expected framing, merging psf-guard's own evidence
+//! (quality scans, astrometry, computed metrics) into N.I.N.A.'s metrics,
+//! and scoring the result. `/analysis/sequence`, `analyze-sequences` and
+//! `night-report` all go through [`score_target_sequences`].
+
+use std::collections::HashMap;
+
+use crate::db::Database;
+
+/// One target's images (optionally narrowed to a filter) with the framing
+/// center each is expected to hit, as `score_target_sequences` takes them.
+pub type TargetImages = (
+    Vec<(crate::models::AcquiredImage, String, String)>,
+    HashMap<i32, Option<(f64, f64)>>,
+);
+
+pub fn load_target_images(
+    conn: &rusqlite::Connection,
+    target_id: i32,
+    filter_name: Option<&str>,
+) -> anyhow::Result<TargetImages> {
+    let db = Database::new(conn);
+    let images: Vec<_> = db
+        .query_light_images_scoped(None, Some(target_id))?
+        .into_iter()
+        .filter(|(img, _, _)| {
+            img.target_id == target_id && filter_name.is_none_or(|f| img.filter_name == f)
+        })
+        .collect();
+    let mut resolver = crate::acquisition_context::FramingResolver::new(conn)?;
+    let expected_by_image = images
+        .iter()
+        .map(|(image, _, _)| {
+            resolver
+                .expected_for_grading(conn, image)
+                .map(|expected| (image.id, expected))
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok((images, expected_by_image))
+}
+
+/// The images of every target in a field, loaded as one target's would be.
+pub fn load_field_images(
+    conn: &rusqlite::Connection,
+    target_ids: &[i32],
+    filter_name: Option<&str>,
+) -> anyhow::Result<TargetImages> {
+    let mut field_images = TargetImages::default();
+    for &target_id in target_ids {
+        let (images, expected_by_image) = load_target_images(conn, target_id, filter_name)?;
+        field_images.0.extend(images);
+        field_images.1.extend(expected_by_image);
+    }
+    Ok(field_images)
+}
+
+/// Per-database evidence the scorer merges into N.I.N.A.'s own metrics.
+pub struct ScoringCaches<'a> {
+    pub spatial: &'a crate::server::spatial_scan::SharedSpatialStore,
+    pub cache_dir: &'a std::path::Path,
+    pub astrometry: &'a crate::astrometry::AstrometryEvidenceCache,
+    /// psf-guard's own measurements, for frames N.I.N.A. didn't measure, as
+    /// loaded; each call keeps only the rows still current.
+    pub computed: &'a HashMap<i32, crate::computed_metrics::ComputedMetrics>,
+}
+
+/// Score one target's images, one sequence set per filter, newest session
+/// first. This is the analysis behind `/analysis/sequence`; the CLI report
+/// calls it too so both produce the same numbers. Blocking.
+pub fn score_target_sequences(
+    images: &[(crate::models::AcquiredImage, String, String)],
+    expected_by_image: &HashMap<i32, Option<(f64, f64)>>,
+    target_id: i32,
+    target_name: &str,
+    config: crate::sequence_analysis::SequenceAnalyzerConfig,
+    caches: &ScoringCaches,
+) -> Vec<crate::sequence_analysis::ScoredSequence> {
+    use crate::sequence_analysis::{extract_metrics_from_metadata, SequenceAnalyzer};
+
+    let session_gap_minutes = config.session_gap_minutes;
+    let analyzer = SequenceAnalyzer::new(config);
+    let computed = crate::computed_metrics::current_for(
+        caches.computed,
+        images.iter().map(|(img, _, _)| img.id),
+    );
+
+    // Group by filter_name and analyze each group
+    let mut by_filter: HashMap<String, Vec<_>> = HashMap::new();
+    let mut entries_by_filter: HashMap<String, Vec<_>> = HashMap::new();
+    for (img, _proj, _target) in images {
+        let mut metrics = extract_metrics_from_metadata(img.id, &img.metadata, img.acquired_date);
+        if let Some(computed) = computed.get(&img.id) {
+            computed.fill_missing(&mut metrics);
+        }
+        merge_spatial_metrics(&mut metrics, caches.spatial, &img.metadata);
+        merge_astrometry_metrics(
+            &mut metrics,
+            caches.cache_dir,
+            &img.metadata,
+            caches.astrometry,
+            expected_by_image.get(&img.id).copied().flatten(),
+        );
+        entries_by_filter
+            .entry(img.filter_name.clone())
+            .or_default()
+            .push(stored_entry_for(caches.spatial, img.id, &img.metadata));
+        by_filter
+            .entry(img.filter_name.clone())
+            .or_default()
+            .push(metrics);
+    }
+
+    let mut all_sequences = Vec::new();
+    for (filter, mut metrics) in by_filter {
+        if let Some(entries) = entries_by_filter.get(&filter) {
+            merge_photometric_signals(&mut metrics, entries, session_gap_minutes);
+        }
+        all_sequences.extend(analyzer.analyze(&metrics, target_id, target_name, &filter));
+    }
+    all_sequences.sort_by(|a, b| {
+        b.session_start
+            .cmp(&a.session_start)
+            .then_with(|| a.filter_name.cmp(&b.filter_name))
+    });
+    all_sequences
+}
+
+/// Extract the FITS basename from an acquiredimage metadata JSON blob.
+pub fn filename_from_metadata(metadata_json: &str) -> Option<String> {
+    let metadata: serde_json::Value = serde_json::from_str(metadata_json).ok()?;
+    let filename = metadata["FileName"].as_str()?;
+    filename
+        .split(&['\\', '/'][..])
+        .next_back()
+        .map(|s| s.to_string())
+}
+
+/// Fetch the filename-validated stored scan entry for an image, if any.
+pub fn stored_entry_for(
+    store: &crate::server::spatial_scan::SharedSpatialStore,
+    image_id: i32,
+    metadata_json: &str,
+) -> Option<crate::server::spatial_scan::StoredSpatialMetrics> {
+    let file_only = filename_from_metadata(metadata_json)?;
+    crate::server::spatial_scan::valid_quality_entry(store, image_id, &file_only)
+}
+
+/// Run the cross-frame photometric pass (transparency, localized extinction,
+/// per-cell temporal baselines) over one filter group and merge the signals
+/// into its `ImageMetrics`. `entries` parallels `metrics` index-for-index;
+/// frames without a stored scan entry contribute empty inputs and receive no
+/// signals. Frames are bucketed by exposure (flux ratios are only meaningful
+/// within one exposure length) and split into sessions before the pass.
+pub fn merge_photometric_signals(
+    metrics: &mut [crate::sequence_analysis::ImageMetrics],
+    entries: &[Option<crate::server::spatial_scan::StoredSpatialMetrics>],
+    session_gap_minutes: u64,
+) {
+    use crate::photometry::{
+        sequence_screening_signals, split_sessions, FrameInputs, PhotometryConfig,
+    };
+
+    if metrics.len() != entries.len() {
+        return;
+    }
+
+    // Time order (the analyzer applies the same stable sort later).
+    let mut order: Vec<usize> = (0..metrics.len()).collect();
+    order.sort_by_key(|&i| (metrics[i].timestamp.unwrap_or(0), metrics[i].image_id));
+
+    // Bucket by exposure seconds.
+    let mut buckets: std::collections::BTreeMap<i64, Vec<usize>> =
+        std::collections::BTreeMap::new();
+    for &i in &order {
+        let key = entries[i]
+            .as_ref()
+            .and_then(|e| e.exposure_s)
+            .map(|e| e.round() as i64)
+            .unwrap_or(-1);
+        buckets.entry(key).or_default().push(i);
+    }
+
+    let phot_config = PhotometryConfig::default();
+    // A gap too large to express never splits a session.
+    let gap_seconds =
+        crate::sequence_analysis::session_gap_seconds(session_gap_minutes).unwrap_or(i64::MAX);
+    for indices in buckets.values() {
+        let timestamps: Vec<Option<i64>> = indices.iter().map(|&i| metrics[i].timestamp).collect();
+        for session in split_sessions(&timestamps, gap_seconds) {
+            let session_idx: Vec<usize> = session.iter().map(|&s| indices[s]).collect();
+            let inputs: Vec<FrameInputs> = session_idx
+                .iter()
+                .map(|&i| match &entries[i] {
+                    Some(e) => FrameInputs {
+                        catalog: e.catalog.clone(),
+                        star_cell_counts: e.star_cell_counts.clone(),
+                        bg_cell_medians: e.bg_cell_medians.clone(),
+                    },
+                    None => FrameInputs::default(),
+                })
+                .collect();
+            let dims = session_idx.iter().find_map(|&i| {
+                entries[i].as_ref().and_then(|e| {
+                    (e.width > 0 && e.grid_cols > 0).then_some((
+                        e.width,
+                        e.height,
+                        e.grid_cols,
+                        e.grid_rows,
+                    ))
+                })
+            });
+            let Some((width, height, grid_cols, grid_rows)) = dims else {
+                continue;
+            };
+            let signals = sequence_screening_signals(
+                &inputs,
+                width,
+                height,
+                (grid_cols, grid_rows),
+                &phot_config,
+            );
+            for (&i, sig) in session_idx.iter().zip(signals) {
+                let m = &mut metrics[i];
+                m.transparency = sig.transparency;
+                m.extinction_cell_fraction = sig.extinction_cell_fraction;
+                m.star_cell_drop_fraction = sig.star_cell_drop_fraction;
+                m.bg_cell_rise_fraction = sig.bg_cell_rise_fraction;
+                m.bg_cell_fall_fraction = sig.bg_cell_fall_fraction;
+            }
+        }
+    }
+}
+
+/// Merge fresh detector and spatial results from the per-DB quality cache.
+/// A quality scan is the source of truth for star count and HFR once present;
+/// the spatial fields fill values that N.I.N.A. does not store.
+pub fn merge_spatial_metrics(
+    metrics: &mut crate::sequence_analysis::ImageMetrics,
+    store: &crate::server::spatial_scan::SharedSpatialStore,
+    metadata_json: &str,
+) {
+    let Some(file_only) = filename_from_metadata(metadata_json) else {
+        return;
+    };
+    if let Some(entry) =
+        crate::server::spatial_scan::valid_entry(store, metrics.image_id, &file_only)
+    {
+        if entry.detector == crate::server::spatial_scan::QUALITY_DETECTOR
+            && entry.detector_version == crate::server::spatial_scan::QUALITY_DETECTOR_VERSION
+        {
+            metrics.star_count = Some(entry.star_count as f64);
+            metrics.hfr = (entry.avg_hfr > 0.0).then_some(entry.avg_hfr);
+        }
+        if metrics.dead_cell_fraction.is_none() {
+            metrics.dead_cell_fraction = entry.dead_cell_fraction;
+        }
+        if metrics.bg_cell_spread.is_none() {
+            metrics.bg_cell_spread = Some(entry.bg_cell_spread);
+        }
+        if metrics.bg_glow_max.is_none() && entry.bg_glow_max > 0.0 {
+            metrics.bg_glow_max = Some(entry.bg_glow_max);
+        }
+    }
+}
+
+pub fn merge_astrometry_metrics(
+    metrics: &mut crate::sequence_analysis::ImageMetrics,
+    cache_dir: &std::path::Path,
+    metadata_json: &str,
+    evidence: &crate::astrometry::AstrometryEvidenceCache,
+    expected_target: Option<(f64, f64)>,
+) {
+    let Some(file_only) = filename_from_metadata(metadata_json) else {
+        return;
+    };
+    let Some(analysis) = evidence.evidence_for_source(cache_dir, metrics.image_id, expected_target)
+    else {
+        return;
+    };
+    let cached_file = std::path::Path::new(&analysis.source_fingerprint.canonical_path)
+        .file_name()
+        .and_then(|name| name.to_str());
+    if cached_file != Some(file_only.as_str()) {
+        return;
+    }
+    metrics.astrometry = crate::sequence_analysis::astrometry_metrics_from_analysis(&analysis);
+    metrics.satellite =
+        crate::satellites::persisted_analysis(cache_dir, metrics.image_id, &analysis)
+            .as_ref()
+            .map(crate::sequence_analysis::SatelliteFrameMetrics::from);
+}
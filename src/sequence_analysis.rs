@@ -1,7 +1,9 @@
+use crate::image_analysis::{ExposureClipping, ExposureThresholds, ExposureVerdict};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// Issue categories for quality problems detected in image sequences.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum IssueCategory {
     LikelyClouds,
@@ -20,11 +22,17 @@ pub enum IssueCategory {
     /// A solved single exposure has a predicted sunlit satellite crossing.
     /// This is orbital prediction evidence, not a pixel-trail detection.
     SatelliteTrailRisk,
+    /// A long straight trail was found in the pixels themselves
+    /// (`trail_detection`), whatever caused it.
+    SatelliteTrail,
+    /// The histogram is clipped at the sensor's floor or ceiling
+    /// (`image_analysis::ExposureVerdict`): an exposure or offset mistake.
+    BadExposure,
     UnknownDegradation,
 }
 
 /// Per-image normalized metric values (0.0 = worst in sequence, 1.0 = best).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NormalizedMetrics {
     pub star_count: Option<f64>,
     pub hfr: Option<f64>,
@@ -46,7 +54,7 @@ pub struct NormalizedMetrics {
     pub pointing: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PointingQuality {
     pub pixel_solved: bool,
     pub solve_failed: bool,
@@ -108,7 +116,7 @@ pub struct AstrometryFrameMetrics {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct SatelliteFrameMetrics {
     pub predicted_tracks: usize,
     pub potentially_bright_count: usize,
@@ -140,6 +148,26 @@ impl From<&crate::satellites::SatelliteAnalysis> for SatelliteFrameMetrics {
     }
 }
 
+/// Pixel trail detection summary for one frame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrailFrameMetrics {
+    pub count: usize,
+    /// Longest detected trail in full-resolution pixels.
+    pub longest_px: f64,
+    /// Frame's shorter side in pixels, to judge trail length in context.
+    pub frame_short_side_px: usize,
+}
+
+impl From<&crate::trail_detection::TrailDetection> for TrailFrameMetrics {
+    fn from(detection: &crate::trail_detection::TrailDetection) -> Self {
+        Self {
+            count: detection.trails.len(),
+            longest_px: detection.longest_px(),
+            frame_short_side_px: detection.width.min(detection.height),
+        }
+    }
+}
+
 pub fn astrometry_metrics_from_analysis(
     analysis: &crate::astrometry::AstrometryAnalysis,
 ) -> Option<AstrometryFrameMetrics> {
@@ -184,7 +212,7 @@ pub fn astrometry_metrics_from_analysis(
 }
 
 /// Quality analysis result for a single image within its sequence context.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ImageQualityResult {
     pub image_id: i32,
     pub quality_score: f64,
@@ -203,7 +231,7 @@ pub struct ImageQualityResult {
 }
 
 /// Reference values representing the best metrics observed in a sequence.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ReferenceValues {
     pub best_star_count: Option<f64>,
     pub best_hfr: Option<f64>,
@@ -213,7 +241,7 @@ pub struct ReferenceValues {
 }
 
 /// Summary statistics for a scored sequence.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SequenceSummary {
     pub excellent_count: usize,
     pub good_count: usize,
@@ -229,6 +257,8 @@ pub struct SequenceSummary {
     pub plate_solve_failed_count: usize,
     #[serde(default)]
     pub satellite_risk_count: usize,
+    #[serde(default)]
+    pub trail_count: usize,
 }
 
 /// A scored sequence of images sharing the same target, filter, and session.
@@ -302,6 +332,54 @@ pub struct ImageMetrics {
     /// Cached orbital prediction for this exact source file and WCS.
     #[serde(default)]
     pub satellite: Option<SatelliteFrameMetrics>,
+    /// Linear trails found by pixel-level Hough detection.
+    #[serde(default)]
+    pub trails: Option<TrailFrameMetrics>,
+    /// Histogram clipping measured from the pixels.
+    #[serde(default)]
+    pub exposure_clipping: Option<ExposureClipping>,
+}
+
+/// Lowest quality score of each summary bucket; scores below `poor` count
+/// as bad.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SummaryThresholds {
+    pub excellent: f64,
+    pub good: f64,
+    pub fair: f64,
+    pub poor: f64,
+}
+
+impl Default for SummaryThresholds {
+    fn default() -> Self {
+        Self {
+            excellent: 0.90,
+            good: 0.70,
+            fair: 0.50,
+            poor: 0.30,
+        }
+    }
+}
+
+impl SummaryThresholds {
+    /// Thresholds must lie in 0..=1 and strictly decrease from excellent to
+    /// poor, so every score lands in exactly one bucket.
+    pub fn validate(&self) -> Result<(), String> {
+        let ordered = [self.excellent, self.good, self.fair, self.poor];
+        if ordered.iter().any(|t| !(0.0..=1.0).contains(t)) {
+            return Err(format!(
+                "summary thresholds must be between 0 and 1, got {:?}",
+                ordered
+            ));
+        }
+        if ordered.windows(2).any(|pair| pair[0] <= pair[1]) {
+            return Err(format!(
+                "summary thresholds must decrease from excellent to poor, got {:?}",
+                ordered
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Configurable weights for composite quality scoring.
@@ -478,6 +556,36 @@ pub struct SequenceAnalyzerConfig {
     /// the absolute spatial-coverage term.
     #[serde(default = "default_baseline_freeze_max_frames")]
     pub baseline_freeze_max_frames: usize,
+    /// Opening frames of a sequence scored against the median of the first
+    /// `warmup_frames + 1` frames instead of a baseline built from whatever
+    /// frame happened to come first. That median also seeds the EWMA
+    /// baselines, which the warm-up frames then leave untouched, so one
+    /// unusually sharp or rich first frame no longer makes the next few
+    /// look anomalous. 0 restores seeding from the first frame.
+    #[serde(default = "default_warmup_frames")]
+    pub warmup_frames: usize,
+    /// Quality-score boundaries of the summary's excellent/good/fair/poor
+    /// buckets.
+    #[serde(default)]
+    pub summary_thresholds: SummaryThresholds,
+    /// Temporal-anomaly score above which a frame gets an `[Auto]` regrade
+    /// reason on its own, whatever its quality score. The anomaly only
+    /// trims the quality score by up to half, so a frame at a cloud's edge
+    /// can keep good absolute metrics and a passing score; this rejects it
+    /// anyway. Off (`None`) by default.
+    #[serde(default)]
+    pub temporal_anomaly_reject_threshold: Option<f64>,
+    /// Frames with fewer detected stars than this fail outright: score 0
+    /// and an `[Auto]` regrade reason, whatever their other metrics. Almost
+    /// always a clouded-out frame whose HFR and background mean nothing.
+    /// Off (`None`) by default.
+    #[serde(default)]
+    pub min_stars: Option<usize>,
+    /// Frames whose measured clipping is judged under- or over-exposed fail
+    /// outright, like `min_stars`. Frames without a clipping measurement
+    /// are never judged. Off (`None`) by default.
+    #[serde(default)]
+    pub exposure_thresholds: Option<ExposureThresholds>,
 }
 
 fn default_dead_cell_rise_threshold() -> f64 {
@@ -500,6 +608,10 @@ fn default_baseline_freeze_max_frames() -> usize {
     15
 }
 
+fn default_warmup_frames() -> usize {
+    2
+}
+
 fn default_extinction_cells_threshold() -> f64 {
     0.06
 }
@@ -537,15 +649,28 @@ impl Default for SequenceAnalyzerConfig {
             bg_spread_rise_threshold: default_bg_spread_rise_threshold(),
             baseline_freeze_threshold: default_baseline_freeze_threshold(),
             baseline_freeze_max_frames: default_baseline_freeze_max_frames(),
+            warmup_frames: default_warmup_frames(),
             extinction_cells_threshold: default_extinction_cells_threshold(),
             transparency_threshold: default_transparency_threshold(),
             star_drop_cells_threshold: default_star_drop_cells_threshold(),
             bg_rise_cells_threshold: default_bg_rise_cells_threshold(),
             bg_glow_threshold: default_bg_glow_threshold(),
+            summary_thresholds: SummaryThresholds::default(),
+            temporal_anomaly_reject_threshold: None,
+            min_stars: None,
+            exposure_thresholds: None,
         }
     }
 }
 
+/// A session gap in seconds, or `None` when `minutes` is too large to
+/// compare against `i64` timestamps.
+pub fn session_gap_seconds(minutes: u64) -> Option<i64> {
+    minutes
+        .checked_mul(60)
+        .and_then(|seconds| i64::try_from(seconds).ok())
+}
+
 /// Analyzer that scores image quality within acquisition sequences.
 pub struct SequenceAnalyzer {
     config: SequenceAnalyzerConfig,
@@ -583,7 +708,8 @@ impl SequenceAnalyzer {
         let mut sorted: Vec<ImageMetrics> = images.to_vec();
         sorted.sort_by_key(|img| img.timestamp.unwrap_or(0));
 
-        let gap_seconds = (self.config.session_gap_minutes * 60) as i64;
+        // A gap too large to express never splits a session.
+        let gap_seconds = session_gap_seconds(self.config.session_gap_minutes).unwrap_or(i64::MAX);
         let mut sequences: Vec<Vec<ImageMetrics>> = Vec::new();
         let mut current_seq: Vec<ImageMetrics> = vec![sorted[0].clone()];
 
@@ -656,6 +782,9 @@ impl SequenceAnalyzer {
                 .collect();
             self.merge_pointing_issues(&mut results);
             self.merge_satellite_issues(&mut results, &images);
+            self.merge_trail_issues(&mut results, &images);
+            self.merge_low_star_failures(&mut results, &images);
+            self.merge_exposure_failures(&mut results, &images);
             let summary = self.build_summary(&results);
 
             return ScoredSequence {
@@ -785,6 +914,10 @@ impl SequenceAnalyzer {
         self.classify_issues(&mut results, &images);
         self.merge_pointing_issues(&mut results);
         self.merge_satellite_issues(&mut results, &images);
+        self.merge_trail_issues(&mut results, &images);
+        self.merge_temporal_anomaly_rejections(&mut results);
+        self.merge_low_star_failures(&mut results, &images);
+        self.merge_exposure_failures(&mut results, &images);
 
         // Build reference values
         let reference_values = ReferenceValues {
@@ -866,6 +999,115 @@ impl SequenceAnalyzer {
         }
     }
 
+    fn merge_trail_issues(&self, results: &mut [ImageQualityResult], images: &[ImageMetrics]) {
+        for (result, image) in results.iter_mut().zip(images) {
+            let Some(trails) = image.trails.as_ref().filter(|t| t.count > 0) else {
+                continue;
+            };
+            push_issue(&mut result.flags, IssueCategory::SatelliteTrail);
+            result.category.get_or_insert(IssueCategory::SatelliteTrail);
+
+            let detail = format!(
+                "Pixel trail detection found {} linear trail(s); longest {:.0} px across a {} px frame side.",
+                trails.count, trails.longest_px, trails.frame_short_side_px,
+            );
+            result.details = Some(match result.details.take() {
+                Some(existing) => format!("{detail} {existing}"),
+                None => detail,
+            });
+
+            result.quality_score = result.quality_score.min(0.35);
+            let reason = format!(
+                "[Auto] Linear trail detected - {} trail(s), longest {:.0} px; verify overlay",
+                trails.count, trails.longest_px,
+            );
+            result.regrade_reason = Some(match result.regrade_reason.take() {
+                Some(existing) => format!("{existing}; {reason}"),
+                None => reason,
+            });
+        }
+    }
+
+    /// Propose rejecting frames whose temporal anomaly alone exceeds the
+    /// configured threshold. The quality score is left as computed.
+    fn merge_temporal_anomaly_rejections(&self, results: &mut [ImageQualityResult]) {
+        let Some(threshold) = self.config.temporal_anomaly_reject_threshold else {
+            return;
+        };
+        for result in results
+            .iter_mut()
+            .filter(|r| r.temporal_anomaly_score > threshold)
+        {
+            if result.category.is_none() {
+                push_issue(&mut result.flags, IssueCategory::UnknownDegradation);
+                result.category = Some(IssueCategory::UnknownDegradation);
+            }
+            let reason = format!(
+                "[Auto] Temporal anomaly - {:.2} over threshold {:.2}; score {:.2}",
+                result.temporal_anomaly_score, threshold, result.quality_score
+            );
+            result.regrade_reason = Some(match result.regrade_reason.take() {
+                Some(existing) => format!("{existing}; {reason}"),
+                None => reason,
+            });
+        }
+    }
+
+    /// Fail frames with fewer stars than `min_stars`: score 0 and a regrade
+    /// reason, as likely clouds unless something else already explains them.
+    fn merge_low_star_failures(&self, results: &mut [ImageQualityResult], images: &[ImageMetrics]) {
+        let Some(min_stars) = self.config.min_stars else {
+            return;
+        };
+        for (result, image) in results.iter_mut().zip(images) {
+            let Some(stars) = image.star_count.filter(|s| *s < min_stars as f64) else {
+                continue;
+            };
+            push_issue(&mut result.flags, IssueCategory::LikelyClouds);
+            result.category.get_or_insert(IssueCategory::LikelyClouds);
+            result.quality_score = 0.0;
+            let reason = format!(
+                "[Auto] Too few stars - {:.0} detected, minimum {}",
+                stars, min_stars
+            );
+            result.regrade_reason = Some(match result.regrade_reason.take() {
+                Some(existing) => format!("{existing}; {reason}"),
+                None => reason,
+            });
+        }
+    }
+
+    /// Fail frames whose histogram is clipped past `exposure_thresholds`:
+    /// score 0 and a regrade reason naming the clipped end.
+    fn merge_exposure_failures(&self, results: &mut [ImageQualityResult], images: &[ImageMetrics]) {
+        let Some(thresholds) = self.config.exposure_thresholds else {
+            return;
+        };
+        for (result, image) in results.iter_mut().zip(images) {
+            let Some(clipping) = image.exposure_clipping else {
+                continue;
+            };
+            let reason = match thresholds.verdict(&clipping) {
+                ExposureVerdict::Ok => continue,
+                ExposureVerdict::Under => format!(
+                    "[Auto] Under-exposed - {:.1}% of pixels clipped at 0 ADU",
+                    clipping.low_fraction * 100.0
+                ),
+                ExposureVerdict::Over => format!(
+                    "[Auto] Over-exposed - {:.1}% of pixels at full scale",
+                    clipping.high_fraction * 100.0
+                ),
+            };
+            push_issue(&mut result.flags, IssueCategory::BadExposure);
+            result.category.get_or_insert(IssueCategory::BadExposure);
+            result.quality_score = 0.0;
+            result.regrade_reason = Some(match result.regrade_reason.take() {
+                Some(existing) => format!("{existing}; {reason}"),
+                None => reason,
+            });
+        }
+    }
+
     /// Normalize values where higher is better (e.g. star count, SNR).
     /// Uses 5th/95th percentile bounds for robustness.
     fn normalize_metric_higher_better(&self, values: &[Option<f64>]) -> Vec<Option<f64>> {
@@ -911,12 +1153,22 @@ impl SequenceAnalyzer {
         let n = images.len();
         let mut scores = vec![0.0f64; n];
 
-        // EWMA baselines for each metric
-        let mut bl_stars: Option<f64> = None;
-        let mut bl_bg: Option<f64> = None;
-        let mut bl_hfr: Option<f64> = None;
-        let mut bl_snr: Option<f64> = None;
-        let mut bl_dead: Option<f64> = None;
+        // EWMA baselines for each metric, seeded from the opening window's
+        // median when warming up (else from the first frame seen).
+        let warmup = self.config.warmup_frames.min(n);
+        let opening = &images[..(warmup + 1).min(n)];
+        let opening_median = |metric: fn(&ImageMetrics) -> Option<f64>| {
+            if warmup == 0 {
+                return None;
+            }
+            let values: Vec<f64> = opening.iter().filter_map(metric).collect();
+            (!values.is_empty()).then(|| median(&values))
+        };
+        let mut bl_stars = opening_median(|m| m.star_count);
+        let mut bl_bg = opening_median(|m| m.background);
+        let mut bl_hfr = opening_median(|m| m.hfr);
+        let mut bl_snr = opening_median(|m| m.snr);
+        let mut bl_dead = opening_median(|m| m.dead_cell_fraction);
         // Consecutive frames excluded from the baseline update; bounds the
         // freeze so a permanent condition change becomes the new baseline.
         let mut frozen_streak: usize = 0;
@@ -985,6 +1237,12 @@ impl SequenceAnalyzer {
                 + tw.snr * snr_dev
                 + tw.spatial * dead_dev;
 
+            // Warm-up frames are judged against the opening median and
+            // leave the baselines at it.
+            if i < warmup {
+                continue;
+            }
+
             // Exclude anomalous frames from the baseline so a slow-growing
             // problem (tree drifting through the field over many frames)
             // cannot normalize itself into the baseline — but only for a
@@ -1828,14 +2086,16 @@ impl SequenceAnalyzer {
             out_of_target_count: 0,
             plate_solve_failed_count: 0,
             satellite_risk_count: 0,
+            trail_count: 0,
         };
 
+        let thresholds = &self.config.summary_thresholds;
         for r in results {
             match r.quality_score {
-                s if s >= 0.90 => summary.excellent_count += 1,
-                s if s >= 0.70 => summary.good_count += 1,
-                s if s >= 0.50 => summary.fair_count += 1,
-                s if s >= 0.30 => summary.poor_count += 1,
+                s if s >= thresholds.excellent => summary.excellent_count += 1,
+                s if s >= thresholds.good => summary.good_count += 1,
+                s if s >= thresholds.fair => summary.fair_count += 1,
+                s if s >= thresholds.poor => summary.poor_count += 1,
                 _ => summary.bad_count += 1,
             }
 
@@ -1864,6 +2124,9 @@ impl SequenceAnalyzer {
             if r.flags.contains(&IssueCategory::SatelliteTrailRisk) {
                 summary.satellite_risk_count += 1;
             }
+            if r.flags.contains(&IssueCategory::SatelliteTrail) {
+                summary.trail_count += 1;
+            }
         }
 
         summary
@@ -2115,6 +2378,8 @@ pub fn extract_metrics_from_metadata(
         bg_glow_max: None,
         astrometry: None,
         satellite: None,
+        trails: None,
+        exposure_clipping: None,
     }
 }
 
@@ -2142,6 +2407,8 @@ mod tests {
             bg_glow_max: None,
             astrometry: None,
             satellite: None,
+            trails: None,
+            exposure_clipping: None,
         }
     }
 
@@ -2173,6 +2440,8 @@ mod tests {
             bg_glow_max: None,
             astrometry: None,
             satellite: None,
+            trails: None,
+            exposure_clipping: None,
         }
     }
 
@@ -2203,6 +2472,8 @@ mod tests {
             bg_glow_max: None,
             astrometry: None,
             satellite: None,
+            trails: None,
+            exposure_clipping: None,
         }
     }
 
@@ -2785,6 +3056,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_warmup_window_judges_opening_frames_against_median() {
+        let mut images: Vec<ImageMetrics> = (0..10)
+            .map(|i| make_image(i, i as i64 * 300, 300.0, 2.5))
+            .collect();
+        // Exceptionally good first frame (transparency just before a haze
+        // layer settled in) must not become the yardstick for the session.
+        images[0].star_count = Some(420.0);
+        images[0].hfr = Some(2.0);
+
+        let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig::default());
+        let scores = analyzer.compute_temporal_scores(&images);
+        for (i, score) in scores.iter().enumerate() {
+            assert!(*score < 0.01, "Frame {} over-penalized: {}", i, score);
+        }
+
+        // Without warm-up the first frame seeds the baseline as before.
+        let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig {
+            warmup_frames: 0,
+            ..Default::default()
+        });
+        let scores = analyzer.compute_temporal_scores(&images);
+        assert!(
+            scores[1] > 0.1,
+            "First-frame seeding should penalize frame 1: {}",
+            scores[1]
+        );
+
+        // A poor first frame is still caught against the opening median.
+        images[0].star_count = Some(150.0);
+        images[0].hfr = Some(2.5);
+        let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig::default());
+        let scores = analyzer.compute_temporal_scores(&images);
+        assert!(
+            scores[0] > 0.1,
+            "Bad opening frame should score: {}",
+            scores[0]
+        );
+    }
+
     #[test]
     fn test_sequence_splitting_single_session() {
         let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig::default());
@@ -3131,6 +3442,196 @@ mod tests {
         assert_eq!(summary.cloud_events_detected, 1);
     }
 
+    #[test]
+    fn temporal_anomaly_alone_proposes_rejection_when_configured() {
+        let result = |image_id: i32, temporal_anomaly_score: f64| ImageQualityResult {
+            image_id,
+            // Good absolute metrics: well above any rejection score.
+            quality_score: 0.82,
+            temporal_anomaly_score,
+            category: None,
+            flags: vec![],
+            normalized_metrics: NormalizedMetrics {
+                star_count: Some(0.9),
+                hfr: Some(0.85),
+                eccentricity: None,
+                snr: Some(0.9),
+                background: Some(0.8),
+                spatial_coverage: None,
+                transparency: None,
+                pointing: None,
+            },
+            pointing: None,
+            satellite: None,
+            regrade_reason: None,
+            details: None,
+        };
+        let scored = || vec![result(1, 0.05), result(2, 0.45)];
+
+        // Off by default: the anomaly only trims the score.
+        let mut results = scored();
+        SequenceAnalyzer::new(SequenceAnalyzerConfig::default())
+            .merge_temporal_anomaly_rejections(&mut results);
+        assert!(results.iter().all(|r| r.regrade_reason.is_none()));
+
+        let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig {
+            temporal_anomaly_reject_threshold: Some(0.3),
+            ..Default::default()
+        });
+        let mut results = scored();
+        analyzer.merge_temporal_anomaly_rejections(&mut results);
+        assert!(results[0].regrade_reason.is_none());
+        let reason = results[1].regrade_reason.as_deref().unwrap();
+        assert!(reason.starts_with("[Auto] Temporal anomaly - 0.45"));
+        assert_eq!(results[1].quality_score, 0.82);
+        assert_eq!(results[1].category, Some(IssueCategory::UnknownDegradation));
+    }
+
+    #[test]
+    fn frames_below_min_stars_fail_regardless_of_other_metrics() {
+        let mut images: Vec<ImageMetrics> = (0..10)
+            .map(|i| make_image(i, i as i64 * 300, 300.0, 2.5))
+            .collect();
+        // Near-starless frame: the few detections are tight and round, so
+        // HFR alone would not condemn it.
+        images[4].star_count = Some(3.0);
+        images[4].hfr = Some(2.4);
+
+        let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig {
+            min_stars: Some(10),
+            ..Default::default()
+        });
+        let sequence = analyzer.analyze(&images, 1, "M31", "L").remove(0);
+        let failed = &sequence.images[4];
+        assert_eq!(failed.quality_score, 0.0);
+        assert!(failed.flags.contains(&IssueCategory::LikelyClouds));
+        let reason = failed.regrade_reason.as_deref().unwrap();
+        assert!(reason.contains("[Auto] Too few stars - 3 detected, minimum 10"));
+        assert!(sequence.images.iter().filter(|r| r.image_id != 4).all(|r| r
+            .regrade_reason
+            .as_deref()
+            .is_none_or(|r| !r.contains("Too few stars"))));
+
+        // Off by default.
+        let sequence = SequenceAnalyzer::new(SequenceAnalyzerConfig::default())
+            .analyze(&images, 1, "M31", "L")
+            .remove(0);
+        assert!(sequence.images[4].quality_score > 0.0);
+    }
+
+    #[test]
+    fn clipped_frames_fail_with_an_exposure_reason() {
+        let mut images: Vec<ImageMetrics> = (0..10)
+            .map(|i| make_image(i, i as i64 * 300, 300.0, 2.5))
+            .collect();
+        let clipped = |low_fraction, high_fraction| {
+            Some(ExposureClipping {
+                low_fraction,
+                high_fraction,
+            })
+        };
+        for image in &mut images {
+            image.exposure_clipping = clipped(0.0, 0.0001);
+        }
+        images[3].exposure_clipping = clipped(0.25, 0.0);
+        images[7].exposure_clipping = clipped(0.0, 0.4);
+
+        let analyzer = SequenceAnalyzer::new(SequenceAnalyzerConfig {
+            exposure_thresholds: Some(ExposureThresholds::default()),
+            ..Default::default()
+        });
+        let sequence = analyzer.analyze(&images, 1, "M31", "L").remove(0);
+        let under = &sequence.images[3];
+        assert_eq!(under.quality_score, 0.0);
+        assert_eq!(under.category, Some(IssueCategory::BadExposure));
+        assert!(under
+            .regrade_reason
+            .as_deref()
+            .unwrap()
+            .contains("[Auto] Under-exposed - 25.0% of pixels clipped at 0 ADU"));
+        let over = &sequence.images[7];
+        assert!(over
+            .regrade_reason
+            .as_deref()
+            .unwrap()
+            .contains("[Auto] Over-exposed - 40.0% of pixels at full scale"));
+        assert!(sequence
+            .images
+            .iter()
+            .filter(|r| r.image_id != 3 && r.image_id != 7)
+            .all(|r| !r.flags.contains(&IssueCategory::BadExposure)));
+
+        // Off by default.
+        let sequence = SequenceAnalyzer::new(SequenceAnalyzerConfig::default())
+            .analyze(&images, 1, "M31", "L")
+            .remove(0);
+        assert!(sequence.images[3].quality_score > 0.0);
+    }
+
+    #[test]
+    fn summary_buckets_follow_configured_thresholds() {
+        let scored = |image_id: i32, quality_score: f64| ImageQualityResult {
+            image_id,
+            quality_score,
+            temporal_anomaly_score: 0.0,
+            category: None,
+            flags: vec![],
+            normalized_metrics: NormalizedMetrics {
+                star_count: None,
+                hfr: None,
+                eccentricity: None,
+                snr: None,
+                background: None,
+                spatial_coverage: None,
+                transparency: None,
+                pointing: None,
+            },
+            pointing: None,
+            satellite: None,
+            regrade_reason: None,
+            details: None,
+        };
+        let results: Vec<_> = [0.95, 0.85, 0.75, 0.6, 0.45, 0.2]
+            .into_iter()
+            .enumerate()
+            .map(|(i, score)| scored(i as i32, score))
+            .collect();
+
+        let counts = |config: SequenceAnalyzerConfig| {
+            let summary = SequenceAnalyzer::new(config).build_summary(&results);
+            [
+                summary.excellent_count,
+                summary.good_count,
+                summary.fair_count,
+                summary.poor_count,
+                summary.bad_count,
+            ]
+        };
+        assert_eq!(counts(SequenceAnalyzerConfig::default()), [1, 2, 1, 1, 1]);
+
+        // A strict imager: only near-perfect frames are excellent and
+        // anything under 0.5 is bad.
+        let strict = SummaryThresholds {
+            excellent: 0.97,
+            good: 0.80,
+            fair: 0.65,
+            poor: 0.50,
+        };
+        assert!(strict.validate().is_ok());
+        let config = SequenceAnalyzerConfig {
+            summary_thresholds: strict,
+            ..Default::default()
+        };
+        assert_eq!(counts(config), [0, 2, 1, 1, 2]);
+
+        let unordered = SummaryThresholds {
+            good: 0.95,
+            ..Default::default()
+        };
+        assert!(unordered.validate().is_err());
+        assert!(SummaryThresholds::default().validate().is_ok());
+    }
+
     #[test]
     fn pixel_aligned_high_satellite_risk_recommends_reviewed_rejection() {
         let mut images = vec![
@@ -3170,6 +3671,40 @@ mod tests {
         assert_eq!(result[0].summary.satellite_risk_count, 1);
     }
 
+    #[test]
+    fn detected_pixel_trail_is_flagged_for_rejection() {
+        let mut images = vec![
+            make_image(1, 1000, 100.0, 2.0),
+            make_image(2, 1060, 100.0, 2.0),
+            make_image(3, 1120, 100.0, 2.0),
+        ];
+        images[1].trails = Some(TrailFrameMetrics {
+            count: 1,
+            longest_px: 1800.0,
+            frame_short_side_px: 4000,
+        });
+        images[2].trails = Some(TrailFrameMetrics::default());
+
+        let result = SequenceAnalyzer::new(SequenceAnalyzerConfig::default())
+            .analyze(&images, 1, "target", "L");
+        let by_id = |id| {
+            result[0]
+                .images
+                .iter()
+                .find(|image| image.image_id == id)
+                .unwrap()
+        };
+        let affected = by_id(2);
+        assert_eq!(affected.category, Some(IssueCategory::SatelliteTrail));
+        assert!(affected.quality_score <= 0.35);
+        assert!(affected
+            .regrade_reason
+            .as_deref()
+            .is_some_and(|reason| reason.contains("Linear trail detected")));
+        assert!(!by_id(3).flags.contains(&IssueCategory::SatelliteTrail));
+        assert_eq!(result[0].summary.trail_count, 1);
+    }
+
     #[test]
     fn possible_satellite_risk_warns_without_regrade() {
         let mut images = vec![
@@ -1,8 +1,9 @@
 use crate::sequence_analysis::{ImageQualityResult, ReferenceValues, SequenceSummary};
 use crate::server::state::RefreshStatus;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Serialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema)]
 pub enum ApiRefreshStatus {
     #[serde(rename = "ready")]
     Ready,
@@ -23,7 +24,7 @@ impl From<RefreshStatus> for ApiRefreshStatus {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -69,7 +70,7 @@ impl<T> ApiResponse<T> {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ProjectResponse {
     pub id: i32,
     pub profile_id: String,
@@ -78,9 +79,15 @@ pub struct ProjectResponse {
     pub display_name: String, // "Profile -> Project" or just "Project"
     pub description: Option<String>,
     pub has_files: bool,
+    /// Frames found on disk out of those with a recorded filename, from the
+    /// last file-cache refresh; absent until one has run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_present: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_total: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ProjectOverviewResponse {
     pub id: i32,
     pub profile_id: String,
@@ -102,7 +109,7 @@ pub struct ProjectOverviewResponse {
     pub recent_images: Vec<crate::models::RecentImageSummary>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TargetResponse {
     pub id: i32,
     pub name: String,
@@ -113,9 +120,15 @@ pub struct TargetResponse {
     pub accepted_count: i32,
     pub rejected_count: i32,
     pub has_files: bool,
+    /// Frames found on disk out of those with a recorded filename, from the
+    /// last file-cache refresh; absent until one has run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_present: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_total: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TargetOverviewResponse {
     pub id: i32,
     pub name: String,
@@ -137,14 +150,31 @@ pub struct TargetOverviewResponse {
     pub coordinates_display: Option<String>, // Human-readable RA/Dec
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DateRange {
     pub earliest: Option<i64>,
     pub latest: Option<i64>,
     pub span_days: Option<i32>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CurationImportQuery {
+    /// Report what would change without writing.
+    pub dry_run: Option<bool>,
+}
+
+/// Query for `/stats/rejections`: project/target names, and `by=night` to
+/// also bucket by night.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RejectionStatsQuery {
+    pub project: Option<String>,
+    pub target: Option<String>,
+    pub by: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct OverallStatsResponse {
     pub total_projects: i32,
     pub active_projects: i32, // Projects with images
@@ -162,14 +192,14 @@ pub struct OverallStatsResponse {
     pub recent_activity: Vec<RecentActivity>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RecentActivity {
     pub date: i64,
     pub images_added: i32,
     pub images_graded: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ImageResponse {
     pub id: i32,
     pub project_id: i32,
@@ -183,32 +213,175 @@ pub struct ImageResponse {
     pub reject_reason: Option<String>,
     pub metadata: serde_json::Value,
     pub filesystem_path: Option<String>,
+    /// Bookmarked by a reviewer; independent of `grading_status`.
+    pub favorite: bool,
+    /// Free-form labels, alphabetically.
+    pub tags: Vec<String>,
+    /// Histogram clipping judged against `[server] low_clip_fraction` /
+    /// `high_clip_fraction`. Only on the single-image response, and only
+    /// for 16-bit frames whose file was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_verdict: Option<crate::image_analysis::ExposureVerdict>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ImageQuery {
     pub project_id: Option<i32>,
     pub target_id: Option<i32>,
     pub status: Option<String>,
+    /// `newest` (default), `oldest` or `filter`.
+    pub sort_by: Option<String>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// Only images flagged as favorites.
+    pub favorites_only: Option<bool>,
+    /// Only images carrying this tag (case-insensitive).
+    pub tag: Option<String>,
+    /// `present` or `missing`: only images whose FITS file is (not) in the
+    /// directory tree cache.
+    pub files: Option<String>,
+    /// `light` or `calibration` (dark/flat/bias), by the recorded IMAGETYP;
+    /// images without one are lights.
+    pub imagetyp: Option<String>,
+}
+
+/// Query for `/images/{id}/neighbors`: the listing's scope and order.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NeighborQuery {
+    #[serde(alias = "project")]
+    pub project_id: Option<i32>,
+    #[serde(alias = "target")]
+    pub target_id: Option<i32>,
+    pub status: Option<String>,
+    pub sort_by: Option<String>,
+    pub favorites_only: Option<bool>,
+    pub tag: Option<String>,
+    pub imagetyp: Option<String>,
+    /// `present` or `missing`, as on the listing.
+    pub files: Option<String>,
+}
+
+/// Favorite state of one image after `POST`/`DELETE .../favorite`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FavoriteResponse {
+    pub image_id: i32,
+    pub favorite: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddTagsRequest {
+    pub tags: Vec<String>,
+}
+
+/// An image's tags after adding or removing some.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageTagsResponse {
+    pub image_id: i32,
+    pub tags: Vec<String>,
+}
+
+/// One tag in use and how many images carry it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// Previous/next image ids in the filtered listing; null at either end.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageNeighbors {
+    pub image_id: i32,
+    pub previous_id: Option<i32>,
+    pub next_id: Option<i32>,
+}
+
+/// Query for `/images/{id}/grading-detail`: statistical grading thresholds,
+/// each defaulting to the `regrade` default, and which rules to run (all by
+/// default).
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GradingDetailQuery {
+    /// Compare against frames acquired in the last this many days, as
+    /// `regrade --days` does (default 90).
+    pub days: Option<u32>,
+    pub hfr_stddev: Option<f64>,
+    pub star_stddev: Option<f64>,
+    pub median_shift_threshold: Option<f64>,
+    pub cloud_threshold: Option<f64>,
+    pub cloud_baseline_count: Option<usize>,
+    pub enable_hfr_analysis: Option<bool>,
+    pub enable_star_count_analysis: Option<bool>,
+    pub enable_distribution_analysis: Option<bool>,
+    pub enable_cloud_detection: Option<bool>,
+}
+
+/// Every statistical grading rule checked against one image, among the
+/// other frames of its target and filter. `rejected` is the grader's verdict:
+/// true exactly when some rule did not pass.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GradingDetail {
+    pub image_id: i32,
+    pub rejected: bool,
+    pub rules: Vec<crate::grading::RuleEvaluation>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Query for `/sessions`; the gap defaults to the sequence analyzer's.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SessionQuery {
+    pub project_id: Option<i32>,
+    pub target_id: Option<i32>,
+    pub session_gap_minutes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateGradeRequest {
     pub status: String, // "accepted", "rejected", "pending"
     pub reason: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Query for `/images/{image_id}/stars`: `bin=2|4` detects on a
+/// software-binned copy for a fast approximate answer (see
+/// `detect_stars_hocus_focus_binned`); the default `bin=1` is exact.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StarQuery {
+    pub bin: Option<usize>,
+    /// Detect only in this region, `x,y,w,h` in full-resolution pixels.
+    pub roi: Option<String>,
+    /// With `roi`: report star positions relative to the crop (`crop`)
+    /// instead of the full frame (`full`, default).
+    pub coords: Option<String>,
+    /// Minimum (signal - background) / noise for a star (detector
+    /// `sensitivity`, default 10).
+    pub threshold: Option<f64>,
+    /// Smallest star bounding box in pixels (default 5).
+    pub min_size: Option<usize>,
+    /// Keep only the brightest N stars in `stars`; `detected_stars` and the
+    /// averages still cover every detection.
+    pub max_stars: Option<usize>,
+    /// `none`, `gaussian` or `moffat4` (default).
+    pub psf_type: Option<String>,
+    /// `psf` (default, the detector's own HFR) or `nina` (N.I.N.A.'s
+    /// definition, to compare with the HFR it recorded).
+    pub hfr_method: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct StarDetectionResponse {
     pub detected_stars: usize,
     pub average_hfr: f64,
     pub average_fwhm: f64,
     pub stars: Vec<StarInfo>,
+    /// Fewer stars were detected than `[server] min_stars`: the frame is
+    /// most likely clouded out and its HFR / FWHM averages mean little.
+    #[serde(default)]
+    pub low_star_warning: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct StarInfo {
     pub x: f64,
     pub y: f64,
@@ -218,29 +391,166 @@ pub struct StarInfo {
     pub eccentricity: f64,
 }
 
-#[derive(Debug, Deserialize)]
+/// Query for `/images/{id}/pixels`: a region in full-resolution pixels.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PixelQuery {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+/// Raw values of a small region, in physical ADU, row-major (`values[row][col]`).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PixelRegionResponse {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<Vec<f64>>,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Fitted PSF parameters for one star, in full-frame pixel coordinates.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PsfStarData {
+    pub x: f64,
+    pub y: f64,
+    pub amplitude: f64,
+    pub background: f64,
+    pub fwhm_x: f64,
+    pub fwhm_y: f64,
+    pub theta: f64,
+    /// Moffat beta; absent for Gaussian fits.
+    pub beta: Option<f64>,
+    pub r2: f64,
+    pub eccentricity: f64,
+    pub hfr: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PsfDataResponse {
+    pub psf_type: String,
+    pub stars: Vec<PsfStarData>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PreviewOptions {
-    pub size: Option<String>, // "screen" or "large"
+    pub size: Option<String>, // a `[preview.sizes]` preset or "original"
     pub stretch: Option<bool>,
     pub midtone: Option<f64>,
     pub shadow: Option<f64>,
+    pub logarithmic: Option<bool>,
+    pub invert: Option<bool>,
     pub max_stars: Option<u32>, // Max number of stars to annotate
+    /// Annotation ranking before `max_stars`: `hfr` (default, best focus
+    /// first), `snr`, `brightness` or `r2`.
+    pub rank_by: Option<String>,
+    /// Annotate only stars with at least this SNR / peak brightness (ADU).
+    pub min_snr: Option<f64>,
+    pub min_brightness: Option<f64>,
+    /// Annotate only stars more elongated than this, to show problems.
+    pub min_eccentricity: Option<f64>,
+    /// Annotation marker: `circle` (default), `cross` or `box`.
+    pub marker: Option<String>,
+    /// Annotation marker size: `hfr-scaled` (default) or `fixed`.
+    pub marker_size: Option<String>,
+    /// Smallest / largest annotation marker radius in pixels (5 / 50).
+    pub marker_min: Option<f64>,
+    pub marker_max: Option<f64>,
+    /// Preview only this region, `x,y,w,h` in full-resolution pixels.
+    pub roi: Option<String>,
+    /// `subtract` removes the estimated sky background before the stretch;
+    /// `show` returns the background estimate itself; `none` (default).
+    pub background: Option<String>,
+    /// `png` (default) or `tiff` for a 16/32-bit TIFF of the frame; on the
+    /// annotated image, `png` or `svg`. Also accepted as `format`.
+    #[serde(alias = "format")]
+    pub fmt: Option<String>,
+    /// TIFF sample depth: 16 (default, integer) or 32 (float, 0..1).
+    pub bit_depth: Option<u32>,
 }
 
-#[derive(Debug, Serialize)]
+/// Query for `/images/{id}/compare`: two stretch settings rendered side by
+/// side. Unset values fall back to the preview defaults.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompareOptions {
+    pub size: Option<String>,
+    pub midtone_a: Option<f64>,
+    pub shadow_a: Option<f64>,
+    pub midtone_b: Option<f64>,
+    pub shadow_b: Option<f64>,
+}
+
+/// Query for `/images/diff`: the two frames to compare, the preview size
+/// preset they're rendered at, and whether to answer with the summary
+/// (`stats=true`) instead of the heatmap PNG.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffQuery {
+    pub a: i32,
+    pub b: i32,
+    pub size: Option<String>,
+    #[serde(default)]
+    pub stats: bool,
+}
+
+/// Query for `/targets/{id}/contact-sheet`. Unset values fall back to the
+/// CLI defaults; grade borders are on unless `grades=false`.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ContactSheetQuery {
+    pub filter: Option<String>,
+    pub cols: Option<u32>,
+    pub cell_size: Option<u32>,
+    pub grades: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ServerInfo {
     pub version: String,
     pub cache_directory: String,
     /// Whether `/api/databases` accepts mutating requests and database sync.
     /// Frontend hides those controls when false.
     pub allow_database_management: bool,
+    /// Server launched with `--read-only`; grading and edits answer 403.
+    pub read_only: bool,
     /// Optional plain-text notice displayed across the application.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub banner: Option<crate::config::SiteBannerConfig>,
 }
 
+/// Body of the `/api/health` liveness probe.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthStatus {
+    pub status: &'static str,
+}
+
+/// Body of the `/api/ready` readiness probe.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessStatus {
+    pub ready: bool,
+    pub databases: Vec<DatabaseReadiness>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DatabaseReadiness {
+    pub id: String,
+    /// The scheduler connection answered a trivial query.
+    pub database_ok: bool,
+    /// The image directory tree has been built at least once.
+    pub directory_tree_ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// Summary of one configured database, returned by `GET /api/databases`.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DatabaseSummary {
     pub id: String,
     pub name: String,
@@ -249,7 +559,7 @@ pub struct DatabaseSummary {
 }
 
 /// Database-to-database operations exposed by the management UI.
-#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SchedulerSyncKind {
     /// Telescope → local: structure, captures, and optional image data.
@@ -266,7 +576,7 @@ fn default_true() -> bool {
 
 /// Body of `POST /api/databases/{db_id}/sync`. `db_id` is the local working
 /// database; `peer_db_id` is the telescope scheduler database.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct SchedulerSyncRequest {
     pub peer_db_id: String,
     pub kind: SchedulerSyncKind,
@@ -294,7 +604,7 @@ pub struct SchedulerSyncRequest {
 }
 
 /// Insert/update counts for one scheduler table.
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
 pub struct SchedulerSyncTableCounts {
     pub inserted: usize,
     pub updated: usize,
@@ -314,7 +624,7 @@ impl From<&crate::commands::sync::TableCounts> for SchedulerSyncTableCounts {
 }
 
 /// Grade-push counts. Present only for `push_grades`.
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
 pub struct SchedulerSyncGradeCounts {
     pub source_considered: usize,
     pub source_no_guid: usize,
@@ -328,7 +638,7 @@ pub struct SchedulerSyncGradeCounts {
 }
 
 /// Result of a database-to-database scheduler sync or dry-run preview.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct SchedulerSyncResponse {
     pub kind: SchedulerSyncKind,
     pub dry_run: bool,
@@ -352,7 +662,7 @@ pub struct SchedulerSyncResponse {
     pub total_updated: usize,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct SchedulerSyncPreviewResponse {
     pub preview_id: String,
     pub created_at: i64,
@@ -361,7 +671,7 @@ pub struct SchedulerSyncPreviewResponse {
 }
 
 /// Body of `POST /api/databases`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AddDatabaseRequest {
     pub name: String,
     pub db_path: String,
@@ -375,7 +685,7 @@ pub struct AddDatabaseRequest {
 /// Body of `POST /api/databases/create` — create a brand-new Target
 /// Scheduler database (vendored schema, user_version 23) and start a
 /// background import of the given directories.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateDatabaseRequest {
     pub name: String,
     /// Directories of FITS files to import; also become the registry entry's
@@ -399,7 +709,7 @@ pub struct CreateDatabaseRequest {
 
 /// `POST /api/databases/create` response: the registered database plus the
 /// just-started import job's first progress snapshot.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CreateDatabaseResponse {
     pub database: DatabaseSummary,
     pub import: crate::server::import_job::ImportJobProgress,
@@ -407,7 +717,7 @@ pub struct CreateDatabaseResponse {
 
 /// Body of `POST /api/db/{db_id}/import` — import FITS folders into an
 /// existing database as a background job.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct ImportRequest {
     /// Directories to scan; defaults to the database's configured image_dirs.
     #[serde(default)]
@@ -430,10 +740,14 @@ pub struct ImportRequest {
     /// Coordinate-match radius in degrees (default 0.5).
     #[serde(default)]
     pub match_radius_deg: Option<f64>,
+    /// Group new frames into targets by sky position within this radius
+    /// (degrees) instead of by OBJECT name.
+    #[serde(default)]
+    pub group_by_position_deg: Option<f64>,
 }
 
 /// Status returned by both the import-start and import-progress endpoints.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ImportStatusResponse {
     /// POST: whether this request started a new job. GET: whether a job is
     /// currently running.
@@ -443,7 +757,8 @@ pub struct ImportStatusResponse {
 
 /// Query for `GET /api/db/{db_id}/export` — stream selected lights as a
 /// store-mode zip laid out `<target>/LIGHT/<filter>/...` (rejects excluded).
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ExportQuery {
     #[serde(default)]
     pub project_id: Option<i32>,
@@ -460,7 +775,7 @@ pub struct ExportQuery {
 /// into a folder on the SERVER's filesystem (desktop/Tauri mode, where the
 /// server is the user's own machine). Management-gated: on a remote server
 /// this writes to arbitrary paths.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LocalExportRequest {
     /// Destination folder (absolute path on the server machine).
     pub dest: String,
@@ -481,7 +796,7 @@ pub struct LocalExportRequest {
 }
 
 /// Body of `PUT /api/db/{db_id}/projects/{project_id}`.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct UpdateProjectRequest {
     #[serde(default)]
     pub name: Option<String>,
@@ -515,7 +830,7 @@ pub struct UpdateProjectRequest {
 
 /// Body of `PUT /api/db/{db_id}/targets/{target_id}` — rename and/or move a
 /// target to another project (same profile).
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct UpdateTargetRequest {
     #[serde(default)]
     pub name: Option<String>,
@@ -536,21 +851,37 @@ pub struct UpdateTargetRequest {
 }
 
 /// Body of `POST /api/db/{db_id}/projects/{project_id}/merge`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct MergeProjectRequest {
     pub into_project_id: i32,
 }
 
 /// Result of a merge: how much moved.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct MergeProjectResponse {
     pub targets_moved: usize,
     pub images_moved: usize,
 }
 
+/// Body of `POST /api/db/{db_id}/targets/{target_id}/merge`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MergeTargetRequest {
+    pub into_target_id: i32,
+    /// Delete the emptied source target and its exposure plans.
+    #[serde(default)]
+    pub delete_source: bool,
+}
+
+/// Result of a target merge.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergeTargetResponse {
+    pub images_moved: usize,
+    pub source_deleted: bool,
+}
+
 /// Body of `PUT /api/databases/{db_id}`. All fields are optional; absent fields
 /// leave the existing value unchanged.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct UpdateDatabaseRequest {
     #[serde(default)]
     pub name: Option<String>,
@@ -562,7 +893,7 @@ pub struct UpdateDatabaseRequest {
     pub image_dirs: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FileCheckResponse {
     pub images_checked: usize,
     pub files_found: usize,
@@ -570,7 +901,14 @@ pub struct FileCheckResponse {
     pub check_time_ms: u128,
 }
 
-#[derive(Debug, Serialize)]
+/// Result of `POST /refresh-cache/cancel`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CancelRefreshResponse {
+    /// False when no refresh was running.
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DirectoryTreeResponse {
     pub total_files: usize,
     pub unique_filenames: usize,
@@ -580,10 +918,72 @@ pub struct DirectoryTreeResponse {
     pub root_directory: String,
 }
 
-#[derive(Debug, Serialize)]
+/// Query for `GET /directory-cache`.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DirectoryCacheQuery {
+    /// Report every cached path for this filename (a path is reduced to its
+    /// file name), with whether each exists on disk now.
+    pub search: Option<String>,
+    /// Include the cached paths themselves, up to `limit`.
+    pub list: Option<bool>,
+    /// Listing size; defaults to 1000, capped at 10000.
+    pub limit: Option<usize>,
+}
+
+/// What the directory tree cache currently holds. Diagnostic for "file not
+/// found but it's right there".
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirectoryCacheResponse {
+    /// False until the first scan finishes; stats and roots are then empty.
+    pub built: bool,
+    pub stats: Option<DirectoryTreeResponse>,
+    pub roots: Vec<DirectoryRootResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<DirectoryCacheSearch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listing: Option<DirectoryCacheListing>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirectoryRootResponse {
+    pub path: String,
+    pub files: usize,
+    pub directories: usize,
+    pub scan_time_ms: u128,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirectoryCacheSearch {
+    pub filename: String,
+    /// Exact-name matches in lookup priority order; the first existing one is
+    /// what previews use.
+    pub matches: Vec<CachedPathResponse>,
+    /// Matches differing only by case (used when
+    /// `case_insensitive_filenames` is on).
+    pub case_insensitive_matches: Vec<CachedPathResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CachedPathResponse {
+    pub path: String,
+    pub exists: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirectoryCacheListing {
+    /// Sorted, at most `limit` entries.
+    pub paths: Vec<String>,
+    pub total: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CacheRefreshProgressResponse {
     pub is_refreshing: bool,
     pub stage: String,
+    /// A cancel was requested and the refresh is winding down.
+    pub cancel_requested: bool,
     pub progress_percentage: f32,
     pub elapsed_seconds: Option<u64>,
     pub directories_total: usize,
@@ -632,11 +1032,16 @@ pub struct FileStatusResponse {
 
 // Sequence analysis request/response types
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SequenceAnalysisQuery {
-    pub target_id: i32,
+    /// Target to analyze; every target with images when omitted.
+    pub target_id: Option<i32>,
     pub filter_name: Option<String>,
     pub session_gap_minutes: Option<u64>,
+    /// Opening frames per sequence scored against the opening median rather
+    /// than a running baseline (default 2; 0 disables).
+    pub warmup_frames: Option<usize>,
     pub weight_star_count: Option<f64>,
     pub weight_hfr: Option<f64>,
     pub weight_eccentricity: Option<f64>,
@@ -644,15 +1049,135 @@ pub struct SequenceAnalysisQuery {
     pub weight_background: Option<f64>,
     pub weight_spatial: Option<f64>,
     pub weight_pointing: Option<f64>,
+    /// Lowest quality score of each summary bucket; unset ones keep the
+    /// defaults (0.90 / 0.70 / 0.50 / 0.30).
+    pub threshold_excellent: Option<f64>,
+    pub threshold_good: Option<f64>,
+    pub threshold_fair: Option<f64>,
+    pub threshold_poor: Option<f64>,
+    /// Propose rejecting frames whose temporal anomaly exceeds this,
+    /// whatever their quality score.
+    pub temporal_anomaly_reject_threshold: Option<f64>,
+    /// Analyze targets of one project within this many degrees of each
+    /// other as one field, whatever they are named.
+    pub position_radius_deg: Option<f64>,
 }
 
-#[derive(Debug, Serialize)]
+/// Hours per filter for `/targets/{target_id}/integration`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FilterIntegrationHours {
+    pub filter_name: String,
+    pub accepted_hours: f64,
+    pub total_hours: f64,
+    pub accepted_images: i32,
+    pub total_images: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TargetIntegrationResponse {
+    pub target_id: i32,
+    pub target_name: String,
+    pub filters: Vec<FilterIntegrationHours>,
+    pub accepted_hours: f64,
+    pub total_hours: f64,
+    /// Images left out because their metadata has no usable `ExposureTime`.
+    pub skipped_images: i32,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimelineQuery {
+    pub filter: Option<String>,
+    /// Idle seconds before a frame above which it counts as a gap.
+    pub gap_threshold: Option<f64>,
+}
+
+/// `/targets/{target_id}/timeline`: every sub in exposure order with the
+/// gaps between them.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TargetTimelineResponse {
+    pub target_id: i32,
+    pub target_name: String,
+    pub gap_threshold: f64,
+    #[serde(flatten)]
+    pub timeline: crate::timeline::Timeline,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FocusStabilityQuery {
+    pub filter: Option<String>,
+    /// Start (Unix seconds) of the session to score, as listed by
+    /// `/sessions`; defaults to the latest session.
+    pub session: Option<i64>,
+    pub session_gap_minutes: Option<u64>,
+}
+
+/// `/targets/{target_id}/focus-stability`: HFR spread over one session's
+/// accepted frames, with the series behind it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FocusStabilityResponse {
+    pub target_id: i32,
+    pub target_name: String,
+    pub filter: Option<String>,
+    pub session_start: i64,
+    pub session_end: i64,
+    /// Starts of every session of the target, oldest first, for picking
+    /// another night.
+    pub sessions: Vec<i64>,
+    #[serde(flatten)]
+    pub stability: crate::focus_stability::FocusStability,
+}
+
+/// Per-image metric plotted by `/targets/{target_id}/trend`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendMetric {
+    Hfr,
+    Stars,
+    Eccentricity,
+    Snr,
+    Background,
+}
+
+impl TrendMetric {
+    pub fn value(self, metrics: &crate::sequence_analysis::ImageMetrics) -> Option<f64> {
+        match self {
+            TrendMetric::Hfr => metrics.hfr,
+            TrendMetric::Stars => metrics.star_count,
+            TrendMetric::Eccentricity => metrics.eccentricity,
+            TrendMetric::Snr => metrics.snr,
+            TrendMetric::Background => metrics.background,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrendQuery {
+    pub metric: TrendMetric,
+    pub filter: Option<String>,
+}
+
+/// One point of a metric time series, oldest first.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrendPoint {
+    pub timestamp: i64,
+    pub value: f64,
+    pub image_id: i32,
+    pub grading_status: i32,
+    /// From the frame's recorded airmass or altitude; `None` when the
+    /// metadata has neither.
+    pub airmass: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SequenceAnalysisResponse {
     pub sequences: Vec<ScoredSequenceResponse>,
 }
 
 /// Request body for starting a spatial (occlusion) metrics scan.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SpatialScanRequest {
     pub target_id: i32,
     pub filter_name: Option<String>,
@@ -668,7 +1193,7 @@ pub struct SpatialScanRequest {
 }
 
 /// Status returned by both the scan-start and scan-progress endpoints.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SpatialScanStatusResponse {
     /// POST: whether this request started a new scan. GET: whether a scan is
     /// currently running.
@@ -678,20 +1203,35 @@ pub struct SpatialScanStatusResponse {
     pub cached_count: usize,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct QualityBackfillRequest {
     /// Recompute cached star, background, photometry, and pointing evidence.
     #[serde(default)]
     pub force: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct QualityBackfillStatusResponse {
     pub started: bool,
     pub progress: crate::server::quality_backfill::QualityBackfillProgress,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WarmCacheQuery {
+    /// Comma-separated preview sizes and/or `annotated` (default `screen`).
+    pub formats: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WarmCacheStatusResponse {
+    /// False when another warm-up was already running; `progress` is then
+    /// that job's.
+    pub started: bool,
+    pub progress: crate::server::warm_cache::WarmCacheProgress,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ScoredSequenceResponse {
     pub target_id: i32,
     pub target_name: String,
@@ -704,7 +1244,23 @@ pub struct ScoredSequenceResponse {
     pub summary: SequenceSummary,
 }
 
-#[derive(Debug, Serialize)]
+impl From<crate::sequence_analysis::ScoredSequence> for ScoredSequenceResponse {
+    fn from(seq: crate::sequence_analysis::ScoredSequence) -> Self {
+        Self {
+            target_id: seq.target_id,
+            target_name: seq.target_name,
+            filter_name: seq.filter_name,
+            session_start: seq.session_start,
+            session_end: seq.session_end,
+            image_count: seq.image_count,
+            reference_values: seq.reference_values,
+            images: seq.images,
+            summary: seq.summary,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ImageQualityContextResponse {
     pub image_id: i32,
     pub quality: Option<ImageQualityResult>,
@@ -0,0 +1,91 @@
+//! Optional bearer-token authentication for the `/api` routes.
+//!
+//! When a token is configured (`[auth] token` in the server TOML or the
+//! `PSF_GUARD_TOKEN` environment variable) every API request must carry
+//! `Authorization: Bearer <token>`. Static files and the health probes stay
+//! public so the UI shell loads and load balancers can poll.
+//!
+//! `<img>` tags and download links can't send headers, so GET and HEAD
+//! requests may present the token in the [`TOKEN_COOKIE`] cookie (which the
+//! UI sets) or a `token` query parameter instead. Anything that changes
+//! state still needs the header, so a cross-site form can't ride the cookie.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, Request, State};
+use axum::http::header::{AUTHORIZATION, COOKIE};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+
+use crate::server::handlers::AppError;
+
+/// Cookie the UI stores the token in, percent-encoded, scoped to `/api`.
+pub const TOKEN_COOKIE: &str = "psf_guard_token";
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Middleware for `axum::middleware::from_fn_with_state`, with the expected
+/// token as state.
+pub async fn require_bearer_token(
+    State(expected): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match presented_token(&request) {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {
+            next.run(request).await
+        }
+        Some(_) => AppError::Unauthorized("Invalid bearer token".to_string()).into_response(),
+        None => AppError::Unauthorized("Missing bearer token".to_string()).into_response(),
+    }
+}
+
+/// The token a request carries: the bearer header, or for reads the cookie
+/// and then the `token` query parameter.
+fn presented_token(request: &Request) -> Option<String> {
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    if bearer.is_some() || !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return bearer;
+    }
+
+    let cookie = request
+        .headers()
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            (name == TOKEN_COOKIE).then_some(value)
+        })
+        .and_then(|value| {
+            percent_encoding::percent_decode_str(value)
+                .decode_utf8()
+                .ok()
+        })
+        .map(|token| token.into_owned());
+    cookie.or_else(|| {
+        Query::<TokenQuery>::try_from_uri(request.uri())
+            .ok()
+            .and_then(|Query(query)| query.token)
+    })
+}
+
+/// Compare without short-circuiting on the first differing byte, so response
+/// timing doesn't leak how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
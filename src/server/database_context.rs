@@ -10,42 +10,111 @@
 //! handlers until B2 nests them under `/api/db/{slug}/...`.
 
 use crate::directory_tree::DirectoryTree;
-use crate::server::state::{FileCheckCache, RefreshProgress, RefreshStage, RefreshStatus};
+use crate::server::state::{
+    FileCheckCache, FileCounts, RefreshProgress, RefreshStage, RefreshStatus,
+};
 use anyhow::Result;
 use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex as TokioMutex;
 
+/// Error a file-cache refresh stops with after [`DatabaseContext::cancel_refresh`].
+#[derive(Debug)]
+pub struct RefreshCancelled;
+
+impl std::fmt::Display for RefreshCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("cache refresh cancelled")
+    }
+}
+
+impl std::error::Error for RefreshCancelled {}
+
+/// How long an image whose FITS file could not be found keeps answering 404
+/// without another search. Short, so a file that lands on disk (a sync
+/// finishing, a share remounting) shows up within a poll or two.
+pub const MISSING_FILE_TTL: Duration = Duration::from_secs(30);
+
+/// Negative cache for `find_fits_file`: image id -> when its file was last
+/// found missing. Browsing a project with many missing files otherwise
+/// re-probes every candidate path and the directory tree on each request.
+/// Cleared whenever the directory tree is rebuilt or refreshed.
+#[derive(Debug, Default)]
+pub struct MissingFileCache {
+    entries: Mutex<HashMap<i32, Instant>>,
+}
+
+impl MissingFileCache {
+    /// True while `image_id` was found missing less than
+    /// [`MISSING_FILE_TTL`] ago.
+    pub fn is_missing(&self, image_id: i32) -> bool {
+        let mut entries = lock_recover(&self.entries);
+        match entries.get(&image_id) {
+            Some(at) if at.elapsed() < MISSING_FILE_TTL => true,
+            Some(_) => {
+                entries.remove(&image_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn mark_missing(&self, image_id: i32) {
+        let mut entries = lock_recover(&self.entries);
+        // Drop expired entries occasionally so the map tracks only live misses.
+        if entries.len() >= 4096 {
+            entries.retain(|_, at| at.elapsed() < MISSING_FILE_TTL);
+        }
+        entries.insert(image_id, Instant::now());
+    }
+
+    pub fn clear(&self) {
+        lock_recover(&self.entries).clear();
+    }
+}
+
 /// Flags used to (re)open every scheduler database connection. `NO_MUTEX`
 /// because we serialize access ourselves with `Mutex<Connection>`; no `CREATE`
 /// so a vanished path errors instead of leaving a junk empty database behind.
-fn db_open_flags() -> OpenFlags {
-    OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX
+/// A `--read-only` server opens `READ_ONLY` so not even a stray write path can
+/// reach the file.
+fn db_open_flags(read_only: bool) -> OpenFlags {
+    let access = if read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+    };
+    access | OpenFlags::SQLITE_OPEN_NO_MUTEX
 }
 
-/// How long a connection waits on SQLITE_BUSY before giving up. The scheduler
-/// DB is rollback-journal SQLite, so a write (grade update) needs the
-/// exclusive lock at commit and conflicts with any in-flight read on ANOTHER
-/// connection. We create that contention OURSELVES: the background file-check
-/// refresh runs on its own dedicated connection (so its slow queries never
-/// hold the request mutex), and each of its streaming reads holds a shared
-/// lock for the statement's duration — 30-80s per project on an SMB-mounted
-/// DB. Without a busy handler, a grade written during a refresh surfaces
-/// instantly as "database is locked" → HTTP 500; with one, the writer waits
-/// out the current refresh statement and wins the gap between statements.
-/// Sized to cover the worst observed single-query duration. (External writers
-/// like N.I.N.A. on a network share get the same courtesy.)
-const DB_BUSY_TIMEOUT: Duration = Duration::from_secs(60);
+/// Whether `find_fits_file` falls back to a case-insensitive filename match
+/// (`[server] case_insensitive_filenames`). Set once at startup.
+static CASE_INSENSITIVE_LOOKUP: AtomicBool = AtomicBool::new(false);
+
+pub fn set_case_insensitive_lookup(enabled: bool) {
+    CASE_INSENSITIVE_LOOKUP.store(enabled, Ordering::Relaxed);
+}
+
+pub fn case_insensitive_lookup() -> bool {
+    CASE_INSENSITIVE_LOOKUP.load(Ordering::Relaxed)
+}
 
 /// The one way to open a scheduler DB connection in the server: flags above +
-/// busy timeout. Every open site (initial, both reopen paths, the refresh
-/// connection) must go through here so none silently loses the busy handler.
-fn open_scheduler_connection(path: &str) -> rusqlite::Result<Connection> {
-    let conn = Connection::open_with_flags(path, db_open_flags())?;
-    conn.busy_timeout(DB_BUSY_TIMEOUT)?;
+/// busy timeout + journal mode. Every open site (initial, both reopen paths,
+/// the refresh connection) must go through here with the context's own
+/// options so none silently loses the busy handler or read-only access.
+fn open_scheduler_connection(
+    path: &str,
+    options: &crate::db::ConnectionOptions,
+) -> rusqlite::Result<Connection> {
+    let conn = Connection::open_with_flags(path, db_open_flags(options.read_only))?;
+    if let Some(warning) = crate::db::configure_connection(&conn, options)? {
+        tracing::warn!("⚠️ {}: {}", path, warning);
+    }
     Ok(conn)
 }
 
@@ -156,6 +225,10 @@ pub struct DatabaseContext {
     /// so two DBs with overlapping image IDs do not collide.
     pub cache_dir: String,
     pub cache_dir_path: PathBuf,
+    /// Tuning and access mode for every connection this context opens,
+    /// including reopens after an external replace and the refresh
+    /// connection. Fixed at construction.
+    connection_options: crate::db::ConnectionOptions,
     db_connection: Arc<Mutex<Connection>>,
     /// File identity of `database_path` as of the currently open connection.
     /// Compared on every `db()` to detect an external replace.
@@ -171,6 +244,11 @@ pub struct DatabaseContext {
     tree_build_lock: Arc<Mutex<()>>,
     /// True while a stale-revalidation/progress tree rebuild is scheduled or running.
     tree_rebuild_inflight: Arc<AtomicBool>,
+    /// Images whose FITS file was recently not found (see [`MissingFileCache`]).
+    pub missing_files: Arc<MissingFileCache>,
+    /// Set by [`Self::cancel_refresh`]; the running file-cache refresh checks
+    /// it between stages, projects and targets.
+    refresh_cancel: Arc<AtomicBool>,
     pub refresh_mutex: Arc<TokioMutex<()>>,
     /// Serializes memory-heavy on-demand plate solves within one database.
     /// A waiting duplicate re-checks the persistent cache before decoding
@@ -186,17 +264,22 @@ pub struct DatabaseContext {
     pub import_job: crate::server::import_job::SharedImportJob,
     /// Database-wide, low-priority quality analysis state.
     pub quality_backfill: crate::server::quality_backfill::SharedQualityBackfill,
+    /// Per-DB singleton project cache-warming job (see `server::warm_cache`).
+    pub warm_cache: crate::server::warm_cache::SharedWarmCache,
 }
 
 impl DatabaseContext {
     /// `cache_root` is the shared parent directory; this constructor appends
     /// the slug to produce a per-DB cache subdir and creates it on disk.
+    /// `connection_options` (busy timeout, WAL, read-only) apply to every
+    /// connection the context opens.
     pub fn new(
         id: String,
         name: String,
         db_path: String,
         image_dirs: Vec<String>,
         cache_root: String,
+        connection_options: crate::db::ConnectionOptions,
     ) -> Result<Self> {
         use std::path::Path;
 
@@ -229,7 +312,13 @@ impl DatabaseContext {
         })?;
         let cache_dir = cache_dir_path.to_string_lossy().into_owned();
 
-        let conn = open_scheduler_connection(&db_path)?;
+        let conn = open_scheduler_connection(&db_path, &connection_options)?;
+        // Logged rather than fatal: the DB stays registered so the UI can
+        // show it and the user can fix or replace the file.
+        match crate::db::Database::new(&conn).detect_schema() {
+            Ok(schema) => tracing::info!("📋 {}: {}", id, schema),
+            Err(e) => tracing::error!("❌ {}: {:#}", id, e),
+        }
         let fingerprint = fingerprint_path(&db_path);
 
         Ok(Self {
@@ -240,6 +329,7 @@ impl DatabaseContext {
             image_dir_paths,
             cache_dir,
             cache_dir_path,
+            connection_options,
             db_connection: Arc::new(Mutex::new(conn)),
             db_fingerprint: Arc::new(Mutex::new(fingerprint)),
             reopen_lock: Arc::new(Mutex::new(())),
@@ -247,12 +337,15 @@ impl DatabaseContext {
             directory_tree_cache: Arc::new(RwLock::new(None)),
             tree_build_lock: Arc::new(Mutex::new(())),
             tree_rebuild_inflight: Arc::new(AtomicBool::new(false)),
+            missing_files: Arc::new(MissingFileCache::default()),
+            refresh_cancel: Arc::new(AtomicBool::new(false)),
             refresh_mutex: Arc::new(TokioMutex::new(())),
             astrometry_solve_mutex: Arc::new(TokioMutex::new(())),
             astrometry_evidence: Arc::new(crate::astrometry::AstrometryEvidenceCache::new()),
             spatial_metrics: Arc::new(RwLock::new(Default::default())),
             import_job: Arc::new(RwLock::new(Default::default())),
             quality_backfill: Arc::new(RwLock::new(Default::default())),
+            warm_cache: Arc::new(RwLock::new(Default::default())),
         })
     }
 
@@ -335,7 +428,7 @@ impl DatabaseContext {
 
         // Open outside the connection/fingerprint locks so a slow open doesn't
         // block queries.
-        match open_scheduler_connection(&self.database_path) {
+        match open_scheduler_connection(&self.database_path, &self.connection_options) {
             Ok(new_conn) => {
                 *lock_recover(&self.db_connection) = new_conn;
                 *lock_recover(&self.db_fingerprint) = Some(new_fp);
@@ -362,7 +455,7 @@ impl DatabaseContext {
     /// queries are not blocked.
     fn force_reopen(&self) {
         let _reopen = lock_recover(&self.reopen_lock);
-        match open_scheduler_connection(&self.database_path) {
+        match open_scheduler_connection(&self.database_path, &self.connection_options) {
             Ok(new_conn) => {
                 *lock_recover(&self.db_connection) = new_conn;
                 *lock_recover(&self.db_fingerprint) = fingerprint_path(&self.database_path);
@@ -489,6 +582,7 @@ impl DatabaseContext {
             let mut cache = self.directory_tree_cache.write().unwrap();
             *cache = Some(tree.clone());
         }
+        self.missing_files.clear();
 
         Ok(Arc::new(tree))
     }
@@ -531,6 +625,7 @@ impl DatabaseContext {
     pub fn clear_directory_tree_cache(&self) {
         let mut cache = self.directory_tree_cache.write().unwrap();
         *cache = None;
+        self.missing_files.clear();
         tracing::info!("🗑️  Directory tree cache cleared for db={}", self.id);
     }
 
@@ -575,6 +670,7 @@ impl DatabaseContext {
         } else {
             tracing::debug!("✅ Directory tree cache ready for unified cache refresh");
         }
+        self.check_refresh_cancelled()?;
 
         {
             let mut cache = self.file_check_cache.write().unwrap();
@@ -588,7 +684,7 @@ impl DatabaseContext {
         // lock round-trips) transactions never hold the shared request
         // connection's mutex. API handlers keep answering while this walks the
         // database; two readers coexist at the SQLite level.
-        let refresh_conn = open_scheduler_connection(&self.database_path)
+        let refresh_conn = open_scheduler_connection(&self.database_path, &self.connection_options)
             .map_err(|e| anyhow::anyhow!("Opening refresh connection: {}", e))?;
 
         let projects = {
@@ -614,11 +710,14 @@ impl DatabaseContext {
 
         let mut project_cache_updates = std::collections::HashMap::new();
         let mut target_cache_updates = std::collections::HashMap::new();
+        let mut project_count_updates = std::collections::HashMap::new();
+        let mut target_count_updates = std::collections::HashMap::new();
         let mut projects_with_files = 0;
         let mut targets_with_files = 0;
         let mut total_targets = 0;
 
         for project in &projects {
+            self.check_refresh_cancelled()?;
             {
                 let mut cache = self.file_check_cache.write().unwrap();
                 cache.refresh_progress.process_project(&project.name);
@@ -639,7 +738,10 @@ impl DatabaseContext {
                 })?
             };
 
-            // target_id -> (files found, files missing)
+            // Every frame is looked up; there is no sampled mode. The lookups
+            // hit the in-memory directory tree, so an exact count costs no
+            // more than a sample would and `has_files` can't be fooled by a
+            // lucky few. target_id -> (files found, files missing)
             let mut per_target: std::collections::HashMap<i32, (usize, usize)> =
                 std::collections::HashMap::new();
             for (image, _project_name, _target_name) in &images {
@@ -669,6 +771,13 @@ impl DatabaseContext {
                 projects_with_files += 1;
             }
             project_cache_updates.insert(project.id, project_has_files);
+            project_count_updates.insert(
+                project.id,
+                FileCounts {
+                    found: project_files_found,
+                    total: project_files_found + project_files_missing,
+                },
+            );
 
             {
                 let mut cache = self.file_check_cache.write().unwrap();
@@ -677,11 +786,19 @@ impl DatabaseContext {
 
             total_targets += per_target.len();
             for (target_id, (found, missing)) in per_target {
+                self.check_refresh_cancelled()?;
                 let target_has_files = found > 0;
                 if target_has_files {
                     targets_with_files += 1;
                 }
                 target_cache_updates.insert(target_id, target_has_files);
+                target_count_updates.insert(
+                    target_id,
+                    FileCounts {
+                        found,
+                        total: found + missing,
+                    },
+                );
 
                 {
                     let mut cache = self.file_check_cache.write().unwrap();
@@ -701,6 +818,8 @@ impl DatabaseContext {
             }
         }
 
+        // Last chance: past here the new results replace the old ones.
+        self.check_refresh_cancelled()?;
         {
             let mut cache = self.file_check_cache.write().unwrap();
             cache
@@ -712,6 +831,8 @@ impl DatabaseContext {
             let mut cache = self.file_check_cache.write().unwrap();
             cache.projects_with_files = project_cache_updates;
             cache.targets_with_files = target_cache_updates;
+            cache.project_file_counts = project_count_updates;
+            cache.target_file_counts = target_count_updates;
             cache.last_updated = std::time::Instant::now();
             cache.has_initial_data = true;
         }
@@ -750,6 +871,7 @@ impl DatabaseContext {
                 };
             }
             cache.mark_refresh_started();
+            self.refresh_cancel.store(false, Ordering::SeqCst);
             true
         };
 
@@ -760,10 +882,15 @@ impl DatabaseContext {
                 tracing::info!("🔄 Starting singleton cache refresh for db={}", ctx.id);
 
                 let refresh_result = ctx.refresh_cache_unified_internal().await;
+                let cancelled = matches!(&refresh_result, Err(e) if e.is::<RefreshCancelled>());
 
                 {
                     let mut cache = ctx.file_check_cache.write().unwrap();
-                    cache.mark_refresh_completed();
+                    if cancelled {
+                        cache.mark_refresh_cancelled();
+                    } else {
+                        cache.mark_refresh_completed();
+                    }
                 }
 
                 match refresh_result {
@@ -773,6 +900,12 @@ impl DatabaseContext {
                             ctx.id, checked, found, missing, duration_ms
                         );
                     }
+                    Err(_) if cancelled => {
+                        tracing::info!(
+                            "⏹️ Cache refresh for db={} cancelled; keeping the previous results",
+                            ctx.id
+                        );
+                    }
                     Err(e) => {
                         tracing::error!("❌ Cache refresh for db={} failed: {:?}", ctx.id, e);
                     }
@@ -788,19 +921,47 @@ impl DatabaseContext {
         }
     }
 
+    /// Progress of the running refresh, or of the last one if it was
+    /// cancelled (so pollers see the `Cancelled` stage).
     pub fn get_cache_refresh_progress(&self) -> Option<RefreshProgress> {
         let cache = self.file_check_cache.read().unwrap();
-        if cache.refresh_in_progress {
+        if cache.refresh_in_progress || cache.refresh_progress.stage == RefreshStage::Cancelled {
             Some(cache.refresh_progress.clone())
         } else {
             None
         }
     }
 
+    /// Ask the running file-cache refresh to stop at its next check. Returns
+    /// false when no refresh is running. The directory scan itself is not
+    /// interrupted; the refresh stops as soon as it finishes.
+    pub fn cancel_refresh(&self) -> bool {
+        let cache = self.file_check_cache.read().unwrap();
+        if !cache.refresh_in_progress {
+            return false;
+        }
+        self.refresh_cancel.store(true, Ordering::SeqCst);
+        tracing::info!("⏹️ Cache refresh cancellation requested for db={}", self.id);
+        true
+    }
+
+    /// Whether a cancellation was requested for the running refresh.
+    pub fn refresh_cancel_requested(&self) -> bool {
+        self.refresh_cancel.load(Ordering::SeqCst)
+    }
+
+    fn check_refresh_cancelled(&self) -> Result<()> {
+        if self.refresh_cancel_requested() {
+            return Err(RefreshCancelled.into());
+        }
+        Ok(())
+    }
+
     pub fn force_directory_tree_refresh(&self) -> RefreshStatus {
         {
             let mut dir_cache = self.directory_tree_cache.write().unwrap();
             *dir_cache = None;
+            self.missing_files.clear();
             tracing::info!(
                 "🗑️  Directory tree cache cleared for db={}, forcing refresh",
                 self.id
@@ -896,6 +1057,7 @@ impl DatabaseContext {
         let (tree_result, built_tree) = tree_result;
 
         if built_tree {
+            self.missing_files.clear();
             let mut cache = self.file_check_cache.write().unwrap();
             cache.refresh_progress.complete_directory();
         } else {
@@ -943,6 +1105,7 @@ impl DatabaseContext {
             image_dir_paths: vec![],
             cache_dir: "/tmp/psf-guard-test".to_string(),
             cache_dir_path: PathBuf::from("/tmp/psf-guard-test"),
+            connection_options: crate::db::ConnectionOptions::default(),
             db_connection: Arc::new(Mutex::new(conn)),
             db_fingerprint: Arc::new(Mutex::new(None)),
             reopen_lock: Arc::new(Mutex::new(())),
@@ -950,12 +1113,15 @@ impl DatabaseContext {
             directory_tree_cache: Arc::new(RwLock::new(None)),
             tree_build_lock: Arc::new(Mutex::new(())),
             tree_rebuild_inflight: Arc::new(AtomicBool::new(false)),
+            missing_files: Arc::new(MissingFileCache::default()),
+            refresh_cancel: Arc::new(AtomicBool::new(false)),
             refresh_mutex: Arc::new(TokioMutex::new(())),
             astrometry_solve_mutex: Arc::new(TokioMutex::new(())),
             astrometry_evidence: Arc::new(crate::astrometry::AstrometryEvidenceCache::new()),
             spatial_metrics: Arc::new(RwLock::new(Default::default())),
             import_job: Arc::new(RwLock::new(Default::default())),
             quality_backfill: Arc::new(RwLock::new(Default::default())),
+            warm_cache: Arc::new(RwLock::new(Default::default())),
         }
     }
 }
@@ -970,6 +1136,7 @@ impl Clone for DatabaseContext {
             image_dir_paths: self.image_dir_paths.clone(),
             cache_dir: self.cache_dir.clone(),
             cache_dir_path: self.cache_dir_path.clone(),
+            connection_options: self.connection_options,
             db_connection: self.db_connection.clone(),
             db_fingerprint: self.db_fingerprint.clone(),
             reopen_lock: self.reopen_lock.clone(),
@@ -977,12 +1144,15 @@ impl Clone for DatabaseContext {
             directory_tree_cache: self.directory_tree_cache.clone(),
             tree_build_lock: self.tree_build_lock.clone(),
             tree_rebuild_inflight: self.tree_rebuild_inflight.clone(),
+            missing_files: self.missing_files.clone(),
+            refresh_cancel: self.refresh_cancel.clone(),
             refresh_mutex: self.refresh_mutex.clone(),
             astrometry_solve_mutex: self.astrometry_solve_mutex.clone(),
             astrometry_evidence: self.astrometry_evidence.clone(),
             spatial_metrics: self.spatial_metrics.clone(),
             import_job: self.import_job.clone(),
             quality_backfill: self.quality_backfill.clone(),
+            warm_cache: self.warm_cache.clone(),
         }
     }
 }
@@ -1043,6 +1213,7 @@ mod tests {
                 (Id, projectId, targetId, acquireddate, filtername, gradingStatus, metadata, rejectreason, profileId)
                 VALUES
                     (100, 1, 10, 1000, 'L', 0, '{\"FileName\":\"/remote/present.fit\"}', NULL, 'default'),
+                    (101, 1, 10, 1100, 'L', 0, '{\"FileName\":\"/remote/gone.fit\"}', NULL, 'default'),
                     (200, 1, 20, 2000, 'L', 0, '{}', NULL, 'default'),
                     (300, 1, 30, 3000, 'L', 0, 'not json', NULL, 'default');",
         )
@@ -1094,6 +1265,7 @@ mod tests {
             db_path.to_string_lossy().into_owned(),
             vec![img_dir.to_string_lossy().into_owned()],
             dir.join("cache").to_string_lossy().into_owned(),
+            crate::db::ConnectionOptions::default(),
         )
         .unwrap()
     }
@@ -1117,6 +1289,79 @@ mod tests {
         assert_eq!(cache.targets_with_files.get(&10), Some(&true));
         assert_eq!(cache.targets_with_files.get(&20), Some(&false));
         assert_eq!(cache.targets_with_files.get(&30), Some(&false));
+
+        // Counts, not just booleans: one of target 10's two frames is on disk.
+        let half = FileCounts { found: 1, total: 2 };
+        assert_eq!(cache.target_file_counts.get(&10), Some(&half));
+        assert_eq!(cache.project_file_counts.get(&1), Some(&half));
+        assert_eq!(
+            cache.target_file_counts.get(&20),
+            Some(&FileCounts::default())
+        );
+        assert_eq!(half.missing(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancelled_refresh_keeps_previous_results() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("sched.sqlite");
+        make_file_refresh_db(&db_path);
+        let ctx = build_ctx(tmp.path(), &db_path);
+        {
+            let mut cache = ctx.file_check_cache.write().unwrap();
+            cache.projects_with_files.insert(1, true);
+            cache.has_initial_data = true;
+            cache.mark_refresh_started();
+        }
+
+        assert!(ctx.cancel_refresh());
+        let err = ctx.refresh_cache_unified_internal().await.unwrap_err();
+        assert!(err.is::<RefreshCancelled>(), "{err:#}");
+        ctx.file_check_cache
+            .write()
+            .unwrap()
+            .mark_refresh_cancelled();
+
+        let cache = ctx.file_check_cache.read().unwrap();
+        assert_eq!(cache.projects_with_files.get(&1), Some(&true));
+        assert!(cache.targets_with_files.is_empty());
+        assert!(!cache.refresh_in_progress);
+        drop(cache);
+        let progress = ctx.get_cache_refresh_progress().unwrap();
+        assert_eq!(progress.stage, RefreshStage::Cancelled);
+
+        // Nothing running: nothing to cancel.
+        assert!(!ctx.cancel_refresh());
+    }
+
+    #[test]
+    fn missing_file_cache_expires_and_clears_on_tree_rebuild() {
+        let cache = MissingFileCache::default();
+        assert!(!cache.is_missing(7));
+        cache.mark_missing(7);
+        assert!(cache.is_missing(7));
+        assert!(!cache.is_missing(8));
+
+        // Past the TTL the image is searched for again.
+        if let Some(at) = Instant::now().checked_sub(MISSING_FILE_TTL) {
+            lock_recover(&cache.entries).insert(7, at);
+            assert!(!cache.is_missing(7));
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("sched.sqlite");
+        make_db(&db_path, "P");
+        let ctx = build_ctx(tmp.path(), &db_path);
+        ctx.missing_files.mark_missing(1);
+        ctx.get_directory_tree().unwrap();
+        assert!(
+            !ctx.missing_files.is_missing(1),
+            "a rebuilt tree may find it"
+        );
+
+        ctx.missing_files.mark_missing(1);
+        ctx.clear_directory_tree_cache();
+        assert!(!ctx.missing_files.is_missing(1));
     }
 
     // Unix-only: the proactive reopen this exercises is itself `#[cfg(unix)]`
@@ -1,8 +1,8 @@
 use axum::{
     extract::{Path, Query, State},
     http::{
-        header::{CACHE_CONTROL, CONTENT_TYPE},
-        StatusCode,
+        header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
     },
     response::{IntoResponse, Response},
     Json,
@@ -11,15 +11,21 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::db::Database;
 use crate::models::{GradingStatus, OverallDesiredStats, ProjectDesiredStats};
 use crate::server::api::*;
 use crate::server::database_context::DatabaseContext;
 use crate::server::extract::DbContext;
-use crate::server::state::AppState;
+use crate::server::openapi::Binary;
+use crate::server::state::{AppState, FileCounts};
+use crate::sky_grouping::{group_targets_by_position, FieldGroup};
+
+pub(crate) use crate::scoring::{
+    filename_from_metadata, load_field_images, merge_astrometry_metrics, merge_photometric_signals,
+    merge_spatial_metrics, score_target_sequences, stored_entry_for, ScoringCaches,
+};
 
 // Helper function to format RA/Dec coordinates
 fn format_coordinates(ra: Option<f64>, dec: Option<f64>) -> Option<String> {
@@ -58,6 +64,12 @@ mod coordinate_format_tests {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/info",
+    tag = "info",
+    responses((status = 200, description = "OK", body = ApiResponse<ServerInfo>))
+)]
 pub async fn get_server_info(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ApiResponse<ServerInfo>>, AppError> {
@@ -65,14 +77,115 @@ pub async fn get_server_info(
         version: env!("CARGO_PKG_VERSION").to_string(),
         cache_directory: state.cache_dir_root.clone(),
         allow_database_management: state.database_management_allowed(),
+        read_only: state.is_read_only(),
         banner: state.site_banner(),
     };
 
     Ok(Json(ApiResponse::success(info)))
 }
 
+/// GET /api/openapi.json
+///
+/// OpenAPI 3.1 description of every `/api` route, built from the
+/// `#[utoipa::path]` annotations collected in
+/// [`ApiDoc`](crate::server::openapi::ApiDoc).
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    tag = "info",
+    responses((status = 200, description = "This document"))
+)]
+pub async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    use utoipa::OpenApi as _;
+    Json(crate::server::openapi::ApiDoc::openapi())
+}
+
+/// GET /api/health
+///
+/// Liveness probe: answers as long as the server is up, without touching any
+/// database.
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "health",
+    responses((status = 200, description = "Server is up", body = HealthStatus))
+)]
+pub async fn get_health() -> Json<HealthStatus> {
+    Json(HealthStatus { status: "ok" })
+}
+
+/// GET /api/ready
+///
+/// Readiness probe: 200 once every loaded database answers a query and has
+/// built its directory tree cache, 503 until then.
+#[utoipa::path(
+    get,
+    path = "/api/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every database is ready", body = ReadinessStatus),
+        (status = 503, description = "Still warming up", body = ReadinessStatus)
+    )
+)]
+pub async fn get_readiness(State(state): State<Arc<AppState>>) -> Response {
+    let databases = state.all_databases();
+    let checks = tokio::task::spawn_blocking(move || {
+        databases
+            .iter()
+            .map(|ctx| {
+                let probe = ctx.db().lock().map_err(|e| e.to_string()).and_then(|conn| {
+                    conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+                        .map_err(|e| e.to_string())
+                });
+                let directory_tree_ready = ctx
+                    .directory_tree_cache
+                    .read()
+                    .map(|tree| tree.is_some())
+                    .unwrap_or(false);
+                DatabaseReadiness {
+                    id: ctx.id.clone(),
+                    database_ok: probe.is_ok(),
+                    directory_tree_ready,
+                    error: probe.err(),
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .await;
+
+    let status = match checks {
+        Ok(databases) => ReadinessStatus {
+            ready: databases
+                .iter()
+                .all(|db| db.database_ok && db.directory_tree_ready),
+            databases,
+        },
+        Err(e) => {
+            tracing::warn!("Readiness check task failed: {}", e);
+            ReadinessStatus {
+                ready: false,
+                databases: Vec::new(),
+            }
+        }
+    };
+    let code = if status.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(status)).into_response()
+}
+
 /// Report which Seiza resources are configured and can be opened. Normal
 /// capability checks are bounded header/index opens, not exhaustive scans.
+#[utoipa::path(
+    get,
+    path = "/api/astrometry/capabilities",
+    tag = "astrometry",
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::astrometry::AstrometryCapabilities>)
+    )
+)]
 pub async fn get_astrometry_capabilities(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ApiResponse<crate::astrometry::AstrometryCapabilities>>, AppError> {
@@ -87,6 +200,14 @@ pub async fn get_astrometry_capabilities(
 
 /// Exhaustively validate every configured Seiza catalog. This deliberately
 /// runs on the blocking pool and participates in the interactive-work gauge.
+#[utoipa::path(
+    post,
+    path = "/api/astrometry/catalogs/validate",
+    tag = "astrometry",
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::astrometry::AstrometryValidationReport>)
+    )
+)]
 pub async fn validate_astrometry_catalogs(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ApiResponse<crate::astrometry::AstrometryValidationReport>>, AppError> {
@@ -105,6 +226,14 @@ pub async fn validate_astrometry_catalogs(
 }
 
 /// Report the current background Seiza catalog installation.
+#[utoipa::path(
+    get,
+    path = "/api/astrometry/catalogs/install",
+    tag = "astrometry",
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::server::catalog_install::CatalogInstallStatus>)
+    )
+)]
 pub async fn get_astrometry_catalog_install(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ApiResponse<crate::server::catalog_install::CatalogInstallStatus>>, AppError> {
@@ -114,6 +243,15 @@ pub async fn get_astrometry_catalog_install(
 /// Download and install one hosted Seiza catalog package. This writes to the
 /// configured catalog directory, so it uses the same trust gate as database
 /// management. The work continues after the request returns.
+#[utoipa::path(
+    post,
+    path = "/api/astrometry/catalogs/install",
+    tag = "astrometry",
+    request_body = crate::server::catalog_install::CatalogInstallRequest,
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::server::catalog_install::CatalogInstallStatus>)
+    )
+)]
 pub async fn start_astrometry_catalog_install(
     State(state): State<Arc<AppState>>,
     Json(request): Json<crate::server::catalog_install::CatalogInstallRequest>,
@@ -130,6 +268,15 @@ pub async fn start_astrometry_catalog_install(
 /// Header-only catalog association and embedded-WCS overlay geometry for one
 /// image. This stays separate from image metadata so provenance, partial
 /// capability, and later plate-solve results retain a typed contract.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/astrometry",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::astrometry::AstrometryAnalysis>)
+    )
+)]
 pub async fn get_image_astrometry(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -154,6 +301,15 @@ pub async fn get_image_astrometry(
 /// Decode pixels and run Seiza's hinted solver with a blind fallback. The
 /// successful WCS is persisted in the per-database cache and immediately
 /// returned in the same contract consumed by the shared overlay component.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/images/{image_id}/astrometry",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::astrometry::AstrometryAnalysis>)
+    )
+)]
 pub async fn solve_image_astrometry(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -194,6 +350,15 @@ pub async fn solve_image_astrometry(
 
 /// Return a source- and WCS-validated cached satellite prediction without
 /// refreshing orbital elements or performing propagation.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/satellites",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::satellites::SatelliteAnalysisStatus>)
+    )
+)]
 pub async fn get_image_satellites(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -229,6 +394,15 @@ pub async fn get_image_satellites(
 /// clipped tracks during this single exposure, and persist the result for
 /// later sequence grading. Orbital prediction and bounded pixel alignment are
 /// returned as separate evidence.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/images/{image_id}/satellites",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::satellites::SatelliteAnalysis>)
+    )
+)]
 pub async fn predict_image_satellites(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -323,6 +497,12 @@ fn resolve_astrometry_input(
 
 /// List all configured databases. Used by the frontend to populate the DB
 /// switcher and resolve the default `?db=` value.
+#[utoipa::path(
+    get,
+    path = "/api/databases",
+    tag = "databases",
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<DatabaseSummary>>))
+)]
 pub async fn list_databases(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ApiResponse<Vec<DatabaseSummary>>>, AppError> {
@@ -364,6 +544,23 @@ pub(super) fn require_database_management_allowed(state: &AppState) -> Result<()
     }
 }
 
+/// Gate for every endpoint that writes to a scheduler database (grades,
+/// favorites, tags, project/target edits, exposure plans, imports, sync) or
+/// creates one. Returns 403 when the
+/// server was launched with `--read-only`; the connections are read-only too,
+/// so this just turns a SQLite "readonly database" error into a clear answer.
+pub(super) fn require_writable(state: &AppState) -> Result<(), AppError> {
+    if state.is_read_only() {
+        Err(AppError::Forbidden(
+            "this server is read-only. Restart it without --read-only to grade \
+            images or edit the scheduler database."
+                .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 fn summary_of(ctx: &crate::server::database_context::DatabaseContext) -> DatabaseSummary {
     DatabaseSummary {
         id: ctx.id.clone(),
@@ -377,6 +574,14 @@ fn summary_of(ctx: &crate::server::database_context::DatabaseContext) -> Databas
 /// database sync. The path database is the local working copy. A pull reads
 /// the peer and fills the local copy; a planning push reads the local copy and
 /// updates planning settings in the peer.
+#[utoipa::path(
+    post,
+    path = "/api/databases/{db_id}/sync",
+    tag = "databases",
+    params(("db_id" = String, Path)),
+    request_body = SchedulerSyncRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<SchedulerSyncResponse>))
+)]
 pub async fn sync_database_route(
     State(state): State<Arc<AppState>>,
     Path(db_id): Path<String>,
@@ -433,6 +638,9 @@ async fn execute_scheduler_sync_guarded(
     use rusqlite::{Connection, OpenFlags, Transaction, TransactionBehavior};
 
     require_database_management_allowed(state)?;
+    if !req.dry_run {
+        require_writable(state)?;
+    }
     if db_id == req.peer_db_id {
         return Err(AppError::BadRequest(
             "Choose two different databases to sync.".into(),
@@ -679,6 +887,14 @@ fn sync_endpoint_paths(
 
 /// Create a server-owned dry preview. Apply is a separate endpoint keyed by
 /// the returned opaque preview ID.
+#[utoipa::path(
+    post,
+    path = "/api/databases/{db_id}/sync/preview",
+    tag = "databases",
+    params(("db_id" = String, Path)),
+    request_body = SchedulerSyncRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<SchedulerSyncPreviewResponse>))
+)]
 pub async fn preview_sync_database_route(
     State(state): State<Arc<AppState>>,
     Path(db_id): Path<String>,
@@ -737,6 +953,13 @@ pub async fn preview_sync_database_route(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/databases/{db_id}/sync/previews/{preview_id}",
+    tag = "databases",
+    params(("db_id" = String, Path), ("preview_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<SchedulerSyncPreviewResponse>))
+)]
 pub async fn get_sync_database_preview_route(
     State(state): State<Arc<AppState>>,
     Path((db_id, preview_id)): Path<(String, String)>,
@@ -756,6 +979,13 @@ pub async fn get_sync_database_preview_route(
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/databases/{db_id}/sync/previews/{preview_id}",
+    tag = "databases",
+    params(("db_id" = String, Path), ("preview_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<bool>))
+)]
 pub async fn delete_sync_database_preview_route(
     State(state): State<Arc<AppState>>,
     Path((db_id, preview_id)): Path<(String, String)>,
@@ -769,6 +999,13 @@ pub async fn delete_sync_database_preview_route(
 }
 
 /// Apply one unexpired preview after proving that neither catalog changed.
+#[utoipa::path(
+    post,
+    path = "/api/databases/{db_id}/sync/previews/{preview_id}/apply",
+    tag = "databases",
+    params(("db_id" = String, Path), ("preview_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<SchedulerSyncResponse>))
+)]
 pub async fn apply_sync_database_preview_route(
     State(state): State<Arc<AppState>>,
     Path((db_id, preview_id)): Path<(String, String)>,
@@ -809,6 +1046,13 @@ pub async fn apply_sync_database_preview_route(
 /// `POST /api/databases` — register a new database. Validates that the file
 /// opens, persists the registry, and inserts the new `DatabaseContext` into
 /// the in-memory map.
+#[utoipa::path(
+    post,
+    path = "/api/databases",
+    tag = "databases",
+    request_body = AddDatabaseRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<DatabaseSummary>))
+)]
 pub async fn add_database_route(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddDatabaseRequest>,
@@ -834,6 +1078,7 @@ pub async fn add_database_route(
             entry.db_path.clone(),
             entry.image_dirs.clone(),
             state.cache_dir_root.clone(),
+            state.connection_options(),
         )
         .map_err(|e| AppError::BadRequest(format!("opening database: {}", e)))?,
     );
@@ -857,6 +1102,14 @@ pub async fn add_database_route(
 /// `PUT /api/databases/{db_id}` — update name / slug / db_path / image_dirs.
 /// Reopens the connection if `db_path` or `image_dirs` change. Slug rename is
 /// allowed; the cache directory move is queued for B5.
+#[utoipa::path(
+    put,
+    path = "/api/databases/{db_id}",
+    tag = "databases",
+    params(("db_id" = String, Path)),
+    request_body = UpdateDatabaseRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<DatabaseSummary>))
+)]
 pub async fn update_database_route(
     State(state): State<Arc<AppState>>,
     Path(db_id): Path<String>,
@@ -923,6 +1176,7 @@ pub async fn update_database_route(
             entry.db_path.clone(),
             entry.image_dirs.clone(),
             state.cache_dir_root.clone(),
+            state.connection_options(),
         )
         .map_err(|e| AppError::BadRequest(format!("opening database: {}", e)))?,
     );
@@ -946,6 +1200,13 @@ pub async fn update_database_route(
 
 /// `DELETE /api/databases/{db_id}` — drop the registered database. Returns
 /// 200 with `{removed: true}` even on first-call (idempotent on the surface).
+#[utoipa::path(
+    delete,
+    path = "/api/databases/{db_id}",
+    tag = "databases",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "Done; `data` is a free-form object"))
+)]
 pub async fn remove_database_route(
     State(state): State<Arc<AppState>>,
     Path(db_id): Path<String>,
@@ -974,6 +1235,15 @@ pub async fn remove_database_route(
 /// (`<target>/LIGHT/<filter>/<basename>`). FITS doesn't compress, so store
 /// mode streams at wire speed with no server-side staging. Read-only, so it
 /// is not management-gated.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/export",
+    tag = "export",
+    params(("db_id" = String, Path), ExportQuery),
+    responses(
+        (status = 200, description = "Zip archive", content_type = "application/zip", body = Binary)
+    )
+)]
 pub async fn export_archive_route(
     ctx: DbContext,
     Query(query): Query<ExportQuery>,
@@ -1078,6 +1348,16 @@ pub async fn export_archive_route(
 /// server and user share a machine. Same selection and layout as the CLI
 /// `export` command and the zip stream. Management-gated: a remote client
 /// could otherwise write files to arbitrary server paths.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/export/local",
+    tag = "export",
+    params(("db_id" = String, Path)),
+    request_body = LocalExportRequest,
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::commands::export::ExportSummary>)
+    )
+)]
 pub async fn export_local_route(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -1138,6 +1418,14 @@ pub async fn export_local_route(
 }
 
 /// `PUT /api/db/{db_id}/projects/{project_id}` — update scheduler fields.
+#[utoipa::path(
+    put,
+    path = "/api/db/{db_id}/projects/{project_id}",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    request_body = UpdateProjectRequest,
+    responses((status = 200, description = "Done; `data` is a free-form object"))
+)]
 pub async fn update_project_route(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -1145,6 +1433,7 @@ pub async fn update_project_route(
     Json(req): Json<UpdateProjectRequest>,
 ) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
     crate::server::scheduler::update_project(ctx, project_id, req)?;
     Ok(Json(ApiResponse::success(
         serde_json::json!({ "updated": true }),
@@ -1153,6 +1442,14 @@ pub async fn update_project_route(
 
 /// `PUT /api/db/{db_id}/targets/{target_id}` — rename a target and/or move it
 /// to another project (same profile; images follow the target).
+#[utoipa::path(
+    put,
+    path = "/api/db/{db_id}/targets/{target_id}",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path)),
+    request_body = UpdateTargetRequest,
+    responses((status = 200, description = "Done; `data` is a free-form object"))
+)]
 pub async fn update_target_route(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -1160,6 +1457,7 @@ pub async fn update_target_route(
     Json(req): Json<UpdateTargetRequest>,
 ) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
     if req.name.is_none()
         && req.project_id.is_none()
         && req.active.is_none()
@@ -1206,8 +1504,26 @@ pub async fn update_target_route(
     }))))
 }
 
+/// A refused merge is the caller's mistake: 404 for a missing project or
+/// target, 400 when the two can't be merged. Anything else is a 500.
+fn merge_error(error: anyhow::Error) -> AppError {
+    match error.downcast_ref::<crate::db::MergeRejected>() {
+        Some(crate::db::MergeRejected::NotFound(_)) => AppError::NotFound,
+        Some(crate::db::MergeRejected::Invalid(message)) => AppError::BadRequest(message.clone()),
+        None => AppError::db(format!("{error:#}")),
+    }
+}
+
 /// `POST /api/db/{db_id}/projects/{project_id}/merge` — merge this project's
 /// targets and images into another project, then delete it.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/projects/{project_id}/merge",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    request_body = MergeProjectRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<MergeProjectResponse>))
+)]
 pub async fn merge_project_route(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -1215,12 +1531,13 @@ pub async fn merge_project_route(
     Json(req): Json<MergeProjectRequest>,
 ) -> Result<Json<ApiResponse<MergeProjectResponse>>, AppError> {
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
     let (targets_moved, images_moved) = {
         let conn = ctx.db();
         let conn = conn.lock().map_err(AppError::db)?;
         Database::new(&conn)
             .merge_projects(project_id, req.into_project_id)
-            .map_err(|e| AppError::BadRequest(e.to_string()))?
+            .map_err(merge_error)?
     };
     Ok(Json(ApiResponse::success(MergeProjectResponse {
         targets_moved,
@@ -1228,9 +1545,47 @@ pub async fn merge_project_route(
     })))
 }
 
+/// `POST /api/db/{db_id}/targets/{target_id}/merge` — reassign this target's
+/// images to another target in the same project, optionally deleting it.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/targets/{target_id}/merge",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path)),
+    request_body = MergeTargetRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<MergeTargetResponse>))
+)]
+pub async fn merge_target_route(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, target_id)): Path<(String, i32)>,
+    Json(req): Json<MergeTargetRequest>,
+) -> Result<Json<ApiResponse<MergeTargetResponse>>, AppError> {
+    require_database_management_allowed(&state)?;
+    require_writable(&state)?;
+    let images_moved = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        Database::new(&conn)
+            .merge_targets(target_id, req.into_target_id, req.delete_source)
+            .map_err(merge_error)?
+    };
+    Ok(Json(ApiResponse::success(MergeTargetResponse {
+        images_moved,
+        source_deleted: req.delete_source,
+    })))
+}
+
 /// `POST /api/databases/create` — bootstrap a brand-new Target Scheduler
 /// database (vendored schema), register it, and start a background import of
 /// the given image directories. Gated like the other management routes.
+#[utoipa::path(
+    post,
+    path = "/api/databases/create",
+    tag = "databases",
+    request_body = CreateDatabaseRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<CreateDatabaseResponse>))
+)]
 pub async fn create_database_route(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateDatabaseRequest>,
@@ -1238,6 +1593,7 @@ pub async fn create_database_route(
     use crate::db_registry::DbRegistry;
 
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
     let registry_path = require_registry_path(&state)?;
 
     if req.image_dirs.is_empty() {
@@ -1295,6 +1651,7 @@ pub async fn create_database_route(
             entry.db_path.clone(),
             entry.image_dirs.clone(),
             state.cache_dir_root.clone(),
+            state.connection_options(),
         )
         .map_err(|e| AppError::InternalError(format!("opening new database: {}", e)))?,
     );
@@ -1332,12 +1689,21 @@ pub async fn create_database_route(
 
 /// `POST /api/db/{db_id}/import` — start a background FITS import into an
 /// existing database. One import runs per database at a time.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/import",
+    tag = "import",
+    params(("db_id" = String, Path)),
+    request_body = ImportRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<ImportStatusResponse>))
+)]
 pub async fn start_import_route(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Json(req): Json<ImportRequest>,
 ) -> Result<Json<ApiResponse<ImportStatusResponse>>, AppError> {
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
 
     let dirs = match req.image_dirs {
         Some(dirs) if !dirs.is_empty() => dirs,
@@ -1367,6 +1733,7 @@ pub async fn start_import_route(
         match_radius_deg: req
             .match_radius_deg
             .unwrap_or(crate::commands::import::DEFAULT_MATCH_RADIUS_DEG),
+        group_by_position: req.group_by_position_deg,
     };
     let started = spawn_import_job(
         &state,
@@ -1382,6 +1749,13 @@ pub async fn start_import_route(
 }
 
 /// `GET /api/db/{db_id}/import` — import job progress (1s poll).
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/import",
+    tag = "import",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<ImportStatusResponse>))
+)]
 pub async fn get_import_progress(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<ImportStatusResponse>>, AppError> {
@@ -1637,6 +2011,14 @@ fn spawn_quality_backfill(
     true
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/analysis/quality-backfill",
+    tag = "analysis",
+    params(("db_id" = String, Path)),
+    request_body = QualityBackfillRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<QualityBackfillStatusResponse>))
+)]
 pub async fn start_quality_backfill_route(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -1659,6 +2041,13 @@ pub async fn start_quality_backfill_route(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/analysis/quality-backfill",
+    tag = "analysis",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<QualityBackfillStatusResponse>))
+)]
 pub async fn get_quality_backfill_progress(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<QualityBackfillStatusResponse>>, AppError> {
@@ -1669,6 +2058,116 @@ pub async fn get_quality_backfill_progress(
     })))
 }
 
+/// POST /api/db/{db_id}/projects/{project_id}/warm-cache?formats=screen,annotated
+///
+/// Generate the given preview sizes and/or annotated images for every frame
+/// of the project in the background, without enabling global
+/// pre-generation. Answers at once; poll `/pregeneration-progress`. One job
+/// per database at a time.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/projects/{project_id}/warm-cache",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path), WarmCacheQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<WarmCacheStatusResponse>))
+)]
+pub async fn start_project_warm_cache(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, project_id)): Path<(String, i32)>,
+    Query(query): Query<WarmCacheQuery>,
+) -> Result<Json<ApiResponse<WarmCacheStatusResponse>>, AppError> {
+    let preview_sizes = &state.pregeneration_config.preview_sizes;
+    let mut sizes: Vec<String> = Vec::new();
+    let mut annotated = false;
+    for format in query.formats.as_deref().unwrap_or("screen").split(',') {
+        match format.trim() {
+            "" => {}
+            "annotated" => annotated = true,
+            size if preview_sizes.contains(size) => {
+                if !sizes.iter().any(|s| s == size) {
+                    sizes.push(size.to_string());
+                }
+            }
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "unknown format '{}': use annotated or a preview size ({}, original)",
+                    other,
+                    preview_sizes.names().collect::<Vec<_>>().join(", ")
+                )))
+            }
+        }
+    }
+    if sizes.is_empty() && !annotated {
+        return Err(AppError::BadRequest(
+            "formats must name a preview size or annotated".to_string(),
+        ));
+    }
+    let mut formats = sizes.clone();
+    if annotated {
+        formats.push("annotated".to_string());
+    }
+
+    let images = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        let db = Database::new(&conn);
+        if !db
+            .get_all_projects()
+            .map_err(AppError::db)?
+            .iter()
+            .any(|project| project.id == project_id)
+        {
+            return Err(AppError::NotFound);
+        }
+        db.query_light_images_scoped(Some(project_id), None)
+            .map_err(AppError::db)?
+    };
+    let images = crate::server::pregeneration_entries(images);
+
+    let started =
+        crate::server::warm_cache::try_begin(&ctx.warm_cache, project_id, formats, images.len())
+            .is_some();
+    if started && !images.is_empty() {
+        tokio::spawn(crate::server::warm_project_cache(
+            Arc::clone(&state),
+            ctx.0.clone(),
+            images,
+            sizes,
+            annotated,
+        ));
+    }
+    Ok(Json(ApiResponse::success(WarmCacheStatusResponse {
+        started,
+        progress: crate::server::warm_cache::snapshot(&ctx.warm_cache),
+    })))
+}
+
+/// GET /api/db/{db_id}/pregeneration-progress — the latest warm-cache job.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/pregeneration-progress",
+    tag = "pregeneration-progress",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<WarmCacheStatusResponse>))
+)]
+pub async fn get_pregeneration_progress(
+    ctx: DbContext,
+) -> Result<Json<ApiResponse<WarmCacheStatusResponse>>, AppError> {
+    let progress = crate::server::warm_cache::snapshot(&ctx.warm_cache);
+    Ok(Json(ApiResponse::success(WarmCacheStatusResponse {
+        started: progress.running,
+        progress,
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/db/{db_id}/refresh-cache",
+    tag = "refresh-cache",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<FileCheckResponse>))
+)]
 pub async fn refresh_file_cache(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<FileCheckResponse>>, AppError> {
@@ -1767,6 +2266,13 @@ pub async fn refresh_file_cache(
     Ok(Json(ApiResponse::success(response)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/cache-progress",
+    tag = "cache-progress",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<CacheRefreshProgressResponse>))
+)]
 pub async fn get_cache_refresh_progress(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<CacheRefreshProgressResponse>>, AppError> {
@@ -1784,11 +2290,14 @@ pub async fn get_cache_refresh_progress(
                 crate::server::state::RefreshStage::ProcessingTargets => "processing_targets",
                 crate::server::state::RefreshStage::UpdatingCache => "updating_cache",
                 crate::server::state::RefreshStage::Completed => "completed",
+                crate::server::state::RefreshStage::Cancelled => "cancelled",
             };
+            let is_refreshing = progress.stage != crate::server::state::RefreshStage::Cancelled;
 
             CacheRefreshProgressResponse {
-                is_refreshing: true,
+                is_refreshing,
                 stage: stage_name.to_string(),
+                cancel_requested: is_refreshing && ctx.refresh_cancel_requested(),
                 progress_percentage: progress.get_progress_percentage(),
                 elapsed_seconds: progress.get_elapsed_time().map(|d| d.as_secs()),
                 directories_total: progress.directories_total,
@@ -1809,6 +2318,7 @@ pub async fn get_cache_refresh_progress(
             CacheRefreshProgressResponse {
                 is_refreshing: false,
                 stage: "idle".to_string(),
+                cancel_requested: false,
                 progress_percentage: 0.0,
                 elapsed_seconds: None,
                 directories_total: 0,
@@ -1829,6 +2339,32 @@ pub async fn get_cache_refresh_progress(
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// Stop a running file-cache refresh at its next project/target boundary,
+/// keeping the previous results. Poll `/cache-progress` for the `cancelled`
+/// stage.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/refresh-cache/cancel",
+    tag = "refresh-cache",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<CancelRefreshResponse>))
+)]
+pub async fn cancel_cache_refresh(
+    ctx: DbContext,
+) -> Result<Json<ApiResponse<CancelRefreshResponse>>, AppError> {
+    let cancelled = ctx.cancel_refresh();
+    Ok(Json(ApiResponse::success(CancelRefreshResponse {
+        cancelled,
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/db/{db_id}/refresh-directory-cache",
+    tag = "refresh-directory-cache",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<DirectoryTreeResponse>))
+)]
 pub async fn refresh_directory_tree_cache(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<DirectoryTreeResponse>>, AppError> {
@@ -1869,6 +2405,128 @@ pub async fn refresh_directory_tree_cache(
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// Default and maximum `limit` for the directory cache listing.
+const DIRECTORY_LISTING_DEFAULT: usize = 1000;
+const DIRECTORY_LISTING_MAX: usize = 10_000;
+
+/// Inspect the directory tree cache without triggering a scan: stats,
+/// per-root timing, optionally where a filename resolves and a capped listing.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/directory-cache",
+    tag = "directory-cache",
+    params(("db_id" = String, Path), DirectoryCacheQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<DirectoryCacheResponse>))
+)]
+pub async fn get_directory_cache(
+    ctx: DbContext,
+    Query(query): Query<DirectoryCacheQuery>,
+) -> Result<Json<ApiResponse<DirectoryCacheResponse>>, AppError> {
+    let tree = ctx.directory_tree_cache.read().unwrap().clone();
+    let Some(tree) = tree else {
+        return Ok(Json(ApiResponse::success(DirectoryCacheResponse {
+            built: false,
+            stats: None,
+            roots: Vec::new(),
+            search: None,
+            listing: None,
+        })));
+    };
+
+    let stats = tree.stats();
+    let roots = stats
+        .root_scans
+        .iter()
+        .map(|root| DirectoryRootResponse {
+            path: root.root.to_string_lossy().into_owned(),
+            files: root.files,
+            directories: root.directories,
+            scan_time_ms: root.elapsed.as_millis(),
+        })
+        .collect();
+
+    let cached = |paths: Option<&Vec<PathBuf>>| -> Vec<CachedPathResponse> {
+        paths
+            .into_iter()
+            .flatten()
+            .map(|path| CachedPathResponse {
+                path: path.to_string_lossy().into_owned(),
+                exists: path.exists(),
+            })
+            .collect()
+    };
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|search| {
+            let filename = std::path::Path::new(search)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| search.to_string());
+            let matches = cached(tree.find_file(&filename));
+            let case_insensitive_matches = tree
+                .find_file_ignore_case(&filename)
+                .map(|paths| {
+                    let exact = tree.find_file(&filename);
+                    let other_case: Vec<PathBuf> = paths
+                        .iter()
+                        .filter(|path| !exact.is_some_and(|exact| exact.contains(*path)))
+                        .cloned()
+                        .collect();
+                    cached(Some(&other_case))
+                })
+                .unwrap_or_default();
+            DirectoryCacheSearch {
+                filename,
+                matches,
+                case_insensitive_matches,
+            }
+        });
+
+    let listing = query.list.unwrap_or(false).then(|| {
+        let limit = query
+            .limit
+            .unwrap_or(DIRECTORY_LISTING_DEFAULT)
+            .min(DIRECTORY_LISTING_MAX);
+        let mut paths: Vec<&PathBuf> = tree.all_paths().collect();
+        let total = paths.len();
+        paths.sort();
+        DirectoryCacheListing {
+            paths: paths
+                .into_iter()
+                .take(limit)
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+            total,
+            truncated: total > limit,
+        }
+    });
+
+    Ok(Json(ApiResponse::success(DirectoryCacheResponse {
+        built: true,
+        stats: Some(DirectoryTreeResponse {
+            total_files: stats.total_files,
+            unique_filenames: stats.unique_filenames,
+            total_directories: stats.total_directories,
+            age_seconds: stats.age.as_secs(),
+            build_time_ms: stats.build_time.as_millis(),
+            root_directory: ctx.image_dirs.join(", "),
+        }),
+        roots,
+        search,
+        listing,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects",
+    tag = "projects",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<ProjectResponse>>))
+)]
 pub async fn list_projects(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<Vec<ProjectResponse>>>, AppError> {
@@ -1896,9 +2554,12 @@ pub async fn list_projects(
     }
 
     // Get file existence info from cache (may be stale, but that's okay)
-    let file_existence_map: HashMap<i32, bool> = {
+    let (file_existence_map, file_counts): (HashMap<i32, bool>, HashMap<i32, FileCounts>) = {
         let cache = ctx.file_check_cache.read().unwrap();
-        cache.projects_with_files.clone()
+        (
+            cache.projects_with_files.clone(),
+            cache.project_file_counts.clone(),
+        )
     };
 
     // Get ALL projects with profile info from database (not just those with files)
@@ -1938,6 +2599,8 @@ pub async fn list_projects(
                     .get(&project.id)
                     .copied()
                     .unwrap_or(false),
+                files_present: file_counts.get(&project.id).map(|c| c.found),
+                files_total: file_counts.get(&project.id).map(|c| c.total),
             }
         })
         .collect();
@@ -1953,6 +2616,13 @@ pub async fn list_projects(
     Ok(Json(ApiResponse::success_with_status(response, api_status)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/targets",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<TargetResponse>>))
+)]
 pub async fn list_targets(
     ctx: DbContext,
     Path((_db_id, project_id)): Path<(String, i32)>,
@@ -1981,9 +2651,12 @@ pub async fn list_targets(
     }
 
     // Get file existence info from cache (may be stale, but that's okay)
-    let file_existence_map: HashMap<i32, bool> = {
+    let (file_existence_map, file_counts): (HashMap<i32, bool>, HashMap<i32, FileCounts>) = {
         let cache = ctx.file_check_cache.read().unwrap();
-        cache.targets_with_files.clone()
+        (
+            cache.targets_with_files.clone(),
+            cache.target_file_counts.clone(),
+        )
     };
 
     // Get ALL targets from database (not just those with files)
@@ -2008,6 +2681,8 @@ pub async fn list_targets(
             accepted_count: accepted,
             rejected_count: rejected,
             has_files: file_existence_map.get(&target.id).copied().unwrap_or(false),
+            files_present: file_counts.get(&target.id).map(|c| c.found),
+            files_total: file_counts.get(&target.id).map(|c| c.total),
         })
         .collect();
 
@@ -2026,10 +2701,21 @@ pub async fn list_targets(
     Ok(Json(ApiResponse::success_with_status(response, api_status)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images",
+    tag = "images",
+    params(("db_id" = String, Path), ImageQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<ImageResponse>>))
+)]
 pub async fn get_images(
     ctx: DbContext,
     Query(params): Query<ImageQuery>,
 ) -> Result<Json<ApiResponse<Vec<ImageResponse>>>, AppError> {
+    let want_present = listing_files_filter(params.files.as_deref())?;
+    let image_type = listing_image_type(params.imagetyp.as_deref())?;
+    let directory_tree = listing_files_tree(&ctx, want_present)?;
+
     let conn = ctx.db();
     let conn = conn.lock().map_err(AppError::db)?;
     let db = Database::new(&conn);
@@ -2038,25 +2724,39 @@ pub async fn get_images(
     let profile_count = db.get_profile_count().map_err(AppError::db)?;
     let show_profile = profile_count > 1;
 
-    // Convert status string to GradingStatus enum
-    let status_filter = params.status.as_ref().and_then(|s| match s.as_str() {
-        "pending" => Some(GradingStatus::Pending),
-        "accepted" => Some(GradingStatus::Accepted),
-        "rejected" => Some(GradingStatus::Rejected),
-        _ => None,
-    });
+    let status_filter = listing_status_filter(params.status.as_deref());
+    let sort = listing_sort(params.sort_by.as_deref())?;
 
     let offset = params.offset.unwrap_or(0).max(0) as usize;
     let limit = params.limit.unwrap_or(100).max(0) as usize;
-    let images = db
-        .query_images_scoped(
-            status_filter,
-            params.project_id,
-            params.target_id,
-            Some(limit),
-            offset,
-        )
-        .map_err(AppError::db)?;
+    let filter = crate::db::ImageFilter {
+        status: status_filter,
+        project_id: params.project_id,
+        target_id: params.target_id,
+        favorites_only: params.favorites_only.unwrap_or(false),
+        tag: params.tag.clone(),
+        image_type,
+        ..Default::default()
+    };
+    let page = crate::db::ImagePage { limit, offset };
+    let images = match (&directory_tree, want_present) {
+        // File presence isn't in SQL, so filter the full listing and page
+        // it here.
+        (Some(tree), Some(want_present)) => db
+            .find_images(&filter, sort, None)
+            .map_err(AppError::db)?
+            .into_iter()
+            .filter(|(img, _, _)| listing_file_present(tree, img) == want_present)
+            .skip(offset)
+            .take(limit)
+            .collect(),
+        _ => db
+            .find_images(&filter, sort, Some(page))
+            .map_err(AppError::db)?,
+    };
+    let ids: Vec<i32> = images.iter().map(|(img, _, _)| img.id).collect();
+    let favorites = db.favorite_ids(&ids).map_err(AppError::db)?;
+    let mut tags = db.tags_for_images(&ids).map_err(AppError::db)?;
 
     let response: Vec<ImageResponse> = images
         .into_iter()
@@ -2083,6 +2783,9 @@ pub async fn get_images(
                 reject_reason: img.reject_reason,
                 metadata,
                 filesystem_path: None, // Not calculated for bulk operations for performance
+                favorite: favorites.contains(&img.id),
+                tags: tags.remove(&img.id).unwrap_or_default(),
+                exposure_verdict: None,
             }
         })
         .collect();
@@ -2090,96 +2793,631 @@ pub async fn get_images(
     Ok(Json(ApiResponse::success(response)))
 }
 
-#[axum::debug_handler(state = Arc<AppState>)]
-pub async fn get_image(
-    ctx: DbContext,
-    Path((_db_id, image_id)): Path<(String, i32)>,
-) -> Result<Json<ApiResponse<ImageResponse>>, AppError> {
-    use crate::image_analysis::FitsImage;
-
-    // Get image data from database first (before any async operations)
-    let (image, proj_name, target_name, mut metadata, show_profile) = {
-        let conn = ctx.db();
-        let conn = conn.lock().map_err(AppError::db)?;
-        let db = Database::new(&conn);
-
-        // Get profile count to determine display format
-        let profile_count = db.get_profile_count().map_err(AppError::db)?;
-        let show_profile = profile_count > 1;
-
-        let images = db.get_images_by_ids(&[image_id]).map_err(AppError::db)?;
+/// The listing's `files` filter: `Some(true)` for present, `Some(false)`
+/// for missing.
+fn listing_files_filter(files: Option<&str>) -> Result<Option<bool>, AppError> {
+    match files {
+        None => Ok(None),
+        Some("present") => Ok(Some(true)),
+        Some("missing") => Ok(Some(false)),
+        Some(other) => Err(AppError::BadRequest(format!(
+            "Invalid files '{}': expected present or missing",
+            other
+        ))),
+    }
+}
 
-        let image = images.into_iter().next().ok_or(AppError::NotFound)?;
+/// The listing's `imagetyp` filter: `light` or `calibration`.
+fn listing_image_type(
+    imagetyp: Option<&str>,
+) -> Result<Option<crate::db::ImageTypeFilter>, AppError> {
+    imagetyp
+        .map(|value| {
+            crate::db::ImageTypeFilter::parse(value).ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Invalid imagetyp '{}': expected light or calibration",
+                    value
+                ))
+            })
+        })
+        .transpose()
+}
 
-        // Get project and target names
-        let scoped_images = db
-            .query_images_scoped(None, Some(image.project_id), Some(image.target_id), None, 0)
-            .map_err(AppError::db)?;
+/// The directory tree the `files` filter checks against, when it is set.
+/// One tree serves the whole request: presence is a lookup in the cached
+/// filename index, never a per-image filesystem walk.
+fn listing_files_tree(
+    ctx: &DatabaseContext,
+    want_present: Option<bool>,
+) -> Result<Option<Arc<crate::directory_tree::DirectoryTree>>, AppError> {
+    if want_present.is_none() {
+        return Ok(None);
+    }
+    ctx.get_directory_tree().map(Some).map_err(|e| {
+        tracing::error!("Failed to get directory tree cache: {}", e);
+        AppError::InternalError("Directory cache error".to_string())
+    })
+}
 
-        let (_, proj_name, target_name) = scoped_images
-            .into_iter()
-            .find(|(img, _, _)| img.id == image_id)
-            .ok_or(AppError::NotFound)?;
+/// Whether `image`'s FITS file is in `tree`, for the `files` filter.
+fn listing_file_present(
+    tree: &crate::directory_tree::DirectoryTree,
+    image: &crate::models::AcquiredImage,
+) -> bool {
+    crate::utils::extract_filename(&image.metadata)
+        .is_some_and(|filename| tree.find_file(&filename).is_some())
+}
 
-        let metadata: serde_json::Value = serde_json::from_str(&image.metadata)
-            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+/// The listing's `status` filter; an unknown value means no filter.
+fn listing_status_filter(status: Option<&str>) -> Option<GradingStatus> {
+    match status? {
+        "pending" => Some(GradingStatus::Pending),
+        "accepted" => Some(GradingStatus::Accepted),
+        "rejected" => Some(GradingStatus::Rejected),
+        _ => None,
+    }
+}
 
-        (image, proj_name, target_name, metadata, show_profile)
-    }; // Database connection is dropped here
+#[cfg(test)]
+mod listing_filter_tests {
+    use super::*;
 
-    // Now we can do async operations
-    let stats_cache_filename = format!(
-        "stats_{}_{}_{}_{}.json",
-        image_id,
-        image.project_id,
-        image.target_id,
-        image.acquired_date.unwrap_or(0)
-    );
-    let stats_cache_path = ctx.get_cache_path("stats", &stats_cache_filename);
+    #[test]
+    fn files_filter_accepts_present_and_missing_only() {
+        assert_eq!(listing_files_filter(None).unwrap(), None);
+        assert_eq!(listing_files_filter(Some("present")).unwrap(), Some(true));
+        assert_eq!(listing_files_filter(Some("missing")).unwrap(), Some(false));
+        assert!(matches!(
+            listing_files_filter(Some("gone")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
 
-    // Ensure cache directory exists
-    if let Some(parent) = stats_cache_path.parent() {
-        let _ = tokio::fs::create_dir_all(parent).await;
+    #[test]
+    fn image_type_filter_accepts_light_and_calibration_only() {
+        assert_eq!(listing_image_type(None).unwrap(), None);
+        assert_eq!(
+            listing_image_type(Some("Light")).unwrap(),
+            Some(crate::db::ImageTypeFilter::Light)
+        );
+        assert_eq!(
+            listing_image_type(Some("calibration")).unwrap(),
+            Some(crate::db::ImageTypeFilter::Calibration)
+        );
+        assert!(matches!(
+            listing_image_type(Some("dark")),
+            Err(AppError::BadRequest(_))
+        ));
     }
+}
 
-    // Try to resolve the filesystem path for the FITS file
-    let filesystem_path = metadata["FileName"].as_str().and_then(|filename| {
-        filename
-            .split(&['\\', '/'][..])
-            .next_back()
-            .map(|file_only| find_fits_file(&ctx, &image, &target_name, file_only))
-    });
+fn listing_sort(sort_by: Option<&str>) -> Result<crate::db::ImageSort, AppError> {
+    match sort_by {
+        None => Ok(crate::db::ImageSort::default()),
+        Some(value) => crate::db::ImageSort::parse(value).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Unknown sort_by '{}' (expected newest, oldest or filter)",
+                value
+            ))
+        }),
+    }
+}
 
-    let resolved_fits_path = filesystem_path
-        .as_ref()
-        .and_then(|result| result.as_ref().ok());
-    let filesystem_path_string = resolved_fits_path.map(|p| p.to_string_lossy().to_string());
+/// GET /api/db/{db_id}/images/{image_id}/neighbors
+///
+/// Previous and next image in the listing with the same filter and order, so
+/// keyboard navigation doesn't need the whole list client-side. 404 when the
+/// image isn't part of that listing.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/neighbors",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), NeighborQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<ImageNeighbors>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_image_neighbors(
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(params): Query<NeighborQuery>,
+) -> Result<Json<ApiResponse<ImageNeighbors>>, AppError> {
+    let filter = crate::db::ImageFilter {
+        status: listing_status_filter(params.status.as_deref()),
+        project_id: params.project_id,
+        target_id: params.target_id,
+        favorites_only: params.favorites_only.unwrap_or(false),
+        tag: params.tag.clone(),
+        image_type: listing_image_type(params.imagetyp.as_deref())?,
+        ..Default::default()
+    };
+    let sort = listing_sort(params.sort_by.as_deref())?;
+    let want_present = listing_files_filter(params.files.as_deref())?;
+    let directory_tree = listing_files_tree(&ctx, want_present)?;
 
-    // Check if statistics are already cached
-    let fits_stats = if tokio::fs::metadata(&stats_cache_path).await.is_ok() {
-        // Load from cache
-        match tokio::fs::read_to_string(&stats_cache_path).await {
-            Ok(cached_data) => serde_json::from_str::<serde_json::Value>(&cached_data).ok(),
-            Err(_) => None,
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    let (previous_id, next_id) = match (&directory_tree, want_present) {
+        // As on the listing, presence isn't in SQL: find the image's place
+        // in the filtered listing.
+        (Some(tree), Some(want_present)) => {
+            let ids: Vec<i32> = db
+                .find_images(&filter, sort, None)
+                .map_err(AppError::db)?
+                .into_iter()
+                .filter(|(img, _, _)| listing_file_present(tree, img) == want_present)
+                .map(|(img, _, _)| img.id)
+                .collect();
+            let index = ids
+                .iter()
+                .position(|&id| id == image_id)
+                .ok_or(AppError::NotFound)?;
+            (
+                index.checked_sub(1).map(|i| ids[i]),
+                ids.get(index + 1).copied(),
+            )
         }
-    } else if let Some(fits_path) = resolved_fits_path {
-        // Calculate statistics from FITS file
-        if let Ok(fits) = FitsImage::from_file(fits_path) {
-            let stats = fits.calculate_basic_statistics();
+        _ => db
+            .get_image_neighbors(image_id, &filter, sort)
+            .map_err(AppError::db)?
+            .ok_or(AppError::NotFound)?,
+    };
 
-            // Extract temperature and camera model from FITS headers
-            let temperature = FitsImage::extract_temperature(fits_path);
-            let camera_model = FitsImage::extract_camera_model(fits_path);
+    Ok(Json(ApiResponse::success(ImageNeighbors {
+        image_id,
+        previous_id,
+        next_id,
+    })))
+}
 
-            let mut stats_json = serde_json::json!({
-                "Min": stats.min,
-                "Max": stats.max,
-                "Mean": stats.mean,
-                "Median": stats.median,
-                "StdDev": stats.std_dev,
+#[cfg(test)]
+mod listing_files_tests {
+    use super::*;
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    /// Frames 1..=6 of one target, one per night; only 2, 3, 5 and 6 have
+    /// their FITS file on disk.
+    fn state_with_frames(dir: &std::path::Path) -> Arc<AppState> {
+        let db_path = dir.join("sched.sqlite");
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(crate::db::test_schema::SCHEDULER_TABLES)
+            .unwrap();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'default', 'Project', NULL);
+            INSERT INTO target VALUES (10, 'M 31', 1, NULL, NULL, 1);",
+        )
+        .unwrap();
+        let images = dir.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        for id in 1..=6 {
+            conn.execute(
+                "INSERT INTO acquiredimage VALUES (?1, 1, 10, ?2, 'L', 0, ?3, NULL, 'default')",
+                rusqlite::params![
+                    id,
+                    id * 86_400,
+                    format!(r#"{{"FileName":"C:\\imaging\\frame_{}.fits"}}"#, id)
+                ],
+            )
+            .unwrap();
+            if ![1, 4].contains(&id) {
+                std::fs::write(images.join(format!("frame_{}.fits", id)), b"").unwrap();
+            }
+        }
+        let state = AppState::new(
+            db_path.to_string_lossy().into_owned(),
+            vec![images.to_string_lossy().into_owned()],
+            dir.join("cache").to_string_lossy().into_owned(),
+            crate::cli::PregenerationConfig::default(),
+            false,
+        )
+        .unwrap();
+        Arc::new(state)
+    }
+
+    async fn get_json(state: &Arc<AppState>, path_and_query: &str) -> (StatusCode, Value) {
+        let db_id = state.all_databases()[0].id.clone();
+        let app = axum::Router::new()
+            .route("/api/db/{db_id}/images", axum::routing::get(get_images))
+            .route(
+                "/api/db/{db_id}/images/{image_id}/neighbors",
+                axum::routing::get(get_image_neighbors),
+            )
+            .with_state(state.clone());
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/db/{}{}", db_id, path_and_query))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn ids(body: &Value) -> Vec<i64> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|image| image["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn listing_filters_on_file_presence_and_pages_after_filtering() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_frames(dir.path());
+
+        let (status, body) = get_json(&state, "/images?files=present&limit=3").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), [6, 5, 3]);
+        let (_, body) = get_json(&state, "/images?files=present&limit=3&offset=3").await;
+        assert_eq!(ids(&body), [2]);
+        let (_, body) = get_json(&state, "/images?files=missing").await;
+        assert_eq!(ids(&body), [4, 1]);
+
+        let (status, _) = get_json(&state, "/images?files=gone").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn neighbors_skip_images_the_files_filter_hides() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_frames(dir.path());
+
+        let (_, body) = get_json(&state, "/images/5/neighbors").await;
+        assert_eq!(
+            (&body["data"]["previous_id"], &body["data"]["next_id"]),
+            (&Value::from(6), &Value::from(4))
+        );
+        let (_, body) = get_json(&state, "/images/5/neighbors?files=present").await;
+        assert_eq!(
+            (&body["data"]["previous_id"], &body["data"]["next_id"]),
+            (&Value::from(6), &Value::from(3))
+        );
+        let (_, body) = get_json(&state, "/images/1/neighbors?files=missing").await;
+        assert_eq!(
+            (&body["data"]["previous_id"], &body["data"]["next_id"]),
+            (&Value::from(4), &Value::Null)
+        );
+
+        // Frame 4 has no file, so it isn't in the present listing at all.
+        let (status, _) = get_json(&state, "/images/4/neighbors?files=present").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
+
+/// GET /api/db/{db_id}/images/{image_id}/grading-detail
+///
+/// Runs the statistical grading rules (as `regrade` would) against one image
+/// and the other frames of its target and filter acquired within the last
+/// `days`, and returns each rule's statistic, threshold and pass/fail
+/// alongside the resulting verdict. An image outside the window has no rules.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/grading-detail",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), GradingDetailQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<GradingDetail>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_grading_detail(
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(params): Query<GradingDetailQuery>,
+) -> Result<Json<ApiResponse<GradingDetail>>, AppError> {
+    let config = grading_detail_config(&params)?;
+    let days = params.days.unwrap_or(DEFAULT_REGRADE_DAYS);
+    let cutoff = chrono::Utc::now()
+        .checked_sub_signed(chrono::Duration::days(days.into()))
+        .ok_or_else(|| AppError::BadRequest(format!("days={} is out of range", days)))?
+        .timestamp();
+
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    let image = db
+        .get_images_by_ids(&[image_id])
+        .map_err(AppError::db)?
+        .into_iter()
+        .next()
+        .ok_or(AppError::NotFound)?;
+    let filter = crate::db::ImageFilter {
+        target_id: Some(image.target_id),
+        image_type: Some(crate::db::ImageTypeFilter::Light),
+        acquired_since: Some(cutoff),
+        ..Default::default()
+    };
+    let stats: Vec<_> = db
+        .find_images(&filter, crate::db::ImageSort::default(), None)
+        .map_err(AppError::db)?
+        .into_iter()
+        .filter(|(other, _, _)| other.filter_name == image.filter_name)
+        .filter_map(|(other, _, target_name)| {
+            crate::grading::parse_image_metadata(
+                other.id,
+                other.target_id,
+                &target_name,
+                &other.metadata,
+                &other.filter_name,
+                other.grading_status,
+            )
+            .ok()
+        })
+        .collect();
+
+    let rules = crate::grading::StatisticalGrader::new(config).evaluate_image(&stats, image_id);
+    Ok(Json(ApiResponse::success(GradingDetail {
+        image_id,
+        rejected: rules.iter().any(|rule| !rule.passed),
+        rules,
+    })))
+}
+
+/// Look-back window of `regrade --days`.
+const DEFAULT_REGRADE_DAYS: u32 = 90;
+
+/// Grading config for a grading-detail query: the `regrade` defaults with
+/// the query's thresholds and rule toggles applied.
+fn grading_detail_config(
+    params: &GradingDetailQuery,
+) -> Result<crate::grading::StatisticalGradingConfig, AppError> {
+    let defaults = crate::grading::StatisticalGradingConfig::default();
+    let config = crate::grading::StatisticalGradingConfig {
+        enable_hfr_analysis: params
+            .enable_hfr_analysis
+            .unwrap_or(defaults.enable_hfr_analysis),
+        hfr_stddev_threshold: params.hfr_stddev.unwrap_or(defaults.hfr_stddev_threshold),
+        enable_star_count_analysis: params
+            .enable_star_count_analysis
+            .unwrap_or(defaults.enable_star_count_analysis),
+        star_count_stddev_threshold: params
+            .star_stddev
+            .unwrap_or(defaults.star_count_stddev_threshold),
+        enable_distribution_analysis: params
+            .enable_distribution_analysis
+            .unwrap_or(defaults.enable_distribution_analysis),
+        median_shift_threshold: params
+            .median_shift_threshold
+            .unwrap_or(defaults.median_shift_threshold),
+        enable_cloud_detection: params
+            .enable_cloud_detection
+            .unwrap_or(defaults.enable_cloud_detection),
+        cloud_threshold: params.cloud_threshold.unwrap_or(defaults.cloud_threshold),
+        cloud_baseline_count: params
+            .cloud_baseline_count
+            .unwrap_or(defaults.cloud_baseline_count),
+    };
+    let thresholds = [
+        config.hfr_stddev_threshold,
+        config.star_count_stddev_threshold,
+        config.median_shift_threshold,
+        config.cloud_threshold,
+    ];
+    if thresholds.iter().any(|t| !(t.is_finite() && *t > 0.0)) || config.cloud_baseline_count == 0 {
+        return Err(AppError::BadRequest(
+            "grading thresholds must be positive".to_string(),
+        ));
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod grading_detail_tests {
+    use super::*;
+
+    #[test]
+    fn every_rule_runs_unless_switched_off() {
+        let config = grading_detail_config(&GradingDetailQuery::default()).unwrap();
+        assert!(config.enable_hfr_analysis && config.enable_star_count_analysis);
+        assert!(config.enable_distribution_analysis && config.enable_cloud_detection);
+
+        let config = grading_detail_config(&GradingDetailQuery {
+            enable_cloud_detection: Some(false),
+            hfr_stddev: Some(2.5),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!config.enable_cloud_detection && config.enable_hfr_analysis);
+        assert_eq!(config.hfr_stddev_threshold, 2.5);
+
+        let zero = GradingDetailQuery {
+            cloud_baseline_count: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            grading_detail_config(&zero),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
+
+/// The `session_gap_minutes` query value, defaulted like the sequence
+/// analyzer's; zero, or a gap too large to express in seconds, is a 400.
+fn session_gap_minutes(requested: Option<u64>) -> Result<u64, AppError> {
+    let gap_minutes = requested.unwrap_or_else(|| {
+        crate::sequence_analysis::SequenceAnalyzerConfig::default().session_gap_minutes
+    });
+    if gap_minutes == 0 {
+        return Err(AppError::BadRequest(
+            "session_gap_minutes must be positive".to_string(),
+        ));
+    }
+    if crate::sequence_analysis::session_gap_seconds(gap_minutes).is_none() {
+        return Err(AppError::BadRequest(
+            "session_gap_minutes is out of range".to_string(),
+        ));
+    }
+    Ok(gap_minutes)
+}
+
+#[cfg(test)]
+mod session_gap_tests {
+    use super::*;
+
+    #[test]
+    fn zero_and_overflowing_gaps_are_bad_requests() {
+        assert_eq!(session_gap_minutes(None).unwrap(), 60);
+        assert_eq!(session_gap_minutes(Some(90)).unwrap(), 90);
+        for gap in [0, u64::MAX, u64::MAX / 60] {
+            assert!(matches!(
+                session_gap_minutes(Some(gap)),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/sessions",
+    tag = "sessions",
+    params(("db_id" = String, Path), SessionQuery),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<Vec<crate::models::AcquisitionSession>>)
+    )
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_sessions(
+    ctx: DbContext,
+    Query(params): Query<SessionQuery>,
+) -> Result<Json<ApiResponse<Vec<crate::models::AcquisitionSession>>>, AppError> {
+    let gap_minutes = session_gap_minutes(params.session_gap_minutes)?;
+
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    let sessions = db
+        .get_sessions(params.project_id, params.target_id, gap_minutes)
+        .map_err(AppError::db)?;
+
+    Ok(Json(ApiResponse::success(sessions)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<ImageResponse>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_image(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+) -> Result<Json<ApiResponse<ImageResponse>>, AppError> {
+    use crate::image_analysis::{ChannelLayout, ExposureClipping, FitsImage};
+
+    // Get image data from database first (before any async operations)
+    let (image, proj_name, target_name, mut metadata, show_profile, favorite, tags) = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        let db = Database::new(&conn);
+
+        // Get profile count to determine display format
+        let profile_count = db.get_profile_count().map_err(AppError::db)?;
+        let show_profile = profile_count > 1;
+
+        let images = db.get_images_by_ids(&[image_id]).map_err(AppError::db)?;
+
+        let image = images.into_iter().next().ok_or(AppError::NotFound)?;
+
+        // Get project and target names
+        let scoped_images = db
+            .query_images_scoped(None, Some(image.project_id), Some(image.target_id), None, 0)
+            .map_err(AppError::db)?;
+
+        let (_, proj_name, target_name) = scoped_images
+            .into_iter()
+            .find(|(img, _, _)| img.id == image_id)
+            .ok_or(AppError::NotFound)?;
+
+        let metadata: serde_json::Value = serde_json::from_str(&image.metadata)
+            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+        let favorite = db
+            .favorite_ids(&[image_id])
+            .map_err(AppError::db)?
+            .contains(&image_id);
+        let tags = db
+            .tags_for_images(&[image_id])
+            .map_err(AppError::db)?
+            .remove(&image_id)
+            .unwrap_or_default();
+
+        (
+            image,
+            proj_name,
+            target_name,
+            metadata,
+            show_profile,
+            favorite,
+            tags,
+        )
+    }; // Database connection is dropped here
+
+    // Now we can do async operations
+    let stats_cache_filename = format!(
+        "stats_{}_{}_{}_{}.json",
+        image_id,
+        image.project_id,
+        image.target_id,
+        image.acquired_date.unwrap_or(0)
+    );
+    let stats_cache_path = ctx.get_cache_path("stats", &stats_cache_filename);
+
+    // Ensure cache directory exists
+    if let Some(parent) = stats_cache_path.parent() {
+        let _ = tokio::fs::create_dir_all(parent).await;
+    }
+
+    // Try to resolve the filesystem path for the FITS file
+    let filesystem_path = metadata["FileName"].as_str().and_then(|filename| {
+        filename
+            .split(&['\\', '/'][..])
+            .next_back()
+            .map(|file_only| find_fits_file(&ctx, &image, &target_name, file_only))
+    });
+
+    let resolved_fits_path = filesystem_path
+        .as_ref()
+        .and_then(|result| result.as_ref().ok());
+    let filesystem_path_string = resolved_fits_path.map(|p| p.to_string_lossy().to_string());
+
+    // Check if statistics are already cached; entries written before the
+    // clip fractions were recorded are measured again.
+    let cached_stats = match tokio::fs::read_to_string(&stats_cache_path).await {
+        Ok(cached_data) => serde_json::from_str::<serde_json::Value>(&cached_data)
+            .ok()
+            .filter(|stats| stats.get("LowClipFraction").is_some()),
+        Err(_) => None,
+    };
+    let fits_stats = if cached_stats.is_some() {
+        cached_stats
+    } else if let Some(fits_path) = resolved_fits_path {
+        // Calculate statistics from FITS file
+        if let Ok(fits) = FitsImage::from_file(fits_path) {
+            let stats = fits.calculate_basic_statistics();
+
+            // Temperature and camera model from the header loaded with the pixels
+            let temperature = fits.header.temperature();
+            let camera_model = fits.header.camera_model();
+
+            let mut stats_json = serde_json::json!({
+                "Min": stats.min,
+                "Max": stats.max,
+                "Mean": stats.mean,
+                "Median": stats.median,
+                "StdDev": stats.std_dev,
                 "Mad": stats.mad
             });
 
+            // Clip fractions stay null for rescaled float frames
+            let clipping = fits.exposure_clipping();
+            stats_json["LowClipFraction"] = serde_json::json!(clipping.map(|c| c.low_fraction));
+            stats_json["HighClipFraction"] = serde_json::json!(clipping.map(|c| c.high_fraction));
+
             // Add temperature if available
             if let Some(temp) = temperature {
                 stats_json["Temperature"] = serde_json::json!(temp);
@@ -2190,6 +3428,13 @@ pub async fn get_image(
                 stats_json["Camera"] = serde_json::json!(camera);
             }
 
+            // Per-channel balance for color frames
+            if ChannelLayout::from_header(&fits.header).is_color()
+                && let Ok(channels) = FitsImage::channel_statistics_from_file(fits_path)
+            {
+                stats_json["Channels"] = serde_json::json!(channels);
+            }
+
             // Cache the statistics
             if let Ok(cached_data) = serde_json::to_string(&stats_json) {
                 let _ = tokio::fs::write(&stats_cache_path, cached_data).await;
@@ -2203,6 +3448,16 @@ pub async fn get_image(
         None
     };
 
+    let exposure_verdict = fits_stats
+        .as_ref()
+        .and_then(|stats| {
+            Some(ExposureClipping {
+                low_fraction: stats["LowClipFraction"].as_f64()?,
+                high_fraction: stats["HighClipFraction"].as_f64()?,
+            })
+        })
+        .map(|clipping| state.exposure_thresholds().verdict(&clipping));
+
     // Merge statistics into metadata if available
     if let (Some(stats), Some(metadata_obj)) = (fits_stats, metadata.as_object_mut())
         && let Some(stats_obj) = stats.as_object()
@@ -2231,16 +3486,29 @@ pub async fn get_image(
         reject_reason: image.reject_reason,
         metadata,
         filesystem_path: filesystem_path_string,
+        favorite,
+        tags,
+        exposure_verdict,
     };
 
     Ok(Json(ApiResponse::success(response)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/db/{db_id}/images/{image_id}/grade",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    request_body = UpdateGradeRequest,
+    responses((status = 200, description = "Done; `data` is null"))
+)]
 pub async fn update_image_grade(
+    State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Path((_db_id, image_id)): Path<(String, i32)>,
     Json(request): Json<UpdateGradeRequest>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
+    require_writable(&state)?;
     let conn = ctx.db();
     let conn = conn.lock().map_err(AppError::db)?;
     let db = Database::new(&conn);
@@ -2258,6 +3526,197 @@ pub async fn update_image_grade(
     Ok(Json(ApiResponse::success(())))
 }
 
+/// POST /api/db/{db_id}/images/{image_id}/favorite
+///
+/// Bookmark an image. Grading is untouched; flagging twice is a no-op.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/images/{image_id}/favorite",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<FavoriteResponse>))
+)]
+pub async fn add_image_favorite(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+) -> Result<Json<ApiResponse<FavoriteResponse>>, AppError> {
+    set_image_favorite(&state, &ctx, image_id, true)
+}
+
+/// DELETE /api/db/{db_id}/images/{image_id}/favorite
+#[utoipa::path(
+    delete,
+    path = "/api/db/{db_id}/images/{image_id}/favorite",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<FavoriteResponse>))
+)]
+pub async fn remove_image_favorite(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+) -> Result<Json<ApiResponse<FavoriteResponse>>, AppError> {
+    set_image_favorite(&state, &ctx, image_id, false)
+}
+
+fn set_image_favorite(
+    state: &AppState,
+    ctx: &DatabaseContext,
+    image_id: i32,
+    favorite: bool,
+) -> Result<Json<ApiResponse<FavoriteResponse>>, AppError> {
+    require_writable(state)?;
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+
+    if db
+        .get_images_by_ids(&[image_id])
+        .map_err(AppError::db)?
+        .is_empty()
+    {
+        return Err(AppError::NotFound);
+    }
+    db.set_favorite(image_id, favorite).map_err(AppError::db)?;
+
+    Ok(Json(ApiResponse::success(FavoriteResponse {
+        image_id,
+        favorite,
+    })))
+}
+
+/// Longest tag accepted, in characters.
+const MAX_TAG_LENGTH: usize = 64;
+
+/// A tag as stored: trimmed, non-empty, short and free of control characters.
+fn normalize_tag(tag: &str) -> Result<String, AppError> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(AppError::BadRequest("Tags must not be empty".to_string()));
+    }
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Tags must be {} characters or fewer",
+            MAX_TAG_LENGTH
+        )));
+    }
+    if tag.chars().any(char::is_control) {
+        return Err(AppError::BadRequest(
+            "Tags must not contain control characters".to_string(),
+        ));
+    }
+    Ok(tag.to_string())
+}
+
+/// POST /api/db/{db_id}/images/{image_id}/tags
+///
+/// Add one or more tags to an image; tags it already has are ignored.
+/// Answers with the image's full tag list.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/images/{image_id}/tags",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    request_body = AddTagsRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<ImageTagsResponse>))
+)]
+pub async fn add_image_tags(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Json(request): Json<AddTagsRequest>,
+) -> Result<Json<ApiResponse<ImageTagsResponse>>, AppError> {
+    require_writable(&state)?;
+    let tags = request
+        .tags
+        .iter()
+        .map(|tag| normalize_tag(tag))
+        .collect::<Result<Vec<_>, _>>()?;
+    if tags.is_empty() {
+        return Err(AppError::BadRequest("No tags given".to_string()));
+    }
+
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    if db
+        .get_images_by_ids(&[image_id])
+        .map_err(AppError::db)?
+        .is_empty()
+    {
+        return Err(AppError::NotFound);
+    }
+    db.add_tags(image_id, &tags).map_err(AppError::db)?;
+
+    image_tags_response(&db, image_id)
+}
+
+/// DELETE /api/db/{db_id}/images/{image_id}/tags/{tag}
+///
+/// 404 when the image doesn't carry the tag.
+#[utoipa::path(
+    delete,
+    path = "/api/db/{db_id}/images/{image_id}/tags/{tag}",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), ("tag" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<ImageTagsResponse>))
+)]
+pub async fn remove_image_tag(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id, tag)): Path<(String, i32, String)>,
+) -> Result<Json<ApiResponse<ImageTagsResponse>>, AppError> {
+    require_writable(&state)?;
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    if !db.remove_tag(image_id, tag.trim()).map_err(AppError::db)? {
+        return Err(AppError::NotFound);
+    }
+
+    image_tags_response(&db, image_id)
+}
+
+fn image_tags_response(
+    db: &Database,
+    image_id: i32,
+) -> Result<Json<ApiResponse<ImageTagsResponse>>, AppError> {
+    let tags = db
+        .tags_for_images(&[image_id])
+        .map_err(AppError::db)?
+        .remove(&image_id)
+        .unwrap_or_default();
+    Ok(Json(ApiResponse::success(ImageTagsResponse {
+        image_id,
+        tags,
+    })))
+}
+
+/// GET /api/db/{db_id}/tags
+///
+/// Every tag in use with its image count, alphabetically.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/tags",
+    tag = "tags",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<TagCount>>))
+)]
+pub async fn get_tags(ctx: DbContext) -> Result<Json<ApiResponse<Vec<TagCount>>>, AppError> {
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    let tags = db
+        .tag_counts()
+        .map_err(AppError::db)?
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect();
+
+    Ok(Json(ApiResponse::success(tags)))
+}
+
 /// Shared DB lookup for the image handlers: the acquired-image row, the FITS
 /// basename (from `metadata.FileName`), and the target name.
 fn resolve_image_meta(
@@ -2297,9 +3756,22 @@ fn resolve_image_meta(
     Ok((image, file_only, target_name))
 }
 
+/// Pixel bounds for a requested preview `size`; 400 listing the valid sizes
+/// when it is not a configured preset or `original`.
+fn requested_max_dimensions(state: &AppState, size: &str) -> Result<Option<(u32, u32)>, AppError> {
+    state
+        .pregeneration_config
+        .preview_sizes
+        .resolve(size)
+        .map_err(AppError::BadRequest)
+}
+
 /// Cache key for a stretched preview PNG. Must stay identical between the
 /// preview handler, the status endpoint, and the pre-generation path so all
 /// three address the same file.
+/// `logarithmic` and `invert` only add a suffix when set, so keys for the
+/// default rendering (and already cached files) are unchanged.
+#[allow(clippy::too_many_arguments)]
 fn preview_cache_key(
     image: &crate::models::AcquiredImage,
     file_only: &str,
@@ -2307,9 +3779,11 @@ fn preview_cache_key(
     stretch: bool,
     midtone: f64,
     shadow: f64,
+    logarithmic: bool,
+    invert: bool,
 ) -> String {
     format!(
-        "{}_{}_{}_{}_{}_{}_{}_{}_{}",
+        "{}_{}_{}_{}_{}_{}_{}_{}_{}{}{}",
         image.id,
         image.project_id,
         image.target_id,
@@ -2319,6 +3793,8 @@ fn preview_cache_key(
         if stretch { "stretch" } else { "linear" },
         (midtone * 10000.0) as i32,
         (shadow * 10000.0) as i32,
+        if logarithmic { "_log" } else { "" },
+        if invert { "_inv" } else { "" },
     )
 }
 
@@ -2327,17 +3803,20 @@ fn annotated_cache_key(
     image: &crate::models::AcquiredImage,
     file_only: &str,
     size: &str,
-    max_stars: usize,
+    selection: &crate::commands::annotate_stars_common::AnnotationSelection,
+    markers: &crate::commands::annotate_stars_common::MarkerOptions,
 ) -> String {
     format!(
-        "annotated_{}_{}_{}_{}_{}_{}_{}",
+        "annotated_{}_{}_{}_{}_{}_{}_{}{}{}",
         image.id,
         image.project_id,
         image.target_id,
         image.acquired_date.unwrap_or(0),
         file_only.replace(&['.', ' ', '-'][..], "_"),
         size,
-        max_stars,
+        selection.max_stars,
+        selection.cache_suffix(),
+        markers.cache_suffix(),
     )
 }
 
@@ -2347,27 +3826,410 @@ fn artifact_cache_path(
     ctx: &DatabaseContext,
     category: &str,
     key: &str,
+) -> Result<PathBuf, AppError> {
+    artifact_cache_path_with_extension(ctx, category, key, "png")
+}
+
+fn artifact_cache_path_with_extension(
+    ctx: &DatabaseContext,
+    category: &str,
+    key: &str,
+    extension: &str,
 ) -> Result<PathBuf, AppError> {
     let cm = crate::server::cache::CacheManager::new(PathBuf::from(&ctx.cache_dir));
     cm.ensure_category_dir(category)
         .map_err(|e| AppError::InternalError(format!("Failed to create cache directory: {}", e)))?;
-    Ok(cm.get_cached_path(category, key, "png"))
+    Ok(cm.get_cached_path(category, key, extension))
+}
+
+/// Serve a cached PNG from disk with a strong ETag, answering 304 (no body)
+/// when the request's `If-None-Match` already names it. Browsers may reuse it
+/// for `max_age` (`[cache] http_max_age`) before revalidating. The body is
+/// streamed from the file rather than read into memory first.
+async fn serve_cached_png(
+    headers: &HeaderMap,
+    cache_path: &std::path::Path,
+    max_age: std::time::Duration,
+) -> Result<Response, AppError> {
+    serve_cached_file(headers, cache_path, max_age, "image/png").await
+}
+
+/// [`serve_cached_png`] for an artifact of another `content_type`.
+async fn serve_cached_file(
+    headers: &HeaderMap,
+    cache_path: &std::path::Path,
+    max_age: std::time::Duration,
+    content_type: &str,
+) -> Result<Response, AppError> {
+    let file = tokio::fs::File::open(cache_path)
+        .await
+        .map_err(|_| AppError::InternalError("Failed to read cache".to_string()))?;
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|_| AppError::InternalError("Failed to read cache".to_string()))?;
+    let etag = cached_file_etag(cache_path, &metadata);
+    let cache_control = format!("max-age={}", max_age.as_secs());
+    if if_none_match(headers, &etag) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [
+                (ETAG, etag.as_str()),
+                (CACHE_CONTROL, cache_control.as_str()),
+            ],
+        )
+            .into_response());
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, content_type)
+        .header(CONTENT_LENGTH, metadata.len())
+        .header(ETAG, etag)
+        .header(CACHE_CONTROL, cache_control)
+        .body(axum::body::Body::from_stream(
+            tokio_util::io::ReaderStream::new(file),
+        ))
+        .map_err(|e| AppError::InternalError(format!("Failed to build response: {}", e)))
+}
+
+/// Strong ETag for a cached artifact: the cache file name (the artifact's
+/// identity key) plus its mtime and size, so a regenerated file gets a new tag.
+fn cached_file_etag(cache_path: &std::path::Path, metadata: &std::fs::Metadata) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    if let Some(name) = cache_path.file_name() {
+        hasher.update(name.as_encoded_bytes());
+    }
+    if let Ok(since_epoch) = metadata.modified().and_then(|t| {
+        t.duration_since(std::time::UNIX_EPOCH)
+            .map_err(std::io::Error::other)
+    }) {
+        hasher.update(since_epoch.as_nanos().to_le_bytes());
+    }
+    hasher.update(metadata.len().to_le_bytes());
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .take(16)
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("\"{hex}\"")
+}
+
+/// `If-None-Match` uses weak comparison, so a `W/` prefix still matches.
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod preview_cache_key_tests {
+    use super::*;
+
+    #[test]
+    fn star_params_validate_and_key_the_cache() {
+        let query = |threshold, min_size, max_stars, psf_type: Option<&str>| StarQuery {
+            bin: None,
+            roi: None,
+            coords: None,
+            threshold,
+            min_size,
+            max_stars,
+            psf_type: psf_type.map(str::to_string),
+            hfr_method: None,
+        };
+
+        let (params, max_stars, suffix) =
+            star_detection_params(&query(None, None, None, None)).unwrap();
+        assert_eq!(params.psf_type, crate::psf_fitting::PSFType::Moffat4);
+        assert_eq!(max_stars, None);
+        assert_eq!(suffix, "");
+
+        let (params, max_stars, suffix) =
+            star_detection_params(&query(Some(5.5), Some(8), Some(200), Some("gaussian"))).unwrap();
+        assert_eq!(params.sensitivity, 5.5);
+        assert_eq!(params.min_star_size, 8);
+        assert_eq!(max_stars, Some(200));
+        assert_eq!(suffix, "_t5500_min8_max200_gaussian");
+        let (_, _, other) =
+            star_detection_params(&query(Some(6.0), Some(8), Some(200), Some("gaussian"))).unwrap();
+        assert_ne!(suffix, other);
+
+        let nina = StarQuery {
+            hfr_method: Some("nina".to_string()),
+            ..query(None, None, None, None)
+        };
+        let (params, _, suffix) = star_detection_params(&nina).unwrap();
+        assert_eq!(
+            params.hfr_method,
+            crate::hocus_focus_star_detection::HfrMethod::Nina
+        );
+        assert_eq!(suffix, "_hfrnina");
+        let bad_method = StarQuery {
+            hfr_method: Some("fwhm".to_string()),
+            ..query(None, None, None, None)
+        };
+        assert!(star_detection_params(&bad_method).is_err());
+
+        for bad in [
+            query(Some(0.0), None, None, None),
+            query(Some(f64::NAN), None, None, None),
+            query(None, Some(0), None, None),
+            query(None, Some(500), None, None),
+            query(None, None, Some(0), None),
+            query(None, None, None, Some("lorentz")),
+        ] {
+            assert!(matches!(
+                star_detection_params(&bad),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn roi_stars_report_full_frame_coordinates_unless_asked() {
+        let response = || StarDetectionResponse {
+            detected_stars: 1,
+            average_hfr: 2.0,
+            average_fwhm: 3.0,
+            stars: vec![StarInfo {
+                x: 5.0,
+                y: 7.0,
+                hfr: 2.0,
+                fwhm: 3.0,
+                brightness: 100.0,
+                eccentricity: 0.1,
+            }],
+            low_star_warning: false,
+        };
+        let roi = parse_roi(Some("100,200,50,50")).unwrap();
+        let full = to_frame_coordinates(response(), roi, false);
+        assert_eq!((full.stars[0].x, full.stars[0].y), (105.0, 207.0));
+        let local = to_frame_coordinates(response(), roi, true);
+        assert_eq!((local.stars[0].x, local.stars[0].y), (5.0, 7.0));
+
+        assert_eq!(roi_cache_suffix(roi.as_ref()), "_roi100_200_50_50");
+        assert_eq!(roi_cache_suffix(None), "");
+
+        use crate::commands::background_extract::BackgroundView;
+        assert_eq!(parse_background(None).unwrap(), None);
+        assert_eq!(parse_background(Some("none")).unwrap(), None);
+        assert_eq!(
+            parse_background(Some("subtract")).unwrap(),
+            Some(BackgroundView::Subtract)
+        );
+        assert!(matches!(
+            parse_background(Some("flatten")),
+            Err(AppError::BadRequest(_))
+        ));
+        assert_eq!(
+            background_cache_suffix(Some(BackgroundView::Show)),
+            "_bgshow"
+        );
+        assert!(matches!(
+            parse_roi(Some("1,2,3")),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            parse_roi(Some("18446744073709551615,0,2,2")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn preview_cache_key_separates_invert_and_logarithmic() {
+        let image = crate::models::AcquiredImage {
+            id: 7,
+            project_id: 1,
+            target_id: 2,
+            acquired_date: Some(1_705_298_400),
+            filter_name: "L".to_string(),
+            grading_status: 0,
+            metadata: "{}".to_string(),
+            reject_reason: None,
+            profile_id: None,
+            guid: None,
+        };
+        let key = |logarithmic, invert| {
+            preview_cache_key(
+                &image,
+                "m31.fits",
+                "screen",
+                true,
+                0.2,
+                -2.8,
+                logarithmic,
+                invert,
+            )
+        };
+        let plain = key(false, false);
+        assert_ne!(plain, key(false, true));
+        assert_ne!(plain, key(true, false));
+        assert_ne!(key(true, false), key(false, true));
+        // The default rendering keeps its historical key.
+        assert_eq!(
+            plain,
+            "7_1_2_1705298400_m31_fits_screen_stretch_2000_-28000"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let cm = crate::server::cache::CacheManager::new(dir.path().to_path_buf());
+        assert_ne!(
+            cm.get_cached_path("previews", &plain, "png"),
+            cm.get_cached_path("previews", &key(false, true), "png")
+        );
+    }
+}
+
+#[cfg(test)]
+mod etag_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cached_png_revalidates_with_if_none_match() {
+        use http_body_util::BodyExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preview_1_screen.png");
+        std::fs::write(&path, b"\x89PNG fake body").unwrap();
+
+        let max_age = std::time::Duration::from_secs(3600);
+        let first = serve_cached_png(&HeaderMap::new(), &path, max_age)
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers().get(CACHE_CONTROL).unwrap(), "max-age=3600");
+        let etag = first.headers().get(ETAG).unwrap().clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, etag.clone());
+        let second = serve_cached_png(&headers, &path, max_age).await.unwrap();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers().get(ETAG), Some(&etag));
+        let body = second.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+
+        headers.insert(IF_NONE_MATCH, "\"stale\"".parse().unwrap());
+        let third = serve_cached_png(&headers, &path, max_age).await.unwrap();
+        assert_eq!(third.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn cached_png_streams_the_file_bytes() {
+        use http_body_util::BodyExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("preview_2_original.png");
+        // Larger than one ReaderStream chunk, so the body arrives in pieces.
+        let bytes: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let max_age = std::time::Duration::from_secs(60);
+        let response = serve_cached_png(&HeaderMap::new(), &path, max_age)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_LENGTH).unwrap(),
+            &bytes.len().to_string()
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body.as_ref(), bytes.as_slice());
+    }
 }
 
-/// Serve a cached PNG from disk.
-async fn serve_cached_png(cache_path: &std::path::Path) -> Result<Response, AppError> {
-    let buffer = tokio::fs::read(cache_path)
-        .await
-        .map_err(|_| AppError::InternalError("Failed to read cache".to_string()))?;
-    Ok((
-        StatusCode::OK,
-        [
-            (CONTENT_TYPE, "image/png"),
-            (CACHE_CONTROL, "max-age=86400"),
-        ],
-        buffer,
-    )
-        .into_response())
+#[cfg(test)]
+mod fits_error_tests {
+    use super::*;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    /// A database with frame 1 of target "M 31" named `frame.fits`, and the
+    /// directory the frame belongs in.
+    fn state_with_frame(dir: &std::path::Path) -> (Arc<AppState>, std::path::PathBuf) {
+        let db_path = dir.join("sched.sqlite");
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(crate::db::test_schema::SCHEDULER_TABLES)
+            .unwrap();
+        conn.execute_batch(
+            "INSERT INTO project VALUES (1, 'default', 'Project', NULL);
+            INSERT INTO target VALUES (10, 'M 31', 1, NULL, NULL, 1);
+            INSERT INTO acquiredimage VALUES (1, 1, 10, 1000, 'L', 0,
+                '{\"FileName\":\"C:\\\\imaging\\\\frame.fits\"}', NULL, 'default');",
+        )
+        .unwrap();
+        let images = dir.join("images");
+        let frame_dir = images.join("M 31").join("1970-01-01").join("LIGHT");
+        std::fs::create_dir_all(&frame_dir).unwrap();
+        let state = AppState::new(
+            db_path.to_string_lossy().into_owned(),
+            vec![images.to_string_lossy().into_owned()],
+            dir.join("cache").to_string_lossy().into_owned(),
+            crate::cli::PregenerationConfig::default(),
+            false,
+        )
+        .unwrap();
+        (Arc::new(state), frame_dir)
+    }
+
+    async fn get_pixels(state: Arc<AppState>) -> (StatusCode, String) {
+        let db_id = state.all_databases()[0].id.clone();
+        let app = axum::Router::new()
+            .route(
+                "/api/db/{db_id}/images/{image_id}/pixels",
+                axum::routing::get(get_image_pixels),
+            )
+            .with_state(state);
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/db/{}/images/1/pixels?x=0&y=0&w=2&h=2", db_id))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn truncated_fits_is_422_without_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let (state, frame_dir) = state_with_frame(dir.path());
+        // One header card and nothing after it.
+        std::fs::write(
+            frame_dir.join("frame.fits"),
+            format!("{:<80}", "SIMPLE  =                    T"),
+        )
+        .unwrap();
+
+        let (status, body) = get_pixels(state).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+        assert!(body.contains("frame.fits"), "{body}");
+        assert!(!body.contains("LIGHT"), "{body}");
+        assert!(!body.contains(&*dir.path().to_string_lossy()), "{body}");
+    }
+
+    #[test]
+    fn unreadable_fits_is_not_reported_as_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let gone = dir.path().join("gone.fits");
+        assert!(matches!(
+            AppError::fits_read(&gone, "open failed"),
+            AppError::NotFound
+        ));
+        // A directory opens but can't be read: an I/O failure, not bad FITS.
+        let err = AppError::fits_read(dir.path(), "read failed");
+        assert!(
+            matches!(&err, AppError::InternalError(msg) if !msg.contains("/")),
+            "{err:?}"
+        );
+    }
 }
 
 /// The immediate "not ready — poll for it" response on a cache miss. `<img>`
@@ -2389,41 +4251,518 @@ fn generating_response() -> Response {
 
 // Image preview endpoint. Cache hit → 200 PNG; miss → enqueue on the bounded
 // interactive queue and return 202 (never generates inside the request).
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/preview",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), PreviewOptions),
+    responses(
+        (
+            status = 200,
+            description = "Preview image",
+            content((Binary = "image/png"), (Binary = "image/tiff"))
+        )
+    )
+)]
 #[axum::debug_handler(state = Arc<AppState>)]
 pub async fn get_image_preview(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Path((_db_id, image_id)): Path<(String, i32)>,
     Query(options): Query<PreviewOptions>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     let size = options.size.as_deref().unwrap_or("screen");
+    let max_dimensions = requested_max_dimensions(&state, size)?;
     let stretch = options.stretch.unwrap_or(true);
     let midtone = options.midtone.unwrap_or(0.2);
     let shadow = options.shadow.unwrap_or(-2.8);
+    let logarithmic = options.logarithmic.unwrap_or(false);
+    let invert = options.invert.unwrap_or(false);
+    let roi = parse_roi(options.roi.as_deref())?;
+    let background = parse_background(options.background.as_deref())?;
+
+    match options.fmt.as_deref() {
+        None | Some("png") => {}
+        Some("tiff") => {
+            if background.is_some() || logarithmic || invert {
+                return Err(AppError::BadRequest(
+                    "fmt=tiff supports stretch, midtone, shadow, size and roi only".to_string(),
+                ));
+            }
+            let depth = crate::commands::fits_to_tiff::TiffDepth::from_bits(
+                options.bit_depth.unwrap_or(16),
+            )
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+            let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
+            let cache_key = format!(
+                "{}{}_tiff{}",
+                preview_cache_key(&image, &file_only, size, stretch, midtone, shadow, false, false),
+                roi_cache_suffix(roi.as_ref()),
+                depth.bits()
+            );
+            let cache_path =
+                artifact_cache_path_with_extension(&ctx, "previews", &cache_key, "tiff")?;
+            if cache_path.exists() {
+                return serve_cached_file(
+                    &headers,
+                    &cache_path,
+                    state.pregeneration_config.http_max_age,
+                    "image/tiff",
+                )
+                .await;
+            }
+            let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+            check_fits_source(&fits_path, roi.as_ref()).await?;
+            state.enqueue_preview(crate::server::preview_queue::GenJob {
+                fits_path,
+                cache_path,
+                kind: crate::server::preview_queue::GenKind::Tiff {
+                    depth,
+                    stretch: stretch.then_some((midtone, shadow)),
+                    max_dimensions,
+                    roi,
+                },
+            });
+            return Ok(generating_response());
+        }
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "fmt must be png or tiff, got '{}'",
+                other
+            )));
+        }
+    }
 
     let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
-    let cache_key = preview_cache_key(&image, &file_only, size, stretch, midtone, shadow);
+    let cache_key = preview_cache_key(
+        &image,
+        &file_only,
+        size,
+        stretch,
+        midtone,
+        shadow,
+        logarithmic,
+        invert,
+    ) + &roi_cache_suffix(roi.as_ref())
+        + &background_cache_suffix(background);
     let cache_path = artifact_cache_path(&ctx, "previews", &cache_key)?;
 
     if cache_path.exists() {
-        return serve_cached_png(&cache_path).await;
+        return serve_cached_png(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+        )
+        .await;
     }
 
-    // Miss: resolve the source (404 if truly missing), hand generation to the
-    // bounded interactive queue, and tell the client to poll.
+    // Miss: resolve the source (404 if truly missing, 422 if unreadable), hand
+    // generation to the bounded interactive queue, and tell the client to poll.
     let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    check_fits_source(&fits_path, roi.as_ref()).await?;
     state.enqueue_preview(crate::server::preview_queue::GenJob {
         fits_path,
         cache_path,
         kind: crate::server::preview_queue::GenKind::Preview {
             midtone,
             shadow,
-            max_dimensions: crate::server::preview_queue::max_dimensions_for_size(size),
+            logarithmic,
+            invert,
+            max_dimensions,
+            roi,
+            background,
+        },
+    });
+    Ok(generating_response())
+}
+
+/// `?roi=x,y,w,h`, parsed; 400 when malformed.
+fn parse_roi(roi: Option<&str>) -> Result<Option<crate::image_analysis::Roi>, AppError> {
+    roi.map(crate::image_analysis::Roi::parse)
+        .transpose()
+        .map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+/// Read the source's header before queueing work on it: 422 when the file is
+/// there but doesn't parse, so the client isn't left polling a job that can
+/// only fail. With a `roi`, also 400 unless it lies inside the frame; the
+/// header sizes it, so the pixels aren't decoded just to reject the request.
+async fn check_fits_source(
+    fits_path: &std::path::Path,
+    roi: Option<&crate::image_analysis::Roi>,
+) -> Result<(), AppError> {
+    let path = fits_path.to_path_buf();
+    let header = tokio::task::spawn_blocking(move || {
+        crate::fits_read::retry_with_backoff(&path, crate::fits_read::read_retry_policy(), || {
+            crate::image_analysis::FitsHeader::read(&path)
+        })
+        .map_err(|e| AppError::fits_read(&path, e))
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Header read panicked: {}", e)))??;
+    let Some(roi) = roi else {
+        return Ok(());
+    };
+    let (width, height) = header.dimensions().ok_or_else(|| {
+        tracing::warn!(
+            "🔭 FITS header of {} has no NAXIS1/NAXIS2",
+            fits_path.display()
+        );
+        AppError::fits_corrupt(fits_path)
+    })?;
+    roi.check_within(width, height)
+        .map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+/// `?background=subtract|show|none`, parsed; 400 otherwise.
+fn parse_background(
+    background: Option<&str>,
+) -> Result<Option<crate::commands::background_extract::BackgroundView>, AppError> {
+    match background {
+        None | Some("none") => Ok(None),
+        Some(view) => view.parse().map(Some).map_err(AppError::BadRequest),
+    }
+}
+
+/// Which stars to annotate, from the query; unset values keep the defaults.
+fn parse_selection(
+    max_stars: Option<u32>,
+    rank_by: Option<&str>,
+    min_snr: Option<f64>,
+    min_brightness: Option<f64>,
+    min_eccentricity: Option<f64>,
+) -> Result<crate::commands::annotate_stars_common::AnnotationSelection, AppError> {
+    let top = crate::commands::annotate_stars_common::AnnotationSelection::top(
+        max_stars.unwrap_or(1000) as usize,
+    );
+    Ok(
+        crate::commands::annotate_stars_common::AnnotationSelection {
+            rank_by: match rank_by {
+                Some(metric) => metric.parse().map_err(AppError::BadRequest)?,
+                None => top.rank_by,
+            },
+            min_snr,
+            min_brightness,
+            min_eccentricity,
+            ..top
+        },
+    )
+}
+
+/// Annotation markers from the query; unset values keep the defaults.
+fn parse_markers(
+    marker: Option<&str>,
+    marker_size: Option<&str>,
+    min_radius: Option<f64>,
+    max_radius: Option<f64>,
+) -> Result<crate::commands::annotate_stars_common::MarkerOptions, AppError> {
+    let defaults = crate::commands::annotate_stars_common::MarkerOptions::default();
+    let markers = crate::commands::annotate_stars_common::MarkerOptions {
+        style: match marker {
+            Some(style) => style.parse().map_err(AppError::BadRequest)?,
+            None => defaults.style,
+        },
+        scaling: match marker_size {
+            Some(scaling) => scaling.parse().map_err(AppError::BadRequest)?,
+            None => defaults.scaling,
+        },
+        min_radius: min_radius.unwrap_or(defaults.min_radius),
+        max_radius: max_radius.unwrap_or(defaults.max_radius),
+    };
+    markers.validate().map_err(AppError::BadRequest)?;
+    Ok(markers)
+}
+
+fn background_cache_suffix(
+    background: Option<crate::commands::background_extract::BackgroundView>,
+) -> String {
+    background
+        .map(|view| format!("_{}", view.tag()))
+        .unwrap_or_default()
+}
+
+/// Cache-key suffix separating crops from the whole frame and each other.
+fn roi_cache_suffix(roi: Option<&crate::image_analysis::Roi>) -> String {
+    roi.map(|roi| format!("_roi{}_{}_{}_{}", roi.x, roi.y, roi.width, roi.height))
+        .unwrap_or_default()
+}
+
+/// GET /api/db/{db_id}/images/{image_id}/compare
+///
+/// Two stretches of one frame composited side by side (A left, B right) so
+/// stretch settings can be judged against each other. Queued like previews:
+/// a miss answers 202 while the PNG is generated.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/compare",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), CompareOptions),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
+pub async fn get_image_compare(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(options): Query<CompareOptions>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let size = options.size.as_deref().unwrap_or("screen");
+    let max_dimensions = requested_max_dimensions(&state, size)?;
+    let a = (
+        options.midtone_a.unwrap_or(0.2),
+        options.shadow_a.unwrap_or(-2.8),
+    );
+    let b = (
+        options.midtone_b.unwrap_or(0.2),
+        options.shadow_b.unwrap_or(-2.8),
+    );
+
+    let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
+    let cache_key = format!(
+        "{}_vs_{}_{}",
+        preview_cache_key(&image, &file_only, size, true, a.0, a.1, false, false),
+        (b.0 * 10000.0) as i32,
+        (b.1 * 10000.0) as i32,
+    );
+    let cache_path = artifact_cache_path(&ctx, "compare", &cache_key)?;
+
+    if cache_path.exists() {
+        return serve_cached_png(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+        )
+        .await;
+    }
+
+    let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    state.enqueue_preview(crate::server::preview_queue::GenJob {
+        fits_path,
+        cache_path,
+        kind: crate::server::preview_queue::GenKind::Compare {
+            a,
+            b,
+            max_dimensions,
+        },
+    });
+    Ok(generating_response())
+}
+
+/// GET /api/db/{db_id}/images/diff?a=ID&b=ID
+///
+/// Per-pixel difference of two frames, stretched to the same size (the
+/// smaller of the two), as a heatmap PNG; `stats=true` returns the mean / max
+/// difference and changed fraction instead. Queued like previews: a miss
+/// answers 202 while it renders. The cache key covers both ids and acquired
+/// dates, so a re-imported frame under an old id isn't served stale.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/diff",
+    tag = "images",
+    params(("db_id" = String, Path), DiffQuery),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
+pub async fn get_image_diff(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Query(query): Query<DiffQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    if query.a == query.b {
+        return Err(AppError::BadRequest(
+            "a and b must be different images".to_string(),
+        ));
+    }
+    let size = query.size.as_deref().unwrap_or("screen");
+    let max_dimensions = requested_max_dimensions(&state, size)?;
+
+    let (image_a, file_a, target_a) = resolve_image_meta(&ctx, query.a)?;
+    let (image_b, file_b, target_b) = resolve_image_meta(&ctx, query.b)?;
+    let cache_key = format!(
+        "{}_{}_vs_{}_{}_{}",
+        image_a.id,
+        image_a.acquired_date.unwrap_or(0),
+        image_b.id,
+        image_b.acquired_date.unwrap_or(0),
+        size
+    );
+    let cache_path = artifact_cache_path(&ctx, "diffs", &cache_key)?;
+
+    if cache_path.exists() {
+        if !query.stats {
+            return serve_cached_png(
+                &headers,
+                &cache_path,
+                state.pregeneration_config.http_max_age,
+            )
+            .await;
+        }
+        let stats_path = crate::server::preview_queue::diff_stats_path(&cache_path);
+        let stats = tokio::fs::read(&stats_path)
+            .await
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<crate::image_diff::DiffStats>(&bytes).ok());
+        if let Some(stats) = stats {
+            return Ok(Json(ApiResponse::success(stats)).into_response());
+        }
+        // A heatmap without its summary predates it; render both again.
+        let _ = tokio::fs::remove_file(&cache_path).await;
+    }
+
+    let fits_a = find_fits_file(&ctx, &image_a, &target_a, &file_a)?;
+    let fits_b = find_fits_file(&ctx, &image_b, &target_b, &file_b)?;
+    state.enqueue_preview(crate::server::preview_queue::GenJob {
+        fits_path: fits_a,
+        cache_path,
+        kind: crate::server::preview_queue::GenKind::Diff {
+            other: fits_b,
+            max_dimensions,
         },
     });
     Ok(generating_response())
 }
 
+/// Largest cell accepted by the contact-sheet endpoint, in pixels.
+const MAX_CONTACT_SHEET_CELL: u32 = 512;
+
+/// GET /api/db/{db_id}/targets/{target_id}/contact-sheet
+///
+/// The target's frames (optionally one filter), oldest first, tiled into one
+/// PNG of stretched thumbnails with grade borders. Queued like previews: a
+/// miss answers 202 while the sheet is generated one frame at a time. The
+/// cache key covers every frame's id, grade and whether its file was found,
+/// so regrading or new subs produce a fresh sheet.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/targets/{target_id}/contact-sheet",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path), ContactSheetQuery),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
+pub async fn get_target_contact_sheet(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, target_id)): Path<(String, i32)>,
+    Query(query): Query<ContactSheetQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    use crate::commands::contact_sheet::{
+        SheetFrame, SheetLayout, DEFAULT_CELL_SIZE, DEFAULT_COLUMNS,
+    };
+    use sha2::{Digest, Sha256};
+
+    let layout = SheetLayout {
+        columns: query.cols.unwrap_or(DEFAULT_COLUMNS),
+        cell_size: query.cell_size.unwrap_or(DEFAULT_CELL_SIZE),
+        grade_borders: query.grades.unwrap_or(true),
+    };
+    if !(1..=100).contains(&layout.columns) {
+        return Err(AppError::BadRequest(
+            "cols must be between 1 and 100".to_string(),
+        ));
+    }
+    if !(16..=MAX_CONTACT_SHEET_CELL).contains(&layout.cell_size) {
+        return Err(AppError::BadRequest(format!(
+            "cell_size must be between 16 and {}",
+            MAX_CONTACT_SHEET_CELL
+        )));
+    }
+
+    let (target_name, mut images) = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        let db = Database::new(&conn);
+        let target = db
+            .get_targets_by_ids(&[target_id])
+            .map_err(AppError::db)?
+            .into_iter()
+            .next()
+            .ok_or(AppError::NotFound)?;
+        let images: Vec<_> = db
+            .query_light_images_scoped(None, Some(target_id))
+            .map_err(AppError::db)?
+            .into_iter()
+            .map(|(image, _, _)| image)
+            .filter(|image| {
+                query
+                    .filter
+                    .as_deref()
+                    .is_none_or(|f| image.filter_name == f)
+            })
+            .collect();
+        (target.name, images)
+    };
+    if images.is_empty() {
+        return Err(AppError::NotFound);
+    }
+    images.sort_by_key(|image| (image.acquired_date, image.id));
+    layout
+        .check_size(images.len())
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let frames: Vec<SheetFrame> = images
+        .iter()
+        .map(|image| SheetFrame {
+            path: filename_from_metadata(&image.metadata)
+                .and_then(|name| find_fits_file(&ctx, image, &target_name, &name).ok()),
+            grading_status: image.grading_status,
+        })
+        .collect();
+    let Some(first_path) = frames.iter().find_map(|frame| frame.path.clone()) else {
+        return Err(AppError::NotFound);
+    };
+
+    let mut hasher = Sha256::new();
+    for (image, frame) in images.iter().zip(&frames) {
+        hasher.update(image.id.to_le_bytes());
+        hasher.update(image.grading_status.to_le_bytes());
+        hasher.update([u8::from(frame.path.is_some())]);
+    }
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let cache_key = format!(
+        "target{}_{}_{}x{}{}_{}",
+        target_id,
+        query
+            .filter
+            .as_deref()
+            .unwrap_or("all")
+            .replace(&['.', ' ', '-', '/', '\\'][..], "_"),
+        layout.columns,
+        layout.cell_size,
+        if layout.grade_borders { "_graded" } else { "" },
+        digest
+    );
+    let cache_path = artifact_cache_path(&ctx, "contact_sheets", &cache_key)?;
+
+    if cache_path.exists() {
+        return serve_cached_png(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+        )
+        .await;
+    }
+
+    state.enqueue_preview(crate::server::preview_queue::GenJob {
+        fits_path: first_path,
+        cache_path,
+        kind: crate::server::preview_queue::GenKind::ContactSheet { frames, layout },
+    });
+    Ok(generating_response())
+}
+
 // Helper function to find FITS file
 pub fn find_fits_file(
     ctx: &DatabaseContext,
@@ -2441,6 +4780,12 @@ pub fn find_fits_file(
         ctx.image_dirs
     );
 
+    // Recently searched for and not found: answer without re-probing.
+    if ctx.missing_files.is_missing(image.id) {
+        tracing::debug!("🚫 Image {} is cached as missing", image.id);
+        return Err(AppError::NotFound);
+    }
+
     // Extract date from acquired_date
     let acquired_date = image
         .acquired_date
@@ -2534,23 +4879,43 @@ pub fn find_fits_file(
         );
     }
 
+    if crate::server::database_context::case_insensitive_lookup()
+        && let Some(path) = directory_tree.find_file_first_ignore_case(filename)
+        && path.exists()
+    {
+        tracing::info!(
+            "✅ Found file ignoring case via directory tree cache in {:?}: {:?}",
+            search_start.elapsed(),
+            path
+        );
+        return Ok(path.clone());
+    }
+
     tracing::warn!(
         "❌ File not found in directory tree cache after {:?} for image {} ({})",
         search_start.elapsed(),
         image.id,
         filename
     );
+    ctx.missing_files.mark_missing(image.id);
     Err(AppError::NotFound)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/stars",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), StarQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<StarDetectionResponse>))
+)]
 #[axum::debug_handler(state = Arc<AppState>)]
 pub async fn get_image_stars(
+    State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(query): Query<StarQuery>,
 ) -> Result<Json<ApiResponse<StarDetectionResponse>>, AppError> {
-    use crate::hocus_focus_star_detection::{detect_stars_hocus_focus, HocusFocusParams};
-    use crate::image_analysis::FitsImage;
-    use crate::psf_fitting::PSFType;
+    use crate::hocus_focus_star_detection::detect_stars_hocus_focus_binned;
     use crate::server::cache::CacheManager;
 
     // Get image metadata from database
@@ -2587,131 +4952,472 @@ pub async fn get_image_stars(
         (image, file_only, target_name)
     };
 
-    // Create comprehensive cache key for star detection results
-    let cache_key = format!(
-        "stars_{}_{}_{}_{}_{}",
-        image_id,
-        image.project_id,
-        image.target_id,
-        image.acquired_date.unwrap_or(0),
-        file_only.replace(&['.', ' ', '-'][..], "_")
-    );
-    let cache_manager = CacheManager::new(PathBuf::from(&ctx.cache_dir));
-    cache_manager
-        .ensure_category_dir("stars")
-        .map_err(|e| AppError::InternalError(format!("Failed to create cache directory: {}", e)))?;
-    let cache_path = cache_manager.get_cached_path("stars", &cache_key, "json");
+    // Create comprehensive cache key for star detection results
+    let cache_key = format!(
+        "stars_{}_{}_{}_{}_{}",
+        image_id,
+        image.project_id,
+        image.target_id,
+        image.acquired_date.unwrap_or(0),
+        file_only.replace(&['.', ' ', '-'][..], "_")
+    );
+    let bin = query.bin.unwrap_or(1);
+    if !matches!(bin, 1 | 2 | 4) {
+        return Err(AppError::BadRequest(format!(
+            "bin must be 1, 2 or 4, got {}",
+            bin
+        )));
+    }
+    let cache_key = if bin > 1 {
+        format!("{}_bin{}", cache_key, bin)
+    } else {
+        cache_key
+    };
+    let roi = parse_roi(query.roi.as_deref())?;
+    let crop_local = match query.coords.as_deref() {
+        None | Some("full") => false,
+        Some("crop") => true,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "coords must be full or crop, got {}",
+                other
+            )))
+        }
+    };
+    let (params, max_stars, params_suffix) = star_detection_params(&query)?;
+    // Stars are cached in crop-local coordinates and shifted on the way out.
+    let cache_key = cache_key + &roi_cache_suffix(roi.as_ref()) + &params_suffix;
+    let cache_manager = CacheManager::new(PathBuf::from(&ctx.cache_dir));
+    cache_manager
+        .ensure_category_dir("stars")
+        .map_err(|e| AppError::InternalError(format!("Failed to create cache directory: {}", e)))?;
+    let cache_path = cache_manager.get_cached_path("stars", &cache_key, "json");
+
+    // The threshold is applied on the way out, so changing it doesn't
+    // invalidate cached detections.
+    let min_stars = state.min_stars();
+
+    // Check if cached version exists
+    if let Some(mut response) = read_cached_stars(&cache_manager, &cache_path).await? {
+        response.low_star_warning = min_stars.is_some_and(|min| response.detected_stars < min);
+        return Ok(Json(ApiResponse::success(to_frame_coordinates(
+            response, roi, crop_local,
+        ))));
+    }
+    // Identical concurrent misses detect once; the rest read its result.
+    let _flight = state.generation_flights.lock(&cache_path).await;
+    if let Some(mut response) = read_cached_stars(&cache_manager, &cache_path).await? {
+        response.low_star_warning = min_stars.is_some_and(|min| response.detected_stars < min);
+        return Ok(Json(ApiResponse::success(to_frame_coordinates(
+            response, roi, crop_local,
+        ))));
+    }
+
+    // Find FITS file path first (this is fast)
+    let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    if let Some(roi) = &roi {
+        check_fits_source(&fits_path, Some(roi)).await?;
+    }
+
+    // A default full-frame detection is what analysis would have wanted from
+    // N.I.N.A.; keep its metrics for frames whose metadata lacks them.
+    let record_metrics =
+        (roi.is_none() && bin == 1 && params_suffix.is_empty() && !state.is_read_only())
+            .then(|| ctx.db());
+
+    // Move expensive operations to spawn_blocking. The cache is written there
+    // too, so a request that times out still leaves the result for its retry.
+    let response = tokio::task::spawn_blocking(move || {
+        // Load FITS file, retrying transient storage errors
+        let fits = crate::fits_read::load_with_retry(&fits_path)
+            .map_err(|e| AppError::fits_read(&fits_path, e))?;
+        let fits = match &roi {
+            Some(roi) => fits
+                .cropped(roi)
+                .map_err(|e| AppError::InternalError(format!("Failed to detect stars: {}", e)))?,
+            None => fits,
+        };
+
+        // Run star detection
+        let detection_result = detect_stars_hocus_focus_binned(&fits, bin, &params);
+        if let Some(conn) = record_metrics {
+            record_computed_metrics(&conn, image_id, &fits_path, &detection_result);
+        }
+
+        // Convert to API response format, brightest first when capped
+        let mut detected: Vec<_> = detection_result.stars.iter().collect();
+        if let Some(max_stars) = max_stars {
+            detected.sort_by(|a, b| b.brightness.total_cmp(&a.brightness));
+            detected.truncate(max_stars);
+        }
+        let stars: Vec<StarInfo> = detected
+            .into_iter()
+            .map(|star| {
+                let eccentricity = if let Some(psf) = &star.psf_model {
+                    psf.eccentricity
+                } else {
+                    0.0
+                };
+
+                StarInfo {
+                    x: star.position.0,
+                    y: star.position.1,
+                    hfr: star.hfr,
+                    fwhm: star.fwhm,
+                    brightness: star.brightness,
+                    eccentricity,
+                }
+            })
+            .collect();
+
+        let response = StarDetectionResponse {
+            detected_stars: detection_result.stars.len(),
+            average_hfr: detection_result.average_hfr,
+            average_fwhm: detection_result.average_fwhm,
+            stars,
+            low_star_warning: min_stars.is_some_and(|min| detection_result.too_few_stars(min)),
+        };
+
+        // Save to cache
+        let cached_data = serde_json::to_string(&response)
+            .map_err(|_| AppError::InternalError("Failed to serialize response".to_string()))?;
+        std::fs::write(&cache_path, cached_data)
+            .map_err(|_| AppError::InternalError("Failed to write cache".to_string()))?;
+
+        Ok::<StarDetectionResponse, AppError>(response)
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Star detection task panicked: {}", e)))??;
+
+    Ok(Json(ApiResponse::success(to_frame_coordinates(
+        response, roi, crop_local,
+    ))))
+}
+
+/// Keep a fresh detection's frame metrics in `psf_guard_computed_metrics`.
+/// Best effort: a failure only costs a later re-detection.
+fn record_computed_metrics(
+    conn: &std::sync::Mutex<rusqlite::Connection>,
+    image_id: i32,
+    fits_path: &std::path::Path,
+    detection: &crate::hocus_focus_star_detection::HocusFocusDetectionResult,
+) {
+    use crate::computed_metrics::{file_mtime, record, ComputedMetrics, FrameMetrics};
+
+    let Some(mtime) = file_mtime(fits_path) else {
+        return;
+    };
+    let frame = FrameMetrics::from_detection(detection);
+    let metrics = ComputedMetrics::from_frame(image_id, fits_path, mtime, &frame);
+    let result = conn
+        .lock()
+        .map_err(|e| anyhow::anyhow!("database lock poisoned: {e}"))
+        .and_then(|conn| record(&conn, &metrics));
+    if let Err(e) = result {
+        tracing::debug!("Not caching metrics for image {}: {}", image_id, e);
+    }
+}
 
-    // Check if cached version exists
-    if cache_manager.is_cached(&cache_path) {
-        // Read from cache
-        let cached_data = tokio::fs::read_to_string(&cache_path)
-            .await
-            .map_err(|_| AppError::InternalError("Failed to read cache".to_string()))?;
+/// The cached star detection at `cache_path`, if there is one.
+async fn read_cached_stars(
+    cache_manager: &crate::server::cache::CacheManager,
+    cache_path: &std::path::Path,
+) -> Result<Option<StarDetectionResponse>, AppError> {
+    if !cache_manager.is_cached(cache_path) {
+        return Ok(None);
+    }
+    let cached_data = tokio::fs::read_to_string(cache_path)
+        .await
+        .map_err(|_| AppError::InternalError("Failed to read cache".to_string()))?;
+    serde_json::from_str(&cached_data)
+        .map(Some)
+        .map_err(|_| AppError::InternalError("Invalid cached data".to_string()))
+}
 
-        let response: StarDetectionResponse = serde_json::from_str(&cached_data)
-            .map_err(|_| AppError::InternalError("Invalid cached data".to_string()))?;
+/// Largest region edge `/pixels` returns, in pixels.
+const MAX_PIXEL_PEEK: usize = 64;
 
-        return Ok(Json(ApiResponse::success(response)));
+/// GET /api/db/{db_id}/images/{image_id}/pixels?x=&y=&w=&h=
+///
+/// Raw values of a small region (at most 64x64) in physical ADU, with the
+/// region's min / max / mean, for checking hot pixels and saturation. Not
+/// cached: the frame is read for each request.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/pixels",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), PixelQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<PixelRegionResponse>))
+)]
+pub async fn get_image_pixels(
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(query): Query<PixelQuery>,
+) -> Result<Json<ApiResponse<PixelRegionResponse>>, AppError> {
+    if query.w == 0 || query.h == 0 || query.w > MAX_PIXEL_PEEK || query.h > MAX_PIXEL_PEEK {
+        return Err(AppError::BadRequest(format!(
+            "w and h must be between 1 and {}",
+            MAX_PIXEL_PEEK
+        )));
     }
+    let roi = crate::image_analysis::Roi {
+        x: query.x,
+        y: query.y,
+        width: query.w,
+        height: query.h,
+    };
 
-    // Find FITS file path first (this is fast)
+    let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
     let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    let fits = tokio::task::spawn_blocking(move || {
+        crate::fits_read::load_with_retry(&fits_path)
+            .map_err(|e| AppError::fits_read(&fits_path, e))
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("FITS read panicked: {}", e)))??;
+    Ok(Json(ApiResponse::success(pixel_region(&fits, &roi)?)))
+}
 
-    // Move expensive operations to spawn_blocking
-    let fits_path_str = fits_path.to_string_lossy().to_string();
-    let (stars, detected_count, average_hfr, average_fwhm) =
-        tokio::task::spawn_blocking(move || {
-            // Load FITS file
-            let fits = FitsImage::from_file(std::path::Path::new(&fits_path_str))?;
+/// Read `roi` out of `fits` in ADU; 400 when it isn't inside the frame.
+fn pixel_region(
+    fits: &crate::image_analysis::FitsImage,
+    roi: &crate::image_analysis::Roi,
+) -> Result<PixelRegionResponse, AppError> {
+    let crop = fits
+        .cropped(roi)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let values: Vec<Vec<f64>> = crop
+        .data
+        .chunks(crop.width)
+        .map(|row| {
+            row.iter()
+                .map(|&v| fits.stored_to_adu(f64::from(v)))
+                .collect()
+        })
+        .collect();
+    let all = || values.iter().flatten().copied();
+    Ok(PixelRegionResponse {
+        x: roi.x,
+        y: roi.y,
+        width: roi.width,
+        height: roi.height,
+        min: all().fold(f64::INFINITY, f64::min),
+        max: all().fold(f64::NEG_INFINITY, f64::max),
+        mean: all().sum::<f64>() / (roi.width * roi.height) as f64,
+        values,
+    })
+}
 
-            // Run star detection
-            let params = HocusFocusParams {
-                psf_type: PSFType::Moffat4,
-                ..Default::default()
-            };
+#[cfg(test)]
+mod pixel_region_tests {
+    use super::*;
 
-            let detection_result =
-                detect_stars_hocus_focus(&fits.data, fits.width, fits.height, &params);
+    #[test]
+    fn pixel_region_reads_adu_and_rejects_out_of_bounds() {
+        let fits = crate::image_analysis::FitsImage {
+            width: 4,
+            height: 3,
+            data: (0..12).collect(),
+            raw_min: 100.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: Default::default(),
+        };
+        let roi = |x, y, width, height| crate::image_analysis::Roi {
+            x,
+            y,
+            width,
+            height,
+        };
 
-            // Convert to API response format
-            let stars: Vec<StarInfo> = detection_result
-                .stars
-                .iter()
-                .map(|star| {
-                    let eccentricity = if let Some(psf) = &star.psf_model {
-                        psf.eccentricity
-                    } else {
-                        0.0
-                    };
+        let region = pixel_region(&fits, &roi(1, 1, 2, 2)).unwrap();
+        assert_eq!(region.values, vec![vec![105.0, 106.0], vec![109.0, 110.0]]);
+        assert_eq!((region.min, region.max, region.mean), (105.0, 110.0, 107.5));
 
-                    StarInfo {
-                        x: star.position.0,
-                        y: star.position.1,
-                        hfr: star.hfr,
-                        fwhm: star.fwhm,
-                        brightness: star.brightness,
-                        eccentricity,
-                    }
-                })
-                .collect();
+        assert!(matches!(
+            pixel_region(&fits, &roi(3, 0, 2, 1)),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            pixel_region(&fits, &roi(0, 2, 1, 2)),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
 
-            Ok::<(Vec<StarInfo>, usize, f64, f64), anyhow::Error>((
-                stars,
-                detection_result.stars.len(),
-                detection_result.average_hfr,
-                detection_result.average_fwhm,
-            ))
-        })
-        .await
-        .map_err(|e| AppError::InternalError(format!("Star detection task panicked: {}", e)))?
-        .map_err(|e| AppError::InternalError(format!("Failed to detect stars: {}", e)))?;
+/// Upper bound for `?max_stars=`.
+const MAX_STARS_LIMIT: usize = 100_000;
 
-    let response = StarDetectionResponse {
-        detected_stars: detected_count,
-        average_hfr,
-        average_fwhm,
-        stars,
-    };
+/// Detector settings from the `/stars` query, the brightest-N cap, and a
+/// cache-key suffix naming them. With none of them given the suffix is
+/// empty, so default results keep their existing cache entries.
+fn star_detection_params(
+    query: &StarQuery,
+) -> Result<
+    (
+        crate::hocus_focus_star_detection::HocusFocusParams,
+        Option<usize>,
+        String,
+    ),
+    AppError,
+> {
+    use crate::hocus_focus_star_detection::{HfrMethod, HocusFocusParams};
+    use crate::psf_fitting::PSFType;
 
-    // Save to cache
-    let cached_data = serde_json::to_string(&response)
-        .map_err(|_| AppError::InternalError("Failed to serialize response".to_string()))?;
+    let mut params = HocusFocusParams {
+        psf_type: PSFType::Moffat4,
+        ..Default::default()
+    };
+    if let Some(threshold) = query.threshold {
+        if !(threshold.is_finite() && threshold > 0.0 && threshold <= 1000.0) {
+            return Err(AppError::BadRequest(
+                "threshold must be greater than 0 and at most 1000".to_string(),
+            ));
+        }
+        params.sensitivity = threshold;
+    }
+    if let Some(min_size) = query.min_size {
+        if min_size == 0 || min_size >= params.max_star_size {
+            return Err(AppError::BadRequest(format!(
+                "min_size must be between 1 and {}",
+                params.max_star_size - 1
+            )));
+        }
+        params.min_star_size = min_size;
+    }
+    if let Some(max_stars) = query.max_stars
+        && !(1..=MAX_STARS_LIMIT).contains(&max_stars)
+    {
+        return Err(AppError::BadRequest(format!(
+            "max_stars must be between 1 and {}",
+            MAX_STARS_LIMIT
+        )));
+    }
+    if let Some(psf_type) = &query.psf_type {
+        params.psf_type = psf_type.parse().map_err(AppError::BadRequest)?;
+    }
+    if let Some(hfr_method) = &query.hfr_method {
+        params.hfr_method = hfr_method.parse().map_err(AppError::BadRequest)?;
+    }
 
-    tokio::fs::write(&cache_path, cached_data)
-        .await
-        .map_err(|_| AppError::InternalError("Failed to write cache".to_string()))?;
+    let tuned = query.threshold.is_some()
+        || query.min_size.is_some()
+        || query.max_stars.is_some()
+        || query.psf_type.is_some();
+    let mut suffix = if tuned {
+        format!(
+            "_t{}_min{}_max{}_{}",
+            (params.sensitivity * 1000.0).round() as i64,
+            params.min_star_size,
+            query.max_stars.unwrap_or(0),
+            format!("{:?}", params.psf_type).to_lowercase()
+        )
+    } else {
+        String::new()
+    };
+    if params.hfr_method == HfrMethod::Nina {
+        suffix.push_str("_hfrnina");
+    }
+    Ok((params, query.max_stars, suffix))
+}
 
-    Ok(Json(ApiResponse::success(response)))
+/// Shift crop-local star positions into full-frame coordinates, unless the
+/// caller asked for crop-local ones (or there's no crop).
+fn to_frame_coordinates(
+    mut response: StarDetectionResponse,
+    roi: Option<crate::image_analysis::Roi>,
+    crop_local: bool,
+) -> StarDetectionResponse {
+    if let Some(roi) = roi
+        && !crop_local
+    {
+        for star in &mut response.stars {
+            star.x += roi.x as f64;
+            star.y += roi.y as f64;
+        }
+    }
+    response
 }
 
 // Annotated (star-marked) image endpoint. Same async model as the preview:
-// cache hit → 200 PNG; miss → enqueue on the interactive queue and 202.
+// cache hit → 200 PNG (or SVG with `format=svg`); miss → enqueue on the
+// interactive queue and 202.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/annotated",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), PreviewOptions),
+    responses(
+        (
+            status = 200,
+            description = "Annotated image",
+            content((Binary = "image/png"), (String = "image/svg+xml"))
+        )
+    )
+)]
 #[axum::debug_handler(state = Arc<AppState>)]
 pub async fn get_annotated_image(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Path((_db_id, image_id)): Path<(String, i32)>,
     Query(options): Query<PreviewOptions>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     let size = options.size.as_deref().unwrap_or("screen");
-    let max_stars = options.max_stars.unwrap_or(1000) as usize;
+    let max_dimensions = requested_max_dimensions(&state, size)?;
+    let selection = parse_selection(
+        options.max_stars,
+        options.rank_by.as_deref(),
+        options.min_snr,
+        options.min_brightness,
+        options.min_eccentricity,
+    )?;
+    let svg = match options.fmt.as_deref() {
+        None | Some("png") => false,
+        Some("svg") => true,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "format must be png or svg, got '{}'",
+                other
+            )));
+        }
+    };
+
+    let markers = parse_markers(
+        options.marker.as_deref(),
+        options.marker_size.as_deref(),
+        options.marker_min,
+        options.marker_max,
+    )?;
 
     let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
-    let cache_key = annotated_cache_key(&image, &file_only, size, max_stars);
-    let cache_path = artifact_cache_path(&ctx, "annotated", &cache_key)?;
+    let cache_key = annotated_cache_key(&image, &file_only, size, &selection, &markers);
+    let cache_path = if svg {
+        artifact_cache_path_with_extension(&ctx, "annotated", &cache_key, "svg")?
+    } else {
+        artifact_cache_path(&ctx, "annotated", &cache_key)?
+    };
 
     if cache_path.exists() {
-        return serve_cached_png(&cache_path).await;
+        return serve_cached_file(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+            if svg { "image/svg+xml" } else { "image/png" },
+        )
+        .await;
     }
 
     let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    check_fits_source(&fits_path, None).await?;
     state.enqueue_preview(crate::server::preview_queue::GenJob {
         fits_path,
         cache_path,
         kind: crate::server::preview_queue::GenKind::Annotated {
-            max_stars,
-            size: size.to_string(),
+            selection,
+            max_dimensions,
+            svg,
+            markers,
         },
     });
     Ok(generating_response())
@@ -2719,7 +5425,7 @@ pub async fn get_annotated_image(
 
 /// One artifact whose readiness the frontend wants to know, sent in a batch so
 /// a grid of generating images produces a single poll instead of one per image.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct GenStatusItem {
     pub image_id: i32,
     /// "preview" (default) or "annotated".
@@ -2734,15 +5440,43 @@ pub struct GenStatusItem {
     #[serde(default)]
     pub shadow: Option<f64>,
     #[serde(default)]
+    pub logarithmic: Option<bool>,
+    #[serde(default)]
+    pub invert: Option<bool>,
+    /// Preview of a region, `x,y,w,h` in full-resolution pixels.
+    #[serde(default)]
+    pub roi: Option<String>,
+    /// "subtract" or "show"; see the preview endpoint's `background`.
+    #[serde(default)]
+    pub background: Option<String>,
+    #[serde(default)]
     pub max_stars: Option<u32>,
+    /// Annotation markers; see the annotated endpoint's query.
+    #[serde(default)]
+    pub marker: Option<String>,
+    #[serde(default)]
+    pub marker_size: Option<String>,
+    #[serde(default)]
+    pub marker_min: Option<f64>,
+    #[serde(default)]
+    pub marker_max: Option<f64>,
+    /// Annotated-star ranking and thresholds; see the annotated endpoint.
+    #[serde(default)]
+    pub rank_by: Option<String>,
+    #[serde(default)]
+    pub min_snr: Option<f64>,
+    #[serde(default)]
+    pub min_brightness: Option<f64>,
+    #[serde(default)]
+    pub min_eccentricity: Option<f64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct GenerationStatusRequest {
     pub requests: Vec<GenStatusItem>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct GenerationStatusBatch {
     /// Parallel to the request's `requests`.
     pub statuses: Vec<crate::server::preview_queue::GenerationStatus>,
@@ -2754,6 +5488,14 @@ pub struct GenerationStatusBatch {
 /// item: cached → `ready`; generating → `generating`; failed → `error`;
 /// unknown → enqueue (idempotent) and report `generating`. Coalesces a whole
 /// grid's polling into one request instead of one-per-image.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/images/generation-status",
+    tag = "images",
+    params(("db_id" = String, Path)),
+    request_body = GenerationStatusRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<GenerationStatusBatch>))
+)]
 pub async fn post_generation_status(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -2822,16 +5564,40 @@ fn status_for_item(
     };
 
     let size = item.size.clone().unwrap_or_else(|| "screen".to_string());
+    let max_dimensions = match state.pregeneration_config.preview_sizes.resolve(&size) {
+        Ok(max_dimensions) => max_dimensions,
+        Err(msg) => return err(&msg),
+    };
     let (cache_path, kind) = match item.kind.as_deref() {
         Some("annotated") => {
-            let max_stars = item.max_stars.unwrap_or(1000) as usize;
-            let key = annotated_cache_key(image, &file_only, &size, max_stars);
+            let selection = match parse_selection(
+                item.max_stars,
+                item.rank_by.as_deref(),
+                item.min_snr,
+                item.min_brightness,
+                item.min_eccentricity,
+            ) {
+                Ok(selection) => selection,
+                Err(_) => return err("invalid star selection"),
+            };
+            let markers = match parse_markers(
+                item.marker.as_deref(),
+                item.marker_size.as_deref(),
+                item.marker_min,
+                item.marker_max,
+            ) {
+                Ok(markers) => markers,
+                Err(_) => return err("invalid marker"),
+            };
+            let key = annotated_cache_key(image, &file_only, &size, &selection, &markers);
             match artifact_cache_path(ctx, "annotated", &key) {
                 Ok(p) => (
                     p,
                     GenKind::Annotated {
-                        max_stars,
-                        size: size.clone(),
+                        selection,
+                        max_dimensions,
+                        svg: false,
+                        markers,
                     },
                 ),
                 Err(_) => return err("cache error"),
@@ -2841,16 +5607,38 @@ fn status_for_item(
             let stretch = item.stretch.unwrap_or(true);
             let midtone = item.midtone.unwrap_or(0.2);
             let shadow = item.shadow.unwrap_or(-2.8);
-            let key = preview_cache_key(image, &file_only, &size, stretch, midtone, shadow);
+            let logarithmic = item.logarithmic.unwrap_or(false);
+            let invert = item.invert.unwrap_or(false);
+            let roi = match parse_roi(item.roi.as_deref()) {
+                Ok(roi) => roi,
+                Err(_) => return err("invalid roi"),
+            };
+            let background = match parse_background(item.background.as_deref()) {
+                Ok(background) => background,
+                Err(_) => return err("invalid background"),
+            };
+            let key = preview_cache_key(
+                image,
+                &file_only,
+                &size,
+                stretch,
+                midtone,
+                shadow,
+                logarithmic,
+                invert,
+            ) + &roi_cache_suffix(roi.as_ref())
+                + &background_cache_suffix(background);
             match artifact_cache_path(ctx, "previews", &key) {
                 Ok(p) => (
                     p,
                     GenKind::Preview {
                         midtone,
                         shadow,
-                        max_dimensions: crate::server::preview_queue::max_dimensions_for_size(
-                            &size,
-                        ),
+                        logarithmic,
+                        invert,
+                        max_dimensions,
+                        roi,
+                        background,
                     },
                 ),
                 Err(_) => return err("cache error"),
@@ -2880,21 +5668,35 @@ fn status_for_item(
 }
 
 // PSF multi image parameters
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PsfMultiOptions {
     pub num_stars: Option<usize>,
     pub psf_type: Option<String>,
     pub sort_by: Option<String>,
     pub grid_cols: Option<usize>,
     pub selection: Option<String>,
+    /// Per-cell metric labels (default true).
+    pub labels: Option<bool>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/psf",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), PsfMultiOptions),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
 #[axum::debug_handler(state = Arc<AppState>)]
 pub async fn get_psf_visualization(
+    State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Path((_db_id, image_id)): Path<(String, i32)>,
     Query(options): Query<PsfMultiOptions>,
-) -> Result<impl IntoResponse, AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     use crate::commands::visualize_psf_multi_common::create_psf_multi_image;
     use crate::image_analysis::FitsImage;
     use crate::psf_fitting::PSFType;
@@ -2942,12 +5744,13 @@ pub async fn get_psf_visualization(
     let sort_by = options.sort_by.as_deref().unwrap_or("r2").to_string();
     let selection = options.selection.as_deref().unwrap_or("top-n").to_string();
     let grid_cols = options.grid_cols;
+    let show_labels = options.labels.unwrap_or(true);
 
     let psf_type: PSFType = psf_type_str.parse().unwrap_or(PSFType::Moffat4);
 
     // Create comprehensive cache key for PSF multi image
     let cache_key = format!(
-        "psf_multi_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}",
+        "psf_multi_{}_{}_{}_{}_{}_{}_{}_{}_{}_{}{}",
         image_id,
         image.project_id,
         image.target_id,
@@ -2957,7 +5760,8 @@ pub async fn get_psf_visualization(
         psf_type_str,
         sort_by,
         selection,
-        grid_cols.unwrap_or(0)
+        grid_cols.unwrap_or(0),
+        if show_labels { "" } else { "_nolabels" }
     );
     let cache_manager = CacheManager::new(PathBuf::from(&ctx.cache_dir));
     cache_manager
@@ -2967,80 +5771,384 @@ pub async fn get_psf_visualization(
 
     // Check if cached version exists
     if cache_manager.is_cached(&cache_path) {
-        // Serve from cache
-        let mut file = File::open(&cache_path)
+        return serve_cached_png(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+        )
+        .await;
+    }
+
+    // Find FITS file path first (this is fast)
+    let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+
+    // Identical concurrent misses render once; the others wait on the key.
+    // Then share the generation pool with queued previews, so a gallery's
+    // worth of misses waits its turn; either wait may end with the image
+    // already rendered.
+    let _flight = state.generation_flights.lock(&cache_path).await;
+    let _guard = state.begin_interactive_job();
+    let _permit = state
+        .preview_queue
+        .permit(&state.worker_policy(), &fits_path)
+        .await;
+    if cache_manager.is_cached(&cache_path) {
+        return serve_cached_png(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+        )
+        .await;
+    }
+
+    // Move expensive operations to spawn_blocking
+    let fits_path_str = fits_path.to_string_lossy().to_string();
+    let cache_path_clone = cache_path.clone();
+    tokio::task::spawn_blocking(move || {
+        // Load FITS file
+        let fits = FitsImage::from_file(std::path::Path::new(&fits_path_str))
+            .map_err(|e| anyhow::anyhow!("Failed to load FITS: {}", e))?;
+
+        // Create PSF multi visualization using the common function
+        let rgba_image = create_psf_multi_image(
+            &fits,
+            num_stars,
+            psf_type,
+            &sort_by,
+            grid_cols,
+            &selection,
+            show_labels,
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to create PSF visualization: {}", e))?;
+
+        // Save to cache
+        let cache_file = std::fs::File::create(&cache_path_clone)
+            .map_err(|e| anyhow::anyhow!("Failed to create cache file: {}", e))?;
+        let writer = std::io::BufWriter::new(cache_file);
+        let encoder =
+            PngEncoder::new_with_quality(writer, CompressionType::Best, FilterType::Adaptive);
+
+        encoder
+            .write_image(
+                &rgba_image,
+                rgba_image.width(),
+                rgba_image.height(),
+                ColorType::Rgba8.into(),
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to encode PNG: {}", e))?;
+
+        Ok::<(), anyhow::Error>(())
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("PSF visualization task panicked: {}", e)))?
+    .map_err(|e| AppError::InternalError(format!("Failed to generate PSF visualization: {}", e)))?;
+
+    serve_cached_png(
+        &headers,
+        &cache_path,
+        state.pregeneration_config.http_max_age,
+    )
+    .await
+}
+
+/// GET /api/db/{db_id}/images/{image_id}/psf/data
+///
+/// The fit numbers behind `get_psf_visualization`, for the same stars (same
+/// query parameters and selection), so clients can draw their own views.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/psf/data",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), PsfMultiOptions),
+    responses((status = 200, description = "OK", body = ApiResponse<PsfDataResponse>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_psf_data(
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(options): Query<PsfMultiOptions>,
+) -> Result<Json<ApiResponse<PsfDataResponse>>, AppError> {
+    use crate::commands::visualize_psf_multi_common::select_psf_stars;
+    use crate::image_analysis::FitsImage;
+    use crate::psf_fitting::PSFType;
+    use crate::server::cache::CacheManager;
+
+    let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
+
+    let num_stars = options.num_stars.unwrap_or(9);
+    let psf_type_str = options.psf_type.as_deref().unwrap_or("moffat").to_string();
+    let sort_by = options.sort_by.as_deref().unwrap_or("r2").to_string();
+    let selection = options.selection.as_deref().unwrap_or("top-n").to_string();
+    let psf_type: PSFType = psf_type_str.parse().unwrap_or(PSFType::Moffat4);
+
+    // Keyed like the visualization, minus the layout-only grid_cols
+    let cache_key = format!(
+        "psf_data_{}_{}_{}_{}_{}_{}_{}_{}_{}",
+        image_id,
+        image.project_id,
+        image.target_id,
+        image.acquired_date.unwrap_or(0),
+        file_only.replace(&['.', ' ', '-'][..], "_"),
+        num_stars,
+        psf_type_str,
+        sort_by,
+        selection,
+    );
+    let cache_manager = CacheManager::new(PathBuf::from(&ctx.cache_dir));
+    cache_manager
+        .ensure_category_dir("psf_data")
+        .map_err(|e| AppError::InternalError(format!("Failed to create cache directory: {}", e)))?;
+    let cache_path = cache_manager.get_cached_path("psf_data", &cache_key, "json");
+
+    if cache_manager.is_cached(&cache_path) {
+        let cached_data = tokio::fs::read_to_string(&cache_path)
             .await
             .map_err(|_| AppError::InternalError("Failed to read cache".to_string()))?;
+        let response: PsfDataResponse = serde_json::from_str(&cached_data)
+            .map_err(|_| AppError::InternalError("Invalid cached data".to_string()))?;
+        return Ok(Json(ApiResponse::success(response)));
+    }
 
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .await
-            .map_err(|_| AppError::InternalError("Failed to read file".to_string()))?;
+    let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    let stars = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<PsfStarData>> {
+        let fits = FitsImage::from_file(&fits_path)?;
+        let stars = select_psf_stars(&fits, num_stars, psf_type, &sort_by, &selection)?;
+        Ok(stars
+            .iter()
+            .flatten()
+            .filter_map(|star| {
+                let psf = star.psf_model.as_ref()?;
+                let (fwhm_x, fwhm_y) = psf.axis_fwhm();
+                Some(PsfStarData {
+                    x: star.position.0 + psf.x0,
+                    y: star.position.1 + psf.y0,
+                    amplitude: psf.amplitude,
+                    background: psf.background,
+                    fwhm_x,
+                    fwhm_y,
+                    theta: psf.theta,
+                    beta: psf.beta(),
+                    r2: psf.r_squared,
+                    eccentricity: psf.eccentricity,
+                    hfr: star.hfr,
+                })
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("PSF fitting task panicked: {}", e)))?
+    .map_err(|e| AppError::InternalError(format!("Failed to fit PSFs: {}", e)))?;
+
+    let response = PsfDataResponse {
+        psf_type: psf_type_str,
+        stars,
+    };
+    let cached_data = serde_json::to_string(&response)
+        .map_err(|_| AppError::InternalError("Failed to serialize response".to_string()))?;
+    tokio::fs::write(&cache_path, cached_data)
+        .await
+        .map_err(|_| AppError::InternalError("Failed to write cache".to_string()))?;
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BackgroundOptions {
+    /// Wavelet levels treated as detail (default 4).
+    pub levels: Option<usize>,
+    /// "flattened" (default) or "background".
+    pub output: Option<String>,
+}
+
+/// GET /api/db/{db_id}/images/{image_id}/background
+///
+/// Wavelet background extraction preview: either the smooth background model
+/// or the frame with it subtracted, auto-stretched to PNG.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/background",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), BackgroundOptions),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
+pub async fn get_background_extraction(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(options): Query<BackgroundOptions>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    use crate::commands::background_extract::{
+        encode_stretched_png, extract_background, DEFAULT_LEVELS,
+    };
+    use crate::image_analysis::FitsImage;
+
+    let levels = options.levels.unwrap_or(DEFAULT_LEVELS);
+    if !(1..=8).contains(&levels) {
+        return Err(AppError::BadRequest("levels must be 1-8".to_string()));
+    }
+    let want_background = match options.output.as_deref().unwrap_or("flattened") {
+        "flattened" => false,
+        "background" => true,
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "unknown output '{}': use flattened or background",
+                other
+            )))
+        }
+    };
 
-        return Ok((
-            StatusCode::OK,
-            [
-                (CONTENT_TYPE, "image/png"),
-                (CACHE_CONTROL, "max-age=86400"), // TODO: Use configurable cache expiry
-            ],
-            buffer,
-        ));
+    let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
+    let cache_key = format!(
+        "background_{}_{}_{}_{}_{}",
+        image_id,
+        image.acquired_date.unwrap_or(0),
+        file_only.replace(&['.', ' ', '-'][..], "_"),
+        levels,
+        if want_background { "model" } else { "flat" }
+    );
+    let cache_path = artifact_cache_path(&ctx, "background", &cache_key)?;
+    if cache_path.exists() {
+        return serve_cached_png(
+            &headers,
+            &cache_path,
+            state.pregeneration_config.http_max_age,
+        )
+        .await;
     }
 
-    // Find FITS file path first (this is fast)
     let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
-
-    // Move expensive operations to spawn_blocking
-    let fits_path_str = fits_path.to_string_lossy().to_string();
-    let cache_path_clone = cache_path.clone();
+    let out_path = cache_path.clone();
     tokio::task::spawn_blocking(move || {
-        // Load FITS file
-        let fits = FitsImage::from_file(std::path::Path::new(&fits_path_str))
-            .map_err(|e| anyhow::anyhow!("Failed to load FITS: {}", e))?;
+        let fits = FitsImage::from_file(&fits_path)?;
+        let extraction = extract_background(&fits, levels);
+        let plane = if want_background {
+            &extraction.background
+        } else {
+            &extraction.flattened
+        };
+        let file = std::fs::File::create(&out_path)?;
+        encode_stretched_png(
+            plane,
+            extraction.width,
+            extraction.height,
+            std::io::BufWriter::new(file),
+        )
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Background task panicked: {}", e)))?
+    .map_err(|e| AppError::InternalError(format!("Failed to extract background: {}", e)))?;
 
-        // Create PSF multi visualization using the common function
-        let rgba_image =
-            create_psf_multi_image(&fits, num_stars, psf_type, &sort_by, grid_cols, &selection)
-                .map_err(|e| anyhow::anyhow!("Failed to create PSF visualization: {}", e))?;
+    serve_cached_png(
+        &headers,
+        &cache_path,
+        state.pregeneration_config.http_max_age,
+    )
+    .await
+}
 
-        // Save to cache
-        let cache_file = std::fs::File::create(&cache_path_clone)
-            .map_err(|e| anyhow::anyhow!("Failed to create cache file: {}", e))?;
-        let writer = std::io::BufWriter::new(cache_file);
-        let encoder =
-            PngEncoder::new_with_quality(writer, CompressionType::Best, FilterType::Adaptive);
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrailOptions {
+    /// Detection threshold in units of background noise (default 4.0).
+    pub sigma: Option<f64>,
+    /// "json" (default) for the detected trails, or "png" for the overlay.
+    pub format: Option<String>,
+}
 
-        encoder
-            .write_image(
-                &rgba_image,
-                rgba_image.width(),
-                rgba_image.height(),
-                ColorType::Rgba8.into(),
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to encode PNG: {}", e))?;
+/// GET /api/db/{db_id}/images/{image_id}/trails
+///
+/// Pixel-level satellite/airplane trail detection: the detected linear
+/// features as JSON, or a stretched overlay PNG with them drawn in red.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/images/{image_id}/trails",
+    tag = "images",
+    params(("db_id" = String, Path), ("image_id" = i32, Path), TrailOptions),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
+pub async fn get_image_trails(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, image_id)): Path<(String, i32)>,
+    Query(options): Query<TrailOptions>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    use crate::image_analysis::FitsImage;
+    use crate::trail_detection::{detect_trails, render_overlay, TrailConfig};
 
-        Ok::<(), anyhow::Error>(())
+    let config = TrailConfig {
+        sigma: options.sigma.unwrap_or(TrailConfig::default().sigma),
+        ..TrailConfig::default()
+    };
+    if !(1.0..=20.0).contains(&config.sigma) {
+        return Err(AppError::BadRequest("sigma must be 1-20".to_string()));
+    }
+    let want_overlay = match options.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "png" => true,
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "unknown format '{}': use json or png",
+                other
+            )))
+        }
+    };
+
+    let (image, file_only, target_name) = resolve_image_meta(&ctx, image_id)?;
+    let cache_path = if want_overlay {
+        let cache_key = format!(
+            "trails_{}_{}_{}_{}",
+            image_id,
+            image.acquired_date.unwrap_or(0),
+            file_only.replace(&['.', ' ', '-'][..], "_"),
+            config.sigma
+        );
+        let path = artifact_cache_path(&ctx, "trails", &cache_key)?;
+        if path.exists() {
+            return serve_cached_png(&headers, &path, state.pregeneration_config.http_max_age)
+                .await;
+        }
+        Some(path)
+    } else {
+        None
+    };
+
+    let fits_path = find_fits_file(&ctx, &image, &target_name, &file_only)?;
+    let out_path = cache_path.clone();
+    let detection = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let fits = FitsImage::from_file(&fits_path)?;
+        let detection = detect_trails(&fits, &config);
+        if let Some(path) = out_path {
+            render_overlay(&fits, &detection).save(&path)?;
+        }
+        Ok(detection)
     })
     .await
-    .map_err(|e| AppError::InternalError(format!("PSF visualization task panicked: {}", e)))?
-    .map_err(|e| AppError::InternalError(format!("Failed to generate PSF visualization: {}", e)))?;
-
-    // Read the cached file
-    let png_buffer = tokio::fs::read(&cache_path)
-        .await
-        .map_err(|_| AppError::InternalError("Failed to read generated PNG".to_string()))?;
+    .map_err(|e| AppError::InternalError(format!("Trail detection task panicked: {}", e)))?
+    .map_err(|e| AppError::InternalError(format!("Failed to detect trails: {}", e)))?;
 
-    Ok((
-        StatusCode::OK,
-        [
-            (CONTENT_TYPE, "image/png"),
-            (CACHE_CONTROL, "max-age=86400"), // Cache for 1 day
-        ],
-        png_buffer,
-    ))
+    match cache_path {
+        Some(path) => {
+            serve_cached_png(&headers, &path, state.pregeneration_config.http_max_age).await
+        }
+        None => Ok(Json(ApiResponse::success(detection)).into_response()),
+    }
 }
 
 // Overview API endpoints
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/overview",
+    tag = "projects",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<ProjectOverviewResponse>>))
+)]
 pub async fn get_projects_overview(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<Vec<ProjectOverviewResponse>>>, AppError> {
@@ -3061,9 +6169,12 @@ pub async fn get_projects_overview(
     let profile_count = db.get_profile_count().map_err(AppError::db)?;
 
     // Get file existence map
-    let file_existence_map: std::collections::HashMap<i32, bool> = {
+    let (file_existence_map, file_counts) = {
         let cache = ctx.file_check_cache.read().unwrap();
-        cache.projects_with_files.clone()
+        (
+            cache.projects_with_files.clone(),
+            cache.project_file_counts.clone(),
+        )
     };
     let mut recent_images_by_project: HashMap<i32, Vec<crate::models::RecentImageSummary>> =
         HashMap::new();
@@ -3102,15 +6213,18 @@ pub async fn get_projects_overview(
             .get_target_count_for_project(project.id)
             .map_err(AppError::db)?;
 
-        // Get basic file statistics (simplified for performance)
-        let files_found = if file_existence_map
-            .get(&project.id)
-            .copied()
-            .unwrap_or(false)
-        {
-            stats.total_images // Optimistic assumption: if we think project has files, assume all files exist
-        } else {
-            0
+        // Counted by the last file-cache refresh; before one has run, assume
+        // all files exist for a project known to have any
+        let files_found = match file_counts.get(&project.id) {
+            Some(counts) => (counts.found as i32).min(stats.total_images),
+            None if file_existence_map
+                .get(&project.id)
+                .copied()
+                .unwrap_or(false) =>
+            {
+                stats.total_images
+            }
+            None => 0,
         };
         let files_missing = stats.total_images - files_found;
 
@@ -3162,6 +6276,13 @@ pub async fn get_projects_overview(
     Ok(Json(ApiResponse::success(response)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/targets/overview",
+    tag = "targets",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<TargetOverviewResponse>>))
+)]
 pub async fn get_targets_overview(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<Vec<TargetOverviewResponse>>>, AppError> {
@@ -3177,9 +6298,12 @@ pub async fn get_targets_overview(
         .map_err(AppError::db)?;
 
     // Get file existence map
-    let file_existence_map: std::collections::HashMap<i32, bool> = {
+    let (file_existence_map, file_counts) = {
         let cache = ctx.file_check_cache.read().unwrap();
-        cache.targets_with_files.clone()
+        (
+            cache.targets_with_files.clone(),
+            cache.target_file_counts.clone(),
+        )
     };
 
     let mut response = Vec::new();
@@ -3218,15 +6342,18 @@ pub async fn get_targets_overview(
             _ => None,
         };
 
-        // Get basic file statistics (simplified for performance)
-        let files_found = if file_existence_map
-            .get(&target_data.target.id)
-            .copied()
-            .unwrap_or(false)
-        {
-            target_data.total_images // Optimistic assumption: if we think target has files, assume all files exist
-        } else {
-            0
+        // Counted by the last file-cache refresh; before one has run, assume
+        // all files exist for a target known to have any
+        let files_found = match file_counts.get(&target_data.target.id) {
+            Some(counts) => (counts.found as i32).min(target_data.total_images),
+            None if file_existence_map
+                .get(&target_data.target.id)
+                .copied()
+                .unwrap_or(false) =>
+            {
+                target_data.total_images
+            }
+            None => 0,
         };
         let files_missing = target_data.total_images - files_found;
 
@@ -3262,6 +6389,144 @@ pub async fn get_targets_overview(
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// GET /api/db/{db_id}/projects/{project_id}/export
+///
+/// The project's curation document (see `commands::curation`) as a JSON
+/// download.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/export",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    responses(
+        (status = 200, description = "Curation export", body = crate::commands::curation::CurationExport)
+    )
+)]
+pub async fn export_project_curation(
+    ctx: DbContext,
+    Path((_db_id, project_id)): Path<(String, i32)>,
+) -> Result<Response, AppError> {
+    let document = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        crate::commands::curation::export_project(&conn, project_id)
+            .map_err(AppError::db)?
+            .ok_or(AppError::NotFound)?
+    };
+    let filename = format!("{}-curation.json", sanitize_file_stem(&document.project));
+    let mut response = Json(document).into_response();
+    response.headers_mut().insert(
+        axum::http::header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"{}\"", filename)
+            .parse()
+            .map_err(|e| AppError::InternalError(format!("building response: {e}")))?,
+    );
+    Ok(response)
+}
+
+/// POST /api/db/{db_id}/projects/{project_id}/import?dry_run=true
+///
+/// Re-apply a curation document to the project's frames, matched by
+/// filename and timestamp.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/projects/{project_id}/import",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path), CurationImportQuery),
+    request_body = crate::commands::curation::CurationExport,
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::commands::curation::CurationImportSummary>)
+    )
+)]
+pub async fn import_project_curation(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Path((_db_id, project_id)): Path<(String, i32)>,
+    Query(params): Query<CurationImportQuery>,
+    Json(document): Json<crate::commands::curation::CurationExport>,
+) -> Result<Json<ApiResponse<crate::commands::curation::CurationImportSummary>>, AppError> {
+    require_writable(&state)?;
+    document
+        .validate()
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    if !Database::new(&conn)
+        .get_all_projects()
+        .map_err(AppError::db)?
+        .iter()
+        .any(|project| project.id == project_id)
+    {
+        return Err(AppError::NotFound);
+    }
+    let summary = crate::commands::curation::import_curation(
+        &conn,
+        &document,
+        Some(project_id),
+        params.dry_run.unwrap_or(false),
+    )
+    .map_err(AppError::db)?;
+
+    Ok(Json(ApiResponse::success(summary)))
+}
+
+/// GET /api/db/{db_id}/stats/rejections?project=&target=&by=night
+///
+/// Rejected frames grouped by reject reason (normalized, or folded into the
+/// `[reject_reasons]` categories), with counts and percentages; `by=night`
+/// adds a per-night breakdown in server local time.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stats/rejections",
+    tag = "stats",
+    params(("db_id" = String, Path), RejectionStatsQuery),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::reject_reasons::RejectionSummary>)
+    )
+)]
+pub async fn get_rejection_stats(
+    State(state): State<Arc<AppState>>,
+    ctx: DbContext,
+    Query(params): Query<RejectionStatsQuery>,
+) -> Result<Json<ApiResponse<crate::reject_reasons::RejectionSummary>>, AppError> {
+    let by_night = match params.by.as_deref() {
+        None => false,
+        Some("night") => true,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "Invalid by '{}': expected night",
+                other
+            )));
+        }
+    };
+
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    let images = db
+        .query_images(
+            Some(GradingStatus::Rejected),
+            params.project.as_deref(),
+            params.target.as_deref(),
+            None,
+        )
+        .map_err(AppError::db)?;
+
+    let summary = crate::reject_reasons::summarize_rejections(
+        images.iter().map(|(image, _, _)| image),
+        state.reason_mapper(),
+        by_night.then_some(&chrono::Local),
+    );
+    Ok(Json(ApiResponse::success(summary)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stats/overall",
+    tag = "stats",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<OverallStatsResponse>))
+)]
 pub async fn get_overall_stats(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<OverallStatsResponse>>, AppError> {
@@ -3282,221 +6547,628 @@ pub async fn get_overall_stats(
             total_accepted: 0,
         });
 
-    let span_days = match (stats.earliest_date, stats.latest_date) {
-        (Some(start), Some(end)) => {
-            let days = (end - start) / 86400;
-            Some(days as i32)
-        }
-        _ => None,
+    let span_days = match (stats.earliest_date, stats.latest_date) {
+        (Some(start), Some(end)) => {
+            let days = (end - start) / 86400;
+            Some(days as i32)
+        }
+        _ => None,
+    };
+
+    // Calculate overall file statistics (simplified for performance)
+    let total_files_found = if stats.active_projects > 0 {
+        stats.total_images
+    } else {
+        0
+    }; // Optimistic: assume all files exist for active projects
+    let total_files_missing = stats.total_images - total_files_found;
+
+    // For now, we'll return empty recent activity - this could be enhanced later
+    let recent_activity = Vec::new();
+
+    let response = OverallStatsResponse {
+        total_projects: stats.total_projects,
+        active_projects: stats.active_projects,
+        total_targets: stats.total_targets,
+        active_targets: stats.active_targets,
+        total_images: stats.total_images,
+        accepted_images: stats.accepted_images,
+        rejected_images: stats.rejected_images,
+        pending_images: stats.pending_images,
+        total_desired: desired_stats.total_desired,
+        files_found: total_files_found,
+        files_missing: total_files_missing,
+        unique_filters: stats.unique_filters,
+        date_range: DateRange {
+            earliest: stats.earliest_date,
+            latest: stats.latest_date,
+            span_days,
+        },
+        recent_activity,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+// Sequence analysis handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/targets/{target_id}/integration",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<TargetIntegrationResponse>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_target_integration(
+    ctx: DbContext,
+    Path((_db_id, target_id)): Path<(String, i32)>,
+) -> Result<Json<ApiResponse<TargetIntegrationResponse>>, AppError> {
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+
+    let target = db
+        .get_targets_by_ids(&[target_id])
+        .map_err(AppError::db)?
+        .into_iter()
+        .next()
+        .ok_or(AppError::NotFound)?;
+    let report = db.get_integration_time(target_id).map_err(AppError::db)?;
+
+    let filters: Vec<FilterIntegrationHours> = report
+        .filters
+        .iter()
+        .map(|filter| FilterIntegrationHours {
+            filter_name: filter.filter_name.clone(),
+            accepted_hours: filter.accepted_seconds / 3600.0,
+            total_hours: filter.total_seconds() / 3600.0,
+            accepted_images: filter.accepted_images,
+            total_images: filter.total_images(),
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(TargetIntegrationResponse {
+        target_id,
+        target_name: target.name,
+        accepted_hours: filters.iter().map(|f| f.accepted_hours).sum(),
+        total_hours: filters.iter().map(|f| f.total_hours).sum(),
+        filters,
+        skipped_images: report.skipped_images,
+    })))
+}
+
+/// When each sub of a target was exposed and the idle gaps between them,
+/// with on-sky time against elapsed time per session.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/targets/{target_id}/timeline",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path), TimelineQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<TargetTimelineResponse>))
+)]
+pub async fn get_target_timeline(
+    ctx: DbContext,
+    Path((_db_id, target_id)): Path<(String, i32)>,
+    Query(params): Query<TimelineQuery>,
+) -> Result<Json<ApiResponse<TargetTimelineResponse>>, AppError> {
+    let gap_threshold = params
+        .gap_threshold
+        .unwrap_or(crate::timeline::DEFAULT_GAP_THRESHOLD_SECS);
+    if !(gap_threshold.is_finite() && gap_threshold >= 0.0) {
+        return Err(AppError::BadRequest(
+            "gap_threshold must be a non-negative number of seconds".to_string(),
+        ));
+    }
+
+    let conn = ctx.db();
+    let conn = conn.lock().map_err(AppError::db)?;
+    let db = Database::new(&conn);
+    let target = db
+        .get_targets_by_ids(&[target_id])
+        .map_err(AppError::db)?
+        .into_iter()
+        .next()
+        .ok_or(AppError::NotFound)?;
+    let images: Vec<_> = db
+        .query_light_images_scoped(None, Some(target_id))
+        .map_err(AppError::db)?
+        .into_iter()
+        .map(|(image, _, _)| image)
+        .filter(|image| {
+            params
+                .filter
+                .as_ref()
+                .is_none_or(|f| image.filter_name == *f)
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(TargetTimelineResponse {
+        target_id,
+        target_name: target.name,
+        gap_threshold,
+        timeline: crate::timeline::build_timeline(&images, gap_threshold),
+    })))
+}
+
+/// Focus stability over one session of a target: the coefficient of
+/// variation of HFR across its accepted frames, classified as stable,
+/// drifting or erratic. Sessions are split over every frame of the target
+/// (in the filter, when given) exactly as `/sessions` does.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/targets/{target_id}/focus-stability",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path), FocusStabilityQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<FocusStabilityResponse>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_focus_stability(
+    ctx: DbContext,
+    Path((_db_id, target_id)): Path<(String, i32)>,
+    Query(params): Query<FocusStabilityQuery>,
+) -> Result<Json<ApiResponse<FocusStabilityResponse>>, AppError> {
+    use crate::focus_stability::{focus_stability, split_into_sessions, FocusFrame};
+    use crate::sequence_analysis::extract_metrics_from_metadata;
+
+    let gap_minutes = session_gap_minutes(params.session_gap_minutes)?;
+    let gap_seconds = crate::sequence_analysis::session_gap_seconds(gap_minutes)
+        .expect("session_gap_minutes rejects gaps that overflow");
+
+    let (target, images, computed) = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        let db = Database::new(&conn);
+        let target = db
+            .get_targets_by_ids(&[target_id])
+            .map_err(AppError::db)?
+            .into_iter()
+            .next()
+            .ok_or(AppError::NotFound)?;
+        let images = db
+            .query_light_images_scoped(None, Some(target_id))
+            .map_err(AppError::db)?;
+        let computed = crate::computed_metrics::load_all(&conn).map_err(AppError::db)?;
+        (target, images, computed)
+    };
+    let images: Vec<_> = images
+        .into_iter()
+        .filter(|(img, _, _)| params.filter.as_ref().is_none_or(|f| img.filter_name == *f))
+        .collect();
+    let computed =
+        crate::computed_metrics::current_for(&computed, images.iter().map(|(img, _, _)| img.id));
+    let frames: Vec<FocusFrame> = images
+        .into_iter()
+        .filter_map(|(img, _, _)| {
+            let mut metrics =
+                extract_metrics_from_metadata(img.id, &img.metadata, img.acquired_date);
+            if let Some(computed) = computed.get(&img.id) {
+                computed.fill_missing(&mut metrics);
+            }
+            Some(FocusFrame {
+                image_id: img.id,
+                timestamp: metrics.timestamp?,
+                hfr: metrics.hfr,
+                accepted: img.grading_status == crate::models::GradingStatus::Accepted as i32,
+            })
+        })
+        .collect();
+
+    let sessions = split_into_sessions(frames, gap_seconds);
+    let starts: Vec<i64> = sessions.iter().map(|s| s[0].timestamp).collect();
+    let session = match params.session {
+        Some(start) => sessions
+            .iter()
+            .find(|s| s[0].timestamp == start)
+            .ok_or_else(|| AppError::BadRequest(format!("no session starts at {}", start)))?,
+        None => sessions.last().ok_or(AppError::NotFound)?,
     };
 
-    // Calculate overall file statistics (simplified for performance)
-    let total_files_found = if stats.active_projects > 0 {
-        stats.total_images
-    } else {
-        0
-    }; // Optimistic: assume all files exist for active projects
-    let total_files_missing = stats.total_images - total_files_found;
+    Ok(Json(ApiResponse::success(FocusStabilityResponse {
+        target_id,
+        target_name: target.name,
+        filter: params.filter.clone(),
+        session_start: session[0].timestamp,
+        session_end: session[session.len() - 1].timestamp,
+        sessions: starts,
+        stability: focus_stability(session),
+    })))
+}
 
-    // For now, we'll return empty recent activity - this could be enhanced later
-    let recent_activity = Vec::new();
+/// Time series of one metadata metric across every night of a target, so
+/// focus drift within a night or improvement across sessions shows up on a
+/// chart. Images missing the metric (or any timestamp) are left out.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/targets/{target_id}/trend",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path), TrendQuery),
+    responses((status = 200, description = "OK", body = ApiResponse<Vec<TrendPoint>>))
+)]
+#[axum::debug_handler(state = Arc<AppState>)]
+pub async fn get_target_trend(
+    ctx: DbContext,
+    Path((_db_id, target_id)): Path<(String, i32)>,
+    Query(params): Query<TrendQuery>,
+) -> Result<Json<ApiResponse<Vec<TrendPoint>>>, AppError> {
+    use crate::sequence_analysis::extract_metrics_from_metadata;
 
-    let response = OverallStatsResponse {
-        total_projects: stats.total_projects,
-        active_projects: stats.active_projects,
-        total_targets: stats.total_targets,
-        active_targets: stats.active_targets,
-        total_images: stats.total_images,
-        accepted_images: stats.accepted_images,
-        rejected_images: stats.rejected_images,
-        pending_images: stats.pending_images,
-        total_desired: desired_stats.total_desired,
-        files_found: total_files_found,
-        files_missing: total_files_missing,
-        unique_filters: stats.unique_filters,
-        date_range: DateRange {
-            earliest: stats.earliest_date,
-            latest: stats.latest_date,
-            span_days,
-        },
-        recent_activity,
+    let (images, computed) = {
+        let conn = ctx.db();
+        let conn = conn.lock().map_err(AppError::db)?;
+        let db = Database::new(&conn);
+
+        if db
+            .get_targets_by_ids(&[target_id])
+            .map_err(AppError::db)?
+            .is_empty()
+        {
+            return Err(AppError::NotFound);
+        }
+
+        let images = db
+            .query_light_images_scoped(None, Some(target_id))
+            .map_err(AppError::db)?;
+        let computed = crate::computed_metrics::load_all(&conn).map_err(AppError::db)?;
+        (images, computed)
     };
+    let images: Vec<_> = images
+        .into_iter()
+        .filter(|(img, _, _)| params.filter.as_ref().is_none_or(|f| img.filter_name == *f))
+        .collect();
+    let computed =
+        crate::computed_metrics::current_for(&computed, images.iter().map(|(img, _, _)| img.id));
 
-    Ok(Json(ApiResponse::success(response)))
+    let mut points: Vec<TrendPoint> = images
+        .into_iter()
+        .filter_map(|(img, _, _)| {
+            let mut metrics =
+                extract_metrics_from_metadata(img.id, &img.metadata, img.acquired_date);
+            if let Some(computed) = computed.get(&img.id) {
+                computed.fill_missing(&mut metrics);
+            }
+            let metadata: serde_json::Value =
+                serde_json::from_str(&img.metadata).unwrap_or_default();
+            Some(TrendPoint {
+                timestamp: metrics.timestamp?,
+                value: params.metric.value(&metrics)?,
+                image_id: img.id,
+                grading_status: img.grading_status,
+                airmass: crate::airmass::airmass_from_metadata(&metadata),
+            })
+        })
+        .collect();
+    points.sort_by_key(|point| (point.timestamp, point.image_id));
+
+    Ok(Json(ApiResponse::success(points)))
 }
 
-// Sequence analysis handlers
+/// Media type of the streamed `/analysis/sequence` variant: one
+/// `ScoredSequenceResponse` per line.
+const NDJSON: &str = "application/x-ndjson";
 
+/// GET /api/db/{db_id}/analysis/sequence
+///
+/// Scores one target's sequences, or every target with images when
+/// `target_id` is omitted. With `Accept: application/x-ndjson` each
+/// sequence is streamed as its own JSON line as soon as its target is
+/// scored, so a whole-library run renders incrementally; otherwise the
+/// sequences come back wrapped in one response.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/analysis/sequence",
+    tag = "analysis",
+    params(("db_id" = String, Path), crate::server::api::SequenceAnalysisQuery),
+    responses(
+        (
+            status = 200,
+            description = "Scored sequences; one `ScoredSequenceResponse` per line when streamed",
+            content(
+                (ApiResponse<SequenceAnalysisResponse> = "application/json"),
+                (ScoredSequenceResponse = "application/x-ndjson")
+            )
+        )
+    )
+)]
 #[axum::debug_handler(state = Arc<AppState>)]
 pub async fn analyze_sequence(
+    State(state): State<Arc<AppState>>,
     ctx: DbContext,
+    headers: HeaderMap,
     Query(params): Query<crate::server::api::SequenceAnalysisQuery>,
-) -> Result<Json<ApiResponse<crate::server::api::SequenceAnalysisResponse>>, AppError> {
-    use crate::sequence_analysis::{
-        extract_metrics_from_metadata, QualityWeights, SequenceAnalyzer, SequenceAnalyzerConfig,
+) -> Result<Response, AppError> {
+    use crate::sequence_analysis::{QualityWeights, SequenceAnalyzerConfig, SummaryThresholds};
+
+    let defaults = SummaryThresholds::default();
+    let summary_thresholds = SummaryThresholds {
+        excellent: params.threshold_excellent.unwrap_or(defaults.excellent),
+        good: params.threshold_good.unwrap_or(defaults.good),
+        fair: params.threshold_fair.unwrap_or(defaults.fair),
+        poor: params.threshold_poor.unwrap_or(defaults.poor),
     };
+    summary_thresholds
+        .validate()
+        .map_err(AppError::BadRequest)?;
+
+    let mut config = SequenceAnalyzerConfig {
+        summary_thresholds,
+        temporal_anomaly_reject_threshold: params.temporal_anomaly_reject_threshold,
+        session_gap_minutes: session_gap_minutes(params.session_gap_minutes)?,
+        min_stars: state.min_stars(),
+        ..Default::default()
+    };
+    if let Some(warmup) = params.warmup_frames {
+        config.warmup_frames = warmup;
+    }
+    // Apply weight overrides from query params if any are provided
+    if params.weight_star_count.is_some()
+        || params.weight_hfr.is_some()
+        || params.weight_eccentricity.is_some()
+        || params.weight_snr.is_some()
+        || params.weight_background.is_some()
+        || params.weight_spatial.is_some()
+        || params.weight_pointing.is_some()
+    {
+        let weights = &config.quality_weights;
+        config.quality_weights = QualityWeights {
+            star_count: params.weight_star_count.unwrap_or(weights.star_count),
+            hfr: params.weight_hfr.unwrap_or(weights.hfr),
+            eccentricity: params.weight_eccentricity.unwrap_or(weights.eccentricity),
+            snr: params.weight_snr.unwrap_or(weights.snr),
+            background: params.weight_background.unwrap_or(weights.background),
+            spatial: params.weight_spatial.unwrap_or(weights.spatial),
+            transparency: weights.transparency,
+            pointing: params.weight_pointing.unwrap_or(weights.pointing),
+        };
+    }
 
-    let target_id = params.target_id;
-    let filter_name = params.filter_name.clone();
-    let session_gap = params.session_gap_minutes;
-    let weight_star_count = params.weight_star_count;
-    let weight_hfr = params.weight_hfr;
-    let weight_eccentricity = params.weight_eccentricity;
-    let weight_snr = params.weight_snr;
-    let weight_background = params.weight_background;
-    let weight_spatial = params.weight_spatial;
-    let weight_pointing = params.weight_pointing;
-
-    // Fetch images from database
-    let (images_data, target_name, expected_by_image) = {
+    if let Some(radius_deg) = params.position_radius_deg
+        && !(radius_deg.is_finite() && radius_deg > 0.0)
+    {
+        return Err(AppError::BadRequest(
+            "position_radius_deg must be a positive number of degrees".to_string(),
+        ));
+    }
+
+    let (fields, computed) = {
         let conn = ctx.db();
         let conn = conn.lock().map_err(AppError::db)?;
         let db = Database::new(&conn);
+        let computed = crate::computed_metrics::load_all(&conn).map_err(AppError::db)?;
+        let target = match params.target_id {
+            Some(target_id) => Some(
+                db.get_targets_by_ids(&[target_id])
+                    .map_err(AppError::db)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        AppError::BadRequest(format!("Target {} not found", target_id))
+                    })?,
+            ),
+            None => None,
+        };
+        let mut fields = match (&target, params.position_radius_deg) {
+            (Some(target), None) => vec![FieldGroup {
+                target_id: target.id,
+                name: target.name.clone(),
+                target_ids: vec![target.id],
+            }],
+            (_, radius_deg) => {
+                let targets: Vec<_> = db
+                    .get_all_targets_with_project_info()
+                    .map_err(AppError::db)?
+                    .into_iter()
+                    .filter(|t| t.total_images > 0)
+                    .map(|t| t.target)
+                    .collect();
+                group_targets_by_position(&targets, radius_deg)
+            }
+        };
+        // A single-target request keeps that target's id and name on its
+        // field, and still answers when the target has no images yet.
+        if let Some(target) = target {
+            let mut field = fields
+                .into_iter()
+                .find(|f| f.target_ids.contains(&target.id))
+                .unwrap_or_else(|| FieldGroup {
+                    target_id: target.id,
+                    name: target.name.clone(),
+                    target_ids: vec![target.id],
+                });
+            field.target_id = target.id;
+            field.name = target.name;
+            fields = vec![field];
+        }
+        (fields, computed)
+    };
 
-        // Get target name
-        let targets = db.get_targets_by_ids(&[target_id]).map_err(AppError::db)?;
-        let target = targets
-            .into_iter()
-            .next()
-            .ok_or_else(|| AppError::BadRequest(format!("Target {} not found", target_id)))?;
-        let target_name = target.name.clone();
-
-        // Query images for this target
-        let all_images = db
-            .query_images_scoped(None, None, Some(target_id), None, 0)
-            .map_err(AppError::db)?;
-
-        let filtered: Vec<_> = all_images
-            .into_iter()
-            .filter(|(img, _, _)| {
-                img.target_id == target_id
-                    && filter_name.as_ref().is_none_or(|f| img.filter_name == *f)
-            })
-            .collect();
-        let mut resolver =
-            crate::acquisition_context::FramingResolver::new(&conn).map_err(AppError::db)?;
-        let expected_by_image = filtered
-            .iter()
-            .map(|(image, _, _)| {
-                resolver
-                    .expected_for_grading(&conn, image)
-                    .map(|expected| (image.id, expected))
-            })
-            .collect::<Result<std::collections::HashMap<_, _>, _>>()
-            .map_err(AppError::db)?;
-
-        (filtered, target_name, expected_by_image)
+    // A prior quality scan supplies fresh star/HFR measurements plus the
+    // spatial fields N.I.N.A. does not store.
+    crate::server::spatial_scan::ensure_loaded(&ctx.spatial_metrics, &ctx.cache_dir_path);
+    let analysis = TargetAnalysis {
+        conn: ctx.db(),
+        filter_name: params.filter_name,
+        config,
+        spatial: ctx.spatial_metrics.clone(),
+        cache_dir: ctx.cache_dir_path.clone(),
+        astrometry: ctx.astrometry_evidence.clone(),
+        computed,
     };
 
-    if images_data.is_empty() {
-        return Ok(Json(ApiResponse::success(
-            crate::server::api::SequenceAnalysisResponse { sequences: vec![] },
-        )));
+    let wants_ndjson = headers
+        .get(axum::http::header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON));
+    if wants_ndjson {
+        return stream_sequence_analysis(analysis, fields);
     }
 
-    // Extract metrics and group by filter. A prior quality scan supplies fresh
-    // star/HFR measurements plus the spatial fields N.I.N.A. does not store.
-    crate::server::spatial_scan::ensure_loaded(&ctx.spatial_metrics, &ctx.cache_dir_path);
-    let spatial_store = ctx.spatial_metrics.clone();
-    let astrometry_cache_dir = ctx.cache_dir_path.clone();
-    let astrometry_evidence = ctx.astrometry_evidence.clone();
-    let target_name_clone = target_name.clone();
-    let result = tokio::task::spawn_blocking(move || {
-        let mut config = SequenceAnalyzerConfig::default();
-        if let Some(gap) = session_gap {
-            config.session_gap_minutes = gap;
-        }
-        // Apply weight overrides from query params if any are provided
-        if weight_star_count.is_some()
-            || weight_hfr.is_some()
-            || weight_eccentricity.is_some()
-            || weight_snr.is_some()
-            || weight_background.is_some()
-            || weight_spatial.is_some()
-            || weight_pointing.is_some()
-        {
-            config.quality_weights = QualityWeights {
-                star_count: weight_star_count.unwrap_or(config.quality_weights.star_count),
-                hfr: weight_hfr.unwrap_or(config.quality_weights.hfr),
-                eccentricity: weight_eccentricity.unwrap_or(config.quality_weights.eccentricity),
-                snr: weight_snr.unwrap_or(config.quality_weights.snr),
-                background: weight_background.unwrap_or(config.quality_weights.background),
-                spatial: weight_spatial.unwrap_or(config.quality_weights.spatial),
-                transparency: config.quality_weights.transparency,
-                pointing: weight_pointing.unwrap_or(config.quality_weights.pointing),
-            };
+    let sequences = tokio::task::spawn_blocking(move || {
+        let mut sequences = Vec::new();
+        for field in &fields {
+            sequences.extend(analysis.score(field)?);
         }
+        Ok::<_, anyhow::Error>(sequences)
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Analysis task failed: {}", e)))?
+    .map_err(AppError::db)?;
 
-        let session_gap_minutes = config.session_gap_minutes;
-        let analyzer = SequenceAnalyzer::new(config);
+    let sequences = sequences.into_iter().map(Into::into).collect();
+    Ok(Json(ApiResponse::success(
+        crate::server::api::SequenceAnalysisResponse { sequences },
+    ))
+    .into_response())
+}
 
-        // Group by filter_name and analyze each group
-        let mut by_filter: std::collections::HashMap<String, Vec<_>> =
-            std::collections::HashMap::new();
-        let mut entries_by_filter: std::collections::HashMap<String, Vec<_>> =
-            std::collections::HashMap::new();
-        for (img, _proj, _target) in &images_data {
-            let mut metrics =
-                extract_metrics_from_metadata(img.id, &img.metadata, img.acquired_date);
-            merge_spatial_metrics(&mut metrics, &spatial_store, &img.metadata);
-            merge_astrometry_metrics(
-                &mut metrics,
-                &astrometry_cache_dir,
-                &img.metadata,
-                &astrometry_evidence,
-                expected_by_image.get(&img.id).copied().flatten(),
-            );
-            entries_by_filter
-                .entry(img.filter_name.clone())
-                .or_default()
-                .push(stored_entry_for(&spatial_store, img.id, &img.metadata));
-            by_filter
-                .entry(img.filter_name.clone())
-                .or_default()
-                .push(metrics);
+/// Everything `/analysis/sequence` needs to score a target off the async
+/// runtime.
+struct TargetAnalysis {
+    conn: Arc<std::sync::Mutex<rusqlite::Connection>>,
+    filter_name: Option<String>,
+    config: crate::sequence_analysis::SequenceAnalyzerConfig,
+    spatial: crate::server::spatial_scan::SharedSpatialStore,
+    cache_dir: PathBuf,
+    astrometry: Arc<crate::astrometry::AstrometryEvidenceCache>,
+    computed: HashMap<i32, crate::computed_metrics::ComputedMetrics>,
+}
+
+impl TargetAnalysis {
+    /// Score one field's sequences. Blocking; holds the connection only
+    /// while loading the images.
+    fn score(
+        &self,
+        field: &FieldGroup,
+    ) -> anyhow::Result<Vec<crate::sequence_analysis::ScoredSequence>> {
+        let (images, expected_by_image) = {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| anyhow::anyhow!("database lock poisoned: {e}"))?;
+            load_field_images(&conn, &field.target_ids, self.filter_name.as_deref())?
+        };
+        if images.is_empty() {
+            return Ok(vec![]);
         }
+        let caches = ScoringCaches {
+            spatial: &self.spatial,
+            cache_dir: &self.cache_dir,
+            astrometry: &self.astrometry,
+            computed: &self.computed,
+        };
+        Ok(score_target_sequences(
+            &images,
+            &expected_by_image,
+            field.target_id,
+            &field.name,
+            self.config.clone(),
+            &caches,
+        ))
+    }
+}
 
-        let mut all_sequences = Vec::new();
-        for (filter, mut metrics) in by_filter {
-            if let Some(entries) = entries_by_filter.get(&filter) {
-                merge_photometric_signals(&mut metrics, entries, session_gap_minutes);
+/// Stream each target's sequences as NDJSON lines while later targets are
+/// still being scored. A failure part way through ends the stream with one
+/// `{"error": ...}` line, so the client can tell it from a complete run.
+fn stream_sequence_analysis(
+    analysis: TargetAnalysis,
+    fields: Vec<FieldGroup>,
+) -> Result<Response, AppError> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+    tokio::task::spawn_blocking(move || {
+        for field in &fields {
+            let sequences = match analysis.score(field) {
+                Ok(sequences) => sequences,
+                Err(e) => {
+                    tracing::warn!(
+                        "📈 sequence analysis: aborting stream at target {}: {:#}",
+                        field.target_id,
+                        e
+                    );
+                    let error = serde_json::json!({ "error": format!("{:#}", e) });
+                    let _ = tx.blocking_send(ndjson_line(&error));
+                    return;
+                }
+            };
+            for sequence in sequences {
+                let response = crate::server::api::ScoredSequenceResponse::from(sequence);
+                // The client went away: stop scoring.
+                if tx.blocking_send(ndjson_line(&response)).is_err() {
+                    return;
+                }
             }
-            let scored = analyzer.analyze(&metrics, target_id, &target_name_clone, &filter);
-            all_sequences.extend(scored);
         }
+    });
 
-        all_sequences
-    })
-    .await
-    .map_err(|e| AppError::InternalError(format!("Analysis task failed: {}", e)))?;
+    let lines = futures_util::stream::unfold(rx, |mut rx| async move {
+        let line = rx.recv().await?;
+        Some((Ok::<_, std::convert::Infallible>(line), rx))
+    });
+    Response::builder()
+        .header(CONTENT_TYPE, NDJSON)
+        .body(axum::body::Body::from_stream(lines))
+        .map_err(|e| AppError::InternalError(format!("Failed to build response: {}", e)))
+}
 
-    let mut sequences: Vec<_> = result
-        .into_iter()
-        .map(|seq| crate::server::api::ScoredSequenceResponse {
-            target_id: seq.target_id,
-            target_name: seq.target_name,
-            filter_name: seq.filter_name,
-            session_start: seq.session_start,
-            session_end: seq.session_end,
-            image_count: seq.image_count,
-            reference_values: seq.reference_values,
-            images: seq.images,
-            summary: seq.summary,
-        })
-        .collect();
-    sequences.sort_by(|a, b| {
-        b.session_start
-            .cmp(&a.session_start)
-            .then_with(|| a.filter_name.cmp(&b.filter_name))
+/// `value` as one NDJSON line.
+fn ndjson_line(value: &impl Serialize) -> Vec<u8> {
+    let mut line = serde_json::to_vec(value).unwrap_or_else(|e| {
+        serde_json::to_vec(&serde_json::json!({ "error": e.to_string() })).unwrap_or_default()
     });
+    line.push(b'\n');
+    line
+}
 
-    Ok(Json(ApiResponse::success(
-        crate::server::api::SequenceAnalysisResponse { sequences },
-    )))
+#[cfg(test)]
+mod sequence_stream_tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn failed_analysis_ends_the_stream_with_an_error_line() {
+        // No image tables: loading the field's images fails.
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let analysis = TargetAnalysis {
+            conn: Arc::new(std::sync::Mutex::new(conn)),
+            filter_name: None,
+            config: crate::sequence_analysis::SequenceAnalyzerConfig::default(),
+            spatial: Default::default(),
+            cache_dir: dir.path().to_path_buf(),
+            astrometry: Arc::new(crate::astrometry::AstrometryEvidenceCache::new()),
+            computed: HashMap::new(),
+        };
+        let fields = vec![FieldGroup {
+            target_id: 1,
+            name: "M31".to_string(),
+            target_ids: vec![1],
+        }];
+
+        let response = stream_sequence_analysis(analysis, fields).unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], NDJSON);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let text = std::str::from_utf8(&body).unwrap();
+        let last: serde_json::Value =
+            serde_json::from_str(text.lines().last().expect("an error line")).unwrap();
+        assert!(
+            last["error"].as_str().is_some_and(|e| !e.is_empty()),
+            "{text}"
+        );
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/analysis/image/{image_id}",
+    tag = "analysis",
+    params(("db_id" = String, Path), ("image_id" = i32, Path)),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<crate::server::api::ImageQualityContextResponse>)
+    )
+)]
 #[axum::debug_handler(state = Arc<AppState>)]
 pub async fn get_image_quality(
+    State(state): State<Arc<AppState>>,
     ctx: DbContext,
     Path((_db_id, image_id)): Path<(String, i32)>,
 ) -> Result<Json<ApiResponse<crate::server::api::ImageQualityContextResponse>>, AppError> {
@@ -3505,7 +7177,7 @@ pub async fn get_image_quality(
     };
 
     // Get the target image and its context from database
-    let (target_image, all_filter_images, target_name, expected_by_image) = {
+    let (target_image, all_filter_images, target_name, expected_by_image, computed) = {
         let conn = ctx.db();
         let conn = conn.lock().map_err(AppError::db)?;
         let db = Database::new(&conn);
@@ -3522,7 +7194,7 @@ pub async fn get_image_quality(
 
         // Get all images for the same target + filter
         let all_images = db
-            .query_images_scoped(None, None, Some(target_image.target_id), None, 0)
+            .query_light_images_scoped(None, Some(target_image.target_id))
             .map_err(AppError::db)?;
 
         let filter_images: Vec<_> = all_images
@@ -3543,8 +7215,15 @@ pub async fn get_image_quality(
             })
             .collect::<Result<std::collections::HashMap<_, _>, _>>()
             .map_err(AppError::db)?;
+        let computed = crate::computed_metrics::load_all(&conn).map_err(AppError::db)?;
 
-        (target_image, filter_images, target_name, expected_by_image)
+        (
+            target_image,
+            filter_images,
+            target_name,
+            expected_by_image,
+            computed,
+        )
     };
 
     if all_filter_images.is_empty() {
@@ -3569,15 +7248,26 @@ pub async fn get_image_quality(
     let astrometry_cache_dir = ctx.cache_dir_path.clone();
     let astrometry_evidence = ctx.astrometry_evidence.clone();
 
+    let min_stars = state.min_stars();
     let result = tokio::task::spawn_blocking(move || {
-        let config = SequenceAnalyzerConfig::default();
+        let config = SequenceAnalyzerConfig {
+            min_stars,
+            ..Default::default()
+        };
         let session_gap_minutes = config.session_gap_minutes;
         let analyzer = SequenceAnalyzer::new(config);
 
+        let computed = crate::computed_metrics::current_for(
+            &computed,
+            all_filter_images.iter().map(|(img, _, _)| img.id),
+        );
         let mut metrics: Vec<_> = Vec::with_capacity(all_filter_images.len());
         let mut entries = Vec::with_capacity(all_filter_images.len());
         for (img, _, _) in &all_filter_images {
             let mut m = extract_metrics_from_metadata(img.id, &img.metadata, img.acquired_date);
+            if let Some(computed) = computed.get(&img.id) {
+                computed.fill_missing(&mut m);
+            }
             merge_spatial_metrics(&mut m, &spatial_store, &img.metadata);
             merge_astrometry_metrics(
                 &mut m,
@@ -3632,175 +7322,20 @@ pub async fn get_image_quality(
 
 // ---------------- Spatial (occlusion) metrics scan ----------------
 
-/// Extract the FITS basename from an acquiredimage metadata JSON blob.
-pub(crate) fn filename_from_metadata(metadata_json: &str) -> Option<String> {
-    let metadata: serde_json::Value = serde_json::from_str(metadata_json).ok()?;
-    let filename = metadata["FileName"].as_str()?;
-    filename
-        .split(&['\\', '/'][..])
-        .next_back()
-        .map(|s| s.to_string())
-}
-
-/// Fetch the filename-validated stored scan entry for an image, if any.
-pub(crate) fn stored_entry_for(
-    store: &crate::server::spatial_scan::SharedSpatialStore,
-    image_id: i32,
-    metadata_json: &str,
-) -> Option<crate::server::spatial_scan::StoredSpatialMetrics> {
-    let file_only = filename_from_metadata(metadata_json)?;
-    crate::server::spatial_scan::valid_quality_entry(store, image_id, &file_only)
-}
-
-/// Run the cross-frame photometric pass (transparency, localized extinction,
-/// per-cell temporal baselines) over one filter group and merge the signals
-/// into its `ImageMetrics`. `entries` parallels `metrics` index-for-index;
-/// frames without a stored scan entry contribute empty inputs and receive no
-/// signals. Frames are bucketed by exposure (flux ratios are only meaningful
-/// within one exposure length) and split into sessions before the pass.
-pub(crate) fn merge_photometric_signals(
-    metrics: &mut [crate::sequence_analysis::ImageMetrics],
-    entries: &[Option<crate::server::spatial_scan::StoredSpatialMetrics>],
-    session_gap_minutes: u64,
-) {
-    use crate::photometry::{
-        sequence_screening_signals, split_sessions, FrameInputs, PhotometryConfig,
-    };
-
-    if metrics.len() != entries.len() {
-        return;
-    }
-
-    // Time order (the analyzer applies the same stable sort later).
-    let mut order: Vec<usize> = (0..metrics.len()).collect();
-    order.sort_by_key(|&i| (metrics[i].timestamp.unwrap_or(0), metrics[i].image_id));
-
-    // Bucket by exposure seconds.
-    let mut buckets: std::collections::BTreeMap<i64, Vec<usize>> =
-        std::collections::BTreeMap::new();
-    for &i in &order {
-        let key = entries[i]
-            .as_ref()
-            .and_then(|e| e.exposure_s)
-            .map(|e| e.round() as i64)
-            .unwrap_or(-1);
-        buckets.entry(key).or_default().push(i);
-    }
-
-    let phot_config = PhotometryConfig::default();
-    let gap_seconds = (session_gap_minutes * 60) as i64;
-    for indices in buckets.values() {
-        let timestamps: Vec<Option<i64>> = indices.iter().map(|&i| metrics[i].timestamp).collect();
-        for session in split_sessions(&timestamps, gap_seconds) {
-            let session_idx: Vec<usize> = session.iter().map(|&s| indices[s]).collect();
-            let inputs: Vec<FrameInputs> = session_idx
-                .iter()
-                .map(|&i| match &entries[i] {
-                    Some(e) => FrameInputs {
-                        catalog: e.catalog.clone(),
-                        star_cell_counts: e.star_cell_counts.clone(),
-                        bg_cell_medians: e.bg_cell_medians.clone(),
-                    },
-                    None => FrameInputs::default(),
-                })
-                .collect();
-            let dims = session_idx.iter().find_map(|&i| {
-                entries[i].as_ref().and_then(|e| {
-                    (e.width > 0 && e.grid_cols > 0).then_some((
-                        e.width,
-                        e.height,
-                        e.grid_cols,
-                        e.grid_rows,
-                    ))
-                })
-            });
-            let Some((width, height, grid_cols, grid_rows)) = dims else {
-                continue;
-            };
-            let signals = sequence_screening_signals(
-                &inputs,
-                width,
-                height,
-                (grid_cols, grid_rows),
-                &phot_config,
-            );
-            for (&i, sig) in session_idx.iter().zip(signals) {
-                let m = &mut metrics[i];
-                m.transparency = sig.transparency;
-                m.extinction_cell_fraction = sig.extinction_cell_fraction;
-                m.star_cell_drop_fraction = sig.star_cell_drop_fraction;
-                m.bg_cell_rise_fraction = sig.bg_cell_rise_fraction;
-                m.bg_cell_fall_fraction = sig.bg_cell_fall_fraction;
-            }
-        }
-    }
-}
-
-/// Merge fresh detector and spatial results from the per-DB quality cache.
-/// A quality scan is the source of truth for star count and HFR once present;
-/// the spatial fields fill values that N.I.N.A. does not store.
-pub(crate) fn merge_spatial_metrics(
-    metrics: &mut crate::sequence_analysis::ImageMetrics,
-    store: &crate::server::spatial_scan::SharedSpatialStore,
-    metadata_json: &str,
-) {
-    let Some(file_only) = filename_from_metadata(metadata_json) else {
-        return;
-    };
-    if let Some(entry) =
-        crate::server::spatial_scan::valid_entry(store, metrics.image_id, &file_only)
-    {
-        if entry.detector == crate::server::spatial_scan::QUALITY_DETECTOR
-            && entry.detector_version == crate::server::spatial_scan::QUALITY_DETECTOR_VERSION
-        {
-            metrics.star_count = Some(entry.star_count as f64);
-            metrics.hfr = (entry.avg_hfr > 0.0).then_some(entry.avg_hfr);
-        }
-        if metrics.dead_cell_fraction.is_none() {
-            metrics.dead_cell_fraction = entry.dead_cell_fraction;
-        }
-        if metrics.bg_cell_spread.is_none() {
-            metrics.bg_cell_spread = Some(entry.bg_cell_spread);
-        }
-        if metrics.bg_glow_max.is_none() && entry.bg_glow_max > 0.0 {
-            metrics.bg_glow_max = Some(entry.bg_glow_max);
-        }
-    }
-}
-
-pub(crate) fn merge_astrometry_metrics(
-    metrics: &mut crate::sequence_analysis::ImageMetrics,
-    cache_dir: &std::path::Path,
-    metadata_json: &str,
-    evidence: &crate::astrometry::AstrometryEvidenceCache,
-    expected_target: Option<(f64, f64)>,
-) {
-    let Some(file_only) = filename_from_metadata(metadata_json) else {
-        return;
-    };
-    let Some(analysis) = evidence.evidence_for_source(cache_dir, metrics.image_id, expected_target)
-    else {
-        return;
-    };
-    let cached_file = std::path::Path::new(&analysis.source_fingerprint.canonical_path)
-        .file_name()
-        .and_then(|name| name.to_str());
-    if cached_file != Some(file_only.as_str()) {
-        return;
-    }
-    metrics.astrometry = crate::sequence_analysis::astrometry_metrics_from_analysis(&analysis);
-    metrics.satellite =
-        crate::satellites::persisted_analysis(cache_dir, metrics.image_id, &analysis)
-            .as_ref()
-            .map(crate::sequence_analysis::SatelliteFrameMetrics::from);
-}
-
 /// POST /api/db/{db_id}/analysis/spatial-scan
 ///
 /// Start a background scan computing spatial occlusion metrics from the FITS
 /// files of a target's images. Singleton per database: if a scan is already
 /// running (or nothing needs computing) this returns `started: false` with
 /// the current progress. Poll GET on the same path for progress.
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/analysis/quality-scan",
+    tag = "analysis",
+    params(("db_id" = String, Path)),
+    request_body = SpatialScanRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<SpatialScanStatusResponse>))
+)]
 pub async fn start_spatial_scan(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -3850,7 +7385,7 @@ async fn start_spatial_scan_with_priority(
         let target_name = target.name.clone();
 
         let all_images = db
-            .query_images_scoped(None, None, Some(req.target_id), None, 0)
+            .query_light_images_scoped(None, Some(req.target_id))
             .map_err(AppError::db)?;
 
         let mut resolver =
@@ -4175,6 +7710,13 @@ async fn start_spatial_scan_with_priority(
 }
 
 /// GET /api/db/{db_id}/analysis/spatial-scan — progress + store size.
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/analysis/quality-scan",
+    tag = "analysis",
+    params(("db_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<SpatialScanStatusResponse>))
+)]
 pub async fn get_spatial_scan_progress(
     ctx: DbContext,
 ) -> Result<Json<ApiResponse<SpatialScanStatusResponse>>, AppError> {
@@ -4197,7 +7739,13 @@ pub enum AppError {
     BadRequest(String),
     Conflict(String),
     Forbidden(String),
+    Unauthorized(String),
     InternalError(String),
+    /// The FITS file exists but couldn't be parsed (truncated, not FITS, bad
+    /// header). Distinct from `NotFound`, which means the file is absent.
+    FitsCorrupt {
+        file: String,
+    },
     NotImplemented,
 }
 
@@ -4209,6 +7757,59 @@ impl AppError {
     pub(crate) fn db(err: impl std::fmt::Display) -> Self {
         AppError::DatabaseError(err.to_string())
     }
+
+    /// A FITS read of `path` failed with `err`; open the file again to tell
+    /// why. If it opens and reads, the content didn't parse: 422. If it's
+    /// gone, 404. Any other I/O failure (permissions, a dropped mount) is a
+    /// 500 rather than a corrupt file. `err` embeds the full path, so it
+    /// goes to the log only. Blocking; call from `spawn_blocking`.
+    pub(crate) fn fits_read(path: &std::path::Path, err: impl std::fmt::Display) -> Self {
+        use std::io::Read;
+
+        let probe = std::fs::File::open(path).and_then(|mut file| file.read(&mut [0u8; 80]));
+        match probe {
+            Ok(_) => {
+                tracing::warn!("🔭 Unparseable FITS file {}: {:#}", path.display(), err);
+                Self::fits_corrupt(path)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::warn!(
+                    "🔍 FITS file vanished before reading {}: {}",
+                    path.display(),
+                    e
+                );
+                AppError::NotFound
+            }
+            Err(e) => {
+                tracing::error!(
+                    "💾 Failed to read FITS file {}: {} ({:#})",
+                    path.display(),
+                    e,
+                    err
+                );
+                AppError::InternalError(format!(
+                    "FITS file '{}' could not be read",
+                    display_file_name(path)
+                ))
+            }
+        }
+    }
+
+    /// `path` was read and is not valid FITS. Only the file name goes to the
+    /// client; the full path stays in the log.
+    pub(crate) fn fits_corrupt(path: &std::path::Path) -> Self {
+        AppError::FitsCorrupt {
+            file: display_file_name(path),
+        }
+    }
+}
+
+/// The last component of `path`, for error messages that mustn't reveal the
+/// directory layout.
+fn display_file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "(unnamed)".to_string())
 }
 
 impl IntoResponse for AppError {
@@ -4250,6 +7851,15 @@ impl IntoResponse for AppError {
                 )
                     .into_response();
             }
+            AppError::Unauthorized(msg) => {
+                tracing::warn!("🔑 Unauthorized: {}", msg);
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(axum::http::header::WWW_AUTHENTICATE, "Bearer")],
+                    Json(ApiResponse::<()>::error(msg.clone())),
+                )
+                    .into_response();
+            }
             AppError::InternalError(msg) => {
                 tracing::error!("⚠️  Internal server error: {}", msg);
                 return (
@@ -4258,6 +7868,17 @@ impl IntoResponse for AppError {
                 )
                     .into_response();
             }
+            AppError::FitsCorrupt { file } => {
+                tracing::warn!("🔭 Corrupt FITS file {}", file);
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(ApiResponse::<()>::error(format!(
+                        "FITS file '{}' is truncated or not valid FITS",
+                        file
+                    ))),
+                )
+                    .into_response();
+            }
             AppError::NotImplemented => {
                 tracing::debug!("🚧 Not implemented endpoint accessed");
                 (StatusCode::NOT_IMPLEMENTED, "Not implemented yet")
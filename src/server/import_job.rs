@@ -11,9 +11,10 @@
 use crate::commands::import::ImportOutcome;
 use serde::Serialize;
 use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
 
 /// Progress of the (singleton per-DB) import job.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
 pub struct ImportJobProgress {
     pub running: bool,
     /// `scanning`, `importing`, `complete`, or `error`.
@@ -1,4 +1,5 @@
 pub mod api;
+pub mod auth;
 pub mod cache;
 pub mod catalog_install;
 pub mod database_context;
@@ -6,30 +7,37 @@ pub mod embedded_static;
 pub mod extract;
 pub mod handlers;
 pub mod import_job;
+pub mod openapi;
 pub mod preview_queue;
 pub mod quality_backfill;
 pub mod scheduler;
 pub mod slug;
 pub mod spatial_scan;
 pub mod stack_preview;
+pub mod standalone;
 pub mod state;
 pub mod static_file_service;
 pub mod sync_preview;
+pub mod warm_cache;
 
 use anyhow::{Context, Result};
 use axum::{
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tower::ServiceBuilder;
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
+use tower_http::timeout::TimeoutLayer;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
 use crate::server::embedded_static::serve_embedded_file;
 use crate::server::static_file_service::StaticFileService;
 
-use crate::cli::PregenerationConfig;
+use crate::cli::{LogFormat, PregenerationConfig};
 use crate::server::state::AppState;
 use tokio::sync::oneshot;
 
@@ -52,72 +60,183 @@ pub struct ServerConfig {
     pub allow_database_management: bool,
     /// Optional notice shown below the application header.
     pub site_banner: Option<crate::config::SiteBannerConfig>,
+    /// Reject-reason categories for the rejection statistics.
+    pub reason_mapper: crate::reject_reasons::ReasonMapper,
     /// Tuning policy for the parallel scans and background pre-generation.
     /// See `concurrency::WorkerPolicy`.
     pub worker_policy: crate::concurrency::WorkerPolicy,
     /// Process-global Seiza catalog configuration from the shared registry.
     pub astrometry_config: Option<crate::astrometry::AstrometryConfig>,
+    /// SQLite busy timeout / journal mode for every scheduler connection.
+    pub connection_options: crate::db::ConnectionOptions,
+    /// Retry policy for FITS reads in preview / annotated / star generation.
+    pub fits_read_retry: crate::fits_read::ReadRetryPolicy,
+    /// Fall back to case-insensitive filename matching when locating frames.
+    pub case_insensitive_filenames: bool,
+    /// Which browser origins may call the API cross-origin.
+    pub cors: crate::config::CorsPolicy,
+    /// When set, every `/api` route requires `Authorization: Bearer <token>`.
+    pub auth_token: Option<String>,
+    /// How long a request may run before the client gets a 504.
+    pub request_timeouts: RequestTimeouts,
+    /// Frames with fewer detected stars than this are flagged and fail
+    /// sequence analysis. Off when `None`.
+    pub min_stars: Option<usize>,
+    /// Clip fractions past which a frame is judged under- or over-exposed.
+    pub exposure_thresholds: crate::image_analysis::ExposureThresholds,
 }
 
-#[allow(clippy::too_many_arguments)]
-pub async fn run_server(
-    databases: Vec<crate::db_registry::DbEntry>,
-    static_dir: Option<String>,
-    cache_dir: String,
-    host: String,
-    port: u16,
-    pregeneration_config: PregenerationConfig,
-    registry_path: Option<PathBuf>,
-    allow_database_management: bool,
-    site_banner: Option<crate::config::SiteBannerConfig>,
-    worker_policy: crate::concurrency::WorkerPolicy,
-    astrometry_config: Option<crate::astrometry::AstrometryConfig>,
-) -> anyhow::Result<()> {
-    // Initialize tracing with environment-based filtering (for CLI mode)
-    // Set RUST_LOG=debug for debug logs, RUST_LOG=info for info logs, etc.
-    // Default to info level if no RUST_LOG is set
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::filter::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::filter::EnvFilter::new("info")),
-        )
-        .with_target(false) // Don't show module paths in logs
-        .with_level(true) // Show log levels
-        .with_thread_ids(false) // Don't show thread IDs for cleaner output
-        .init();
-
-    let config = ServerConfig {
-        databases,
-        static_dir,
-        cache_dir,
-        host,
-        port,
-        pregeneration_config,
-        registry_path,
-        allow_database_management,
-        site_banner,
-        worker_policy,
-        astrometry_config,
+/// Upper bound on a request's run time, per route class; past it the client
+/// gets a 504 instead of a connection that hangs on slow storage. Only the
+/// time to the response head counts, so a long streamed download isn't cut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeouts {
+    /// Browsing: lists, frame details, stars, pixels. Previews and other
+    /// rendered artifacts answer 202 and generate on the queue, so they only
+    /// need long enough to locate the file.
+    pub interactive: Duration,
+    /// Whole-dataset work answered in one response: exports, database sync,
+    /// sequence analysis, plate solving and catalog validation.
+    pub bulk: Duration,
+}
+
+impl RequestTimeouts {
+    pub const DEFAULT: Self = Self {
+        interactive: Duration::from_secs(30),
+        bulk: Duration::from_secs(600),
     };
+}
+
+/// 504 once `timeout` elapses. The handler's future is dropped; work it
+/// already handed to the blocking pool runs to completion (it can't be
+/// interrupted), so such handlers write their cache from that work.
+fn timeout_layer(timeout: Duration) -> TimeoutLayer {
+    TimeoutLayer::with_status_code(axum::http::StatusCode::GATEWAY_TIMEOUT, timeout)
+}
 
-    run_server_internal(config, None).await
+/// Install the global tracing subscriber, once per process, in the format
+/// named by `RUST_LOG_FORMAT`. Every server entry point (CLI, Tauri,
+/// restarts, tests) calls this, so repeated starts never hit the
+/// double-`init()` panic.
+pub fn init_tracing_once() {
+    init_tracing(LogFormat::from_env());
+}
+
+/// Like [`init_tracing_once`] with an explicit format (the CLI
+/// `--log-format` flag). Only the first call in a process has any effect.
+///
+/// Filtering follows `RUST_LOG` (e.g. `RUST_LOG=debug`), defaulting to info.
+pub fn init_tracing(format: LogFormat) {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let builder = tracing_subscriber::fmt()
+            .with_env_filter(
+                tracing_subscriber::filter::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::filter::EnvFilter::new("info")),
+            )
+            .with_level(true) // Show log levels
+            .with_thread_ids(false); // Don't show thread IDs for cleaner output
+
+        // try_init rather than init: a host embedding the server may already
+        // have installed a global subscriber, and that one wins.
+        let _ = match format {
+            LogFormat::Text => builder
+                .with_target(false) // Don't show module paths in logs
+                .try_init(),
+            // Event fields (image_id, timings, ...) become top-level keys.
+            LogFormat::Json => builder.json().flatten_event(true).try_init(),
+        };
+    });
 }
 
 pub async fn run_server_with_config(config: ServerConfig) -> anyhow::Result<()> {
-    // Initialize tracing with environment-based filtering (for CLI mode)
-    // Set RUST_LOG=debug for debug logs, RUST_LOG=info for info logs, etc.
-    // Default to info level if no RUST_LOG is set
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::filter::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::filter::EnvFilter::new("info")),
-        )
-        .with_target(false) // Don't show module paths in logs
-        .with_level(true) // Show log levels
-        .with_thread_ids(false) // Don't show thread IDs for cleaner output
-        .init();
-
-    run_server_internal(config, None).await
+    init_tracing_once();
+
+    run_server_internal(config, Some(shutdown_on_signal())).await
+}
+
+fn cors_layer(policy: &crate::config::CorsPolicy) -> CorsLayer {
+    use crate::config::CorsPolicy;
+    use axum::http::HeaderValue;
+    use tower_http::cors::{AllowOrigin, Any};
+
+    match policy {
+        CorsPolicy::Disabled => CorsLayer::new(),
+        CorsPolicy::Permissive => CorsLayer::permissive(),
+        CorsPolicy::AllowList(origins) => {
+            // Origins were validated as ASCII in `Config::get_cors_policy`.
+            let origins: Vec<HeaderValue> = origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok())
+                .collect();
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::list(origins))
+                .allow_methods(Any)
+                .allow_headers(Any)
+        }
+    }
+}
+
+/// gzip/brotli for clients that send `Accept-Encoding`. The default
+/// predicate already skips images and tiny bodies; FITS and archive
+/// downloads are skipped too since they are large, streamed and gain little.
+pub fn compression_layer() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new().compress_when(
+        DefaultPredicate::new()
+            .and(NotForContentType::const_new("application/fits"))
+            .and(NotForContentType::const_new("application/zip"))
+            .and(NotForContentType::const_new("application/octet-stream")),
+    )
+}
+
+/// Fire a graceful shutdown on Ctrl-C (or SIGTERM on Unix, as sent by
+/// systemd and docker). In-flight requests finish and the listener drains;
+/// a second signal while draining exits immediately.
+fn shutdown_on_signal() -> oneshot::Receiver<()> {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let signal = wait_for_shutdown_signal().await;
+        tracing::info!(
+            "🛑 {} received, draining in-flight requests (repeat to force exit)",
+            signal
+        );
+        let _ = shutdown_tx.send(());
+
+        let signal = wait_for_shutdown_signal().await;
+        tracing::warn!("🛑 {} received again, exiting immediately", signal);
+        std::process::exit(130);
+    });
+    shutdown_rx
+}
+
+async fn wait_for_shutdown_signal() -> &'static str {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => "Ctrl-C",
+        _ = terminate => "SIGTERM",
+    }
 }
 
 async fn run_server_internal(
@@ -151,15 +270,49 @@ async fn run_server_internal(
     // Create cache directory if it doesn't exist
     std::fs::create_dir_all(&config.cache_dir)?;
 
+    tracing::info!(
+        "🗄️ SQLite busy timeout {}, WAL {}",
+        humantime::format_duration(config.connection_options.busy_timeout),
+        if config.connection_options.wal {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    crate::fits_read::set_read_retry_policy(config.fits_read_retry);
+    crate::server::database_context::set_case_insensitive_lookup(config.case_insensitive_filenames);
+    if config.case_insensitive_filenames {
+        tracing::info!("🔠 Filename lookups fall back to case-insensitive matching");
+    }
+    tracing::info!(
+        "⏱️ Request timeouts: {} interactive, {} bulk",
+        humantime::format_duration(config.request_timeouts.interactive),
+        humantime::format_duration(config.request_timeouts.bulk)
+    );
+    if config.fits_read_retry.attempts > 1 {
+        tracing::info!(
+            "💾 FITS reads retried up to {} times (backoff from {})",
+            config.fits_read_retry.attempts,
+            humantime::format_duration(config.fits_read_retry.delay)
+        );
+    }
+
     // Create app state
-    let state = match AppState::from_databases_with_astrometry(
+    let state = match AppState::from_databases_with_options(
         config.databases.clone(),
         config.cache_dir.clone(),
         config.pregeneration_config.clone(),
         config.astrometry_config.clone(),
+        config.connection_options,
     ) {
         Ok(state) => {
             tracing::info!("✅ Application state initialized successfully");
+            let state = state.with_settings(crate::server::state::ServerSettings {
+                reason_mapper: config.reason_mapper.clone(),
+                min_stars: config.min_stars,
+                exposure_thresholds: config.exposure_thresholds,
+            });
             state.set_registry_path(config.registry_path.clone());
             state.set_allow_database_management(config.allow_database_management);
             state.set_site_banner(config.site_banner.clone());
@@ -173,6 +326,12 @@ async fn run_server_internal(
                 config.worker_policy.background_ratio,
                 crate::concurrency::logical_cores()
             );
+            if config.connection_options.read_only {
+                tracing::warn!(
+                    "🔒 READ-ONLY mode: databases are opened with SQLITE_OPEN_READ_ONLY; \
+                     grade updates, regrades and edits are refused (403)."
+                );
+            }
             if config.allow_database_management {
                 tracing::warn!(
                     "⚠️ Database management via HTTP is ENABLED. Anyone who can reach \
@@ -223,11 +382,16 @@ async fn run_server_internal(
     // Per-DB routes — nested under /api/db/{db_id}/.
     let db_routes: Router<Arc<AppState>> = Router::new()
         .route("/refresh-cache", put(handlers::refresh_file_cache))
+        .route(
+            "/refresh-cache/cancel",
+            post(handlers::cancel_cache_refresh),
+        )
         .route("/cache-progress", get(handlers::get_cache_refresh_progress))
         .route(
             "/refresh-directory-cache",
             put(handlers::refresh_directory_tree_cache),
         )
+        .route("/directory-cache", get(handlers::get_directory_cache))
         .route("/projects", get(handlers::list_projects))
         .route(
             "/projects/{project_id}",
@@ -237,11 +401,51 @@ async fn run_server_internal(
             "/projects/{project_id}/merge",
             post(handlers::merge_project_route),
         )
+        .route(
+            "/projects/{project_id}/export",
+            get(handlers::export_project_curation),
+        )
+        .route(
+            "/projects/{project_id}/import",
+            post(handlers::import_project_curation),
+        )
         .route(
             "/projects/{project_id}/scheduler",
             get(scheduler::get_project_scheduler),
         )
+        .route(
+            "/projects/{project_id}/warm-cache",
+            post(handlers::start_project_warm_cache),
+        )
+        .route(
+            "/pregeneration-progress",
+            get(handlers::get_pregeneration_progress),
+        )
         .route("/targets/{target_id}", put(handlers::update_target_route))
+        .route(
+            "/targets/{target_id}/trend",
+            get(handlers::get_target_trend),
+        )
+        .route(
+            "/targets/{target_id}/integration",
+            get(handlers::get_target_integration),
+        )
+        .route(
+            "/targets/{target_id}/timeline",
+            get(handlers::get_target_timeline),
+        )
+        .route(
+            "/targets/{target_id}/focus-stability",
+            get(handlers::get_focus_stability),
+        )
+        .route(
+            "/targets/{target_id}/contact-sheet",
+            get(handlers::get_target_contact_sheet),
+        )
+        .route(
+            "/targets/{target_id}/merge",
+            post(handlers::merge_target_route),
+        )
         .route(
             "/targets/{target_id}/exposure-plans",
             post(scheduler::create_exposure_plan),
@@ -253,6 +457,7 @@ async fn run_server_internal(
         .route("/projects/overview", get(handlers::get_projects_overview))
         .route("/targets/overview", get(handlers::get_targets_overview))
         .route("/stats/overall", get(handlers::get_overall_stats))
+        .route("/stats/rejections", get(handlers::get_rejection_stats))
         .route(
             "/projects/{project_id}/targets",
             get(handlers::list_targets),
@@ -307,11 +512,8 @@ async fn run_server_internal(
             get(stack_preview::color::download_stack_color_fits),
         )
         .route("/images", get(handlers::get_images))
+        .route("/sessions", get(handlers::get_sessions))
         .route("/images/{image_id}", get(handlers::get_image))
-        .route(
-            "/images/{image_id}/astrometry",
-            get(handlers::get_image_astrometry).post(handlers::solve_image_astrometry),
-        )
         .route(
             "/images/{image_id}/satellites",
             get(handlers::get_image_satellites).post(handlers::predict_image_satellites),
@@ -320,11 +522,21 @@ async fn run_server_internal(
             "/images/generation-status",
             post(handlers::post_generation_status),
         )
+        .route("/images/diff", get(handlers::get_image_diff))
         .route(
             "/images/{image_id}/preview",
             get(handlers::get_image_preview),
         )
+        .route(
+            "/images/{image_id}/neighbors",
+            get(handlers::get_image_neighbors),
+        )
+        .route(
+            "/images/{image_id}/compare",
+            get(handlers::get_image_compare),
+        )
         .route("/images/{image_id}/stars", get(handlers::get_image_stars))
+        .route("/images/{image_id}/pixels", get(handlers::get_image_pixels))
         .route(
             "/images/{image_id}/annotated",
             get(handlers::get_annotated_image),
@@ -333,11 +545,30 @@ async fn run_server_internal(
             "/images/{image_id}/psf",
             get(handlers::get_psf_visualization),
         )
+        .route("/images/{image_id}/psf/data", get(handlers::get_psf_data))
+        .route(
+            "/images/{image_id}/background",
+            get(handlers::get_background_extraction),
+        )
+        .route("/images/{image_id}/trails", get(handlers::get_image_trails))
         .route(
             "/images/{image_id}/grade",
             put(handlers::update_image_grade),
         )
-        .route("/analysis/sequence", get(handlers::analyze_sequence))
+        .route(
+            "/images/{image_id}/grading-detail",
+            get(handlers::get_grading_detail),
+        )
+        .route(
+            "/images/{image_id}/favorite",
+            post(handlers::add_image_favorite).delete(handlers::remove_image_favorite),
+        )
+        .route("/images/{image_id}/tags", post(handlers::add_image_tags))
+        .route(
+            "/images/{image_id}/tags/{tag}",
+            delete(handlers::remove_image_tag),
+        )
+        .route("/tags", get(handlers::get_tags))
         .route(
             "/analysis/image/{image_id}",
             get(handlers::get_image_quality),
@@ -359,20 +590,46 @@ async fn run_server_internal(
             "/import",
             post(handlers::start_import_route).get(handlers::get_import_progress),
         )
+        .layer(timeout_layer(config.request_timeouts.interactive));
+    // Whole-dataset work answered in one response gets the bulk timeout.
+    let db_bulk_routes: Router<Arc<AppState>> = Router::new()
+        .route(
+            "/images/{image_id}/astrometry",
+            get(handlers::get_image_astrometry).post(handlers::solve_image_astrometry),
+        )
+        .route("/analysis/sequence", get(handlers::analyze_sequence))
         .route("/export", get(handlers::export_archive_route))
-        .route("/export/local", post(handlers::export_local_route));
+        .route("/export/local", post(handlers::export_local_route))
+        .layer(timeout_layer(config.request_timeouts.bulk));
+    let db_routes = db_routes.merge(db_bulk_routes);
+
+    let global_bulk_routes: Router<Arc<AppState>> = Router::new()
+        .route(
+            "/astrometry/catalogs/validate",
+            post(handlers::validate_astrometry_catalogs),
+        )
+        .route(
+            "/databases/{db_id}/sync",
+            post(handlers::sync_database_route),
+        )
+        .route(
+            "/databases/{db_id}/sync/preview",
+            post(handlers::preview_sync_database_route),
+        )
+        .route(
+            "/databases/{db_id}/sync/previews/{preview_id}/apply",
+            post(handlers::apply_sync_database_preview_route),
+        )
+        .layer(timeout_layer(config.request_timeouts.bulk));
 
     // Top-level API: global endpoints + nested per-DB routes.
     let api_routes = Router::new()
         .route("/info", get(handlers::get_server_info))
+        .route("/openapi.json", get(handlers::get_openapi))
         .route(
             "/astrometry/capabilities",
             get(handlers::get_astrometry_capabilities),
         )
-        .route(
-            "/astrometry/catalogs/validate",
-            post(handlers::validate_astrometry_catalogs),
-        )
         .route(
             "/astrometry/catalogs/install",
             get(handlers::get_astrometry_catalog_install)
@@ -387,18 +644,6 @@ async fn run_server_internal(
         // be updated/deleted (only POST collides, and POST /databases/create
         // is exactly this route).
         .route("/databases/create", post(handlers::create_database_route))
-        .route(
-            "/databases/{db_id}/sync",
-            post(handlers::sync_database_route),
-        )
-        .route(
-            "/databases/{db_id}/sync/preview",
-            post(handlers::preview_sync_database_route),
-        )
-        .route(
-            "/databases/{db_id}/sync/previews/{preview_id}/apply",
-            post(handlers::apply_sync_database_preview_route),
-        )
         .route(
             "/databases/{db_id}/sync/previews/{preview_id}",
             get(handlers::get_sync_database_preview_route)
@@ -408,7 +653,26 @@ async fn run_server_internal(
             "/databases/{db_id}",
             put(handlers::update_database_route).delete(handlers::remove_database_route),
         )
+        .layer(timeout_layer(config.request_timeouts.interactive))
+        .merge(global_bulk_routes)
         .nest("/db/{db_id}", db_routes)
+        .with_state(state.clone());
+    let api_routes = match &config.auth_token {
+        Some(token) => {
+            tracing::info!("🔑 API bearer-token authentication enabled");
+            api_routes.layer(axum::middleware::from_fn_with_state(
+                Arc::<str>::from(token.as_str()),
+                auth::require_bearer_token,
+            ))
+        }
+        None => api_routes,
+    };
+
+    // Probes are merged after the trace layer is applied so load balancer
+    // polling doesn't flood the request log.
+    let probe_routes = Router::new()
+        .route("/api/health", get(handlers::get_health))
+        .route("/api/ready", get(handlers::get_readiness))
         .with_state(state);
 
     // Create main app with either embedded or filesystem static serving
@@ -425,7 +689,8 @@ async fn run_server_internal(
             .layer(
                 ServiceBuilder::new()
                     .layer(TraceLayer::new_for_http())
-                    .layer(CorsLayer::permissive()),
+                    .layer(cors_layer(&config.cors))
+                    .layer(compression_layer()),
             )
     } else {
         // Use embedded static serving (for production)
@@ -437,9 +702,11 @@ async fn run_server_internal(
             .layer(
                 ServiceBuilder::new()
                     .layer(TraceLayer::new_for_http())
-                    .layer(CorsLayer::permissive()),
+                    .layer(cors_layer(&config.cors))
+                    .layer(compression_layer()),
             )
     };
+    let app = app.merge(probe_routes);
 
     // Create listener
     let listener =
@@ -450,6 +717,9 @@ async fn run_server_internal(
         config.host,
         config.port
     );
+    if let crate::config::CorsPolicy::AllowList(origins) = &config.cors {
+        tracing::info!("🔒 CORS restricted to: {}", origins.join(", "));
+    }
     tracing::info!(
         "🔧 Environment: RUST_LOG={}",
         std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string())
@@ -463,7 +733,9 @@ async fn run_server_internal(
             axum::serve(listener, app)
                 .with_graceful_shutdown(async {
                     shutdown_rx.await.ok();
-                    tracing::info!("🛑 Graceful shutdown signal received");
+                    tracing::info!(
+                        "🛑 Graceful shutdown signal received, no longer accepting connections"
+                    );
                 })
                 .await?;
         }
@@ -480,7 +752,7 @@ pub async fn run_server_with_shutdown(
     config: ServerConfig,
     shutdown_rx: oneshot::Receiver<()>,
 ) -> anyhow::Result<()> {
-    // Don't initialize tracing here - it should already be initialized by the first server or Tauri app
+    init_tracing_once();
     run_server_internal(config, Some(shutdown_rx)).await
 }
 
@@ -640,6 +912,75 @@ async fn pregenerate_one_image(
     image_id: i32,
     file_only: &str,
     target_name: &str,
+) -> (u64, u64, u64) {
+    pregenerate_formats(
+        state,
+        ctx,
+        image_id,
+        file_only,
+        target_name,
+        &state.pregeneration_config.preview_sizes_to_generate(),
+        state.pregeneration_config.annotated_enabled,
+    )
+    .await
+}
+
+/// Warm the cache for one project's frames (`(image_id, file_only,
+/// target_name)`, as [`pregeneration_entries`] builds them): `sizes`, plus the
+/// annotated image when `annotated`. Frames go one at a time, each generation
+/// holding a permit from the shared generation pool, and progress lands in
+/// the database's `warm_cache` store.
+pub(crate) async fn warm_project_cache(
+    state: Arc<AppState>,
+    ctx: Arc<crate::server::database_context::DatabaseContext>,
+    images: Vec<(i32, String, String)>,
+    sizes: Vec<String>,
+    annotated: bool,
+) {
+    use crate::server::warm_cache as job;
+
+    tracing::info!(
+        "🔥 Warming cache for {} images (db={}): {}{}",
+        images.len(),
+        ctx.id,
+        sizes.join(", "),
+        if annotated { ", annotated" } else { "" }
+    );
+    let sizes: Vec<&str> = sizes.iter().map(String::as_str).collect();
+    for (image_id, file_only, target_name) in &images {
+        let counts = pregenerate_formats(
+            &state,
+            &ctx,
+            *image_id,
+            file_only,
+            target_name,
+            &sizes,
+            annotated,
+        )
+        .await;
+        job::finish_image(&ctx.warm_cache, counts);
+    }
+    job::finish(&ctx.warm_cache);
+    let progress = job::snapshot(&ctx.warm_cache);
+    tracing::info!(
+        "✅ Cache warm for db={}: {} generated, {} skipped, {} errors",
+        ctx.id,
+        progress.generated,
+        progress.skipped,
+        progress.errors
+    );
+}
+
+/// Generate `sizes` previews (and the annotated image when `annotated`) for
+/// one frame. Returns `(generated, skipped, errors)` counts.
+async fn pregenerate_formats(
+    state: &Arc<AppState>,
+    ctx: &Arc<crate::server::database_context::DatabaseContext>,
+    image_id: i32,
+    file_only: &str,
+    target_name: &str,
+    sizes: &[&str],
+    annotated: bool,
 ) -> (u64, u64, u64) {
     let (mut generated, mut skipped, mut errors) = (0u64, 0u64, 0u64);
 
@@ -658,19 +999,11 @@ async fn pregenerate_one_image(
         }
     };
 
-    if state.pregeneration_config.screen_enabled {
-        let r = pregenerate_preview(state, ctx, image_id, file_only, target_name, "screen").await;
-        tally(r, "screen preview");
-    }
-    if state.pregeneration_config.large_enabled {
-        let r = pregenerate_preview(state, ctx, image_id, file_only, target_name, "large").await;
-        tally(r, "large preview");
+    for &size in sizes {
+        let r = pregenerate_preview(state, ctx, image_id, file_only, target_name, size).await;
+        tally(r, &format!("{} preview", size));
     }
-    if state.pregeneration_config.original_enabled {
-        let r = pregenerate_preview(state, ctx, image_id, file_only, target_name, "original").await;
-        tally(r, "original preview");
-    }
-    if state.pregeneration_config.annotated_enabled {
+    if annotated {
         let r = pregenerate_annotated(state, ctx, image_id, file_only, target_name).await;
         tally(r, "annotated image");
     }
@@ -686,10 +1019,18 @@ async fn get_all_images_for_pregeneration(
     // of erroring forever. `.context` keeps the underlying rusqlite error in the
     // chain so the corruption detector can see it.
     let images = ctx.with_db(|db| {
-        db.query_images(None, None, None, None)
+        db.query_light_images(None, None, None, None)
             .context("querying images for pre-generation")
     })?;
 
+    Ok(pregeneration_entries(images))
+}
+
+/// `(image_id, file_only, target_name)` for each image whose metadata names
+/// its file; the rest can't be located, so there's nothing to generate.
+pub(crate) fn pregeneration_entries(
+    images: Vec<(crate::models::AcquiredImage, String, String)>,
+) -> Vec<(i32, String, String)> {
     let mut result = Vec::new();
 
     for (image, _project_name, target_name) in images {
@@ -707,7 +1048,7 @@ async fn get_all_images_for_pregeneration(
         }
     }
 
-    Ok(result)
+    result
 }
 
 async fn pregenerate_preview(
@@ -779,13 +1120,10 @@ async fn pregenerate_preview(
     let fits_path = handlers::find_fits_file(ctx, &image_data, target_name, file_only)
         .map_err(|_| anyhow::anyhow!("FITS file not found for image {}", image_id))?;
 
-    // Determine target dimensions
-    let max_dimensions = match size {
-        "large" => Some((2000, 2000)),
-        "screen" => Some((1200, 1200)),
-        "original" => None,
-        _ => Some((1200, 1200)),
-    };
+    let max_dimensions = state
+        .pregeneration_config
+        .preview_sizes
+        .max_dimensions(size);
 
     // Generate atomically via the shared queue helper (temp file then rename),
     // so a concurrent viewer's readiness poll never observes a half-written PNG.
@@ -795,9 +1133,17 @@ async fn pregenerate_preview(
         kind: crate::server::preview_queue::GenKind::Preview {
             midtone: 0.2,
             shadow: -2.8,
+            logarithmic: false,
+            invert: false,
             max_dimensions,
+            roi: None,
+            background: None,
         },
     };
+    let _permit = state
+        .preview_queue
+        .permit(&state.worker_policy(), &job.fits_path)
+        .await;
     tokio::task::spawn_blocking(move || crate::server::preview_queue::generate(&job)).await??;
 
     tracing::trace!("✅ Generated {} preview for image {}", size, image_id);
@@ -836,15 +1182,17 @@ async fn pregenerate_annotated(
     // Create cache key matching the on-demand annotated format for consistency
     let size = "screen"; // Pre-generation uses screen size for annotated images
     let max_stars = 1000; // Pre-generation uses default max_stars
+    let markers = crate::commands::annotate_stars_common::MarkerOptions::default();
     let cache_key = format!(
-        "annotated_{}_{}_{}_{}_{}_{}_{}",
+        "annotated_{}_{}_{}_{}_{}_{}_{}{}",
         image_id,
         image_data.project_id,
         image_data.target_id,
         image_data.acquired_date.unwrap_or(0),
         file_only.replace(&['.', ' ', '-'][..], "_"),
         size,
-        max_stars
+        max_stars,
+        markers.cache_suffix()
     );
 
     let cache_manager = CacheManager::new(std::path::PathBuf::from(&ctx.cache_dir));
@@ -878,12 +1226,121 @@ async fn pregenerate_annotated(
         fits_path,
         cache_path,
         kind: crate::server::preview_queue::GenKind::Annotated {
-            max_stars: max_stars as usize,
-            size: size.to_string(),
+            selection: crate::commands::annotate_stars_common::AnnotationSelection::top(
+                max_stars as usize,
+            ),
+            max_dimensions: state
+                .pregeneration_config
+                .preview_sizes
+                .max_dimensions(size),
+            svg: false,
+            markers,
         },
     };
+    let _permit = state
+        .preview_queue
+        .permit(&state.worker_policy(), &job.fits_path)
+        .await;
     tokio::task::spawn_blocking(move || crate::server::preview_queue::generate(&job)).await??;
 
     tracing::trace!("✅ Generated annotated image for image {}", image_id);
     Ok(true) // Successfully generated
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn fetch(app: Router, uri: &str) -> (Option<String>, usize) {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .uri(uri)
+            .header("accept-encoding", "gzip, br")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let encoding = response
+            .headers()
+            .get("content-encoding")
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (encoding, body.len())
+    }
+
+    #[tokio::test]
+    async fn json_is_compressed_but_png_is_not() {
+        let images: Vec<_> = (0..2000)
+            .map(|id| {
+                serde_json::json!({
+                    "id": id,
+                    "target_name": "NGC 7000",
+                    "filter_name": "Ha",
+                    "grading_status": 0,
+                    "metadata": {"HFR": 2.31, "DetectedStars": 1500},
+                })
+            })
+            .collect();
+        let json = serde_json::to_vec(&images).unwrap();
+        let json_len = json.len();
+        let app = Router::new()
+            .route(
+                "/api/images",
+                get(move || {
+                    let json = json.clone();
+                    async move { ([("content-type", "application/json")], json) }
+                }),
+            )
+            .route(
+                "/api/preview.png",
+                get(|| async { ([("content-type", "image/png")], vec![7u8; 64 * 1024]) }),
+            )
+            .layer(compression_layer());
+
+        let (encoding, len) = fetch(app.clone(), "/api/images").await;
+        assert!(encoding.is_some());
+        assert!(
+            len * 5 < json_len,
+            "compressed {len} bytes vs {json_len} uncompressed"
+        );
+
+        let (encoding, len) = fetch(app, "/api/preview.png").await;
+        assert!(encoding.is_none());
+        assert_eq!(len, 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn slow_request_times_out_with_504() {
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .layer(timeout_layer(Duration::from_millis(50)))
+            .route("/fast", get(|| async { "done" }));
+
+        let request = |uri: &str| {
+            axum::http::Request::builder()
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let slow = app.clone().oneshot(request("/slow")).await.unwrap();
+        assert_eq!(slow.status(), axum::http::StatusCode::GATEWAY_TIMEOUT);
+        let fast = app.oneshot(request("/fast")).await.unwrap();
+        assert_eq!(fast.status(), axum::http::StatusCode::OK);
+    }
+
+    #[test]
+    fn tracing_init_is_idempotent() {
+        init_tracing_once();
+        init_tracing_once();
+        tracing::info!("still alive after repeated init");
+    }
+}
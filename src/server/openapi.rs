@@ -0,0 +1,320 @@
+//! OpenAPI document served at `/api/openapi.json`.
+//!
+//! Paths come from the `#[utoipa::path]` annotation on each handler and
+//! schemas from the `ToSchema`/`IntoParams` derives on the types they take
+//! and return, so the document follows the code. A new route needs its
+//! handler annotated and listed in [`ApiDoc`].
+
+use utoipa::openapi::path::{Operation, PathItem};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::Ref;
+use utoipa::{Modify, ToResponse, ToSchema};
+
+use super::auth::TOKEN_COOKIE;
+use super::{handlers, scheduler, stack_preview};
+
+/// Routes that answer without the [`ErrorResponse`] envelope.
+const PLAIN_ROUTES: [&str; 3] = ["/api/health", "/api/ready", "/api/openapi.json"];
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    info(
+        title = "PSF Guard",
+        description = "HTTP API of `psf-guard server`. Every route sits under `/api`; \
+                       per-database routes are nested under `/api/db/{db_id}`."
+    ),
+    paths(
+        handlers::refresh_file_cache,
+        handlers::cancel_cache_refresh,
+        handlers::get_cache_refresh_progress,
+        handlers::refresh_directory_tree_cache,
+        handlers::get_directory_cache,
+        handlers::list_projects,
+        handlers::update_project_route,
+        handlers::merge_project_route,
+        handlers::export_project_curation,
+        handlers::import_project_curation,
+        scheduler::get_project_scheduler,
+        handlers::start_project_warm_cache,
+        handlers::get_pregeneration_progress,
+        handlers::update_target_route,
+        handlers::get_target_trend,
+        handlers::get_target_integration,
+        handlers::get_target_timeline,
+        handlers::get_focus_stability,
+        handlers::get_target_contact_sheet,
+        handlers::merge_target_route,
+        scheduler::create_exposure_plan,
+        scheduler::update_exposure_plan,
+        handlers::get_projects_overview,
+        handlers::get_targets_overview,
+        handlers::get_overall_stats,
+        handlers::get_rejection_stats,
+        handlers::list_targets,
+        stack_preview::start_stack_previews,
+        stack_preview::get_latest_stack_previews,
+        stack_preview::color::get_stack_color_catalog,
+        stack_preview::color::start_stack_color,
+        stack_preview::color::get_stack_color_job,
+        stack_preview::get_stack_preview_job,
+        stack_preview::get_stack_preview_image,
+        stack_preview::apply_stack_preview_stretch,
+        stack_preview::download_stack_preview_fits,
+        stack_preview::color::get_stack_color_image,
+        stack_preview::stretch::get_stack_stretch_image,
+        stack_preview::stretch::download_stack_stretch_fits,
+        stack_preview::color::download_stack_color_fits,
+        handlers::get_images,
+        handlers::get_sessions,
+        handlers::get_image,
+        handlers::get_image_satellites,
+        handlers::predict_image_satellites,
+        handlers::post_generation_status,
+        handlers::get_image_diff,
+        handlers::get_image_preview,
+        handlers::get_image_neighbors,
+        handlers::get_image_compare,
+        handlers::get_image_stars,
+        handlers::get_image_pixels,
+        handlers::get_annotated_image,
+        handlers::get_psf_visualization,
+        handlers::get_psf_data,
+        handlers::get_background_extraction,
+        handlers::get_image_trails,
+        handlers::update_image_grade,
+        handlers::get_grading_detail,
+        handlers::add_image_favorite,
+        handlers::remove_image_favorite,
+        handlers::add_image_tags,
+        handlers::remove_image_tag,
+        handlers::get_tags,
+        handlers::get_image_quality,
+        handlers::start_spatial_scan,
+        handlers::get_spatial_scan_progress,
+        handlers::start_quality_backfill_route,
+        handlers::get_quality_backfill_progress,
+        handlers::start_import_route,
+        handlers::get_import_progress,
+        handlers::get_image_astrometry,
+        handlers::solve_image_astrometry,
+        handlers::analyze_sequence,
+        handlers::export_archive_route,
+        handlers::export_local_route,
+        handlers::validate_astrometry_catalogs,
+        handlers::sync_database_route,
+        handlers::preview_sync_database_route,
+        handlers::apply_sync_database_preview_route,
+        handlers::get_server_info,
+        handlers::get_openapi,
+        handlers::get_astrometry_capabilities,
+        handlers::get_astrometry_catalog_install,
+        handlers::start_astrometry_catalog_install,
+        handlers::list_databases,
+        handlers::add_database_route,
+        handlers::create_database_route,
+        handlers::get_sync_database_preview_route,
+        handlers::delete_sync_database_preview_route,
+        handlers::update_database_route,
+        handlers::remove_database_route,
+        handlers::get_health,
+        handlers::get_readiness,
+    ),
+    components(responses(ErrorResponse)),
+    modifiers(&SpatialScanAlias, &DefaultErrorResponse, &TokenSecuritySchemes),
+    security((), ("bearerAuth" = []), ("tokenCookie" = []), ("tokenQuery" = []))
+)]
+pub struct ApiDoc;
+
+/// Raw bytes of a non-JSON response body: an image, FITS file or zip archive.
+#[derive(ToSchema)]
+#[schema(value_type = String, format = Binary)]
+pub struct Binary(Vec<u8>);
+
+/// Body of every failed `/api` request: `success` is false and `error`
+/// carries the message.
+#[derive(ToResponse)]
+#[response(description = "Error")]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub error: String,
+}
+
+/// The operations of one path item, for modifiers that touch them all.
+fn operations(item: &mut PathItem) -> impl Iterator<Item = &mut Operation> {
+    [
+        &mut item.get,
+        &mut item.put,
+        &mut item.post,
+        &mut item.delete,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// `/analysis/spatial-scan` is routed to the same handlers as
+/// `/analysis/quality-scan`, which carry the annotations; copy the path item
+/// across with distinct operation ids.
+struct SpatialScanAlias;
+
+impl Modify for SpatialScanAlias {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let paths = &mut openapi.paths.paths;
+        let Some(mut item) = paths.get("/api/db/{db_id}/analysis/quality-scan").cloned() else {
+            return;
+        };
+        for operation in operations(&mut item) {
+            operation.operation_id = operation
+                .operation_id
+                .take()
+                .map(|id| format!("{id}_alias"));
+        }
+        paths.insert("/api/db/{db_id}/analysis/spatial-scan".to_string(), item);
+    }
+}
+
+/// Adds [`ErrorResponse`] as the `default` response of every operation
+/// except the [`PLAIN_ROUTES`], rather than repeating it on each handler.
+struct DefaultErrorResponse;
+
+impl Modify for DefaultErrorResponse {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if PLAIN_ROUTES.contains(&path.as_str()) {
+                continue;
+            }
+            for operation in operations(item) {
+                operation.responses.responses.insert(
+                    "default".to_string(),
+                    Ref::from_response_name("ErrorResponse").into(),
+                );
+            }
+        }
+    }
+}
+
+/// The three ways [`super::auth`] accepts the token when one is configured.
+struct TokenSecuritySchemes;
+
+impl Modify for TokenSecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearerAuth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("Required when the server runs with an auth token."))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "tokenCookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                TOKEN_COOKIE,
+                "Percent-encoded token; GET and HEAD only.",
+            ))),
+        );
+        components.add_security_scheme(
+            "tokenQuery",
+            SecurityScheme::ApiKey(ApiKey::Query(ApiKeyValue::with_description(
+                "token",
+                "GET and HEAD only, for image and download URLs.",
+            ))),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::api::{FavoriteResponse, HealthStatus, SchedulerSyncTableCounts};
+    use std::collections::BTreeSet;
+    use utoipa::OpenApi;
+
+    fn document() -> serde_json::Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    /// Property names the document declares for a component schema.
+    fn schema_properties(document: &serde_json::Value, name: &str) -> BTreeSet<String> {
+        document["components"]["schemas"][name]["properties"]
+            .as_object()
+            .unwrap_or_else(|| panic!("no schema {name}"))
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn serialized_keys(value: impl serde::Serialize) -> BTreeSet<String> {
+        serde_json::to_value(value)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn operation_ids_are_unique_and_the_alias_is_documented() {
+        let document = document();
+        let paths = document["paths"].as_object().unwrap();
+        let mut ids = BTreeSet::new();
+        for item in paths.values() {
+            for operation in item.as_object().unwrap().values() {
+                let id = operation["operationId"].as_str().unwrap();
+                assert!(ids.insert(id.to_string()), "duplicate operationId {id}");
+            }
+        }
+        assert!(ids.len() > 90, "paths missing: {ids:?}");
+        let alias = &paths["/api/db/{db_id}/analysis/spatial-scan"];
+        assert_eq!(alias["post"]["operationId"], "start_spatial_scan_alias");
+        assert!(alias["get"].is_object());
+    }
+
+    #[test]
+    fn operations_carry_parameters_errors_and_auth() {
+        let document = document();
+        let images = &document["paths"]["/api/db/{db_id}/images"]["get"];
+        let parameters: Vec<(&str, &str)> = images["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| (p["in"].as_str().unwrap(), p["name"].as_str().unwrap()))
+            .collect();
+        assert!(parameters.contains(&("path", "db_id")));
+        assert!(parameters.contains(&("query", "project_id")));
+        assert_eq!(
+            images["responses"]["default"]["$ref"],
+            "#/components/responses/ErrorResponse"
+        );
+        assert!(document["paths"]["/api/health"]["get"]["responses"]
+            .get("default")
+            .is_none());
+
+        let schemes = &document["components"]["securitySchemes"];
+        assert_eq!(schemes["bearerAuth"]["scheme"], "bearer");
+        assert_eq!(schemes["tokenCookie"]["name"], TOKEN_COOKIE);
+        assert_eq!(schemes["tokenQuery"]["in"], "query");
+    }
+
+    #[test]
+    fn schemas_match_the_serialized_field_names() {
+        let document = document();
+        assert_eq!(
+            schema_properties(&document, "HealthStatus"),
+            serialized_keys(HealthStatus { status: "ok" })
+        );
+        assert_eq!(
+            schema_properties(&document, "FavoriteResponse"),
+            serialized_keys(FavoriteResponse {
+                image_id: 1,
+                favorite: true,
+            })
+        );
+        assert_eq!(
+            schema_properties(&document, "SchedulerSyncTableCounts"),
+            serialized_keys(SchedulerSyncTableCounts::default())
+        );
+    }
+}
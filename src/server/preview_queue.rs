@@ -20,25 +20,63 @@
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
 use serde::Serialize;
-use tokio::sync::Semaphore;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use utoipa::ToSchema;
 
 use crate::concurrency::{self, Priority, WorkerPolicy};
 use crate::server::state::AppState;
 
-/// What to generate for a job. Mirrors the two artifact handlers.
+/// What to generate for a job. Mirrors the artifact handlers.
 #[derive(Debug, Clone)]
 pub enum GenKind {
     Preview {
         midtone: f64,
         shadow: f64,
+        logarithmic: bool,
+        invert: bool,
         max_dimensions: Option<(u32, u32)>,
+        /// Render only this region (full-resolution pixels) of the frame.
+        roi: Option<crate::image_analysis::Roi>,
+        /// Show the frame with its background removed, or just the background.
+        background: Option<crate::commands::background_extract::BackgroundView>,
     },
     Annotated {
-        max_stars: usize,
-        size: String,
+        selection: crate::commands::annotate_stars_common::AnnotationSelection,
+        max_dimensions: Option<(u32, u32)>,
+        /// Vector markers over an embedded raster instead of a PNG.
+        svg: bool,
+        markers: crate::commands::annotate_stars_common::MarkerOptions,
+    },
+    /// Two stretches of the same frame, side by side; `(midtone, shadow)`
+    /// per half.
+    Compare {
+        a: (f64, f64),
+        b: (f64, f64),
+        max_dimensions: Option<(u32, u32)>,
+    },
+    /// Difference heatmap of `fits_path` against `other`; the summary is
+    /// written next to the PNG (see [`diff_stats_path`]).
+    Diff {
+        other: PathBuf,
+        max_dimensions: Option<(u32, u32)>,
+    },
+    /// The frame as a 16-bit or float TIFF; `stretch` is `(midtone, shadow)`
+    /// for the MTF stretch, linear otherwise.
+    Tiff {
+        depth: crate::commands::fits_to_tiff::TiffDepth,
+        stretch: Option<(f64, f64)>,
+        max_dimensions: Option<(u32, u32)>,
+        roi: Option<crate::image_analysis::Roi>,
+    },
+    /// A target's frames tiled into one sheet. `fits_path` is the first frame
+    /// found (used to size the pool); frames load one at a time.
+    ContactSheet {
+        frames: Vec<crate::commands::contact_sheet::SheetFrame>,
+        layout: crate::commands::contact_sheet::SheetLayout,
     },
 }
 
@@ -51,7 +89,7 @@ pub struct GenJob {
 }
 
 /// Readiness of a cache artifact, as reported to the polling frontend.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum GenerationState {
     Ready,
@@ -60,7 +98,7 @@ pub enum GenerationState {
 }
 
 /// Status payload for one artifact (batch-status response element).
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GenerationStatus {
     pub state: GenerationState,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -91,12 +129,22 @@ impl GenerationStatus {
 /// Recent-error map cap, so a run of unresolvable frames can't grow it forever.
 const MAX_RECENT_ERRORS: usize = 512;
 
+/// How long a failed generation is reported as `error` before the next status
+/// poll enqueues it again. Failures are not a permanent negative result: on
+/// networked storage most are transient and succeed a little later.
+pub const ERROR_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+struct RecentError {
+    message: String,
+    at: Instant,
+}
+
 #[derive(Default)]
 struct QueueInner {
     /// `cache_path`s currently being generated (dedup).
     in_flight: HashSet<PathBuf>,
-    /// `cache_path` -> last generation error message.
-    recent_errors: HashMap<PathBuf, String>,
+    /// `cache_path` -> last generation error, until it expires.
+    recent_errors: HashMap<PathBuf, RecentError>,
 }
 
 /// Process-global interactive preview/annotated generation queue. Held on
@@ -112,7 +160,8 @@ pub struct PreviewQueue {
 
 impl PreviewQueue {
     /// Report the state of one artifact by its `cache_path`. Pure read — does
-    /// not enqueue (the caller enqueues when appropriate).
+    /// not enqueue (the caller enqueues when appropriate). An error older than
+    /// [`ERROR_RETRY_AFTER`] reads as unknown, so the caller retries it.
     pub fn status(&self, cache_path: &Path) -> Option<GenerationStatus> {
         // A completed artifact is always the truth, even if a stale error entry
         // lingers.
@@ -126,7 +175,8 @@ impl PreviewQueue {
         inner
             .recent_errors
             .get(cache_path)
-            .map(|e| GenerationStatus::error(e.clone()))
+            .filter(|e| e.at.elapsed() < ERROR_RETRY_AFTER)
+            .map(|e| GenerationStatus::error(e.message.clone()))
     }
 
     /// Lazily create (and reuse) the concurrency-bounding semaphore, sized from
@@ -137,7 +187,12 @@ impl PreviewQueue {
             return Arc::clone(s);
         }
         let frame_pixels = concurrency::probe_frame_pixels(sample_fits);
-        let budget = concurrency::plan_workers(None, policy, Priority::Interactive, frame_pixels);
+        let budget = concurrency::plan_workers(
+            policy.generation_workers,
+            policy,
+            Priority::Interactive,
+            frame_pixels,
+        );
         tracing::info!(
             "🖼️ Preview generation pool: {} worker(s) — {}",
             budget.workers,
@@ -147,6 +202,55 @@ impl PreviewQueue {
         *slot = Some(Arc::clone(&s));
         s
     }
+
+    /// Wait for a generation slot. Hold the permit for the whole generation;
+    /// handlers that render inside the request (PSF images) share the pool
+    /// with queued jobs, so a burst of misses queues instead of piling onto
+    /// the blocking pool.
+    pub async fn permit(
+        &self,
+        policy: &WorkerPolicy,
+        sample_fits: &Path,
+    ) -> Option<OwnedSemaphorePermit> {
+        // `None` only if the semaphore were closed, which never happens.
+        self.semaphore(policy, sample_fits)
+            .acquire_owned()
+            .await
+            .ok()
+    }
+}
+
+/// Per-key locks for artifacts rendered inside the request (stars, PSF
+/// images), so identical concurrent misses generate once: the first holder
+/// renders and writes the cache, the rest wait on the key and then find it
+/// cached. Queued artifacts don't need this; [`AppState::enqueue_preview`]
+/// already dedups them by `cache_path`.
+#[derive(Default)]
+pub struct SingleFlight {
+    /// `cache_path` -> lock of the generation in progress. Weak, so a key is
+    /// gone once its last waiter finishes.
+    keys: Mutex<HashMap<PathBuf, Weak<tokio::sync::Mutex<()>>>>,
+}
+
+impl SingleFlight {
+    /// Wait until no other request is generating `key`, then hold it. Check
+    /// the cache again after this returns: a request that waited usually
+    /// finds the artifact there.
+    pub async fn lock(&self, key: &Path) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = {
+            let mut keys = self.keys.lock().unwrap();
+            keys.retain(|_, lock| lock.strong_count() > 0);
+            match keys.get(key).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    keys.insert(key.to_path_buf(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
 }
 
 impl AppState {
@@ -164,17 +268,16 @@ impl AppState {
             }
         }
 
-        let sem = self
-            .preview_queue
-            .semaphore(&self.worker_policy(), &job.fits_path);
         let state = Arc::clone(self);
         tokio::spawn(async move {
             // Mark interactive-active for the whole job so background pregen
             // yields; drops even if the task is cancelled/panics.
             let _guard = state.begin_interactive_job();
-            // Bound concurrency to the interactive budget. A closed semaphore
-            // (never happens — we never close it) would just skip the permit.
-            let _permit = sem.acquire_owned().await;
+            // Bound concurrency to the generation budget.
+            let _permit = state
+                .preview_queue
+                .permit(&state.worker_policy(), &job.fits_path)
+                .await;
 
             let cache_path = job.cache_path.clone();
             let outcome = tokio::task::spawn_blocking(move || generate(&job)).await;
@@ -201,7 +304,13 @@ fn record_error(inner: &mut QueueInner, cache_path: PathBuf, msg: String) {
     if inner.recent_errors.len() >= MAX_RECENT_ERRORS {
         inner.recent_errors.clear();
     }
-    inner.recent_errors.insert(cache_path, msg);
+    inner.recent_errors.insert(
+        cache_path,
+        RecentError {
+            message: msg,
+            at: Instant::now(),
+        },
+    );
 }
 
 /// Generate one artifact to a unique temp path, then atomically rename into
@@ -215,18 +324,78 @@ pub fn generate(job: &GenJob) -> anyhow::Result<()> {
         GenKind::Preview {
             midtone,
             shadow,
+            logarithmic,
+            invert,
+            max_dimensions,
+            roi,
+            background,
+        } if roi.is_some() || background.is_some() => generate_processed(
+            &job.fits_path,
+            &tmp,
+            roi.as_ref(),
+            *background,
+            (*midtone, *shadow),
+            (*logarithmic, *invert),
+            *max_dimensions,
+        ),
+        GenKind::Preview {
+            midtone,
+            shadow,
+            logarithmic,
+            invert,
             max_dimensions,
+            ..
         } => crate::commands::stretch_to_png::stretch_to_png_with_resize(
             &job.fits_path.to_string_lossy(),
             Some(tmp.to_string_lossy().into_owned()),
             *midtone,
             *shadow,
-            false, // logarithmic
-            false, // invert
+            *logarithmic,
+            *invert,
+            *max_dimensions,
+        ),
+        GenKind::Annotated {
+            selection,
+            max_dimensions,
+            svg: false,
+            markers,
+        } => generate_annotated(&job.fits_path, &tmp, *max_dimensions, selection, markers),
+        GenKind::Annotated {
+            selection,
+            max_dimensions,
+            svg: true,
+            markers,
+        } => generate_annotated_svg(&job.fits_path, &tmp, *max_dimensions, selection, markers),
+        GenKind::Compare {
+            a,
+            b,
+            max_dimensions,
+        } => generate_compare(&job.fits_path, &tmp, *a, *b, *max_dimensions),
+        GenKind::Diff {
+            other,
+            max_dimensions,
+        } => generate_diff(
+            &job.fits_path,
+            other,
+            &tmp,
+            &job.cache_path,
+            *max_dimensions,
+        ),
+        GenKind::Tiff {
+            depth,
+            stretch,
+            max_dimensions,
+            roi,
+        } => crate::commands::fits_to_tiff::render_tiff(
+            &job.fits_path,
+            &tmp,
+            *depth,
+            *stretch,
+            roi.as_ref(),
             *max_dimensions,
         ),
-        GenKind::Annotated { max_stars, size } => {
-            generate_annotated(&job.fits_path, &tmp, size, *max_stars)
+        GenKind::ContactSheet { frames, layout } => {
+            crate::commands::contact_sheet::write_contact_sheet(frames, layout, &tmp).map(|_| ())
         }
     };
 
@@ -259,17 +428,20 @@ pub fn temp_path(cache_path: &Path) -> PathBuf {
 pub fn generate_annotated(
     fits_path: &Path,
     out_path: &Path,
-    size: &str,
-    max_stars: usize,
+    max_dimensions: Option<(u32, u32)>,
+    selection: &crate::commands::annotate_stars_common::AnnotationSelection,
+    markers: &crate::commands::annotate_stars_common::MarkerOptions,
 ) -> anyhow::Result<()> {
     use crate::commands::annotate_stars_common::create_annotated_image;
-    use crate::image_analysis::FitsImage;
     use image::codecs::png::{CompressionType, FilterType, PngEncoder};
     use image::{ColorType, ImageEncoder, Rgb};
 
-    let fits = FitsImage::from_file(fits_path)?;
-    let rgb = create_annotated_image(&fits, max_stars, 0.2, -2.8, Rgb([255, 255, 0]))?;
-    let final_image = resize_rgb_for_size(rgb, fits.width, fits.height, size);
+    let fits = crate::fits_read::load_with_retry(fits_path)?;
+    let rgb = create_annotated_image(&fits, selection, 0.2, -2.8, Rgb([255, 255, 0]), markers)?;
+    let final_image = match max_dimensions {
+        Some((max_w, max_h)) => crate::image_utils::resize_to_max(rgb, max_w, max_h),
+        None => rgb,
+    };
 
     let file = std::fs::File::create(out_path)?;
     let writer = std::io::BufWriter::new(file);
@@ -279,40 +451,126 @@ pub fn generate_annotated(
     Ok(())
 }
 
-/// Resize an RGB image to the requested size bucket (matches the preview
-/// dimension buckets): `large` → 2000px, `original` → none, else → 1200px.
-fn resize_rgb_for_size(
-    img: image::RgbImage,
-    width: usize,
-    height: usize,
-    size: &str,
-) -> image::RgbImage {
-    let cap: Option<u32> = match size {
-        "original" => None,
-        "large" => Some(2000),
-        _ => Some(1200),
-    };
-    let Some(cap) = cap else { return img };
-    if width as u32 <= cap && height as u32 <= cap {
-        return img;
+/// Build the annotated SVG for a frame: the same stars as the PNG, as vector
+/// circles and HFR labels over the embedded stretched frame.
+pub fn generate_annotated_svg(
+    fits_path: &Path,
+    out_path: &Path,
+    max_dimensions: Option<(u32, u32)>,
+    selection: &crate::commands::annotate_stars_common::AnnotationSelection,
+    markers: &crate::commands::annotate_stars_common::MarkerOptions,
+) -> anyhow::Result<()> {
+    use crate::commands::annotate_stars_common::create_annotated_svg;
+    use image::Rgb;
+
+    let fits = crate::fits_read::load_with_retry(fits_path)?;
+    let svg = create_annotated_svg(
+        &fits,
+        selection,
+        0.2,
+        -2.8,
+        Rgb([255, 255, 0]),
+        markers,
+        max_dimensions,
+    )?;
+    std::fs::write(out_path, svg)?;
+    Ok(())
+}
+
+/// Build the side-by-side stretch comparison PNG for a frame. The frame is
+/// loaded once and stretched twice.
+pub fn generate_compare(
+    fits_path: &Path,
+    out_path: &Path,
+    (midtone_a, shadow_a): (f64, f64),
+    (midtone_b, shadow_b): (f64, f64),
+    max_dimensions: Option<(u32, u32)>,
+) -> anyhow::Result<()> {
+    use crate::commands::stretch_to_png::{compose_side_by_side, render_stretched, write_gray_png};
+
+    let fits = crate::fits_read::load_with_retry(fits_path)?;
+    let left = render_stretched(&fits, midtone_a, shadow_a, max_dimensions)?;
+    let right = render_stretched(&fits, midtone_b, shadow_b, max_dimensions)?;
+    write_gray_png(&compose_side_by_side(&left, &right), out_path)
+}
+
+/// Build a preview that needs the frame processed before the stretch: the
+/// background removed (or shown) over the whole frame, then cropped to a
+/// region. Only the crop is stretched, so the auto-stretch adapts to the
+/// region (a galaxy core isn't blown out by the frame's background
+/// statistics).
+pub fn generate_processed(
+    fits_path: &Path,
+    out_path: &Path,
+    roi: Option<&crate::image_analysis::Roi>,
+    background: Option<crate::commands::background_extract::BackgroundView>,
+    (midtone, shadow): (f64, f64),
+    (logarithmic, invert): (bool, bool),
+    max_dimensions: Option<(u32, u32)>,
+) -> anyhow::Result<()> {
+    use crate::commands::stretch_to_png::{render_preview, write_gray_png};
+
+    let mut fits = crate::fits_read::load_with_retry(fits_path)?;
+    if let Some(view) = background {
+        fits = view.apply(&fits);
     }
-    let aspect = width as f32 / height as f32;
-    let (nw, nh) = if width > height {
-        (cap, (cap as f32 / aspect) as u32)
-    } else {
-        ((cap as f32 * aspect) as u32, cap)
-    };
-    image::imageops::resize(&img, nw, nh, image::imageops::FilterType::Lanczos3)
+    if let Some(roi) = roi {
+        fits = fits.cropped(roi)?;
+    }
+    let image = render_preview(&fits, midtone, shadow, logarithmic, invert, max_dimensions)?;
+    write_gray_png(&image, out_path)
+}
+
+/// Where the diff summary for the heatmap at `cache_path` is kept.
+pub fn diff_stats_path(cache_path: &Path) -> PathBuf {
+    cache_path.with_extension("json")
 }
 
-/// Pixel dimension bucket for a preview `size` (shared by the preview handler
-/// and the queue): `large` → 2000², `original` → none, else → 1200².
-pub fn max_dimensions_for_size(size: &str) -> Option<(u32, u32)> {
-    match size {
-        "large" => Some((2000, 2000)),
-        "original" => None,
-        _ => Some((1200, 1200)),
+/// Build the difference heatmap of two frames. The stats sidecar is renamed
+/// into place before the PNG, so a ready PNG always has its summary.
+pub fn generate_diff(
+    fits_a: &Path,
+    fits_b: &Path,
+    out_path: &Path,
+    cache_path: &Path,
+    max_dimensions: Option<(u32, u32)>,
+) -> anyhow::Result<()> {
+    use crate::commands::stretch_to_png::render_stretched;
+    use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+    use image::{ColorType, ImageEncoder};
+
+    // One frame in memory at a time; only the stretched renderings are kept.
+    let a = render_stretched(
+        &crate::fits_read::load_with_retry(fits_a)?,
+        0.2,
+        -2.8,
+        max_dimensions,
+    )?;
+    let b = render_stretched(
+        &crate::fits_read::load_with_retry(fits_b)?,
+        0.2,
+        -2.8,
+        max_dimensions,
+    )?;
+    let (heatmap, stats) = crate::image_diff::difference(&a, &b);
+
+    let stats_path = diff_stats_path(cache_path);
+    let stats_tmp = temp_path(&stats_path);
+    std::fs::write(&stats_tmp, serde_json::to_vec(&stats)?)?;
+    if let Err(e) = std::fs::rename(&stats_tmp, &stats_path) {
+        let _ = std::fs::remove_file(&stats_tmp);
+        return Err(e.into());
     }
+
+    let file = std::fs::File::create(out_path)?;
+    let encoder = PngEncoder::new_with_quality(
+        std::io::BufWriter::new(file),
+        CompressionType::Best,
+        FilterType::Adaptive,
+    );
+    let (w, h) = heatmap.dimensions();
+    encoder.write_image(&heatmap, w, h, ColorType::Rgb8.into())?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -344,21 +602,88 @@ mod tests {
         assert_eq!(q.status(&p).unwrap().state, GenerationState::Generating);
 
         q.inner.lock().unwrap().in_flight.remove(&p);
-        q.inner
-            .lock()
-            .unwrap()
-            .recent_errors
-            .insert(p.clone(), "boom".into());
+        record_error(&mut q.inner.lock().unwrap(), p.clone(), "boom".into());
         let s = q.status(&p).unwrap();
         assert_eq!(s.state, GenerationState::Error);
         assert_eq!(s.error.as_deref(), Some("boom"));
     }
 
     #[test]
-    fn max_dimensions_buckets() {
-        assert_eq!(max_dimensions_for_size("large"), Some((2000, 2000)));
-        assert_eq!(max_dimensions_for_size("screen"), Some((1200, 1200)));
-        assert_eq!(max_dimensions_for_size("original"), None);
-        assert_eq!(max_dimensions_for_size("weird"), Some((1200, 1200)));
+    fn status_forgets_errors_after_retry_window() {
+        let q = PreviewQueue::default();
+        let p = PathBuf::from("/nonexistent/z.png");
+        let Some(at) = Instant::now().checked_sub(ERROR_RETRY_AFTER) else {
+            return; // monotonic clock too young to backdate
+        };
+        q.inner.lock().unwrap().recent_errors.insert(
+            p.clone(),
+            RecentError {
+                message: "transient".into(),
+                at,
+            },
+        );
+        // Expired: unknown again, so the status poll re-enqueues it.
+        assert!(q.status(&p).is_none());
+    }
+
+    #[tokio::test]
+    async fn permits_bound_concurrent_generations() {
+        let q = Arc::new(PreviewQueue::default());
+        let policy = WorkerPolicy::default().with_generation_workers(Some(2));
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let (q, running, peak) = (Arc::clone(&q), Arc::clone(&running), Arc::clone(&peak));
+                tokio::spawn(async move {
+                    let _permit = q
+                        .permit(&policy, Path::new("/nonexistent/frame.fits"))
+                        .await;
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn single_flight_generates_a_shared_miss_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("stars.json");
+        let flights = Arc::new(SingleFlight::default());
+        let decodes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let (flights, decodes, cache_path) = (
+                    Arc::clone(&flights),
+                    Arc::clone(&decodes),
+                    cache_path.clone(),
+                );
+                tokio::spawn(async move {
+                    let _flight = flights.lock(&cache_path).await;
+                    if !cache_path.exists() {
+                        decodes.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        std::fs::write(&cache_path, "[]").unwrap();
+                    }
+                    std::fs::read_to_string(&cache_path).unwrap()
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), "[]");
+        }
+        assert_eq!(decodes.load(Ordering::SeqCst), 1);
+        // Nobody holds the key any more, so it is dropped on the next lock.
+        drop(flights.lock(Path::new("other")).await);
+        assert_eq!(flights.keys.lock().unwrap().len(), 1);
     }
 }
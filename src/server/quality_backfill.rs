@@ -6,8 +6,9 @@
 
 use serde::Serialize;
 use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
 pub struct QualityBackfillProgress {
     pub running: bool,
     pub force: bool,
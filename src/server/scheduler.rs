@@ -8,14 +8,15 @@ use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 use crate::db::Database;
 use crate::server::api::{ApiResponse, UpdateProjectRequest, UpdateTargetRequest};
 use crate::server::extract::DbContext;
-use crate::server::handlers::{require_database_management_allowed, AppError};
+use crate::server::handlers::{require_database_management_allowed, require_writable, AppError};
 use crate::server::state::AppState;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ProjectSchedulerResponse {
     pub id: i32,
     pub profile_id: String,
@@ -42,7 +43,7 @@ pub struct ProjectSchedulerResponse {
     pub targets: Vec<SchedulerTargetResponse>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ExposureTemplateResponse {
     pub id: i32,
     pub profile_id: String,
@@ -67,7 +68,7 @@ pub struct ExposureTemplateResponse {
     pub plan_count: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SchedulerTargetResponse {
     pub id: i32,
     pub name: String,
@@ -80,7 +81,7 @@ pub struct SchedulerTargetResponse {
     pub exposure_plans: Vec<ExposurePlanResponse>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ExposurePlanResponse {
     pub id: i32,
     pub exposure_template_id: i32,
@@ -97,7 +98,7 @@ pub struct ExposurePlanResponse {
     pub enabled: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateExposurePlanRequest {
     /// Reuse this exact profile-scoped template. When absent, the settings
     /// below match an existing template or create a new one.
@@ -121,7 +122,7 @@ pub struct CreateExposurePlanRequest {
     pub enabled: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateExposurePlanRequest {
     pub exposure: f64,
     pub desired: i32,
@@ -372,6 +373,13 @@ fn plans_for_target(
     .collect()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/scheduler",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<ProjectSchedulerResponse>))
+)]
 pub async fn get_project_scheduler(
     ctx: DbContext,
     Path((_db_id, project_id)): Path<(String, i32)>,
@@ -547,6 +555,14 @@ pub fn update_target_fields(
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/targets/{target_id}/exposure-plans",
+    tag = "targets",
+    params(("db_id" = String, Path), ("target_id" = i32, Path)),
+    request_body = CreateExposurePlanRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<ExposurePlanResponse>))
+)]
 pub async fn create_exposure_plan(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -554,6 +570,7 @@ pub async fn create_exposure_plan(
     Json(req): Json<CreateExposurePlanRequest>,
 ) -> Result<Json<ApiResponse<ExposurePlanResponse>>, AppError> {
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
     let filter_name = req.filter_name.as_deref().unwrap_or_default().trim();
     if req.exposure_template_id.is_none() && filter_name.is_empty() {
         return Err(AppError::BadRequest("filter name must not be empty".into()));
@@ -684,6 +701,14 @@ pub async fn create_exposure_plan(
     Ok(Json(ApiResponse::success(plan)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/db/{db_id}/exposure-plans/{plan_id}",
+    tag = "exposure-plans",
+    params(("db_id" = String, Path), ("plan_id" = i32, Path)),
+    request_body = UpdateExposurePlanRequest,
+    responses((status = 200, description = "Done; `data` is a free-form object"))
+)]
 pub async fn update_exposure_plan(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -691,6 +716,7 @@ pub async fn update_exposure_plan(
     Json(req): Json<UpdateExposurePlanRequest>,
 ) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
     require_database_management_allowed(&state)?;
+    require_writable(&state)?;
     validate_plan_values(req.exposure, req.desired)?;
     let conn = ctx.db();
     let conn = conn.lock().map_err(AppError::db)?;
@@ -16,6 +16,7 @@ use std::sync::{Arc, RwLock};
 
 use seiza_stretch::{stretch_u16_to_u16, StretchParams};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::image_analysis::FitsImage;
 use crate::nina_star_detection::{
@@ -85,7 +86,7 @@ pub const QUALITY_DETECTOR: &str = "nina_fast";
 pub const QUALITY_DETECTOR_VERSION: u32 = 1;
 
 /// Progress of the (singleton per-DB) spatial scan.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
 pub struct SpatialScanProgress {
     pub running: bool,
     /// `spatial`, `astrometry`, or `complete`.
@@ -28,8 +28,10 @@ use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::Semaphore;
 use tokio_util::io::ReaderStream;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::acquisition_context::FramingResolver;
+use crate::computed_metrics::ComputedMetrics;
 use crate::db::Database;
 use crate::models::AcquiredImage;
 use crate::sequence_analysis::{
@@ -39,6 +41,7 @@ use crate::server::api::ApiResponse;
 use crate::server::database_context::DatabaseContext;
 use crate::server::extract::DbContext;
 use crate::server::handlers::AppError;
+use crate::server::openapi::Binary;
 use crate::server::state::AppState;
 
 pub const SEIZA_STACKING_VERSION: &str = "0.1.0";
@@ -50,7 +53,7 @@ const MAX_REMEMBERED_JOBS: usize = 64;
 const PREVIEW_MAX_DIMENSION: u32 = 2400;
 const STACK_BYTES_PER_OUTPUT_SAMPLE: u64 = 40;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct StackPreviewRequest {
     pub image_ids: Vec<i32>,
     #[serde(default)]
@@ -59,7 +62,7 @@ pub struct StackPreviewRequest {
     pub force: bool,
 }
 
-#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackPreviewImageSize {
     #[default]
@@ -67,13 +70,14 @@ pub enum StackPreviewImageSize {
     Original,
 }
 
-#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct StackPreviewImageQuery {
     #[serde(default)]
     pub size: StackPreviewImageSize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackJobState {
     Queued,
@@ -82,7 +86,7 @@ pub enum StackJobState {
     Failed,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackGroupState {
     Queued,
@@ -92,7 +96,7 @@ pub enum StackGroupState {
     Error,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StackFrameDecision {
     pub image_id: i32,
     pub disposition: String,
@@ -105,13 +109,13 @@ pub struct StackFrameDecision {
     pub integrated_fraction: Option<f32>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct StackInputImage {
     pub image_id: i32,
     pub grading_status: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StackGroupStatus {
     pub index: usize,
     pub target_id: i32,
@@ -137,7 +141,7 @@ pub struct StackGroupStatus {
     pub frames: Vec<StackFrameDecision>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StackPreviewJob {
     pub schema_version: u32,
     pub job_id: String,
@@ -155,7 +159,7 @@ pub struct StackPreviewJob {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LatestStackPreviewGroup {
     pub job_id: String,
     pub artifact_revision: String,
@@ -164,7 +168,7 @@ pub struct LatestStackPreviewGroup {
     pub group: StackGroupStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LatestStackPreviews {
     pub schema_version: u32,
     pub database_id: String,
@@ -254,6 +258,14 @@ struct PreparedJob {
     cache_root: PathBuf,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/projects/{project_id}/stack-previews",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    request_body = StackPreviewRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<StackPreviewJob>))
+)]
 pub async fn start_stack_previews(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -264,8 +276,9 @@ pub async fn start_stack_previews(
 
     let ctx_arc = Arc::clone(&ctx.0);
     let request_for_prepare = request.clone();
+    let min_stars = state.min_stars();
     let prepared = tokio::task::spawn_blocking(move || {
-        prepare_job(&ctx_arc, project_id, &request_for_prepare)
+        prepare_job(&ctx_arc, project_id, &request_for_prepare, min_stars)
     })
     .await
     .map_err(|error| {
@@ -313,6 +326,13 @@ pub async fn start_stack_previews(
     Ok(Json(ApiResponse::success(response)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/stack-previews/latest",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<LatestStackPreviews>))
+)]
 pub async fn get_latest_stack_previews(
     ctx: DbContext,
     Path((_db_id, project_id)): Path<(String, i32)>,
@@ -341,6 +361,13 @@ pub async fn get_latest_stack_previews(
     Ok(Json(ApiResponse::success(latest)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/stack-previews/{job_id}",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path), ("job_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<StackPreviewJob>))
+)]
 pub async fn get_stack_preview_job(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -364,6 +391,20 @@ pub async fn get_stack_preview_job(
     Ok(Json(ApiResponse::success(job)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stack-previews/{job_id}/{group_index}/preview",
+    tag = "stack-previews",
+    params(
+        ("db_id" = String, Path),
+        ("job_id" = String, Path),
+        ("group_index" = usize, Path),
+        StackPreviewImageQuery,
+    ),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
 pub async fn get_stack_preview_image(
     ctx: DbContext,
     Path((_db_id, job_id, group_index)): Path<(String, String, usize)>,
@@ -395,6 +436,15 @@ pub async fn get_stack_preview_image(
         })
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stack-previews/{job_id}/{group_index}/fits",
+    tag = "stack-previews",
+    params(("db_id" = String, Path), ("job_id" = String, Path), ("group_index" = usize, Path)),
+    responses(
+        (status = 200, description = "FITS file", content_type = "application/fits", body = Binary)
+    )
+)]
 pub async fn download_stack_preview_fits(
     ctx: DbContext,
     Path((_db_id, job_id, group_index)): Path<(String, String, usize)>,
@@ -425,6 +475,14 @@ pub async fn download_stack_preview_fits(
         })
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/stack-previews/{job_id}/{group_index}/stretch",
+    tag = "stack-previews",
+    params(("db_id" = String, Path), ("job_id" = String, Path), ("group_index" = usize, Path)),
+    request_body = stretch::StackViewProcessingRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<stretch::StackStretchPreview>))
+)]
 pub async fn apply_stack_preview_stretch(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -503,9 +561,10 @@ fn prepare_job(
     ctx: &Arc<DatabaseContext>,
     project_id: i32,
     request: &StackPreviewRequest,
+    min_stars: Option<usize>,
 ) -> Result<PreparedJob, AppError> {
     let requested = request.image_ids.iter().copied().collect::<HashSet<_>>();
-    let (project_images, expected_by_image) = {
+    let (project_images, expected_by_image, computed) = {
         let conn = ctx.db();
         let conn = conn.lock().map_err(AppError::db)?;
         let db = Database::new(&conn);
@@ -547,10 +606,17 @@ fn prepare_job(
             })
             .collect::<Result<HashMap<_, _>, _>>()
             .map_err(AppError::db)?;
-        (relevant, expected)
+        let computed = crate::computed_metrics::load_all(&conn).map_err(AppError::db)?;
+        (relevant, expected, computed)
     };
 
-    let quality = quality_results(ctx, &project_images, &expected_by_image);
+    let quality = quality_results(
+        ctx,
+        &project_images,
+        &expected_by_image,
+        &computed,
+        min_stars,
+    );
     let quality_by_id = quality
         .into_iter()
         .map(|result| (result.image_id, result))
@@ -620,7 +686,7 @@ fn prepare_job(
                 continue;
             }
 
-            let Some(filename) = super::handlers::filename_from_metadata(&image.metadata) else {
+            let Some(filename) = crate::scoring::filename_from_metadata(&image.metadata) else {
                 missing_files += 1;
                 decisions.push(excluded_decision(
                     &image,
@@ -748,8 +814,12 @@ fn quality_results(
     ctx: &DatabaseContext,
     images: &[(AcquiredImage, String, String)],
     expected_by_image: &HashMap<i32, Option<(f64, f64)>>,
+    computed: &HashMap<i32, ComputedMetrics>,
+    min_stars: Option<usize>,
 ) -> Vec<ImageQualityResult> {
     crate::server::spatial_scan::ensure_loaded(&ctx.spatial_metrics, &ctx.cache_dir_path);
+    let computed =
+        crate::computed_metrics::current_for(computed, images.iter().map(|(image, _, _)| image.id));
     let mut grouped: BTreeMap<(i32, String, String), Vec<&AcquiredImage>> = BTreeMap::new();
     for (image, _, target_name) in images {
         grouped
@@ -761,7 +831,10 @@ fn quality_results(
             .or_default()
             .push(image);
     }
-    let config = SequenceAnalyzerConfig::default();
+    let config = SequenceAnalyzerConfig {
+        min_stars,
+        ..Default::default()
+    };
     let session_gap = config.session_gap_minutes;
     let analyzer = SequenceAnalyzer::new(config);
     let mut output = Vec::new();
@@ -771,26 +844,29 @@ fn quality_results(
         for image in group {
             let mut value =
                 extract_metrics_from_metadata(image.id, &image.metadata, image.acquired_date);
-            super::handlers::merge_spatial_metrics(
+            if let Some(computed) = computed.get(&image.id) {
+                computed.fill_missing(&mut value);
+            }
+            crate::scoring::merge_spatial_metrics(
                 &mut value,
                 &ctx.spatial_metrics,
                 &image.metadata,
             );
-            super::handlers::merge_astrometry_metrics(
+            crate::scoring::merge_astrometry_metrics(
                 &mut value,
                 &ctx.cache_dir_path,
                 &image.metadata,
                 &ctx.astrometry_evidence,
                 expected_by_image.get(&image.id).copied().flatten(),
             );
-            entries.push(super::handlers::stored_entry_for(
+            entries.push(crate::scoring::stored_entry_for(
                 &ctx.spatial_metrics,
                 image.id,
                 &image.metadata,
             ));
             metrics.push(value);
         }
-        super::handlers::merge_photometric_signals(&mut metrics, &entries, session_gap);
+        crate::scoring::merge_photometric_signals(&mut metrics, &entries, session_gap);
         for sequence in analyzer.analyze(&metrics, target_id, &target_name, &filter_name) {
             output.extend(sequence.images);
         }
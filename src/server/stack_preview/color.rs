@@ -34,10 +34,12 @@ use std::fmt::Write as _;
 use std::path::{Path as FsPath, PathBuf};
 use std::sync::Arc;
 use tokio_util::io::ReaderStream;
+use utoipa::ToSchema;
 
 use crate::server::api::ApiResponse;
 use crate::server::extract::DbContext;
 use crate::server::handlers::AppError;
+use crate::server::openapi::Binary;
 use crate::server::state::AppState;
 
 const STACK_COLOR_CACHE_VERSION: u32 = 5;
@@ -47,7 +49,9 @@ const MAX_REGISTRATION_RMS_PIXELS: f64 = 2.0;
 const COLOR_BYTES_PER_PIXEL: u64 = 64;
 const COLOR_DECONVOLUTION_BYTES_PER_PIXEL: u64 = 40;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum StackColorRole {
     Luminance,
@@ -73,7 +77,7 @@ impl StackColorRole {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackColorKind {
     Rgb,
@@ -81,7 +85,7 @@ pub enum StackColorKind {
     Narrowband,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum StackNarrowbandPalette {
     Sho,
@@ -139,7 +143,7 @@ impl StackNarrowbandPalette {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct StackColorRequest {
     pub target_id: i32,
     pub kind: StackColorKind,
@@ -153,7 +157,7 @@ pub struct StackColorRequest {
     pub processing: Option<StackColorProcessing>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct StackColorProcessing {
     /// Optional smooth background correction applied to each linear channel
     /// independently before cross-channel registration.
@@ -163,6 +167,7 @@ pub struct StackColorProcessing {
     /// before display normalization. Empty by default: color previews never
     /// restore pixels unless the user explicitly enables a role.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = BTreeMap<String, Value>)]
     pub input_deconvolutions: BTreeMap<StackColorRole, seiza_deconvolution::DeconvolutionConfig>,
     /// Ordered display stretches applied independently after registration and
     /// robust normalization of each physical input channel.
@@ -173,14 +178,16 @@ pub struct StackColorProcessing {
     pub output_stretches: Vec<super::stretch::StackStretchRequest>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StackBackgroundExtraction {
+    #[schema(value_type = Value)]
     pub config: BackgroundConfig,
     #[serde(default)]
+    #[schema(value_type = Value)]
     pub correction_mode: CorrectionMode,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackColorProgressState {
     Pending,
@@ -191,7 +198,7 @@ pub enum StackColorProgressState {
     Failed,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackColorProgressPhase {
     LoadingSources,
@@ -208,7 +215,7 @@ pub enum StackColorProgressPhase {
     PublishingArtifacts,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StackColorPhaseProgress {
     pub phase: StackColorProgressPhase,
     pub label: String,
@@ -217,7 +224,7 @@ pub struct StackColorPhaseProgress {
     pub total_units: usize,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct StackColorProgress {
     pub completed_units: usize,
     pub total_units: usize,
@@ -228,7 +235,7 @@ pub struct StackColorProgress {
     pub phases: Vec<StackColorPhaseProgress>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 pub struct StackColorSource {
     pub role: StackColorRole,
     pub filter_name: String,
@@ -238,7 +245,7 @@ pub struct StackColorSource {
     pub accepted_frames: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StackColorJob {
     pub schema_version: u32,
     pub job_id: String,
@@ -276,6 +283,7 @@ pub struct StackColorJob {
     #[serde(default)]
     pub resolved_output_stretches: Vec<serde_json::Value>,
     #[serde(default)]
+    #[schema(value_type = BTreeMap<String, Value>)]
     pub resolved_backgrounds: BTreeMap<StackColorRole, BackgroundFit>,
     pub preview_url: String,
     pub fits_url: String,
@@ -286,13 +294,13 @@ pub struct StackColorJob {
     pub outdated_reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct StackColorAvailableRole {
     pub role: StackColorRole,
     pub filter_name: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct StackColorTargetAvailability {
     pub target_id: i32,
     pub target_name: String,
@@ -322,7 +330,7 @@ struct CachedColorInputs {
     resolved_deconvolutions: BTreeMap<StackColorRole, super::stretch::StackDeconvolutionResult>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct StackColorCatalog {
     pub schema_version: u32,
     pub database_id: String,
@@ -601,6 +609,13 @@ impl StackPreviewManager {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/stack-previews/color",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<StackColorCatalog>))
+)]
 pub async fn get_stack_color_catalog(
     ctx: DbContext,
     Path((_db_id, project_id)): Path<(String, i32)>,
@@ -627,6 +642,14 @@ pub async fn get_stack_color_catalog(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/db/{db_id}/projects/{project_id}/stack-previews/color",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path)),
+    request_body = StackColorRequest,
+    responses((status = 200, description = "OK", body = ApiResponse<StackColorJob>))
+)]
 pub async fn start_stack_color(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -704,6 +727,13 @@ fn mark_color_reused(mut job: StackColorJob) -> StackColorJob {
     job
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/projects/{project_id}/stack-previews/color/{job_id}",
+    tag = "projects",
+    params(("db_id" = String, Path), ("project_id" = i32, Path), ("job_id" = String, Path)),
+    responses((status = 200, description = "OK", body = ApiResponse<StackColorJob>))
+)]
 pub async fn get_stack_color_job(
     State(state): State<Arc<AppState>>,
     ctx: DbContext,
@@ -727,6 +757,15 @@ pub async fn get_stack_color_job(
     Ok(Json(ApiResponse::success(job)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stack-previews/color/{job_id}/preview",
+    tag = "stack-previews",
+    params(("db_id" = String, Path), ("job_id" = String, Path), StackPreviewImageQuery),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
 pub async fn get_stack_color_image(
     ctx: DbContext,
     Path((_db_id, job_id)): Path<(String, String)>,
@@ -742,6 +781,15 @@ pub async fn get_stack_color_image(
     stream_artifact(path, "image/png", None).await
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stack-previews/color/{job_id}/fits",
+    tag = "stack-previews",
+    params(("db_id" = String, Path), ("job_id" = String, Path)),
+    responses(
+        (status = 200, description = "FITS file", content_type = "application/fits", body = Binary)
+    )
+)]
 pub async fn download_stack_color_fits(
     ctx: DbContext,
     Path((_db_id, job_id)): Path<(String, String)>,
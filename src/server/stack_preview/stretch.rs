@@ -31,10 +31,12 @@ use std::fmt::Write as _;
 use std::path::{Path as FsPath, PathBuf};
 use std::sync::Arc;
 use tokio_util::io::ReaderStream;
+use utoipa::ToSchema;
 
 use crate::server::api::ApiResponse;
 use crate::server::extract::DbContext;
 use crate::server::handlers::AppError;
+use crate::server::openapi::Binary;
 use crate::server::state::AppState;
 
 const STRETCH_CACHE_VERSION: u32 = 2;
@@ -46,26 +48,31 @@ const LINEAR_BLACK_PERCENTILE: f64 = 0.001;
 const LINEAR_WHITE_PERCENTILE: f64 = 0.999;
 pub(super) const SEIZA_STRETCH_VERSION: &str = "0.1.0";
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct StackDeconvolutionResult {
+    #[schema(value_type = Value)]
     pub config: DeconvolutionConfig,
+    #[schema(value_type = Vec<Value>)]
     pub channels: Vec<ChannelDiagnostics>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct StackStretchRequest {
+    #[schema(value_type = Value)]
     pub model: StretchModel,
     #[serde(default)]
+    #[schema(value_type = Value)]
     pub color_strategy: ColorStrategy,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct StackViewProcessingRequest {
     #[serde(flatten)]
     pub stretch: StackStretchRequest,
     /// Optional linear-light restoration. Omitted by default so existing and
     /// newly created previews retain their original pixels unless requested.
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Value>)]
     pub deconvolution: Option<DeconvolutionConfig>,
 }
 
@@ -79,14 +86,14 @@ impl StackStretchRequest {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StackStretchSourceTransfer {
     Linear,
     DisplayReferred,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct StackStretchPreview {
     pub schema_version: u32,
     pub stretch_id: String,
@@ -95,12 +102,16 @@ pub struct StackStretchPreview {
     pub deconvolution_version: Option<String>,
     #[serde(default)]
     pub deconvolution_id: Option<String>,
+    #[schema(value_type = Value)]
     pub config: StretchConfig,
     pub resolved_plan: serde_json::Value,
     pub source_transfer: StackStretchSourceTransfer,
     pub input_range: Option<StackStretchInputRange>,
+    #[schema(value_type = Value)]
     pub linked_statistics: RobustStatistics,
+    #[schema(value_type = Vec<Value>)]
     pub channel_statistics: Vec<Option<RobustStatistics>>,
+    #[schema(value_type = Option<Value>)]
     pub luminance_statistics: Option<RobustStatistics>,
     #[serde(default)]
     pub deconvolution: Option<StackDeconvolutionResult>,
@@ -110,7 +121,7 @@ pub struct StackStretchPreview {
     pub fits_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, ToSchema)]
 pub struct StackStretchInputRange {
     pub black: f32,
     pub white: f32,
@@ -636,6 +647,15 @@ fn deconvolution_cards(request: DeconvolutionConfig) -> Vec<WriteHeaderCard> {
     ]
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stack-previews/stretch/{stretch_id}/preview",
+    tag = "stack-previews",
+    params(("db_id" = String, Path), ("stretch_id" = String, Path), StackPreviewImageQuery),
+    responses(
+        (status = 200, description = "PNG image", content_type = "image/png", body = Binary)
+    )
+)]
 pub async fn get_stack_stretch_image(
     ctx: DbContext,
     Path((_db_id, stretch_id)): Path<(String, String)>,
@@ -667,6 +687,15 @@ pub async fn get_stack_stretch_image(
         })
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/db/{db_id}/stack-previews/stretch/{stretch_id}/fits",
+    tag = "stack-previews",
+    params(("db_id" = String, Path), ("stretch_id" = String, Path)),
+    responses(
+        (status = 200, description = "FITS file", content_type = "application/fits", body = Binary)
+    )
+)]
 pub async fn download_stack_stretch_fits(
     ctx: DbContext,
     Path((_db_id, stretch_id)): Path<(String, String)>,
@@ -0,0 +1,71 @@
+//! Filesystem mode: serve folders of FITS files without a N.I.N.A. database.
+//!
+//! `server --no-database --scan-dirs <dir>...` imports the folders into a
+//! scheduler database kept in the cache directory, synthesizing projects,
+//! targets and image rows from the FITS headers exactly as `create-db` does
+//! (OBJECT → target, capture time gaps → projects). Every handler then works
+//! unchanged against it.
+//!
+//! The database survives restarts so grades aren't lost; each start imports
+//! only frames that appeared since, because the import skips basenames it
+//! already holds.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::commands::import::{collect_fits_files, import_frames, scan_frames, ImportOptions};
+use crate::db_registry::DbEntry;
+
+/// Database id (URL slug) of the synthesized database.
+pub const FILESYSTEM_DB_ID: &str = "filesystem";
+
+/// Location of the synthesized database under the cache root.
+pub fn filesystem_db_path(cache_dir: &str) -> PathBuf {
+    Path::new(cache_dir)
+        .join("standalone")
+        .join("filesystem.sqlite")
+}
+
+/// Create (or update) the filesystem-mode database from `scan_dirs` and
+/// return the entry to serve it under. Blocking: reads every new frame's
+/// header.
+pub fn prepare_filesystem_database(scan_dirs: &[String], cache_dir: &str) -> Result<DbEntry> {
+    if scan_dirs.is_empty() {
+        bail!("Filesystem mode needs at least one directory to scan");
+    }
+    let dirs: Vec<PathBuf> = scan_dirs.iter().map(PathBuf::from).collect();
+    if let Some(missing) = dirs.iter().find(|dir| !dir.exists()) {
+        bail!("Scan directory does not exist: {}", missing.display());
+    }
+
+    let db_path = filesystem_db_path(cache_dir);
+    let mut conn = if db_path.exists() {
+        crate::db::open_connection(&db_path, false)?
+    } else {
+        crate::ts_schema::create_fresh_db(&db_path)?
+    };
+
+    let files = collect_fits_files(&dirs)?;
+    tracing::info!(
+        "🗂️ Filesystem mode: reading headers of {} FITS file(s)",
+        files.len()
+    );
+    let outcome = import_frames(&mut conn, scan_frames(&files), &ImportOptions::default())
+        .with_context(|| format!("importing into {}", db_path.display()))?;
+    tracing::info!(
+        "🗂️ Filesystem mode: {} new frame(s), {} already known, {} non-light, {} unreadable",
+        outcome.imported,
+        outcome.skipped_existing,
+        outcome.non_light,
+        outcome.unreadable
+    );
+
+    Ok(DbEntry {
+        id: FILESYSTEM_DB_ID.to_string(),
+        name: "Filesystem".to_string(),
+        db_path: db_path.to_string_lossy().into_owned(),
+        image_dirs: scan_dirs.to_vec(),
+        reject_archive: None,
+    })
+}
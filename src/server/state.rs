@@ -22,6 +22,21 @@ pub enum RefreshStatus {
     NeedsRefresh,
 }
 
+/// Startup settings handlers read but never change. Set once with
+/// [`AppState::with_settings`] before the state is shared; new knobs of this
+/// kind belong here rather than in a lock of their own.
+#[derive(Debug, Clone, Default)]
+pub struct ServerSettings {
+    /// `[reject_reasons]` categories for the rejection statistics.
+    pub reason_mapper: crate::reject_reasons::ReasonMapper,
+    /// Star count below which a frame counts as a detection failure
+    /// (`[server] min_stars`); `None` when the gate is off.
+    pub min_stars: Option<usize>,
+    /// Clip fractions past which a frame is judged under- or over-exposed
+    /// (`[server] low_clip_fraction` / `high_clip_fraction`).
+    pub exposure_thresholds: crate::image_analysis::ExposureThresholds,
+}
+
 /// Process-global server state.
 ///
 /// All per-database work routes through entries in `databases`, keyed by slug.
@@ -50,6 +65,12 @@ pub struct AppState {
     /// `concurrency::WorkerPolicy`). Process-global; sourced from the TOML
     /// `[server]` ratios, otherwise the compiled-in defaults.
     pub worker_policy: RwLock<crate::concurrency::WorkerPolicy>,
+    /// Fixed at startup from `ServerConfig`; see [`ServerSettings`].
+    settings: ServerSettings,
+    /// Connection tuning handed to every `DatabaseContext` this state opens,
+    /// at startup or later over HTTP. `read_only` (`--read-only`) also makes
+    /// every endpoint that writes to a scheduler database answer 403.
+    connection_options: crate::db::ConnectionOptions,
     /// Count of interactive (user-triggered) CPU-heavy jobs currently running,
     /// process-wide. Background work reads this to yield: while it is nonzero,
     /// pre-generation pauses so it doesn't compete for cores or memory with a
@@ -60,6 +81,9 @@ pub struct AppState {
     /// PNG generation (see `preview_queue`). Process-global so total concurrent
     /// generation is bounded regardless of how many databases are loaded.
     pub preview_queue: crate::server::preview_queue::PreviewQueue,
+    /// Coalesces identical concurrent misses of artifacts rendered inside the
+    /// request (stars, PSF images), keyed on their cache path.
+    pub generation_flights: crate::server::preview_queue::SingleFlight,
     /// Process-global, single-flight project stacking preview jobs. Full-frame
     /// stacking is memory intensive, so groups and databases share one permit.
     pub stack_previews: crate::server::stack_preview::StackPreviewManager,
@@ -94,6 +118,10 @@ impl Drop for InteractiveJobGuard {
 pub struct FileCheckCache {
     pub projects_with_files: HashMap<i32, bool>,
     pub targets_with_files: HashMap<i32, bool>,
+    /// How many of each project's frames were found on disk.
+    pub project_file_counts: HashMap<i32, FileCounts>,
+    /// How many of each target's frames were found on disk.
+    pub target_file_counts: HashMap<i32, FileCounts>,
     pub last_updated: Instant,
     pub cache_duration: Duration,
     pub refresh_in_progress: bool,
@@ -101,6 +129,19 @@ pub struct FileCheckCache {
     pub refresh_progress: RefreshProgress,
 }
 
+/// Frames found on disk out of those with a recorded filename.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileCounts {
+    pub found: usize,
+    pub total: usize,
+}
+
+impl FileCounts {
+    pub fn missing(&self) -> usize {
+        self.total - self.found
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RefreshProgress {
     pub stage: RefreshStage,
@@ -127,6 +168,8 @@ pub enum RefreshStage {
     ProcessingTargets,
     UpdatingCache,
     Completed,
+    /// Stopped by `POST /refresh-cache/cancel`; the previous cache was kept.
+    Cancelled,
 }
 
 impl Default for RefreshProgress {
@@ -214,6 +257,12 @@ impl RefreshProgress {
         self.current_directory_name = None;
     }
 
+    pub fn cancel_refresh(&mut self) {
+        self.stage = RefreshStage::Cancelled;
+        self.current_project_name = None;
+        self.current_directory_name = None;
+    }
+
     pub fn update_files_scanned(&mut self, files_scanned: usize) {
         self.files_scanned = files_scanned;
     }
@@ -245,6 +294,7 @@ impl RefreshProgress {
             }
             RefreshStage::UpdatingCache => 95.0,
             RefreshStage::Completed => 100.0,
+            RefreshStage::Cancelled => 0.0,
         }
     }
 
@@ -264,6 +314,8 @@ impl FileCheckCache {
         Self {
             projects_with_files: HashMap::new(),
             targets_with_files: HashMap::new(),
+            project_file_counts: HashMap::new(),
+            target_file_counts: HashMap::new(),
             last_updated: Instant::now(),
             cache_duration: Duration::from_secs(60), // 1 minute cache
             refresh_in_progress: false,
@@ -279,6 +331,8 @@ impl FileCheckCache {
     pub fn clear(&mut self) {
         self.projects_with_files.clear();
         self.targets_with_files.clear();
+        self.project_file_counts.clear();
+        self.target_file_counts.clear();
         self.last_updated = Instant::now();
         self.refresh_in_progress = false;
         self.has_initial_data = false;
@@ -297,6 +351,16 @@ impl FileCheckCache {
         self.refresh_progress.complete_refresh();
     }
 
+    /// End a cancelled refresh. The existing results stay as they were, and
+    /// count as fresh for another `cache_duration` so the refresh isn't
+    /// restarted by the next request; without earlier results the next
+    /// listing starts one again.
+    pub fn mark_refresh_cancelled(&mut self) {
+        self.refresh_in_progress = false;
+        self.last_updated = Instant::now();
+        self.refresh_progress.cancel_refresh();
+    }
+
     pub fn should_serve_stale(&self) -> bool {
         // Serve stale data if we have initial data and refresh is in progress
         self.has_initial_data && self.refresh_in_progress
@@ -332,12 +396,42 @@ impl AppState {
         Self::from_databases_with_astrometry(databases, cache_dir, pregeneration_config, None)
     }
 
+    /// Build state for filesystem mode: one database synthesized from the
+    /// FITS headers under `scan_dirs` (see [`crate::server::standalone`]).
+    /// Blocking on the first start, while headers are read.
+    pub fn from_image_dirs(
+        scan_dirs: &[String],
+        cache_dir: String,
+        pregeneration_config: PregenerationConfig,
+    ) -> Result<Self> {
+        let entry = crate::server::standalone::prepare_filesystem_database(scan_dirs, &cache_dir)?;
+        Self::from_databases(vec![entry], cache_dir, pregeneration_config)
+    }
+
     /// Build state with optional process-global Seiza catalog configuration.
     pub fn from_databases_with_astrometry(
         databases: Vec<DbEntry>,
         cache_dir: String,
         pregeneration_config: PregenerationConfig,
         astrometry_config: Option<crate::astrometry::AstrometryConfig>,
+    ) -> Result<Self> {
+        Self::from_databases_with_options(
+            databases,
+            cache_dir,
+            pregeneration_config,
+            astrometry_config,
+            crate::db::ConnectionOptions::default(),
+        )
+    }
+
+    /// Build state whose database connections, including ones added later
+    /// over HTTP, all open with `connection_options`.
+    pub fn from_databases_with_options(
+        databases: Vec<DbEntry>,
+        cache_dir: String,
+        pregeneration_config: PregenerationConfig,
+        astrometry_config: Option<crate::astrometry::AstrometryConfig>,
+        connection_options: crate::db::ConnectionOptions,
     ) -> Result<Self> {
         let astrometry_config = astrometry_config.unwrap_or_default();
         let satellites = crate::satellites::SatelliteContext::new(
@@ -353,6 +447,7 @@ impl AppState {
                 entry.db_path,
                 entry.image_dirs,
                 cache_dir.clone(),
+                connection_options,
             )?);
             map.insert(entry.id, ctx);
         }
@@ -365,8 +460,11 @@ impl AppState {
             allow_database_management: RwLock::new(false),
             site_banner: RwLock::new(None),
             worker_policy: RwLock::new(crate::concurrency::WorkerPolicy::default()),
+            settings: ServerSettings::default(),
+            connection_options,
             active_interactive_jobs: Arc::new(AtomicUsize::new(0)),
             preview_queue: crate::server::preview_queue::PreviewQueue::default(),
+            generation_flights: crate::server::preview_queue::SingleFlight::default(),
             stack_previews: crate::server::stack_preview::StackPreviewManager::default(),
             catalog_install: crate::server::catalog_install::CatalogInstallManager::default(),
             sync_previews: crate::server::sync_preview::SyncPreviewManager::new(&cache_dir),
@@ -393,6 +491,24 @@ impl AppState {
         *self.allow_database_management.read().unwrap()
     }
 
+    /// Fix the startup settings. Called before the state is shared.
+    pub fn with_settings(mut self, settings: ServerSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Options every database connection of this state opens with.
+    pub fn connection_options(&self) -> crate::db::ConnectionOptions {
+        self.connection_options
+    }
+
+    /// Read-only mode. The connections are opened read-only as well; this
+    /// only gates the handlers so they fail with a clear 403 instead of a
+    /// SQLite error.
+    pub fn is_read_only(&self) -> bool {
+        self.connection_options.read_only
+    }
+
     pub fn set_site_banner(&self, banner: Option<crate::config::SiteBannerConfig>) {
         *self.site_banner.write().unwrap() = banner;
     }
@@ -401,6 +517,10 @@ impl AppState {
         self.site_banner.read().unwrap().clone()
     }
 
+    pub fn reason_mapper(&self) -> &crate::reject_reasons::ReasonMapper {
+        &self.settings.reason_mapper
+    }
+
     /// Set the worker tuning policy (from the TOML `[server]` config).
     pub fn set_worker_policy(&self, policy: crate::concurrency::WorkerPolicy) {
         *self.worker_policy.write().unwrap() = policy;
@@ -411,6 +531,16 @@ impl AppState {
         *self.worker_policy.read().unwrap()
     }
 
+    /// The minimum star count below which a frame fails, if configured.
+    pub fn min_stars(&self) -> Option<usize> {
+        self.settings.min_stars
+    }
+
+    /// The clip fractions past which a frame is judged mis-exposed.
+    pub fn exposure_thresholds(&self) -> crate::image_analysis::ExposureThresholds {
+        self.settings.exposure_thresholds
+    }
+
     /// Mark the start of an interactive CPU-heavy job (e.g. an occlusion
     /// scan). Hold the returned guard for the job's lifetime; background work
     /// yields while any guard is alive.
@@ -427,11 +557,14 @@ impl AppState {
 
     /// Convenience constructor for a single database. Computes a default slug
     /// from the path. Used by callers that don't go through `DbRegistry`.
+    /// `read_only` opens the connection `SQLITE_OPEN_READ_ONLY` and rejects
+    /// grade updates.
     pub fn new(
         db_path: String,
         image_dirs: Vec<String>,
         cache_dir: String,
         pregeneration_config: PregenerationConfig,
+        read_only: bool,
     ) -> Result<Self> {
         let slug = compute_default_slug(&db_path);
         let name = PathBuf::from(&db_path)
@@ -439,7 +572,7 @@ impl AppState {
             .map(|s| s.to_string_lossy().into_owned())
             .filter(|s| !s.is_empty())
             .unwrap_or_else(|| "Database".to_string());
-        Self::from_databases(
+        Self::from_databases_with_options(
             vec![DbEntry {
                 id: slug,
                 name,
@@ -449,6 +582,11 @@ impl AppState {
             }],
             cache_dir,
             pregeneration_config,
+            None,
+            crate::db::ConnectionOptions {
+                read_only,
+                ..crate::db::ConnectionOptions::default()
+            },
         )
     }
 
@@ -482,8 +620,11 @@ impl AppState {
             allow_database_management: RwLock::new(false),
             site_banner: RwLock::new(None),
             worker_policy: RwLock::new(crate::concurrency::WorkerPolicy::default()),
+            settings: ServerSettings::default(),
+            connection_options: crate::db::ConnectionOptions::default(),
             active_interactive_jobs: Arc::new(AtomicUsize::new(0)),
             preview_queue: crate::server::preview_queue::PreviewQueue::default(),
+            generation_flights: crate::server::preview_queue::SingleFlight::default(),
             stack_previews: crate::server::stack_preview::StackPreviewManager::default(),
             catalog_install: crate::server::catalog_install::CatalogInstallManager::default(),
             sync_previews: crate::server::sync_preview::SyncPreviewManager::new(
@@ -540,6 +681,19 @@ mod tests {
         assert_eq!(state.site_banner(), Some(banner));
     }
 
+    #[test]
+    fn settings_default_off_and_apply_at_startup() {
+        let state = test_state();
+        assert!(!state.is_read_only());
+        assert_eq!(state.min_stars(), None);
+
+        let state = state.with_settings(ServerSettings {
+            min_stars: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(state.min_stars(), Some(10));
+    }
+
     #[test]
     fn interactive_job_gauge_nests() {
         // Concurrent scans (e.g. across databases) must both have to finish
@@ -574,4 +728,39 @@ mod tests {
             "guard must decrement while unwinding"
         );
     }
+
+    #[test]
+    fn read_only_applies_to_one_state_only() {
+        // Read-only access used to be a process-wide switch, so one read-only
+        // state made every later state's connections read-only too.
+        let dir = tempfile::tempdir().unwrap();
+        let images = dir.path().join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        let open_state = |name: &str, read_only: bool| {
+            let db_path = dir.path().join(name);
+            Connection::open(&db_path)
+                .unwrap()
+                .execute_batch("CREATE TABLE t (x INTEGER);")
+                .unwrap();
+            AppState::new(
+                db_path.to_string_lossy().into_owned(),
+                vec![images.to_string_lossy().into_owned()],
+                dir.path().join("cache").to_string_lossy().into_owned(),
+                PregenerationConfig::default(),
+                read_only,
+            )
+            .unwrap()
+        };
+        let insert = |state: &AppState| {
+            let conn = state.all_databases()[0].db();
+            let conn = conn.lock().unwrap();
+            conn.execute("INSERT INTO t VALUES (1)", [])
+        };
+
+        let browsing = open_state("browse.sqlite", true);
+        let grading = open_state("grade.sqlite", false);
+        assert!(browsing.is_read_only() && !grading.is_read_only());
+        assert!(insert(&browsing).is_err());
+        assert!(insert(&grading).is_ok());
+    }
 }
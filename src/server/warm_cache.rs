@@ -0,0 +1,116 @@
+//! On-demand cache warming for one project.
+//!
+//! Generates the requested preview sizes (and annotated images) for every
+//! frame of a project ahead of a review session, without turning on global
+//! pre-generation. One job per database at a time; frames are processed one
+//! after another and every generation takes a permit from the shared
+//! generation pool, so warming never crowds out previews the user is
+//! waiting on.
+
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct WarmCacheProgress {
+    /// Id of the latest job; `None` before the first one.
+    pub job_id: Option<u64>,
+    pub project_id: Option<i32>,
+    /// Preview size names, plus `annotated`.
+    pub formats: Vec<String>,
+    pub running: bool,
+    pub total_images: usize,
+    pub processed_images: usize,
+    pub generated: u64,
+    /// Already cached and fresh.
+    pub skipped: u64,
+    pub errors: u64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct WarmCacheStore {
+    pub progress: WarmCacheProgress,
+    last_job_id: u64,
+}
+
+pub type SharedWarmCache = Arc<RwLock<WarmCacheStore>>;
+
+/// Start a job, returning its id; `None` while another is still running.
+pub fn try_begin(
+    store: &RwLock<WarmCacheStore>,
+    project_id: i32,
+    formats: Vec<String>,
+    total_images: usize,
+) -> Option<u64> {
+    let mut state = store.write().unwrap();
+    if state.progress.running {
+        return None;
+    }
+    state.last_job_id += 1;
+    state.progress = WarmCacheProgress {
+        job_id: Some(state.last_job_id),
+        project_id: Some(project_id),
+        formats,
+        running: total_images > 0,
+        total_images,
+        started_at: Some(chrono::Utc::now().timestamp()),
+        ..Default::default()
+    };
+    if total_images == 0 {
+        state.progress.finished_at = state.progress.started_at;
+    }
+    Some(state.last_job_id)
+}
+
+/// Count one frame done, with its `(generated, skipped, errors)` tallies.
+pub fn finish_image(store: &RwLock<WarmCacheStore>, (generated, skipped, errors): (u64, u64, u64)) {
+    let mut state = store.write().unwrap();
+    state.progress.processed_images += 1;
+    state.progress.generated += generated;
+    state.progress.skipped += skipped;
+    state.progress.errors += errors;
+}
+
+pub fn finish(store: &RwLock<WarmCacheStore>) {
+    let mut state = store.write().unwrap();
+    state.progress.running = false;
+    state.progress.finished_at = Some(chrono::Utc::now().timestamp());
+}
+
+pub fn snapshot(store: &RwLock<WarmCacheStore>) -> WarmCacheProgress {
+    store.read().unwrap().progress.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_one_job_per_database() {
+        let store = RwLock::new(WarmCacheStore::default());
+        let formats = vec!["screen".to_string(), "annotated".to_string()];
+        assert_eq!(try_begin(&store, 7, formats.clone(), 2), Some(1));
+        assert_eq!(try_begin(&store, 8, formats.clone(), 1), None);
+        finish_image(&store, (2, 0, 0));
+        finish_image(&store, (1, 0, 1));
+        finish(&store);
+
+        let progress = snapshot(&store);
+        assert!(!progress.running);
+        assert_eq!(progress.job_id, Some(1));
+        assert_eq!(progress.project_id, Some(7));
+        assert_eq!(progress.processed_images, 2);
+        assert_eq!(
+            (progress.generated, progress.skipped, progress.errors),
+            (3, 0, 1)
+        );
+
+        // An empty project finishes at once; ids keep counting.
+        assert_eq!(try_begin(&store, 9, formats, 0), Some(2));
+        let progress = snapshot(&store);
+        assert!(!progress.running);
+        assert!(progress.finished_at.is_some());
+    }
+}
@@ -0,0 +1,201 @@
+//! Group captures by where the telescope pointed instead of by name.
+//!
+//! OBJECT strings drift ("M 31", "M31", "Andromeda", a sequence name, or
+//! nothing at all), while the mount's RA/Dec stays put. Clustering on
+//! angular separation puts frames of one field together whatever they were
+//! called. Used by import (`--group-by-position`) and by sequence analysis
+//! (`position_radius_deg`).
+
+use crate::models::Target;
+
+/// Great-circle separation of two positions, in degrees.
+pub fn angular_distance_deg(ra1: f64, dec1: f64, ra2: f64, dec2: f64) -> f64 {
+    let (ra1, dec1, ra2, dec2) = (
+        ra1.to_radians(),
+        dec1.to_radians(),
+        ra2.to_radians(),
+        dec2.to_radians(),
+    );
+    let cosine =
+        (dec1.sin() * dec2.sin() + dec1.cos() * dec2.cos() * (ra1 - ra2).cos()).clamp(-1.0, 1.0);
+    cosine.acos().to_degrees()
+}
+
+/// Cluster positions (RA, Dec in degrees) that lie within `radius_deg` of a
+/// cluster's first member. Returns each position's cluster index, numbered in
+/// order of first appearance, or `None` for a missing position.
+///
+/// Anchoring on the first member rather than chaining neighbor to neighbor
+/// keeps a slow drift across a mosaic from pulling every panel into one
+/// cluster. A position near several clusters joins the closest.
+pub fn cluster_by_position(
+    positions: &[Option<(f64, f64)>],
+    radius_deg: f64,
+) -> Vec<Option<usize>> {
+    let mut anchors: Vec<(f64, f64)> = Vec::new();
+    positions
+        .iter()
+        .map(|position| {
+            let (ra, dec) = (*position)?;
+            let nearest = anchors
+                .iter()
+                .enumerate()
+                .map(|(i, &(ara, adec))| (i, angular_distance_deg(ra, dec, ara, adec)))
+                .filter(|&(_, distance)| distance <= radius_deg)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            Some(match nearest {
+                Some((cluster, _)) => cluster,
+                None => {
+                    anchors.push((ra, dec));
+                    anchors.len() - 1
+                }
+            })
+        })
+        .collect()
+}
+
+/// Targets pointing at one field, analyzed as one. The lead target lends the
+/// group its id and name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldGroup {
+    pub target_id: i32,
+    pub name: String,
+    /// Every member's id, the lead first.
+    pub target_ids: Vec<i32>,
+}
+
+/// Merge targets of one project whose coordinates lie within `radius_deg`
+/// of each other, led by the first target of each cluster. Targets of
+/// different projects never merge: each project (and so each profile) keeps
+/// its own frames. With no radius, or for targets without coordinates, every
+/// target stands alone.
+pub fn group_targets_by_position(targets: &[Target], radius_deg: Option<f64>) -> Vec<FieldGroup> {
+    // Cluster each project on its own; a cluster is keyed by its project.
+    let mut clusters: Vec<Option<(i32, usize)>> = vec![None; targets.len()];
+    let mut projects: Vec<i32> = Vec::new();
+    for target in targets {
+        if !projects.contains(&target.project_id) {
+            projects.push(target.project_id);
+        }
+    }
+    for project_id in projects {
+        let members: Vec<usize> = (0..targets.len())
+            .filter(|&i| targets[i].project_id == project_id)
+            .collect();
+        let positions: Vec<_> = members
+            .iter()
+            .map(|&i| radius_deg.and(targets[i].ra.zip(targets[i].dec)))
+            .collect();
+        let project_clusters = cluster_by_position(&positions, radius_deg.unwrap_or_default());
+        for (&i, cluster) in members.iter().zip(project_clusters) {
+            clusters[i] = cluster.map(|c| (project_id, c));
+        }
+    }
+
+    let mut groups: Vec<FieldGroup> = Vec::new();
+    let mut group_of_cluster = std::collections::HashMap::new();
+    for (target, cluster) in targets.iter().zip(clusters) {
+        if let Some(&index) = cluster.and_then(|c| group_of_cluster.get(&c)) {
+            groups[index].target_ids.push(target.id);
+            continue;
+        }
+        if let Some(cluster) = cluster {
+            group_of_cluster.insert(cluster, groups.len());
+        }
+        groups.push(FieldGroup {
+            target_id: target.id,
+            name: target.name.clone(),
+            target_ids: vec![target.id],
+        });
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearby_positions_cluster_and_distant_ones_do_not() {
+        let positions = [
+            Some((10.684, 41.269)), // M31
+            Some((10.80, 41.10)),   // M31, re-framed: ~0.2 deg away
+            Some((10.125, 41.685)), // M110: ~0.6 deg from M31
+            None,
+            Some((359.9, 0.0)), // either side of RA 0h
+            Some((0.1, 0.1)),
+            Some((10.70, 41.30)),
+        ];
+        let clusters = cluster_by_position(&positions, 0.5);
+        assert_eq!(
+            clusters,
+            [Some(0), Some(0), Some(1), None, Some(2), Some(2), Some(0)]
+        );
+
+        // A tighter radius splits the re-framed night off.
+        let clusters = cluster_by_position(&positions[..2], 0.1);
+        assert_eq!(clusters, [Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn targets_of_one_field_share_a_group() {
+        let target = |id: i32, name: &str, position: Option<(f64, f64)>| Target {
+            id,
+            name: name.to_string(),
+            active: true,
+            ra: position.map(|p| p.0),
+            dec: position.map(|p| p.1),
+            project_id: 1,
+            guid: None,
+        };
+        let targets = [
+            target(1, "M31", Some((10.684, 41.269))),
+            target(2, "M42", Some((83.82, -5.39))),
+            target(3, "Andromeda", Some((10.75, 41.2))),
+            target(4, "Sequence 1", None),
+            target(5, "Orion Nebula", Some((83.9, -5.3))),
+        ];
+
+        let groups = group_targets_by_position(&targets, Some(0.5));
+        let members: Vec<_> = groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.target_ids.clone()))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("M31", vec![1, 3]),
+                ("M42", vec![2, 5]),
+                ("Sequence 1", vec![4])
+            ]
+        );
+
+        // Without a radius each target stands alone.
+        assert_eq!(group_targets_by_position(&targets, None).len(), 5);
+    }
+
+    #[test]
+    fn targets_of_other_projects_never_merge() {
+        let target = |id: i32, project_id: i32, ra: f64| Target {
+            id,
+            name: format!("T{id}"),
+            active: true,
+            ra: Some(ra),
+            dec: Some(41.27),
+            project_id,
+            guid: None,
+        };
+        // Same field shot by two rigs, each in its own project.
+        let targets = [
+            target(1, 1, 10.68),
+            target(2, 2, 10.68),
+            target(3, 1, 10.70),
+            target(4, 2, 10.71),
+        ];
+        let members: Vec<_> = group_targets_by_position(&targets, Some(0.5))
+            .into_iter()
+            .map(|g| g.target_ids)
+            .collect();
+        assert_eq!(members, [vec![1, 3], vec![2, 4]]);
+    }
+}
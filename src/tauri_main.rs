@@ -4,7 +4,6 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use tokio::sync::oneshot;
-use tracing_subscriber;
 
 /// Tauri-side server bootstrap parameters. Built once at startup; rebuilt
 /// (with the latest registry contents) on `restart_server`.
@@ -27,16 +26,7 @@ struct ServerState {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn main() {
-    // Initialize tracing once for the entire Tauri application
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::filter::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::filter::EnvFilter::new("info")),
-        )
-        .with_target(false)
-        .with_level(true)
-        .with_thread_ids(false)
-        .init();
+    crate::server::init_tracing_once();
 
     let registry_path = DbRegistry::default_path().expect("Could not resolve config path");
     let initial_registry = DbRegistry::load_or_init(&registry_path).unwrap_or_else(|err| {
@@ -272,8 +262,18 @@ async fn start_server_for_tauri(
         allow_database_management: true,
         // The desktop app does not read the server TOML.
         site_banner: None,
+        reason_mapper: Default::default(),
         worker_policy: config.get_worker_policy(),
         astrometry_config,
+        connection_options: config.get_connection_options(),
+        fits_read_retry: config.get_fits_read_retry(),
+        case_insensitive_filenames: config.get_case_insensitive_filenames(),
+        // Bound to localhost and loaded from the app's own webview origin.
+        cors: crate::config::CorsPolicy::Permissive,
+        auth_token: None,
+        request_timeouts: config.get_request_timeouts(),
+        min_stars: config.get_min_stars(),
+        exposure_thresholds: config.get_exposure_thresholds(),
     };
 
     crate::server::run_server_with_shutdown(server_config, shutdown_rx).await
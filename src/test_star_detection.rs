@@ -53,6 +53,7 @@ mod tests {
             raw_min: 0.0,
             raw_scale: 1.0,
             bzero: 0.0,
+            header: Default::default(),
         };
         let stats = fits.calculate_basic_statistics();
         let stretch_params = StretchParams::default();
@@ -0,0 +1,180 @@
+//! Acquisition timeline of a target: when each sub was exposed, the idle
+//! gaps between subs (clouds, meridian flips, equipment pauses) and how much
+//! of the time at the telescope was spent exposing.
+//!
+//! A frame starts at its `ExposureStartTime` when the metadata has one;
+//! otherwise at its acquired date less its exposure, since Target Scheduler
+//! stamps a frame when it is saved. Idle time longer than
+//! [`SESSION_GAP_SECS`] starts a new session (night), and elapsed time is
+//! summed per session so the daytime between nights doesn't count against
+//! efficiency.
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::AcquiredImage;
+
+/// Idle seconds before a frame above which it is reported as a gap.
+pub const DEFAULT_GAP_THRESHOLD_SECS: f64 = 180.0;
+/// Idle seconds that split the timeline into sessions; the sequence
+/// analyzer's default session gap.
+pub const SESSION_GAP_SECS: f64 = 3600.0;
+
+/// One sub on the timeline.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct TimelineEntry {
+    pub image_id: i32,
+    /// Exposure start, Unix seconds.
+    pub timestamp: i64,
+    pub filter: String,
+    /// Exposure length in seconds; `None` when the metadata has none.
+    pub duration: Option<f64>,
+    /// Idle seconds since the previous frame ended, when over the threshold.
+    pub gap_before: Option<f64>,
+    /// First frame of a session.
+    pub new_session: bool,
+    pub grading_status: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Timeline {
+    pub entries: Vec<TimelineEntry>,
+    /// Sum of exposure lengths.
+    pub on_sky_seconds: f64,
+    /// First start to last end, summed over sessions.
+    pub elapsed_seconds: f64,
+    /// `on_sky_seconds / elapsed_seconds`; `None` with nothing elapsed.
+    pub efficiency: Option<f64>,
+    pub sessions: usize,
+    /// Frames without an acquired date or start time.
+    pub skipped_images: usize,
+}
+
+/// Exposure start and length of a frame, when it can be placed in time.
+fn placement(image: &AcquiredImage) -> Option<(f64, Option<f64>)> {
+    let metadata: serde_json::Value = serde_json::from_str(&image.metadata).unwrap_or_default();
+    let duration = crate::db::exposure_seconds(&image.metadata);
+    let start = metadata["ExposureStartTime"]
+        .as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|start| start.timestamp() as f64)
+        .or_else(|| {
+            image
+                .acquired_date
+                .map(|saved| saved as f64 - duration.unwrap_or(0.0))
+        })?;
+    Some((start, duration))
+}
+
+pub fn build_timeline(images: &[AcquiredImage], gap_threshold: f64) -> Timeline {
+    let mut placed: Vec<(&AcquiredImage, f64, Option<f64>)> = images
+        .iter()
+        .filter_map(|image| placement(image).map(|(start, duration)| (image, start, duration)))
+        .collect();
+    placed.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.id.cmp(&b.0.id)));
+
+    let mut entries = Vec::with_capacity(placed.len());
+    let (mut on_sky, mut elapsed, mut sessions) = (0.0, 0.0, 0);
+    let mut session_start = 0.0;
+    let mut previous_end: Option<f64> = None;
+    for (image, start, duration) in &placed {
+        let end = start + duration.unwrap_or(0.0);
+        let idle = previous_end.map(|previous| (start - previous).max(0.0));
+        let new_session = idle.is_none_or(|idle| idle > SESSION_GAP_SECS);
+        if new_session {
+            if let Some(previous) = previous_end {
+                elapsed += previous - session_start;
+            }
+            session_start = *start;
+            sessions += 1;
+        }
+        on_sky += duration.unwrap_or(0.0);
+        // Overlapping stamps (clock jitter) don't move the end backwards.
+        previous_end = Some(previous_end.map_or(end, |previous| previous.max(end)));
+        entries.push(TimelineEntry {
+            image_id: image.id,
+            timestamp: start.round() as i64,
+            filter: image.filter_name.clone(),
+            duration: *duration,
+            gap_before: idle.filter(|idle| *idle > gap_threshold),
+            new_session,
+            grading_status: image.grading_status,
+        });
+    }
+    if let Some(previous) = previous_end {
+        elapsed += previous - session_start;
+    }
+
+    Timeline {
+        entries,
+        on_sky_seconds: on_sky,
+        elapsed_seconds: elapsed,
+        efficiency: (elapsed > 0.0).then(|| on_sky / elapsed),
+        sessions,
+        skipped_images: images.len() - placed.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(id: i32, saved: Option<i64>, metadata: &str) -> AcquiredImage {
+        AcquiredImage {
+            id,
+            project_id: 1,
+            target_id: 1,
+            acquired_date: saved,
+            filter_name: "L".into(),
+            grading_status: 0,
+            metadata: metadata.into(),
+            reject_reason: None,
+            profile_id: None,
+            guid: None,
+        }
+    }
+
+    #[test]
+    fn gaps_sessions_and_efficiency() {
+        let exposure = r#"{"ExposureTime":300}"#;
+        let images = vec![
+            // Saved at the end of each 300 s sub, 10 s apart...
+            image(1, Some(10_300), exposure),
+            image(2, Some(10_610), exposure),
+            // ...then a 20-minute meridian flip...
+            image(3, Some(12_110), exposure),
+            // ...and the next night.
+            image(4, Some(100_300), exposure),
+            image(5, None, exposure),
+        ];
+        let timeline = build_timeline(&images, DEFAULT_GAP_THRESHOLD_SECS);
+
+        let ids: Vec<i32> = timeline.entries.iter().map(|e| e.image_id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(timeline.entries[0].timestamp, 10_000);
+        assert_eq!(timeline.entries[1].gap_before, None);
+        assert_eq!(timeline.entries[2].gap_before, Some(1_200.0));
+        assert!(!timeline.entries[2].new_session);
+        assert!(timeline.entries[3].new_session);
+        assert_eq!(timeline.sessions, 2);
+        assert_eq!(timeline.skipped_images, 1);
+
+        assert_eq!(timeline.on_sky_seconds, 1_200.0);
+        // Night one runs 10_000..12_110, night two is a single sub.
+        assert_eq!(timeline.elapsed_seconds, 2_110.0 + 300.0);
+        assert!((timeline.efficiency.unwrap() - 1_200.0 / 2_410.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn exposure_start_time_wins_over_the_saved_stamp() {
+        let images = vec![image(
+            1,
+            Some(1_705_356_000),
+            r#"{"ExposureTime":60,"ExposureStartTime":"2024-01-15T22:00:00Z"}"#,
+        )];
+        let timeline = build_timeline(&images, DEFAULT_GAP_THRESHOLD_SECS);
+        assert_eq!(timeline.entries[0].timestamp, 1_705_356_000);
+        assert_eq!(timeline.entries[0].duration, Some(60.0));
+        assert_eq!(timeline.efficiency, Some(1.0));
+    }
+}
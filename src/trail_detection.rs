@@ -0,0 +1,520 @@
+//! Pixel-level detection of satellite and airplane trails.
+//!
+//! Orbital predictions (`satellites`) only cover catalogued objects on a
+//! solved frame. Aircraft, uncatalogued satellites and frames without a plate
+//! solution still leave long straight lines, so this module looks for them
+//! directly in the pixels:
+//!
+//! 1. Bin large frames down to a ~1k working size.
+//! 2. High-pass the frame against a local box-mean background and threshold
+//!    at `sigma` times the robust (MAD) noise.
+//! 3. Drop isolated noise specks, then vote every remaining pixel into a
+//!    Hough (θ, ρ) accumulator.
+//! 4. Take the strongest line, walk it to find the longest contiguous run of
+//!    bright pixels (small gaps allowed), keep it as a trail when the run is
+//!    long enough, erase its pixels from the accumulator and repeat.
+//!
+//! Stars and short star streaks vote only a handful of pixels onto any one
+//! line, so they never reach the length threshold.
+
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::image_analysis::FitsImage;
+
+/// Frames are binned so their longer side is at most this many pixels.
+const MAX_WORKING_SIZE: usize = 1024;
+/// Radius (working pixels) of the box-mean background used for high-pass.
+const BACKGROUND_RADIUS: usize = 15;
+/// Bright components smaller than this in both directions are noise.
+const MIN_COMPONENT_EXTENT: usize = 3;
+/// Half-width (working pixels) of the band erased around each examined line.
+const ERASE_BAND: f64 = 3.0;
+/// Upper bound on accumulator peaks examined per frame.
+const MAX_PEAKS: usize = 32;
+
+/// Tuning for [`detect_trails`].
+#[derive(Debug, Clone)]
+pub struct TrailConfig {
+    /// Detection threshold in units of background noise (default 4.0).
+    pub sigma: f64,
+    /// Minimum trail length as a fraction of the frame's shorter side
+    /// (default 0.15).
+    pub min_length_fraction: f64,
+    /// Largest gap (working pixels) tolerated inside one trail (default 6).
+    pub max_gap: usize,
+    /// Stop after this many trails (default 8).
+    pub max_trails: usize,
+}
+
+impl Default for TrailConfig {
+    fn default() -> Self {
+        Self {
+            sigma: 4.0,
+            min_length_fraction: 0.15,
+            max_gap: 6,
+            max_trails: 8,
+        }
+    }
+}
+
+/// One detected linear feature, in full-resolution pixel coordinates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trail {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub length_px: f64,
+    /// Direction in degrees from the +x axis toward +y, in [0, 180).
+    pub angle_deg: f64,
+    /// Mean high-pass signal along the trail in units of background noise.
+    pub contrast: f64,
+}
+
+/// Result of [`detect_trails`] for one frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrailDetection {
+    pub width: usize,
+    pub height: usize,
+    pub trails: Vec<Trail>,
+}
+
+impl TrailDetection {
+    pub fn longest_px(&self) -> f64 {
+        self.trails.iter().map(|t| t.length_px).fold(0.0, f64::max)
+    }
+}
+
+/// Find long straight trails in a frame.
+pub fn detect_trails(image: &FitsImage, config: &TrailConfig) -> TrailDetection {
+    let bin = image
+        .width
+        .max(image.height)
+        .div_ceil(MAX_WORKING_SIZE)
+        .max(1);
+    let (w, h, plane) = bin_plane(&image.data, image.width, image.height, bin);
+    let highpass = high_pass(&plane, w, h, BACKGROUND_RADIUS);
+    let noise = robust_sigma(&highpass).max(1.0);
+    let threshold = (config.sigma * noise) as f32;
+
+    let mut mask: Vec<bool> = highpass.iter().map(|&v| v > threshold).collect();
+    drop_small_components(&mut mask, w, h, MIN_COMPONENT_EXTENT);
+
+    let min_len = config.min_length_fraction * w.min(h) as f64;
+    let mut hough = Hough::new(w, h);
+    for (i, _) in mask.iter().enumerate().filter(|(_, m)| **m) {
+        hough.vote(i % w, i / w, 1);
+    }
+
+    let mut trails = Vec::new();
+    for _ in 0..MAX_PEAKS {
+        if trails.len() >= config.max_trails {
+            break;
+        }
+        let (theta_idx, rho_idx, votes) = hough.peak();
+        if (votes as f64) < min_len {
+            break;
+        }
+        let (cos, sin, rho) = hough.line(theta_idx, rho_idx);
+
+        if let Some(run) = longest_run(&mask, &highpass, w, h, (cos, sin, rho), config.max_gap)
+            && run.length >= min_len
+        {
+            let scale = bin as f64;
+            let offset = (bin as f64 - 1.0) / 2.0;
+            let (dx, dy) = (-sin, cos);
+            let mut angle = dy.atan2(dx).to_degrees();
+            if angle < 0.0 {
+                angle += 180.0;
+            }
+            if angle >= 180.0 {
+                angle -= 180.0;
+            }
+            trails.push(Trail {
+                x1: run.start.0 * scale + offset,
+                y1: run.start.1 * scale + offset,
+                x2: run.end.0 * scale + offset,
+                y2: run.end.1 * scale + offset,
+                length_px: run.length * scale,
+                angle_deg: angle,
+                contrast: run.mean_signal / noise,
+            });
+        }
+
+        // Erase the examined line so the next peak is a different feature.
+        let mut removed = 0;
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                if mask[i] && (x as f64 * cos + y as f64 * sin - rho).abs() <= ERASE_BAND {
+                    mask[i] = false;
+                    hough.vote(x, y, -1);
+                    removed += 1;
+                }
+            }
+        }
+        if removed == 0 {
+            hough.clear(theta_idx, rho_idx);
+        }
+    }
+
+    TrailDetection {
+        width: image.width,
+        height: image.height,
+        trails,
+    }
+}
+
+/// Stretched grayscale copy of the frame with detected trails drawn in red.
+pub fn render_overlay(image: &FitsImage, detection: &TrailDetection) -> RgbaImage {
+    use imageproc::drawing::draw_line_segment_mut;
+
+    let values: Vec<f32> = image.data.iter().map(|&v| v as f32).collect();
+    let gray =
+        crate::commands::background_extract::stretch_to_l8(&values, image.width, image.height);
+    let mut out = RgbaImage::from_fn(image.width as u32, image.height as u32, |x, y| {
+        let v = gray[y as usize * image.width + x as usize];
+        Rgba([v, v, v, 255])
+    });
+
+    let red = Rgba([255, 40, 40, 255]);
+    let thickness = (image.width.max(image.height) / 800).max(1) as i32;
+    for trail in &detection.trails {
+        // Offset copies along the normal give the line some weight.
+        let (nx, ny) = {
+            let a = trail.angle_deg.to_radians();
+            (-a.sin(), a.cos())
+        };
+        for k in -thickness..=thickness {
+            let (ox, oy) = (nx * k as f64, ny * k as f64);
+            draw_line_segment_mut(
+                &mut out,
+                ((trail.x1 + ox) as f32, (trail.y1 + oy) as f32),
+                ((trail.x2 + ox) as f32, (trail.y2 + oy) as f32),
+                red,
+            );
+        }
+    }
+    out
+}
+
+/// Average-bin a u16 plane by `bin` in both directions.
+fn bin_plane(data: &[u16], width: usize, height: usize, bin: usize) -> (usize, usize, Vec<f32>) {
+    if bin == 1 {
+        return (width, height, data.iter().map(|&v| v as f32).collect());
+    }
+    let (w, h) = (width / bin, height / bin);
+    let mut out = vec![0f32; w * h];
+    let norm = 1.0 / (bin * bin) as f32;
+    for by in 0..h {
+        for bx in 0..w {
+            let mut sum = 0f32;
+            for y in by * bin..(by + 1) * bin {
+                let row = &data[y * width + bx * bin..y * width + (bx + 1) * bin];
+                sum += row.iter().map(|&v| v as f32).sum::<f32>();
+            }
+            out[by * w + bx] = sum * norm;
+        }
+    }
+    (w, h, out)
+}
+
+/// Subtract a box-mean background of the given radius (clamped at the edges).
+fn high_pass(plane: &[f32], w: usize, h: usize, radius: usize) -> Vec<f32> {
+    // Summed-area table with a zero row/column border.
+    let stride = w + 1;
+    let mut sat = vec![0f64; stride * (h + 1)];
+    for y in 0..h {
+        let mut row_sum = 0f64;
+        for x in 0..w {
+            row_sum += plane[y * w + x] as f64;
+            sat[(y + 1) * stride + x + 1] = sat[y * stride + x + 1] + row_sum;
+        }
+    }
+
+    let mut out = vec![0f32; w * h];
+    for y in 0..h {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(h));
+        for x in 0..w {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(w));
+            let sum = sat[y1 * stride + x1] - sat[y0 * stride + x1] - sat[y1 * stride + x0]
+                + sat[y0 * stride + x0];
+            let mean = sum / ((y1 - y0) * (x1 - x0)) as f64;
+            out[y * w + x] = plane[y * w + x] - mean as f32;
+        }
+    }
+    out
+}
+
+/// 1.4826 × median absolute deviation.
+fn robust_sigma(values: &[f32]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut v = values.to_vec();
+    let mid = v.len() / 2;
+    let median = *v.select_nth_unstable_by(mid, f32::total_cmp).1;
+    let mut dev: Vec<f32> = v.iter().map(|&x| (x - median).abs()).collect();
+    let mad = *dev.select_nth_unstable_by(mid, f32::total_cmp).1;
+    1.4826 * mad as f64
+}
+
+/// Clear 8-connected components whose bounding box is smaller than
+/// `min_extent` in both directions.
+fn drop_small_components(mask: &mut [bool], w: usize, h: usize, min_extent: usize) {
+    let mut seen = vec![false; mask.len()];
+    let mut queue = VecDeque::new();
+    let mut members = Vec::new();
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        queue.push_back(start);
+        members.clear();
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (w, 0, h, 0);
+        while let Some(i) = queue.pop_front() {
+            members.push(i);
+            let (x, y) = (i % w, i / w);
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+            for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                    let j = ny * w + nx;
+                    if mask[j] && !seen[j] {
+                        seen[j] = true;
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        if max_x - min_x + 1 < min_extent && max_y - min_y + 1 < min_extent {
+            for &i in &members {
+                mask[i] = false;
+            }
+        }
+    }
+}
+
+/// Hough accumulator over θ ∈ [0°, 180°) in 1° steps and integer ρ.
+struct Hough {
+    cos: Vec<f64>,
+    sin: Vec<f64>,
+    diag: usize,
+    rho_bins: usize,
+    acc: Vec<i32>,
+}
+
+impl Hough {
+    const THETA_BINS: usize = 180;
+
+    fn new(w: usize, h: usize) -> Self {
+        let diag = ((w * w + h * h) as f64).sqrt().ceil() as usize;
+        let rho_bins = 2 * diag + 1;
+        let (cos, sin): (Vec<f64>, Vec<f64>) = (0..Self::THETA_BINS)
+            .map(|t| (t as f64).to_radians())
+            .map(|a| (a.cos(), a.sin()))
+            .unzip();
+        Self {
+            cos,
+            sin,
+            diag,
+            rho_bins,
+            acc: vec![0; Self::THETA_BINS * rho_bins],
+        }
+    }
+
+    fn vote(&mut self, x: usize, y: usize, weight: i32) {
+        for t in 0..Self::THETA_BINS {
+            let rho = x as f64 * self.cos[t] + y as f64 * self.sin[t];
+            let r = (rho + self.diag as f64).round() as usize;
+            self.acc[t * self.rho_bins + r] += weight;
+        }
+    }
+
+    fn peak(&self) -> (usize, usize, i32) {
+        let (idx, &votes) = self
+            .acc
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))
+            .unwrap_or((0, &0));
+        (idx / self.rho_bins, idx % self.rho_bins, votes)
+    }
+
+    fn clear(&mut self, theta_idx: usize, rho_idx: usize) {
+        self.acc[theta_idx * self.rho_bins + rho_idx] = 0;
+    }
+
+    /// (cos θ, sin θ, ρ) of an accumulator cell.
+    fn line(&self, theta_idx: usize, rho_idx: usize) -> (f64, f64, f64) {
+        (
+            self.cos[theta_idx],
+            self.sin[theta_idx],
+            rho_idx as f64 - self.diag as f64,
+        )
+    }
+}
+
+struct Run {
+    start: (f64, f64),
+    end: (f64, f64),
+    length: f64,
+    mean_signal: f64,
+}
+
+/// Walk the line `x cos θ + y sin θ = ρ` across the frame and return its
+/// longest stretch of mask hits with gaps of at most `max_gap` pixels.
+fn longest_run(
+    mask: &[bool],
+    highpass: &[f32],
+    w: usize,
+    h: usize,
+    (cos, sin, rho): (f64, f64, f64),
+    max_gap: usize,
+) -> Option<Run> {
+    let diag = ((w * w + h * h) as f64).sqrt().ceil() as i64;
+    let (px, py) = (rho * cos, rho * sin);
+    let (dx, dy) = (-sin, cos);
+
+    let sample = |t: i64| -> Option<f32> {
+        let (cx, cy) = (px + t as f64 * dx, py + t as f64 * dy);
+        let mut best: Option<f32> = None;
+        for s in [-1.0, 0.0, 1.0] {
+            let (x, y) = ((cx + s * cos).round(), (cy + s * sin).round());
+            if x < 0.0 || y < 0.0 || x >= w as f64 || y >= h as f64 {
+                continue;
+            }
+            let i = y as usize * w + x as usize;
+            if mask[i] {
+                best = Some(best.map_or(highpass[i], |b| b.max(highpass[i])));
+            }
+        }
+        best
+    };
+
+    let mut best: Option<(i64, i64, f64, usize)> = None;
+    let mut current: Option<(i64, i64, f64, usize)> = None;
+    for t in -diag..=diag {
+        let Some(signal) = sample(t) else {
+            continue;
+        };
+        let run = match current {
+            Some((start, last, sum, hits)) if t - last <= max_gap as i64 + 1 => {
+                (start, t, sum + signal as f64, hits + 1)
+            }
+            _ => (t, t, signal as f64, 1),
+        };
+        current = Some(run);
+        if best.is_none_or(|(bs, bl, _, _)| run.1 - run.0 > bl - bs) {
+            best = Some(run);
+        }
+    }
+
+    best.map(|(start, end, sum, hits)| Run {
+        start: (px + start as f64 * dx, py + start as f64 * dy),
+        end: (px + end as f64 * dx, py + end as f64 * dy),
+        length: (end - start) as f64,
+        mean_signal: sum / hits as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 256×256 sky with deterministic noise and a scatter of stars.
+    fn star_field() -> (usize, usize, Vec<f32>) {
+        let (w, h) = (256usize, 256usize);
+        let mut state = 0x2545_f491_u32;
+        let mut noise = || {
+            // Sum of uniforms: roughly Gaussian, sigma ≈ 10 ADU.
+            let mut sum = 0.0;
+            for _ in 0..4 {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                sum += (state >> 8) as f32 / (1u32 << 24) as f32;
+            }
+            (sum - 2.0) * 17.3
+        };
+        let mut data: Vec<f32> = (0..w * h).map(|_| 1000.0 + noise()).collect();
+        let stars = [
+            (31.0, 47.0),
+            (88.0, 19.0),
+            (140.0, 61.0),
+            (205.0, 33.0),
+            (52.0, 132.0),
+            (117.0, 170.0),
+            (190.0, 120.0),
+            (236.0, 214.0),
+            (23.0, 221.0),
+            (160.0, 238.0),
+        ];
+        for &(sx, sy) in &stars {
+            for y in 0..h {
+                for x in 0..w {
+                    let r2 = (x as f32 - sx).powi(2) + (y as f32 - sy).powi(2);
+                    if r2 < 100.0 {
+                        data[y * w + x] += 2000.0 * (-r2 / 4.5).exp();
+                    }
+                }
+            }
+        }
+        (w, h, data)
+    }
+
+    fn to_image(w: usize, h: usize, data: &[f32]) -> FitsImage {
+        FitsImage {
+            width: w,
+            height: h,
+            data: data.iter().map(|&v| v.clamp(0.0, 65535.0) as u16).collect(),
+            raw_min: 0.0,
+            raw_scale: 1.0,
+            bzero: 0.0,
+            header: Default::default(),
+        }
+    }
+
+    #[test]
+    fn star_field_has_no_trails() {
+        let (w, h, data) = star_field();
+        let detection = detect_trails(&to_image(w, h, &data), &TrailConfig::default());
+        assert!(detection.trails.is_empty(), "{:?}", detection.trails);
+    }
+
+    #[test]
+    fn long_line_is_reported_with_length_and_angle() {
+        let (w, h, mut data) = star_field();
+        // Trail from (20, 30) to (230, 200): ~270 px at ~39°.
+        let (x1, y1, x2, y2) = (20.0f32, 30.0f32, 230.0f32, 200.0f32);
+        let len = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        let (ux, uy) = ((x2 - x1) / len, (y2 - y1) / len);
+        for y in 0..h {
+            for x in 0..w {
+                let (rx, ry) = (x as f32 - x1, y as f32 - y1);
+                let along = rx * ux + ry * uy;
+                let across = (rx * uy - ry * ux).abs();
+                if (0.0..=len).contains(&along) && across <= 1.0 {
+                    data[y * w + x] += 400.0;
+                }
+            }
+        }
+
+        let image = to_image(w, h, &data);
+        let detection = detect_trails(&image, &TrailConfig::default());
+        assert_eq!(detection.trails.len(), 1, "{:?}", detection.trails);
+        let trail = &detection.trails[0];
+        assert!((trail.angle_deg - 39.0).abs() < 2.0, "{trail:?}");
+        assert!(
+            (trail.length_px - len as f64).abs() < 0.15 * len as f64,
+            "{trail:?}"
+        );
+        assert!(trail.contrast > 10.0, "{trail:?}");
+
+        let overlay = render_overlay(&image, &detection);
+        assert_eq!(overlay.dimensions(), (w as u32, h as u32));
+    }
+}
@@ -17,10 +17,45 @@ pub fn extract_filename(metadata: &str) -> Option<String> {
     })
 }
 
+/// `png` as a `data:` URI, for images embedded in HTML reports and SVGs.
+pub fn png_data_uri(png: &[u8]) -> String {
+    format!("data:image/png;base64,{}", base64_encode(png))
+}
+
+/// Standard (RFC 4648) base64 with padding.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn base64_matches_rfc_4648_vectors() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(png_data_uri(b"fo"), "data:image/png;base64,Zm8=");
+    }
+
     #[test]
     fn test_truncate_string_short() {
         assert_eq!(truncate_string("hello", 10), "hello");
@@ -0,0 +1,325 @@
+//! Rough photometric zero-point from stars matched to a reference catalog.
+//!
+//! Reference stars (RA/Dec/magnitude from a user-supplied CSV, e.g. a Gaia
+//! or Tycho extract covering the field) are projected into the frame through
+//! its TAN WCS and paired one-to-one with the nearest detected star inside
+//! a radius. Each pair gives `catalog_mag - instrumental_mag`, where the
+//! instrumental magnitude is `-2.5 log10(flux / exposure)` of the
+//! background-subtracted flux in physical ADU. The zero-point is the
+//! sigma-clipped mean of those differences and the scatter their standard
+//! deviation.
+//!
+//! Rough by design: no color term, aperture correction or extinction, so
+//! expect a few tenths of a magnitude of scatter against broadband catalogs.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::astrometry_headers::FitsWcsHeaders;
+use crate::photometry::CatalogStar;
+
+/// Column names tried, in order, when the CSV header doesn't say otherwise.
+const RA_COLUMNS: [&str; 4] = ["ra", "ra_deg", "raj2000", "ra_icrs"];
+const DEC_COLUMNS: [&str; 5] = ["dec", "dec_deg", "dej2000", "decj2000", "de_icrs"];
+const MAG_COLUMNS: [&str; 5] = ["mag", "phot_g_mean_mag", "gmag", "vtmag", "vmag"];
+
+/// Sigma-clipping rounds and threshold for the zero-point fit.
+const CLIP_ROUNDS: usize = 5;
+const CLIP_SIGMA: f64 = 3.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceStar {
+    pub ra_deg: f64,
+    pub dec_deg: f64,
+    pub mag: f64,
+}
+
+/// Read a reference catalog CSV; see [`parse_reference_csv`].
+pub fn load_reference_csv(path: &Path, mag_column: Option<&str>) -> Result<Vec<ReferenceStar>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("reading catalog {}", path.display()))?;
+    parse_reference_csv(&text, mag_column).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Parse a reference catalog: a header row, then one star per row with RA
+/// and Dec in decimal degrees and a magnitude. Columns are found by name
+/// (case-insensitive): `ra`/`dec`/`mag` or common Gaia/VizieR spellings, or
+/// `mag_column` to pick a band. Rows with a blank or non-numeric value are
+/// skipped. Plain comma-separated values; quoted fields aren't supported.
+pub fn parse_reference_csv(text: &str, mag_column: Option<&str>) -> Result<Vec<ReferenceStar>> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let Some(header) = lines.next() else {
+        bail!("Catalog is empty");
+    };
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+    let find = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|n| columns.iter().position(|c| c == n))
+    };
+    let ra = find(&RA_COLUMNS).context("No RA column (ra, ra_deg, raj2000, ...)")?;
+    let dec = find(&DEC_COLUMNS).context("No Dec column (dec, dec_deg, dej2000, ...)")?;
+    let mag = match mag_column {
+        Some(name) => find(&[name.to_ascii_lowercase().as_str()])
+            .with_context(|| format!("No '{}' column", name))?,
+        None => find(&MAG_COLUMNS).context("No magnitude column (mag, phot_g_mean_mag, ...)")?,
+    };
+
+    Ok(lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let number = |i: usize| fields.get(i)?.parse::<f64>().ok().filter(|v| v.is_finite());
+            Some(ReferenceStar {
+                ra_deg: number(ra)?,
+                dec_deg: number(dec)?,
+                mag: number(mag)?,
+            })
+        })
+        .collect())
+}
+
+/// One detected star paired with a reference star.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedStar {
+    pub x: f64,
+    pub y: f64,
+    pub ra_deg: f64,
+    pub dec_deg: f64,
+    pub catalog_mag: f64,
+    pub instrumental_mag: f64,
+    pub separation_arcsec: f64,
+    /// `catalog_mag - instrumental_mag - zero_point` once fitted.
+    pub residual: f64,
+    /// Rejected by the sigma clip.
+    pub clipped: bool,
+}
+
+/// Pair detected stars with reference stars that project into the
+/// `width` x `height` frame, nearest first, each star used at most once.
+pub fn match_stars(
+    detected: &[CatalogStar],
+    reference: &[ReferenceStar],
+    wcs: &FitsWcsHeaders,
+    (width, height): (usize, usize),
+    exposure_s: f64,
+    radius_arcsec: f64,
+) -> Vec<MatchedStar> {
+    let scale = wcs.pixel_scale_arcsec();
+    let radius_px = radius_arcsec / scale;
+
+    let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+    for (r, star) in reference.iter().enumerate() {
+        let Some((px, py)) = wcs.world_to_pixel(star.ra_deg, star.dec_deg) else {
+            continue;
+        };
+        if px < 0.0 || py < 0.0 || px >= width as f64 || py >= height as f64 {
+            continue;
+        }
+        for (d, det) in detected.iter().enumerate() {
+            let distance = (det.x - px).hypot(det.y - py);
+            if distance <= radius_px && det.flux > 0.0 {
+                candidates.push((distance, r, d));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut reference_used = vec![false; reference.len()];
+    let mut detected_used = vec![false; detected.len()];
+    let mut matches = Vec::new();
+    for (distance, r, d) in candidates {
+        if reference_used[r] || detected_used[d] {
+            continue;
+        }
+        reference_used[r] = true;
+        detected_used[d] = true;
+        let (star, det) = (&reference[r], &detected[d]);
+        matches.push(MatchedStar {
+            x: det.x,
+            y: det.y,
+            ra_deg: star.ra_deg,
+            dec_deg: star.dec_deg,
+            catalog_mag: star.mag,
+            instrumental_mag: -2.5 * (det.flux / exposure_s).log10(),
+            separation_arcsec: distance * scale,
+            residual: 0.0,
+            clipped: false,
+        });
+    }
+    matches
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ZeroPointFit {
+    /// Magnitude of a source giving 1 ADU/s.
+    pub zero_point: f64,
+    /// Standard deviation of the kept residuals.
+    pub scatter: f64,
+    /// Standard error of the zero-point.
+    pub zero_point_error: f64,
+    pub matched: usize,
+    pub used: usize,
+    /// Least-squares slope of catalog against instrumental magnitude over
+    /// the kept stars; far from 1 means non-linearity, saturation or a
+    /// mismatched band.
+    pub slope: Option<f64>,
+}
+
+/// Sigma-clipped zero-point over `matches`, filling in each star's residual
+/// and clip flag. `None` with fewer than three stars.
+pub fn fit_zero_point(matches: &mut [MatchedStar]) -> Option<ZeroPointFit> {
+    if matches.len() < 3 {
+        return None;
+    }
+    let offsets: Vec<f64> = matches
+        .iter()
+        .map(|m| m.catalog_mag - m.instrumental_mag)
+        .collect();
+    let mut kept = vec![true; offsets.len()];
+    let (mut mean, mut std_dev) = mean_std(&offsets, &kept);
+
+    for _ in 0..CLIP_ROUNDS {
+        let next: Vec<bool> = offsets
+            .iter()
+            .map(|o| std_dev == 0.0 || (o - mean).abs() <= CLIP_SIGMA * std_dev)
+            .collect();
+        if next == kept || next.iter().filter(|k| **k).count() < 3 {
+            break;
+        }
+        kept = next;
+        (mean, std_dev) = mean_std(&offsets, &kept);
+    }
+
+    for ((m, offset), keep) in matches.iter_mut().zip(&offsets).zip(&kept) {
+        m.residual = offset - mean;
+        m.clipped = !keep;
+    }
+    let used = kept.iter().filter(|k| **k).count();
+    Some(ZeroPointFit {
+        zero_point: mean,
+        scatter: std_dev,
+        zero_point_error: std_dev / (used as f64).sqrt(),
+        matched: matches.len(),
+        used,
+        slope: slope(matches.iter().filter(|m| !m.clipped)),
+    })
+}
+
+/// Mean and sample standard deviation of the kept values.
+fn mean_std(values: &[f64], kept: &[bool]) -> (f64, f64) {
+    let kept: Vec<f64> = values
+        .iter()
+        .zip(kept)
+        .filter(|(_, k)| **k)
+        .map(|(v, _)| *v)
+        .collect();
+    let n = kept.len() as f64;
+    let mean = kept.iter().sum::<f64>() / n;
+    let variance = kept.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+    (mean, variance.sqrt())
+}
+
+fn slope<'a>(matches: impl Iterator<Item = &'a MatchedStar>) -> Option<f64> {
+    let points: Vec<(f64, f64)> = matches
+        .map(|m| (m.instrumental_mag, m.catalog_mag))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    (sxx > 0.0).then(|| sxy / sxx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wcs() -> FitsWcsHeaders {
+        FitsWcsHeaders {
+            crval: [83.8, -5.4],
+            crpix: [500.0, 500.0],
+            cd: [[-1.0 / 3600.0, 0.0], [0.0, 1.0 / 3600.0]],
+            ctype: ["RA---TAN".into(), "DEC--TAN".into()],
+            cunit: ["deg".into(), "deg".into()],
+            radesys: "ICRS".into(),
+            equinox: 2000.0,
+        }
+    }
+
+    #[test]
+    fn parses_named_columns_and_skips_incomplete_rows() {
+        let csv = "# Gaia DR3 extract\n\
+                   source_id,ra,dec,phot_g_mean_mag,phot_bp_mean_mag\n\
+                   1,83.81,-5.39,9.5,9.9\n\
+                   2,83.82,-5.38,,10.1\n\
+                   3,83.83,-5.37,11.0,11.2\n";
+        let stars = parse_reference_csv(csv, None).unwrap();
+        assert_eq!(stars.len(), 2);
+        assert_eq!(stars[1].mag, 11.0);
+
+        let bp = parse_reference_csv(csv, Some("PHOT_BP_MEAN_MAG")).unwrap();
+        assert_eq!(bp.len(), 3);
+        assert_eq!(bp[1].mag, 10.1);
+        assert!(parse_reference_csv("ra,dec\n1,2\n", None).is_err());
+    }
+
+    #[test]
+    fn recovers_zero_point_and_clips_a_blend() {
+        let wcs = wcs();
+        let exposure = 60.0;
+        let zero_point = 21.5;
+        let mut reference = Vec::new();
+        let mut detected = Vec::new();
+        for i in 0..12 {
+            let (x, y) = (100.0 + 60.0 * i as f64, 200.0 + 40.0 * i as f64);
+            let (ra, dec) = wcs.pixel_to_world(x, y);
+            let mag = 9.0 + 0.4 * i as f64;
+            // +/-0.02 mag of noise, alternating.
+            let noise = if i % 2 == 0 { 0.02 } else { -0.02 };
+            let flux = exposure * 10f64.powf(-0.4 * (mag - zero_point + noise));
+            reference.push(ReferenceStar {
+                ra_deg: ra,
+                dec_deg: dec,
+                mag,
+            });
+            // Detections land half a pixel off the catalog position.
+            detected.push(CatalogStar {
+                x: x + 0.5,
+                y,
+                flux,
+            });
+        }
+        // A blend: twice as bright as the catalog says.
+        detected[5].flux *= 2.0;
+        // Off the frame: never matched.
+        reference.push(ReferenceStar {
+            ra_deg: 90.0,
+            dec_deg: -5.4,
+            mag: 8.0,
+        });
+
+        let mut matches = match_stars(&detected, &reference, &wcs, (1000, 1000), exposure, 2.0);
+        assert_eq!(matches.len(), 12);
+        assert!((matches[0].separation_arcsec - 0.5).abs() < 1e-6);
+
+        let fit = fit_zero_point(&mut matches).unwrap();
+        assert_eq!((fit.matched, fit.used), (12, 11));
+        assert!(
+            (fit.zero_point - zero_point).abs() < 0.01,
+            "{}",
+            fit.zero_point
+        );
+        assert!(fit.scatter < 0.03);
+        assert!((fit.slope.unwrap() - 1.0).abs() < 0.01);
+        let blend = matches.iter().find(|m| m.clipped).unwrap();
+        assert_eq!(blend.catalog_mag, 9.0 + 0.4 * 5.0);
+    }
+}
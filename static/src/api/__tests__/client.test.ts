@@ -1,7 +1,8 @@
-import { describe, it, expect, beforeEach } from 'vitest';
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
 import { http, HttpResponse } from 'msw';
 import { server } from '../../test/msw-server';
 import { apiClient } from '../client';
+import { saveAuthToken } from '../../utils/auth';
 import normalFixture from '../../__fixtures__/sequence-analysis-normal.json';
 import cloudsFixture from '../../__fixtures__/sequence-analysis-clouds.json';
 import imageQualityFixture from '../../__fixtures__/image-quality-context.json';
@@ -155,3 +156,24 @@ describe('apiClient database errors', () => {
     );
   });
 });
+
+describe('apiClient auth token', () => {
+  afterEach(() => saveAuthToken(null));
+
+  it('sends the stored token as a bearer header', async () => {
+    let authorization: string | null = null;
+    server.use(
+      http.get('/api/info', ({ request }) => {
+        authorization = request.headers.get('authorization');
+        return HttpResponse.json({ success: true, data: { read_only: false }, error: null });
+      }),
+    );
+
+    await apiClient.getServerInfo();
+    expect(authorization).toBeNull();
+
+    saveAuthToken('s3cret-token');
+    await apiClient.getServerInfo();
+    expect(authorization).toBe('Bearer s3cret-token');
+  });
+});
@@ -1,15 +1,34 @@
 import axios from 'axios';
 import type { AxiosInstance } from 'axios';
 import { getServerUrl } from '../utils/tauri';
+import { initializeAuthToken, loadAuthToken } from '../utils/auth';
 import type {
   ApiResponse,
   Project,
   Target,
   Image,
   ImageQuery,
+  SessionQuery,
+  ImageNeighbors,
+  GradingDetail,
+  AcquisitionSession,
+  TargetIntegration,
+  TargetTimeline,
+  FocusStability,
+  PsfDataResponse,
+  TrendMetric,
+  TrendPoint,
   UpdateGradeRequest,
+  FavoriteResponse,
+  ImageTagsResponse,
+  TagCount,
   StarDetectionResponse,
   PreviewOptions,
+  CompareOptions,
+  ContactSheetOptions,
+  DiffStats,
+  PixelRegionResponse,
+  StarDetectionParams,
   ServerInfo,
   SchedulerSyncRequest,
   SchedulerSyncPreviewResponse,
@@ -21,6 +40,8 @@ import type {
   ImportStatus,
   FileCheckResponse,
   DirectoryTreeResponse,
+  DirectoryCacheQuery,
+  DirectoryCacheResponse,
   ProjectOverview,
   TargetOverview,
   ProjectSchedulerDetails,
@@ -29,6 +50,10 @@ import type {
   CreateExposurePlanRequest,
   ExposurePlanDetails,
   OverallStats,
+  RejectionStats,
+  RejectionStatsQuery,
+  CurationExport,
+  CurationImportSummary,
   CacheRefreshProgress,
   SequenceAnalysisRequest,
   SequenceAnalysisResponse,
@@ -37,6 +62,7 @@ import type {
   SpatialScanStatus,
   QualityBackfillRequest,
   QualityBackfillStatus,
+  WarmCacheStatus,
   PreviewDescriptor,
   GenerationStatus,
   AstrometryAnalysis,
@@ -74,6 +100,14 @@ const initializeApi = async () => {
         'Content-Type': 'application/json',
       },
     });
+    initializeAuthToken();
+
+    // Read the token per request so one saved after startup applies too.
+    initializedApi.interceptors.request.use((config) => {
+      const token = loadAuthToken();
+      if (token) config.headers.set('Authorization', `Bearer ${token}`);
+      return config;
+    });
 
     // Add response interceptor for error handling
     initializedApi.interceptors.response.use(
@@ -418,6 +452,53 @@ export const apiClient = {
     return data.data;
   },
 
+  /** A project's grades, reasons, favorites and tags as a portable document. */
+  exportProjectCuration: async (
+    dbId: string,
+    projectId: number
+  ): Promise<CurationExport> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<CurationExport>(
+      dbPath(dbId, `/projects/${projectId}/export`)
+    );
+    return data;
+  },
+
+  /** Re-apply a curation document, matched by filename and timestamp. */
+  importProjectCuration: async (
+    dbId: string,
+    projectId: number,
+    document: CurationExport,
+    dryRun = false
+  ): Promise<CurationImportSummary> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.post<ApiResponse<CurationImportSummary>>(
+      dbPath(dbId, `/projects/${projectId}/import`),
+      document,
+      { params: { dry_run: dryRun } }
+    );
+    if (!data.data) throw new Error(data.error || 'Failed to import curation');
+    return data.data;
+  },
+
+  /** Reassign a target's images to another target in the same project. */
+  mergeTarget: async (
+    dbId: string,
+    targetId: number,
+    intoTargetId: number,
+    deleteSource = false
+  ): Promise<{ images_moved: number; source_deleted: boolean }> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.post<
+      ApiResponse<{ images_moved: number; source_deleted: boolean }>
+    >(dbPath(dbId, `/targets/${targetId}/merge`), {
+      into_target_id: intoTargetId,
+      delete_source: deleteSource,
+    });
+    if (!data.data) throw new Error(data.error || 'Failed to merge target');
+    return data.data;
+  },
+
   // ── Per-DB ────────────────────────────────────────────────────────────────
 
   refreshFileCache: async (dbId: string): Promise<FileCheckResponse> => {
@@ -438,6 +519,19 @@ export const apiClient = {
     return data.data;
   },
 
+  getDirectoryCache: async (
+    dbId: string,
+    params: DirectoryCacheQuery = {}
+  ): Promise<DirectoryCacheResponse> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<DirectoryCacheResponse>>(
+      dbPath(dbId, '/directory-cache'),
+      { params }
+    );
+    if (!data.data) throw new Error('Failed to fetch directory cache');
+    return data.data;
+  },
+
   getProjects: async (dbId: string): Promise<Project[]> => {
     const apiInstance = await getApi();
     const { data } = await apiInstance.get<ApiResponse<Project[]>>(dbPath(dbId, '/projects'));
@@ -632,12 +726,95 @@ export const apiClient = {
     return data.data || [];
   },
 
+  getImageNeighbors: async (
+    dbId: string,
+    imageId: number,
+    query: Omit<ImageQuery, 'limit' | 'offset'>
+  ): Promise<ImageNeighbors> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<ImageNeighbors>>(
+      dbPath(dbId, `/images/${imageId}/neighbors`),
+      { params: query }
+    );
+    if (!data.data) throw new Error(data.error || 'Image not found');
+    return data.data;
+  },
+
+  getGradingDetail: async (dbId: string, imageId: number): Promise<GradingDetail> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<GradingDetail>>(
+      dbPath(dbId, `/images/${imageId}/grading-detail`)
+    );
+    if (!data.data) throw new Error(data.error || 'Image not found');
+    return data.data;
+  },
+
+  getSessions: async (dbId: string, query: SessionQuery): Promise<AcquisitionSession[]> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<AcquisitionSession[]>>(
+      dbPath(dbId, '/sessions'),
+      { params: query }
+    );
+    return data.data || [];
+  },
+
+  getTargetIntegration: async (dbId: string, targetId: number): Promise<TargetIntegration> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<TargetIntegration>>(
+      dbPath(dbId, `/targets/${targetId}/integration`)
+    );
+    if (!data.data) throw new Error(data.error || 'Integration report unavailable');
+    return data.data;
+  },
+
+  getTargetTimeline: async (
+    dbId: string,
+    targetId: number,
+    options: { filter?: string; gap_threshold?: number } = {}
+  ): Promise<TargetTimeline> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<TargetTimeline>>(
+      dbPath(dbId, `/targets/${targetId}/timeline`),
+      { params: options }
+    );
+    if (!data.data) throw new Error(data.error || 'Timeline unavailable');
+    return data.data;
+  },
+
+  getFocusStability: async (
+    dbId: string,
+    targetId: number,
+    options: { filter?: string; session?: number; session_gap_minutes?: number } = {}
+  ): Promise<FocusStability> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<FocusStability>>(
+      dbPath(dbId, `/targets/${targetId}/focus-stability`),
+      { params: options }
+    );
+    if (!data.data) throw new Error(data.error || 'Focus stability unavailable');
+    return data.data;
+  },
+
+  getTargetTrend: async (
+    dbId: string,
+    targetId: number,
+    metric: TrendMetric,
+    filter?: string
+  ): Promise<TrendPoint[]> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<TrendPoint[]>>(
+      dbPath(dbId, `/targets/${targetId}/trend`),
+      { params: { metric, filter } }
+    );
+    return data.data || [];
+  },
+
   getImage: async (dbId: string, imageId: number): Promise<Image> => {
     const apiInstance = await getApi();
     const { data } = await apiInstance.get<ApiResponse<Image>>(
       dbPath(dbId, `/images/${imageId}`)
     );
-    if (!data.data) throw new Error('Image not found');
+    if (!data.data) throw new Error(data.error || 'Image not found');
     return data.data;
   },
 
@@ -686,15 +863,88 @@ export const apiClient = {
     await apiInstance.put(dbPath(dbId, `/images/${imageId}/grade`), request);
   },
 
-  getStarDetection: async (dbId: string, imageId: number): Promise<StarDetectionResponse> => {
+  setImageFavorite: async (
+    dbId: string,
+    imageId: number,
+    favorite: boolean
+  ): Promise<FavoriteResponse> => {
+    const apiInstance = await getApi();
+    const path = dbPath(dbId, `/images/${imageId}/favorite`);
+    const { data } = favorite
+      ? await apiInstance.post<ApiResponse<FavoriteResponse>>(path)
+      : await apiInstance.delete<ApiResponse<FavoriteResponse>>(path);
+    if (!data.data) throw new Error(data.error || 'Failed to update favorite');
+    return data.data;
+  },
+
+  addImageTags: async (
+    dbId: string,
+    imageId: number,
+    tags: string[]
+  ): Promise<ImageTagsResponse> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.post<ApiResponse<ImageTagsResponse>>(
+      dbPath(dbId, `/images/${imageId}/tags`),
+      { tags }
+    );
+    if (!data.data) throw new Error(data.error || 'Failed to add tags');
+    return data.data;
+  },
+
+  removeImageTag: async (
+    dbId: string,
+    imageId: number,
+    tag: string
+  ): Promise<ImageTagsResponse> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.delete<ApiResponse<ImageTagsResponse>>(
+      dbPath(dbId, `/images/${imageId}/tags/${encodeURIComponent(tag)}`)
+    );
+    if (!data.data) throw new Error(data.error || 'Failed to remove tag');
+    return data.data;
+  },
+
+  getTags: async (dbId: string): Promise<TagCount[]> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<TagCount[]>>(
+      dbPath(dbId, '/tags')
+    );
+    if (!data.data) throw new Error(data.error || 'Failed to fetch tags');
+    return data.data;
+  },
+
+  getStarDetection: async (
+    dbId: string,
+    imageId: number,
+    bin?: 1 | 2 | 4,
+    // Detect in "x,y,w,h" only; positions are full-frame unless coords is 'crop'.
+    region?: { roi: string; coords?: 'full' | 'crop' },
+    tuning?: StarDetectionParams
+  ): Promise<StarDetectionResponse> => {
     const apiInstance = await getApi();
     const { data } = await apiInstance.get<ApiResponse<StarDetectionResponse>>(
-      dbPath(dbId, `/images/${imageId}/stars`)
+      dbPath(dbId, `/images/${imageId}/stars`),
+      { params: { ...(bin && bin > 1 ? { bin } : {}), ...region, ...tuning } }
     );
     if (!data.data) throw new Error('Star detection failed');
     return data.data;
   },
 
+  // Raw ADU values of a region of at most 64x64 pixels.
+  getImagePixels: async (
+    dbId: string,
+    imageId: number,
+    region: { x: number; y: number; w: number; h: number }
+  ): Promise<PixelRegionResponse> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<PixelRegionResponse>>(
+      dbPath(dbId, `/images/${imageId}/pixels`),
+      { params: region }
+    );
+    if (!data.data) throw new Error(data.error || 'Failed to read pixels');
+    return data.data;
+  },
+
   // Batch readiness poll for on-demand previews/annotated images. One request
   // for a whole grid of pending images instead of one poll per image. Returns
   // statuses parallel to `requests`.
@@ -711,7 +961,11 @@ export const apiClient = {
         stretch: d.stretch,
         midtone: d.midtone,
         shadow: d.shadow,
+        logarithmic: d.logarithmic,
+        invert: d.invert,
         max_stars: d.maxStars,
+        roi: d.roi,
+        background: d.background,
       })),
     };
     const { data } = await apiInstance.post<
@@ -727,6 +981,12 @@ export const apiClient = {
     if (options?.stretch !== undefined) params.append('stretch', String(options.stretch));
     if (options?.midtone !== undefined) params.append('midtone', String(options.midtone));
     if (options?.shadow !== undefined) params.append('shadow', String(options.shadow));
+    if (options?.logarithmic) params.append('logarithmic', 'true');
+    if (options?.invert) params.append('invert', 'true');
+    if (options?.roi) params.append('roi', options.roi);
+    if (options?.background) params.append('background', options.background);
+    if (options?.fmt) params.append('fmt', options.fmt);
+    if (options?.bit_depth) params.append('bit_depth', String(options.bit_depth));
 
     const queryString = params.toString();
     const basePath = serverUrl ? `${serverUrl}/api` : '/api';
@@ -735,11 +995,76 @@ export const apiClient = {
     }`;
   },
 
+  getCompareUrl: (dbId: string, imageId: number, options?: CompareOptions): string => {
+    const serverUrl = getCachedServerUrl();
+    const params = new URLSearchParams();
+    if (options) {
+      for (const [key, value] of Object.entries(options)) {
+        if (value !== undefined) params.append(key, String(value));
+      }
+    }
+
+    const queryString = params.toString();
+    const basePath = serverUrl ? `${serverUrl}/api` : '/api';
+    return `${basePath}${dbPath(dbId, `/images/${imageId}/compare`)}${
+      queryString ? `?${queryString}` : ''
+    }`;
+  },
+
+  getDiffUrl: (dbId: string, a: number, b: number, size?: string): string => {
+    const serverUrl = getCachedServerUrl();
+    const params = new URLSearchParams({ a: String(a), b: String(b) });
+    if (size) params.append('size', size);
+    const basePath = serverUrl ? `${serverUrl}/api` : '/api';
+    return `${basePath}${dbPath(dbId, '/images/diff')}?${params.toString()}`;
+  },
+
+  // Null while the diff is still rendering (202); poll again.
+  getDiffStats: async (
+    dbId: string,
+    a: number,
+    b: number,
+    size?: string
+  ): Promise<DiffStats | null> => {
+    const apiInstance = await getApi();
+    const response = await apiInstance.get<ApiResponse<DiffStats>>(dbPath(dbId, '/images/diff'), {
+      params: { a, b, size, stats: true },
+    });
+    if (response.status === 202) return null;
+    if (!response.data.data) throw new Error('Failed to fetch diff stats');
+    return response.data.data;
+  },
+
+  getContactSheetUrl: (
+    dbId: string,
+    targetId: number,
+    options?: ContactSheetOptions
+  ): string => {
+    const serverUrl = getCachedServerUrl();
+    const params = new URLSearchParams();
+    if (options) {
+      for (const [key, value] of Object.entries(options)) {
+        if (value !== undefined) params.append(key, String(value));
+      }
+    }
+
+    const queryString = params.toString();
+    const basePath = serverUrl ? `${serverUrl}/api` : '/api';
+    return `${basePath}${dbPath(dbId, `/targets/${targetId}/contact-sheet`)}${
+      queryString ? `?${queryString}` : ''
+    }`;
+  },
+
   getAnnotatedUrl: (
     dbId: string,
     imageId: number,
     size: 'screen' | 'large' | 'original' = 'large',
-    maxStars?: number
+    maxStars?: number,
+    format?: 'png' | 'svg',
+    markers?: Pick<
+      PreviewOptions,
+      'marker' | 'marker_size' | 'marker_min' | 'marker_max'
+    >
   ): string => {
     const serverUrl = getCachedServerUrl();
     const params = new URLSearchParams();
@@ -747,6 +1072,14 @@ export const apiClient = {
     if (maxStars !== undefined) {
       params.append('max_stars', String(maxStars));
     }
+    if (format) {
+      params.append('format', format);
+    }
+    Object.entries(markers ?? {}).forEach(([key, value]) => {
+      if (value !== undefined) {
+        params.append(key, String(value));
+      }
+    });
     const basePath = serverUrl ? `${serverUrl}/api` : '/api';
     return `${basePath}${dbPath(dbId, `/images/${imageId}/annotated`)}?${params.toString()}`;
   },
@@ -760,6 +1093,7 @@ export const apiClient = {
       sort_by?: string;
       grid_cols?: number;
       selection?: string;
+      labels?: boolean;
     }
   ): string => {
     const serverUrl = getCachedServerUrl();
@@ -769,6 +1103,7 @@ export const apiClient = {
     if (options?.sort_by) params.append('sort_by', options.sort_by);
     if (options?.grid_cols) params.append('grid_cols', String(options.grid_cols));
     if (options?.selection) params.append('selection', options.selection);
+    if (options?.labels === false) params.append('labels', 'false');
 
     const queryString = params.toString();
     const basePath = serverUrl ? `${serverUrl}/api` : '/api';
@@ -777,6 +1112,25 @@ export const apiClient = {
     }`;
   },
 
+  getPsfData: async (
+    dbId: string,
+    imageId: number,
+    options?: {
+      num_stars?: number;
+      psf_type?: string;
+      sort_by?: string;
+      selection?: string;
+    }
+  ): Promise<PsfDataResponse> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<PsfDataResponse>>(
+      dbPath(dbId, `/images/${imageId}/psf/data`),
+      { params: options }
+    );
+    if (!data.data) throw new Error(data.error || 'PSF data unavailable');
+    return data.data;
+  },
+
   getProjectsOverview: async (dbId: string): Promise<ProjectOverview[]> => {
     const apiInstance = await getApi();
     const { data } = await apiInstance.get<ApiResponse<ProjectOverview[]>>(
@@ -841,6 +1195,19 @@ export const apiClient = {
     return data.data;
   },
 
+  getRejectionStats: async (
+    dbId: string,
+    query: RejectionStatsQuery = {}
+  ): Promise<RejectionStats> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<RejectionStats>>(
+      dbPath(dbId, '/stats/rejections'),
+      { params: query }
+    );
+    if (!data.data) throw new Error(data.error || 'Failed to get rejection stats');
+    return data.data;
+  },
+
   getCacheProgress: async (dbId: string): Promise<CacheRefreshProgress> => {
     const apiInstance = await getApi();
     const { data } = await apiInstance.get<ApiResponse<CacheRefreshProgress>>(
@@ -850,6 +1217,15 @@ export const apiClient = {
     return data.data;
   },
 
+  cancelCacheRefresh: async (dbId: string): Promise<boolean> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.post<ApiResponse<{ cancelled: boolean }>>(
+      dbPath(dbId, '/refresh-cache/cancel')
+    );
+    if (!data.data) throw new Error('Failed to cancel cache refresh');
+    return data.data.cancelled;
+  },
+
   analyzeSequence: async (
     dbId: string,
     request: SequenceAnalysisRequest
@@ -915,4 +1291,28 @@ export const apiClient = {
     if (!data.data) throw new Error('Failed to get database quality status');
     return data.data;
   },
+
+  startProjectWarmCache: async (
+    dbId: string,
+    projectId: number,
+    formats: string[] = ['screen']
+  ): Promise<WarmCacheStatus> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.post<ApiResponse<WarmCacheStatus>>(
+      dbPath(dbId, `/projects/${projectId}/warm-cache`),
+      null,
+      { params: { formats: formats.join(',') } }
+    );
+    if (!data.data) throw new Error('Failed to start cache warming');
+    return data.data;
+  },
+
+  getPregenerationProgress: async (dbId: string): Promise<WarmCacheStatus> => {
+    const apiInstance = await getApi();
+    const { data } = await apiInstance.get<ApiResponse<WarmCacheStatus>>(
+      dbPath(dbId, '/pregeneration-progress')
+    );
+    if (!data.data) throw new Error('Failed to get cache warming progress');
+    return data.data;
+  },
 };
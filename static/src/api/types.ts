@@ -8,6 +8,9 @@ export interface Project {
   display_name: string;
   description: string | null;
   has_files: boolean;
+  /** Frames found on disk / frames with a filename; absent before the first file-cache refresh. */
+  files_present?: number;
+  files_total?: number;
 }
 
 export interface Target {
@@ -20,6 +23,8 @@ export interface Target {
   accepted_count: number;
   rejected_count: number;
   has_files: boolean;
+  files_present?: number;
+  files_total?: number;
 }
 
 export interface Image {
@@ -35,6 +40,9 @@ export interface Image {
   reject_reason: string | null;
   metadata: Record<string, any>; // eslint-disable-line @typescript-eslint/no-explicit-any
   filesystem_path: string | null;
+  favorite: boolean;
+  tags: string[];
+  exposure_verdict?: 'under' | 'ok' | 'over';
 }
 
 export interface StarInfo {
@@ -51,6 +59,7 @@ export interface StarDetectionResponse {
   average_hfr: number;
   average_fwhm: number;
   stars: StarInfo[];
+  low_star_warning?: boolean;
 }
 
 export type AstrometryAnalysisStatus = 'unavailable' | 'catalog_only' | 'solved' | 'failed';
@@ -238,12 +247,170 @@ export interface ApiResponse<T> {
   status?: 'ready' | 'loading' | 'refreshing';
 }
 
+export type ImageSort = 'newest' | 'oldest' | 'filter';
+
 export interface ImageQuery {
   project_id?: number;
   target_id?: number;
   status?: 'pending' | 'accepted' | 'rejected';
+  sort_by?: ImageSort;
   limit?: number;
   offset?: number;
+  favorites_only?: boolean;
+  tag?: string;
+  // Only images whose FITS file is / isn't in the directory tree cache.
+  files?: 'present' | 'missing';
+  // Light or calibration (dark/flat/bias) frames, by recorded IMAGETYP.
+  imagetyp?: 'light' | 'calibration';
+}
+
+// Previous/next image in the listing with the same filter and sort.
+export interface ImageNeighbors {
+  image_id: number;
+  previous_id: number | null;
+  next_id: number | null;
+}
+
+// One statistical grading rule checked against an image; metric_value is the
+// rule's statistic (z-score, MAD multiple or baseline ratio).
+export interface RuleEvaluation {
+  rule_name: string;
+  metric_value: number;
+  threshold: number;
+  passed: boolean;
+  details: string;
+}
+
+export interface GradingDetail {
+  image_id: number;
+  rejected: boolean;
+  rules: RuleEvaluation[];
+}
+
+export interface SessionQuery {
+  project_id?: number;
+  target_id?: number;
+  session_gap_minutes?: number;
+}
+
+export interface AcquisitionSession {
+  project_id: number;
+  target_id: number;
+  target_name: string;
+  filter_name: string;
+  start: number;
+  end: number;
+  image_count: number;
+  accepted_count: number;
+  rejected_count: number;
+  pending_count: number;
+}
+
+export interface FilterIntegrationHours {
+  filter_name: string;
+  accepted_hours: number;
+  total_hours: number;
+  accepted_images: number;
+  total_images: number;
+}
+
+export interface TargetIntegration {
+  target_id: number;
+  target_name: string;
+  filters: FilterIntegrationHours[];
+  accepted_hours: number;
+  total_hours: number;
+  skipped_images: number;
+}
+
+// One sub on a target's acquisition timeline; times are Unix seconds.
+export interface TimelineEntry {
+  image_id: number;
+  timestamp: number;
+  filter: string;
+  duration: number | null;
+  // Idle seconds before this frame, only when over the gap threshold.
+  gap_before: number | null;
+  new_session: boolean;
+  grading_status: number;
+}
+
+export interface TargetTimeline {
+  target_id: number;
+  target_name: string;
+  gap_threshold: number;
+  entries: TimelineEntry[];
+  on_sky_seconds: number;
+  elapsed_seconds: number;
+  efficiency: number | null;
+  sessions: number;
+  skipped_images: number;
+}
+
+export interface FocusStability {
+  target_id: number;
+  target_name: string;
+  filter: string | null;
+  session_start: number;
+  session_end: number;
+  /** Start of every session of the target, oldest first. */
+  sessions: number[];
+  /** Accepted frames with an HFR, in time order. */
+  series: { image_id: number; timestamp: number; hfr: number }[];
+  /** Null with fewer than three frames. */
+  summary: {
+    mean_hfr: number;
+    std_dev: number;
+    coefficient_of_variation: number;
+    trend_per_hour: number;
+    trend_r_squared: number;
+    classification: 'stable' | 'drifting' | 'erratic';
+  } | null;
+}
+
+export type TrendMetric = 'hfr' | 'stars' | 'eccentricity' | 'snr' | 'background';
+
+export interface TrendPoint {
+  timestamp: number;
+  value: number;
+  image_id: number;
+  grading_status: number;
+  /** Recorded (or altitude-derived) airmass; null when unknown. */
+  airmass: number | null;
+}
+
+export interface PsfStarData {
+  x: number;
+  y: number;
+  amplitude: number;
+  background: number;
+  fwhm_x: number;
+  fwhm_y: number;
+  theta: number;
+  beta?: number | null;
+  r2: number;
+  eccentricity: number;
+  hfr: number;
+}
+
+export interface PsfDataResponse {
+  psf_type: string;
+  stars: PsfStarData[];
+}
+
+export interface FavoriteResponse {
+  image_id: number;
+  favorite: boolean;
+}
+
+export interface ImageTagsResponse {
+  image_id: number;
+  tags: string[];
+}
+
+export interface TagCount {
+  tag: string;
+  count: number;
 }
 
 export interface UpdateGradeRequest {
@@ -256,7 +423,86 @@ export interface PreviewOptions {
   stretch?: boolean;
   midtone?: number;
   shadow?: number;
+  logarithmic?: boolean;
+  invert?: boolean;
+  max_stars?: number;
+  // Annotated stars: ranking before max_stars and thresholds. A minimum
+  // eccentricity marks only elongated stars.
+  rank_by?: 'hfr' | 'snr' | 'brightness' | 'r2';
+  min_snr?: number;
+  min_brightness?: number;
+  min_eccentricity?: number;
+  // Annotation markers: shape, HFR-scaled or fixed size, radius limits in px.
+  marker?: 'circle' | 'cross' | 'box';
+  marker_size?: 'fixed' | 'hfr-scaled';
+  marker_min?: number;
+  marker_max?: number;
+  // Region only, "x,y,w,h" in full-resolution pixels.
+  roi?: string;
+  // Remove the estimated sky background, or show just the estimate.
+  background?: 'subtract' | 'show';
+  // A TIFF download (16-bit integer or 32-bit float) instead of the PNG.
+  fmt?: 'png' | 'tiff';
+  bit_depth?: 16 | 32;
+}
+
+// Two stretch settings rendered side by side (A left, B right).
+export interface CompareOptions {
+  size?: 'screen' | 'large' | 'original';
+  midtone_a?: number;
+  shadow_a?: number;
+  midtone_b?: number;
+  shadow_b?: number;
+}
+
+// Detector settings for /stars; omitted values use the defaults.
+export interface StarDetectionParams {
+  threshold?: number;
+  min_size?: number;
   max_stars?: number;
+  psf_type?: 'none' | 'gaussian' | 'moffat4';
+  // 'nina' reports HFR as N.I.N.A. computes it, for comparing with its
+  // recorded values; 'psf' (default) is the detector's own.
+  hfr_method?: 'psf' | 'nina';
+}
+
+// Raw values of a small region in physical ADU; values[row][col].
+export interface PixelRegionResponse {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  values: number[][];
+  min: number;
+  max: number;
+  mean: number;
+}
+
+// Summary of a two-frame difference heatmap (differences normalized 0..1).
+export interface DiffStats {
+  width: number;
+  height: number;
+  mean_difference: number;
+  max_difference: number;
+  changed_fraction: number;
+}
+
+// One color channel's summary, from `metadata.Channels` on color frames
+// (R, G, B order; raw mosaic sites for one-shot-color cameras).
+export interface ChannelStatistics {
+  channel: string;
+  min: number;
+  max: number;
+  mean: number;
+  median: number;
+}
+
+// A target's frames tiled into one PNG; grade borders unless grades=false.
+export interface ContactSheetOptions {
+  filter?: string;
+  cols?: number;
+  cell_size?: number;
+  grades?: boolean;
 }
 
 // Readiness of an on-demand preview/annotated artifact (the server generates
@@ -277,7 +523,11 @@ export interface PreviewDescriptor {
   stretch?: boolean;
   midtone?: number;
   shadow?: number;
+  logarithmic?: boolean;
+  invert?: boolean;
   maxStars?: number;
+  roi?: string;
+  background?: 'subtract' | 'show';
 }
 
 export type StackJobState = 'queued' | 'running' | 'completed' | 'failed';
@@ -694,6 +944,8 @@ export interface ServerInfo {
   cache_directory: string;
   /** Whether database mutations and sync are accepted on this server. */
   allow_database_management: boolean;
+  /** Server runs with --read-only: grading and database edits are refused. */
+  read_only?: boolean;
   /** Optional plain-text notice configured by the server administrator. */
   banner?: SiteBanner;
 }
@@ -867,6 +1119,38 @@ export interface DirectoryTreeResponse {
   root_directory: string;
 }
 
+export interface DirectoryCacheQuery {
+  search?: string;
+  list?: boolean;
+  limit?: number;
+}
+
+export interface CachedPath {
+  path: string;
+  exists: boolean;
+}
+
+export interface DirectoryCacheResponse {
+  built: boolean;
+  stats: DirectoryTreeResponse | null;
+  roots: {
+    path: string;
+    files: number;
+    directories: number;
+    scan_time_ms: number;
+  }[];
+  search?: {
+    filename: string;
+    matches: CachedPath[];
+    case_insensitive_matches: CachedPath[];
+  };
+  listing?: {
+    paths: string[];
+    total: number;
+    truncated: boolean;
+  };
+}
+
 export const GradingStatus = {
   Pending: 0,
   Accepted: 1,
@@ -1068,6 +1352,64 @@ export interface OverallStats {
   recent_activity: RecentActivity[];
 }
 
+// Versioned curation backup of one project (GET /projects/{id}/export).
+export interface CuratedImage {
+  filename: string;
+  acquired_date: number | null;
+  target: string;
+  filter: string;
+  grading_status: 'pending' | 'accepted' | 'rejected';
+  reject_reason: string | null;
+  favorite: boolean;
+  tags: string[];
+  metrics: Record<string, unknown>;
+}
+
+export interface CurationExport {
+  format: 'psf-guard-curation';
+  version: number;
+  exported_at: number;
+  project: string;
+  images: CuratedImage[];
+  // Frames left out because their metadata has no FileName.
+  skipped_without_filename: number;
+}
+
+export interface CurationImportSummary {
+  records: number;
+  matched: number;
+  unchanged: number;
+  updated: number;
+  unmatched: number;
+  ambiguous: number;
+  dry_run: boolean;
+}
+
+export interface ReasonCount {
+  reason: string;
+  count: number;
+  percent: number;
+}
+
+// Rejections from one night (YYYY-MM-DD, null when undated).
+export interface NightRejections {
+  night: string | null;
+  total_rejected: number;
+  reasons: ReasonCount[];
+}
+
+export interface RejectionStats {
+  total_rejected: number;
+  reasons: ReasonCount[];
+  nights?: NightRejections[];
+}
+
+export interface RejectionStatsQuery {
+  project?: string;
+  target?: string;
+  by?: 'night';
+}
+
 export interface RecentActivity {
   date: number;
   images_added: number;
@@ -1077,6 +1419,7 @@ export interface RecentActivity {
 export interface CacheRefreshProgress {
   is_refreshing: boolean;
   stage: string;
+  cancel_requested: boolean;
   progress_percentage: number;
   elapsed_seconds: number | null;
   directories_total: number;
@@ -1104,6 +1447,13 @@ export interface SequenceAnalysisRequest {
   weight_background?: number;
   weight_spatial?: number;
   weight_pointing?: number;
+  // Lowest score of each summary bucket; must decrease excellent → poor.
+  threshold_excellent?: number;
+  threshold_good?: number;
+  threshold_fair?: number;
+  threshold_poor?: number;
+  // Propose rejecting frames whose temporal anomaly alone exceeds this.
+  temporal_anomaly_reject_threshold?: number;
 }
 
 export interface SequenceAnalysisResponse {
@@ -1234,6 +1584,25 @@ export interface QualityBackfillRequest {
   force?: boolean;
 }
 
+export interface WarmCacheProgress {
+  job_id: number | null;
+  project_id: number | null;
+  formats: string[];
+  running: boolean;
+  total_images: number;
+  processed_images: number;
+  generated: number;
+  skipped: number;
+  errors: number;
+  started_at: number | null;
+  finished_at: number | null;
+}
+
+export interface WarmCacheStatus {
+  started: boolean;
+  progress: WarmCacheProgress;
+}
+
 export interface SequenceSummary {
   excellent_count: number;
   good_count: number;
@@ -1246,6 +1615,7 @@ export interface SequenceSummary {
   out_of_target_count: number;
   plate_solve_failed_count: number;
   satellite_risk_count: number;
+  trail_count?: number;
 }
 
 export interface ReferenceValues {
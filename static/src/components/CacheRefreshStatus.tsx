@@ -16,7 +16,8 @@ const STAGE_LABELS: Record<string, string> = {
   processing_projects: 'Processing projects',
   processing_targets: 'Processing targets',
   updating_cache: 'Updating cache',
-  completed: 'Completed'
+  completed: 'Completed',
+  cancelled: 'Cancelled'
 };
 
 export default function CacheRefreshStatus({ className = '' }: CacheRefreshStatusProps) {
@@ -3,7 +3,7 @@ import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query';
 import { useHotkeys } from 'react-hotkeys-hook';
 import { apiClient } from '../api/client';
 import { GradingStatus } from '../api/types';
-import type { PreviewDescriptor } from '../api/types';
+import type { ChannelStatistics, PreviewDescriptor } from '../api/types';
 import { useImagePreloader } from '../hooks/useImagePreloader';
 import { useImageZoom } from '../hooks/useImageZoom';
 import { useAsyncImage } from '../hooks/useAsyncImage';
@@ -664,6 +664,13 @@ export default function ImageDetailView({
                   </>
                 )}
                 
+                {Array.isArray(image.metadata?.Channels) && image.metadata.Channels.length > 1 && (
+                  <>
+                    <dt>Median R/G/B:</dt>
+                    <dd>{image.metadata.Channels.map((c: ChannelStatistics) => c.median.toFixed(0)).join(' / ')}</dd>
+                  </>
+                )}
+                
                 {image.metadata?.HFR !== undefined && (
                   <>
                     <dt>HFR:</dt>
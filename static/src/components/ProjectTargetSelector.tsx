@@ -160,6 +160,9 @@ export default function ProjectTargetSelector() {
               disabled={!target.has_files}
             >
               {target.name} ({target.accepted_count}/{target.image_count})
+              {target.files_total !== undefined &&
+                target.files_present !== target.files_total &&
+                ` · ${target.files_present}/${target.files_total} files present`}
             </option>
           ))}
         </select>
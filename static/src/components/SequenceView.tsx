@@ -825,6 +825,8 @@ const SequenceStrip = memo(function SequenceStrip({
           reject_reason: null,
           metadata: {},
           filesystem_path: null,
+          favorite: false,
+          tags: [],
         };
         const belowThreshold = quality.quality_score < threshold;
         return (
@@ -26,6 +26,8 @@ const mockImages = normalFixture.data.sequences[0].images.map((img, i) => ({
   reject_reason: null,
   metadata: { FileName: `image_${img.image_id}.fits` },
   filesystem_path: `/images/image_${img.image_id}.fits`,
+  favorite: false,
+  tags: [],
 }));
 
 const mockTargets = [
@@ -0,0 +1,40 @@
+import { afterEach, describe, expect, it } from 'vitest';
+import {
+  AUTH_TOKEN_STORAGE_KEY,
+  authTokenCookie,
+  initializeAuthToken,
+  loadAuthToken,
+  saveAuthToken,
+} from '../auth';
+
+afterEach(() => {
+  saveAuthToken(null);
+  window.history.replaceState(null, '', '/');
+});
+
+describe('initializeAuthToken', () => {
+  it('moves a ?token= from the address bar into storage', () => {
+    window.history.replaceState(null, '', '/projects?token=s3cret&view=grid');
+
+    expect(initializeAuthToken()).toBe('s3cret');
+    expect(window.localStorage.getItem(AUTH_TOKEN_STORAGE_KEY)).toBe('s3cret');
+    expect(window.location.search).toBe('?view=grid');
+  });
+
+  it('falls back to the stored token', () => {
+    saveAuthToken('stored-token');
+    expect(initializeAuthToken()).toBe('stored-token');
+  });
+
+  it('is null without a token', () => {
+    expect(initializeAuthToken()).toBeNull();
+    expect(loadAuthToken()).toBeNull();
+  });
+});
+
+describe('authTokenCookie', () => {
+  it('percent-encodes the token for the /api routes', () => {
+    expect(authTokenCookie('a b;c')).toBe('psf_guard_token=a%20b%3Bc; path=/api; SameSite=Strict');
+    expect(authTokenCookie(null)).toContain('max-age=0');
+  });
+});
@@ -20,6 +20,8 @@ function image(id: number, acquiredDate: number, filter = 'HA'): Image {
     reject_reason: null,
     metadata: {},
     filesystem_path: null,
+    favorite: false,
+    tags: [],
   };
 }
 
@@ -0,0 +1,70 @@
+// API token for servers started with `[auth] token` / `PSF_GUARD_TOKEN`.
+// Opening the UI once as `/?token=<token>` stores it; from then on the API
+// client sends it as a bearer header, and a cookie scoped to /api lets
+// <img> and download URLs, which can't carry headers, through as well.
+
+export const AUTH_TOKEN_STORAGE_KEY = 'psf-guard:api-token';
+
+// Mirrors `TOKEN_COOKIE` in src/server/auth.rs.
+export const AUTH_TOKEN_COOKIE = 'psf_guard_token';
+
+const defaultStorage = (): Storage | undefined =>
+  typeof window === 'undefined' ? undefined : window.localStorage;
+
+// The `document.cookie` assignment that stores `token`, or expires the
+// cookie for null. The server percent-decodes the value.
+export function authTokenCookie(token: string | null): string {
+  return token
+    ? `${AUTH_TOKEN_COOKIE}=${encodeURIComponent(token)}; path=/api; SameSite=Strict`
+    : `${AUTH_TOKEN_COOKIE}=; path=/api; SameSite=Strict; max-age=0`;
+}
+
+const writeCookie = (token: string | null): void => {
+  if (typeof document === 'undefined') return;
+  document.cookie = authTokenCookie(token);
+};
+
+// Remember `token` (or forget it with null) and refresh the cookie.
+export function saveAuthToken(
+  token: string | null,
+  storage: Storage | undefined = defaultStorage()
+): void {
+  try {
+    if (token) storage?.setItem(AUTH_TOKEN_STORAGE_KEY, token);
+    else storage?.removeItem(AUTH_TOKEN_STORAGE_KEY);
+  } catch {
+    // A blocked browser store still leaves this page's cookie working.
+  }
+  writeCookie(token);
+}
+
+// The stored token, or null. Never throws.
+export function loadAuthToken(
+  storage: Storage | undefined = defaultStorage()
+): string | null {
+  try {
+    return storage?.getItem(AUTH_TOKEN_STORAGE_KEY) || null;
+  } catch {
+    return null;
+  }
+}
+
+// Called once at startup: take a `?token=` from the address bar (and drop it
+// from there so it doesn't end up in bookmarks or history), otherwise the
+// stored token, and make sure the cookie matches.
+export function initializeAuthToken(): string | null {
+  if (typeof window === 'undefined') return null;
+
+  const url = new URL(window.location.href);
+  const fromUrl = url.searchParams.get('token');
+  if (fromUrl) {
+    url.searchParams.delete('token');
+    window.history.replaceState(window.history.state, '', url.toString());
+    saveAuthToken(fromUrl);
+    return fromUrl;
+  }
+
+  const stored = loadAuthToken();
+  if (stored) writeCookie(stored);
+  return stored;
+}
@@ -0,0 +1,118 @@
+//! Bearer-token middleware on the `/api` routes: missing, wrong and correct
+//! tokens against an in-process app wired like the production router, and
+//! the cookie / query forms the UI's `<img>` and download URLs rely on.
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::{get, post};
+use axum::Router;
+use psf_guard::server::auth::require_bearer_token;
+use psf_guard::server::handlers;
+use psf_guard::server::state::AppState;
+use tempfile::tempdir;
+use tower::ServiceExt;
+
+fn build_app(state: Arc<AppState>, token: &str) -> Router {
+    Router::new()
+        .route("/api/info", get(handlers::get_server_info))
+        .route("/api/echo", post(|| async { "ok" }))
+        .with_state(state)
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_bearer_token,
+        ))
+}
+
+async fn status_with(app: Router, authorization: Option<&str>) -> StatusCode {
+    let mut builder = Request::builder().uri("/api/info");
+    if let Some(value) = authorization {
+        builder = builder.header("authorization", value);
+    }
+    app.oneshot(builder.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn send(app: Router, request: Request<Body>) -> StatusCode {
+    app.oneshot(request).await.unwrap().status()
+}
+
+fn empty_state() -> (tempfile::TempDir, Arc<AppState>) {
+    let dir = tempdir().unwrap();
+    let state = Arc::new(
+        AppState::from_databases(
+            vec![],
+            dir.path().join("cache").to_string_lossy().into_owned(),
+            psf_guard::cli::PregenerationConfig::default(),
+        )
+        .unwrap(),
+    );
+    (dir, state)
+}
+
+#[tokio::test]
+async fn api_requires_the_configured_bearer_token() {
+    let (_dir, state) = empty_state();
+    let app = || build_app(state.clone(), "s3cret-token");
+
+    assert_eq!(status_with(app(), None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        status_with(app(), Some("Bearer wrong-token")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status_with(app(), Some("Basic czNjcmV0LXRva2Vu")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status_with(app(), Some("Bearer s3cret-token")).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn ui_requests_pass_with_the_token_cookie_or_query() {
+    let (_dir, state) = empty_state();
+    let app = || build_app(state.clone(), "s3cret token;1");
+    let get = |uri: &str| Request::builder().uri(uri);
+
+    // The cookie the UI sets, percent-encoded, among others.
+    let with_cookie = get("/api/info")
+        .header("cookie", "theme=dark; psf_guard_token=s3cret%20token%3B1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(app(), with_cookie).await, StatusCode::OK);
+
+    let with_query = get("/api/info?token=s3cret%20token%3B1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(app(), with_query).await, StatusCode::OK);
+
+    let wrong_cookie = get("/api/info")
+        .header("cookie", "psf_guard_token=guess")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(app(), wrong_cookie).await, StatusCode::UNAUTHORIZED);
+
+    // Writes still need the header; a cookie alone could be sent cross-site.
+    let post_with_cookie = Request::builder()
+        .method("POST")
+        .uri("/api/echo?token=s3cret%20token%3B1")
+        .header("cookie", "psf_guard_token=s3cret%20token%3B1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        send(app(), post_with_cookie).await,
+        StatusCode::UNAUTHORIZED
+    );
+    let post_with_header = Request::builder()
+        .method("POST")
+        .uri("/api/echo")
+        .header("authorization", "Bearer s3cret token;1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(app(), post_with_header).await, StatusCode::OK);
+}
@@ -19,10 +19,16 @@ use tower::ServiceExt;
 fn build_app(state: Arc<AppState>) -> Router {
     let db_routes: Router<Arc<AppState>> = Router::new()
         .route("/projects", get(handlers::list_projects))
-        .route("/projects/overview", get(handlers::get_projects_overview));
+        .route("/projects/overview", get(handlers::get_projects_overview))
+        .route(
+            "/images/{image_id}/grade",
+            put(handlers::update_image_grade),
+        );
 
     Router::new()
         .route("/api/info", get(handlers::get_server_info))
+        .route("/api/health", get(handlers::get_health))
+        .route("/api/ready", get(handlers::get_readiness))
         .route(
             "/api/databases",
             get(handlers::list_databases).post(handlers::add_database_route),
@@ -111,6 +117,48 @@ async fn server_info_exposes_the_configured_site_banner() {
     assert_eq!(body["data"]["banner"]["link_url"], "https://psf-guard.com/");
 }
 
+#[tokio::test]
+async fn readiness_waits_for_the_directory_tree() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("scratch.sqlite");
+    create_sqlite(&db_path);
+    let image_dir = dir.path().join("imgs");
+    std::fs::create_dir_all(&image_dir).unwrap();
+    let state = Arc::new(
+        AppState::from_databases(
+            vec![psf_guard::db_registry::DbEntry {
+                id: "rig".into(),
+                name: "Rig".into(),
+                db_path: db_path.to_string_lossy().into_owned(),
+                image_dirs: vec![image_dir.to_string_lossy().into_owned()],
+                reject_archive: None,
+            }],
+            dir.path().join("cache").to_string_lossy().into_owned(),
+            psf_guard::cli::PregenerationConfig::default(),
+        )
+        .unwrap(),
+    );
+
+    let (status, body) = json_request(build_app(state.clone()), "GET", "/api/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = json_request(build_app(state.clone()), "GET", "/api/ready", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["ready"], false);
+    assert_eq!(body["databases"][0]["database_ok"], true);
+    assert_eq!(body["databases"][0]["directory_tree_ready"], false);
+
+    state
+        .get_database("rig")
+        .unwrap()
+        .get_directory_tree()
+        .unwrap();
+    let (status, body) = json_request(build_app(state), "GET", "/api/ready", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["ready"], true);
+}
+
 #[tokio::test]
 async fn crud_lifecycle_adds_uses_and_removes_a_database() {
     let dir = tempdir().unwrap();
@@ -438,3 +486,68 @@ async fn crud_is_disabled_when_no_registry_path_configured() {
     .await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
 }
+
+#[tokio::test]
+async fn read_only_server_refuses_grade_updates() {
+    let dir = tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let db_path = dir.path().join("scratch.sqlite");
+    create_sqlite(&db_path);
+    rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .execute(
+            "INSERT INTO acquiredimage (Id, projectId, targetId, gradingStatus, metadata) \
+             VALUES (1, 1, 1, 0, '{}')",
+            [],
+        )
+        .unwrap();
+    let image_dir = dir.path().join("imgs");
+    std::fs::create_dir_all(&image_dir).unwrap();
+
+    let state = Arc::new(
+        AppState::from_databases_with_options(
+            vec![psf_guard::db_registry::DbEntry {
+                id: "rig".into(),
+                name: "Rig".into(),
+                db_path: db_path.to_string_lossy().into_owned(),
+                image_dirs: vec![image_dir.to_string_lossy().into_owned()],
+                reject_archive: None,
+            }],
+            cache_dir.to_string_lossy().into_owned(),
+            psf_guard::cli::PregenerationConfig::default(),
+            None,
+            psf_guard::db::ConnectionOptions {
+                read_only: true,
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+
+    let (status, body) = json_request(build_app(state.clone()), "GET", "/api/info", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["read_only"], true);
+
+    let (status, _) = json_request(
+        build_app(state.clone()),
+        "PUT",
+        "/api/db/rig/images/1/grade",
+        Some(serde_json::json!({"status": "rejected"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let grade: i32 = rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .query_row(
+            "SELECT gradingStatus FROM acquiredimage WHERE Id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(grade, 0, "read-only server must leave the grade untouched");
+
+    // Browsing keeps working.
+    let (status, _) = json_request(build_app(state), "GET", "/api/db/rig/projects", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
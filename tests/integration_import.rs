@@ -38,6 +38,10 @@ fn build_app(state: Arc<AppState>) -> Router {
             get(scheduler::get_project_scheduler),
         )
         .route("/targets/{target_id}", put(handlers::update_target_route))
+        .route(
+            "/targets/{target_id}/merge",
+            post(handlers::merge_target_route),
+        )
         .route(
             "/targets/{target_id}/exposure-plans",
             post(scheduler::create_exposure_plan),
@@ -331,6 +335,74 @@ async fn import_into_existing_db_is_idempotent() {
     assert_eq!(outcome["imported"], 1, "outcome: {outcome}");
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn filesystem_mode_serves_folders_and_keeps_grades_across_restarts() {
+    use psf_guard::server::standalone::{filesystem_db_path, FILESYSTEM_DB_ID};
+
+    let dir = tempdir().unwrap();
+    let images = dir.path().join("lights");
+    std::fs::create_dir_all(&images).unwrap();
+    write_fits(
+        &images.join("m31_ha_0001.fits"),
+        "M31",
+        "Ha",
+        "2026-01-15T04:00:00.000",
+        10.6847,
+    );
+    write_fits(
+        &images.join("m33_oiii_0001.fits"),
+        "M33",
+        "OIII",
+        "2026-01-15T05:00:00.000",
+        23.4621,
+    );
+    let cache_dir = dir.path().join("cache").to_string_lossy().into_owned();
+    let scan_dirs = vec![images.to_string_lossy().into_owned()];
+    let start = || {
+        Arc::new(
+            AppState::from_image_dirs(
+                &scan_dirs,
+                cache_dir.clone(),
+                psf_guard::cli::PregenerationConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+
+    let state = start();
+    let projects = wait_for_projects(&state, FILESYSTEM_DB_ID).await;
+    assert_eq!(projects.len(), 2, "projects: {projects:?}");
+
+    // Grade one frame, add another, and start again.
+    let db_path = filesystem_db_path(&cache_dir);
+    rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .execute(
+            "UPDATE acquiredimage SET gradingStatus = 1 WHERE metadata LIKE '%m31_ha_0001%'",
+            [],
+        )
+        .unwrap();
+    write_fits(
+        &images.join("m31_ha_0002.fits"),
+        "M31",
+        "Ha",
+        "2026-01-15T04:05:10.000",
+        10.6851,
+    );
+    drop(state);
+    let _state = start();
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let counts: (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), SUM(gradingStatus = 1) FROM acquiredimage",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(counts, (3, 1), "new frame imported, grade kept");
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn import_dry_run_writes_nothing() {
     let dir = tempdir().unwrap();
@@ -834,7 +906,7 @@ async fn organize_rename_move_and_merge() {
         "merge must delete the source rule weights"
     );
 
-    // Self-merge and merging into a missing project are rejected.
+    // Self-merge is a bad request; merging into a missing project is a 404.
     let (status, _) = json_request(
         build_app(state.clone()),
         "POST",
@@ -843,6 +915,14 @@ async fn organize_rename_move_and_merge() {
     )
     .await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = json_request(
+        build_app(state.clone()),
+        "POST",
+        &format!("/api/db/{slug}/projects/{p1}/merge"),
+        Some(serde_json::json!({ "into_project_id": 9999 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
     let (status, _) = json_request(
         build_app(state.clone()),
         "PUT",
@@ -851,6 +931,45 @@ async fn organize_rename_move_and_merge() {
     )
     .await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Both targets now live in project 1: merge t2 into the other one and
+    // drop it along with its exposure plans.
+    let t1: i32 = conn
+        .query_row(
+            "SELECT Id FROM target WHERE projectid = ? AND Id != ?",
+            [p1, t2],
+            |r| r.get(0),
+        )
+        .unwrap();
+    let (status, body) = json_request(
+        build_app(state.clone()),
+        "POST",
+        &format!("/api/db/{slug}/targets/{t2}/merge"),
+        Some(serde_json::json!({ "into_target_id": t1, "delete_source": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "target merge failed: {body}");
+    assert_eq!(body["data"]["images_moved"], 1);
+    let (images_on_t1, leftovers): (i64, i64) = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM acquiredimage WHERE targetId = ?1),
+                    (SELECT COUNT(*) FROM target WHERE Id = ?2)
+                  + (SELECT COUNT(*) FROM exposureplan WHERE targetid = ?2)",
+            [t1, t2],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((images_on_t1, leftovers), (2, 0));
+
+    // The source is gone now, so merging it again is a 404.
+    let (status, _) = json_request(
+        build_app(state.clone()),
+        "POST",
+        &format!("/api/db/{slug}/targets/{t2}/merge"),
+        Some(serde_json::json!({ "into_target_id": t1 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
 }
 
 /// Raw request helper for binary responses (the export zip).
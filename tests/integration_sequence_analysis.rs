@@ -116,6 +116,10 @@ fn create_test_app(conn: Connection) -> Router {
         .route(
             "/analysis/image/{image_id}",
             get(handlers::get_image_quality),
+        )
+        .route(
+            "/targets/{target_id}/trend",
+            get(handlers::get_target_trend),
         );
 
     Router::new()
@@ -602,6 +606,107 @@ async fn test_analyze_sequence_nonexistent_target() {
     );
 }
 
+/// Whole-database analysis streamed as NDJSON: one sequence per line, every
+/// target with images, no response wrapper.
+#[tokio::test]
+async fn test_analyze_sequence_ndjson_stream() {
+    let conn = Connection::open_in_memory().unwrap();
+    create_test_schema(&conn);
+    load_normal_sequence(&conn);
+    load_session_gap(&conn);
+    insert_target(&conn, 9, 1, "Empty");
+    let app = create_test_app(conn);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/db/test/analysis/sequence")
+                .header("accept", "application/x-ndjson")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let lines: Vec<Value> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // M42's L sequence plus NGC7000's two sessions; the empty target adds
+    // nothing.
+    assert_eq!(lines.len(), 3);
+    let mut targets: Vec<_> = lines
+        .iter()
+        .map(|sequence| sequence["target_name"].as_str().unwrap())
+        .collect();
+    targets.sort();
+    assert_eq!(targets, ["M42", "NGC7000", "NGC7000"]);
+    assert!(lines.iter().all(|sequence| sequence["summary"].is_object()));
+
+    // Without the Accept header the same request is wrapped JSON.
+    let conn = Connection::open_in_memory().unwrap();
+    create_test_schema(&conn);
+    load_normal_sequence(&conn);
+    load_session_gap(&conn);
+    let (status, json) = get_json(create_test_app(conn), "/api/db/test/analysis/sequence").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["sequences"].as_array().unwrap().len(), 3);
+}
+
+/// Metric trend: filtered, oldest first, images without the metric omitted
+#[tokio::test]
+async fn test_target_trend_series() {
+    let conn = Connection::open_in_memory().unwrap();
+    create_test_schema(&conn);
+    load_normal_sequence(&conn);
+    load_cloud_passage(&conn);
+    let app = create_test_app(conn);
+
+    let (status, json) = get_json(
+        app.clone(),
+        "/api/db/test/targets/1/trend?metric=snr&filter=L",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let points = json["data"].as_array().unwrap();
+    // Only the first 7 L frames carry SNR.
+    assert_eq!(points.len(), 7);
+    let ids: Vec<i64> = points
+        .iter()
+        .map(|p| p["image_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5, 6, 7]);
+    let timestamps: Vec<i64> = points
+        .iter()
+        .map(|p| p["timestamp"].as_i64().unwrap())
+        .collect();
+    assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(points[0]["grading_status"], 0);
+
+    let (_, json) = get_json(app.clone(), "/api/db/test/targets/1/trend?metric=hfr").await;
+    let points = json["data"].as_array().unwrap();
+    assert_eq!(points.len(), 18);
+    assert_eq!(points[13]["value"].as_f64().unwrap(), 3.8);
+
+    let (status, _) = get_json(app.clone(), "/api/db/test/targets/9999/trend?metric=hfr").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/db/test/targets/1/trend?metric=fwhm")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 /// Test 8: Image quality context endpoint
 #[tokio::test]
 async fn test_image_quality_context() {